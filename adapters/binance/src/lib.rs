@@ -4,57 +4,1007 @@
 
 use arbfinder_core::prelude::*;
 use arbfinder_exchange::prelude::*;
+use arbfinder_core::config::ProxyConfig;
+use arbfinder_exchange::{channel_stream, http_client, retry_after, DefaultExchangeConfig, SingleFlight, DEFAULT_SYMBOLS_TTL};
 use async_trait::async_trait;
-use reqwest::Client;
-use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use reqwest::{Client, Method};
+use chrono::{DateTime, TimeZone, Utc};
 use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tracing::{info, warn};
 
+pub mod margin;
+mod multiplex;
+pub mod user_data;
+mod weight;
 pub mod websocket;
-pub use websocket::BinanceOrderbookStream;
+pub use margin::{MarginAsset, MarginMode};
+pub use user_data::{BinanceUserDataStream, UserDataEvent};
+pub use websocket::{BinanceChannel, BinanceMarketDataStream, BinanceOrderbookStream};
+
+use multiplex::{StreamMultiplexer, BINANCE_MAX_STREAMS_PER_CONNECTION, DEFAULT_STREAMS_PER_CONNECTION};
+use user_data::{OrderIds, UserDataSource};
+use weight::{binance_rate_limits, UsedWeight, BINANCE_DEFAULT_WEIGHT_BUDGET};
 
 const BINANCE_API_URL: &str = "https://api.binance.com";
-const BINANCE_WS_URL: &str = "wss://stream.binance.com:9443/ws";
+const BINANCE_TESTNET_API_URL: &str = "https://testnet.binance.vision";
+
+/// How long after its timestamp Binance accepts a signed request, in ms.
+const BINANCE_DEFAULT_RECV_WINDOW: u64 = 5000;
+
+/// Listen keys expire after 60 minutes without a keepalive.
+const BINANCE_LISTEN_KEY_KEEPALIVE: Duration = Duration::from_secs(30 * 60);
+
+const BINANCE_EXCHANGE_INFO: &str = "/api/v3/exchangeInfo";
+const BINANCE_SYSTEM_STATUS: &str = "/sapi/v1/system/status";
+
+fn exchange_info_cache(ttl: Duration) -> ResponseCache {
+    ResponseCache::new().with_ttl(BINANCE_EXCHANGE_INFO, ttl)
+}
+
+/// Formats a symbol as a Binance spot symbol, e.g. `BTCUSDT`.
+fn binance_symbol(symbol: &Symbol) -> String {
+    format!("{}{}", symbol.base().to_uppercase(), symbol.quote().to_uppercase())
+}
+
+fn value_to_decimal(value: &serde_json::Value) -> Option<Decimal> {
+    value.as_str().and_then(|s| s.parse().ok())
+}
+
+/// A symbol's entry in `/api/v3/exchangeInfo`, with its trading
+/// constraints taken from the PRICE_FILTER, LOT_SIZE and NOTIONAL (or the
+/// older MIN_NOTIONAL) filters. A filter that is absent, or disabled with a
+/// zero value, leaves the permissive default.
+fn parse_symbol_info(symbol: &Symbol, data: &serde_json::Value) -> SymbolInfo {
+    let filter = |filter_type: &str, field: &str| {
+        data["filters"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|filter| filter["filterType"].as_str() == Some(filter_type))
+            .and_then(|filter| value_to_decimal(&filter[field]))
+            .filter(|value| !value.is_zero())
+    };
+
+    SymbolInfo {
+        symbol: symbol.clone(),
+        status: data["status"].as_str().unwrap_or("UNKNOWN").to_string(),
+        base_asset_precision: data["baseAssetPrecision"].as_u64().unwrap_or(8) as u32,
+        quote_asset_precision: data["quoteAssetPrecision"].as_u64().unwrap_or(8) as u32,
+        tick_size: filter("PRICE_FILTER", "tickSize").unwrap_or(Decimal::new(1, 8)),
+        lot_size: filter("LOT_SIZE", "stepSize").unwrap_or(Decimal::new(1, 8)),
+        min_order_size: filter("LOT_SIZE", "minQty").unwrap_or(Decimal::new(1, 8)),
+        max_order_size: filter("LOT_SIZE", "maxQty").unwrap_or(Decimal::new(1000000, 0)),
+        min_notional: filter("NOTIONAL", "minNotional")
+            .or_else(|| filter("MIN_NOTIONAL", "minNotional"))
+            .unwrap_or(Decimal::new(10, 0)),
+        trading_fees: TradingFees {
+            maker_fee: Decimal::new(1, 3), // 0.1%
+            taker_fee: Decimal::new(1, 3), // 0.1%
+        },
+    }
+}
+
+/// A row of `/api/v3/klines`: open time in milliseconds, then open, high,
+/// low, close and volume as strings.
+fn parse_kline(symbol: &Symbol, interval: CandleInterval, kline: &serde_json::Value) -> Option<Candle> {
+    Some(Candle {
+        symbol: symbol.clone(),
+        open: value_to_decimal(&kline[1])?,
+        high: value_to_decimal(&kline[2])?,
+        low: value_to_decimal(&kline[3])?,
+        close: value_to_decimal(&kline[4])?,
+        volume: value_to_decimal(&kline[5])?,
+        timestamp: Utc.timestamp_millis_opt(kline[0].as_i64()?).single()?,
+        interval: interval.to_string(),
+    })
+}
+
+/// An entry of `/api/v3/trades`. A maker buyer means the seller took
+/// liquidity, so the trade is on the ask.
+fn parse_trade(symbol: &Symbol, trade: &serde_json::Value) -> Option<Trade> {
+    let side = if trade["isBuyerMaker"].as_bool()? { Side::Ask } else { Side::Bid };
+    let mut parsed = Trade::new(
+        symbol.clone(),
+        value_to_decimal(&trade["price"])?,
+        value_to_decimal(&trade["qty"])?,
+        side,
+        trade["id"].as_u64()?.to_string(),
+    );
+    parsed.timestamp = Utc.timestamp_millis_opt(trade["time"].as_i64()?).single()?;
+    Some(parsed)
+}
+
+/// The entry of `/sapi/v1/asset/tradeFee` for one symbol.
+fn parse_trade_fee(data: &serde_json::Value) -> Option<TradingFee> {
+    let entry = data.as_array()?.first()?;
+    Some(TradingFee::new(
+        value_to_decimal(&entry["makerCommission"])?,
+        value_to_decimal(&entry["takerCommission"])?,
+    ))
+}
+
+/// The spot wallet's balances in an `/api/v3/account` response.
+fn parse_account_balances(data: &serde_json::Value) -> Result<Vec<Balance>> {
+    let balances = data["balances"]
+        .as_array()
+        .ok_or_else(|| ArbFinderError::InvalidData("Missing balances".to_string()))?;
+    Ok(balances
+        .iter()
+        .filter_map(|entry| {
+            let (free, locked) = (value_to_decimal(&entry["free"])?, value_to_decimal(&entry["locked"])?);
+            Some(Balance::new(entry["asset"].as_str()?.to_string(), free + locked, free, locked))
+        })
+        .collect())
+}
+
+/// The network `asset` is deposited on by default, from a
+/// `/sapi/v1/capital/config/getall` response.
+fn default_network(coins: &serde_json::Value, asset: &str) -> Option<String> {
+    coins
+        .as_array()?
+        .iter()
+        .find(|coin| coin["coin"].as_str().is_some_and(|coin| coin.eq_ignore_ascii_case(asset)))?["networkList"]
+        .as_array()?
+        .iter()
+        .find(|network| network["isDefault"].as_bool().unwrap_or(false))
+        .and_then(|network| network["network"].as_str())
+        .map(str::to_string)
+}
+
+/// A `/sapi/v1/capital/deposit/address` response; an empty tag means the
+/// coin needs none.
+fn parse_deposit_address(data: &serde_json::Value) -> Option<DepositAddress> {
+    let mut address = DepositAddress::new(data["coin"].as_str()?, data["address"].as_str()?);
+    address.tag = data["tag"].as_str().filter(|tag| !tag.is_empty()).map(str::to_string);
+    Some(address)
+}
+
+/// Binance's ids are strings on some endpoints and numbers on others.
+fn id_string(value: &serde_json::Value) -> Option<String> {
+    value.as_str().map(str::to_string).or_else(|| value.as_u64().map(|id| id.to_string()))
+}
+
+/// An entry of `/sapi/v1/capital/withdraw/history`. Status 6 is sent; 1, 3
+/// and 5 are canceled, rejected and failed; the rest are still in hand.
+fn parse_withdrawal_progress(data: &serde_json::Value) -> Option<WithdrawalProgress> {
+    let status = match data["status"].as_u64()? {
+        6 => TransferStatus::Completed,
+        1 | 3 | 5 => TransferStatus::Failed,
+        _ => TransferStatus::Pending,
+    };
+    Some(WithdrawalProgress {
+        status,
+        tx_id: data["txId"].as_str().filter(|tx| !tx.is_empty()).map(str::to_string),
+    })
+}
+
+/// An entry of `/sapi/v1/capital/deposit/hisrec`. Status 1 is credited and
+/// 6 credited but locked; 2 and 7 are rejected and wrong deposits.
+/// `confirmTimes` reads as seen over needed, e.g. `3/12`.
+fn parse_deposit(data: &serde_json::Value) -> Option<Deposit> {
+    let status = match data["status"].as_u64()? {
+        1 | 6 => TransferStatus::Completed,
+        2 | 7 => TransferStatus::Failed,
+        _ => TransferStatus::Confirming,
+    };
+    Some(Deposit {
+        id: id_string(&data["id"])?,
+        venue: VenueId::Binance,
+        asset: data["coin"].as_str()?.to_string(),
+        amount: value_to_decimal(&data["amount"])?,
+        tx_id: data["txId"].as_str().filter(|tx| !tx.is_empty()).map(str::to_string),
+        status,
+        confirmations: data["confirmTimes"]
+            .as_str()
+            .and_then(|confirmations| confirmations.split('/').next()?.parse().ok()),
+        time: Utc.timestamp_millis_opt(data["insertTime"].as_i64()?).single()?,
+    })
+}
+
+/// `/sapi/v1/system/status`: status 0 is normal, 1 system maintenance.
+/// Binance doesn't publish its maintenance schedule there.
+fn parse_system_status(data: &serde_json::Value) -> Result<VenueStatusReport> {
+    let msg = data["msg"].as_str().unwrap_or("system_maintenance");
+    let health = match data["status"].as_u64() {
+        Some(0) => VenueHealth::Operational,
+        Some(_) => VenueHealth::Maintenance(msg.to_string()),
+        None => {
+            return Err(ArbFinderError::InvalidData(format!("Binance system status without a status: {}", data)))
+        }
+    };
+    Ok(VenueStatusReport::new(health))
+}
+
+fn binance_time_in_force(tif: TimeInForce) -> &'static str {
+    match tif {
+        TimeInForce::GoodTillCanceled | TimeInForce::PostOnly => "GTC",
+        TimeInForce::ImmediateOrCancel => "IOC",
+        TimeInForce::FillOrKill => "FOK",
+    }
+}
+
+/// Parameters for `POST /api/v3/order`, without timestamp and signature.
+fn order_params(request: &OrderRequest, client_order_id: &str) -> Result<Vec<(&'static str, String)>> {
+    let side = match request.side {
+        OrderSide::Buy => "BUY",
+        OrderSide::Sell => "SELL",
+    };
+    let mut params = vec![
+        ("symbol", binance_symbol(&request.symbol)),
+        ("side", side.to_string()),
+    ];
+
+    let limit_price = || {
+        request
+            .price
+            .map(|price| price.normalize().to_string())
+            .ok_or_else(|| ArbFinderError::InvalidOrder(format!("{} order requires a price", request.order_type)))
+    };
+    let stop_price = || {
+        request
+            .stop_price
+            .map(|price| price.normalize().to_string())
+            .ok_or_else(|| ArbFinderError::InvalidOrder(format!("{} order requires a stop price", request.order_type)))
+    };
+
+    match request.order_type {
+        OrderType::Market => params.push(("type", "MARKET".to_string())),
+        OrderType::Limit | OrderType::PostOnly
+            if request.post_only
+                || request.order_type == OrderType::PostOnly
+                || request.time_in_force == TimeInForce::PostOnly =>
+        {
+            params.push(("type", "LIMIT_MAKER".to_string()));
+            params.push(("price", limit_price()?));
+        }
+        OrderType::Limit | OrderType::PostOnly => {
+            params.push(("type", "LIMIT".to_string()));
+            params.push(("timeInForce", binance_time_in_force(request.time_in_force).to_string()));
+            params.push(("price", limit_price()?));
+        }
+        OrderType::ImmediateOrCancel | OrderType::FillOrKill => {
+            let tif = if request.order_type == OrderType::FillOrKill { "FOK" } else { "IOC" };
+            params.push(("type", "LIMIT".to_string()));
+            params.push(("timeInForce", tif.to_string()));
+            params.push(("price", limit_price()?));
+        }
+        OrderType::StopMarket => {
+            params.push(("type", "STOP_LOSS".to_string()));
+            params.push(("stopPrice", stop_price()?));
+        }
+        OrderType::StopLimit => {
+            params.push(("type", "STOP_LOSS_LIMIT".to_string()));
+            params.push(("timeInForce", binance_time_in_force(request.time_in_force).to_string()));
+            params.push(("price", limit_price()?));
+            params.push(("stopPrice", stop_price()?));
+        }
+        OrderType::Oco => {
+            return Err(ArbFinderError::InvalidOrder(
+                "OCO orders go to /api/v3/orderList/oco; see oco_params".to_string(),
+            ));
+        }
+    }
+
+    match request.size_unit {
+        SizeUnit::Base => params.push(("quantity", request.quantity.normalize().to_string())),
+        // Binance fills quote sizes natively, but only on market orders
+        SizeUnit::Quote if request.order_type == OrderType::Market => {
+            params.push(("quoteOrderQty", request.quantity.normalize().to_string()))
+        }
+        SizeUnit::Quote => {
+            return Err(ArbFinderError::InvalidOrder(
+                "Binance only takes quote-currency sizes on market orders".to_string(),
+            ));
+        }
+    }
+
+    params.push(("newClientOrderId", client_order_id.to_string()));
+    params.push(("newOrderRespType", "RESULT".to_string()));
+    Ok(params)
+}
+
+/// Parameters for `POST /api/v3/orderList/oco`, without timestamp and
+/// signature. The take profit rests as a maker limit on one side of the
+/// market and the stop loss waits on the other.
+fn oco_params(request: &OrderRequest, client_order_id: &str) -> Result<Vec<(&'static str, String)>> {
+    let (take_profit, stop) = match (request.price, request.stop_price) {
+        (Some(take_profit), Some(stop)) => (take_profit, stop),
+        _ => {
+            return Err(ArbFinderError::InvalidOrder(
+                "OCO order requires a take-profit price and a stop price".to_string(),
+            ))
+        }
+    };
+    if request.size_unit == SizeUnit::Quote {
+        return Err(ArbFinderError::InvalidOrder(
+            "Binance only takes quote-currency sizes on market orders".to_string(),
+        ));
+    }
+
+    // Type, price, stop price and time in force of the leg above the market
+    // and the one below it
+    const ABOVE: [&str; 4] = ["aboveType", "abovePrice", "aboveStopPrice", "aboveTimeInForce"];
+    const BELOW: [&str; 4] = ["belowType", "belowPrice", "belowStopPrice", "belowTimeInForce"];
+    // Selling takes profit above the market and stops out below it; buying
+    // back, the other way round
+    let (side, [tp_type, tp_price, _, _], [stop_type, stop_price, stop_trigger, stop_tif]) = match request.side {
+        OrderSide::Sell if take_profit > stop => ("SELL", ABOVE, BELOW),
+        OrderSide::Buy if take_profit < stop => ("BUY", BELOW, ABOVE),
+        _ => {
+            return Err(ArbFinderError::InvalidOrder(format!(
+                "OCO {} take profit {} is on the wrong side of stop {}",
+                request.side, take_profit, stop
+            )))
+        }
+    };
+
+    let mut params = vec![
+        ("symbol", binance_symbol(&request.symbol)),
+        ("side", side.to_string()),
+        ("quantity", request.quantity.normalize().to_string()),
+        (tp_type, "LIMIT_MAKER".to_string()),
+        (tp_price, take_profit.normalize().to_string()),
+        (stop_trigger, stop.normalize().to_string()),
+    ];
+    match request.stop_limit_price {
+        Some(limit) => {
+            params.push((stop_type, "STOP_LOSS_LIMIT".to_string()));
+            params.push((stop_price, limit.normalize().to_string()));
+            params.push((stop_tif, "GTC".to_string()));
+        }
+        None => params.push((stop_type, "STOP_LOSS".to_string())),
+    }
+
+    params.push(("listClientOrderId", client_order_id.to_string()));
+    params.push(("newOrderRespType", "RESULT".to_string()));
+    Ok(params)
+}
+
+fn parse_order_status(status: &str) -> OrderStatus {
+    match status {
+        "NEW" | "PENDING_NEW" => OrderStatus::Open,
+        "PARTIALLY_FILLED" => OrderStatus::PartiallyFilled,
+        "FILLED" => OrderStatus::Filled,
+        "CANCELED" | "PENDING_CANCEL" => OrderStatus::Canceled,
+        "REJECTED" => OrderStatus::Rejected,
+        "EXPIRED" | "EXPIRED_IN_MATCH" => OrderStatus::Expired,
+        _ => OrderStatus::Pending,
+    }
+}
+
+/// Maps a Binance order, as returned by the order endpoints, onto `order`,
+/// keeping its id.
+fn apply_order_response(order: &mut Order, data: &serde_json::Value) -> Result<()> {
+    let venue_order_id = data["orderId"]
+        .as_i64()
+        .ok_or_else(|| ArbFinderError::InvalidData("Missing orderId".to_string()))?;
+    order.venue_order_id = Some(venue_order_id.to_string());
+    if let Some(client_order_id) = data["clientOrderId"].as_str() {
+        order.client_order_id = Some(client_order_id.to_string());
+    }
+
+    order.side = match data["side"].as_str() {
+        Some("SELL") => OrderSide::Sell,
+        Some("BUY") => OrderSide::Buy,
+        _ => order.side,
+    };
+    order.order_type = match data["type"].as_str() {
+        Some("MARKET") => OrderType::Market,
+        Some("LIMIT_MAKER") => OrderType::PostOnly,
+        Some("STOP_LOSS") => OrderType::StopMarket,
+        Some("STOP_LOSS_LIMIT") => OrderType::StopLimit,
+        Some("LIMIT") => match data["timeInForce"].as_str() {
+            Some("IOC") => OrderType::ImmediateOrCancel,
+            Some("FOK") => OrderType::FillOrKill,
+            _ => OrderType::Limit,
+        },
+        _ => order.order_type,
+    };
+    order.time_in_force = match data["timeInForce"].as_str() {
+        Some("IOC") => TimeInForce::ImmediateOrCancel,
+        Some("FOK") => TimeInForce::FillOrKill,
+        Some("GTC") => TimeInForce::GoodTillCanceled,
+        _ => order.time_in_force,
+    };
+
+    if let Some(quantity) = value_to_decimal(&data["origQty"]).filter(|q| !q.is_zero()) {
+        order.quantity = quantity;
+    }
+    if let Some(price) = value_to_decimal(&data["price"]).filter(|p| !p.is_zero()) {
+        order.price = Some(price);
+    }
+    if let Some(stop_price) = value_to_decimal(&data["stopPrice"]).filter(|p| !p.is_zero()) {
+        order.stop_price = Some(stop_price);
+    }
+
+    let filled = value_to_decimal(&data["executedQty"]).unwrap_or(Decimal::ZERO);
+    order.filled_quantity = filled;
+    order.remaining_quantity = (order.quantity - filled).max(Decimal::ZERO);
+    order.average_fill_price = value_to_decimal(&data["cummulativeQuoteQty"])
+        .filter(|_| !filled.is_zero())
+        .map(|quote| quote / filled);
+    order.status = data["status"].as_str().map(parse_order_status).unwrap_or(OrderStatus::Open);
+
+    let time = |field: &str| data[field].as_i64().and_then(DateTime::<Utc>::from_timestamp_millis);
+    if let Some(created) = time("time").or_else(|| time("transactTime")) {
+        order.created_at = created;
+    }
+    if let Some(updated) = time("updateTime").or_else(|| time("transactTime")) {
+        order.updated_at = updated;
+    }
+    Ok(())
+}
+
+/// Maps an order list, as returned by `/api/v3/orderList/oco`, onto an OCO
+/// `order`. The venue order id is the list's; status and fills come from
+/// whichever leg got furthest.
+fn apply_order_list_response(order: &mut Order, data: &serde_json::Value) -> Result<()> {
+    let list_id = data["orderListId"]
+        .as_i64()
+        .ok_or_else(|| ArbFinderError::InvalidData("Missing orderListId".to_string()))?;
+    order.venue_order_id = Some(list_id.to_string());
+    if let Some(client_order_id) = data["listClientOrderId"].as_str() {
+        order.client_order_id = Some(client_order_id.to_string());
+    }
+
+    let legs = data["orderReports"].as_array().map(Vec::as_slice).unwrap_or_default();
+    let filled_leg = legs
+        .iter()
+        .max_by_key(|leg| value_to_decimal(&leg["executedQty"]).unwrap_or(Decimal::ZERO));
+    let filled = filled_leg
+        .and_then(|leg| value_to_decimal(&leg["executedQty"]))
+        .unwrap_or(Decimal::ZERO);
+    order.filled_quantity = filled;
+    order.remaining_quantity = (order.quantity - filled).max(Decimal::ZERO);
+    order.average_fill_price = filled_leg
+        .and_then(|leg| value_to_decimal(&leg["cummulativeQuoteQty"]))
+        .filter(|_| !filled.is_zero())
+        .map(|quote| quote / filled);
+
+    order.status = match filled_leg.and_then(|leg| leg["status"].as_str()) {
+        Some(status) if !filled.is_zero() => parse_order_status(status),
+        _ => match data["listOrderStatus"].as_str() {
+            Some("REJECT") => OrderStatus::Rejected,
+            Some("ALL_DONE") => OrderStatus::Canceled,
+            _ => OrderStatus::Open,
+        },
+    };
+
+    if let Some(time) = data["transactionTime"].as_i64().and_then(DateTime::<Utc>::from_timestamp_millis) {
+        order.created_at = time;
+        order.updated_at = time;
+    }
+    Ok(())
+}
+
+/// A Binance order, as listed by the order endpoints, under the id it was
+/// placed with through this adapter, if it was.
+fn listed_order(symbol: &Symbol, data: &serde_json::Value, known: &HashMap<OrderId, (Symbol, String)>) -> Result<Order> {
+    let mut order = Order::new_market(VenueId::Binance, symbol.clone(), OrderSide::Buy, Decimal::ZERO);
+    let venue_order_id = id_string(&data["orderId"]);
+    if let Some((id, _)) = known.iter().find(|(_, (_, venue))| Some(venue) == venue_order_id.as_ref()) {
+        order.id = id.clone();
+    }
+    apply_order_response(&mut order, data)?;
+    Ok(order)
+}
+
+/// An entry of `/api/v3/myTrades` on `symbol`. Fills of orders placed
+/// elsewhere get an order id of their own.
+fn parse_my_trade(symbol: &Symbol, data: &serde_json::Value, known: &HashMap<OrderId, (Symbol, String)>) -> Option<OrderFill> {
+    let venue_order_id = id_string(&data["orderId"])?;
+    let price = value_to_decimal(&data["price"])?;
+    let quantity = value_to_decimal(&data["qty"])?;
+
+    let fee = value_to_decimal(&data["commission"]).map(|amount| {
+        let asset = data["commissionAsset"].as_str().unwrap_or_default().to_string();
+        // Only a fee in the quote asset is a rate of the notional
+        let notional = price * quantity;
+        let rate = if asset.eq_ignore_ascii_case(symbol.quote()) && !notional.is_zero() {
+            amount / notional
+        } else {
+            Decimal::ZERO
+        };
+        OrderFee { asset, amount, rate }
+    });
+
+    Some(OrderFill {
+        id: id_string(&data["id"])?,
+        order_id: known
+            .iter()
+            .find(|(_, (_, venue))| *venue == venue_order_id)
+            .map(|(id, _)| id.clone())
+            .unwrap_or_default(),
+        venue_order_id,
+        price,
+        quantity,
+        fee,
+        timestamp: data["time"].as_i64().and_then(DateTime::<Utc>::from_timestamp_millis)?,
+        is_maker: data["isMaker"].as_bool().unwrap_or(false),
+    })
+}
 
 pub struct BinanceAdapter {
     client: Client,
-    api_key: Option<String>,
-    api_secret: Option<String>,
+    tls_pins: Option<TlsPins>,
+    proxy: Option<ProxyConfig>,
+    /// Replaced whole on rotation; requests copy them out rather than hold
+    /// the lock while in flight.
+    credentials: RwLock<Option<VenueCredentials>>,
     base_url: String,
     ws_url: String,
     connected: bool,
+    /// Channels carried by `market_data_stream`.
+    subscriptions: Vec<(Symbol, BinanceChannel)>,
+    /// Streams `market_data_stream` puts on one connection before opening
+    /// another.
+    streams_per_connection: usize,
+    recv_window: u64,
+    /// Binance's clock, measured on connect and by whoever syncs clocks,
+    /// that signed requests are stamped with.
+    clock: SharedClockSkew,
+    /// Symbol and venue id of orders placed through this adapter; Binance
+    /// needs the symbol to cancel.
+    orders: OrderIds,
+    /// Symbol and order list id of OCO orders placed through this adapter.
+    order_lists: OrderIds,
+    /// Margin account orders go to; `None` trades the spot wallet.
+    margin: Option<MarginMode>,
+    /// Margin orders borrow what they lack and repay loans from proceeds.
+    auto_borrow: bool,
+    /// API key user data streams listen under; they reopen when it is
+    /// rotated.
+    user_data_key: watch::Sender<Option<SecretString>>,
+    /// Request weight used this minute, which requests wait on near the
+    /// limit.
+    weight: UsedWeight,
+    /// Binance's request and order budget, shared with whoever else sends
+    /// it requests.
+    rate_limiter: SharedRateLimiter,
+    /// Circuit the market data connection reconnects through.
+    circuit: SharedCircuitBreaker,
+    /// Ping frames on the market data connections and the pongs answering
+    /// them.
+    heartbeat: HeartbeatManager,
+    /// `exchangeInfo`, which every symbol lookup reads and which costs 20
+    /// weight a request.
+    cache: ResponseCache,
+    /// GETs being sent, which identical ones arriving meanwhile share.
+    in_flight: SingleFlight<String, serde_json::Value>,
+}
+
+/// Sends a request carrying the API key header, mapping Binance's error
+/// codes onto error kinds. It waits first for room in `rate_limiter`, and
+/// if `weight` is near the limit, and feeds the response back to both.
+async fn keyed_request(
+    client: &Client,
+    weight: &UsedWeight,
+    rate_limiter: &WeightedRateLimiter,
+    method: Method,
+    url: &str,
+    api_key: &str,
+) -> Result<serde_json::Value> {
+    acquire(rate_limiter, &method, url).await;
+    weight.throttle().await;
+    let response = client
+        .request(method, url)
+        .header("X-MBX-APIKEY", api_key)
+        .send()
+        .await
+        .map_err(ArbFinderError::Http)?;
+
+    let status = response.status();
+    let now = Utc::now();
+    weight.record(status, response.headers(), now);
+    rate_limiter.record_response(status.as_u16(), retry_after(response.headers(), now));
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS || status == reqwest::StatusCode::IM_A_TEAPOT {
+        return Err(ArbFinderError::RateLimit("Binance rate limit exceeded".to_string()));
+    }
+
+    let body: serde_json::Value = response.json().await.map_err(ArbFinderError::Http)?;
+    if !status.is_success() {
+        let code = body["code"].as_i64().unwrap_or_default();
+        let msg = body["msg"].as_str().unwrap_or_default();
+        return Err(match code {
+            // -1021 is a timestamp outside recvWindow
+            -1021 | -1022 | -2014 | -2015 => ArbFinderError::Authentication(format!("Binance: {}", msg)),
+            -2010 if msg.contains("insufficient balance") => {
+                ArbFinderError::InsufficientBalance(format!("Binance: {}", msg))
+            }
+            -2010 | -1013 => ArbFinderError::InvalidOrder(format!("Binance: {}", msg)),
+            _ => ArbFinderError::Exchange(format!("Binance API error {} ({}): {}", status, code, msg)),
+        });
+    }
+
+    Ok(body)
+}
+
+/// Waits for room in `rate_limiter` for a request to `url`.
+async fn acquire(rate_limiter: &WeightedRateLimiter, method: &Method, url: &str) {
+    let path = url::Url::parse(url).map(|url| url.path().to_string()).unwrap_or_default();
+    let places_order = *method != Method::GET && rate_limiter.is_order_endpoint(&path);
+    let priority = RequestPriority::for_request(method, places_order);
+    rate_limiter.acquire_with_priority(&path, places_order, priority).await;
 }
 
 impl BinanceAdapter {
     pub fn new() -> Self {
         Self {
             client: Client::new(),
-            api_key: None,
-            api_secret: None,
+            tls_pins: None,
+            proxy: None,
+            credentials: RwLock::new(None),
             base_url: BINANCE_API_URL.to_string(),
-            ws_url: BINANCE_WS_URL.to_string(),
+            ws_url: websocket::BINANCE_WS_BASE.to_string(),
             connected: false,
+            subscriptions: Vec::new(),
+            streams_per_connection: DEFAULT_STREAMS_PER_CONNECTION,
+            recv_window: BINANCE_DEFAULT_RECV_WINDOW,
+            clock: ClockSkew::default().shared(),
+            orders: OrderIds::default(),
+            order_lists: OrderIds::default(),
+            margin: None,
+            auto_borrow: false,
+            user_data_key: watch::channel(None).0,
+            weight: UsedWeight::new(BINANCE_DEFAULT_WEIGHT_BUDGET),
+            rate_limiter: WeightedRateLimiter::new(&binance_rate_limits()).shared(),
+            circuit: CircuitBreaker::default().shared(),
+            heartbeat: HeartbeatManager::default(),
+            cache: exchange_info_cache(DEFAULT_SYMBOLS_TTL),
+            in_flight: SingleFlight::new(),
         }
     }
 
-    pub fn with_credentials(api_key: String, api_secret: String) -> Self {
+    pub fn with_credentials(api_key: impl Into<SecretString>, api_secret: impl Into<SecretString>) -> Self {
+        let api_key = api_key.into();
         Self {
             client: Client::new(),
-            api_key: Some(api_key),
-            api_secret: Some(api_secret),
+            tls_pins: None,
+            proxy: None,
+            credentials: RwLock::new(Some(VenueCredentials {
+                api_key: api_key.clone(),
+                secret_key: api_secret.into(),
+                passphrase: None,
+                sandbox: false,
+            })),
             base_url: BINANCE_API_URL.to_string(),
-            ws_url: BINANCE_WS_URL.to_string(),
+            ws_url: websocket::BINANCE_WS_BASE.to_string(),
             connected: false,
+            subscriptions: Vec::new(),
+            streams_per_connection: DEFAULT_STREAMS_PER_CONNECTION,
+            recv_window: BINANCE_DEFAULT_RECV_WINDOW,
+            clock: ClockSkew::default().shared(),
+            orders: OrderIds::default(),
+            order_lists: OrderIds::default(),
+            margin: None,
+            auto_borrow: false,
+            user_data_key: watch::channel(Some(api_key)).0,
+            weight: UsedWeight::new(BINANCE_DEFAULT_WEIGHT_BUDGET),
+            rate_limiter: WeightedRateLimiter::new(&binance_rate_limits()).shared(),
+            circuit: CircuitBreaker::default().shared(),
+            heartbeat: HeartbeatManager::default(),
+            cache: exchange_info_cache(DEFAULT_SYMBOLS_TTL),
+            in_flight: SingleFlight::new(),
+        }
+    }
+
+    /// Only talks to servers presenting one of the pinned keys, over REST
+    /// and WebSocket alike.
+    pub fn with_tls_pins(mut self, pins: TlsPins) -> Result<Self> {
+        self.tls_pins = Some(pins);
+        self.client = http_client(Client::builder(), self.proxy.as_ref(), self.tls_pins.as_ref())?;
+        Ok(self)
+    }
+
+    /// Sends REST requests and opens WebSocket connections through `proxy`.
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Result<Self> {
+        self.proxy = Some(proxy);
+        self.client = http_client(Client::builder(), self.proxy.as_ref(), self.tls_pins.as_ref())?;
+        Ok(self)
+    }
+
+    /// Points REST requests at `base_url` and WebSocket streams at
+    /// `ws_url`, e.g. a local mock of either.
+    pub fn with_base_urls(mut self, base_url: impl Into<String>, ws_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self.cache.clear();
+        self.ws_url = ws_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Targets the spot testnet, testnet.binance.vision, instead of live
+    /// trading. Testnet keys are separate from live ones and its funds are
+    /// not real.
+    pub fn sandbox(self, sandbox: bool) -> Self {
+        if sandbox {
+            self.with_base_urls(BINANCE_TESTNET_API_URL, websocket::BINANCE_TESTNET_WS_BASE)
+        } else {
+            self.with_base_urls(BINANCE_API_URL, websocket::BINANCE_WS_BASE)
+        }
+    }
+
+    pub fn is_sandbox(&self) -> bool {
+        self.base_url == BINANCE_TESTNET_API_URL
+    }
+
+    /// Trades the `mode` margin account instead of the spot wallet. With
+    /// `auto_borrow`, sells borrow what the account lacks and buys repay
+    /// what it owes. The spot testnet has no margin trading.
+    pub fn with_margin(mut self, mode: MarginMode, auto_borrow: bool) -> Self {
+        self.margin = Some(mode);
+        self.auto_borrow = auto_borrow;
+        self
+    }
+
+    /// Sets the `recvWindow` sent with signed requests, capped at Binance's
+    /// maximum of 60 seconds.
+    pub fn with_recv_window(mut self, recv_window_ms: u64) -> Self {
+        self.recv_window = recv_window_ms.min(60_000);
+        self
+    }
+
+    /// Request weight a minute the adapter uses before waiting for the
+    /// next minute, capped at Binance's limit of 6000. Lower it when other
+    /// processes share the IP.
+    pub fn with_weight_budget(mut self, budget: u32) -> Self {
+        self.weight = UsedWeight::new(budget.min(weight::BINANCE_WEIGHT_LIMIT));
+        self
+    }
+
+    /// Streams `market_data_stream` puts on one connection before opening
+    /// another, capped at Binance's limit of 1024.
+    pub fn with_streams_per_connection(mut self, streams: usize) -> Self {
+        self.streams_per_connection = streams.clamp(1, BINANCE_MAX_STREAMS_PER_CONNECTION);
+        self
+    }
+
+    /// How long `exchangeInfo`, and with it symbols and trading rules, is
+    /// kept before being fetched again. Zero fetches it every time.
+    pub fn with_symbols_ttl(mut self, ttl: Duration) -> Self {
+        self.cache = exchange_info_cache(ttl);
+        self
+    }
+
+    /// Draws requests from `rate_limiter` instead of Binance's default
+    /// limits, e.g. to share one budget between adapters on the same key.
+    pub fn with_rate_limiter(mut self, rate_limiter: SharedRateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Request weight used in the current minute, as Binance last reported
+    /// it.
+    pub fn used_weight(&self) -> u32 {
+        self.weight.used(Utc::now())
+    }
+
+    fn credentials(&self) -> Result<VenueCredentials> {
+        self.credentials
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .ok_or_else(|| ArbFinderError::Authentication("Binance API key not configured".to_string()))
+    }
+
+    /// Hex HMAC-SHA256 of the query string.
+    fn sign(secret: &SecretString, query: &str) -> Result<String> {
+        use hmac::{Hmac, Mac};
+        use sha2::Sha256;
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.expose().as_bytes())
+            .map_err(|e| ArbFinderError::Authentication(format!("Invalid secret key: {}", e)))?;
+        mac.update(query.as_bytes());
+
+        Ok(hex::encode(mac.finalize().into_bytes()))
+    }
+
+    /// `params` with timestamp and recvWindow appended, URL-encoded and
+    /// signed.
+    fn signed_query(&self, credentials: &VenueCredentials, params: &[(&str, String)]) -> Result<String> {
+        let timestamp = self.clock.lock().unwrap_or_else(|e| e.into_inner()).venue_now().timestamp_millis();
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        for (key, value) in params {
+            query.append_pair(key, value);
+        }
+        query.append_pair("timestamp", &timestamp.to_string());
+        query.append_pair("recvWindow", &self.recv_window.to_string());
+        let query = query.finish();
+
+        let signature = Self::sign(&credentials.secret_key, &query)?;
+        Ok(format!("{}&signature={}", query, signature))
+    }
+
+    /// Sends a signed request. Identical concurrent GETs share one, told
+    /// apart by their parameters before the timestamp and signature.
+    async fn signed_request(
+        &self,
+        method: Method,
+        endpoint: &str,
+        params: &[(&str, String)],
+    ) -> Result<serde_json::Value> {
+        if method == Method::GET {
+            let query: Vec<String> = params.iter().map(|(key, value)| format!("{}={}", key, value)).collect();
+            let key = format!("{}?{}", endpoint, query.join("&"));
+            return self.in_flight.run(key, || self.send_signed(method, endpoint, params)).await;
+        }
+        self.send_signed(method, endpoint, params).await
+    }
+
+    async fn send_signed(
+        &self,
+        method: Method,
+        endpoint: &str,
+        params: &[(&str, String)],
+    ) -> Result<serde_json::Value> {
+        // Signed with one copy, so a rotation can't pair the old key with the new secret
+        let credentials = self.credentials()?;
+        let url = format!("{}{}?{}", self.base_url, endpoint, self.signed_query(&credentials, params)?);
+        keyed_request(&self.client, &self.weight, &self.rate_limiter, method, &url, credentials.api_key.expose()).await
+    }
+
+    fn unsubscribe(&mut self, symbol: &Symbol, channel: impl Fn(&BinanceChannel) -> bool) {
+        self.subscriptions.retain(|(s, c)| s != symbol || !channel(c));
+    }
+
+    /// Places an OCO order as a Binance order list, tracking it by its list
+    /// id so both legs cancel together.
+    async fn place_oco_order(&self, mut order: Order, request: &OrderRequest, client_order_id: &str) -> Result<Order> {
+        let params = oco_params(request, client_order_id)?;
+        let data = self.signed_request(Method::POST, "/api/v3/orderList/oco", &params).await?;
+
+        apply_order_list_response(&mut order, &data)?;
+        if let Some(list_id) = &order.venue_order_id {
+            self.order_lists
+                .write()
+                .map_err(|e| ArbFinderError::Internal(e.to_string()))?
+                .insert(order.id.clone(), (order.symbol.clone(), list_id.clone()));
+        }
+        Ok(order)
+    }
+
+    /// Order and balance updates for the account. The listen key is kept
+    /// alive every 30 minutes until the stream is dropped. When the API key
+    /// is rotated, a stream under the new key replaces the old one.
+    pub async fn user_data_stream(&self) -> Result<Pin<Box<dyn Stream<Item = Result<UserDataEvent>> + Send>>> {
+        let mut api_keys = self.user_data_key.subscribe();
+        let mut api_key = api_keys.borrow_and_update().clone()
+            .ok_or_else(|| ArbFinderError::Authentication("Binance API key not configured".to_string()))?;
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let source = UserDataSource {
+            client: self.client.clone(),
+            base_url: self.base_url.clone(),
+            ws_url: self.ws_url.clone(),
+            tls_pins: self.tls_pins.clone(),
+            proxy: self.proxy.clone(),
+            weight: self.weight.clone(),
+            rate_limiter: Arc::clone(&self.rate_limiter),
+            orders: Arc::clone(&self.orders),
+            order_lists: Arc::clone(&self.order_lists),
+        };
+        let (mut listen_key, mut connection) = source.open(api_key.expose(), tx.clone()).await?;
+
+        tokio::spawn(async move {
+            let mut keepalive = tokio::time::interval(BINANCE_LISTEN_KEY_KEEPALIVE);
+            keepalive.tick().await;
+            // Until the adapter is dropped
+            let mut rotating = true;
+            loop {
+                tokio::select! {
+                    _ = tx.closed() => break,
+                    _ = keepalive.tick() => {
+                        if let Err(e) = source.listen_key_request(Method::PUT, api_key.expose(), &listen_key).await {
+                            warn!("Failed to keep Binance listen key alive: {}", e);
+                        }
+                    }
+                    changed = api_keys.changed(), if rotating => {
+                        let rotated = api_keys.borrow_and_update().clone();
+                        match (changed, rotated) {
+                            (Ok(()), Some(rotated)) => match source.open(rotated.expose(), tx.clone()).await {
+                                // Opened before the old stream closes, so no update is missed
+                                Ok((rotated_listen_key, rotated_connection)) => {
+                                    connection.abort();
+                                    let _ = source.listen_key_request(Method::DELETE, api_key.expose(), &listen_key).await;
+                                    (api_key, listen_key, connection) = (rotated, rotated_listen_key, rotated_connection);
+                                    info!("Reopened Binance user data stream under the rotated API key");
+                                }
+                                Err(e) => warn!("Keeping Binance user data stream under the previous API key: {}", e),
+                            },
+                            (Ok(()), None) => {}
+                            (Err(_), _) => rotating = false,
+                        }
+                    }
+                }
+            }
+            connection.abort();
+        });
+
+        Ok(channel_stream(rx))
+    }
+
+    /// Binance symbol names mapped to symbols, for orders listed across
+    /// every symbol.
+    async fn symbol_names(&self) -> Result<HashMap<String, Symbol>> {
+        let response = self.exchange_info().await?;
+        Ok(response["symbols"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|data| {
+                Some((
+                    data["symbol"].as_str()?.to_string(),
+                    Symbol::new(data["baseAsset"].as_str()?, data["quoteAsset"].as_str()?),
+                ))
+            })
+            .collect())
+    }
+
+    /// `symbol`, or else the symbols of orders placed through this adapter.
+    fn history_symbols(&self, symbol: Option<&Symbol>) -> Result<Vec<Symbol>> {
+        if let Some(symbol) = symbol {
+            return Ok(vec![symbol.clone()]);
         }
+        let orders = self.orders.read().map_err(|e| ArbFinderError::Internal(e.to_string()))?;
+        let mut symbols: Vec<Symbol> = Vec::new();
+        for (symbol, _) in orders.values() {
+            if !symbols.contains(symbol) {
+                symbols.push(symbol.clone());
+            }
+        }
+        Ok(symbols)
     }
 
+    /// Parameters of an order or trade history request on `symbol`, of at
+    /// most the 1000 entries Binance allows.
+    fn history_params(&self, symbol: &Symbol, limit: Option<u32>) -> Vec<(&'static str, String)> {
+        let mut params = vec![("symbol", binance_symbol(symbol))];
+        if let Some(limit) = limit {
+            params.push(("limit", limit.min(1000).to_string()));
+        }
+        if let Some(mode) = self.margin {
+            params.push(mode.isolated_param());
+        }
+        params
+    }
+
+    /// `exchangeInfo`, refetched behind other requests once stale.
+    async fn exchange_info(&self) -> Result<serde_json::Value> {
+        self.cache
+            .get_or_fetch(BINANCE_EXCHANGE_INFO, || {
+                RequestPriority::Background.scope(self.get_request(BINANCE_EXCHANGE_INFO))
+            })
+            .await
+    }
+
+    /// Sends a public GET, or joins an identical one already in flight.
     async fn get_request(&self, endpoint: &str) -> Result<serde_json::Value> {
+        self.in_flight.run(endpoint.to_string(), || self.send_get(endpoint)).await
+    }
+
+    async fn send_get(&self, endpoint: &str) -> Result<serde_json::Value> {
         let url = format!("{}{}", self.base_url, endpoint);
+        acquire(&self.rate_limiter, &Method::GET, &url).await;
+        self.weight.throttle().await;
         let response = self.client
             .get(&url)
             .send()
             .await
             .map_err(|e| ArbFinderError::Http(e))?;
+        let now = Utc::now();
+        self.weight.record(response.status(), response.headers(), now);
+        self.rate_limiter.record_response(response.status().as_u16(), retry_after(response.headers(), now));
 
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS
+            || response.status() == reqwest::StatusCode::IM_A_TEAPOT
+        {
+            return Err(ArbFinderError::RateLimit("Binance rate limit exceeded".to_string()));
+        }
         if !response.status().is_success() {
             return Err(ArbFinderError::Exchange(format!(
                 "Binance API error: {}",
@@ -65,13 +1015,13 @@ impl BinanceAdapter {
         response.json().await.map_err(|e| ArbFinderError::Http(e))
     }
 
-    /// Fetch orderbook depth from Binance
+    /// Fetch orderbook depth from Binance, behind other requests
     pub async fn get_orderbook(&self, symbol: &Symbol, limit: Option<u32>) -> Result<OrderBook> {
         let symbol_str = format!("{}{}", symbol.base(), symbol.quote());
         let limit = limit.unwrap_or(100).min(5000); // Binance max is 5000
         let endpoint = format!("/api/v3/depth?symbol={}&limit={}", symbol_str, limit);
         
-        let response = self.get_request(&endpoint).await?;
+        let response = RequestPriority::Background.scope(self.get_request(&endpoint)).await?;
         
         let mut orderbook = OrderBook::new(symbol.clone());
         
@@ -114,14 +1064,16 @@ impl Default for BinanceAdapter {
 }
 
 #[async_trait]
-impl ExchangeAdapter for BinanceAdapter {
+impl MarketDataAdapter for BinanceAdapter {
     fn venue_id(&self) -> VenueId {
         VenueId::Binance
     }
 
     async fn connect(&mut self) -> Result<()> {
-        // Test connection with server time
-        let _ = self.get_server_time().await?;
+        // Test connection with server time, and keep the clock offset so
+        // signed requests land inside recvWindow
+        let sample = measure_clock_skew(&*self).await?;
+        self.clock.lock().unwrap_or_else(|e| e.into_inner()).record(sample);
         self.connected = true;
         Ok(())
     }
@@ -152,7 +1104,7 @@ impl ExchangeAdapter for BinanceAdapter {
     }
 
     async fn get_symbols(&self) -> Result<Vec<Symbol>> {
-        let response = self.get_request("/api/v3/exchangeInfo").await?;
+        let response = self.exchange_info().await?;
         let symbols = response["symbols"]
             .as_array()
             .ok_or_else(|| ArbFinderError::InvalidData("Missing symbols array".to_string()))?;
@@ -170,8 +1122,16 @@ impl ExchangeAdapter for BinanceAdapter {
         Ok(result)
     }
 
+    fn venue_symbol(&self, symbol: &Symbol) -> String {
+        binance_symbol(symbol)
+    }
+
+    fn clear_response_cache(&self) {
+        self.cache.clear();
+    }
+
     async fn get_symbol_info(&self, symbol: &Symbol) -> Result<SymbolInfo> {
-        let response = self.get_request("/api/v3/exchangeInfo").await?;
+        let response = self.exchange_info().await?;
         let symbols = response["symbols"].as_array().ok_or_else(|| {
             ArbFinderError::InvalidData("Missing symbols array".to_string())
         })?;
@@ -180,71 +1140,285 @@ impl ExchangeAdapter for BinanceAdapter {
         
         for symbol_data in symbols {
             if symbol_data["symbol"].as_str() == Some(&symbol_str) {
-                return Ok(SymbolInfo {
-                    symbol: symbol.clone(),
-                    status: symbol_data["status"].as_str().unwrap_or("UNKNOWN").to_string(),
-                    base_asset_precision: symbol_data["baseAssetPrecision"].as_u64().unwrap_or(8) as u32,
-                    quote_asset_precision: symbol_data["quoteAssetPrecision"].as_u64().unwrap_or(8) as u32,
-                    tick_size: Decimal::new(1, 8),
-                    lot_size: Decimal::new(1, 8),
-                    min_order_size: Decimal::new(1, 8),
-                    max_order_size: Decimal::new(1000000, 0),
-                    min_notional: Decimal::new(10, 0),
-                    trading_fees: TradingFees {
-                        maker_fee: Decimal::new(1, 3), // 0.1%
-                        taker_fee: Decimal::new(1, 3), // 0.1%
-                    },
-                });
+                return Ok(parse_symbol_info(symbol, symbol_data));
             }
         }
 
         Err(ArbFinderError::SymbolNotFound(symbol_str))
     }
 
-    async fn subscribe_orderbook(&mut self, _symbol: &Symbol, _depth: Option<u32>) -> Result<()> {
-        // WebSocket subscription would go here
+    async fn subscribe_orderbook(&mut self, symbol: &Symbol, depth: Option<u32>) -> Result<()> {
+        self.unsubscribe(symbol, |channel| matches!(channel, BinanceChannel::Depth(_)));
+        self.subscriptions.push((symbol.clone(), BinanceChannel::depth(depth)));
         Ok(())
     }
 
-    async fn subscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
-        // WebSocket subscription would go here
+    async fn subscribe_trades(&mut self, symbol: &Symbol) -> Result<()> {
+        self.unsubscribe(symbol, |channel| *channel == BinanceChannel::Trade);
+        self.subscriptions.push((symbol.clone(), BinanceChannel::Trade));
         Ok(())
     }
 
-    async fn subscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
-        // WebSocket subscription would go here
+    async fn subscribe_ticker(&mut self, symbol: &Symbol) -> Result<()> {
+        self.unsubscribe(symbol, |channel| *channel == BinanceChannel::Ticker);
+        self.subscriptions.push((symbol.clone(), BinanceChannel::Ticker));
         Ok(())
     }
 
-    async fn unsubscribe_orderbook(&mut self, _symbol: &Symbol) -> Result<()> {
+    async fn subscribe_bbo(&mut self, symbol: &Symbol) -> Result<()> {
+        self.unsubscribe(symbol, |channel| *channel == BinanceChannel::BookTicker);
+        self.subscriptions.push((symbol.clone(), BinanceChannel::BookTicker));
         Ok(())
     }
 
-    async fn unsubscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+    async fn unsubscribe_orderbook(&mut self, symbol: &Symbol) -> Result<()> {
+        self.unsubscribe(symbol, |channel| matches!(channel, BinanceChannel::Depth(_)));
         Ok(())
     }
 
-    async fn unsubscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+    async fn unsubscribe_trades(&mut self, symbol: &Symbol) -> Result<()> {
+        self.unsubscribe(symbol, |channel| *channel == BinanceChannel::Trade);
+        Ok(())
+    }
+
+    async fn unsubscribe_ticker(&mut self, symbol: &Symbol) -> Result<()> {
+        self.unsubscribe(symbol, |channel| *channel == BinanceChannel::Ticker);
+        Ok(())
+    }
+
+    async fn unsubscribe_bbo(&mut self, symbol: &Symbol) -> Result<()> {
+        self.unsubscribe(symbol, |channel| *channel == BinanceChannel::BookTicker);
         Ok(())
     }
 
     async fn market_data_stream(&self) -> Result<MarketDataStream> {
-        Err(ArbFinderError::Exchange("Market data stream not implemented yet".to_string()))
+        if self.subscriptions.is_empty() {
+            return Err(ArbFinderError::Exchange("No Binance streams subscribed".to_string()));
+        }
+
+        let streams: Vec<String> = self
+            .subscriptions
+            .iter()
+            .map(|(symbol, channel)| channel.stream_name(symbol))
+            .collect();
+        // Streams are subscribed per connection, so that they can move
+        // between connections
+        let config = DefaultExchangeConfig {
+            base_url: self.base_url.clone(),
+            websocket_url: format!("{}/stream", self.ws_url),
+            tls_pins: self.tls_pins.clone(),
+            proxy: self.proxy.clone(),
+            circuit_breaker: Some(self.circuit.clone()),
+            heartbeat: Some(self.heartbeat.clone()),
+            ..Default::default()
+        };
+        let symbols: Vec<Symbol> = self.subscriptions.iter().map(|(symbol, _)| symbol.clone()).collect();
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        StreamMultiplexer::new(config, symbols, self.streams_per_connection, tx)
+            .start(streams)
+            .await?;
+        Ok(channel_stream(rx))
+    }
+
+    fn circuit_breaker(&self) -> Option<SharedCircuitBreaker> {
+        Some(self.circuit.clone())
+    }
+
+    fn clock_skew(&self) -> Option<SharedClockSkew> {
+        Some(Arc::clone(&self.clock))
+    }
+
+    fn heartbeat(&self) -> Option<HeartbeatManager> {
+        Some(self.heartbeat.clone())
+    }
+
+    async fn venue_status(&self) -> Result<VenueStatusReport> {
+        let response = self.get_request(BINANCE_SYSTEM_STATUS).await?;
+        parse_system_status(&response)
+    }
+
+    async fn fetch_orderbook(&self, symbol: &Symbol, depth: Option<u32>) -> Result<OrderBook> {
+        self.get_orderbook(symbol, depth).await
+    }
+
+    async fn get_klines(&self, symbol: &Symbol, interval: CandleInterval, limit: u32) -> Result<Vec<Candle>> {
+        // Binance names its intervals as we do, and returns at most 1000
+        let endpoint = format!(
+            "/api/v3/klines?symbol={}&interval={}&limit={}",
+            binance_symbol(symbol),
+            interval,
+            limit.clamp(1, 1000)
+        );
+        let response = self.get_request(&endpoint).await?;
+        let klines = response
+            .as_array()
+            .ok_or_else(|| ArbFinderError::InvalidData("Expected klines array".to_string()))?;
+        Ok(klines.iter().filter_map(|kline| parse_kline(symbol, interval, kline)).collect())
     }
 
+    async fn get_recent_trades(&self, symbol: &Symbol, limit: u32) -> Result<Vec<Trade>> {
+        // Oldest first already, and at most 1000
+        let endpoint = format!("/api/v3/trades?symbol={}&limit={}", binance_symbol(symbol), limit.clamp(1, 1000));
+        let response = self.get_request(&endpoint).await?;
+        let trades = response
+            .as_array()
+            .ok_or_else(|| ArbFinderError::InvalidData("Expected trades array".to_string()))?;
+        Ok(trades.iter().filter_map(|trade| parse_trade(symbol, trade)).collect())
+    }
+}
+
+#[async_trait]
+impl TradingAdapter for BinanceAdapter {
     async fn order_update_stream(&self) -> Result<OrderUpdateStream> {
-        Err(ArbFinderError::Exchange("Order update stream not implemented yet".to_string()))
+        let stream = self.user_data_stream().await?.filter_map(|event| async move {
+            match event {
+                Ok(UserDataEvent::Order(update)) => Some(Ok(update)),
+                Ok(UserDataEvent::Balances(_)) => None,
+                Err(e) => Some(Err(e)),
+            }
+        });
+        Ok(Box::pin(stream))
     }
 
-    async fn place_order(&mut self, _request: &OrderRequest) -> Result<Order> {
-        Err(ArbFinderError::Exchange("Order placement requires authenticated API - not implemented in this version".to_string()))
+    async fn place_order(&self, request: &OrderRequest) -> Result<Order> {
+        let mut order = match request.price {
+            Some(price) if request.order_type != OrderType::Market => Order::new_limit(
+                VenueId::Binance,
+                request.symbol.clone(),
+                request.side,
+                request.quantity,
+                price,
+            ),
+            _ => Order::new_market(VenueId::Binance, request.symbol.clone(), request.side, request.quantity),
+        };
+        order.order_type = request.order_type;
+        order.time_in_force = request.time_in_force;
+        order.stop_price = request.stop_price;
+
+        // Binance rejects a duplicate newClientOrderId among open orders,
+        // so retries with the same request are safe
+        let client_order_id = request
+            .client_order_id
+            .clone()
+            .unwrap_or_else(|| order.id.0.simple().to_string());
+        if request.order_type == OrderType::Oco {
+            if self.margin.is_some() {
+                return Err(ArbFinderError::InvalidOrder(
+                    "OCO orders are only placed from the spot wallet".to_string(),
+                ));
+            }
+            return self.place_oco_order(order, request, &client_order_id).await;
+        }
+        let mut params = order_params(request, &client_order_id)?;
+        let endpoint = match self.margin {
+            Some(mode) => {
+                params.extend(margin::margin_order_params(mode, self.auto_borrow));
+                "/sapi/v1/margin/order"
+            }
+            None => "/api/v3/order",
+        };
+        let data = self.signed_request(Method::POST, endpoint, &params).await?;
+
+        apply_order_response(&mut order, &data)?;
+        if request.size_unit == SizeUnit::Quote {
+            // origQty of a quote-sized order is what was filled, not the notional
+            order.quantity = order.filled_quantity;
+            order.remaining_quantity = Decimal::ZERO;
+        }
+        if let Some(venue_order_id) = &order.venue_order_id {
+            self.orders
+                .write()
+                .map_err(|e| ArbFinderError::Internal(e.to_string()))?
+                .insert(order.id.clone(), (order.symbol.clone(), venue_order_id.clone()));
+        }
+
+        Ok(order)
     }
 
-    async fn cancel_order(&mut self, _order_id: &OrderId) -> Result<()> {
-        Err(ArbFinderError::Exchange("Order cancellation not implemented yet".to_string()))
+    fn accepts_quote_size(&self, request: &OrderRequest) -> bool {
+        request.order_type == OrderType::Market
     }
 
-    async fn cancel_all_orders(&mut self, _symbol: Option<&Symbol>) -> Result<Vec<OrderId>> {
+    async fn cancel_order(&self, order_id: &OrderId) -> Result<()> {
+        let list = self
+            .order_lists
+            .read()
+            .map_err(|e| ArbFinderError::Internal(e.to_string()))?
+            .get(order_id)
+            .cloned();
+        if let Some((symbol, list_id)) = list {
+            // Canceling the list cancels both legs
+            let params = [("symbol", binance_symbol(&symbol)), ("orderListId", list_id)];
+            self.signed_request(Method::DELETE, "/api/v3/orderList", &params).await?;
+            if let Ok(mut lists) = self.order_lists.write() {
+                lists.remove(order_id);
+            }
+            return Ok(());
+        }
+
+        let (symbol, venue_order_id) = self
+            .orders
+            .read()
+            .map_err(|e| ArbFinderError::Internal(e.to_string()))?
+            .get(order_id)
+            .cloned()
+            .ok_or_else(|| ArbFinderError::InvalidOrder(format!("Unknown Binance order {}", order_id)))?;
+
+        let mut params = vec![("symbol", binance_symbol(&symbol)), ("orderId", venue_order_id)];
+        let endpoint = match self.margin {
+            Some(mode) => {
+                params.push(mode.isolated_param());
+                "/sapi/v1/margin/order"
+            }
+            None => "/api/v3/order",
+        };
+        self.signed_request(Method::DELETE, endpoint, &params).await?;
+        if let Ok(mut orders) = self.orders.write() {
+            orders.remove(order_id);
+        }
+        Ok(())
+    }
+
+    /// Spot orders only; margin orders have no amend endpoint.
+    fn supports_amend(&self) -> bool {
+        self.margin.is_none()
+    }
+
+    /// Binance amends keep the order's priority but can only lower its
+    /// quantity, so price changes are refused.
+    async fn amend_order(
+        &self,
+        order_id: &OrderId,
+        new_price: Option<Decimal>,
+        new_quantity: Option<Decimal>,
+    ) -> Result<()> {
+        if self.margin.is_some() {
+            return Err(ArbFinderError::Exchange("Binance margin orders cannot be amended".to_string()));
+        }
+        if new_price.is_some() {
+            return Err(ArbFinderError::InvalidOrder("Binance can only amend an order's quantity".to_string()));
+        }
+        let quantity =
+            new_quantity.ok_or_else(|| ArbFinderError::InvalidOrder("Amend needs a new quantity".to_string()))?;
+        let (symbol, venue_order_id) = self
+            .orders
+            .read()
+            .map_err(|e| ArbFinderError::Internal(e.to_string()))?
+            .get(order_id)
+            .cloned()
+            .ok_or_else(|| ArbFinderError::InvalidOrder(format!("Unknown Binance order {}", order_id)))?;
+
+        let params = [
+            ("symbol", binance_symbol(&symbol)),
+            ("orderId", venue_order_id),
+            ("newQty", quantity.normalize().to_string()),
+        ];
+        self.signed_request(Method::PUT, "/api/v3/order/amend/keepPriority", &params).await?;
+        Ok(())
+    }
+
+    async fn cancel_all_orders(&self, _symbol: Option<&Symbol>) -> Result<Vec<OrderId>> {
         Err(ArbFinderError::Exchange("Cancel all orders not implemented yet".to_string()))
     }
 
@@ -252,24 +1426,194 @@ impl ExchangeAdapter for BinanceAdapter {
         Ok(None)
     }
 
-    async fn get_open_orders(&self, _symbol: Option<&Symbol>) -> Result<Vec<Order>> {
-        Ok(Vec::new())
+    async fn get_open_orders(&self, symbol: Option<&Symbol>) -> Result<Vec<Order>> {
+        let (mut params, names) = match symbol {
+            Some(symbol) => (
+                vec![("symbol", binance_symbol(symbol))],
+                HashMap::from([(binance_symbol(symbol), symbol.clone())]),
+            ),
+            None => (Vec::new(), self.symbol_names().await?),
+        };
+        let endpoint = match self.margin {
+            Some(MarginMode::Isolated) if symbol.is_none() => {
+                return Err(ArbFinderError::InvalidData(
+                    "Open orders of an isolated margin account are listed per pair".to_string(),
+                ))
+            }
+            Some(mode) => {
+                params.push(mode.isolated_param());
+                "/sapi/v1/margin/openOrders"
+            }
+            None => "/api/v3/openOrders",
+        };
+        let response = self.signed_request(Method::GET, endpoint, &params).await?;
+
+        let known = self.orders.read().map_err(|e| ArbFinderError::Internal(e.to_string()))?;
+        let mut orders = Vec::new();
+        for data in response.as_array().into_iter().flatten() {
+            let Some(symbol) = data["symbol"].as_str().and_then(|name| names.get(name)) else {
+                continue;
+            };
+            orders.push(listed_order(symbol, data, &known)?);
+        }
+
+        Ok(orders)
     }
 
-    async fn get_order_history(&self, _symbol: Option<&Symbol>, _limit: Option<u32>) -> Result<Vec<Order>> {
-        Ok(Vec::new())
+    /// Binance lists orders per symbol, so without one only the symbols of
+    /// orders placed through this adapter are listed.
+    async fn get_order_history(&self, symbol: Option<&Symbol>, limit: Option<u32>) -> Result<Vec<Order>> {
+        let endpoint = match self.margin {
+            Some(_) => "/sapi/v1/margin/allOrders",
+            None => "/api/v3/allOrders",
+        };
+        let mut orders = Vec::new();
+        for symbol in self.history_symbols(symbol)? {
+            let response = self.signed_request(Method::GET, endpoint, &self.history_params(&symbol, limit)).await?;
+            let known = self.orders.read().map_err(|e| ArbFinderError::Internal(e.to_string()))?;
+            for data in response.as_array().into_iter().flatten() {
+                orders.push(listed_order(&symbol, data, &known)?);
+            }
+        }
+        Ok(orders)
     }
 
     async fn get_balances(&self) -> Result<Vec<Balance>> {
-        Ok(Vec::new())
+        if self.margin.is_none() {
+            let params = [("omitZeroBalances", "true".to_string())];
+            let response = self.signed_request(Method::GET, "/api/v3/account", &params).await?;
+            return parse_account_balances(&response);
+        }
+        // Loans show up as negative totals
+        Ok(self
+            .margin_assets()
+            .await?
+            .into_iter()
+            .map(|asset| {
+                let total = asset.free + asset.locked - asset.owed();
+                Balance::new(asset.asset, total, asset.free, asset.locked)
+            })
+            .collect())
     }
 
-    async fn get_balance(&self, _asset: &str) -> Result<Option<Balance>> {
-        Ok(None)
+    async fn get_balance(&self, asset: &str) -> Result<Option<Balance>> {
+        Ok(self
+            .get_balances()
+            .await?
+            .into_iter()
+            .find(|balance| balance.asset.eq_ignore_ascii_case(asset)))
+    }
+
+    /// Per symbol, like the order history.
+    async fn get_trade_history(&self, symbol: Option<&Symbol>, limit: Option<u32>) -> Result<Vec<OrderFill>> {
+        let endpoint = match self.margin {
+            Some(_) => "/sapi/v1/margin/myTrades",
+            None => "/api/v3/myTrades",
+        };
+        let mut fills = Vec::new();
+        for symbol in self.history_symbols(symbol)? {
+            let response = self.signed_request(Method::GET, endpoint, &self.history_params(&symbol, limit)).await?;
+            let known = self.orders.read().map_err(|e| ArbFinderError::Internal(e.to_string()))?;
+            fills.extend(
+                response
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|data| parse_my_trade(&symbol, data, &known)),
+            );
+        }
+        Ok(fills)
     }
 
-    async fn get_trade_history(&self, _symbol: Option<&Symbol>, _limit: Option<u32>) -> Result<Vec<OrderFill>> {
-        Ok(Vec::new())
+    async fn get_trading_fees(&self, symbol: &Symbol) -> Result<TradingFee> {
+        let response = self
+            .signed_request(Method::GET, "/sapi/v1/asset/tradeFee", &[("symbol", binance_symbol(symbol))])
+            .await?;
+        parse_trade_fee(&response)
+            .ok_or_else(|| ArbFinderError::InvalidData(format!("Missing trade fee for {}", symbol)))
+    }
+
+    /// An address on the asset's default network, which the response
+    /// leaves out, so it is looked up first.
+    async fn get_deposit_address(&self, asset: &str) -> Result<DepositAddress> {
+        let coins = self.signed_request(Method::GET, "/sapi/v1/capital/config/getall", &[]).await?;
+        let network = default_network(&coins, asset)
+            .ok_or_else(|| ArbFinderError::Exchange(format!("Binance has no deposit network for {}", asset)))?;
+
+        let params = [("coin", asset.to_string()), ("network", network.clone())];
+        let response = self.signed_request(Method::GET, "/sapi/v1/capital/deposit/address", &params).await?;
+        let mut address = parse_deposit_address(&response)
+            .ok_or_else(|| ArbFinderError::InvalidData(format!("Malformed deposit address for {}", asset)))?;
+        address.network = Some(network);
+        Ok(address)
+    }
+
+    async fn withdraw(&self, asset: &str, amount: Decimal, address: &DepositAddress) -> Result<Withdrawal> {
+        let mut params = vec![
+            ("coin", asset.to_string()),
+            ("address", address.address.clone()),
+            ("amount", amount.normalize().to_string()),
+        ];
+        if let Some(tag) = &address.tag {
+            params.push(("addressTag", tag.clone()));
+        }
+        if let Some(network) = &address.network {
+            params.push(("network", network.clone()));
+        }
+        let response = self.signed_request(Method::POST, "/sapi/v1/capital/withdraw/apply", &params).await?;
+        let id = response["id"]
+            .as_str()
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing withdrawal id".to_string()))?;
+
+        Ok(Withdrawal {
+            id: id.to_string(),
+            venue: VenueId::Binance,
+            asset: asset.to_string(),
+            amount,
+            address: address.clone(),
+            requested_at: Utc::now(),
+        })
+    }
+
+    async fn get_withdrawal_progress(&self, withdrawal: &Withdrawal) -> Result<WithdrawalProgress> {
+        let params = [("coin", withdrawal.asset.clone()), ("idList", withdrawal.id.clone())];
+        let response = self.signed_request(Method::GET, "/sapi/v1/capital/withdraw/history", &params).await?;
+        response
+            .as_array()
+            .into_iter()
+            .flatten()
+            .find(|entry| id_string(&entry["id"]).as_deref() == Some(withdrawal.id.as_str()))
+            .and_then(parse_withdrawal_progress)
+            .ok_or_else(|| ArbFinderError::InvalidData(format!("Binance has no withdrawal {}", withdrawal.id)))
+    }
+
+    async fn get_deposits(&self, asset: &str, since: DateTime<Utc>) -> Result<Vec<Deposit>> {
+        let params = [("coin", asset.to_string()), ("startTime", since.timestamp_millis().to_string())];
+        let response = self.signed_request(Method::GET, "/sapi/v1/capital/deposit/hisrec", &params).await?;
+        Ok(response.as_array().into_iter().flatten().filter_map(parse_deposit).collect())
+    }
+
+    async fn rotate_credentials(&self, credentials: &VenueCredentials) -> Result<bool> {
+        {
+            let mut current = self.credentials.write().unwrap_or_else(|e| e.into_inner());
+            let unchanged = current.as_ref().is_some_and(|current| {
+                current.api_key == credentials.api_key && current.secret_key == credentials.secret_key
+            });
+            if unchanged {
+                return Ok(false);
+            }
+            *current = Some(credentials.clone());
+        }
+        // Listen keys belong to the API key, not the secret
+        if self.user_data_key.borrow().as_ref() != Some(&credentials.api_key) {
+            self.user_data_key.send_replace(Some(credentials.api_key.clone()));
+        }
+        Ok(true)
+    }
+
+    async fn verify_credentials(&self) -> Result<()> {
+        let params = [("omitZeroBalances", "true".to_string())];
+        self.signed_request(Method::GET, "/api/v3/account", &params).await.map(|_| ())
     }
 
     async fn get_account_info(&self) -> Result<AccountInfo> {
@@ -299,6 +1643,354 @@ mod tests {
         assert!(!adapter.is_connected().await);
     }
 
+    #[test]
+    fn test_sandbox_urls() {
+        let adapter = BinanceAdapter::new().sandbox(true);
+        assert!(adapter.is_sandbox());
+        assert_eq!(adapter.base_url, "https://testnet.binance.vision");
+        assert_eq!(
+            websocket::combined_stream_url(&adapter.ws_url, &["btcusdt@trade".to_string()]),
+            "wss://stream.testnet.binance.vision/stream?streams=btcusdt@trade"
+        );
+        assert!(!adapter.sandbox(false).is_sandbox());
+
+        let mock = BinanceAdapter::new().with_base_urls("http://localhost:8080/", "ws://localhost:8081");
+        assert_eq!(mock.base_url, "http://localhost:8080");
+        assert!(!mock.is_sandbox());
+    }
+
+    #[test]
+    fn test_parse_system_status() {
+        let normal = parse_system_status(&serde_json::json!({"status": 0, "msg": "normal"})).unwrap();
+        assert_eq!(normal.health, VenueHealth::Operational);
+        let maintenance = parse_system_status(&serde_json::json!({"status": 1, "msg": "system_maintenance"})).unwrap();
+        assert!(!maintenance.health.accepts_orders());
+        assert!(parse_system_status(&serde_json::json!({})).is_err());
+    }
+
+    #[test]
+    fn test_parse_symbol_filters() {
+        let data = serde_json::json!({
+            "symbol": "BTCUSDT",
+            "status": "TRADING",
+            "baseAssetPrecision": 8,
+            "quoteAssetPrecision": 8,
+            "filters": [
+                {"filterType": "PRICE_FILTER", "minPrice": "0.01000000", "maxPrice": "1000000.00000000", "tickSize": "0.01000000"},
+                {"filterType": "LOT_SIZE", "minQty": "0.00001000", "maxQty": "9000.00000000", "stepSize": "0.00001000"},
+                {"filterType": "ICEBERG_PARTS", "limit": 10},
+                {"filterType": "NOTIONAL", "minNotional": "5.00000000", "applyMinToMarket": true, "maxNotional": "9000000.00000000"}
+            ]
+        });
+        let btc = Symbol::new("BTC", "USDT");
+        let info = parse_symbol_info(&btc, &data);
+        assert_eq!(info.status, "TRADING");
+        assert_eq!(info.tick_size, Decimal::new(1, 2));
+        assert_eq!(info.lot_size, Decimal::new(1, 5));
+        assert_eq!(info.min_order_size, Decimal::new(1, 5));
+        assert_eq!(info.max_order_size, Decimal::new(9000, 0));
+        assert_eq!(info.min_notional, Decimal::new(5, 0));
+
+        // Older listings carry MIN_NOTIONAL; a zero tick size means the
+        // filter is disabled
+        let data = serde_json::json!({
+            "status": "BREAK",
+            "filters": [
+                {"filterType": "PRICE_FILTER", "tickSize": "0.00000000"},
+                {"filterType": "MIN_NOTIONAL", "minNotional": "0.00010000"}
+            ]
+        });
+        let info = parse_symbol_info(&btc, &data);
+        assert_eq!(info.tick_size, Decimal::new(1, 8));
+        assert_eq!(info.min_notional, Decimal::new(1, 4));
+    }
+
+    #[test]
+    fn test_parse_kline() {
+        // From the Binance API docs
+        let kline = serde_json::json!([
+            1499040000000u64, "0.01634790", "0.80000000", "0.01575800", "0.01577100",
+            "148976.11427815", 1499644799999u64, "2434.19055334", 308, "1756.87402397",
+            "28.46694368", "0"
+        ]);
+        let candle = parse_kline(&Symbol::new("LTC", "BTC"), CandleInterval::OneDay, &kline).unwrap();
+        assert_eq!(candle.open, "0.01634790".parse::<Decimal>().unwrap());
+        assert_eq!(candle.high, "0.8".parse::<Decimal>().unwrap());
+        assert_eq!(candle.close, "0.01577100".parse::<Decimal>().unwrap());
+        assert_eq!(candle.volume, "148976.11427815".parse::<Decimal>().unwrap());
+        assert_eq!(candle.timestamp.timestamp_millis(), 1499040000000);
+        assert_eq!(candle.interval, "1d");
+        assert!(parse_kline(&Symbol::new("LTC", "BTC"), CandleInterval::OneDay, &serde_json::json!([])).is_none());
+    }
+
+    #[test]
+    fn test_parse_trade() {
+        let trade = serde_json::json!({
+            "id": 28457, "price": "4.00000100", "qty": "12.00000000", "quoteQty": "48.000012",
+            "time": 1499865549590u64, "isBuyerMaker": true, "isBestMatch": true
+        });
+        let parsed = parse_trade(&Symbol::new("LTC", "BTC"), &trade).unwrap();
+        assert_eq!(parsed.price, "4.000001".parse::<Decimal>().unwrap());
+        assert_eq!(parsed.quantity, Decimal::from(12));
+        assert_eq!(parsed.side, Side::Ask);
+        assert_eq!(parsed.trade_id, "28457");
+        assert_eq!(parsed.timestamp.timestamp_millis(), 1499865549590);
+    }
+
+    #[test]
+    fn test_parse_trade_fee() {
+        let data = serde_json::json!([{"symbol": "BTCUSDT", "makerCommission": "0.00075", "takerCommission": "0.001"}]);
+        let fee = parse_trade_fee(&data).unwrap();
+        assert_eq!(fee.maker_fee, "0.00075".parse::<Decimal>().unwrap());
+        assert_eq!(fee.taker_fee, "0.001".parse::<Decimal>().unwrap());
+        assert!(parse_trade_fee(&serde_json::json!([])).is_none());
+    }
+
+    #[test]
+    fn test_parse_account_balances() {
+        let data = serde_json::json!({
+            "makerCommission": 15, "canTrade": true, "accountType": "SPOT",
+            "balances": [
+                {"asset": "BTC", "free": "4723846.89208129", "locked": "0.00000000"},
+                {"asset": "BNB", "free": "0.25000000", "locked": "0.50000000"}
+            ]
+        });
+        let balances = parse_account_balances(&data).unwrap();
+        assert_eq!(balances.len(), 2);
+        assert_eq!(balances[1].asset, "BNB");
+        assert_eq!(balances[1].total, "0.75".parse::<Decimal>().unwrap());
+        assert_eq!(balances[1].available, "0.25".parse::<Decimal>().unwrap());
+        assert!(parse_account_balances(&serde_json::json!({"code": -2015})).is_err());
+    }
+
+    #[test]
+    fn test_parse_my_trade() {
+        let symbol = Symbol::new("BNB", "BTC");
+        let data = serde_json::json!({
+            "symbol": "BNBBTC", "id": 28457, "orderId": 100234, "orderListId": -1, "price": "4.00000100",
+            "qty": "12.00000000", "quoteQty": "48.000012", "commission": "0.00048000", "commissionAsset": "BTC",
+            "time": 1499865549590i64, "isBuyer": true, "isMaker": false, "isBestMatch": true
+        });
+        let ours = OrderId::new();
+        let known = HashMap::from([(ours.clone(), (symbol.clone(), "100234".to_string()))]);
+
+        let fill = parse_my_trade(&symbol, &data, &known).unwrap();
+        assert_eq!((fill.id.as_str(), fill.venue_order_id.as_str()), ("28457", "100234"));
+        assert_eq!(fill.order_id, ours);
+        assert_eq!(fill.quantity, Decimal::from(12));
+        assert_eq!(fill.fee.as_ref().unwrap().asset, "BTC");
+        assert!(!fill.fee.unwrap().rate.is_zero());
+        assert_ne!(parse_my_trade(&symbol, &data, &HashMap::new()).unwrap().order_id, ours);
+    }
+
+    #[test]
+    fn test_parse_deposit_address() {
+        let data = serde_json::json!({
+            "address": "1HPn8Rx2y6nNSfagQBKy27GB99Vbzg89wv", "coin": "BTC", "tag": "", "url": ""
+        });
+        let address = parse_deposit_address(&data).unwrap();
+        assert_eq!(address, DepositAddress::new("BTC", "1HPn8Rx2y6nNSfagQBKy27GB99Vbzg89wv"));
+
+        let data = serde_json::json!({"address": "bnb136ns6lfw4zs5hg4n85vdthaad7hq5m4gtkgf23", "coin": "BNB", "tag": "101"});
+        assert_eq!(parse_deposit_address(&data).unwrap().tag.as_deref(), Some("101"));
+
+        let coins = serde_json::json!([
+            {"coin": "USDT", "networkList": [
+                {"network": "BSC", "isDefault": false},
+                {"network": "ETH", "isDefault": true},
+            ]},
+            {"coin": "BTC", "networkList": [{"network": "BTC", "isDefault": true}]},
+        ]);
+        assert_eq!(default_network(&coins, "usdt").as_deref(), Some("ETH"));
+        assert_eq!(default_network(&coins, "BTC").as_deref(), Some("BTC"));
+        assert_eq!(default_network(&coins, "SOL"), None);
+    }
+
+    #[test]
+    fn test_parse_transfers() {
+        let data = serde_json::json!({
+            "id": "b6ae22b3aa844210a7041aee7589627c", "amount": "8.91000000", "transactionFee": "0.004",
+            "coin": "USDT", "status": 6, "txId": "0xb5ef8c13b968a406cc62a93a8bd80f9e9a906ef1b3fcf20a2e48573c17659268"
+        });
+        let progress = parse_withdrawal_progress(&data).unwrap();
+        assert_eq!(progress.status, TransferStatus::Completed);
+        assert!(progress.tx_id.unwrap().starts_with("0xb5ef"));
+        let data = serde_json::json!({"id": "b6ae22b3", "status": 4, "txId": ""});
+        assert_eq!(parse_withdrawal_progress(&data).unwrap(), WithdrawalProgress { status: TransferStatus::Pending, tx_id: None });
+
+        let data = serde_json::json!({
+            "id": "769800519366885376", "amount": "0.001", "coin": "BNB", "network": "BNB", "status": 0,
+            "txId": "98A3EA560C6B3336D348B6C83F0F95ECE4F1F5919E94BD006E5BF3BF264FACFC",
+            "insertTime": 1661493146000i64, "confirmTimes": "1/1"
+        });
+        let deposit = parse_deposit(&data).unwrap();
+        assert_eq!(deposit.id, "769800519366885376");
+        assert_eq!(deposit.amount, "0.001".parse::<Decimal>().unwrap());
+        assert_eq!(deposit.status, TransferStatus::Confirming);
+        assert_eq!(deposit.confirmations, Some(1));
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions() {
+        let mut adapter = BinanceAdapter::new();
+        let btc = Symbol::new("BTC", "USDT");
+        adapter.subscribe_orderbook(&btc, Some(5)).await.unwrap();
+        adapter.subscribe_orderbook(&btc, Some(20)).await.unwrap();
+        adapter.subscribe_trades(&btc).await.unwrap();
+        assert_eq!(
+            adapter.subscriptions,
+            vec![(btc.clone(), BinanceChannel::Depth(20)), (btc.clone(), BinanceChannel::Trade)]
+        );
+
+        adapter.unsubscribe_orderbook(&btc).await.unwrap();
+        assert_eq!(adapter.subscriptions, vec![(btc, BinanceChannel::Trade)]);
+    }
+
+    #[test]
+    fn test_sign_matches_binance_example() {
+        // Example from the Binance API docs
+        let adapter = BinanceAdapter::with_credentials(
+            "vmPUZE6mv9SD5VNHk4HlWFsOr6aKE2zvsw0MuIgwCIPy6utIco14y7Ju91duEh8A".to_string(),
+            "NhqPtmdSJYdKjVHjA7PZj4Mge3R5YNiP1e3UZjInClVN65XAbvqqM6A7H5fATj0j".to_string(),
+        );
+        let query = "symbol=LTCBTC&side=BUY&type=LIMIT&timeInForce=GTC&quantity=1&price=0.1&recvWindow=5000&timestamp=1499827319559";
+        let credentials = adapter.credentials().unwrap();
+        assert_eq!(
+            BinanceAdapter::sign(&credentials.secret_key, query).unwrap(),
+            "c8db56825ae71d6d79447849e617115f4a920fa2acdcab2b053c4b2838bd6b71"
+        );
+
+        let signed = adapter.signed_query(&credentials, &[("symbol", "LTCBTC".to_string())]).unwrap();
+        assert!(signed.starts_with("symbol=LTCBTC&timestamp="));
+        assert!(signed.contains("&recvWindow=5000&signature="));
+    }
+
+    #[tokio::test]
+    async fn test_rotate_credentials() {
+        let adapter = BinanceAdapter::with_credentials("old-key", "old-secret");
+        let mut api_keys = adapter.user_data_key.subscribe();
+        let credentials = |api_key: &str, secret_key: &str| VenueCredentials {
+            api_key: api_key.into(),
+            secret_key: secret_key.into(),
+            passphrase: None,
+            sandbox: false,
+        };
+
+        assert!(!adapter.rotate_credentials(&credentials("old-key", "old-secret")).await.unwrap());
+        assert!(!api_keys.has_changed().unwrap());
+
+        // A new secret alone leaves user data streams as they are
+        assert!(adapter.rotate_credentials(&credentials("old-key", "new-secret")).await.unwrap());
+        assert_eq!(adapter.credentials().unwrap().secret_key.expose(), "new-secret");
+        assert!(!api_keys.has_changed().unwrap());
+
+        assert!(adapter.rotate_credentials(&credentials("new-key", "new-secret")).await.unwrap());
+        assert_eq!(adapter.credentials().unwrap().api_key.expose(), "new-key");
+        assert_eq!(api_keys.borrow_and_update().as_ref().map(SecretString::expose), Some("new-key"));
+    }
+
+    #[test]
+    fn test_order_params() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let limit = OrderRequest::new_limit(symbol.clone(), OrderSide::Buy, Decimal::new(15, 1), Decimal::new(30000, 0));
+        let params: HashMap<_, _> = order_params(&limit, "abc").unwrap().into_iter().collect();
+        assert_eq!(params["symbol"], "BTCUSDT");
+        assert_eq!(params["type"], "LIMIT");
+        assert_eq!(params["timeInForce"], "GTC");
+        assert_eq!(params["quantity"], "1.5");
+        assert_eq!(params["price"], "30000");
+        assert_eq!(params["newClientOrderId"], "abc");
+
+        let maker: HashMap<_, _> = order_params(&limit.clone().post_only(), "abc").unwrap().into_iter().collect();
+        assert_eq!(maker["type"], "LIMIT_MAKER");
+        assert!(!maker.contains_key("timeInForce"));
+
+        let quote = OrderRequest::new_market_quote(symbol.clone(), OrderSide::Sell, Decimal::new(100, 0));
+        let params: HashMap<_, _> = order_params(&quote, "abc").unwrap().into_iter().collect();
+        assert_eq!(params["type"], "MARKET");
+        assert_eq!(params["quoteOrderQty"], "100");
+        assert!(!params.contains_key("quantity"));
+
+        assert!(order_params(&limit.in_quote(), "abc").is_err());
+    }
+
+    #[test]
+    fn test_oco_params() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let sell = OrderRequest::new_oco(symbol.clone(), OrderSide::Sell, Decimal::ONE, Decimal::new(32000, 0), Decimal::new(29000, 0));
+        let params: HashMap<_, _> = oco_params(&sell, "abc").unwrap().into_iter().collect();
+        assert_eq!(params["side"], "SELL");
+        assert_eq!(params["quantity"], "1");
+        assert_eq!(params["aboveType"], "LIMIT_MAKER");
+        assert_eq!(params["abovePrice"], "32000");
+        assert_eq!(params["belowType"], "STOP_LOSS");
+        assert_eq!(params["belowStopPrice"], "29000");
+        assert!(!params.contains_key("belowPrice"));
+        assert_eq!(params["listClientOrderId"], "abc");
+
+        let buy = OrderRequest::new_oco(symbol.clone(), OrderSide::Buy, Decimal::ONE, Decimal::new(29000, 0), Decimal::new(32000, 0))
+            .with_stop_limit(Decimal::new(32100, 0));
+        let params: HashMap<_, _> = oco_params(&buy, "abc").unwrap().into_iter().collect();
+        assert_eq!(params["belowType"], "LIMIT_MAKER");
+        assert_eq!(params["belowPrice"], "29000");
+        assert_eq!(params["aboveType"], "STOP_LOSS_LIMIT");
+        assert_eq!(params["aboveStopPrice"], "32000");
+        assert_eq!(params["abovePrice"], "32100");
+        assert_eq!(params["aboveTimeInForce"], "GTC");
+
+        let inverted = OrderRequest::new_oco(symbol, OrderSide::Sell, Decimal::ONE, Decimal::new(29000, 0), Decimal::new(32000, 0));
+        assert!(oco_params(&inverted, "abc").is_err());
+        assert!(order_params(&sell, "abc").is_err());
+    }
+
+    #[test]
+    fn test_apply_order_list_response() {
+        let data: serde_json::Value = serde_json::from_str(
+            r#"{"orderListId":1,"contingencyType":"OCO","listStatusType":"EXEC_STARTED","listOrderStatus":"EXECUTING",
+                "listClientOrderId":"abc","transactionTime":1712289389158,"symbol":"BTCUSDT",
+                "orders":[{"symbol":"BTCUSDT","orderId":2,"clientOrderId":"x1"},{"symbol":"BTCUSDT","orderId":3,"clientOrderId":"x2"}],
+                "orderReports":[
+                    {"symbol":"BTCUSDT","orderId":2,"orderListId":1,"price":"0.00000000","origQty":"1.00000000",
+                     "executedQty":"0.00000000","cummulativeQuoteQty":"0.00000000","status":"NEW","type":"STOP_LOSS",
+                     "side":"SELL","stopPrice":"29000.00000000"},
+                    {"symbol":"BTCUSDT","orderId":3,"orderListId":1,"price":"32000.00000000","origQty":"1.00000000",
+                     "executedQty":"0.00000000","cummulativeQuoteQty":"0.00000000","status":"NEW","type":"LIMIT_MAKER",
+                     "side":"SELL"}]}"#,
+        )
+        .unwrap();
+        let request = OrderRequest::new_oco(Symbol::new("BTC", "USDT"), OrderSide::Sell, Decimal::ONE, Decimal::new(32000, 0), Decimal::new(29000, 0));
+        let mut order = Order::new_limit(VenueId::Binance, request.symbol.clone(), request.side, request.quantity, Decimal::new(32000, 0));
+        apply_order_list_response(&mut order, &data).unwrap();
+
+        assert_eq!(order.venue_order_id.as_deref(), Some("1"));
+        assert_eq!(order.client_order_id.as_deref(), Some("abc"));
+        assert_eq!(order.status, OrderStatus::Open);
+        assert_eq!(order.remaining_quantity, Decimal::ONE);
+        assert_eq!(order.created_at.timestamp_millis(), 1712289389158);
+    }
+
+    #[test]
+    fn test_apply_order_response() {
+        let data: serde_json::Value = serde_json::from_str(
+            r#"{"symbol":"BTCUSDT","orderId":28,"clientOrderId":"6gCrw2kRUAF9CvJDGP16IP","transactTime":1507725176595,
+                "price":"30000.00000000","origQty":"2.00000000","executedQty":"0.50000000",
+                "cummulativeQuoteQty":"15000.00000000","status":"PARTIALLY_FILLED","timeInForce":"GTC",
+                "type":"LIMIT","side":"SELL"}"#,
+        )
+        .unwrap();
+        let mut order = Order::new_market(VenueId::Binance, Symbol::new("BTC", "USDT"), OrderSide::Buy, Decimal::ZERO);
+        apply_order_response(&mut order, &data).unwrap();
+
+        assert_eq!(order.venue_order_id.as_deref(), Some("28"));
+        assert_eq!(order.side, OrderSide::Sell);
+        assert_eq!(order.order_type, OrderType::Limit);
+        assert_eq!(order.status, OrderStatus::PartiallyFilled);
+        assert_eq!(order.quantity, Decimal::new(2, 0));
+        assert_eq!(order.remaining_quantity, Decimal::new(15, 1));
+        assert_eq!(order.average_fill_price, Some(Decimal::new(30000, 0)));
+        assert_eq!(order.created_at.timestamp_millis(), 1507725176595);
+    }
+
     #[tokio::test]
     async fn test_binance_connect() {
         let mut adapter = BinanceAdapter::new();
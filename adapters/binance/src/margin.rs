@@ -0,0 +1,201 @@
+//! Binance margin accounts.
+//!
+//! With a margin mode set, the adapter places, cancels and lists orders in
+//! the cross or isolated margin account instead of the spot wallet. Sells
+//! can then go short of an asset borrowed beforehand with
+//! [`BinanceAdapter::borrow`], or borrowed by the order itself with
+//! auto-borrow on, which also repays loans from what later orders bring in.
+
+use std::str::FromStr;
+
+use arbfinder_core::prelude::*;
+use reqwest::Method;
+use rust_decimal::Decimal;
+
+use crate::{binance_symbol, value_to_decimal, BinanceAdapter};
+
+/// Which margin account orders go to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarginMode {
+    /// One account whose collateral backs every pair.
+    Cross,
+    /// An account per pair, whose losses stop at its own collateral.
+    Isolated,
+}
+
+impl MarginMode {
+    /// The parameter picking this account in margin requests.
+    pub(crate) fn isolated_param(self) -> (&'static str, String) {
+        let isolated = match self {
+            MarginMode::Cross => "FALSE",
+            MarginMode::Isolated => "TRUE",
+        };
+        ("isIsolated", isolated.to_string())
+    }
+}
+
+impl FromStr for MarginMode {
+    type Err = ArbFinderError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "cross" => Ok(MarginMode::Cross),
+            "isolated" => Ok(MarginMode::Isolated),
+            other => Err(ArbFinderError::Parse(format!(
+                "Unknown margin mode {}, expected cross or isolated",
+                other
+            ))),
+        }
+    }
+}
+
+/// An asset in a margin account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarginAsset {
+    pub asset: String,
+    pub free: Decimal,
+    pub locked: Decimal,
+    pub borrowed: Decimal,
+    /// Interest accrued on the loan and not yet repaid.
+    pub interest: Decimal,
+}
+
+impl MarginAsset {
+    /// What has to be repaid to clear the loan.
+    pub fn owed(&self) -> Decimal {
+        self.borrowed + self.interest
+    }
+}
+
+/// Parameters that send an order to the `mode` margin account, on top of
+/// those of `POST /api/v3/order`.
+pub(crate) fn margin_order_params(mode: MarginMode, auto_borrow: bool) -> [(&'static str, String); 2] {
+    let side_effect = if auto_borrow { "AUTO_BORROW_REPAY" } else { "NO_SIDE_EFFECT" };
+    [mode.isolated_param(), ("sideEffectType", side_effect.to_string())]
+}
+
+fn parse_margin_asset(data: &serde_json::Value) -> Option<MarginAsset> {
+    Some(MarginAsset {
+        asset: data["asset"].as_str()?.to_string(),
+        free: value_to_decimal(&data["free"])?,
+        locked: value_to_decimal(&data["locked"])?,
+        borrowed: value_to_decimal(&data["borrowed"])?,
+        interest: value_to_decimal(&data["interest"])?,
+    })
+}
+
+/// Assets of `/sapi/v1/margin/account`, or of `/sapi/v1/margin/isolated/account`
+/// where each pair holds its base and quote asset.
+fn parse_margin_account(mode: MarginMode, data: &serde_json::Value) -> Vec<MarginAsset> {
+    match mode {
+        MarginMode::Cross => data["userAssets"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(parse_margin_asset)
+            .collect(),
+        MarginMode::Isolated => data["assets"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .flat_map(|pair| [&pair["baseAsset"], &pair["quoteAsset"]])
+            .filter_map(parse_margin_asset)
+            .collect(),
+    }
+}
+
+impl BinanceAdapter {
+    fn margin_mode(&self) -> Result<MarginMode> {
+        self.margin
+            .ok_or_else(|| ArbFinderError::Exchange("Binance margin trading is not enabled".to_string()))
+    }
+
+    /// Borrows `amount` of `asset` into the margin account; an isolated
+    /// account borrows for `symbol`'s pair. Returns the transaction id.
+    pub async fn borrow(&self, asset: &str, amount: Decimal, symbol: Option<&Symbol>) -> Result<String> {
+        self.margin_loan("BORROW", asset, amount, symbol).await
+    }
+
+    /// Repays `amount` of `asset`, interest first.
+    pub async fn repay(&self, asset: &str, amount: Decimal, symbol: Option<&Symbol>) -> Result<String> {
+        self.margin_loan("REPAY", asset, amount, symbol).await
+    }
+
+    async fn margin_loan(&self, kind: &str, asset: &str, amount: Decimal, symbol: Option<&Symbol>) -> Result<String> {
+        let mode = self.margin_mode()?;
+        let mut params = vec![
+            ("asset", asset.to_string()),
+            ("amount", amount.normalize().to_string()),
+            ("type", kind.to_string()),
+            mode.isolated_param(),
+        ];
+        match (mode, symbol) {
+            (MarginMode::Isolated, None) => {
+                return Err(ArbFinderError::InvalidData(format!(
+                    "Isolated margin needs the pair to {} {} for",
+                    kind.to_lowercase(),
+                    asset
+                )))
+            }
+            (MarginMode::Isolated, Some(symbol)) => params.push(("symbol", binance_symbol(symbol))),
+            (MarginMode::Cross, _) => {}
+        }
+
+        let response = self.signed_request(Method::POST, "/sapi/v1/margin/borrow-repay", &params).await?;
+        response["tranId"]
+            .as_i64()
+            .map(|id| id.to_string())
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing margin transaction id".to_string()))
+    }
+
+    /// Assets held or owed in the margin account.
+    pub async fn margin_assets(&self) -> Result<Vec<MarginAsset>> {
+        let mode = self.margin_mode()?;
+        let endpoint = match mode {
+            MarginMode::Cross => "/sapi/v1/margin/account",
+            MarginMode::Isolated => "/sapi/v1/margin/isolated/account",
+        };
+        let response = self.signed_request(Method::GET, endpoint, &[]).await?;
+        Ok(parse_margin_account(mode, &response))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_margin_account() {
+        let cross: serde_json::Value = serde_json::from_str(
+            r#"{"borrowEnabled":true,"marginLevel":"11.64405625","tradeEnabled":true,
+                "userAssets":[{"asset":"BTC","borrowed":"0.50000000","free":"0.00499500","interest":"0.00001000",
+                               "locked":"0.00000000","netAsset":"-0.49501500"}]}"#,
+        )
+        .unwrap();
+        let assets = parse_margin_account(MarginMode::Cross, &cross);
+        assert_eq!(assets.len(), 1);
+        assert_eq!(assets[0].asset, "BTC");
+        assert_eq!(assets[0].owed(), Decimal::new(50001, 5));
+
+        let isolated: serde_json::Value = serde_json::from_str(
+            r#"{"assets":[{"symbol":"BTCUSDT",
+                "baseAsset":{"asset":"BTC","borrowed":"0.1","free":"0","interest":"0","locked":"0","netAsset":"-0.1"},
+                "quoteAsset":{"asset":"USDT","borrowed":"0","free":"3500","interest":"0","locked":"0","netAsset":"3500"}}]}"#,
+        )
+        .unwrap();
+        let assets = parse_margin_account(MarginMode::Isolated, &isolated);
+        assert_eq!(assets.iter().map(|a| a.asset.as_str()).collect::<Vec<_>>(), ["BTC", "USDT"]);
+        assert_eq!(assets[1].free, Decimal::from(3500));
+    }
+
+    #[test]
+    fn test_margin_order_params() {
+        assert_eq!(
+            margin_order_params(MarginMode::Isolated, true),
+            [("isIsolated", "TRUE".to_string()), ("sideEffectType", "AUTO_BORROW_REPAY".to_string())]
+        );
+        assert_eq!(margin_order_params(MarginMode::Cross, false)[1].1, "NO_SIDE_EFFECT");
+        assert!("portfolio".parse::<MarginMode>().is_err());
+        assert_eq!("Cross".parse::<MarginMode>().unwrap(), MarginMode::Cross);
+    }
+}
@@ -0,0 +1,385 @@
+//! Binance market data over several connections.
+//!
+//! One connection carries at most 1024 streams, and a socket that carries
+//! hundreds falls behind long before that, so `market_data_stream` shards
+//! its streams across as many connections as they need, all feeding one
+//! channel. Each shard subscribes to its streams on every (re)connect. When
+//! a shard stays down past a grace period, its streams move to shards with
+//! room to spare, or to a new one if none has; the shard unsubscribes from
+//! them if it comes back.
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use arbfinder_exchange::{spawn_connection_with, DefaultExchangeConfig};
+use async_trait::async_trait;
+use serde_json::json;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+use crate::websocket::BinanceMarketDataStream;
+
+/// Streams Binance allows on one connection.
+pub(crate) const BINANCE_MAX_STREAMS_PER_CONNECTION: usize = 1024;
+
+/// Streams a connection carries before another is opened.
+pub(crate) const DEFAULT_STREAMS_PER_CONNECTION: usize = 200;
+
+/// How long a shard may be down before its streams move elsewhere; most
+/// drops reconnect well within it.
+const REBALANCE_GRACE: Duration = Duration::from_secs(10);
+
+const SUPERVISE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Default)]
+struct Shard {
+    streams: Vec<String>,
+    /// Streams moved off this shard while it was down, to unsubscribe from
+    /// when it reconnects.
+    released: Vec<String>,
+    down_since: Option<Instant>,
+    /// The connection gave up or never opened.
+    gone: bool,
+}
+
+impl Shard {
+    fn is_up(&self) -> bool {
+        !self.gone && self.down_since.is_none()
+    }
+}
+
+/// Which shard carries which stream. Shards are never removed, so a
+/// shard's index identifies it for good.
+#[derive(Debug, Default)]
+struct ShardMap {
+    shards: Vec<Shard>,
+    /// Streams no shard had room for.
+    pending: Vec<String>,
+    next_request_id: u64,
+}
+
+impl ShardMap {
+    fn add_shard(&mut self, streams: Vec<String>) -> usize {
+        self.shards.push(Shard {
+            streams,
+            ..Default::default()
+        });
+        self.shards.len() - 1
+    }
+
+    /// Marks `shard` up, returning what it should unsubscribe from and
+    /// subscribe to.
+    fn connected(&mut self, shard: usize) -> (Vec<String>, Vec<String>) {
+        let shard = &mut self.shards[shard];
+        shard.down_since = None;
+        (std::mem::take(&mut shard.released), shard.streams.clone())
+    }
+
+    fn disconnected(&mut self, shard: usize, now: Instant) {
+        let shard = &mut self.shards[shard];
+        shard.down_since.get_or_insert(now);
+    }
+
+    /// Marks `shard` gone for good, freeing its streams at the next
+    /// rebalance.
+    fn gone(&mut self, shard: usize) {
+        self.shards[shard].gone = true;
+    }
+
+    fn request_id(&mut self) -> u64 {
+        self.next_request_id += 1;
+        self.next_request_id
+    }
+
+    /// Moves the streams of shards down for `grace` or gone onto shards
+    /// that are up, least loaded first, keeping each within
+    /// `per_connection`. Returns the streams each receiving shard should
+    /// subscribe to; those that don't fit are left in `pending`.
+    fn rebalance(&mut self, now: Instant, grace: Duration, per_connection: usize) -> Vec<(usize, Vec<String>)> {
+        let mut orphaned = std::mem::take(&mut self.pending);
+        for shard in &mut self.shards {
+            let stale = shard.gone || shard.down_since.is_some_and(|since| now.duration_since(since) >= grace);
+            if stale && !shard.streams.is_empty() {
+                let streams = std::mem::take(&mut shard.streams);
+                if !shard.gone {
+                    shard.released.extend(streams.iter().cloned());
+                }
+                orphaned.extend(streams);
+            }
+        }
+        if orphaned.is_empty() {
+            return Vec::new();
+        }
+
+        let mut receivers: Vec<usize> = (0..self.shards.len())
+            .filter(|&index| self.shards[index].is_up() && self.shards[index].streams.len() < per_connection)
+            .collect();
+        receivers.sort_by_key(|&index| self.shards[index].streams.len());
+
+        let mut moves = Vec::new();
+        let mut orphaned = orphaned.into_iter();
+        for index in receivers {
+            let shard = &mut self.shards[index];
+            let room = per_connection - shard.streams.len();
+            let taken: Vec<String> = orphaned.by_ref().take(room).collect();
+            if taken.is_empty() {
+                break;
+            }
+            shard.released.retain(|stream| !taken.contains(stream));
+            shard.streams.extend(taken.iter().cloned());
+            moves.push((index, taken));
+        }
+        self.pending = orphaned.collect();
+        moves
+    }
+}
+
+type SharedShardMap = Arc<Mutex<ShardMap>>;
+
+fn subscription(method: &str, streams: &[String], id: u64) -> String {
+    json!({ "method": method, "params": streams, "id": id }).to_string()
+}
+
+/// One shard's connection: parses what it receives like any combined
+/// stream and subscribes to the shard's streams whenever it connects.
+struct ShardHandler {
+    shard: usize,
+    map: SharedShardMap,
+    outbound: mpsc::UnboundedSender<String>,
+    stream: BinanceMarketDataStream,
+}
+
+#[async_trait]
+impl WebSocketHandler for ShardHandler {
+    async fn on_message(&mut self, message: &str) -> Result<()> {
+        self.stream.on_message(message).await
+    }
+
+    async fn on_connect(&mut self) -> Result<()> {
+        let mut map = self.map.lock().unwrap_or_else(|e| e.into_inner());
+        let (released, streams) = map.connected(self.shard);
+        if !released.is_empty() {
+            let id = map.request_id();
+            let _ = self.outbound.send(subscription("UNSUBSCRIBE", &released, id));
+        }
+        if !streams.is_empty() {
+            let id = map.request_id();
+            let _ = self.outbound.send(subscription("SUBSCRIBE", &streams, id));
+        }
+        info!("Binance market data shard {} connected with {} streams", self.shard, streams.len());
+        Ok(())
+    }
+
+    async fn on_disconnect(&mut self) -> Result<()> {
+        self.map
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .disconnected(self.shard, Instant::now());
+        self.stream.on_disconnect().await
+    }
+
+    async fn on_error(&mut self, error: &ArbFinderError) -> Result<()> {
+        self.map
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .disconnected(self.shard, Instant::now());
+        self.stream.on_error(error).await
+    }
+
+    async fn on_ping(&mut self) -> Result<()> {
+        self.stream.on_ping().await
+    }
+
+    async fn on_pong(&mut self) -> Result<()> {
+        self.stream.on_pong().await
+    }
+}
+
+struct Link {
+    outbound: mpsc::UnboundedSender<String>,
+    task: JoinHandle<Result<()>>,
+}
+
+/// Shards market data streams across connections to `config`'s
+/// `websocket_url`, a Binance `/stream` endpoint, and keeps them spread
+/// over the connections that are up.
+pub(crate) struct StreamMultiplexer {
+    config: DefaultExchangeConfig,
+    symbols: Vec<Symbol>,
+    per_connection: usize,
+    tx: mpsc::UnboundedSender<MarketData>,
+    map: SharedShardMap,
+    /// Connections by shard; `None` for shards that never opened.
+    links: Vec<Option<Link>>,
+}
+
+impl StreamMultiplexer {
+    pub(crate) fn new(
+        config: DefaultExchangeConfig,
+        symbols: Vec<Symbol>,
+        per_connection: usize,
+        tx: mpsc::UnboundedSender<MarketData>,
+    ) -> Self {
+        Self {
+            config,
+            symbols,
+            per_connection: per_connection.clamp(1, BINANCE_MAX_STREAMS_PER_CONNECTION),
+            tx,
+            map: SharedShardMap::default(),
+            links: Vec::new(),
+        }
+    }
+
+    /// Opens connections for `streams` and leaves a task looking after
+    /// them until the receiving end of the channel is dropped. Fails if
+    /// any of the first connections can't be opened.
+    pub(crate) async fn start(mut self, streams: Vec<String>) -> Result<JoinHandle<()>> {
+        let mut seen = HashSet::new();
+        let streams: Vec<String> = streams.into_iter().filter(|stream| seen.insert(stream.clone())).collect();
+
+        for shard in streams.chunks(self.per_connection) {
+            if let Err(e) = self.open(shard.to_vec()).await {
+                self.close();
+                return Err(e);
+            }
+        }
+        info!(
+            "Binance market data: {} streams over {} connections",
+            streams.len(),
+            self.links.len()
+        );
+        Ok(tokio::spawn(self.supervise()))
+    }
+
+    async fn open(&mut self, streams: Vec<String>) -> Result<()> {
+        let shard = self
+            .map
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .add_shard(streams);
+        let map = Arc::clone(&self.map);
+        let symbols = self.symbols.clone();
+        let tx = self.tx.clone();
+        let mut outbound = None;
+        let opened = spawn_connection_with(&self.config, &[], |sender| {
+            outbound = Some(sender.clone());
+            ShardHandler {
+                shard,
+                map,
+                outbound: sender,
+                stream: BinanceMarketDataStream::new(symbols, tx),
+            }
+        })
+        .await;
+
+        match (opened, outbound) {
+            (Ok(task), Some(outbound)) => {
+                self.links.push(Some(Link { outbound, task }));
+                Ok(())
+            }
+            (Err(e), _) => {
+                self.map.lock().unwrap_or_else(|e| e.into_inner()).gone(shard);
+                self.links.push(None);
+                Err(e)
+            }
+            (Ok(task), None) => {
+                task.abort();
+                self.map.lock().unwrap_or_else(|e| e.into_inner()).gone(shard);
+                self.links.push(None);
+                Err(ArbFinderError::WebSocket("Connection has no message sender".to_string()))
+            }
+        }
+    }
+
+    async fn supervise(mut self) {
+        let mut ticker = tokio::time::interval(SUPERVISE_INTERVAL);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            ticker.tick().await;
+            if self.tx.is_closed() {
+                self.close();
+                return;
+            }
+
+            let (moves, pending) = {
+                let mut map = self.map.lock().unwrap_or_else(|e| e.into_inner());
+                for (shard, link) in self.links.iter().enumerate() {
+                    if link.as_ref().is_some_and(|link| link.task.is_finished()) {
+                        map.gone(shard);
+                    }
+                }
+                let moves = map.rebalance(Instant::now(), REBALANCE_GRACE, self.per_connection);
+                let moves: Vec<(usize, String)> = moves
+                    .into_iter()
+                    .map(|(shard, streams)| (shard, subscription("SUBSCRIBE", &streams, map.request_id())))
+                    .collect();
+                (moves, std::mem::take(&mut map.pending))
+            };
+
+            for (shard, message) in moves {
+                if let Some(link) = &self.links[shard] {
+                    let _ = link.outbound.send(message);
+                }
+            }
+            for streams in pending.chunks(self.per_connection) {
+                warn!("Opening another Binance market data connection for {} streams", streams.len());
+                if let Err(e) = self.open(streams.to_vec()).await {
+                    // Picked up again at the next rebalance
+                    warn!("Failed to open Binance market data connection: {}", e);
+                }
+            }
+        }
+    }
+
+    fn close(&mut self) {
+        for link in self.links.iter().flatten() {
+            link.task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn streams(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn test_rebalance_moves_streams_off_a_down_shard() {
+        let start = Instant::now();
+        let grace = Duration::from_secs(10);
+        let mut map = ShardMap::default();
+        map.add_shard(streams(&["a", "b", "c"]));
+        map.add_shard(streams(&["d"]));
+        map.add_shard(streams(&["e", "f"]));
+
+        // A short drop keeps its streams
+        map.disconnected(0, start);
+        assert!(map.rebalance(start + Duration::from_secs(5), grace, 3).is_empty());
+
+        // One that lasts moves them, least loaded first, and what doesn't
+        // fit waits for a new shard
+        let moves = map.rebalance(start + grace, grace, 3);
+        assert_eq!(moves, vec![(1, streams(&["a", "b"])), (2, streams(&["c"]))]);
+        assert!(map.pending.is_empty());
+        map.gone(2);
+        let moves = map.rebalance(start + grace, grace, 3);
+        assert_eq!(moves, vec![]);
+        assert_eq!(map.pending, streams(&["e", "f", "c"]));
+
+        // Back up, the first shard drops what moved away and takes new ones
+        assert_eq!(map.connected(0), (streams(&["a", "b", "c"]), vec![]));
+        let moves = map.rebalance(start + grace, grace, 3);
+        assert_eq!(moves, vec![(0, streams(&["e", "f", "c"]))]);
+        assert!(map.pending.is_empty());
+        assert_eq!(map.connected(0), (vec![], streams(&["e", "f", "c"])));
+    }
+}
@@ -0,0 +1,332 @@
+//! Binance user data stream.
+//!
+//! Binance pushes order and account changes over a WebSocket addressed by a
+//! listen key. The key expires an hour after it was created or last kept
+//! alive, so the adapter refreshes it while the stream is open. Listen keys
+//! belong to an API key, so rotating the key opens a new stream.
+
+use arbfinder_core::config::ProxyConfig;
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use arbfinder_exchange::{spawn_connection_with, DefaultExchangeConfig};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::{Client, Method};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use crate::weight::UsedWeight;
+use crate::{keyed_request, parse_order_status, value_to_decimal};
+
+/// Venue order ids of orders placed through the adapter, mapped to their
+/// ids, shared with user data streams so updates carry the same ids.
+pub(crate) type OrderIds = Arc<RwLock<HashMap<OrderId, (Symbol, String)>>>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UserDataEvent {
+    Order(OrderUpdate),
+    /// Balances of the assets that changed, as they now stand.
+    Balances(Vec<Balance>),
+}
+
+/// What user data streams are opened from, held apart from the adapter so
+/// a stream can reopen itself.
+pub(crate) struct UserDataSource {
+    pub(crate) client: Client,
+    pub(crate) base_url: String,
+    pub(crate) ws_url: String,
+    pub(crate) tls_pins: Option<TlsPins>,
+    pub(crate) proxy: Option<ProxyConfig>,
+    pub(crate) weight: UsedWeight,
+    pub(crate) rate_limiter: SharedRateLimiter,
+    pub(crate) orders: OrderIds,
+    pub(crate) order_lists: OrderIds,
+}
+
+impl UserDataSource {
+    /// Creates a listen key under `api_key` and connects to its stream,
+    /// sending events to `update_tx`. Returns the listen key and the
+    /// connection's task.
+    pub(crate) async fn open(
+        &self,
+        api_key: &str,
+        update_tx: mpsc::UnboundedSender<UserDataEvent>,
+    ) -> Result<(String, tokio::task::JoinHandle<Result<()>>)> {
+        let url = format!("{}/api/v3/userDataStream", self.base_url);
+        let response = keyed_request(&self.client, &self.weight, &self.rate_limiter, Method::POST, &url, api_key).await?;
+        let listen_key = response["listenKey"]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing listenKey".to_string()))?;
+
+        let config = DefaultExchangeConfig {
+            base_url: self.base_url.clone(),
+            websocket_url: format!("{}/ws/{}", self.ws_url, listen_key),
+            tls_pins: self.tls_pins.clone(),
+            proxy: self.proxy.clone(),
+            ..Default::default()
+        };
+        let orders = Arc::clone(&self.orders);
+        let order_lists = Arc::clone(&self.order_lists);
+        let connection = spawn_connection_with(&config, &[], move |_outbound| {
+            BinanceUserDataStream::new(orders, order_lists, update_tx)
+        })
+        .await?;
+        Ok((listen_key, connection))
+    }
+
+    /// Keeps `listen_key` alive with `PUT`, or closes it with `DELETE`.
+    pub(crate) async fn listen_key_request(&self, method: Method, api_key: &str, listen_key: &str) -> Result<Value> {
+        let url = format!("{}/api/v3/userDataStream?listenKey={}", self.base_url, listen_key);
+        keyed_request(&self.client, &self.weight, &self.rate_limiter, method, &url, api_key).await
+    }
+}
+
+/// The id an order was placed under given its venue id, if it is known.
+fn known_id(ids: &OrderIds, venue_id: &str) -> Option<OrderId> {
+    ids.read().ok().and_then(|ids| {
+        ids.iter()
+            .find(|(_, (_, venue))| venue == venue_id)
+            .map(|(id, _)| id.clone())
+    })
+}
+
+/// Handles `executionReport` and `outboundAccountPosition` events.
+pub struct BinanceUserDataStream {
+    orders: OrderIds,
+    /// Order list ids of OCO orders, whose legs are reported one by one.
+    order_lists: OrderIds,
+    update_tx: mpsc::UnboundedSender<UserDataEvent>,
+}
+
+impl BinanceUserDataStream {
+    pub(crate) fn new(
+        orders: OrderIds,
+        order_lists: OrderIds,
+        update_tx: mpsc::UnboundedSender<UserDataEvent>,
+    ) -> Self {
+        Self {
+            orders,
+            order_lists,
+            update_tx,
+        }
+    }
+
+    /// The id of the order a report is about: the one it was placed under
+    /// through this adapter, or else its client order id if that is one of
+    /// ours, or a fresh id for an order placed elsewhere.
+    fn order_id(&self, venue_order_id: &str, client_order_id: Option<&str>) -> OrderId {
+        known_id(&self.orders, venue_order_id)
+            .or_else(|| client_order_id.and_then(OrderId::from_string))
+            .unwrap_or_default()
+    }
+
+    fn process_execution_report(&self, data: &Value) -> Option<UserDataEvent> {
+        let venue_order_id = data["i"].as_i64()?.to_string();
+        let status = parse_order_status(data["X"].as_str()?);
+        // Legs of an OCO order report under the order's id. The leg that
+        // expires when the other fills is left out, as the order did not.
+        let list_order = data["g"]
+            .as_i64()
+            .filter(|list| *list >= 0)
+            .and_then(|list| known_id(&self.order_lists, &list.to_string()));
+        if list_order.is_some() && status == OrderStatus::Expired {
+            return None;
+        }
+        // A cancel carries the canceled order's client id in `C`
+        let client_order_id = data["C"]
+            .as_str()
+            .filter(|id| !id.is_empty())
+            .or_else(|| data["c"].as_str());
+
+        let quantity = value_to_decimal(&data["q"]).unwrap_or(Decimal::ZERO);
+        let filled = value_to_decimal(&data["z"]).unwrap_or(Decimal::ZERO);
+        let reason = data["r"].as_str().filter(|r| *r != "NONE").map(str::to_string);
+        let timestamp = data["E"]
+            .as_i64()
+            .and_then(DateTime::<Utc>::from_timestamp_millis)
+            .unwrap_or_else(Utc::now);
+
+        Some(UserDataEvent::Order(OrderUpdate {
+            order_id: list_order.unwrap_or_else(|| self.order_id(&venue_order_id, client_order_id)),
+            venue_order_id: Some(venue_order_id),
+            status,
+            filled_quantity: filled,
+            remaining_quantity: (quantity - filled).max(Decimal::ZERO),
+            average_fill_price: value_to_decimal(&data["Z"])
+                .filter(|_| !filled.is_zero())
+                .map(|quote| quote / filled),
+            timestamp,
+            reason,
+        }))
+    }
+
+    fn process_account_position(data: &Value) -> Option<UserDataEvent> {
+        let timestamp = data["u"]
+            .as_i64()
+            .and_then(DateTime::<Utc>::from_timestamp_millis)
+            .unwrap_or_else(Utc::now);
+
+        let balances = data["B"]
+            .as_array()?
+            .iter()
+            .filter_map(|balance| {
+                let free = value_to_decimal(&balance["f"])?;
+                let locked = value_to_decimal(&balance["l"])?;
+                let mut balance = Balance::new(balance["a"].as_str()?.to_string(), free + locked, free, locked);
+                balance.timestamp = timestamp;
+                Some(balance)
+            })
+            .collect();
+        Some(UserDataEvent::Balances(balances))
+    }
+}
+
+#[async_trait]
+impl WebSocketHandler for BinanceUserDataStream {
+    async fn on_message(&mut self, message: &str) -> Result<()> {
+        let data: Value = serde_json::from_str(message)?;
+        let event = match data["e"].as_str() {
+            Some("executionReport") => self.process_execution_report(&data),
+            Some("outboundAccountPosition") => Self::process_account_position(&data),
+            Some("listenKeyExpired") => {
+                error!("Binance listen key expired; user data stream must be reopened");
+                None
+            }
+            // balanceUpdate is a deposit or withdrawal delta, always
+            // followed by an outboundAccountPosition with the new balance
+            _ => {
+                debug!("Ignoring Binance user data message: {}", message);
+                None
+            }
+        };
+
+        if let Some(event) = event {
+            let _ = self.update_tx.send(event);
+        }
+        Ok(())
+    }
+
+    async fn on_connect(&mut self) -> Result<()> {
+        info!("Binance user data stream connected");
+        Ok(())
+    }
+
+    async fn on_disconnect(&mut self) -> Result<()> {
+        warn!("Binance user data stream disconnected");
+        Ok(())
+    }
+
+    async fn on_error(&mut self, error: &ArbFinderError) -> Result<()> {
+        error!("Binance user data stream error: {}", error);
+        Ok(())
+    }
+
+    async fn on_ping(&mut self) -> Result<()> {
+        debug!("Received Binance user data ping");
+        Ok(())
+    }
+
+    async fn on_pong(&mut self) -> Result<()> {
+        debug!("Received Binance user data pong");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[tokio::test]
+    async fn test_execution_report_maps_known_order() {
+        let orders: OrderIds = Arc::default();
+        let id = OrderId::new();
+        orders
+            .write()
+            .unwrap()
+            .insert(id.clone(), (Symbol::new("BTC", "USDT"), "4293153".to_string()));
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut stream = BinanceUserDataStream::new(orders, Arc::default(), tx);
+        stream
+            .on_message(
+                r#"{"e":"executionReport","E":1499405658658,"s":"ETHBTC","c":"mUvoqJxFIILMdfAW5iGSOW","S":"BUY",
+                    "o":"LIMIT","f":"GTC","q":"1.00000000","p":"0.10264410","X":"PARTIALLY_FILLED","r":"NONE",
+                    "i":4293153,"l":"0.25000000","z":"0.25000000","L":"0.10264410","Z":"0.02566102","C":""}"#,
+            )
+            .await
+            .unwrap();
+
+        match rx.recv().await.unwrap() {
+            UserDataEvent::Order(update) => {
+                assert_eq!(update.order_id, id);
+                assert_eq!(update.status, OrderStatus::PartiallyFilled);
+                assert_eq!(update.filled_quantity, Decimal::from_str("0.25").unwrap());
+                assert_eq!(update.remaining_quantity, Decimal::from_str("0.75").unwrap());
+                assert_eq!(update.average_fill_price, Some(Decimal::from_str("0.10264408").unwrap()));
+                assert_eq!(update.reason, None);
+            }
+            other => panic!("expected order update, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_oco_legs_report_under_the_order() {
+        let order_lists: OrderIds = Arc::default();
+        let id = OrderId::new();
+        order_lists
+            .write()
+            .unwrap()
+            .insert(id.clone(), (Symbol::new("BTC", "USDT"), "1".to_string()));
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut stream = BinanceUserDataStream::new(Arc::default(), order_lists, tx);
+        // The stop loss expires as the take profit fills
+        for report in [
+            r#"{"e":"executionReport","E":1499405658658,"s":"BTCUSDT","c":"x1","S":"SELL","o":"STOP_LOSS",
+                "q":"1.00000000","X":"EXPIRED","r":"NONE","i":2,"g":1,"z":"0.00000000","Z":"0.00000000","C":""}"#,
+            r#"{"e":"executionReport","E":1499405658659,"s":"BTCUSDT","c":"x2","S":"SELL","o":"LIMIT_MAKER",
+                "q":"1.00000000","X":"FILLED","r":"NONE","i":3,"g":1,"z":"1.00000000","Z":"32000.00000000","C":""}"#,
+        ] {
+            stream.on_message(report).await.unwrap();
+        }
+
+        match rx.recv().await.unwrap() {
+            UserDataEvent::Order(update) => {
+                assert_eq!(update.order_id, id);
+                assert_eq!(update.venue_order_id.as_deref(), Some("3"));
+                assert_eq!(update.status, OrderStatus::Filled);
+                assert_eq!(update.average_fill_price, Some(Decimal::from(32000)));
+            }
+            other => panic!("expected order update, got {:?}", other),
+        }
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_account_position() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut stream = BinanceUserDataStream::new(Arc::default(), Arc::default(), tx);
+        stream
+            .on_message(
+                r#"{"e":"outboundAccountPosition","E":1564034571105,"u":1564034571073,
+                    "B":[{"a":"ETH","f":"10000.000000","l":"1.000000"}]}"#,
+            )
+            .await
+            .unwrap();
+
+        match rx.recv().await.unwrap() {
+            UserDataEvent::Balances(balances) => {
+                assert_eq!(balances.len(), 1);
+                assert_eq!(balances[0].asset, "ETH");
+                assert_eq!(balances[0].total, Decimal::from_str("10001").unwrap());
+                assert_eq!(balances[0].locked, Decimal::ONE);
+            }
+            other => panic!("expected balances, got {:?}", other),
+        }
+    }
+}
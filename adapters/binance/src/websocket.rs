@@ -1,14 +1,132 @@
 use arbfinder_core::prelude::*;
 use arbfinder_exchange::prelude::*;
 use async_trait::async_trait;
+use chrono::{DateTime, Utc};
 use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::{mpsc, RwLock};
 use tracing::{debug, error, info, warn};
 use rust_decimal::Decimal;
 use std::str::FromStr;
 
-const BINANCE_WS_BASE: &str = "wss://stream.binance.com:9443";
+use crate::{binance_symbol, value_to_decimal};
+
+pub(crate) const BINANCE_WS_BASE: &str = "wss://stream.binance.com:9443";
+pub(crate) const BINANCE_TESTNET_WS_BASE: &str = "wss://stream.testnet.binance.vision";
+
+/// A market data channel of one symbol on the combined stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinanceChannel {
+    /// Snapshots of the top 5, 10 or 20 levels every 100ms.
+    Depth(u32),
+    Trade,
+    Ticker,
+    /// Best bid and offer, pushed on every change.
+    BookTicker,
+}
+
+impl BinanceChannel {
+    /// The smallest partial book covering `depth` levels; Binance serves at
+    /// most 20.
+    pub fn depth(depth: Option<u32>) -> Self {
+        match depth {
+            Some(levels) if levels <= 5 => BinanceChannel::Depth(5),
+            Some(levels) if levels <= 10 => BinanceChannel::Depth(10),
+            _ => BinanceChannel::Depth(20),
+        }
+    }
+
+    pub fn stream_name(&self, symbol: &Symbol) -> String {
+        let name = binance_symbol(symbol).to_lowercase();
+        match self {
+            BinanceChannel::Depth(levels) => format!("{}@depth{}@100ms", name, levels),
+            BinanceChannel::Trade => format!("{}@trade", name),
+            BinanceChannel::Ticker => format!("{}@ticker", name),
+            BinanceChannel::BookTicker => format!("{}@bookTicker", name),
+        }
+    }
+}
+
+/// URL of the combined stream endpoint carrying every stream in `streams`.
+pub fn combined_stream_url(ws_base: &str, streams: &[String]) -> String {
+    format!("{}/stream?streams={}", ws_base, streams.join("/"))
+}
+
+fn apply_levels(book: &mut OrderBook, levels: &Value, side: Side) {
+    for level in levels.as_array().into_iter().flatten() {
+        if let (Some(price), Some(quantity)) = (value_to_decimal(&level[0]), value_to_decimal(&level[1])) {
+            match side {
+                Side::Bid => book.update_bid(price, quantity),
+                Side::Ask => book.update_ask(price, quantity),
+            }
+        }
+    }
+}
+
+/// Handles combined stream messages, `{"stream": ..., "data": ...}`, for
+/// depth, trade, ticker and book ticker streams of any number of symbols.
+pub struct BinanceMarketDataStream {
+    /// Subscribed symbols by lowercase Binance name, as in stream names.
+    symbols: HashMap<String, Symbol>,
+    update_tx: mpsc::UnboundedSender<MarketData>,
+}
+
+impl BinanceMarketDataStream {
+    pub fn new(symbols: impl IntoIterator<Item = Symbol>, update_tx: mpsc::UnboundedSender<MarketData>) -> Self {
+        let symbols = symbols
+            .into_iter()
+            .map(|symbol| (binance_symbol(&symbol).to_lowercase(), symbol))
+            .collect();
+        Self { symbols, update_tx }
+    }
+
+    fn process_depth(symbol: Symbol, data: &Value) -> Option<MarketData> {
+        // Partial depth messages are whole snapshots, so there is no
+        // sequence to keep in step with
+        let mut book = OrderBook::new(symbol);
+        apply_levels(&mut book, &data["bids"], Side::Bid);
+        apply_levels(&mut book, &data["asks"], Side::Ask);
+        book.sequence = data["lastUpdateId"].as_u64();
+        Some(MarketData::OrderBook(book))
+    }
+
+    /// A book ticker as a one-level book.
+    fn process_book_ticker(symbol: Symbol, data: &Value) -> Option<MarketData> {
+        let mut book = OrderBook::new(symbol);
+        book.update_bid(value_to_decimal(&data["b"])?, value_to_decimal(&data["B"])?);
+        book.update_ask(value_to_decimal(&data["a"])?, value_to_decimal(&data["A"])?);
+        book.sequence = data["u"].as_u64();
+        Some(MarketData::OrderBook(book))
+    }
+
+    fn process_trade(symbol: Symbol, data: &Value) -> Option<MarketData> {
+        let price = value_to_decimal(&data["p"])?;
+        let quantity = value_to_decimal(&data["q"])?;
+        // A maker buyer means the seller took liquidity
+        let side = if data["m"].as_bool()? { Side::Ask } else { Side::Bid };
+
+        let mut trade = Trade::new(symbol, price, quantity, side, data["t"].to_string());
+        if let Some(ts) = data["T"].as_i64().and_then(DateTime::<Utc>::from_timestamp_millis) {
+            trade.timestamp = ts;
+        }
+        Some(MarketData::Trade(trade))
+    }
+
+    fn process_ticker(symbol: Symbol, data: &Value) -> Option<MarketData> {
+        Some(MarketData::Ticker(Ticker {
+            symbol,
+            price: value_to_decimal(&data["c"])?,
+            volume_24h: value_to_decimal(&data["v"]).unwrap_or(Decimal::ZERO),
+            change_24h: value_to_decimal(&data["p"]).unwrap_or(Decimal::ZERO),
+            timestamp: data["E"]
+                .as_i64()
+                .and_then(DateTime::<Utc>::from_timestamp_millis)
+                .unwrap_or_else(Utc::now),
+        }))
+    }
+}
 
 #[derive(Debug, Clone, Deserialize)]
 struct BinanceDepthUpdate {
@@ -154,6 +272,64 @@ impl WebSocketHandler for BinanceOrderbookStream {
     }
 }
 
+#[async_trait]
+impl WebSocketHandler for BinanceMarketDataStream {
+    async fn on_message(&mut self, message: &str) -> Result<()> {
+        let message: Value = serde_json::from_str(message)?;
+        let Some((name, channel)) = message["stream"].as_str().and_then(|stream| stream.split_once('@')) else {
+            // Replies to SUBSCRIBE and friends carry an id instead
+            debug!("Ignoring Binance message: {}", message);
+            return Ok(());
+        };
+        let Some(symbol) = self.symbols.get(name).cloned() else {
+            return Ok(());
+        };
+
+        let data = &message["data"];
+        let update = if channel.starts_with("depth") {
+            Self::process_depth(symbol, data)
+        } else if channel == "trade" {
+            Self::process_trade(symbol, data)
+        } else if channel == "ticker" {
+            Self::process_ticker(symbol, data)
+        } else if channel == "bookTicker" {
+            Self::process_book_ticker(symbol, data)
+        } else {
+            None
+        };
+
+        if let Some(update) = update {
+            let _ = self.update_tx.send(update);
+        }
+        Ok(())
+    }
+
+    async fn on_connect(&mut self) -> Result<()> {
+        info!("Binance market data stream connected for {} symbols", self.symbols.len());
+        Ok(())
+    }
+
+    async fn on_disconnect(&mut self) -> Result<()> {
+        warn!("Binance market data stream disconnected");
+        Ok(())
+    }
+
+    async fn on_error(&mut self, error: &ArbFinderError) -> Result<()> {
+        error!("Binance market data stream error: {}", error);
+        Ok(())
+    }
+
+    async fn on_ping(&mut self) -> Result<()> {
+        debug!("Received Binance market data ping");
+        Ok(())
+    }
+
+    async fn on_pong(&mut self) -> Result<()> {
+        debug!("Received Binance market data pong");
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -201,4 +377,73 @@ mod tests {
         assert_eq!(best_bid.price, Decimal::from_str("50000.00").unwrap());
         assert_eq!(best_ask.price, Decimal::from_str("50001.00").unwrap());
     }
+
+    #[test]
+    fn test_stream_names() {
+        let symbol = Symbol::new("BTC", "USDT");
+        assert_eq!(BinanceChannel::depth(Some(8)).stream_name(&symbol), "btcusdt@depth10@100ms");
+        assert_eq!(BinanceChannel::depth(None), BinanceChannel::Depth(20));
+        let streams = [BinanceChannel::Trade.stream_name(&symbol), BinanceChannel::Ticker.stream_name(&symbol)];
+        assert_eq!(
+            combined_stream_url(BINANCE_WS_BASE, &streams),
+            "wss://stream.binance.com:9443/stream?streams=btcusdt@trade/btcusdt@ticker"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_combined_stream_messages() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut stream = BinanceMarketDataStream::new([Symbol::new("BNB", "BTC")], tx);
+
+        stream
+            .on_message(
+                r#"{"stream":"bnbbtc@depth5@100ms","data":{"lastUpdateId":160,
+                    "bids":[["0.0024","10"]],"asks":[["0.0026","100"]]}}"#,
+            )
+            .await
+            .unwrap();
+        stream
+            .on_message(
+                r#"{"stream":"bnbbtc@trade","data":{"e":"trade","E":1672515782136,"s":"BNBBTC","t":12345,
+                    "p":"0.001","q":"100","T":1672515782136,"m":true,"M":true}}"#,
+            )
+            .await
+            .unwrap();
+        stream
+            .on_message(
+                r#"{"stream":"bnbbtc@bookTicker","data":{"u":400900217,"s":"BNBBTC",
+                    "b":"0.0025","B":"31.21","a":"0.0027","A":"40.66"}}"#,
+            )
+            .await
+            .unwrap();
+        stream.on_message(r#"{"result":null,"id":1}"#).await.unwrap();
+
+        match rx.recv().await.unwrap() {
+            MarketData::OrderBook(book) => {
+                assert_eq!(book.symbol, Symbol::new("BNB", "BTC"));
+                assert_eq!(book.sequence, Some(160));
+                assert_eq!(book.best_bid().unwrap().price, Decimal::from_str("0.0024").unwrap());
+                assert_eq!(book.best_ask().unwrap().quantity, Decimal::from(100));
+            }
+            other => panic!("expected order book, got {:?}", other),
+        }
+        match rx.recv().await.unwrap() {
+            MarketData::Trade(trade) => {
+                assert_eq!(trade.side, Side::Ask);
+                assert_eq!(trade.trade_id, "12345");
+                assert_eq!(trade.timestamp.timestamp_millis(), 1672515782136);
+            }
+            other => panic!("expected trade, got {:?}", other),
+        }
+        match rx.recv().await.unwrap() {
+            MarketData::OrderBook(book) => {
+                assert_eq!((book.bids.len(), book.asks.len()), (1, 1));
+                assert_eq!(book.sequence, Some(400900217));
+                assert_eq!(book.best_bid().unwrap().quantity, Decimal::from_str("31.21").unwrap());
+                assert_eq!(book.best_ask().unwrap().price, Decimal::from_str("0.0027").unwrap());
+            }
+            other => panic!("expected book ticker, got {:?}", other),
+        }
+        assert!(rx.try_recv().is_err());
+    }
 }
@@ -0,0 +1,209 @@
+//! Binance request weight.
+//!
+//! Every REST response reports in `X-MBX-USED-WEIGHT-1M` the weight the IP
+//! has used in the current minute, out of 6000. Once it reaches the
+//! adapter's budget, requests wait for the next minute instead of running
+//! into a 429 and, if they keep coming, a 418 ban. After a 429 or 418,
+//! requests wait out its `Retry-After`.
+//!
+//! Requests also draw on the adapter's `WeightedRateLimiter`, which counts
+//! their weight up front, before Binance reports it.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use arbfinder_core::config::{RateLimitConfig, RateLimitScope, RateLimitWindow};
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use tracing::warn;
+
+/// Weight a minute Binance allows an IP on the spot REST API.
+pub(crate) const BINANCE_WEIGHT_LIMIT: u32 = 6000;
+
+/// Weight used before requests wait for the next minute, leaving room for
+/// requests already in flight.
+pub(crate) const BINANCE_DEFAULT_WEIGHT_BUDGET: u32 = BINANCE_WEIGHT_LIMIT * 9 / 10;
+
+const USED_WEIGHT_HEADER: &str = "x-mbx-used-weight-1m";
+
+/// Orders Binance allows an account every 10 seconds.
+const BINANCE_ORDER_LIMIT: u32 = 50;
+
+/// Binance's spot limits within the weight budget, and the weights of the
+/// heavier endpoints the adapter calls, at the default depth for `depth`.
+pub(crate) fn binance_rate_limits() -> RateLimitConfig {
+    RateLimitConfig {
+        windows: vec![
+            RateLimitWindow {
+                scope: RateLimitScope::Requests,
+                limit: BINANCE_DEFAULT_WEIGHT_BUDGET,
+                window_secs: 60,
+            },
+            RateLimitWindow {
+                scope: RateLimitScope::Orders,
+                limit: BINANCE_ORDER_LIMIT,
+                window_secs: 10,
+            },
+        ],
+        endpoint_weights: HashMap::from([
+            ("/api/v3/exchangeInfo".to_string(), 20),
+            ("/api/v3/depth".to_string(), 5),
+            ("/api/v3/account".to_string(), 20),
+            ("/api/v3/allOrders".to_string(), 20),
+            ("/api/v3/myTrades".to_string(), 20),
+            ("/api/v3/openOrders".to_string(), 6),
+            ("/sapi/v1/capital/config/getall".to_string(), 10),
+            ("/sapi/v1/capital/deposit/address".to_string(), 10),
+        ]),
+        default_weight: 1,
+        order_endpoints: vec![
+            "/api/v3/order".to_string(),
+            "/api/v3/orderList/oco".to_string(),
+            "/sapi/v1/margin/order".to_string(),
+        ],
+    }
+}
+
+#[derive(Debug, Default)]
+struct WeightState {
+    used: u32,
+    /// Unix minute `used` was reported in; Binance counts weight per clock
+    /// minute.
+    minute: i64,
+    /// Until when Binance asked for no requests after a 429 or 418.
+    retry_after: Option<DateTime<Utc>>,
+}
+
+/// Weight used this minute, shared by every request of an adapter.
+#[derive(Debug, Clone)]
+pub(crate) struct UsedWeight {
+    budget: u32,
+    state: Arc<Mutex<WeightState>>,
+}
+
+impl UsedWeight {
+    pub(crate) fn new(budget: u32) -> Self {
+        Self {
+            budget,
+            state: Arc::default(),
+        }
+    }
+
+    /// Weight used in the minute of `now`, as last reported.
+    pub(crate) fn used(&self, now: DateTime<Utc>) -> u32 {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.minute == now.timestamp().div_euclid(60) {
+            state.used
+        } else {
+            0
+        }
+    }
+
+    /// Takes the used weight from a response's headers and, for a 429 or
+    /// 418, how long to back off.
+    pub(crate) fn record(&self, status: reqwest::StatusCode, headers: &HeaderMap, now: DateTime<Utc>) {
+        let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+
+        if let Some(used) = header(USED_WEIGHT_HEADER).and_then(|used| used.parse().ok()) {
+            let minute = now.timestamp().div_euclid(60);
+            // Responses can arrive out of order; weight only grows within a minute
+            if minute > state.minute || (minute == state.minute && used > state.used) {
+                state.used = used;
+                state.minute = minute;
+            }
+        }
+
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS || status == reqwest::StatusCode::IM_A_TEAPOT {
+            let secs = header(RETRY_AFTER.as_str()).and_then(|secs| secs.parse().ok()).unwrap_or(60);
+            let until = now + chrono::Duration::seconds(secs);
+            state.retry_after = Some(state.retry_after.map_or(until, |after| after.max(until)));
+        }
+    }
+
+    /// How long a request made at `now` should wait, if at all.
+    pub(crate) fn wait(&self, now: DateTime<Utc>) -> Option<Duration> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let minute = now.timestamp().div_euclid(60);
+        let until = match state.retry_after {
+            Some(after) if after > now => after,
+            _ if state.minute == minute && state.used >= self.budget => {
+                DateTime::from_timestamp((minute + 1) * 60, 0)?
+            }
+            _ => return None,
+        };
+        (until - now).to_std().ok()
+    }
+
+    /// Waits until a request can be made without going over the budget.
+    pub(crate) async fn throttle(&self) {
+        if let Some(wait) = self.wait(Utc::now()) {
+            warn!(
+                "Binance request weight at {} of {}, waiting {:?}",
+                self.used(Utc::now()),
+                BINANCE_WEIGHT_LIMIT,
+                wait
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+    use reqwest::StatusCode;
+
+    /// `secs` after the start of a minute.
+    fn at(secs: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(1_760_000_040 + secs, 0).unwrap()
+    }
+
+    fn headers(used: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(USED_WEIGHT_HEADER, HeaderValue::from_str(used).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_waits_for_next_minute_over_budget() {
+        let weight = UsedWeight::new(5400);
+        weight.record(StatusCode::OK, &headers("5399"), at(0));
+        assert_eq!(weight.wait(at(0)), None);
+
+        weight.record(StatusCode::OK, &headers("5400"), at(5));
+        // A response from earlier in the minute does not lower it
+        weight.record(StatusCode::OK, &headers("20"), at(6));
+        assert_eq!(weight.used(at(6)), 5400);
+        assert_eq!(weight.wait(at(10)), Some(Duration::from_secs(50)));
+
+        assert_eq!(weight.wait(at(60)), None);
+        assert_eq!(weight.used(at(60)), 0);
+    }
+
+    #[test]
+    fn test_backs_off_after_ban() {
+        let weight = UsedWeight::new(5400);
+        let mut banned = headers("6001");
+        banned.insert(RETRY_AFTER, HeaderValue::from_static("120"));
+        weight.record(StatusCode::IM_A_TEAPOT, &banned, at(0));
+        assert_eq!(weight.wait(at(30)), Some(Duration::from_secs(90)));
+        assert_eq!(weight.wait(at(120)), None);
+
+        // Without Retry-After, a minute
+        weight.record(StatusCode::TOO_MANY_REQUESTS, &HeaderMap::new(), at(200));
+        assert_eq!(weight.wait(at(200)), Some(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn test_binance_rate_limits() {
+        let limiter = arbfinder_exchange::WeightedRateLimiter::new(&binance_rate_limits());
+        assert_eq!(limiter.weight("/api/v3/exchangeInfo"), 20);
+        assert_eq!(limiter.weight("/api/v3/depth?symbol=BTCUSDT&limit=100"), 5);
+        assert_eq!(limiter.weight("/api/v3/ticker/price"), 1);
+        assert!(limiter.is_order_endpoint("/api/v3/order?symbol=BTCUSDT"));
+        assert!(!limiter.is_order_endpoint("/api/v3/openOrders"));
+    }
+}
@@ -0,0 +1,372 @@
+//! Bitfinex Exchange Adapter
+//!
+//! Implementation of MarketDataAdapter for Bitfinex (API v2)
+
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use arbfinder_exchange::{channel_stream, spawn_connection, DefaultExchangeConfig};
+use async_trait::async_trait;
+use reqwest::Client;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use tokio::sync::mpsc;
+
+pub mod websocket;
+pub use websocket::BitfinexMarketDataStream;
+
+const BITFINEX_API_URL: &str = "https://api-pub.bitfinex.com";
+
+/// Bitfinex tickers for assets that differ from the common ones.
+const BITFINEX_CURRENCY_ALIASES: &[(&str, &str)] = &[
+    ("UST", "USDT"),
+    ("UDC", "USDC"),
+    ("DSH", "DASH"),
+    ("IOT", "IOTA"),
+];
+
+fn from_bitfinex_currency(currency: &str) -> String {
+    let upper = currency.to_uppercase();
+    BITFINEX_CURRENCY_ALIASES
+        .iter()
+        .find(|(bitfinex, _)| *bitfinex == upper)
+        .map(|(_, common)| common.to_string())
+        .unwrap_or(upper)
+}
+
+fn to_bitfinex_currency(currency: &str) -> String {
+    let upper = currency.to_uppercase();
+    BITFINEX_CURRENCY_ALIASES
+        .iter()
+        .find(|(_, common)| *common == upper)
+        .map(|(bitfinex, _)| bitfinex.to_string())
+        .unwrap_or(upper)
+}
+
+/// Formats a symbol as a Bitfinex trading pair: `tBTCUSD`, or `tTESTBTC:TESTUSD`
+/// when either side is longer than three characters.
+pub fn bitfinex_symbol(symbol: &Symbol) -> String {
+    let base = to_bitfinex_currency(symbol.base());
+    let quote = to_bitfinex_currency(symbol.quote());
+    if base.len() > 3 || quote.len() > 3 {
+        format!("t{}:{}", base, quote)
+    } else {
+        format!("t{}{}", base, quote)
+    }
+}
+
+/// Parses `tBTCUSD`, `BTCUSD` or `tAVAX:USD` into a `Symbol`.
+pub fn parse_bitfinex_symbol(pair: &str) -> Option<Symbol> {
+    let pair = pair.strip_prefix('t').unwrap_or(pair);
+    let (base, quote) = match pair.split_once(':') {
+        Some((base, quote)) => (base, quote),
+        None if pair.len() == 6 => pair.split_at(3),
+        None => return None,
+    };
+    if base.is_empty() || quote.is_empty() {
+        return None;
+    }
+
+    Some(Symbol::new(from_bitfinex_currency(base), from_bitfinex_currency(quote)))
+}
+
+fn value_to_decimal(value: &serde_json::Value) -> Option<Decimal> {
+    match value {
+        serde_json::Value::String(s) => s.parse().ok(),
+        serde_json::Value::Number(n) => n.as_f64().and_then(Decimal::from_f64),
+        _ => None,
+    }
+}
+
+pub struct BitfinexAdapter {
+    client: Client,
+    base_url: String,
+    ws_url: String,
+    connected: bool,
+    subscriptions: Vec<Symbol>,
+    normalizer: DefaultSymbolNormalizer,
+}
+
+impl BitfinexAdapter {
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+            base_url: BITFINEX_API_URL.to_string(),
+            ws_url: websocket::BITFINEX_WS_URL.to_string(),
+            connected: false,
+            subscriptions: Vec::new(),
+            normalizer: DefaultSymbolNormalizer::new(),
+        }
+    }
+
+    /// Symbol normalizer seeded with every pair seen so far; `tBTCUSD` style
+    /// names cannot be split by the generic parser.
+    pub fn normalizer(&self) -> &DefaultSymbolNormalizer {
+        &self.normalizer
+    }
+
+    fn register_symbol(&mut self, symbol: &Symbol) {
+        self.normalizer.add_symbol_mapping(bitfinex_symbol(symbol), symbol.clone());
+    }
+
+    async fn get_request(&self, endpoint: &str) -> Result<serde_json::Value> {
+        let url = format!("{}{}", self.base_url, endpoint);
+        let response = self.client
+            .get(&url)
+            .send()
+            .await
+            .map_err(ArbFinderError::Http)?;
+
+        if !response.status().is_success() {
+            return Err(ArbFinderError::Exchange(format!(
+                "Bitfinex API error: {}",
+                response.status()
+            )));
+        }
+
+        response.json().await.map_err(ArbFinderError::Http)
+    }
+
+    /// Fetch the ticker for a symbol. Bitfinex returns a positional array:
+    /// `[BID, BID_SIZE, ASK, ASK_SIZE, DAILY_CHANGE, DAILY_CHANGE_RELATIVE,
+    /// LAST_PRICE, VOLUME, HIGH, LOW]`.
+    pub async fn get_ticker(&self, symbol: &Symbol) -> Result<Ticker> {
+        let response = self
+            .get_request(&format!("/v2/ticker/{}", bitfinex_symbol(symbol)))
+            .await?;
+
+        let price = value_to_decimal(&response[6])
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing last price".to_string()))?;
+
+        Ok(Ticker {
+            symbol: symbol.clone(),
+            price,
+            volume_24h: value_to_decimal(&response[7]).unwrap_or(Decimal::ZERO),
+            change_24h: value_to_decimal(&response[4]).unwrap_or(Decimal::ZERO),
+            timestamp: Utc::now(),
+        })
+    }
+
+    /// Fetch orderbook depth from Bitfinex
+    pub async fn get_orderbook(&self, symbol: &Symbol, limit: Option<u32>) -> Result<OrderBook> {
+        // P0 books only accept 1, 25 or 100 levels
+        let len = match limit.unwrap_or(100) {
+            0..=1 => 1,
+            2..=25 => 25,
+            _ => 100,
+        };
+        let endpoint = format!("/v2/book/{}/P0?len={}", bitfinex_symbol(symbol), len);
+        let response = self.get_request(&endpoint).await?;
+
+        let mut orderbook = OrderBook::new(symbol.clone());
+        for entry in response.as_array().into_iter().flatten() {
+            // [price, count, amount]; positive amounts are bids
+            if let (Some(price), Some(amount)) = (value_to_decimal(&entry[0]), value_to_decimal(&entry[2])) {
+                if amount.is_sign_positive() {
+                    orderbook.update_bid(price, amount);
+                } else {
+                    orderbook.update_ask(price, amount.abs());
+                }
+            }
+        }
+
+        Ok(orderbook)
+    }
+}
+
+impl Default for BitfinexAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl MarketDataAdapter for BitfinexAdapter {
+    fn venue_id(&self) -> VenueId {
+        VenueId::Bitfinex
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        let status = self.get_request("/v2/platform/status").await?;
+        if status[0].as_i64() != Some(1) {
+            return Err(ArbFinderError::MarketClosed("Bitfinex is in maintenance".to_string()));
+        }
+
+        for symbol in self.get_symbols().await? {
+            self.register_symbol(&symbol);
+        }
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.connected = false;
+        Ok(())
+    }
+
+    async fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        // No time endpoint; the HTTP Date header is the server clock
+        let url = format!("{}/v2/platform/status", self.base_url);
+        let response = self.client.get(&url).send().await.map_err(ArbFinderError::Http)?;
+        let date = response
+            .headers()
+            .get(reqwest::header::DATE)
+            .and_then(|h| h.to_str().ok())
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing Date header".to_string()))?;
+
+        DateTime::parse_from_rfc2822(date)
+            .map(|d| d.with_timezone(&Utc))
+            .map_err(|e| ArbFinderError::Parse(format!("Invalid Date header: {}", e)))
+    }
+
+    async fn ping(&self) -> Result<u64> {
+        let start = std::time::Instant::now();
+        let _ = self.get_request("/v2/platform/status").await?;
+        Ok(start.elapsed().as_millis() as u64)
+    }
+
+    async fn get_symbols(&self) -> Result<Vec<Symbol>> {
+        let response = self.get_request("/v2/conf/pub:list:pair:exchange").await?;
+        let pairs = response[0]
+            .as_array()
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing pair list".to_string()))?;
+
+        Ok(pairs
+            .iter()
+            .filter_map(|p| p.as_str())
+            .filter_map(parse_bitfinex_symbol)
+            .collect())
+    }
+
+    async fn get_symbol_info(&self, symbol: &Symbol) -> Result<SymbolInfo> {
+        let response = self.get_request("/v2/conf/pub:info:pair").await?;
+        let pairs = response[0]
+            .as_array()
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing pair info".to_string()))?;
+
+        let symbol_str = bitfinex_symbol(symbol);
+        let pair = symbol_str.trim_start_matches('t');
+
+        for entry in pairs {
+            if entry[0].as_str() == Some(pair) {
+                // [.., .., .., MIN_ORDER_SIZE, MAX_ORDER_SIZE, ...]
+                let info = &entry[1];
+                return Ok(SymbolInfo {
+                    symbol: symbol.clone(),
+                    status: "TRADING".to_string(),
+                    base_asset_precision: 8,
+                    // Prices use five significant digits rather than fixed decimals
+                    quote_asset_precision: 5,
+                    tick_size: Decimal::new(1, 8),
+                    lot_size: Decimal::new(1, 8),
+                    min_order_size: value_to_decimal(&info[3]).unwrap_or(Decimal::new(1, 8)),
+                    max_order_size: value_to_decimal(&info[4]).unwrap_or(Decimal::new(1000000, 0)),
+                    min_notional: Decimal::ZERO,
+                    trading_fees: TradingFees {
+                        maker_fee: Decimal::new(1, 3), // 0.1%
+                        taker_fee: Decimal::new(2, 3), // 0.2%
+                    },
+                });
+            }
+        }
+
+        Err(ArbFinderError::SymbolNotFound(symbol_str))
+    }
+
+    async fn subscribe_orderbook(&mut self, symbol: &Symbol, _depth: Option<u32>) -> Result<()> {
+        self.register_symbol(symbol);
+        if !self.subscriptions.contains(symbol) {
+            self.subscriptions.push(symbol.clone());
+        }
+        Ok(())
+    }
+
+    async fn subscribe_trades(&mut self, symbol: &Symbol) -> Result<()> {
+        // Book and trades channels are subscribed together per symbol
+        self.subscribe_orderbook(symbol, None).await
+    }
+
+    async fn subscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_orderbook(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscriptions.retain(|s| s != symbol);
+        Ok(())
+    }
+
+    async fn unsubscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn market_data_stream(&self) -> Result<MarketDataStream> {
+        if self.subscriptions.is_empty() {
+            return Err(ArbFinderError::Exchange("No Bitfinex symbols subscribed".to_string()));
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let handler = BitfinexMarketDataStream::new(
+            self.normalizer.clone(),
+            self.subscriptions.clone(),
+            tx,
+        );
+        let subscribe = handler.subscribe_messages();
+        let config = DefaultExchangeConfig {
+            base_url: self.base_url.clone(),
+            websocket_url: self.ws_url.clone(),
+            ..Default::default()
+        };
+
+        spawn_connection(&config, &subscribe, handler).await?;
+        Ok(channel_stream(rx))
+    }
+
+    async fn fetch_orderbook(&self, symbol: &Symbol, depth: Option<u32>) -> Result<OrderBook> {
+        self.get_orderbook(symbol, depth).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_bitfinex_adapter_creation() {
+        let adapter = BitfinexAdapter::new();
+        assert_eq!(adapter.venue_id(), VenueId::Bitfinex);
+        assert!(!adapter.is_connected().await);
+    }
+
+    #[test]
+    fn test_bitfinex_symbol_round_trip() {
+        assert_eq!(bitfinex_symbol(&Symbol::new("BTC", "USD")), "tBTCUSD");
+        assert_eq!(bitfinex_symbol(&Symbol::new("ETH", "USDT")), "tETHUST");
+        assert_eq!(bitfinex_symbol(&Symbol::new("AVAX", "USD")), "tAVAX:USD");
+
+        assert_eq!(parse_bitfinex_symbol("tBTCUSD"), Some(Symbol::new("BTC", "USD")));
+        assert_eq!(parse_bitfinex_symbol("ETHUST"), Some(Symbol::new("ETH", "USDT")));
+        assert_eq!(parse_bitfinex_symbol("TESTBTC:TESTUSD"), Some(Symbol::new("TESTBTC", "TESTUSD")));
+        assert_eq!(parse_bitfinex_symbol("tBTCF0:USTF0"), Some(Symbol::new("BTCF0", "USTF0")));
+        assert_eq!(parse_bitfinex_symbol("BTCUSDT"), None);
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_register_with_normalizer() {
+        let mut adapter = BitfinexAdapter::new();
+        adapter.subscribe_orderbook(&Symbol::new("ETH", "USDT"), None).await.unwrap();
+
+        assert_eq!(
+            adapter.normalizer().normalize_symbol("tETHUST").unwrap(),
+            Symbol::new("ETH", "USDT")
+        );
+        assert_eq!(adapter.normalizer().denormalize_symbol(&Symbol::new("ETH", "USDT")).unwrap(), "tETHUST");
+    }
+}
@@ -0,0 +1,419 @@
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use arbfinder_orderbook::L3OrderBook;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use crate::bitfinex_symbol;
+
+pub const BITFINEX_WS_URL: &str = "wss://api-pub.bitfinex.com/ws/2";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ChannelKind {
+    Book,
+    Trades,
+}
+
+#[derive(Debug, Clone)]
+struct Channel {
+    kind: ChannelKind,
+    symbol: Symbol,
+}
+
+/// Handles Bitfinex v2 public `book` and `trades` channels.
+///
+/// Bitfinex acknowledges each subscription with a numeric `chanId`, and every
+/// data message afterwards is a bare array keyed by that id, so the
+/// `subscribed` events are what tie payloads back to symbols.
+pub struct BitfinexMarketDataStream {
+    normalizer: DefaultSymbolNormalizer,
+    symbols: Vec<Symbol>,
+    channels: HashMap<u64, Channel>,
+    books: HashMap<u64, OrderBook>,
+    /// Raw books by channel, order by order, when subscribed to them
+    raw_books: Option<HashMap<u64, L3OrderBook>>,
+    update_tx: mpsc::UnboundedSender<MarketData>,
+}
+
+impl BitfinexMarketDataStream {
+    pub fn new(
+        normalizer: DefaultSymbolNormalizer,
+        symbols: Vec<Symbol>,
+        update_tx: mpsc::UnboundedSender<MarketData>,
+    ) -> Self {
+        Self {
+            normalizer,
+            symbols,
+            channels: HashMap::new(),
+            books: HashMap::new(),
+            raw_books: None,
+            update_tx,
+        }
+    }
+
+    /// Subscribes raw (`R0`) books, which list individual orders, in place
+    /// of price-aggregated ones. Each is kept as an [`L3OrderBook`] and
+    /// still sent on by price level.
+    pub fn with_raw_books(mut self) -> Self {
+        self.raw_books = Some(HashMap::new());
+        self
+    }
+
+    /// One message per channel and symbol; Bitfinex has no batch subscribe.
+    pub fn subscribe_messages(&self) -> Vec<String> {
+        let mut messages = Vec::new();
+        for symbol in &self.symbols {
+            let pair = bitfinex_symbol(symbol);
+            messages.push(
+                serde_json::json!({
+                    "event": "subscribe",
+                    "channel": "book",
+                    "symbol": pair,
+                    "prec": if self.raw_books.is_some() { "R0" } else { "P0" },
+                    "len": if self.raw_books.is_some() { "250" } else { "25" },
+                })
+                .to_string(),
+            );
+            messages.push(
+                serde_json::json!({ "event": "subscribe", "channel": "trades", "symbol": pair })
+                    .to_string(),
+            );
+        }
+        messages
+    }
+
+    pub fn get_orderbook(&self, symbol: &Symbol) -> Option<&OrderBook> {
+        self.channels
+            .iter()
+            .find(|(_, c)| c.kind == ChannelKind::Book && &c.symbol == symbol)
+            .and_then(|(id, _)| self.books.get(id))
+    }
+
+    pub fn get_l3_orderbook(&self, symbol: &Symbol) -> Option<&L3OrderBook> {
+        let raw_books = self.raw_books.as_ref()?;
+        self.channels
+            .iter()
+            .find(|(_, c)| c.kind == ChannelKind::Book && &c.symbol == symbol)
+            .and_then(|(id, _)| raw_books.get(id))
+    }
+
+    fn process_event(&mut self, event: &Value) -> Result<()> {
+        match event["event"].as_str() {
+            Some("subscribed") => {
+                let chan_id = event["chanId"]
+                    .as_u64()
+                    .ok_or_else(|| ArbFinderError::InvalidData("Missing chanId".to_string()))?;
+                let kind = match event["channel"].as_str() {
+                    Some("book") => ChannelKind::Book,
+                    Some("trades") => ChannelKind::Trades,
+                    _ => return Ok(()),
+                };
+                let exchange_symbol = event["symbol"].as_str().unwrap_or_default();
+                let symbol = self.normalizer.normalize_symbol(exchange_symbol)?;
+
+                debug!("Bitfinex {:?} channel {} -> {}", kind, chan_id, symbol);
+                self.channels.insert(chan_id, Channel { kind, symbol });
+            }
+            Some("unsubscribed") => {
+                if let Some(chan_id) = event["chanId"].as_u64() {
+                    self.channels.remove(&chan_id);
+                    self.books.remove(&chan_id);
+                    if let Some(raw_books) = &mut self.raw_books {
+                        raw_books.remove(&chan_id);
+                    }
+                }
+            }
+            Some("error") => {
+                warn!("Bitfinex WebSocket error event: {}", event);
+            }
+            Some("info") => {
+                // Code 20051 asks clients to reconnect; 20060/20061 bracket maintenance
+                if let Some(code) = event["code"].as_u64() {
+                    info!("Bitfinex info event {}: {}", code, event["msg"]);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn process_book(&mut self, chan_id: u64, symbol: Symbol, payload: &Value) {
+        let Some(entries) = payload.as_array() else {
+            return;
+        };
+        if let Some(raw_books) = &mut self.raw_books {
+            // A snapshot is an array of [order id, price, amount] arrays
+            let book = raw_books.entry(chan_id).or_insert_with(|| L3OrderBook::new(symbol.clone()));
+            if entries.first().is_some_and(|e| e.is_array()) {
+                book.clear();
+                for entry in entries {
+                    apply_raw_book_entry(book, entry);
+                }
+            } else {
+                apply_raw_book_entry(book, payload);
+            }
+            let levels = book.to_fast_orderbook(None).to_core_orderbook();
+            let _ = self.update_tx.send(MarketData::OrderBook(levels));
+            return;
+        }
+
+        // A snapshot is an array of [price, count, amount] arrays
+        let is_snapshot = entries.first().is_some_and(|e| e.is_array());
+        let book = self
+            .books
+            .entry(chan_id)
+            .or_insert_with(|| OrderBook::new(symbol.clone()));
+        if is_snapshot {
+            *book = OrderBook::new(symbol);
+            for entry in entries {
+                apply_book_entry(book, entry);
+            }
+        } else {
+            apply_book_entry(book, payload);
+        }
+
+        let _ = self.update_tx.send(MarketData::OrderBook(book.clone()));
+    }
+
+    fn process_trade(&self, symbol: &Symbol, entry: &Value) {
+        // [id, mts, amount, price]; negative amount means the taker sold
+        let (Some(id), Some(mts), Some(amount), Some(price)) = (
+            entry[0].as_u64(),
+            entry[1].as_i64(),
+            entry[2].as_f64().and_then(Decimal::from_f64),
+            entry[3].as_f64().and_then(Decimal::from_f64),
+        ) else {
+            return;
+        };
+
+        let side = if amount.is_sign_negative() { Side::Ask } else { Side::Bid };
+        let mut trade = Trade::new(symbol.clone(), price, amount.abs(), side, id.to_string());
+        trade.timestamp = DateTime::<Utc>::from_timestamp_millis(mts).unwrap_or_else(Utc::now);
+
+        let _ = self.update_tx.send(MarketData::Trade(trade));
+    }
+
+    fn process_channel_message(&mut self, message: &[Value]) {
+        let Some(chan_id) = message.first().and_then(|v| v.as_u64()) else {
+            return;
+        };
+        let Some(channel) = self.channels.get(&chan_id).cloned() else {
+            debug!("Bitfinex message for unknown channel {}", chan_id);
+            return;
+        };
+
+        match (channel.kind, message.get(1)) {
+            (_, Some(Value::String(hb))) if hb == "hb" => {}
+            (ChannelKind::Book, Some(payload)) => self.process_book(chan_id, channel.symbol, payload),
+            (ChannelKind::Trades, Some(Value::Array(snapshot))) => {
+                // Snapshot arrives newest first
+                for entry in snapshot.iter().rev() {
+                    self.process_trade(&channel.symbol, entry);
+                }
+            }
+            // "te" is the execution, "tu" repeats it with the trade id filled in
+            (ChannelKind::Trades, Some(Value::String(kind))) if kind == "te" => {
+                if let Some(entry) = message.get(2) {
+                    self.process_trade(&channel.symbol, entry);
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+fn apply_book_entry(book: &mut OrderBook, entry: &Value) {
+    let (Some(price), Some(count), Some(amount)) = (
+        entry[0].as_f64().and_then(Decimal::from_f64),
+        entry[1].as_u64(),
+        entry[2].as_f64().and_then(Decimal::from_f64),
+    ) else {
+        return;
+    };
+
+    // count == 0 deletes the level; amount's sign says which side it was on
+    let quantity = if count == 0 { Decimal::ZERO } else { amount.abs() };
+    if amount.is_sign_positive() {
+        book.update_bid(price, quantity);
+    } else {
+        book.update_ask(price, quantity);
+    }
+}
+
+fn apply_raw_book_entry(book: &mut L3OrderBook, entry: &Value) {
+    let (Some(id), Some(price), Some(amount)) = (
+        entry[0].as_u64(),
+        entry[1].as_f64().and_then(Decimal::from_f64),
+        entry[2].as_f64().and_then(Decimal::from_f64),
+    ) else {
+        return;
+    };
+
+    // Price 0 removes the order; amount's sign says which side it is on
+    if price.is_zero() {
+        book.remove_order(&id.to_string());
+    } else {
+        let side = if amount.is_sign_positive() { Side::Bid } else { Side::Ask };
+        book.add_order(id.to_string(), side, price, amount.abs());
+    }
+}
+
+#[async_trait]
+impl WebSocketHandler for BitfinexMarketDataStream {
+    async fn on_message(&mut self, message: &str) -> Result<()> {
+        match serde_json::from_str::<Value>(message)? {
+            Value::Array(items) => self.process_channel_message(&items),
+            event @ Value::Object(_) => self.process_event(&event)?,
+            _ => debug!("Ignoring Bitfinex message: {}", message),
+        }
+        Ok(())
+    }
+
+    async fn on_connect(&mut self) -> Result<()> {
+        info!("Bitfinex WebSocket connected for {} symbols", self.symbols.len());
+        Ok(())
+    }
+
+    async fn on_disconnect(&mut self) -> Result<()> {
+        warn!("Bitfinex WebSocket disconnected");
+        // Channel ids are per connection
+        self.channels.clear();
+        self.books.clear();
+        if let Some(raw_books) = &mut self.raw_books {
+            raw_books.clear();
+        }
+        Ok(())
+    }
+
+    async fn on_error(&mut self, error: &ArbFinderError) -> Result<()> {
+        error!("Bitfinex WebSocket error: {}", error);
+        Ok(())
+    }
+
+    async fn on_ping(&mut self) -> Result<()> {
+        debug!("Received Bitfinex ping");
+        Ok(())
+    }
+
+    async fn on_pong(&mut self) -> Result<()> {
+        debug!("Received Bitfinex pong");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn stream() -> (BitfinexMarketDataStream, mpsc::UnboundedReceiver<MarketData>) {
+        let symbol = Symbol::new("BTC", "USDT");
+        let mut normalizer = DefaultSymbolNormalizer::new();
+        normalizer.add_symbol_mapping(bitfinex_symbol(&symbol), symbol.clone());
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        (BitfinexMarketDataStream::new(normalizer, vec![symbol], tx), rx)
+    }
+
+    #[test]
+    fn test_subscribe_messages() {
+        let (stream, _rx) = stream();
+        let messages = stream.subscribe_messages();
+        assert_eq!(messages.len(), 2);
+
+        let book: Value = serde_json::from_str(&messages[0]).unwrap();
+        assert_eq!(book["channel"], "book");
+        assert_eq!(book["symbol"], "tBTCUST");
+    }
+
+    #[tokio::test]
+    async fn test_book_snapshot_and_update() {
+        let (mut stream, mut rx) = stream();
+        stream
+            .on_message(r#"{"event":"subscribed","channel":"book","chanId":17082,"symbol":"tBTCUST","prec":"P0"}"#)
+            .await
+            .unwrap();
+        stream
+            .on_message("[17082,[[7254.7,3,3.3],[7254.6,2,1.2],[7254.8,1,-0.5]]]")
+            .await
+            .unwrap();
+
+        match rx.recv().await.unwrap() {
+            MarketData::OrderBook(book) => {
+                assert_eq!(book.symbol, Symbol::new("BTC", "USDT"));
+                assert_eq!(book.best_bid().unwrap().price, Decimal::from_str("7254.7").unwrap());
+                assert_eq!(book.best_ask().unwrap().quantity, Decimal::from_str("0.5").unwrap());
+            }
+            other => panic!("expected order book, got {:?}", other),
+        }
+
+        // count 0 removes the level
+        stream.on_message("[17082,[7254.7,0,1]]").await.unwrap();
+        stream.on_message(r#"[17082,"hb"]"#).await.unwrap();
+        let book = stream.get_orderbook(&Symbol::new("BTC", "USDT")).unwrap();
+        assert_eq!(book.best_bid().unwrap().price, Decimal::from_str("7254.6").unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_raw_book_orders() {
+        let (stream, mut rx) = stream();
+        let mut stream = stream.with_raw_books();
+        let book: Value = serde_json::from_str(&stream.subscribe_messages()[0]).unwrap();
+        assert_eq!(book["prec"], "R0");
+
+        stream
+            .on_message(r#"{"event":"subscribed","channel":"book","chanId":9,"symbol":"tBTCUST","prec":"R0"}"#)
+            .await
+            .unwrap();
+        stream
+            .on_message("[9,[[101,7254.7,1.5],[102,7254.7,0.5],[103,7254.8,-0.5]]]")
+            .await
+            .unwrap();
+        match rx.recv().await.unwrap() {
+            MarketData::OrderBook(book) => {
+                assert_eq!(book.best_bid().unwrap().quantity, Decimal::from(2));
+            }
+            other => panic!("expected order book, got {:?}", other),
+        }
+
+        // Price 0 removes order 101, putting 102 at the front
+        stream.on_message("[9,[101,0,1]]").await.unwrap();
+        let book = stream.get_l3_orderbook(&Symbol::new("BTC", "USDT")).unwrap();
+        assert_eq!(book.order_count(), 2);
+        assert_eq!(book.queue_position("102").unwrap().orders_ahead, 0);
+        assert_eq!(book.get_order("103").unwrap().side, Side::Ask);
+    }
+
+    #[tokio::test]
+    async fn test_trade_execution() {
+        let (mut stream, mut rx) = stream();
+        stream
+            .on_message(r#"{"event":"subscribed","channel":"trades","chanId":42,"symbol":"tBTCUST","pair":"BTCUST"}"#)
+            .await
+            .unwrap();
+        stream
+            .on_message(r#"[42,"te",[401597393,1574694475039,-0.005,7244.9]]"#)
+            .await
+            .unwrap();
+        stream
+            .on_message(r#"[42,"tu",[401597393,1574694475039,-0.005,7244.9]]"#)
+            .await
+            .unwrap();
+
+        match rx.recv().await.unwrap() {
+            MarketData::Trade(trade) => {
+                assert_eq!(trade.side, Side::Ask);
+                assert_eq!(trade.quantity, Decimal::from_str("0.005").unwrap());
+                assert_eq!(trade.trade_id, "401597393");
+            }
+            other => panic!("expected trade, got {:?}", other),
+        }
+        assert!(rx.try_recv().is_err());
+    }
+}
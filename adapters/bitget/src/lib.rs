@@ -0,0 +1,884 @@
+//! Bitget Exchange Adapter
+//!
+//! Implementation of ExchangeAdapter trait for Bitget spot (API v2): public
+//! market data over REST and WebSocket, plus signed order placement.
+
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use arbfinder_core::config::{ProxyConfig, RateLimitConfig, RateLimitScope, RateLimitWindow};
+use arbfinder_exchange::{channel_stream, http_client, retry_after, spawn_connection_with, DefaultExchangeConfig, SingleFlight};
+use async_trait::async_trait;
+use base64::Engine;
+use reqwest::{Client, Method};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+pub mod websocket;
+pub use websocket::BitgetMarketDataStream;
+
+const BITGET_API_URL: &str = "https://api.bitget.com";
+
+/// Bitget drops connections that have not sent a text `ping` for two minutes.
+const BITGET_WS_PING_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Success code in Bitget's `{code, msg, data}` response envelope.
+const BITGET_OK: &str = "00000";
+
+/// Bitget allows 20 requests a second on most endpoints, and 10 order
+/// placements or cancels a second.
+fn bitget_rate_limits() -> RateLimitConfig {
+    RateLimitConfig {
+        windows: vec![
+            RateLimitWindow { scope: RateLimitScope::Requests, limit: 20, window_secs: 1 },
+            RateLimitWindow { scope: RateLimitScope::Orders, limit: 10, window_secs: 1 },
+        ],
+        endpoint_weights: HashMap::new(),
+        default_weight: 1,
+        order_endpoints: vec![
+            "/api/v2/spot/trade/place-order".to_string(),
+            "/api/v2/spot/trade/cancel-order".to_string(),
+            "/api/v2/spot/trade/cancel-symbol-order".to_string(),
+        ],
+    }
+}
+
+/// Formats a symbol as a Bitget spot instrument id, e.g. `BTCUSDT`.
+pub fn bitget_symbol(symbol: &Symbol) -> String {
+    format!("{}{}", symbol.base().to_uppercase(), symbol.quote().to_uppercase())
+}
+
+fn value_to_decimal(value: &serde_json::Value) -> Option<Decimal> {
+    match value {
+        serde_json::Value::String(s) => s.parse().ok(),
+        serde_json::Value::Number(n) => n.as_f64().and_then(Decimal::from_f64),
+        _ => None,
+    }
+}
+
+/// Bitget `force` value for an order request.
+fn bitget_force(request: &OrderRequest) -> Result<&'static str> {
+    if request.post_only {
+        return Ok("post_only");
+    }
+    match request.order_type {
+        OrderType::Market | OrderType::Limit => Ok(match request.time_in_force {
+            TimeInForce::GoodTillCanceled => "gtc",
+            TimeInForce::ImmediateOrCancel => "ioc",
+            TimeInForce::FillOrKill => "fok",
+            TimeInForce::PostOnly => "post_only",
+        }),
+        OrderType::PostOnly => Ok("post_only"),
+        OrderType::ImmediateOrCancel => Ok("ioc"),
+        OrderType::FillOrKill => Ok("fok"),
+        OrderType::StopMarket | OrderType::StopLimit | OrderType::Oco => Err(ArbFinderError::InvalidOrder(format!(
+            "Bitget spot does not support {} orders through this adapter",
+            request.order_type
+        ))),
+    }
+}
+
+/// The `data` of `/api/v2/spot/wallet/deposit-address`.
+fn parse_deposit_address(data: &serde_json::Value) -> Option<DepositAddress> {
+    let mut address = DepositAddress::new(data["coin"].as_str()?, data["address"].as_str()?);
+    address.tag = data["tag"].as_str().filter(|tag| !tag.is_empty()).map(str::to_string);
+    address.network = data["chain"].as_str().filter(|chain| !chain.is_empty()).map(str::to_string);
+    Some(address)
+}
+
+/// A record's `status`, one of `pending`, `success` and `fail`. Its
+/// `tradeId` is the chain transaction, once there is one.
+fn parse_record_status(record: &serde_json::Value) -> Option<(TransferStatus, Option<String>)> {
+    let status = match record["status"].as_str()? {
+        "success" => TransferStatus::Completed,
+        "fail" => TransferStatus::Failed,
+        _ => TransferStatus::Pending,
+    };
+    let tx_id = record["tradeId"].as_str().filter(|tx| !tx.is_empty()).map(str::to_string);
+    Some((status, tx_id))
+}
+
+/// An entry of `/api/v2/spot/wallet/deposit-records`.
+fn parse_deposit(record: &serde_json::Value) -> Option<Deposit> {
+    let (status, tx_id) = parse_record_status(record)?;
+    Some(Deposit {
+        id: record["orderId"].as_str()?.to_string(),
+        venue: VenueId::Bitget,
+        asset: record["coin"].as_str()?.to_string(),
+        amount: value_to_decimal(&record["size"])?,
+        tx_id,
+        status: match status {
+            TransferStatus::Pending => TransferStatus::Confirming,
+            status => status,
+        },
+        confirmations: None,
+        time: DateTime::from_timestamp_millis(record["cTime"].as_str()?.parse().ok()?)?,
+    })
+}
+
+/// Request body for `/api/v2/spot/trade/place-order`.
+fn place_order_body(request: &OrderRequest, client_oid: &str) -> Result<serde_json::Value> {
+    let force = bitget_force(request)?;
+    let market_buy = request.order_type == OrderType::Market && request.side == OrderSide::Buy;
+    if request.size_unit == SizeUnit::Quote && !market_buy {
+        return Err(ArbFinderError::InvalidOrder(
+            "Bitget only takes quote-currency sizes on market buys".to_string(),
+        ));
+    }
+    let mut body = serde_json::json!({
+        "symbol": bitget_symbol(&request.symbol),
+        "side": request.side.to_string(),
+        "force": force,
+        "size": request.quantity.normalize().to_string(),
+        "clientOid": client_oid,
+    });
+
+    match (request.order_type, request.price) {
+        (OrderType::Market, _) => {
+            // Market buys are sized in quote currency on Bitget, so a base
+            // quantity cannot be passed through as-is.
+            if market_buy && request.size_unit != SizeUnit::Quote {
+                return Err(ArbFinderError::InvalidOrder(
+                    "Bitget market buys are sized in quote currency; use a quote-sized request or a limit IOC order"
+                        .to_string(),
+                ));
+            }
+            body["orderType"] = "market".into();
+        }
+        (_, Some(price)) => {
+            body["orderType"] = "limit".into();
+            body["price"] = price.normalize().to_string().into();
+        }
+        (_, None) => {
+            return Err(ArbFinderError::InvalidOrder("Limit order requires a price".to_string()));
+        }
+    }
+
+    Ok(body)
+}
+
+pub struct BitgetAdapter {
+    client: Client,
+    tls_pins: Option<TlsPins>,
+    proxy: Option<ProxyConfig>,
+    /// Replaced whole on rotation; requests copy them out rather than hold
+    /// the lock while in flight.
+    credentials: RwLock<Option<VenueCredentials>>,
+    base_url: String,
+    ws_url: String,
+    connected: bool,
+    subscriptions: Vec<Symbol>,
+    /// Symbol and venue id of orders placed through this adapter; Bitget
+    /// needs the symbol to cancel.
+    orders: RwLock<HashMap<OrderId, (Symbol, String)>>,
+    /// Bitget's request and order budget, shared with whoever else sends
+    /// it requests.
+    rate_limiter: SharedRateLimiter,
+    /// GETs being sent, which identical ones arriving meanwhile share.
+    in_flight: SingleFlight<String, serde_json::Value>,
+}
+
+impl BitgetAdapter {
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+            tls_pins: None,
+            proxy: None,
+            credentials: RwLock::new(None),
+            base_url: BITGET_API_URL.to_string(),
+            ws_url: websocket::BITGET_WS_URL.to_string(),
+            connected: false,
+            subscriptions: Vec::new(),
+            orders: RwLock::new(HashMap::new()),
+            rate_limiter: WeightedRateLimiter::new(&bitget_rate_limits()).shared(),
+            in_flight: SingleFlight::new(),
+        }
+    }
+
+    pub fn with_credentials(
+        api_key: impl Into<SecretString>,
+        api_secret: impl Into<SecretString>,
+        passphrase: impl Into<SecretString>,
+    ) -> Self {
+        Self {
+            client: Client::new(),
+            tls_pins: None,
+            proxy: None,
+            credentials: RwLock::new(Some(VenueCredentials {
+                api_key: api_key.into(),
+                secret_key: api_secret.into(),
+                passphrase: Some(passphrase.into()),
+                sandbox: false,
+            })),
+            base_url: BITGET_API_URL.to_string(),
+            ws_url: websocket::BITGET_WS_URL.to_string(),
+            connected: false,
+            subscriptions: Vec::new(),
+            orders: RwLock::new(HashMap::new()),
+            rate_limiter: WeightedRateLimiter::new(&bitget_rate_limits()).shared(),
+            in_flight: SingleFlight::new(),
+        }
+    }
+
+    /// Only talks to servers presenting one of the pinned keys, over REST
+    /// and WebSocket alike.
+    pub fn with_tls_pins(mut self, pins: TlsPins) -> Result<Self> {
+        self.tls_pins = Some(pins);
+        self.client = http_client(Client::builder(), self.proxy.as_ref(), self.tls_pins.as_ref())?;
+        Ok(self)
+    }
+
+    /// Sends REST requests and opens WebSocket connections through `proxy`.
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Result<Self> {
+        self.proxy = Some(proxy);
+        self.client = http_client(Client::builder(), self.proxy.as_ref(), self.tls_pins.as_ref())?;
+        Ok(self)
+    }
+
+    /// Draws requests from `rate_limiter` instead of Bitget's default
+    /// limits, e.g. to share one budget between adapters on the same key.
+    pub fn with_rate_limiter(mut self, rate_limiter: SharedRateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Waits for room in the rate limiter for a request to `endpoint`.
+    async fn acquire(&self, method: &Method, endpoint: &str) {
+        let places_order = *method != Method::GET && self.rate_limiter.is_order_endpoint(endpoint);
+        let priority = RequestPriority::for_request(method, places_order);
+        self.rate_limiter.acquire_with_priority(endpoint, places_order, priority).await;
+    }
+
+    /// Base64 HMAC-SHA256 over `timestamp + METHOD + path?query + body`.
+    fn sign(secret: &SecretString, timestamp: &str, method: &Method, path: &str, body: &str) -> Result<String> {
+        use hmac::{Hmac, Mac};
+        use sha2::Sha256;
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(secret.expose().as_bytes())
+            .map_err(|e| ArbFinderError::Authentication(format!("Invalid secret key: {}", e)))?;
+        mac.update(format!("{}{}{}{}", timestamp, method.as_str(), path, body).as_bytes());
+
+        Ok(base64::engine::general_purpose::STANDARD.encode(mac.finalize().into_bytes()))
+    }
+
+    fn credentials(&self) -> Result<VenueCredentials> {
+        self.credentials
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .ok_or_else(|| ArbFinderError::Authentication("Bitget API key not configured".to_string()))
+    }
+
+    /// Checks the response envelope and returns its `data` field, feeding
+    /// the status back to the rate limiter.
+    async fn parse_response(&self, response: reqwest::Response) -> Result<serde_json::Value> {
+        let status = response.status();
+        self.rate_limiter.record_response(status.as_u16(), retry_after(response.headers(), Utc::now()));
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(ArbFinderError::RateLimit("Bitget rate limit exceeded".to_string()));
+        }
+
+        let body: serde_json::Value = response.json().await.map_err(ArbFinderError::Http)?;
+        let code = body["code"].as_str().unwrap_or_default();
+        if !status.is_success() || code != BITGET_OK {
+            let msg = body["msg"].as_str().unwrap_or_default();
+            return Err(match code {
+                "40006" | "40012" | "40037" => ArbFinderError::Authentication(format!("Bitget: {}", msg)),
+                "43012" => ArbFinderError::InsufficientBalance(format!("Bitget: {}", msg)),
+                _ => ArbFinderError::Exchange(format!("Bitget API error {} ({}): {}", status, code, msg)),
+            });
+        }
+
+        Ok(body["data"].clone())
+    }
+
+    /// Sends a public GET, or joins an identical one already in flight.
+    async fn get_request(&self, endpoint: &str) -> Result<serde_json::Value> {
+        self.in_flight.run(endpoint.to_string(), || self.send_get(endpoint)).await
+    }
+
+    async fn send_get(&self, endpoint: &str) -> Result<serde_json::Value> {
+        self.acquire(&Method::GET, endpoint).await;
+        let url = format!("{}{}", self.base_url, endpoint);
+        let response = self.client
+            .get(&url)
+            .send()
+            .await
+            .map_err(ArbFinderError::Http)?;
+
+        self.parse_response(response).await
+    }
+
+    /// Sends a signed GET, or joins an identical one already in flight;
+    /// each is signed when it is sent, not when it is shared.
+    async fn signed_get(&self, endpoint: &str) -> Result<serde_json::Value> {
+        self.in_flight
+            .run(format!("signed {}", endpoint), || self.signed_request(Method::GET, endpoint, None))
+            .await
+    }
+
+    async fn signed_post(&self, endpoint: &str, body: &serde_json::Value) -> Result<serde_json::Value> {
+        self.signed_request(Method::POST, endpoint, Some(body)).await
+    }
+
+    /// Sends a signed request; a GET's query string is part of `endpoint`
+    /// and so of what is signed.
+    async fn signed_request(
+        &self,
+        method: Method,
+        endpoint: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<serde_json::Value> {
+        let credentials = self.credentials()?;
+        let passphrase = credentials.passphrase.as_ref()
+            .ok_or_else(|| ArbFinderError::Authentication("Bitget passphrase not configured".to_string()))?;
+
+        // Waits before stamping the request, so it isn't stale once sent
+        self.acquire(&method, endpoint).await;
+        let timestamp = Utc::now().timestamp_millis().to_string();
+        let body = body.map(|body| body.to_string()).unwrap_or_default();
+        let signature = Self::sign(&credentials.secret_key, &timestamp, &method, endpoint, &body)?;
+
+        let mut request = self.client
+            .request(method, format!("{}{}", self.base_url, endpoint))
+            .header("ACCESS-KEY", credentials.api_key.expose())
+            .header("ACCESS-SIGN", signature)
+            .header("ACCESS-TIMESTAMP", timestamp)
+            .header("ACCESS-PASSPHRASE", passphrase.expose())
+            .header("locale", "en-US");
+        if !body.is_empty() {
+            request = request.header(reqwest::header::CONTENT_TYPE, "application/json").body(body);
+        }
+        let response = request.send().await.map_err(ArbFinderError::Http)?;
+
+        self.parse_response(response).await
+    }
+
+    /// Fetch the ticker for a symbol
+    pub async fn get_ticker(&self, symbol: &Symbol) -> Result<Ticker> {
+        let data = self
+            .get_request(&format!("/api/v2/spot/market/tickers?symbol={}", bitget_symbol(symbol)))
+            .await?;
+        let ticker = &data[0];
+
+        let price = value_to_decimal(&ticker["lastPr"])
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing last price".to_string()))?;
+        let timestamp = ticker["ts"]
+            .as_str()
+            .and_then(|ts| ts.parse::<i64>().ok())
+            .and_then(DateTime::<Utc>::from_timestamp_millis)
+            .unwrap_or_else(Utc::now);
+
+        Ok(Ticker {
+            symbol: symbol.clone(),
+            price,
+            volume_24h: value_to_decimal(&ticker["baseVolume"]).unwrap_or(Decimal::ZERO),
+            // change24h is a ratio; convert to an absolute change like the other adapters
+            change_24h: value_to_decimal(&ticker["open"])
+                .map(|open| price - open)
+                .unwrap_or(Decimal::ZERO),
+            timestamp,
+        })
+    }
+
+    /// Fetch orderbook depth from Bitget, behind other requests
+    pub async fn get_orderbook(&self, symbol: &Symbol, limit: Option<u32>) -> Result<OrderBook> {
+        let endpoint = format!(
+            "/api/v2/spot/market/orderbook?symbol={}&type=step0&limit={}",
+            bitget_symbol(symbol),
+            limit.unwrap_or(100).min(150)
+        );
+        let data = RequestPriority::Background.scope(self.get_request(&endpoint)).await?;
+
+        let mut orderbook = OrderBook::new(symbol.clone());
+        websocket::apply_levels(&mut orderbook, &data["bids"], Side::Bid);
+        websocket::apply_levels(&mut orderbook, &data["asks"], Side::Ask);
+
+        Ok(orderbook)
+    }
+}
+
+impl Default for BitgetAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl MarketDataAdapter for BitgetAdapter {
+    fn venue_id(&self) -> VenueId {
+        VenueId::Bitget
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        let _ = self.get_server_time().await?;
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.connected = false;
+        Ok(())
+    }
+
+    async fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        let data = self.get_request("/api/v2/public/time").await?;
+        let millis = data["serverTime"]
+            .as_str()
+            .and_then(|s| s.parse::<i64>().ok())
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing serverTime".to_string()))?;
+
+        DateTime::<Utc>::from_timestamp_millis(millis)
+            .ok_or_else(|| ArbFinderError::InvalidData("Invalid timestamp".to_string()))
+    }
+
+    async fn ping(&self) -> Result<u64> {
+        let start = std::time::Instant::now();
+        let _ = self.get_request("/api/v2/public/time").await?;
+        Ok(start.elapsed().as_millis() as u64)
+    }
+
+    async fn get_symbols(&self) -> Result<Vec<Symbol>> {
+        let data = RequestPriority::Background
+            .scope(self.get_request("/api/v2/spot/public/symbols"))
+            .await?;
+        let symbols = data
+            .as_array()
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing symbol list".to_string()))?;
+
+        Ok(symbols
+            .iter()
+            .filter(|s| s["status"].as_str() == Some("online"))
+            .filter_map(|s| Some(Symbol::new(s["baseCoin"].as_str()?, s["quoteCoin"].as_str()?)))
+            .collect())
+    }
+
+    fn venue_symbol(&self, symbol: &Symbol) -> String {
+        bitget_symbol(symbol)
+    }
+
+    async fn get_symbol_info(&self, symbol: &Symbol) -> Result<SymbolInfo> {
+        let symbol_str = bitget_symbol(symbol);
+        let endpoint = format!("/api/v2/spot/public/symbols?symbol={}", symbol_str);
+        let data = RequestPriority::Background.scope(self.get_request(&endpoint)).await?;
+        let info = data
+            .as_array()
+            .and_then(|symbols| symbols.first())
+            .ok_or_else(|| ArbFinderError::SymbolNotFound(symbol_str.clone()))?;
+
+        let precision = |key: &str| info[key].as_str().and_then(|s| s.parse::<u32>().ok());
+        let price_precision = precision("pricePrecision").unwrap_or(8);
+        let quantity_precision = precision("quantityPrecision").unwrap_or(8);
+
+        Ok(SymbolInfo {
+            symbol: symbol.clone(),
+            status: info["status"].as_str().unwrap_or("unknown").to_string(),
+            base_asset_precision: quantity_precision,
+            quote_asset_precision: price_precision,
+            tick_size: Decimal::new(1, price_precision),
+            lot_size: Decimal::new(1, quantity_precision),
+            min_order_size: value_to_decimal(&info["minTradeAmount"]).unwrap_or(Decimal::ZERO),
+            max_order_size: value_to_decimal(&info["maxTradeAmount"]).unwrap_or(Decimal::new(1000000, 0)),
+            min_notional: value_to_decimal(&info["minTradeUSDT"]).unwrap_or(Decimal::ZERO),
+            trading_fees: TradingFees {
+                maker_fee: value_to_decimal(&info["makerFeeRate"]).unwrap_or(Decimal::new(1, 3)),
+                taker_fee: value_to_decimal(&info["takerFeeRate"]).unwrap_or(Decimal::new(1, 3)),
+            },
+        })
+    }
+
+    async fn subscribe_orderbook(&mut self, symbol: &Symbol, _depth: Option<u32>) -> Result<()> {
+        if !self.subscriptions.contains(symbol) {
+            self.subscriptions.push(symbol.clone());
+        }
+        Ok(())
+    }
+
+    async fn subscribe_trades(&mut self, symbol: &Symbol) -> Result<()> {
+        // Book and trade channels are subscribed together per symbol
+        self.subscribe_orderbook(symbol, None).await
+    }
+
+    async fn subscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_orderbook(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscriptions.retain(|s| s != symbol);
+        Ok(())
+    }
+
+    async fn unsubscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn market_data_stream(&self) -> Result<MarketDataStream> {
+        if self.subscriptions.is_empty() {
+            return Err(ArbFinderError::Exchange("No Bitget symbols subscribed".to_string()));
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let subscribe = vec![websocket::subscribe_message(&self.subscriptions)];
+        let config = DefaultExchangeConfig {
+            base_url: self.base_url.clone(),
+            websocket_url: self.ws_url.clone(),
+            tls_pins: self.tls_pins.clone(),
+            proxy: self.proxy.clone(),
+            ..Default::default()
+        };
+        let symbols = self.subscriptions.clone();
+
+        spawn_connection_with(&config, &subscribe, move |outbound| {
+            let keepalive = outbound.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(BITGET_WS_PING_INTERVAL);
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    if keepalive.send("ping".to_string()).is_err() {
+                        break;
+                    }
+                }
+            });
+            BitgetMarketDataStream::new(symbols, tx, outbound)
+        })
+        .await?;
+        Ok(channel_stream(rx))
+    }
+
+    async fn fetch_orderbook(&self, symbol: &Symbol, depth: Option<u32>) -> Result<OrderBook> {
+        self.get_orderbook(symbol, depth).await
+    }
+}
+
+#[async_trait]
+impl TradingAdapter for BitgetAdapter {
+    async fn order_update_stream(&self) -> Result<OrderUpdateStream> {
+        Err(ArbFinderError::Unsupported("Bitget order update stream is not implemented yet".to_string()))
+    }
+
+    async fn place_order(&self, request: &OrderRequest) -> Result<Order> {
+        let mut order = match request.price {
+            Some(price) if request.order_type != OrderType::Market => Order::new_limit(
+                VenueId::Bitget,
+                request.symbol.clone(),
+                request.side,
+                request.quantity,
+                price,
+            ),
+            _ => Order::new_market(VenueId::Bitget, request.symbol.clone(), request.side, request.quantity),
+        };
+        order.order_type = request.order_type;
+        order.time_in_force = request.time_in_force;
+
+        // Bitget dedupes on clientOid, so retries with the same request are safe
+        let client_oid = request
+            .client_order_id
+            .clone()
+            .unwrap_or_else(|| order.id.0.simple().to_string());
+        let body = place_order_body(request, &client_oid)?;
+        let data = self.signed_post("/api/v2/spot/trade/place-order", &body).await?;
+
+        let venue_order_id = data["orderId"]
+            .as_str()
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing orderId".to_string()))?
+            .to_string();
+
+        order.client_order_id = Some(client_oid);
+        order.venue_order_id = Some(venue_order_id.clone());
+        order.status = OrderStatus::Open;
+        self.orders
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(order.id.clone(), (order.symbol.clone(), venue_order_id));
+
+        Ok(order)
+    }
+
+    fn accepts_quote_size(&self, request: &OrderRequest) -> bool {
+        request.order_type == OrderType::Market && request.side == OrderSide::Buy
+    }
+
+    async fn cancel_order(&self, order_id: &OrderId) -> Result<()> {
+        let (symbol, venue_order_id) = self
+            .orders
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(order_id)
+            .cloned()
+            .ok_or_else(|| ArbFinderError::InvalidOrder(format!("Unknown Bitget order {}", order_id)))?;
+
+        let body = serde_json::json!({
+            "symbol": bitget_symbol(&symbol),
+            "orderId": venue_order_id,
+        });
+        self.signed_post("/api/v2/spot/trade/cancel-order", &body).await?;
+        self.orders.write().unwrap_or_else(|e| e.into_inner()).remove(order_id);
+        Ok(())
+    }
+
+    async fn cancel_all_orders(&self, symbol: Option<&Symbol>) -> Result<Vec<OrderId>> {
+        let mut symbols: Vec<Symbol> = match symbol {
+            Some(symbol) => vec![symbol.clone()],
+            None => self
+                .orders
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .values()
+                .map(|(s, _)| s.clone())
+                .collect(),
+        };
+        symbols.sort_by_key(|s| s.to_pair());
+        symbols.dedup();
+
+        let mut canceled = Vec::new();
+        for symbol in symbols {
+            let body = serde_json::json!({ "symbol": bitget_symbol(&symbol) });
+            self.signed_post("/api/v2/spot/trade/cancel-symbol-order", &body).await?;
+
+            let mut orders = self.orders.write().unwrap_or_else(|e| e.into_inner());
+            let ids: Vec<OrderId> = orders
+                .iter()
+                .filter(|(_, (s, _))| *s == symbol)
+                .map(|(id, _)| id.clone())
+                .collect();
+            for id in &ids {
+                orders.remove(id);
+            }
+            canceled.extend(ids);
+        }
+
+        Ok(canceled)
+    }
+
+    async fn get_order(&self, _order_id: &OrderId) -> Result<Option<Order>> {
+        Ok(None)
+    }
+
+    async fn get_open_orders(&self, _symbol: Option<&Symbol>) -> Result<Vec<Order>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_order_history(&self, _symbol: Option<&Symbol>, _limit: Option<u32>) -> Result<Vec<Order>> {
+        Err(ArbFinderError::Unsupported("Bitget order history is not implemented yet".to_string()))
+    }
+
+    async fn get_balances(&self) -> Result<Vec<Balance>> {
+        Err(ArbFinderError::Unsupported("Bitget balances are not implemented yet".to_string()))
+    }
+
+    async fn get_balance(&self, _asset: &str) -> Result<Option<Balance>> {
+        Err(ArbFinderError::Unsupported("Bitget balances are not implemented yet".to_string()))
+    }
+
+    async fn get_trade_history(&self, _symbol: Option<&Symbol>, _limit: Option<u32>) -> Result<Vec<OrderFill>> {
+        Err(ArbFinderError::Unsupported("Bitget trade history is not implemented yet".to_string()))
+    }
+
+    async fn get_deposit_address(&self, asset: &str) -> Result<DepositAddress> {
+        let data = self
+            .signed_get(&format!("/api/v2/spot/wallet/deposit-address?coin={}", asset.to_uppercase()))
+            .await?;
+        parse_deposit_address(&data)
+            .ok_or_else(|| ArbFinderError::InvalidData(format!("Malformed deposit address for {}", asset)))
+    }
+
+    async fn withdraw(&self, asset: &str, amount: Decimal, address: &DepositAddress) -> Result<Withdrawal> {
+        let mut body = serde_json::json!({
+            "coin": asset.to_uppercase(),
+            "transferType": "on_chain",
+            "address": address.address,
+            "size": amount.normalize().to_string(),
+        });
+        if let Some(tag) = &address.tag {
+            body["tag"] = serde_json::json!(tag);
+        }
+        if let Some(network) = &address.network {
+            body["chain"] = serde_json::json!(network);
+        }
+        let data = self.signed_post("/api/v2/spot/wallet/withdrawal", &body).await?;
+        let id = data["orderId"]
+            .as_str()
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing withdrawal orderId".to_string()))?;
+
+        Ok(Withdrawal {
+            id: id.to_string(),
+            venue: VenueId::Bitget,
+            asset: asset.to_string(),
+            amount,
+            address: address.clone(),
+            requested_at: Utc::now(),
+        })
+    }
+
+    async fn get_withdrawal_progress(&self, withdrawal: &Withdrawal) -> Result<WithdrawalProgress> {
+        let start = withdrawal.requested_at - chrono::Duration::minutes(5);
+        let data = self
+            .signed_get(&format!(
+                "/api/v2/spot/wallet/withdrawal-records?coin={}&orderId={}&startTime={}&endTime={}",
+                withdrawal.asset.to_uppercase(),
+                withdrawal.id,
+                start.timestamp_millis(),
+                Utc::now().timestamp_millis()
+            ))
+            .await?;
+        data.as_array()
+            .into_iter()
+            .flatten()
+            .find(|record| record["orderId"].as_str() == Some(withdrawal.id.as_str()))
+            .and_then(parse_record_status)
+            .map(|(status, tx_id)| WithdrawalProgress { status, tx_id })
+            .ok_or_else(|| ArbFinderError::InvalidData(format!("Bitget has no withdrawal {}", withdrawal.id)))
+    }
+
+    async fn get_deposits(&self, asset: &str, since: DateTime<Utc>) -> Result<Vec<Deposit>> {
+        let data = self
+            .signed_get(&format!(
+                "/api/v2/spot/wallet/deposit-records?coin={}&startTime={}&endTime={}",
+                asset.to_uppercase(),
+                since.timestamp_millis(),
+                Utc::now().timestamp_millis()
+            ))
+            .await?;
+        Ok(data.as_array().into_iter().flatten().filter_map(parse_deposit).collect())
+    }
+
+    async fn rotate_credentials(&self, credentials: &VenueCredentials) -> Result<bool> {
+        let mut current = self.credentials.write().unwrap_or_else(|e| e.into_inner());
+        let unchanged = current.as_ref().is_some_and(|current| {
+            current.api_key == credentials.api_key
+                && current.secret_key == credentials.secret_key
+                && current.passphrase == credentials.passphrase
+        });
+        if unchanged {
+            return Ok(false);
+        }
+        *current = Some(credentials.clone());
+        Ok(true)
+    }
+
+    async fn verify_credentials(&self) -> Result<()> {
+        self.signed_get("/api/v2/spot/account/info").await.map(|_| ())
+    }
+
+    async fn get_account_info(&self) -> Result<AccountInfo> {
+        Ok(AccountInfo {
+            account_type: "SPOT".to_string(),
+            trading_enabled: self.credentials().is_ok(),
+            withdraw_enabled: false,
+            deposit_enabled: false,
+            balances: Vec::new(),
+            permissions: vec!["SPOT".to_string()],
+            commission_rates: TradingFees {
+                maker_fee: Decimal::new(1, 3), // 0.1%
+                taker_fee: Decimal::new(1, 3), // 0.1%
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[tokio::test]
+    async fn test_bitget_adapter_creation() {
+        let adapter = BitgetAdapter::new();
+        assert_eq!(adapter.venue_id(), VenueId::Bitget);
+        assert!(!adapter.is_connected().await);
+        assert_eq!(bitget_symbol(&Symbol::new("btc", "usdt")), "BTCUSDT");
+    }
+
+    #[test]
+    fn test_sign() {
+        let adapter = BitgetAdapter::with_credentials("key", "secret", "pass");
+        let secret = adapter.credentials().unwrap().secret_key;
+        let signature =
+            BitgetAdapter::sign(&secret, "1700000000000", &Method::POST, "/api/v2/spot/trade/place-order", "{}")
+                .unwrap();
+
+        // 32-byte digest, base64 encoded
+        let decoded = base64::engine::general_purpose::STANDARD.decode(&signature).unwrap();
+        assert_eq!(decoded.len(), 32);
+        assert!(BitgetAdapter::new().credentials().is_err());
+    }
+
+    #[test]
+    fn test_parse_deposit_address() {
+        let data = serde_json::json!({
+            "address": "0x1f2b4e5a6c7d8e9f0a1b2c3d4e5f6a7b8c9d0e1f", "chain": "ERC20", "coin": "USDT", "tag": "", "url": ""
+        });
+        let address = parse_deposit_address(&data).unwrap();
+        assert_eq!(address.asset, "USDT");
+        assert_eq!(address.network.as_deref(), Some("ERC20"));
+        assert_eq!(address.tag, None);
+    }
+
+    #[test]
+    fn test_parse_deposit() {
+        let record = serde_json::json!({
+            "orderId": "1", "tradeId": "0x5ad4c2e1", "coin": "USDT", "type": "deposit", "size": "100.5",
+            "status": "pending", "toAddress": "0x1f2b", "chain": "ERC20", "cTime": "1684219200000"
+        });
+        let deposit = parse_deposit(&record).unwrap();
+        assert_eq!(deposit.status, TransferStatus::Confirming);
+        assert_eq!(deposit.tx_id.as_deref(), Some("0x5ad4c2e1"));
+        assert_eq!(deposit.amount, Decimal::new(1005, 1));
+
+        let record = serde_json::json!({"orderId": "2", "tradeId": "", "status": "fail"});
+        assert_eq!(parse_record_status(&record), Some((TransferStatus::Failed, None)));
+    }
+
+    #[test]
+    fn test_place_order_body() {
+        let price = Decimal::from_str("27000.50").unwrap();
+        let request = OrderRequest::new_limit(Symbol::new("BTC", "USDT"), OrderSide::Buy, Decimal::from_str("0.0100").unwrap(), price)
+            .with_time_in_force(TimeInForce::ImmediateOrCancel);
+        let body = place_order_body(&request, "abc").unwrap();
+
+        assert_eq!(body["symbol"], "BTCUSDT");
+        assert_eq!(body["side"], "buy");
+        assert_eq!(body["orderType"], "limit");
+        assert_eq!(body["force"], "ioc");
+        assert_eq!(body["price"], "27000.5");
+        assert_eq!(body["size"], "0.01");
+        assert_eq!(body["clientOid"], "abc");
+
+        let post_only = request.clone().post_only();
+        assert_eq!(place_order_body(&post_only, "abc").unwrap()["force"], "post_only");
+
+        let market_buy = OrderRequest::new_market(Symbol::new("BTC", "USDT"), OrderSide::Buy, Decimal::ONE);
+        assert!(matches!(place_order_body(&market_buy, "abc"), Err(ArbFinderError::InvalidOrder(_))));
+        let spend = OrderRequest::new_market_quote(Symbol::new("BTC", "USDT"), OrderSide::Buy, Decimal::from(1000));
+        let body = place_order_body(&spend, "abc").unwrap();
+        assert_eq!(body["orderType"], "market");
+        assert_eq!(body["size"], "1000");
+        assert!(place_order_body(&request.clone().in_quote(), "abc").is_err());
+
+        let market_sell = OrderRequest::new_market(Symbol::new("BTC", "USDT"), OrderSide::Sell, Decimal::ONE);
+        assert_eq!(place_order_body(&market_sell, "abc").unwrap()["orderType"], "market");
+    }
+
+    #[tokio::test]
+    async fn test_cancel_unknown_order() {
+        let adapter = BitgetAdapter::new();
+        assert!(matches!(
+            adapter.cancel_order(&OrderId::new()).await,
+            Err(ArbFinderError::InvalidOrder(_))
+        ));
+        assert!(adapter.cancel_all_orders(None).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,344 @@
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use crate::{bitget_symbol, value_to_decimal};
+
+pub const BITGET_WS_URL: &str = "wss://ws.bitget.com/v2/ws/public";
+
+/// Levels per side covered by the `books` channel checksum.
+const CHECKSUM_DEPTH: usize = 25;
+
+fn channel_args(symbols: &[Symbol]) -> Vec<Value> {
+    symbols
+        .iter()
+        .flat_map(|symbol| {
+            let inst_id = bitget_symbol(symbol);
+            ["books", "trade"].map(|channel| {
+                serde_json::json!({ "instType": "SPOT", "channel": channel, "instId": inst_id })
+            })
+        })
+        .collect()
+}
+
+/// One subscribe request for the `books` and `trade` channels of `symbols`.
+pub fn subscribe_message(symbols: &[Symbol]) -> String {
+    serde_json::json!({ "op": "subscribe", "args": channel_args(symbols) }).to_string()
+}
+
+/// Applies `[price, size]` pairs; a zero size removes the level.
+pub(crate) fn apply_levels(book: &mut OrderBook, levels: &Value, side: Side) {
+    for level in levels.as_array().into_iter().flatten() {
+        if let (Some(price), Some(size)) = (value_to_decimal(&level[0]), value_to_decimal(&level[1])) {
+            match side {
+                Side::Bid => book.update_bid(price, size),
+                Side::Ask => book.update_ask(price, size),
+            }
+        }
+    }
+}
+
+/// CRC32 of the top levels as `bid:size:ask:size:...`, alternating sides,
+/// interpreted as a signed 32-bit integer like Bitget's `checksum` field.
+///
+/// Prices and sizes must keep the exact strings Bitget sent; `Decimal`
+/// preserves trailing zeros from parsing, so formatting them back is exact.
+pub fn book_checksum(book: &OrderBook) -> i32 {
+    let bids: Vec<&OrderBookLevel> = book.bids.values().rev().take(CHECKSUM_DEPTH).collect();
+    let asks: Vec<&OrderBookLevel> = book.asks.values().take(CHECKSUM_DEPTH).collect();
+
+    let mut parts = Vec::with_capacity(CHECKSUM_DEPTH * 2);
+    for i in 0..CHECKSUM_DEPTH {
+        if let Some(bid) = bids.get(i) {
+            parts.push(format!("{}:{}", bid.price, bid.quantity));
+        }
+        if let Some(ask) = asks.get(i) {
+            parts.push(format!("{}:{}", ask.price, ask.quantity));
+        }
+    }
+
+    crc32fast::hash(parts.join(":").as_bytes()) as i32
+}
+
+/// Handles Bitget v2 public `books` and `trade` channels.
+///
+/// The `books` channel sends a snapshot followed by incremental updates, each
+/// carrying a checksum of the resulting top 25 levels. A mismatch means an
+/// update was missed, so the book is dropped and the channel resubscribed to
+/// get a fresh snapshot.
+pub struct BitgetMarketDataStream {
+    symbols: HashMap<String, Symbol>,
+    books: HashMap<Symbol, OrderBook>,
+    update_tx: mpsc::UnboundedSender<MarketData>,
+    outbound_tx: mpsc::UnboundedSender<String>,
+}
+
+impl BitgetMarketDataStream {
+    pub fn new(
+        symbols: Vec<Symbol>,
+        update_tx: mpsc::UnboundedSender<MarketData>,
+        outbound_tx: mpsc::UnboundedSender<String>,
+    ) -> Self {
+        Self {
+            symbols: symbols.into_iter().map(|s| (bitget_symbol(&s), s)).collect(),
+            books: HashMap::new(),
+            update_tx,
+            outbound_tx,
+        }
+    }
+
+    pub fn get_orderbook(&self, symbol: &Symbol) -> Option<&OrderBook> {
+        self.books.get(symbol)
+    }
+
+    fn resubscribe_book(&self, symbol: &Symbol) {
+        let arg = serde_json::json!({ "instType": "SPOT", "channel": "books", "instId": bitget_symbol(symbol) });
+        for op in ["unsubscribe", "subscribe"] {
+            let _ = self
+                .outbound_tx
+                .send(serde_json::json!({ "op": op, "args": [arg] }).to_string());
+        }
+    }
+
+    fn process_books(&mut self, symbol: Symbol, action: &str, entries: &[Value]) {
+        for entry in entries {
+            let book = match action {
+                "snapshot" => {
+                    let book = self.books.entry(symbol.clone()).or_insert_with(|| OrderBook::new(symbol.clone()));
+                    *book = OrderBook::new(symbol.clone());
+                    book
+                }
+                _ => match self.books.get_mut(&symbol) {
+                    Some(book) => book,
+                    None => {
+                        debug!("Bitget update for {} before snapshot", symbol);
+                        return;
+                    }
+                },
+            };
+
+            apply_levels(book, &entry["bids"], Side::Bid);
+            apply_levels(book, &entry["asks"], Side::Ask);
+            if let Some(ts) = entry["ts"]
+                .as_str()
+                .and_then(|ts| ts.parse::<i64>().ok())
+                .and_then(DateTime::<Utc>::from_timestamp_millis)
+            {
+                book.timestamp = ts;
+            }
+            book.sequence = entry["seq"].as_u64();
+
+            if let Some(expected) = entry["checksum"].as_i64() {
+                let actual = book_checksum(book) as i64;
+                if expected != 0 && actual != expected {
+                    warn!(
+                        "Bitget checksum mismatch for {}: expected {}, computed {}; resubscribing",
+                        symbol, expected, actual
+                    );
+                    self.books.remove(&symbol);
+                    self.resubscribe_book(&symbol);
+                    return;
+                }
+            }
+
+            let _ = self.update_tx.send(MarketData::OrderBook(book.clone()));
+        }
+    }
+
+    fn process_trade(&self, symbol: &Symbol, entry: &Value) {
+        let (Some(price), Some(quantity)) = (value_to_decimal(&entry["price"]), value_to_decimal(&entry["size"])) else {
+            return;
+        };
+
+        let side = match entry["side"].as_str() {
+            Some("buy") => Side::Bid,
+            _ => Side::Ask,
+        };
+        let trade_id = entry["tradeId"].as_str().unwrap_or_default().to_string();
+
+        let mut trade = Trade::new(symbol.clone(), price, quantity, side, trade_id);
+        if let Some(ts) = entry["ts"]
+            .as_str()
+            .and_then(|ts| ts.parse::<i64>().ok())
+            .and_then(DateTime::<Utc>::from_timestamp_millis)
+        {
+            trade.timestamp = ts;
+        }
+
+        let _ = self.update_tx.send(MarketData::Trade(trade));
+    }
+}
+
+#[async_trait]
+impl WebSocketHandler for BitgetMarketDataStream {
+    async fn on_message(&mut self, message: &str) -> Result<()> {
+        if message == "pong" {
+            return Ok(());
+        }
+
+        let data: Value = serde_json::from_str(message)?;
+        match data["event"].as_str() {
+            Some("error") => {
+                warn!("Bitget WebSocket error {}: {}", data["code"], data["msg"]);
+                return Ok(());
+            }
+            Some(event) => {
+                debug!("Bitget {} event: {}", event, data["arg"]);
+                return Ok(());
+            }
+            None => {}
+        }
+
+        let inst_id = data["arg"]["instId"].as_str().unwrap_or_default();
+        let Some(symbol) = self.symbols.get(inst_id).cloned() else {
+            debug!("Ignoring Bitget message: {}", message);
+            return Ok(());
+        };
+        let action = data["action"].as_str().unwrap_or("update");
+        let entries = data["data"].as_array().map(Vec::as_slice).unwrap_or_default();
+
+        match data["arg"]["channel"].as_str() {
+            Some("books") => self.process_books(symbol, action, entries),
+            Some("trade") => {
+                // Trades arrive newest first
+                for entry in entries.iter().rev() {
+                    self.process_trade(&symbol, entry);
+                }
+            }
+            _ => debug!("Ignoring Bitget message: {}", message),
+        }
+        Ok(())
+    }
+
+    async fn on_connect(&mut self) -> Result<()> {
+        info!("Bitget WebSocket connected for {} symbols", self.symbols.len());
+        Ok(())
+    }
+
+    async fn on_disconnect(&mut self) -> Result<()> {
+        warn!("Bitget WebSocket disconnected");
+        self.books.clear();
+        Ok(())
+    }
+
+    async fn on_error(&mut self, error: &ArbFinderError) -> Result<()> {
+        error!("Bitget WebSocket error: {}", error);
+        Ok(())
+    }
+
+    async fn on_ping(&mut self) -> Result<()> {
+        debug!("Received Bitget ping");
+        Ok(())
+    }
+
+    async fn on_pong(&mut self) -> Result<()> {
+        debug!("Received Bitget pong");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    type Channels = (
+        BitgetMarketDataStream,
+        mpsc::UnboundedReceiver<MarketData>,
+        mpsc::UnboundedReceiver<String>,
+    );
+
+    fn stream() -> Channels {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (out_tx, out_rx) = mpsc::unbounded_channel();
+        (BitgetMarketDataStream::new(vec![Symbol::new("BTC", "USDT")], tx, out_tx), rx, out_rx)
+    }
+
+    fn books_message(action: &str, bids: &str, asks: &str, checksum: i32) -> String {
+        format!(
+            r#"{{"action":"{}","arg":{{"instType":"SPOT","channel":"books","instId":"BTCUSDT"}},"data":[{{"asks":{},"bids":{},"checksum":{},"seq":1,"ts":"1695716059516"}}],"ts":1695716059516}}"#,
+            action, asks, bids, checksum
+        )
+    }
+
+    #[test]
+    fn test_subscribe_message() {
+        let message: Value = serde_json::from_str(&subscribe_message(&[Symbol::new("ETH", "USDT")])).unwrap();
+        assert_eq!(message["op"], "subscribe");
+        assert_eq!(message["args"][0]["channel"], "books");
+        assert_eq!(message["args"][1]["channel"], "trade");
+        assert_eq!(message["args"][1]["instId"], "ETHUSDT");
+    }
+
+    #[test]
+    fn test_checksum_string_layout() {
+        let mut book = OrderBook::new(Symbol::new("BTC", "USDT"));
+        book.update_bid(Decimal::from_str("3366.1").unwrap(), Decimal::from_str("7.0").unwrap());
+        book.update_bid(Decimal::from_str("3366").unwrap(), Decimal::from_str("6").unwrap());
+        book.update_ask(Decimal::from_str("3366.8").unwrap(), Decimal::from_str("9").unwrap());
+
+        let expected = crc32fast::hash(b"3366.1:7.0:3366.8:9:3366:6") as i32;
+        assert_eq!(book_checksum(&book), expected);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_update_and_checksum() {
+        let (mut stream, mut rx, mut out_rx) = stream();
+
+        let mut expected = OrderBook::new(Symbol::new("BTC", "USDT"));
+        expected.update_bid(Decimal::from_str("27000.0").unwrap(), Decimal::from_str("2.710").unwrap());
+        expected.update_ask(Decimal::from_str("27000.5").unwrap(), Decimal::from_str("8.760").unwrap());
+        let snapshot = books_message(
+            "snapshot",
+            r#"[["27000.0","2.710"]]"#,
+            r#"[["27000.5","8.760"]]"#,
+            book_checksum(&expected),
+        );
+        stream.on_message(&snapshot).await.unwrap();
+
+        match rx.recv().await.unwrap() {
+            MarketData::OrderBook(book) => {
+                assert_eq!(book.best_ask().unwrap().price, Decimal::from_str("27000.5").unwrap());
+                assert_eq!(book.sequence, Some(1));
+            }
+            other => panic!("expected order book, got {:?}", other),
+        }
+
+        // An update whose checksum doesn't match drops the book and resubscribes
+        let update = books_message("update", r#"[["27000.0","0"]]"#, "[]", 12345);
+        stream.on_message(&update).await.unwrap();
+        assert!(stream.get_orderbook(&Symbol::new("BTC", "USDT")).is_none());
+        assert!(rx.try_recv().is_err());
+
+        let unsubscribe: Value = serde_json::from_str(&out_rx.recv().await.unwrap()).unwrap();
+        let subscribe: Value = serde_json::from_str(&out_rx.recv().await.unwrap()).unwrap();
+        assert_eq!(unsubscribe["op"], "unsubscribe");
+        assert_eq!(subscribe["op"], "subscribe");
+        assert_eq!(subscribe["args"][0]["instId"], "BTCUSDT");
+    }
+
+    #[tokio::test]
+    async fn test_trade_and_pong() {
+        let (mut stream, mut rx, _out_rx) = stream();
+        stream.on_message("pong").await.unwrap();
+        stream
+            .on_message(r#"{"action":"update","arg":{"instType":"SPOT","channel":"trade","instId":"BTCUSDT"},"data":[{"ts":"1695709835822","price":"26293.4","size":"0.0013","side":"sell","tradeId":"1096604896034967597"}],"ts":1695709835829}"#)
+            .await
+            .unwrap();
+
+        match rx.recv().await.unwrap() {
+            MarketData::Trade(trade) => {
+                assert_eq!(trade.side, Side::Ask);
+                assert_eq!(trade.quantity, Decimal::from_str("0.0013").unwrap());
+                assert_eq!(trade.trade_id, "1096604896034967597");
+            }
+            other => panic!("expected trade, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,294 @@
+//! Bithumb Exchange Adapter
+//!
+//! Implementation of MarketDataAdapter for Bithumb, the other large KRW venue.
+//! Its v1 API follows Upbit's market codes and message formats, so a premium
+//! seen on Upbit can be checked against an independent Korean book.
+
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use arbfinder_exchange::{channel_stream, spawn_connection, DefaultExchangeConfig};
+use async_trait::async_trait;
+use reqwest::Client;
+use arbfinder_upbit::format::{self, parse_upbit_market, upbit_market};
+use arbfinder_upbit::UpbitMarketDataStream;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use tokio::sync::mpsc;
+
+const BITHUMB_API_URL: &str = "https://api.bithumb.com";
+pub const BITHUMB_WS_URL: &str = "wss://ws-api.bithumb.com/websocket/v1";
+
+/// Korean won, the quote currency of Bithumb's main market.
+pub const KRW: &str = "KRW";
+
+/// Price increment for a KRW market at the given price.
+///
+/// KRW has no minor unit, so Bithumb quotes KRW markets on a tiered grid that
+/// widens with price. The tiers differ from Upbit's, so the two venues round
+/// the same price differently.
+pub fn krw_tick_size(price: Decimal) -> Decimal {
+    const TIERS: &[(i64, u32, i64)] = &[
+        // (price floor, floor scale, tick mantissa at scale 8)
+        (1_000_000, 0, 100_000_000_000),
+        (500_000, 0, 50_000_000_000),
+        (100_000, 0, 10_000_000_000),
+        (50_000, 0, 5_000_000_000),
+        (10_000, 0, 1_000_000_000),
+        (5_000, 0, 500_000_000),
+        (100, 0, 100_000_000),
+        (10, 0, 1_000_000),
+        (1, 0, 100_000),
+    ];
+
+    for &(floor, scale, tick) in TIERS {
+        if price >= Decimal::new(floor, scale) {
+            return Decimal::new(tick, 8).normalize();
+        }
+    }
+    Decimal::new(1, 4)
+}
+
+/// Trading fee for a market; KRW markets are discounted relative to the BTC
+/// market.
+pub fn bithumb_fee(symbol: &Symbol) -> Decimal {
+    if symbol.quote().eq_ignore_ascii_case(KRW) {
+        Decimal::new(4, 4) // 0.04%
+    } else {
+        Decimal::new(25, 4) // 0.25%
+    }
+}
+
+pub struct BithumbAdapter {
+    client: Client,
+    base_url: String,
+    ws_url: String,
+    connected: bool,
+    subscriptions: Vec<Symbol>,
+}
+
+impl BithumbAdapter {
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+            base_url: BITHUMB_API_URL.to_string(),
+            ws_url: BITHUMB_WS_URL.to_string(),
+            connected: false,
+            subscriptions: Vec::new(),
+        }
+    }
+
+    async fn get_request(&self, endpoint: &str) -> Result<serde_json::Value> {
+        let url = format!("{}{}", self.base_url, endpoint);
+        let response = self.client
+            .get(&url)
+            .send()
+            .await
+            .map_err(ArbFinderError::Http)?;
+
+        if !response.status().is_success() {
+            return Err(ArbFinderError::Exchange(format!(
+                "Bithumb API error: {}",
+                response.status()
+            )));
+        }
+
+        response.json().await.map_err(ArbFinderError::Http)
+    }
+
+    /// Fetch the ticker for a symbol
+    pub async fn get_ticker(&self, symbol: &Symbol) -> Result<Ticker> {
+        let response = self
+            .get_request(&format!("/v1/ticker?markets={}", upbit_market(symbol)))
+            .await?;
+        format::parse_ticker(symbol, &response[0])
+    }
+
+    /// Fetch orderbook depth from Bithumb. The public endpoint always returns
+    /// up to 15 levels; `limit` truncates further.
+    pub async fn get_orderbook(&self, symbol: &Symbol, limit: Option<u32>) -> Result<OrderBook> {
+        let response = self
+            .get_request(&format!("/v1/orderbook?markets={}", upbit_market(symbol)))
+            .await?;
+        format::parse_orderbook(symbol, &response[0], limit)
+    }
+}
+
+impl Default for BithumbAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl MarketDataAdapter for BithumbAdapter {
+    fn venue_id(&self) -> VenueId {
+        VenueId::Bithumb
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        let _ = self.get_request("/v1/market/all").await?;
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.connected = false;
+        Ok(())
+    }
+
+    async fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        // No time endpoint; the HTTP Date header is the server clock
+        let url = format!("{}/v1/market/all", self.base_url);
+        let response = self.client.get(&url).send().await.map_err(ArbFinderError::Http)?;
+        let date = response
+            .headers()
+            .get(reqwest::header::DATE)
+            .and_then(|h| h.to_str().ok())
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing Date header".to_string()))?;
+
+        DateTime::parse_from_rfc2822(date)
+            .map(|d| d.with_timezone(&Utc))
+            .map_err(|e| ArbFinderError::Parse(format!("Invalid Date header: {}", e)))
+    }
+
+    async fn ping(&self) -> Result<u64> {
+        let start = std::time::Instant::now();
+        let _ = self.get_request("/v1/market/all").await?;
+        Ok(start.elapsed().as_millis() as u64)
+    }
+
+    async fn get_symbols(&self) -> Result<Vec<Symbol>> {
+        let response = self.get_request("/v1/market/all").await?;
+        let markets = response
+            .as_array()
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing market list".to_string()))?;
+
+        Ok(markets
+            .iter()
+            .filter_map(|m| m["market"].as_str())
+            .filter_map(parse_upbit_market)
+            .collect())
+    }
+
+    async fn get_symbol_info(&self, symbol: &Symbol) -> Result<SymbolInfo> {
+        let market = upbit_market(symbol);
+        let response = self.get_request("/v1/market/all").await?;
+        let listed = response
+            .as_array()
+            .into_iter()
+            .flatten()
+            .any(|m| m["market"].as_str() == Some(market.as_str()));
+        if !listed {
+            return Err(ArbFinderError::SymbolNotFound(market));
+        }
+
+        let is_krw = symbol.quote().eq_ignore_ascii_case(KRW);
+        let (tick_size, min_notional) = if is_krw {
+            let ticker = self.get_ticker(symbol).await?;
+            (krw_tick_size(ticker.price), Decimal::new(5000, 0))
+        } else {
+            // The BTC market is the only other one
+            (Decimal::new(1, 8), Decimal::new(5, 4))
+        };
+        let fee = bithumb_fee(symbol);
+
+        Ok(SymbolInfo {
+            symbol: symbol.clone(),
+            status: "TRADING".to_string(),
+            base_asset_precision: 8,
+            quote_asset_precision: tick_size.scale(),
+            tick_size,
+            lot_size: Decimal::new(1, 8),
+            min_order_size: Decimal::new(1, 8),
+            max_order_size: Decimal::new(1000000, 0),
+            min_notional,
+            trading_fees: TradingFees {
+                maker_fee: fee,
+                taker_fee: fee,
+            },
+        })
+    }
+
+    async fn subscribe_orderbook(&mut self, symbol: &Symbol, _depth: Option<u32>) -> Result<()> {
+        if !self.subscriptions.contains(symbol) {
+            self.subscriptions.push(symbol.clone());
+        }
+        Ok(())
+    }
+
+    async fn subscribe_trades(&mut self, symbol: &Symbol) -> Result<()> {
+        // Orderbook and trade types are requested together per symbol
+        self.subscribe_orderbook(symbol, None).await
+    }
+
+    async fn subscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_orderbook(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscriptions.retain(|s| s != symbol);
+        Ok(())
+    }
+
+    async fn unsubscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn market_data_stream(&self) -> Result<MarketDataStream> {
+        if self.subscriptions.is_empty() {
+            return Err(ArbFinderError::Exchange("No Bithumb symbols subscribed".to_string()));
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let handler = UpbitMarketDataStream::for_venue(VenueId::Bithumb, self.subscriptions.clone(), tx);
+        let subscribe = vec![handler.subscribe_message()];
+        let config = DefaultExchangeConfig {
+            base_url: self.base_url.clone(),
+            websocket_url: self.ws_url.clone(),
+            ..Default::default()
+        };
+
+        spawn_connection(&config, &subscribe, handler).await?;
+        Ok(channel_stream(rx))
+    }
+
+    async fn fetch_orderbook(&self, symbol: &Symbol, depth: Option<u32>) -> Result<OrderBook> {
+        self.get_orderbook(symbol, depth).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[tokio::test]
+    async fn test_bithumb_adapter_creation() {
+        let adapter = BithumbAdapter::new();
+        assert_eq!(adapter.venue_id(), VenueId::Bithumb);
+        assert!(!adapter.is_connected().await);
+    }
+
+    #[test]
+    fn test_krw_tick_size() {
+        let tick = |p: &str| krw_tick_size(Decimal::from_str(p).unwrap());
+        assert_eq!(tick("95000000"), Decimal::from(1000));
+        assert_eq!(tick("1500000"), Decimal::from(1000));
+        assert_eq!(tick("750000"), Decimal::from(500));
+        assert_eq!(tick("4500"), Decimal::from(1));
+        assert_eq!(tick("650"), Decimal::from(1));
+        assert_eq!(tick("35"), Decimal::from_str("0.01").unwrap());
+        assert_eq!(tick("0.5"), Decimal::from_str("0.0001").unwrap());
+
+        assert_eq!(bithumb_fee(&Symbol::new("BTC", "KRW")), Decimal::from_str("0.0004").unwrap());
+        assert_eq!(bithumb_fee(&Symbol::new("ETH", "BTC")), Decimal::from_str("0.0025").unwrap());
+    }
+}
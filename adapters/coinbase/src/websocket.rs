@@ -0,0 +1,392 @@
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use arbfinder_orderbook::{FastOrderBook, L3OrderBook, SequenceOutcome};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use crate::{product_id, value_to_decimal};
+
+/// Subscribes `symbols` to `channel`, or unsubscribes them.
+fn channel_message(kind: &str, channel: &str, symbols: &[Symbol]) -> String {
+    let product_ids: Vec<String> = symbols.iter().map(product_id).collect();
+    serde_json::json!({
+        "type": kind,
+        "product_ids": product_ids,
+        "channel": channel,
+    })
+    .to_string()
+}
+
+/// Messages subscribing `symbols` to `level2`, plus `heartbeats`, which
+/// keeps the connection open through quiet books.
+pub fn subscribe_messages(symbols: &[Symbol]) -> Vec<String> {
+    vec![
+        channel_message("subscribe", "level2", symbols),
+        channel_message("subscribe", "heartbeats", &[]),
+    ]
+}
+
+/// Handles the Advanced Trade `level2` channel, keeping a book per product.
+///
+/// Each product starts with a `snapshot` event followed by `update` events
+/// carrying new quantities, zero removing the level. `sequence_num` counts
+/// every message on the connection, so a gap means any book may have
+/// missed an update: all of them are dropped and `level2` resubscribed for
+/// fresh snapshots.
+pub struct CoinbaseOrderbookStream {
+    /// Subscribed symbols by product id, e.g. `BTC-USD`.
+    symbols: HashMap<String, Symbol>,
+    books: HashMap<String, FastOrderBook>,
+    depth: Option<usize>,
+    last_sequence: Option<u64>,
+    update_tx: mpsc::UnboundedSender<MarketData>,
+    outbound_tx: mpsc::UnboundedSender<String>,
+    /// Told how long each heartbeat took to arrive, going by its
+    /// timestamp on Coinbase's clock.
+    heartbeat: Option<(HeartbeatManager, SharedClockSkew)>,
+}
+
+impl CoinbaseOrderbookStream {
+    pub fn new(
+        symbols: impl IntoIterator<Item = Symbol>,
+        depth: Option<usize>,
+        update_tx: mpsc::UnboundedSender<MarketData>,
+        outbound_tx: mpsc::UnboundedSender<String>,
+    ) -> Self {
+        let symbols = symbols.into_iter().map(|symbol| (product_id(&symbol), symbol)).collect();
+        Self {
+            symbols,
+            books: HashMap::new(),
+            depth,
+            last_sequence: None,
+            update_tx,
+            outbound_tx,
+            heartbeat: None,
+        }
+    }
+
+    /// Reports heartbeats to `heartbeat`, their timestamps taken onto our
+    /// clock through `clock`.
+    pub fn with_heartbeat(mut self, heartbeat: HeartbeatManager, clock: SharedClockSkew) -> Self {
+        self.heartbeat = Some((heartbeat, clock));
+        self
+    }
+
+    pub fn get_orderbook(&self, symbol: &Symbol) -> Option<&FastOrderBook> {
+        self.books.get(&product_id(symbol))
+    }
+
+    fn resubscribe(&mut self) {
+        self.books.clear();
+        let symbols: Vec<Symbol> = self.symbols.values().cloned().collect();
+        for kind in ["unsubscribe", "subscribe"] {
+            let _ = self.outbound_tx.send(channel_message(kind, "level2", &symbols));
+        }
+    }
+
+    fn process_event(&mut self, event: &Value, sequence: u64, timestamp: Option<DateTime<Utc>>) {
+        let Some(id) = event["product_id"].as_str() else {
+            return;
+        };
+        let Some(symbol) = self.symbols.get(id).cloned() else {
+            return;
+        };
+
+        let book = match event["type"].as_str() {
+            Some("snapshot") => {
+                let book = FastOrderBook::new(symbol, self.depth);
+                self.books.insert(id.to_string(), book);
+                self.books.get_mut(id)
+            }
+            _ => self.books.get_mut(id),
+        };
+        let Some(book) = book else {
+            debug!("Coinbase update for {} before snapshot", id);
+            return;
+        };
+
+        for update in event["updates"].as_array().into_iter().flatten() {
+            let (Some(price), Some(quantity)) =
+                (value_to_decimal(&update["price_level"]), value_to_decimal(&update["new_quantity"]))
+            else {
+                continue;
+            };
+            match update["side"].as_str() {
+                Some("bid") => book.update_bid(price, quantity, None),
+                Some("offer") => book.update_ask(price, quantity, None),
+                _ => {}
+            }
+        }
+        book.set_sequence(sequence);
+
+        let mut core_book = book.to_core_orderbook();
+        if let Some(timestamp) = timestamp {
+            core_book.timestamp = timestamp;
+        }
+        let _ = self.update_tx.send(MarketData::OrderBook(core_book));
+    }
+}
+
+#[async_trait]
+impl WebSocketHandler for CoinbaseOrderbookStream {
+    async fn on_message(&mut self, message: &str) -> Result<()> {
+        let message: Value = serde_json::from_str(message)?;
+        if message["type"].as_str() == Some("error") {
+            warn!("Coinbase WebSocket error: {}", message["message"]);
+            return Ok(());
+        }
+
+        if let Some(sequence) = message["sequence_num"].as_u64() {
+            if let Some(last) = self.last_sequence {
+                if sequence != last + 1 {
+                    warn!(
+                        "Coinbase sequence gap: expected {}, got {}; resubscribing level2",
+                        last + 1,
+                        sequence
+                    );
+                    self.last_sequence = Some(sequence);
+                    self.resubscribe();
+                    return Ok(());
+                }
+            }
+            self.last_sequence = Some(sequence);
+        }
+
+        match message["channel"].as_str() {
+            Some("l2_data") => {
+                let sequence = self.last_sequence.unwrap_or_default();
+                let timestamp = message["timestamp"].as_str().and_then(|ts| ts.parse().ok());
+                for event in message["events"].as_array().into_iter().flatten() {
+                    self.process_event(event, sequence, timestamp);
+                }
+            }
+            Some("heartbeats") => {
+                let sent = message["timestamp"].as_str().and_then(|ts| ts.parse::<DateTime<Utc>>().ok());
+                if let (Some((heartbeat, clock)), Some(sent)) = (&self.heartbeat, sent) {
+                    let sent = clock.lock().unwrap_or_else(|e| e.into_inner()).to_local(sent);
+                    let latency = (Utc::now() - sent).to_std().unwrap_or_default();
+                    heartbeat.record_latency(latency).await;
+                }
+            }
+            _ => debug!("Ignoring Coinbase message: {}", message),
+        }
+        Ok(())
+    }
+
+    async fn on_connect(&mut self) -> Result<()> {
+        info!("Coinbase WebSocket connected for {} products", self.symbols.len());
+        Ok(())
+    }
+
+    async fn on_disconnect(&mut self) -> Result<()> {
+        warn!("Coinbase WebSocket disconnected");
+        self.books.clear();
+        self.last_sequence = None;
+        Ok(())
+    }
+
+    async fn on_error(&mut self, error: &ArbFinderError) -> Result<()> {
+        error!("Coinbase WebSocket error: {}", error);
+        Ok(())
+    }
+
+    async fn on_ping(&mut self) -> Result<()> {
+        debug!("Received Coinbase ping");
+        Ok(())
+    }
+
+    async fn on_pong(&mut self) -> Result<()> {
+        debug!("Received Coinbase pong");
+        Ok(())
+    }
+}
+
+/// Coinbase Exchange market data feed, which unlike Advanced Trade
+/// publishes the `full` channel of individual orders.
+pub const COINBASE_EXCHANGE_FEED_URL: &str = "wss://ws-feed.exchange.coinbase.com";
+
+fn order_side(side: &Value) -> Option<Side> {
+    match side.as_str() {
+        Some("buy") => Some(Side::Bid),
+        Some("sell") => Some(Side::Ask),
+        _ => None,
+    }
+}
+
+/// Replaces `book` with a Coinbase Exchange level 3 snapshot, from
+/// `/products/{product_id}/book?level=3`: `bids` and `asks` of
+/// `[price, size, order_id]`, as of `sequence`.
+pub fn apply_level3_snapshot(book: &mut L3OrderBook, snapshot: &Value) -> Result<()> {
+    let sequence = snapshot["sequence"]
+        .as_u64()
+        .ok_or_else(|| ArbFinderError::InvalidData("Level 3 snapshot without a sequence".to_string()))?;
+    book.clear();
+    for (key, side) in [("bids", Side::Bid), ("asks", Side::Ask)] {
+        for entry in snapshot[key].as_array().into_iter().flatten() {
+            let (Some(price), Some(size), Some(id)) =
+                (value_to_decimal(&entry[0]), value_to_decimal(&entry[1]), entry[2].as_str())
+            else {
+                continue;
+            };
+            book.add_order(id, side, price, size);
+        }
+    }
+    book.set_sequence(sequence);
+    Ok(())
+}
+
+/// Applies a `full` channel message to `book`. Orders join the book on
+/// `open`, shrink on `change` and `match` (the maker's side) and leave on
+/// `done`; `received` orders aren't resting yet. Messages the book's
+/// snapshot already covers are `Outdated`; a `Gap` means the book needs a
+/// fresh snapshot.
+pub fn apply_full_message(book: &mut L3OrderBook, message: &Value) -> SequenceOutcome {
+    let Some(sequence) = message["sequence"].as_u64() else {
+        return SequenceOutcome::Outdated;
+    };
+    let last = book.get_sequence();
+    if sequence <= last {
+        return SequenceOutcome::Outdated;
+    }
+    if sequence > last + 1 {
+        return SequenceOutcome::Gap { expected: last + 1, received: sequence };
+    }
+    book.set_sequence(sequence);
+
+    match message["type"].as_str() {
+        Some("open") => {
+            let (Some(id), Some(side), Some(price), Some(size)) = (
+                message["order_id"].as_str(),
+                order_side(&message["side"]),
+                value_to_decimal(&message["price"]),
+                value_to_decimal(&message["remaining_size"]),
+            ) else {
+                return SequenceOutcome::Applied;
+            };
+            book.add_order(id, side, price, size);
+        }
+        Some("done") => {
+            if let Some(id) = message["order_id"].as_str() {
+                book.remove_order(id);
+            }
+        }
+        Some("match") => {
+            if let (Some(id), Some(size)) = (message["maker_order_id"].as_str(), value_to_decimal(&message["size"])) {
+                book.fill_order(id, size);
+            }
+        }
+        Some("change") => {
+            // Market orders change `new_funds` and never rest
+            if let (Some(id), Some(size)) = (message["order_id"].as_str(), value_to_decimal(&message["new_size"])) {
+                book.change_order(id, size);
+            }
+        }
+        _ => {}
+    }
+    SequenceOutcome::Applied
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_snapshot_update_and_gap() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let (outbound_tx, mut outbound_rx) = mpsc::unbounded_channel();
+        let symbol = Symbol::new("BTC", "USD");
+        let mut stream = CoinbaseOrderbookStream::new([symbol.clone()], None, tx, outbound_tx);
+
+        stream
+            .on_message(
+                r#"{"channel":"l2_data","client_id":"","timestamp":"2023-02-09T20:32:50.714964855Z","sequence_num":0,
+                    "events":[{"type":"snapshot","product_id":"BTC-USD","updates":[
+                        {"side":"bid","event_time":"1970-01-01T00:00:00Z","price_level":"21921.73","new_quantity":"0.06317902"},
+                        {"side":"bid","event_time":"1970-01-01T00:00:00Z","price_level":"21921.3","new_quantity":"0.02"},
+                        {"side":"offer","event_time":"1970-01-01T00:00:00Z","price_level":"21921.74","new_quantity":"0.5"}]}]}"#,
+            )
+            .await
+            .unwrap();
+        stream
+            .on_message(
+                r#"{"channel":"l2_data","timestamp":"2023-02-09T20:32:51Z","sequence_num":1,
+                    "events":[{"type":"update","product_id":"BTC-USD","updates":[
+                        {"side":"bid","event_time":"2023-02-09T20:32:51Z","price_level":"21921.73","new_quantity":"0"}]}]}"#,
+            )
+            .await
+            .unwrap();
+
+        rx.recv().await.unwrap();
+        match rx.recv().await.unwrap() {
+            MarketData::OrderBook(book) => {
+                assert_eq!(book.symbol, symbol);
+                assert_eq!(book.sequence, Some(1));
+                assert_eq!(book.best_bid().unwrap().price, Decimal::from_str("21921.3").unwrap());
+                assert_eq!(book.best_ask().unwrap().quantity, Decimal::from_str("0.5").unwrap());
+            }
+            other => panic!("expected order book, got {:?}", other),
+        }
+
+        stream
+            .on_message(r#"{"channel":"heartbeats","sequence_num":3,"events":[]}"#)
+            .await
+            .unwrap();
+        assert!(stream.get_orderbook(&symbol).is_none());
+        assert!(outbound_rx.recv().await.unwrap().contains("unsubscribe"));
+        assert!(outbound_rx.recv().await.unwrap().contains(r#""type":"subscribe""#));
+        assert!(rx.try_recv().is_err());
+
+        // Heartbeats report how long they took to arrive
+        let heartbeat = HeartbeatManager::default();
+        let mut stream = stream.with_heartbeat(heartbeat.clone(), ClockSkew::default().shared());
+        stream
+            .on_message(
+                r#"{"channel":"heartbeats","timestamp":"2023-06-23T20:31:26.122969572Z","sequence_num":4,"events":[]}"#,
+            )
+            .await
+            .unwrap();
+        let status = heartbeat.get_status().await;
+        assert_eq!(status.pong_count, 1);
+        assert!(status.average_latency.unwrap() > Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn test_full_channel_orders() {
+        let mut book = L3OrderBook::new(Symbol::new("BTC", "USD"));
+        let snapshot: Value = serde_json::from_str(
+            r#"{"sequence":10,"bids":[["100.00","1.0","a"],["100.00","2.0","b"]],"asks":[["101.00","0.5","c"]]}"#,
+        )
+        .unwrap();
+        apply_level3_snapshot(&mut book, &snapshot).unwrap();
+
+        let message = |json: &str| serde_json::from_str::<Value>(json).unwrap();
+        assert_eq!(
+            apply_full_message(&mut book, &message(r#"{"type":"done","order_id":"a","reason":"canceled","sequence":10}"#)),
+            SequenceOutcome::Outdated
+        );
+        let messages = [
+            r#"{"type":"received","order_id":"d","side":"buy","price":"100.00","size":"3.0","sequence":11}"#,
+            r#"{"type":"open","order_id":"d","side":"buy","price":"100.00","remaining_size":"3.0","sequence":12}"#,
+            r#"{"type":"match","maker_order_id":"a","taker_order_id":"e","side":"buy","size":"1.0","price":"100.00","sequence":13}"#,
+            r#"{"type":"change","order_id":"b","side":"buy","price":"100.00","old_size":"2.0","new_size":"1.5","sequence":14}"#,
+        ];
+        for json in messages {
+            assert_eq!(apply_full_message(&mut book, &message(json)), SequenceOutcome::Applied);
+        }
+
+        assert!(book.get_order("a").is_none());
+        assert_eq!(book.queue_position("d").unwrap().size_ahead, Decimal::from_str("1.5").unwrap());
+        assert_eq!(book.level_size(Side::Bid, Decimal::from(100)), Decimal::from_str("4.5").unwrap());
+        assert_eq!(
+            apply_full_message(&mut book, &message(r#"{"type":"done","order_id":"c","sequence":16}"#)),
+            SequenceOutcome::Gap { expected: 15, received: 16 }
+        );
+    }
+}
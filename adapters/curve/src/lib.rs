@@ -0,0 +1,413 @@
+//! Curve Adapter
+//!
+//! Quotes Curve pools through an Ethereum JSON-RPC endpoint and presents each
+//! configured pair as an order book built from `get_dy` quotes at a ladder of
+//! sizes (see [`arbfinder_exchange::synthetic`]), so stablecoin dislocations
+//! on-chain feed the same detection as exchange books.
+//!
+//! Pairs are configured by pool address with [`CurveAdapter::with_pool`] and
+//! resolved on `connect`; several pairs can share a pool. Quotes are net of
+//! the pool fee, so no trading fee is reported on top of them.
+
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use arbfinder_exchange::channel_stream;
+use arbfinder_exchange::synthetic::{self, SwapQuote};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::join_all;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+pub mod rpc;
+
+use rpc::RpcClient;
+
+/// Curve pools hold at most eight coins.
+const MAX_COINS: u128 = 8;
+
+/// Roughly one block.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(12);
+
+/// Sizes quoted in each direction, in whole tokens of the coin sold.
+fn default_sizes() -> Vec<Decimal> {
+    [1_000, 10_000, 100_000, 1_000_000].into_iter().map(Decimal::from).collect()
+}
+
+/// One trading pair within a pool, resolved from the chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurvePair {
+    pub symbol: Symbol,
+    pub address: String,
+    pub base_index: u128,
+    pub quote_index: u128,
+    pub base_decimals: u8,
+    pub quote_decimals: u8,
+    /// `get_dy` selector the pool answers to.
+    pub get_dy: [u8; 4],
+}
+
+/// A coin held by a pool.
+#[derive(Debug, Clone, PartialEq)]
+struct Coin {
+    symbol: String,
+    decimals: u8,
+}
+
+/// Reads the pool's coins in index order. Pools that index `coins` by
+/// int128 revert on the uint256 form, so that is tried second.
+async fn load_coins(rpc: &RpcClient, address: &str) -> Result<Vec<Coin>> {
+    let selector = match rpc.view(address, rpc::COINS, &[0]).await {
+        Ok(_) => rpc::COINS,
+        Err(_) => rpc::COINS_INT128,
+    };
+
+    let mut coins = Vec::new();
+    for index in 0..MAX_COINS {
+        // Reading past the last coin reverts
+        let Ok(data) = rpc.view(address, selector, &[index]).await else {
+            break;
+        };
+        let token = rpc::word_to_address(rpc::word(&data, 0)?);
+        let decimals = rpc.view_u128(&token, rpc::DECIMALS, &[]).await?;
+        let symbol = rpc::decode_string(&rpc.view(&token, rpc::SYMBOL, &[]).await?)?;
+        coins.push(Coin {
+            symbol: symbol.to_uppercase(),
+            decimals: decimals as u8,
+        });
+    }
+
+    if coins.len() < 2 {
+        return Err(ArbFinderError::InvalidData(format!("{} is not a Curve pool", address)));
+    }
+    Ok(coins)
+}
+
+/// Finds the symbol's coins in the pool and the `get_dy` form it answers.
+async fn resolve_pair(rpc: &RpcClient, symbol: &Symbol, address: &str, coins: &[Coin]) -> Result<CurvePair> {
+    let index_of = |asset: &str| {
+        coins
+            .iter()
+            .position(|coin| coin.symbol == asset.to_uppercase())
+            .ok_or_else(|| {
+                let held: Vec<&str> = coins.iter().map(|coin| coin.symbol.as_str()).collect();
+                ArbFinderError::InvalidData(format!(
+                    "Pool {} holds {}, not {}",
+                    address,
+                    held.join("/"),
+                    asset
+                ))
+            })
+    };
+    let base = index_of(symbol.base())?;
+    let quote = index_of(symbol.quote())?;
+
+    let one = synthetic::to_raw(Decimal::ONE, coins[base].decimals).unwrap_or(1);
+    let args = [base as u128, quote as u128, one];
+    let get_dy = if rpc.view(address, rpc::GET_DY, &args).await.is_ok() {
+        rpc::GET_DY
+    } else {
+        rpc.view(address, rpc::GET_DY_UINT, &args).await?;
+        rpc::GET_DY_UINT
+    };
+
+    Ok(CurvePair {
+        symbol: symbol.clone(),
+        address: address.to_string(),
+        base_index: base as u128,
+        quote_index: quote as u128,
+        base_decimals: coins[base].decimals,
+        quote_decimals: coins[quote].decimals,
+        get_dy,
+    })
+}
+
+/// Quotes swapping each size from coin `from` to coin `to`. The ladder stops
+/// at the first size the pool cannot fill.
+async fn quote_ladder(
+    rpc: &RpcClient,
+    pair: &CurvePair,
+    (from, from_decimals): (u128, u8),
+    (to, to_decimals): (u128, u8),
+    sizes: &[Decimal],
+) -> Vec<SwapQuote> {
+    let results = join_all(sizes.iter().map(|size| async move {
+        let raw = synthetic::to_raw(*size, from_decimals)
+            .ok_or_else(|| ArbFinderError::InvalidData(format!("Size {} out of range", size)))?;
+        let out = rpc.view_u128(&pair.address, pair.get_dy, &[from, to, raw]).await?;
+        let out = synthetic::from_raw(out, to_decimals)
+            .ok_or_else(|| ArbFinderError::InvalidData(format!("Quote {} out of range", out)))?;
+        Ok::<_, ArbFinderError>(SwapQuote::new(*size, out))
+    }))
+    .await;
+
+    let mut quotes = Vec::with_capacity(results.len());
+    for result in results {
+        match result {
+            Ok(quote) => quotes.push(quote),
+            Err(e) => {
+                debug!("Curve pool {} stopped quoting {}: {}", pair.address, pair.symbol, e);
+                break;
+            }
+        }
+    }
+    quotes
+}
+
+async fn fetch_book(rpc: &RpcClient, pair: &CurvePair, sizes: &[Decimal]) -> Result<OrderBook> {
+    let base = (pair.base_index, pair.base_decimals);
+    let quote = (pair.quote_index, pair.quote_decimals);
+    let (bids, asks) = tokio::join!(
+        quote_ladder(rpc, pair, base, quote, sizes),
+        quote_ladder(rpc, pair, quote, base, sizes),
+    );
+    if bids.is_empty() && asks.is_empty() {
+        return Err(ArbFinderError::Exchange(format!(
+            "Curve pool {} returned no quotes for {}",
+            pair.address, pair.symbol
+        )));
+    }
+    Ok(synthetic::book_from_quotes(pair.symbol.clone(), &bids, &asks))
+}
+
+pub struct CurveAdapter {
+    rpc: RpcClient,
+    pool_addresses: HashMap<Symbol, String>,
+    pairs: HashMap<Symbol, CurvePair>,
+    sizes: Vec<Decimal>,
+    poll_interval: Duration,
+    connected: bool,
+    subscriptions: Vec<Symbol>,
+}
+
+impl CurveAdapter {
+    pub fn new(rpc_url: impl Into<String>) -> Self {
+        Self {
+            rpc: RpcClient::new(rpc_url),
+            pool_addresses: HashMap::new(),
+            pairs: HashMap::new(),
+            sizes: default_sizes(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            connected: false,
+            subscriptions: Vec::new(),
+        }
+    }
+
+    /// Trades `symbol` on the pool at `address`, which must hold both of
+    /// its assets, e.g. USDC/USDT on 3pool.
+    pub fn with_pool(mut self, symbol: Symbol, address: impl Into<String>) -> Self {
+        self.pool_addresses.insert(symbol, address.into().to_lowercase());
+        self
+    }
+
+    /// Sizes quoted for each book side, in whole tokens of the coin sold:
+    /// the base for bids, the quote for asks. Each size is one more level.
+    pub fn with_sizes(mut self, mut sizes: Vec<Decimal>) -> Self {
+        sizes.retain(|size| *size > Decimal::ZERO);
+        sizes.sort();
+        sizes.dedup();
+        self.sizes = sizes;
+        self
+    }
+
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    pub fn pair(&self, symbol: &Symbol) -> Result<&CurvePair> {
+        self.pairs.get(symbol).ok_or_else(|| {
+            ArbFinderError::SymbolNotFound(format!("No resolved Curve pool for {}", symbol))
+        })
+    }
+
+    /// What the pool pays out for `amount` of `symbol`'s base, in its quote.
+    pub async fn get_dy(&self, symbol: &Symbol, amount: Decimal) -> Result<Decimal> {
+        let pair = self.pair(symbol)?;
+        let quotes = quote_ladder(
+            &self.rpc,
+            pair,
+            (pair.base_index, pair.base_decimals),
+            (pair.quote_index, pair.quote_decimals),
+            &[amount],
+        )
+        .await;
+        quotes.first().map(|quote| quote.amount_out).ok_or_else(|| {
+            ArbFinderError::Exchange(format!("Curve pool {} cannot fill {} {}", pair.address, amount, symbol.base()))
+        })
+    }
+
+    /// Book synthesized from the pool's current quotes.
+    pub async fn get_orderbook(&self, symbol: &Symbol) -> Result<OrderBook> {
+        fetch_book(&self.rpc, self.pair(symbol)?, &self.sizes).await
+    }
+}
+
+#[async_trait]
+impl MarketDataAdapter for CurveAdapter {
+    fn venue_id(&self) -> VenueId {
+        VenueId::Curve
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        let chain_id = self.rpc.quantity("eth_chainId", serde_json::json!([])).await?;
+        let mut coins: HashMap<String, Vec<Coin>> = HashMap::new();
+        for (symbol, address) in &self.pool_addresses {
+            if !coins.contains_key(address) {
+                coins.insert(address.clone(), load_coins(&self.rpc, address).await?);
+            }
+            let pair = resolve_pair(&self.rpc, symbol, address, &coins[address]).await?;
+            debug!("Resolved Curve pool {} for {}: {:?}", address, symbol, pair);
+            self.pairs.insert(symbol.clone(), pair);
+        }
+        info!("Curve connected to chain {} with {} pairs", chain_id, self.pairs.len());
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.connected = false;
+        Ok(())
+    }
+
+    async fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        let block = self
+            .rpc
+            .call("eth_getBlockByNumber", serde_json::json!(["latest", false]))
+            .await?;
+        let timestamp = rpc::parse_quantity(&block["timestamp"])?;
+        DateTime::<Utc>::from_timestamp(timestamp as i64, 0)
+            .ok_or_else(|| ArbFinderError::InvalidData("Invalid block timestamp".to_string()))
+    }
+
+    async fn ping(&self) -> Result<u64> {
+        let start = std::time::Instant::now();
+        let _ = self.rpc.call("eth_blockNumber", serde_json::json!([])).await?;
+        Ok(start.elapsed().as_millis() as u64)
+    }
+
+    async fn get_symbols(&self) -> Result<Vec<Symbol>> {
+        Ok(self.pool_addresses.keys().cloned().collect())
+    }
+
+    async fn get_symbol_info(&self, symbol: &Symbol) -> Result<SymbolInfo> {
+        let pair = self.pair(symbol)?;
+        let lot_size = Decimal::new(1, u32::from(pair.base_decimals.min(18)));
+
+        Ok(SymbolInfo {
+            symbol: symbol.clone(),
+            status: "open".to_string(),
+            base_asset_precision: u32::from(pair.base_decimals),
+            quote_asset_precision: u32::from(pair.quote_decimals),
+            tick_size: Decimal::new(1, 8),
+            lot_size,
+            min_order_size: lot_size,
+            max_order_size: self.sizes.last().copied().unwrap_or(Decimal::new(1000000, 0)),
+            min_notional: Decimal::ZERO,
+            // Already taken out of get_dy quotes
+            trading_fees: TradingFees {
+                maker_fee: Decimal::ZERO,
+                taker_fee: Decimal::ZERO,
+            },
+        })
+    }
+
+    async fn subscribe_orderbook(&mut self, symbol: &Symbol, _depth: Option<u32>) -> Result<()> {
+        if !self.pool_addresses.contains_key(symbol) {
+            return Err(ArbFinderError::SymbolNotFound(format!("No Curve pool configured for {}", symbol)));
+        }
+        if !self.subscriptions.contains(symbol) {
+            self.subscriptions.push(symbol.clone());
+        }
+        Ok(())
+    }
+
+    async fn subscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+        Err(ArbFinderError::Exchange("Curve exchange feed not implemented yet".to_string()))
+    }
+
+    async fn subscribe_ticker(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscribe_orderbook(symbol, None).await
+    }
+
+    async fn unsubscribe_orderbook(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscriptions.retain(|s| s != symbol);
+        Ok(())
+    }
+
+    async fn unsubscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    /// Re-quotes the subscribed pairs every poll interval; there is no push
+    /// feed for pool state.
+    async fn market_data_stream(&self) -> Result<MarketDataStream> {
+        if self.subscriptions.is_empty() {
+            return Err(ArbFinderError::Exchange("No Curve pairs subscribed".to_string()));
+        }
+        let pairs: Vec<CurvePair> = self
+            .subscriptions
+            .iter()
+            .map(|symbol| self.pair(symbol).cloned())
+            .collect::<Result<_>>()?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let rpc = self.rpc.clone();
+        let sizes = self.sizes.clone();
+        let mut interval = tokio::time::interval(self.poll_interval);
+        tokio::spawn(async move {
+            loop {
+                interval.tick().await;
+                for pair in &pairs {
+                    match fetch_book(&rpc, pair, &sizes).await {
+                        Ok(book) => {
+                            if tx.send(MarketData::OrderBook(book)).is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => warn!("Failed to quote Curve pool {}: {}", pair.address, e),
+                    }
+                }
+            }
+        });
+        Ok(channel_stream(rx))
+    }
+
+    async fn fetch_orderbook(&self, symbol: &Symbol, _depth: Option<u32>) -> Result<OrderBook> {
+        self.get_orderbook(symbol).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn test_curve_adapter_creation() {
+        let three_pool = "0xbEbc44782C7dB0a1A60Cb6fe97d0b483032FF1C7";
+        let mut adapter = CurveAdapter::new("http://localhost:8545")
+            .with_pool(Symbol::new("USDC", "USDT"), three_pool)
+            .with_pool(Symbol::new("DAI", "USDC"), three_pool)
+            .with_sizes(vec![dec!(10000), dec!(1000), dec!(0), dec!(1000)]);
+        assert_eq!(adapter.venue_id(), VenueId::Curve);
+        assert!(!adapter.is_connected().await);
+        assert_eq!(adapter.get_symbols().await.unwrap().len(), 2);
+        assert_eq!(adapter.sizes, vec![dec!(1000), dec!(10000)]);
+
+        // Unresolved until connect
+        assert!(adapter.pair(&Symbol::new("USDC", "USDT")).is_err());
+        assert!(adapter.subscribe_orderbook(&Symbol::new("FRAX", "USDC"), None).await.is_err());
+    }
+}
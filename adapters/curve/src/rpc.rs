@@ -0,0 +1,170 @@
+//! Minimal Ethereum JSON-RPC client and ABI helpers for the view calls the
+//! adapter makes. Arguments are unsigned words: coin indices and amounts.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use reqwest::Client;
+use serde_json::Value;
+
+use arbfinder_core::prelude::*;
+
+// Function selectors: the first four bytes of the keccak-256 hash of the
+// signature. Older pools index coins by int128, newer ones by uint256; the
+// encoding of a non-negative index is the same either way.
+pub const GET_DY: [u8; 4] = [0x5e, 0x0d, 0x44, 0x3f]; // get_dy(int128,int128,uint256)
+pub const GET_DY_UINT: [u8; 4] = [0x55, 0x6d, 0x6e, 0x9f]; // get_dy(uint256,uint256,uint256)
+pub const COINS: [u8; 4] = [0xc6, 0x61, 0x06, 0x57]; // coins(uint256)
+pub const COINS_INT128: [u8; 4] = [0x23, 0x74, 0x6e, 0xb8]; // coins(int128)
+pub const DECIMALS: [u8; 4] = [0x31, 0x3c, 0xe5, 0x67]; // decimals()
+pub const SYMBOL: [u8; 4] = [0x95, 0xd8, 0x9b, 0x41]; // symbol()
+
+/// Calldata for `selector` with unsigned integer arguments.
+pub fn encode_call(selector: [u8; 4], args: &[u128]) -> String {
+    let mut data = selector.to_vec();
+    for arg in args {
+        data.extend([0u8; 16]);
+        data.extend(arg.to_be_bytes());
+    }
+    format!("0x{}", hex::encode(data))
+}
+
+pub fn decode_hex(data: &str) -> Result<Vec<u8>> {
+    hex::decode(data.trim_start_matches("0x"))
+        .map_err(|e| ArbFinderError::Parse(format!("Invalid hex from RPC: {}", e)))
+}
+
+/// The `index`th 32-byte word of ABI-encoded return data.
+pub fn word(data: &[u8], index: usize) -> Result<&[u8]> {
+    data.get(index * 32..(index + 1) * 32)
+        .ok_or_else(|| ArbFinderError::InvalidData(format!("Return data has no word {}", index)))
+}
+
+/// Unsigned word, failing if it does not fit in a `u128`.
+pub fn word_to_u128(word: &[u8]) -> Result<u128> {
+    if word[..16].iter().any(|byte| *byte != 0) {
+        return Err(ArbFinderError::InvalidData("Value does not fit in 128 bits".to_string()));
+    }
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&word[16..]);
+    Ok(u128::from_be_bytes(bytes))
+}
+
+pub fn word_to_address(word: &[u8]) -> String {
+    format!("0x{}", hex::encode(&word[12..]))
+}
+
+/// A dynamic `string` return value.
+pub fn decode_string(data: &[u8]) -> Result<String> {
+    let truncated = || ArbFinderError::InvalidData("Truncated string".to_string());
+    let offset = word_to_u128(word(data, 0)?)? as usize;
+    let length = data
+        .get(offset..offset + 32)
+        .ok_or_else(truncated)
+        .and_then(word_to_u128)? as usize;
+    let bytes = data.get(offset + 32..offset + 32 + length).ok_or_else(truncated)?;
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+#[derive(Clone)]
+pub struct RpcClient {
+    client: Client,
+    url: String,
+    next_id: Arc<AtomicU64>,
+}
+
+impl RpcClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            url: url.into(),
+            next_id: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    pub async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params,
+        });
+        let response = self.client
+            .post(&self.url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(ArbFinderError::Http)?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(ArbFinderError::RateLimit(format!("RPC endpoint limited {}", method)));
+        }
+        let body: Value = response.json().await.map_err(ArbFinderError::Http)?;
+        if !body["error"].is_null() {
+            return Err(ArbFinderError::Exchange(format!(
+                "RPC {} failed: {}",
+                method, body["error"]["message"]
+            )));
+        }
+        if !status.is_success() {
+            return Err(ArbFinderError::Exchange(format!("RPC error: {}", status)));
+        }
+
+        Ok(body["result"].clone())
+    }
+
+    /// `eth_call` against the latest block, returning the raw return data.
+    /// A reverted call is an error.
+    pub async fn view(&self, to: &str, selector: [u8; 4], args: &[u128]) -> Result<Vec<u8>> {
+        let data = encode_call(selector, args);
+        let result = self
+            .call("eth_call", serde_json::json!([{ "to": to, "data": data }, "latest"]))
+            .await?;
+        decode_hex(result.as_str().unwrap_or_default())
+    }
+
+    /// A view returning a single unsigned word.
+    pub async fn view_u128(&self, to: &str, selector: [u8; 4], args: &[u128]) -> Result<u128> {
+        word_to_u128(word(&self.view(to, selector, args).await?, 0)?)
+    }
+
+    /// A hex quantity result (`eth_chainId`, `eth_blockNumber`, ...).
+    pub async fn quantity(&self, method: &str, params: Value) -> Result<u128> {
+        let result = self.call(method, params).await?;
+        parse_quantity(&result)
+    }
+}
+
+pub fn parse_quantity(value: &Value) -> Result<u128> {
+    let text = value
+        .as_str()
+        .ok_or_else(|| ArbFinderError::InvalidData(format!("Expected hex quantity, got {}", value)))?;
+    u128::from_str_radix(text.trim_start_matches("0x"), 16)
+        .map_err(|e| ArbFinderError::Parse(format!("Invalid quantity {}: {}", text, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_and_decode_words() {
+        // get_dy(1, 2, 1000 USDC)
+        let data = encode_call(GET_DY, &[1, 2, 1_000_000_000]);
+        assert!(data.starts_with("0x5e0d443f"));
+        assert_eq!(data.len(), 2 + 8 + 3 * 64);
+
+        let bytes = decode_hex(&data).unwrap();
+        assert_eq!(word_to_u128(word(&bytes[4..], 2).unwrap()).unwrap(), 1_000_000_000);
+        assert!(word_to_u128(&[0xff; 32]).is_err());
+
+        // symbol() returning "DAI"
+        let encoded = decode_hex(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "0000000000000000000000000000000000000000000000000000000000000003",
+            "4441490000000000000000000000000000000000000000000000000000000000",
+        ))
+        .unwrap();
+        assert_eq!(decode_string(&encoded).unwrap(), "DAI");
+    }
+}
@@ -0,0 +1,470 @@
+//! Deribit Exchange Adapter
+//!
+//! Public market data for Deribit futures, perpetuals and options: instrument
+//! metadata, order books, trades, mark prices and funding over the JSON-RPC
+//! API (REST and WebSocket).
+//!
+//! Perpetuals map to plain symbols so they line up with spot books on other
+//! venues: `BTC/USD` is `BTC-PERPETUAL` and `ETH/USDC` is
+//! `ETH_USDC-PERPETUAL`. Dated futures and options use the instrument name
+//! as the base (`BTC-27DEC24-60000-C/USD`); take the symbol from
+//! [`DeribitAdapter::get_instruments`] rather than building it by hand.
+
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use arbfinder_exchange::{channel_stream, spawn_connection_with, DefaultExchangeConfig};
+use async_trait::async_trait;
+use reqwest::Client;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::str::FromStr;
+use tokio::sync::mpsc;
+
+pub mod websocket;
+pub use websocket::DeribitMarketDataStream;
+
+const DERIBIT_API_URL: &str = "https://www.deribit.com/api/v2";
+
+/// JSON-RPC error code for an exceeded request budget.
+const DERIBIT_TOO_MANY_REQUESTS: i64 = 10028;
+
+/// Deribit instrument name for a symbol. Symbols whose base is already an
+/// instrument name (dated futures, options) pass through unchanged.
+pub fn deribit_instrument(symbol: &Symbol) -> String {
+    let base = symbol.base().to_uppercase();
+    if base.contains('-') {
+        return base;
+    }
+    match symbol.quote().to_uppercase().as_str() {
+        "USD" => format!("{}-PERPETUAL", base),
+        quote => format!("{}_{}-PERPETUAL", base, quote),
+    }
+}
+
+/// Deribit sends numbers as JSON floats. Parsing their shortest decimal
+/// representation keeps values like `0.0001` exact.
+fn value_to_decimal(value: &Value) -> Option<Decimal> {
+    let text = match value {
+        Value::String(s) => s.clone(),
+        Value::Number(n) => n.to_string(),
+        _ => return None,
+    };
+    Decimal::from_str(&text)
+        .or_else(|_| Decimal::from_scientific(&text))
+        .ok()
+}
+
+fn millis_to_datetime(value: &Value) -> DateTime<Utc> {
+    value
+        .as_i64()
+        .and_then(DateTime::<Utc>::from_timestamp_millis)
+        .unwrap_or_else(Utc::now)
+}
+
+/// Hourly rates from `public/get_funding_rate_history`, oldest first. Each
+/// entry carries the hour's `interest_1h` and the 8h rate it annualises to
+/// on Deribit's terms, `interest_8h`.
+fn parse_funding_history(symbol: &Symbol, result: &Value) -> Vec<FundingRate> {
+    let mut rates: Vec<FundingRate> = result
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            Some(FundingRate {
+                symbol: symbol.clone(),
+                current_rate: value_to_decimal(&entry["interest_1h"])?,
+                rate_8h: value_to_decimal(&entry["interest_8h"]),
+                timestamp: DateTime::<Utc>::from_timestamp_millis(entry["timestamp"].as_i64()?)?,
+            })
+        })
+        .collect();
+    rates.sort_by_key(|rate| rate.timestamp);
+    rates
+}
+
+/// Builds instrument metadata from a `public/get_instruments` entry. Spot
+/// pairs and combos are not covered and return `None`.
+pub fn parse_instrument(info: &Value) -> Option<InstrumentInfo> {
+    let name = info["instrument_name"].as_str()?.to_string();
+    let expiry = info["expiration_timestamp"]
+        .as_i64()
+        .and_then(DateTime::<Utc>::from_timestamp_millis);
+
+    let kind = match (info["kind"].as_str()?, info["settlement_period"].as_str()) {
+        ("future", Some("perpetual")) => InstrumentKind::Perpetual,
+        ("future", _) => InstrumentKind::Future { expiry: expiry? },
+        ("option", _) => InstrumentKind::Option {
+            expiry: expiry?,
+            strike: value_to_decimal(&info["strike"])?,
+            option_kind: match info["option_type"].as_str()? {
+                "call" => OptionKind::Call,
+                "put" => OptionKind::Put,
+                _ => return None,
+            },
+        },
+        _ => return None,
+    };
+
+    let base = info["base_currency"].as_str()?;
+    let quote = info["quote_currency"].as_str()?;
+    let symbol = match kind {
+        InstrumentKind::Perpetual => Symbol::new(base, quote),
+        _ => Symbol::new(&name, quote),
+    };
+
+    let tick_size = value_to_decimal(&info["tick_size"])?;
+    let min_trade_amount = value_to_decimal(&info["min_trade_amount"])?;
+    let is_active = info["is_active"].as_bool().unwrap_or(false);
+
+    Some(InstrumentInfo {
+        name,
+        kind,
+        underlying: base.to_string(),
+        settlement_currency: info["settlement_currency"].as_str().unwrap_or(base).to_string(),
+        contract_size: value_to_decimal(&info["contract_size"]).unwrap_or(Decimal::ONE),
+        inverse: info["instrument_type"].as_str() == Some("reversed"),
+        symbol_info: SymbolInfo {
+            symbol,
+            status: if is_active { "open" } else { "closed" }.to_string(),
+            base_asset_precision: min_trade_amount.scale(),
+            quote_asset_precision: tick_size.scale(),
+            tick_size,
+            lot_size: min_trade_amount,
+            min_order_size: min_trade_amount,
+            max_order_size: Decimal::new(1000000, 0),
+            min_notional: Decimal::ZERO,
+            trading_fees: TradingFees {
+                maker_fee: value_to_decimal(&info["maker_commission"]).unwrap_or(Decimal::ZERO),
+                taker_fee: value_to_decimal(&info["taker_commission"]).unwrap_or(Decimal::new(5, 4)),
+            },
+        },
+    })
+}
+
+pub struct DeribitAdapter {
+    client: Client,
+    base_url: String,
+    ws_url: String,
+    connected: bool,
+    subscriptions: Vec<Symbol>,
+}
+
+impl DeribitAdapter {
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+            base_url: DERIBIT_API_URL.to_string(),
+            ws_url: websocket::DERIBIT_WS_URL.to_string(),
+            connected: false,
+            subscriptions: Vec::new(),
+        }
+    }
+
+    /// Calls a public JSON-RPC method over HTTP and returns its `result`.
+    async fn get_request(&self, method: &str, params: &[(&str, String)]) -> Result<Value> {
+        let url = format!("{}/public/{}", self.base_url, method);
+        let response = self.client
+            .get(&url)
+            .query(params)
+            .send()
+            .await
+            .map_err(ArbFinderError::Http)?;
+
+        let status = response.status();
+        let body: Value = response.json().await.map_err(ArbFinderError::Http)?;
+        let error = &body["error"];
+        if !error.is_null() {
+            let message = error["message"].as_str().unwrap_or_default();
+            return Err(match error["code"].as_i64() {
+                Some(DERIBIT_TOO_MANY_REQUESTS) => {
+                    ArbFinderError::RateLimit(format!("Deribit: {}", message))
+                }
+                code => ArbFinderError::Exchange(format!(
+                    "Deribit API error {} ({:?}): {}",
+                    status, code, message
+                )),
+            });
+        }
+        if !status.is_success() {
+            return Err(ArbFinderError::Exchange(format!("Deribit API error: {}", status)));
+        }
+
+        Ok(body["result"].clone())
+    }
+
+    /// Active futures, perpetuals and options for `currency` (e.g. `BTC`, or
+    /// `any`). `kind` narrows to `future` or `option`.
+    pub async fn get_instruments(&self, currency: &str, kind: Option<&str>) -> Result<Vec<InstrumentInfo>> {
+        let mut params = vec![("currency", currency.to_string())];
+        if let Some(kind) = kind {
+            params.push(("kind", kind.to_string()));
+        }
+        let result = self.get_request("get_instruments", &params).await?;
+        let instruments = result
+            .as_array()
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing instrument list".to_string()))?;
+
+        Ok(instruments.iter().filter_map(parse_instrument).collect())
+    }
+
+    pub async fn get_instrument(&self, symbol: &Symbol) -> Result<InstrumentInfo> {
+        let name = deribit_instrument(symbol);
+        let result = self
+            .get_request("get_instrument", &[("instrument_name", name.clone())])
+            .await?;
+        parse_instrument(&result).ok_or(ArbFinderError::SymbolNotFound(name))
+    }
+
+    async fn get_ticker_data(&self, symbol: &Symbol) -> Result<Value> {
+        self.get_request("ticker", &[("instrument_name", deribit_instrument(symbol))])
+            .await
+    }
+
+    /// Fetch the ticker for an instrument
+    pub async fn get_ticker(&self, symbol: &Symbol) -> Result<Ticker> {
+        let data = self.get_ticker_data(symbol).await?;
+        let price = value_to_decimal(&data["last_price"])
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing last price".to_string()))?;
+
+        // price_change is a percentage; convert to an absolute change like the other adapters
+        let change_24h = value_to_decimal(&data["stats"]["price_change"])
+            .map(|pct| price - price / (Decimal::ONE + pct / Decimal::from(100)))
+            .unwrap_or(Decimal::ZERO);
+
+        Ok(Ticker {
+            symbol: symbol.clone(),
+            price,
+            volume_24h: value_to_decimal(&data["stats"]["volume"]).unwrap_or(Decimal::ZERO),
+            change_24h,
+            timestamp: millis_to_datetime(&data["timestamp"]),
+        })
+    }
+
+    pub async fn get_mark_price(&self, symbol: &Symbol) -> Result<MarkPrice> {
+        let data = self.get_ticker_data(symbol).await?;
+        websocket::mark_price_from(symbol, &data)
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing mark price".to_string()))
+    }
+
+    /// Fetch orderbook depth from Deribit
+    pub async fn get_orderbook(&self, symbol: &Symbol, depth: Option<u32>) -> Result<OrderBook> {
+        let params = [
+            ("instrument_name", deribit_instrument(symbol)),
+            ("depth", depth.unwrap_or(100).min(10000).to_string()),
+        ];
+        let data = self.get_request("get_order_book", &params).await?;
+
+        let mut orderbook = OrderBook::new(symbol.clone());
+        websocket::apply_levels(&mut orderbook, &data["bids"], Side::Bid);
+        websocket::apply_levels(&mut orderbook, &data["asks"], Side::Ask);
+        orderbook.timestamp = millis_to_datetime(&data["timestamp"]);
+        orderbook.sequence = data["change_id"].as_u64();
+
+        Ok(orderbook)
+    }
+}
+
+impl Default for DeribitAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl MarketDataAdapter for DeribitAdapter {
+    fn venue_id(&self) -> VenueId {
+        VenueId::Deribit
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        let _ = self.get_server_time().await?;
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.connected = false;
+        Ok(())
+    }
+
+    async fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        let result = self.get_request("get_time", &[]).await?;
+        result
+            .as_i64()
+            .and_then(DateTime::<Utc>::from_timestamp_millis)
+            .ok_or_else(|| ArbFinderError::InvalidData("Invalid timestamp".to_string()))
+    }
+
+    async fn ping(&self) -> Result<u64> {
+        let start = std::time::Instant::now();
+        let _ = self.get_request("test", &[]).await?;
+        Ok(start.elapsed().as_millis() as u64)
+    }
+
+    async fn get_symbols(&self) -> Result<Vec<Symbol>> {
+        Ok(self
+            .get_instruments("any", None)
+            .await?
+            .into_iter()
+            .map(|instrument| instrument.symbol_info.symbol)
+            .collect())
+    }
+
+    async fn get_symbol_info(&self, symbol: &Symbol) -> Result<SymbolInfo> {
+        Ok(self.get_instrument(symbol).await?.symbol_info)
+    }
+
+    async fn subscribe_orderbook(&mut self, symbol: &Symbol, _depth: Option<u32>) -> Result<()> {
+        if !self.subscriptions.contains(symbol) {
+            self.subscriptions.push(symbol.clone());
+        }
+        Ok(())
+    }
+
+    async fn subscribe_trades(&mut self, symbol: &Symbol) -> Result<()> {
+        // Book, trade and ticker channels are subscribed together per instrument
+        self.subscribe_orderbook(symbol, None).await
+    }
+
+    async fn subscribe_ticker(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscribe_orderbook(symbol, None).await
+    }
+
+    async fn unsubscribe_orderbook(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscriptions.retain(|s| s != symbol);
+        Ok(())
+    }
+
+    async fn unsubscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn market_data_stream(&self) -> Result<MarketDataStream> {
+        if self.subscriptions.is_empty() {
+            return Err(ArbFinderError::Exchange("No Deribit instruments subscribed".to_string()));
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let subscribe = vec![
+            websocket::heartbeat_message(),
+            websocket::subscribe_message(&self.subscriptions),
+        ];
+        let config = DefaultExchangeConfig {
+            base_url: self.base_url.clone(),
+            websocket_url: self.ws_url.clone(),
+            ..Default::default()
+        };
+        let symbols = self.subscriptions.clone();
+
+        spawn_connection_with(&config, &subscribe, move |outbound| {
+            DeribitMarketDataStream::new(symbols, tx, outbound)
+        })
+        .await?;
+        Ok(channel_stream(rx))
+    }
+
+    async fn fetch_orderbook(&self, symbol: &Symbol, depth: Option<u32>) -> Result<OrderBook> {
+        self.get_orderbook(symbol, depth).await
+    }
+
+    /// Errors for instruments without funding, i.e. all but perpetuals.
+    async fn get_funding_rate(&self, symbol: &Symbol) -> Result<FundingRate> {
+        let data = self.get_ticker_data(symbol).await?;
+        websocket::funding_from(symbol, &data).ok_or_else(|| {
+            ArbFinderError::InvalidData(format!("{} has no funding rate", deribit_instrument(symbol)))
+        })
+    }
+
+    async fn get_funding_history(&self, symbol: &Symbol, limit: u32) -> Result<Vec<FundingRate>> {
+        // One entry an hour, at most 744 (a month) per request
+        let limit = limit.clamp(1, 744);
+        let end = Utc::now();
+        let start = end - chrono::Duration::hours(limit as i64);
+        let params = [
+            ("instrument_name", deribit_instrument(symbol)),
+            ("start_timestamp", start.timestamp_millis().to_string()),
+            ("end_timestamp", end.timestamp_millis().to_string()),
+        ];
+        let result = self.get_request("get_funding_rate_history", &params).await?;
+
+        let mut rates = parse_funding_history(symbol, &result);
+        let skip = rates.len().saturating_sub(limit as usize);
+        rates.drain(..skip);
+        Ok(rates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_funding_history() {
+        let result = serde_json::json!([
+            {"timestamp": 1569891600000u64, "index_price": 8222.87, "prev_index_price": 8305.72,
+             "interest_8h": -0.00009234, "interest_1h": -4.739e-7},
+            {"timestamp": 1569888000000u64, "index_price": 8305.72, "prev_index_price": 8311.02,
+             "interest_8h": -0.00008781, "interest_1h": -0.00000112}
+        ]);
+        let rates = parse_funding_history(&Symbol::new("BTC", "USD"), &result);
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0].timestamp.timestamp_millis(), 1569888000000);
+        assert_eq!(rates[0].current_rate, Decimal::from_str("-0.00000112").unwrap());
+        assert_eq!(rates[1].current_rate, Decimal::from_str("-0.0000004739").unwrap());
+        assert_eq!(rates[1].rate_8h, Some(Decimal::from_str("-0.00009234").unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_deribit_adapter_creation() {
+        let adapter = DeribitAdapter::new();
+        assert_eq!(adapter.venue_id(), VenueId::Deribit);
+        assert!(!adapter.is_connected().await);
+    }
+
+    #[test]
+    fn test_instrument_names() {
+        assert_eq!(deribit_instrument(&Symbol::new("btc", "usd")), "BTC-PERPETUAL");
+        assert_eq!(deribit_instrument(&Symbol::new("ETH", "USDC")), "ETH_USDC-PERPETUAL");
+        assert_eq!(
+            deribit_instrument(&Symbol::new("BTC-27DEC24-60000-C", "USD")),
+            "BTC-27DEC24-60000-C"
+        );
+    }
+
+    #[test]
+    fn test_parse_instruments() {
+        let option: Value = serde_json::from_str(r#"{"tick_size":0.0005,"taker_commission":0.0003,"strike":60000.0,"settlement_period":"month","settlement_currency":"BTC","quote_currency":"BTC","option_type":"call","min_trade_amount":0.1,"maker_commission":0.0003,"kind":"option","is_active":true,"instrument_name":"BTC-27DEC24-60000-C","instrument_type":"reversed","expiration_timestamp":1735286400000,"contract_size":1.0,"base_currency":"BTC"}"#).unwrap();
+        let info = parse_instrument(&option).unwrap();
+        assert_eq!(
+            info.kind,
+            InstrumentKind::Option {
+                expiry: DateTime::<Utc>::from_timestamp_millis(1735286400000).unwrap(),
+                strike: Decimal::from(60000),
+                option_kind: OptionKind::Call,
+            }
+        );
+        assert_eq!(info.symbol_info.symbol, Symbol::new("BTC-27DEC24-60000-C", "BTC"));
+        assert_eq!(info.symbol_info.tick_size, Decimal::new(5, 4));
+        assert_eq!(deribit_instrument(&info.symbol_info.symbol), info.name);
+
+        let perpetual: Value = serde_json::from_str(r#"{"tick_size":0.5,"taker_commission":0.0005,"settlement_period":"perpetual","settlement_currency":"BTC","quote_currency":"USD","min_trade_amount":10.0,"maker_commission":0.0,"kind":"future","is_active":true,"instrument_name":"BTC-PERPETUAL","instrument_type":"reversed","expiration_timestamp":32503708800000,"contract_size":10.0,"base_currency":"BTC"}"#).unwrap();
+        let info = parse_instrument(&perpetual).unwrap();
+        assert_eq!(info.kind, InstrumentKind::Perpetual);
+        assert!(info.inverse);
+        assert_eq!(info.contract_size, Decimal::from(10));
+        assert_eq!(info.symbol_info.symbol, Symbol::new("BTC", "USD"));
+        assert_eq!(deribit_instrument(&info.symbol_info.symbol), "BTC-PERPETUAL");
+
+        let spot: Value = serde_json::from_str(r#"{"kind":"spot","instrument_name":"BTC_USDC","base_currency":"BTC","quote_currency":"USDC","tick_size":1.0,"min_trade_amount":0.0001}"#).unwrap();
+        assert!(parse_instrument(&spot).is_none());
+    }
+}
@@ -0,0 +1,372 @@
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use crate::{deribit_instrument, millis_to_datetime, value_to_decimal};
+
+pub const DERIBIT_WS_URL: &str = "wss://www.deribit.com/ws/api/v2";
+
+/// Aggregation interval for public channels; `raw` requires authentication.
+const CHANNEL_INTERVAL: &str = "100ms";
+
+/// Seconds between server heartbeats. Deribit closes the connection when a
+/// `test_request` heartbeat goes unanswered.
+pub const HEARTBEAT_INTERVAL_SECS: u64 = 30;
+
+fn rpc_request(id: u64, method: &str, params: Value) -> String {
+    serde_json::json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }).to_string()
+}
+
+fn channels(instrument: &str) -> [String; 3] {
+    ["book", "trades", "ticker"].map(|kind| format!("{}.{}.{}", kind, instrument, CHANNEL_INTERVAL))
+}
+
+/// `public/subscribe` request for the book, trade and ticker channels of
+/// `symbols`.
+pub fn subscribe_message(symbols: &[Symbol]) -> String {
+    let channels: Vec<String> = symbols
+        .iter()
+        .flat_map(|symbol| channels(&deribit_instrument(symbol)))
+        .collect();
+    rpc_request(1, "public/subscribe", serde_json::json!({ "channels": channels }))
+}
+
+/// Asks the server to send heartbeats, which keeps idle connections open.
+pub fn heartbeat_message() -> String {
+    rpc_request(0, "public/set_heartbeat", serde_json::json!({ "interval": HEARTBEAT_INTERVAL_SECS }))
+}
+
+/// Applies book levels, either REST `[price, amount]` pairs or streamed
+/// `[action, price, amount]` triples; `delete` and zero amounts remove the
+/// level.
+pub(crate) fn apply_levels(book: &mut OrderBook, levels: &Value, side: Side) {
+    for level in levels.as_array().into_iter().flatten() {
+        let (price, amount) = match level.as_array().map(Vec::len) {
+            Some(3) => {
+                let amount = if level[0].as_str() == Some("delete") {
+                    Some(Decimal::ZERO)
+                } else {
+                    value_to_decimal(&level[2])
+                };
+                (value_to_decimal(&level[1]), amount)
+            }
+            _ => (value_to_decimal(&level[0]), value_to_decimal(&level[1])),
+        };
+        if let (Some(price), Some(amount)) = (price, amount) {
+            match side {
+                Side::Bid => book.update_bid(price, amount),
+                Side::Ask => book.update_ask(price, amount),
+            }
+        }
+    }
+}
+
+/// Mark price from a `ticker` payload (REST or streamed).
+pub(crate) fn mark_price_from(symbol: &Symbol, data: &Value) -> Option<MarkPrice> {
+    Some(MarkPrice {
+        symbol: symbol.clone(),
+        mark_price: value_to_decimal(&data["mark_price"])?,
+        index_price: value_to_decimal(&data["index_price"])?,
+        timestamp: millis_to_datetime(&data["timestamp"]),
+    })
+}
+
+/// Funding from a `ticker` payload; only perpetuals carry `current_funding`.
+pub(crate) fn funding_from(symbol: &Symbol, data: &Value) -> Option<FundingRate> {
+    Some(FundingRate {
+        symbol: symbol.clone(),
+        current_rate: value_to_decimal(&data["current_funding"])?,
+        rate_8h: value_to_decimal(&data["funding_8h"]),
+        timestamp: millis_to_datetime(&data["timestamp"]),
+    })
+}
+
+/// Handles Deribit JSON-RPC subscription notifications.
+///
+/// Book notifications after the first snapshot are deltas chained by
+/// `prev_change_id`; a break in the chain drops the book and resubscribes
+/// its channel to get a fresh snapshot.
+pub struct DeribitMarketDataStream {
+    symbols: HashMap<String, Symbol>,
+    books: HashMap<Symbol, OrderBook>,
+    update_tx: mpsc::UnboundedSender<MarketData>,
+    outbound_tx: mpsc::UnboundedSender<String>,
+    next_request_id: u64,
+}
+
+impl DeribitMarketDataStream {
+    pub fn new(
+        symbols: Vec<Symbol>,
+        update_tx: mpsc::UnboundedSender<MarketData>,
+        outbound_tx: mpsc::UnboundedSender<String>,
+    ) -> Self {
+        Self {
+            symbols: symbols.into_iter().map(|s| (deribit_instrument(&s), s)).collect(),
+            books: HashMap::new(),
+            update_tx,
+            outbound_tx,
+            next_request_id: 2,
+        }
+    }
+
+    pub fn get_orderbook(&self, symbol: &Symbol) -> Option<&OrderBook> {
+        self.books.get(symbol)
+    }
+
+    fn send_request(&mut self, method: &str, params: Value) {
+        let id = self.next_request_id;
+        self.next_request_id += 1;
+        let _ = self.outbound_tx.send(rpc_request(id, method, params));
+    }
+
+    fn resubscribe_book(&mut self, instrument: &str) {
+        let channel = format!("book.{}.{}", instrument, CHANNEL_INTERVAL);
+        for method in ["public/unsubscribe", "public/subscribe"] {
+            self.send_request(method, serde_json::json!({ "channels": [channel] }));
+        }
+    }
+
+    fn process_book(&mut self, instrument: &str, symbol: Symbol, data: &Value) {
+        let change_id = data["change_id"].as_u64();
+
+        if data["type"].as_str() == Some("snapshot") {
+            self.books.insert(symbol.clone(), OrderBook::new(symbol.clone()));
+        } else {
+            let prev_change_id = data["prev_change_id"].as_u64();
+            match self.books.get(&symbol).map(|book| book.sequence) {
+                None => {
+                    debug!("Deribit change for {} before snapshot", instrument);
+                    return;
+                }
+                Some(sequence) if sequence != prev_change_id => {
+                    warn!(
+                        "Deribit book gap for {}: have {:?}, update follows {:?}; resubscribing",
+                        instrument, sequence, prev_change_id
+                    );
+                    self.books.remove(&symbol);
+                    self.resubscribe_book(instrument);
+                    return;
+                }
+                Some(_) => {}
+            }
+        }
+        let Some(book) = self.books.get_mut(&symbol) else { return };
+
+        apply_levels(book, &data["bids"], Side::Bid);
+        apply_levels(book, &data["asks"], Side::Ask);
+        book.timestamp = millis_to_datetime(&data["timestamp"]);
+        book.sequence = change_id;
+
+        let _ = self.update_tx.send(MarketData::OrderBook(book.clone()));
+    }
+
+    fn process_trades(&self, symbol: &Symbol, data: &Value) {
+        for entry in data.as_array().into_iter().flatten() {
+            let (Some(price), Some(amount)) = (value_to_decimal(&entry["price"]), value_to_decimal(&entry["amount"])) else {
+                continue;
+            };
+            let side = match entry["direction"].as_str() {
+                Some("buy") => Side::Bid,
+                _ => Side::Ask,
+            };
+            let trade_id = entry["trade_id"].as_str().unwrap_or_default().to_string();
+
+            let mut trade = Trade::new(symbol.clone(), price, amount, side, trade_id);
+            trade.timestamp = millis_to_datetime(&entry["timestamp"]);
+            let _ = self.update_tx.send(MarketData::Trade(trade));
+        }
+    }
+
+    fn process_ticker(&self, symbol: &Symbol, data: &Value) {
+        if let Some(mark) = mark_price_from(symbol, data) {
+            let _ = self.update_tx.send(MarketData::MarkPrice(mark));
+        }
+        if let Some(funding) = funding_from(symbol, data) {
+            let _ = self.update_tx.send(MarketData::Funding(funding));
+        }
+    }
+}
+
+#[async_trait]
+impl WebSocketHandler for DeribitMarketDataStream {
+    async fn on_message(&mut self, message: &str) -> Result<()> {
+        let data: Value = serde_json::from_str(message)?;
+
+        match data["method"].as_str() {
+            Some("heartbeat") => {
+                if data["params"]["type"].as_str() == Some("test_request") {
+                    self.send_request("public/test", serde_json::json!({}));
+                }
+                return Ok(());
+            }
+            Some("subscription") => {}
+            _ => {
+                if !data["error"].is_null() {
+                    warn!("Deribit request {} failed: {}", data["id"], data["error"]);
+                } else {
+                    debug!("Deribit response {}: {}", data["id"], data["result"]);
+                }
+                return Ok(());
+            }
+        }
+
+        let params = &data["params"];
+        let channel = params["channel"].as_str().unwrap_or_default();
+        let mut parts = channel.split('.');
+        let (Some(kind), Some(instrument)) = (parts.next(), parts.next()) else {
+            debug!("Ignoring Deribit message: {}", message);
+            return Ok(());
+        };
+        let Some(symbol) = self.symbols.get(instrument).cloned() else {
+            debug!("Ignoring Deribit message for {}", instrument);
+            return Ok(());
+        };
+
+        match kind {
+            "book" => self.process_book(instrument, symbol, &params["data"]),
+            "trades" => self.process_trades(&symbol, &params["data"]),
+            "ticker" => self.process_ticker(&symbol, &params["data"]),
+            _ => debug!("Ignoring Deribit channel {}", channel),
+        }
+        Ok(())
+    }
+
+    async fn on_connect(&mut self) -> Result<()> {
+        info!("Deribit WebSocket connected for {} instruments", self.symbols.len());
+        Ok(())
+    }
+
+    async fn on_disconnect(&mut self) -> Result<()> {
+        warn!("Deribit WebSocket disconnected");
+        self.books.clear();
+        Ok(())
+    }
+
+    async fn on_error(&mut self, error: &ArbFinderError) -> Result<()> {
+        error!("Deribit WebSocket error: {}", error);
+        Ok(())
+    }
+
+    async fn on_ping(&mut self) -> Result<()> {
+        debug!("Received Deribit ping");
+        Ok(())
+    }
+
+    async fn on_pong(&mut self) -> Result<()> {
+        debug!("Received Deribit pong");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    type Channels = (
+        DeribitMarketDataStream,
+        mpsc::UnboundedReceiver<MarketData>,
+        mpsc::UnboundedReceiver<String>,
+    );
+
+    fn perpetual() -> Symbol {
+        Symbol::new("BTC", "USD")
+    }
+
+    fn stream() -> Channels {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (out_tx, out_rx) = mpsc::unbounded_channel();
+        (DeribitMarketDataStream::new(vec![perpetual()], tx, out_tx), rx, out_rx)
+    }
+
+    fn notification(channel: &str, data: &str) -> String {
+        format!(
+            r#"{{"jsonrpc":"2.0","method":"subscription","params":{{"channel":"{}","data":{}}}}}"#,
+            channel, data
+        )
+    }
+
+    #[test]
+    fn test_subscribe_message() {
+        let message: Value = serde_json::from_str(&subscribe_message(&[perpetual()])).unwrap();
+        assert_eq!(message["method"], "public/subscribe");
+        let channels = message["params"]["channels"].as_array().unwrap();
+        assert_eq!(channels[0], "book.BTC-PERPETUAL.100ms");
+        assert_eq!(channels[2], "ticker.BTC-PERPETUAL.100ms");
+    }
+
+    #[tokio::test]
+    async fn test_book_snapshot_change_and_gap() {
+        let (mut stream, mut rx, mut out_rx) = stream();
+        let channel = "book.BTC-PERPETUAL.100ms";
+
+        let snapshot = r#"{"type":"snapshot","timestamp":1700000000000,"instrument_name":"BTC-PERPETUAL","change_id":10,"bids":[["new",37000.0,1500.0]],"asks":[["new",37000.5,2000.0],["new",37001.0,10.0]]}"#;
+        stream.on_message(&notification(channel, snapshot)).await.unwrap();
+        assert!(matches!(rx.recv().await.unwrap(), MarketData::OrderBook(_)));
+
+        let change = r#"{"type":"change","timestamp":1700000000100,"instrument_name":"BTC-PERPETUAL","prev_change_id":10,"change_id":11,"bids":[],"asks":[["delete",37000.5,0.0]]}"#;
+        stream.on_message(&notification(channel, change)).await.unwrap();
+        match rx.recv().await.unwrap() {
+            MarketData::OrderBook(book) => {
+                assert_eq!(book.best_ask().unwrap().price, Decimal::from(37001));
+                assert_eq!(book.best_bid().unwrap().quantity, Decimal::from(1500));
+                assert_eq!(book.sequence, Some(11));
+            }
+            other => panic!("expected order book, got {:?}", other),
+        }
+
+        // Change 12 never arrived, so the book is dropped until a new snapshot
+        let gap = r#"{"type":"change","timestamp":1700000000300,"instrument_name":"BTC-PERPETUAL","prev_change_id":12,"change_id":13,"bids":[],"asks":[]}"#;
+        stream.on_message(&notification(channel, gap)).await.unwrap();
+        assert!(stream.get_orderbook(&perpetual()).is_none());
+        assert!(rx.try_recv().is_err());
+
+        let unsubscribe: Value = serde_json::from_str(&out_rx.recv().await.unwrap()).unwrap();
+        let subscribe: Value = serde_json::from_str(&out_rx.recv().await.unwrap()).unwrap();
+        assert_eq!(unsubscribe["method"], "public/unsubscribe");
+        assert_eq!(subscribe["params"]["channels"][0], channel);
+    }
+
+    #[tokio::test]
+    async fn test_ticker_trades_and_heartbeat() {
+        let (mut stream, mut rx, mut out_rx) = stream();
+
+        let ticker = r#"{"timestamp":1700000000000,"instrument_name":"BTC-PERPETUAL","mark_price":37000.12,"index_price":36990.5,"current_funding":0.00012,"funding_8h":0.0003,"last_price":37000.0}"#;
+        stream.on_message(&notification("ticker.BTC-PERPETUAL.100ms", ticker)).await.unwrap();
+        match rx.recv().await.unwrap() {
+            MarketData::MarkPrice(mark) => {
+                assert_eq!(mark.mark_price, Decimal::from_str("37000.12").unwrap());
+                assert_eq!(mark.index_price, Decimal::from_str("36990.5").unwrap());
+            }
+            other => panic!("expected mark price, got {:?}", other),
+        }
+        match rx.recv().await.unwrap() {
+            MarketData::Funding(funding) => {
+                assert_eq!(funding.current_rate, Decimal::from_str("0.00012").unwrap());
+                assert_eq!(funding.rate_8h, Some(Decimal::from_str("0.0003").unwrap()));
+            }
+            other => panic!("expected funding, got {:?}", other),
+        }
+
+        let trades = r#"[{"trade_id":"BTC-1","price":37000.5,"amount":20.0,"direction":"sell","timestamp":1700000000000,"instrument_name":"BTC-PERPETUAL"}]"#;
+        stream.on_message(&notification("trades.BTC-PERPETUAL.100ms", trades)).await.unwrap();
+        match rx.recv().await.unwrap() {
+            MarketData::Trade(trade) => {
+                assert_eq!(trade.side, Side::Ask);
+                assert_eq!(trade.quantity, Decimal::from(20));
+            }
+            other => panic!("expected trade, got {:?}", other),
+        }
+
+        stream
+            .on_message(r#"{"jsonrpc":"2.0","method":"heartbeat","params":{"type":"test_request"}}"#)
+            .await
+            .unwrap();
+        let reply: Value = serde_json::from_str(&out_rx.recv().await.unwrap()).unwrap();
+        assert_eq!(reply["method"], "public/test");
+    }
+}
@@ -0,0 +1,288 @@
+//! Gemini Exchange Adapter
+//!
+//! Implementation of MarketDataAdapter for Gemini
+
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use arbfinder_exchange::{channel_stream, spawn_connection, DefaultExchangeConfig};
+use async_trait::async_trait;
+use reqwest::Client;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use tokio::sync::mpsc;
+
+pub mod websocket;
+pub use websocket::GeminiMarketDataStream;
+
+const GEMINI_API_URL: &str = "https://api.gemini.com";
+
+/// Quote currencies Gemini lists, longest first so `GUSD` wins over `USD`.
+const GEMINI_QUOTE_ASSETS: &[&str] = &[
+    "RLUSD", "GUSD", "USDT", "USDC", "USD", "EUR", "GBP", "SGD", "DAI", "BTC", "ETH", "SOL",
+];
+
+/// Splits a Gemini symbol such as `btcusd` or `ETHGUSD` into a `Symbol`.
+/// Perpetuals (`...PERP`) are not spot pairs and are rejected.
+pub fn parse_gemini_symbol(symbol: &str) -> Option<Symbol> {
+    let upper = symbol.to_uppercase();
+    if upper.ends_with("PERP") {
+        return None;
+    }
+
+    GEMINI_QUOTE_ASSETS.iter().find_map(|quote| {
+        upper
+            .strip_suffix(quote)
+            .filter(|base| !base.is_empty())
+            .map(|base| Symbol::new(base, *quote))
+    })
+}
+
+fn gemini_symbol(symbol: &Symbol) -> String {
+    format!("{}{}", symbol.base(), symbol.quote()).to_lowercase()
+}
+
+fn value_to_decimal(value: &serde_json::Value) -> Option<Decimal> {
+    match value {
+        serde_json::Value::String(s) => s.parse().ok(),
+        serde_json::Value::Number(n) => n.as_f64().and_then(Decimal::from_f64),
+        _ => None,
+    }
+}
+
+pub struct GeminiAdapter {
+    client: Client,
+    base_url: String,
+    ws_url: String,
+    connected: bool,
+    subscriptions: Vec<Symbol>,
+}
+
+impl GeminiAdapter {
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+            base_url: GEMINI_API_URL.to_string(),
+            ws_url: websocket::GEMINI_WS_URL.to_string(),
+            connected: false,
+            subscriptions: Vec::new(),
+        }
+    }
+
+    async fn get_request(&self, endpoint: &str) -> Result<serde_json::Value> {
+        let url = format!("{}{}", self.base_url, endpoint);
+        let response = self.client
+            .get(&url)
+            .send()
+            .await
+            .map_err(ArbFinderError::Http)?;
+
+        if !response.status().is_success() {
+            return Err(ArbFinderError::Exchange(format!(
+                "Gemini API error: {}",
+                response.status()
+            )));
+        }
+
+        response.json().await.map_err(ArbFinderError::Http)
+    }
+
+    /// Fetch the latest ticker for a symbol. Price and 24h change come from the
+    /// v2 ticker, volume from the v1 pubticker which is the only one carrying it.
+    pub async fn get_ticker(&self, symbol: &Symbol) -> Result<Ticker> {
+        let symbol_str = gemini_symbol(symbol);
+        let v2_endpoint = format!("/v2/ticker/{}", symbol_str);
+        let v1_endpoint = format!("/v1/pubticker/{}", symbol_str);
+        let (v2, v1) = tokio::try_join!(
+            self.get_request(&v2_endpoint),
+            self.get_request(&v1_endpoint),
+        )?;
+
+        let price = value_to_decimal(&v2["close"])
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing close price".to_string()))?;
+        let open = value_to_decimal(&v2["open"]).unwrap_or(price);
+        let volume_24h = value_to_decimal(&v1["volume"][symbol.base().to_uppercase()])
+            .unwrap_or(Decimal::ZERO);
+        let timestamp = v1["volume"]["timestamp"]
+            .as_i64()
+            .and_then(DateTime::from_timestamp_millis)
+            .unwrap_or_else(Utc::now);
+
+        Ok(Ticker {
+            symbol: symbol.clone(),
+            price,
+            volume_24h,
+            change_24h: price - open,
+            timestamp,
+        })
+    }
+}
+
+impl Default for GeminiAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl MarketDataAdapter for GeminiAdapter {
+    fn venue_id(&self) -> VenueId {
+        VenueId::Gemini
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        // Gemini has no time endpoint; a symbols fetch proves connectivity
+        let _ = self.ping().await?;
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.connected = false;
+        Ok(())
+    }
+
+    async fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        let response = self.get_request("/v1/pubticker/btcusd").await?;
+        let timestamp = response["volume"]["timestamp"]
+            .as_i64()
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing volume timestamp".to_string()))?;
+
+        Ok(DateTime::from_timestamp_millis(timestamp)
+            .unwrap_or_else(Utc::now))
+    }
+
+    async fn ping(&self) -> Result<u64> {
+        let start = std::time::Instant::now();
+        let _ = self.get_request("/v1/symbols").await?;
+        Ok(start.elapsed().as_millis() as u64)
+    }
+
+    async fn get_symbols(&self) -> Result<Vec<Symbol>> {
+        let response = self.get_request("/v1/symbols").await?;
+        let symbols = response
+            .as_array()
+            .ok_or_else(|| ArbFinderError::InvalidData("Expected symbols array".to_string()))?;
+
+        Ok(symbols
+            .iter()
+            .filter_map(|s| s.as_str())
+            .filter_map(parse_gemini_symbol)
+            .collect())
+    }
+
+    async fn get_symbol_info(&self, symbol: &Symbol) -> Result<SymbolInfo> {
+        let symbol_str = gemini_symbol(symbol);
+        let details = self
+            .get_request(&format!("/v1/symbols/details/{}", symbol_str))
+            .await
+            .map_err(|_| ArbFinderError::SymbolNotFound(symbol_str.clone()))?;
+
+        // Gemini's tick_size is the quantity increment, quote_increment the price tick
+        let lot_size = value_to_decimal(&details["tick_size"]).unwrap_or(Decimal::new(1, 8));
+        let tick_size = value_to_decimal(&details["quote_increment"]).unwrap_or(Decimal::new(1, 2));
+
+        Ok(SymbolInfo {
+            symbol: symbol.clone(),
+            status: if details["status"].as_str() == Some("open") {
+                "TRADING".to_string()
+            } else {
+                "INACTIVE".to_string()
+            },
+            base_asset_precision: lot_size.scale(),
+            quote_asset_precision: tick_size.scale(),
+            tick_size,
+            lot_size,
+            min_order_size: value_to_decimal(&details["min_order_size"]).unwrap_or(lot_size),
+            max_order_size: Decimal::new(1000000, 0),
+            min_notional: Decimal::ZERO,
+            trading_fees: TradingFees {
+                maker_fee: Decimal::new(2, 3), // 0.2%
+                taker_fee: Decimal::new(4, 3), // 0.4%
+            },
+        })
+    }
+
+    async fn subscribe_orderbook(&mut self, symbol: &Symbol, _depth: Option<u32>) -> Result<()> {
+        if !self.subscriptions.contains(symbol) {
+            self.subscriptions.push(symbol.clone());
+        }
+        Ok(())
+    }
+
+    async fn subscribe_trades(&mut self, symbol: &Symbol) -> Result<()> {
+        // The l2 channel carries trades alongside book updates
+        self.subscribe_orderbook(symbol, None).await
+    }
+
+    async fn subscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_orderbook(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscriptions.retain(|s| s != symbol);
+        Ok(())
+    }
+
+    async fn unsubscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn market_data_stream(&self) -> Result<MarketDataStream> {
+        if self.subscriptions.is_empty() {
+            return Err(ArbFinderError::Exchange("No Gemini symbols subscribed".to_string()));
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let handler = GeminiMarketDataStream::new(self.subscriptions.clone(), tx);
+        let subscribe = handler.subscribe_message();
+        let config = DefaultExchangeConfig {
+            base_url: self.base_url.clone(),
+            websocket_url: self.ws_url.clone(),
+            ..Default::default()
+        };
+
+        spawn_connection(&config, &[subscribe], handler).await?;
+        Ok(channel_stream(rx))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_gemini_adapter_creation() {
+        let adapter = GeminiAdapter::new();
+        assert_eq!(adapter.venue_id(), VenueId::Gemini);
+        assert!(!adapter.is_connected().await);
+    }
+
+    #[test]
+    fn test_parse_gemini_symbol() {
+        assert_eq!(parse_gemini_symbol("btcusd"), Some(Symbol::new("BTC", "USD")));
+        assert_eq!(parse_gemini_symbol("ETHGUSD"), Some(Symbol::new("ETH", "GUSD")));
+        assert_eq!(parse_gemini_symbol("ethbtc"), Some(Symbol::new("ETH", "BTC")));
+        assert_eq!(parse_gemini_symbol("btcusdt"), Some(Symbol::new("BTC", "USDT")));
+        assert_eq!(parse_gemini_symbol("btcgusdperp"), None);
+        assert_eq!(parse_gemini_symbol("usd"), None);
+    }
+
+    #[tokio::test]
+    async fn test_market_data_stream_requires_subscriptions() {
+        let mut adapter = GeminiAdapter::new();
+        assert!(adapter.market_data_stream().await.is_err());
+
+        adapter.subscribe_orderbook(&Symbol::new("BTC", "USD"), None).await.unwrap();
+        adapter.subscribe_trades(&Symbol::new("BTC", "USD")).await.unwrap();
+        assert_eq!(adapter.subscriptions.len(), 1);
+    }
+}
@@ -0,0 +1,233 @@
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use crate::parse_gemini_symbol;
+
+pub const GEMINI_WS_URL: &str = "wss://api.gemini.com/v2/marketdata";
+
+#[derive(Debug, Clone, Deserialize)]
+struct GeminiTrade {
+    symbol: String,
+    event_id: u64,
+    timestamp: i64,
+    price: String,
+    quantity: String,
+    side: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum GeminiMessage {
+    L2Updates {
+        symbol: String,
+        changes: Vec<(String, String, String)>, // [side, price, quantity]
+        #[serde(default)]
+        trades: Vec<GeminiTrade>,
+    },
+    Trade(GeminiTrade),
+    Heartbeat,
+    #[serde(other)]
+    Other,
+}
+
+/// Handles Gemini v2 market data for any number of symbols on one socket.
+/// The first `l2_updates` per symbol is a full snapshot, later ones are deltas.
+pub struct GeminiMarketDataStream {
+    symbols: Vec<Symbol>,
+    books: HashMap<String, OrderBook>,
+    update_tx: mpsc::UnboundedSender<MarketData>,
+}
+
+impl GeminiMarketDataStream {
+    pub fn new(symbols: Vec<Symbol>, update_tx: mpsc::UnboundedSender<MarketData>) -> Self {
+        Self {
+            symbols,
+            books: HashMap::new(),
+            update_tx,
+        }
+    }
+
+    pub fn subscribe_message(&self) -> String {
+        let symbols: Vec<String> = self
+            .symbols
+            .iter()
+            .map(|s| format!("{}{}", s.base(), s.quote()).to_uppercase())
+            .collect();
+
+        serde_json::json!({
+            "type": "subscribe",
+            "subscriptions": [{ "name": "l2", "symbols": symbols }]
+        })
+        .to_string()
+    }
+
+    pub fn get_orderbook(&self, symbol: &Symbol) -> Option<&OrderBook> {
+        let key = format!("{}{}", symbol.base(), symbol.quote()).to_uppercase();
+        self.books.get(&key)
+    }
+
+    fn process_l2_update(&mut self, symbol: String, changes: Vec<(String, String, String)>) -> Result<()> {
+        let parsed = parse_gemini_symbol(&symbol)
+            .ok_or_else(|| ArbFinderError::SymbolNotFound(symbol.clone()))?;
+
+        let book = self
+            .books
+            .entry(symbol)
+            .or_insert_with(|| OrderBook::new(parsed));
+
+        for (side, price_str, qty_str) in changes {
+            let (Ok(price), Ok(quantity)) = (
+                Decimal::from_str(&price_str),
+                Decimal::from_str(&qty_str)
+            ) else {
+                continue;
+            };
+            match side.as_str() {
+                "buy" => book.update_bid(price, quantity),
+                "sell" => book.update_ask(price, quantity),
+                other => warn!("Unknown Gemini book side: {}", other),
+            }
+        }
+
+        let _ = self.update_tx.send(MarketData::OrderBook(book.clone()));
+        Ok(())
+    }
+
+    fn process_trade(&mut self, trade: GeminiTrade) -> Result<()> {
+        let symbol = parse_gemini_symbol(&trade.symbol)
+            .ok_or_else(|| ArbFinderError::SymbolNotFound(trade.symbol.clone()))?;
+
+        let price = Decimal::from_str(&trade.price)
+            .map_err(|e| ArbFinderError::Parse(format!("Invalid Gemini trade price: {}", e)))?;
+        let quantity = Decimal::from_str(&trade.quantity)
+            .map_err(|e| ArbFinderError::Parse(format!("Invalid Gemini trade quantity: {}", e)))?;
+
+        let side = if trade.side == "buy" { Side::Bid } else { Side::Ask };
+        let mut update = Trade::new(symbol, price, quantity, side, trade.event_id.to_string());
+        update.timestamp = DateTime::<Utc>::from_timestamp_millis(trade.timestamp).unwrap_or_else(Utc::now);
+
+        let _ = self.update_tx.send(MarketData::Trade(update));
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl WebSocketHandler for GeminiMarketDataStream {
+    async fn on_message(&mut self, message: &str) -> Result<()> {
+        match serde_json::from_str::<GeminiMessage>(message) {
+            Ok(GeminiMessage::L2Updates { symbol, changes, trades }) => {
+                self.process_l2_update(symbol, changes)?;
+                for trade in trades {
+                    self.process_trade(trade)?;
+                }
+            }
+            Ok(GeminiMessage::Trade(trade)) => self.process_trade(trade)?,
+            Ok(GeminiMessage::Heartbeat) | Ok(GeminiMessage::Other) => {}
+            Err(e) => {
+                debug!("Failed to parse Gemini message: {}", e);
+            }
+        }
+        Ok(())
+    }
+
+    async fn on_connect(&mut self) -> Result<()> {
+        info!("Gemini WebSocket connected for {} symbols", self.symbols.len());
+        Ok(())
+    }
+
+    async fn on_disconnect(&mut self) -> Result<()> {
+        warn!("Gemini WebSocket disconnected");
+        // The next l2_updates after a resubscribe is a fresh snapshot
+        self.books.clear();
+        Ok(())
+    }
+
+    async fn on_error(&mut self, error: &ArbFinderError) -> Result<()> {
+        error!("Gemini WebSocket error: {}", error);
+        Ok(())
+    }
+
+    async fn on_ping(&mut self) -> Result<()> {
+        debug!("Received Gemini ping");
+        Ok(())
+    }
+
+    async fn on_pong(&mut self) -> Result<()> {
+        debug!("Received Gemini pong");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscribe_message() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let stream = GeminiMarketDataStream::new(
+            vec![Symbol::new("BTC", "USD"), Symbol::new("ETH", "GUSD")],
+            tx,
+        );
+
+        let message: serde_json::Value = serde_json::from_str(&stream.subscribe_message()).unwrap();
+        assert_eq!(message["type"], "subscribe");
+        assert_eq!(message["subscriptions"][0]["name"], "l2");
+        assert_eq!(message["subscriptions"][0]["symbols"][1], "ETHGUSD");
+    }
+
+    #[tokio::test]
+    async fn test_l2_snapshot_and_trades() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut stream = GeminiMarketDataStream::new(vec![Symbol::new("BTC", "USD")], tx);
+
+        let snapshot = r#"{
+            "type": "l2_updates",
+            "symbol": "BTCUSD",
+            "changes": [
+                ["buy", "9122.04", "0.00121425"],
+                ["sell", "9122.07", "0.98942292"]
+            ],
+            "trades": [{
+                "type": "trade",
+                "symbol": "BTCUSD",
+                "event_id": 169841458,
+                "timestamp": 1560976400428,
+                "price": "9122.04",
+                "quantity": "0.0073173",
+                "side": "sell"
+            }]
+        }"#;
+        stream.on_message(snapshot).await.unwrap();
+
+        match rx.recv().await.unwrap() {
+            MarketData::OrderBook(book) => {
+                assert_eq!(book.symbol, Symbol::new("BTC", "USD"));
+                assert_eq!(book.best_bid().unwrap().price, Decimal::from_str("9122.04").unwrap());
+                assert_eq!(book.best_ask().unwrap().price, Decimal::from_str("9122.07").unwrap());
+            }
+            other => panic!("expected order book, got {:?}", other),
+        }
+
+        match rx.recv().await.unwrap() {
+            MarketData::Trade(trade) => {
+                assert_eq!(trade.side, Side::Ask);
+                assert_eq!(trade.trade_id, "169841458");
+            }
+            other => panic!("expected trade, got {:?}", other),
+        }
+
+        let delta = r#"{"type":"l2_updates","symbol":"BTCUSD","changes":[["buy","9122.04","0"]]}"#;
+        stream.on_message(delta).await.unwrap();
+        let book = stream.get_orderbook(&Symbol::new("BTC", "USD")).unwrap();
+        assert!(book.best_bid().is_none());
+    }
+}
@@ -0,0 +1,307 @@
+//! HTX (Huobi) Exchange Adapter
+//!
+//! Implementation of MarketDataAdapter for HTX
+
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use arbfinder_exchange::{channel_stream, spawn_connection_with, DefaultExchangeConfig};
+use async_trait::async_trait;
+use reqwest::Client;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use tokio::sync::mpsc;
+
+pub mod websocket;
+pub use websocket::HtxMarketDataStream;
+
+const HTX_API_URL: &str = "https://api.huobi.pro";
+
+/// HTX symbols are the lowercase base and quote run together, e.g. `btcusdt`.
+pub fn htx_symbol(symbol: &Symbol) -> String {
+    format!("{}{}", symbol.base(), symbol.quote()).to_lowercase()
+}
+
+fn value_to_decimal(value: &serde_json::Value) -> Option<Decimal> {
+    match value {
+        serde_json::Value::String(s) => s.parse().ok(),
+        serde_json::Value::Number(n) => n.as_f64().and_then(Decimal::from_f64),
+        _ => None,
+    }
+}
+
+pub struct HtxAdapter {
+    client: Client,
+    base_url: String,
+    ws_url: String,
+    connected: bool,
+    subscriptions: Vec<Symbol>,
+}
+
+impl HtxAdapter {
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+            base_url: HTX_API_URL.to_string(),
+            ws_url: websocket::HTX_WS_URL.to_string(),
+            connected: false,
+            subscriptions: Vec::new(),
+        }
+    }
+
+    /// HTX wraps every REST response in `{"status": "ok", "data": ...}`; market
+    /// endpoints use `tick` instead of `data`, so the full body is returned.
+    async fn get_request(&self, endpoint: &str) -> Result<serde_json::Value> {
+        let url = format!("{}{}", self.base_url, endpoint);
+        let response = self.client
+            .get(&url)
+            .send()
+            .await
+            .map_err(ArbFinderError::Http)?;
+
+        if !response.status().is_success() {
+            return Err(ArbFinderError::Exchange(format!(
+                "HTX API error: {}",
+                response.status()
+            )));
+        }
+
+        let body: serde_json::Value = response.json().await.map_err(ArbFinderError::Http)?;
+        if body["status"].as_str() == Some("error") {
+            return Err(ArbFinderError::Exchange(format!(
+                "HTX API error: {}",
+                body["err-msg"].as_str().unwrap_or("unknown")
+            )));
+        }
+
+        Ok(body)
+    }
+
+    /// Fetch the merged 24h ticker for a symbol
+    pub async fn get_ticker(&self, symbol: &Symbol) -> Result<Ticker> {
+        let endpoint = format!("/market/detail/merged?symbol={}", htx_symbol(symbol));
+        let response = self.get_request(&endpoint).await?;
+        let tick = &response["tick"];
+
+        let price = value_to_decimal(&tick["close"])
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing close price".to_string()))?;
+        let open = value_to_decimal(&tick["open"]).unwrap_or(price);
+
+        Ok(Ticker {
+            symbol: symbol.clone(),
+            price,
+            volume_24h: value_to_decimal(&tick["amount"]).unwrap_or(Decimal::ZERO),
+            change_24h: price - open,
+            timestamp: response["ts"]
+                .as_i64()
+                .and_then(DateTime::from_timestamp_millis)
+                .unwrap_or_else(Utc::now),
+        })
+    }
+
+    /// Fetch orderbook depth from HTX
+    pub async fn get_orderbook(&self, symbol: &Symbol) -> Result<OrderBook> {
+        let endpoint = format!("/market/depth?symbol={}&type=step0", htx_symbol(symbol));
+        let response = self.get_request(&endpoint).await?;
+
+        let mut orderbook = OrderBook::new(symbol.clone());
+        if let Some(bids) = response["tick"]["bids"].as_array() {
+            for bid in bids {
+                if let (Some(price), Some(qty)) = (value_to_decimal(&bid[0]), value_to_decimal(&bid[1])) {
+                    orderbook.update_bid(price, qty);
+                }
+            }
+        }
+        if let Some(asks) = response["tick"]["asks"].as_array() {
+            for ask in asks {
+                if let (Some(price), Some(qty)) = (value_to_decimal(&ask[0]), value_to_decimal(&ask[1])) {
+                    orderbook.update_ask(price, qty);
+                }
+            }
+        }
+        orderbook.sequence = response["tick"]["version"].as_u64();
+
+        Ok(orderbook)
+    }
+}
+
+impl Default for HtxAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl MarketDataAdapter for HtxAdapter {
+    fn venue_id(&self) -> VenueId {
+        VenueId::Huobi
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        // Test connection with server time
+        let _ = self.get_server_time().await?;
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.connected = false;
+        Ok(())
+    }
+
+    async fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        let response = self.get_request("/v1/common/timestamp").await?;
+        let server_time = response["data"]
+            .as_i64()
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing timestamp".to_string()))?;
+
+        Ok(DateTime::from_timestamp_millis(server_time)
+            .unwrap_or_else(Utc::now))
+    }
+
+    async fn ping(&self) -> Result<u64> {
+        let start = std::time::Instant::now();
+        let _ = self.get_request("/v1/common/timestamp").await?;
+        Ok(start.elapsed().as_millis() as u64)
+    }
+
+    async fn get_symbols(&self) -> Result<Vec<Symbol>> {
+        let response = self.get_request("/v1/common/symbols").await?;
+        let symbols = response["data"]
+            .as_array()
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing symbols array".to_string()))?;
+
+        let mut result = Vec::new();
+        for symbol_data in symbols {
+            if symbol_data["state"].as_str() != Some("online") {
+                continue;
+            }
+            if let (Some(base), Some(quote)) = (
+                symbol_data["base-currency"].as_str(),
+                symbol_data["quote-currency"].as_str(),
+            ) {
+                result.push(Symbol::new(base.to_uppercase(), quote.to_uppercase()));
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn get_symbol_info(&self, symbol: &Symbol) -> Result<SymbolInfo> {
+        let response = self.get_request("/v1/common/symbols").await?;
+        let symbols = response["data"].as_array().ok_or_else(|| {
+            ArbFinderError::InvalidData("Missing symbols array".to_string())
+        })?;
+
+        let symbol_str = htx_symbol(symbol);
+
+        for symbol_data in symbols {
+            if symbol_data["symbol"].as_str() == Some(&symbol_str) {
+                let price_precision = symbol_data["price-precision"].as_u64().unwrap_or(8) as u32;
+                let amount_precision = symbol_data["amount-precision"].as_u64().unwrap_or(8) as u32;
+
+                return Ok(SymbolInfo {
+                    symbol: symbol.clone(),
+                    status: if symbol_data["state"].as_str() == Some("online") {
+                        "TRADING".to_string()
+                    } else {
+                        "INACTIVE".to_string()
+                    },
+                    base_asset_precision: amount_precision,
+                    quote_asset_precision: price_precision,
+                    tick_size: Decimal::new(1, price_precision),
+                    lot_size: Decimal::new(1, amount_precision),
+                    min_order_size: value_to_decimal(&symbol_data["min-order-amt"])
+                        .unwrap_or(Decimal::new(1, amount_precision)),
+                    max_order_size: value_to_decimal(&symbol_data["max-order-amt"])
+                        .unwrap_or(Decimal::new(1000000, 0)),
+                    min_notional: value_to_decimal(&symbol_data["min-order-value"])
+                        .unwrap_or(Decimal::new(5, 0)),
+                    trading_fees: TradingFees {
+                        maker_fee: Decimal::new(2, 3), // 0.2%
+                        taker_fee: Decimal::new(2, 3), // 0.2%
+                    },
+                });
+            }
+        }
+
+        Err(ArbFinderError::SymbolNotFound(symbol_str))
+    }
+
+    async fn subscribe_orderbook(&mut self, symbol: &Symbol, _depth: Option<u32>) -> Result<()> {
+        if !self.subscriptions.contains(symbol) {
+            self.subscriptions.push(symbol.clone());
+        }
+        Ok(())
+    }
+
+    async fn subscribe_trades(&mut self, symbol: &Symbol) -> Result<()> {
+        // Depth and trade channels are subscribed together per symbol
+        self.subscribe_orderbook(symbol, None).await
+    }
+
+    async fn subscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_orderbook(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscriptions.retain(|s| s != symbol);
+        Ok(())
+    }
+
+    async fn unsubscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn market_data_stream(&self) -> Result<MarketDataStream> {
+        if self.subscriptions.is_empty() {
+            return Err(ArbFinderError::Exchange("No HTX symbols subscribed".to_string()));
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let symbols = self.subscriptions.clone();
+        let subscribe = websocket::subscribe_messages(&symbols);
+        let config = DefaultExchangeConfig {
+            base_url: self.base_url.clone(),
+            websocket_url: self.ws_url.clone(),
+            frame_encoding: FrameEncoding::Gzip,
+            ..Default::default()
+        };
+
+        spawn_connection_with(&config, &subscribe, |outbound| {
+            HtxMarketDataStream::new(symbols, tx, outbound)
+        })
+        .await?;
+        Ok(channel_stream(rx))
+    }
+
+    async fn fetch_orderbook(&self, symbol: &Symbol, _depth: Option<u32>) -> Result<OrderBook> {
+        self.get_orderbook(symbol).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_htx_adapter_creation() {
+        let adapter = HtxAdapter::new();
+        assert_eq!(adapter.venue_id(), VenueId::Huobi);
+        assert!(!adapter.is_connected().await);
+    }
+
+    #[test]
+    fn test_htx_symbol_format() {
+        assert_eq!(htx_symbol(&Symbol::new("BTC", "USDT")), "btcusdt");
+        assert_eq!(htx_symbol(&Symbol::new("eth", "BTC")), "ethbtc");
+    }
+}
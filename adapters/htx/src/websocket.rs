@@ -0,0 +1,297 @@
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use serde::Deserialize;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use crate::htx_symbol;
+
+pub const HTX_WS_URL: &str = "wss://api.huobi.pro/ws";
+
+#[derive(Debug, Clone, Deserialize)]
+struct HtxDepthTick {
+    bids: Vec<(f64, f64)>, // [price, amount]
+    asks: Vec<(f64, f64)>,
+    #[serde(default)]
+    version: Option<u64>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct HtxTradeTick {
+    data: Vec<HtxTrade>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct HtxTrade {
+    #[serde(rename = "tradeId")]
+    trade_id: u64,
+    ts: i64,
+    amount: f64,
+    price: f64,
+    direction: String,
+}
+
+/// Decompresses an HTX frame. Every market data message, pings included, is
+/// a gzip-compressed JSON document sent as a binary frame. Connections set
+/// up with [`FrameEncoding::Gzip`] hand over frames already decompressed,
+/// which pass through.
+pub fn decompress_htx_frame(data: &[u8]) -> Result<String> {
+    let decoded = FrameEncoding::Detect.decode(data)?;
+    String::from_utf8(decoded.into_owned())
+        .map_err(|e| ArbFinderError::WebSocket(format!("HTX frame is not UTF-8: {}", e)))
+}
+
+/// One `sub` request per channel; HTX has no batch subscribe.
+pub fn subscribe_messages(symbols: &[Symbol]) -> Vec<String> {
+    let mut messages = Vec::new();
+    for symbol in symbols {
+        let htx = htx_symbol(symbol);
+        for channel in ["depth.step0", "trade.detail"] {
+            messages.push(
+                serde_json::json!({
+                    "sub": format!("market.{}.{}", htx, channel),
+                    "id": format!("{}.{}", htx, channel),
+                })
+                .to_string(),
+            );
+        }
+    }
+    messages
+}
+
+/// Handles HTX depth and trade channels. `depth.step0` pushes full snapshots,
+/// so each message replaces the book rather than patching it.
+pub struct HtxMarketDataStream {
+    symbols: HashMap<String, Symbol>,
+    update_tx: mpsc::UnboundedSender<MarketData>,
+    outbound_tx: mpsc::UnboundedSender<String>,
+}
+
+impl HtxMarketDataStream {
+    pub fn new(
+        symbols: Vec<Symbol>,
+        update_tx: mpsc::UnboundedSender<MarketData>,
+        outbound_tx: mpsc::UnboundedSender<String>,
+    ) -> Self {
+        Self {
+            symbols: symbols.into_iter().map(|s| (htx_symbol(&s), s)).collect(),
+            update_tx,
+            outbound_tx,
+        }
+    }
+
+    pub fn subscribe_messages(&self) -> Vec<String> {
+        let symbols: Vec<Symbol> = self.symbols.values().cloned().collect();
+        subscribe_messages(&symbols)
+    }
+
+    fn process_depth(&self, symbol: &Symbol, tick: HtxDepthTick) {
+        let mut book = OrderBook::new(symbol.clone());
+        for (price, amount) in tick.bids {
+            if let (Some(price), Some(amount)) = (Decimal::from_f64(price), Decimal::from_f64(amount)) {
+                book.update_bid(price, amount);
+            }
+        }
+        for (price, amount) in tick.asks {
+            if let (Some(price), Some(amount)) = (Decimal::from_f64(price), Decimal::from_f64(amount)) {
+                book.update_ask(price, amount);
+            }
+        }
+        book.sequence = tick.version;
+
+        let _ = self.update_tx.send(MarketData::OrderBook(book));
+    }
+
+    fn process_trades(&self, symbol: &Symbol, tick: HtxTradeTick) {
+        for trade in tick.data {
+            let (Some(price), Some(quantity)) = (Decimal::from_f64(trade.price), Decimal::from_f64(trade.amount)) else {
+                continue;
+            };
+            let side = if trade.direction == "buy" { Side::Bid } else { Side::Ask };
+            let mut update = Trade::new(symbol.clone(), price, quantity, side, trade.trade_id.to_string());
+            update.timestamp = DateTime::<Utc>::from_timestamp_millis(trade.ts).unwrap_or_else(Utc::now);
+
+            let _ = self.update_tx.send(MarketData::Trade(update));
+        }
+    }
+}
+
+#[async_trait]
+impl WebSocketHandler for HtxMarketDataStream {
+    async fn on_message(&mut self, message: &str) -> Result<()> {
+        let value: serde_json::Value = serde_json::from_str(message)?;
+
+        // Application-level heartbeat; the server drops us after missing two
+        if let Some(ping) = value.get("ping") {
+            let pong = serde_json::json!({ "pong": ping }).to_string();
+            let _ = self.outbound_tx.send(pong);
+            return Ok(());
+        }
+
+        if let Some(status) = value.get("status").and_then(|s| s.as_str()) {
+            if status != "ok" {
+                warn!("HTX subscription error: {}", message);
+            }
+            return Ok(());
+        }
+
+        let Some(channel) = value.get("ch").and_then(|c| c.as_str()) else {
+            debug!("Ignoring HTX message: {}", message);
+            return Ok(());
+        };
+
+        // market.<symbol>.<channel...>
+        let mut parts = channel.splitn(3, '.');
+        let (Some("market"), Some(htx_sym), Some(kind)) = (parts.next(), parts.next(), parts.next()) else {
+            return Ok(());
+        };
+        let Some(symbol) = self.symbols.get(htx_sym) else {
+            return Ok(());
+        };
+
+        let tick = value["tick"].clone();
+        if kind.starts_with("depth") {
+            self.process_depth(symbol, serde_json::from_value(tick)?);
+        } else if kind == "trade.detail" {
+            self.process_trades(symbol, serde_json::from_value(tick)?);
+        }
+
+        Ok(())
+    }
+
+    async fn on_binary(&mut self, data: &[u8]) -> Result<()> {
+        let text = decompress_htx_frame(data)?;
+        self.on_message(&text).await
+    }
+
+    async fn on_connect(&mut self) -> Result<()> {
+        info!("HTX WebSocket connected for {} symbols", self.symbols.len());
+        Ok(())
+    }
+
+    async fn on_disconnect(&mut self) -> Result<()> {
+        warn!("HTX WebSocket disconnected");
+        Ok(())
+    }
+
+    async fn on_error(&mut self, error: &ArbFinderError) -> Result<()> {
+        error!("HTX WebSocket error: {}", error);
+        Ok(())
+    }
+
+    async fn on_ping(&mut self) -> Result<()> {
+        debug!("Received HTX ping");
+        Ok(())
+    }
+
+    async fn on_pong(&mut self) -> Result<()> {
+        debug!("Received HTX pong");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+    use std::str::FromStr;
+
+    fn gzip(text: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    fn stream() -> (
+        HtxMarketDataStream,
+        mpsc::UnboundedReceiver<MarketData>,
+        mpsc::UnboundedReceiver<String>,
+    ) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (out_tx, out_rx) = mpsc::unbounded_channel();
+        (
+            HtxMarketDataStream::new(vec![Symbol::new("BTC", "USDT")], tx, out_tx),
+            rx,
+            out_rx,
+        )
+    }
+
+    #[tokio::test]
+    async fn test_answers_gzipped_ping() {
+        let (mut stream, _rx, mut out_rx) = stream();
+        stream.on_binary(&gzip(r#"{"ping":1492420473027}"#)).await.unwrap();
+
+        assert_eq!(out_rx.recv().await.unwrap(), r#"{"pong":1492420473027}"#);
+    }
+
+    #[tokio::test]
+    async fn test_depth_snapshot() {
+        let (mut stream, mut rx, _out_rx) = stream();
+        let message = r#"{
+            "ch": "market.btcusdt.depth.step0",
+            "ts": 1630983549503,
+            "tick": {
+                "bids": [[52690.69, 0.36281], [52690.68, 0.2]],
+                "asks": [[52690.7, 0.372591], [52691.26, 0.13]],
+                "version": 136998124622,
+                "ts": 1630983549500
+            }
+        }"#;
+        stream.on_binary(&gzip(message)).await.unwrap();
+
+        match rx.recv().await.unwrap() {
+            MarketData::OrderBook(book) => {
+                assert_eq!(book.symbol, Symbol::new("BTC", "USDT"));
+                assert_eq!(book.best_bid().unwrap().price, Decimal::from_str("52690.69").unwrap());
+                assert_eq!(book.best_ask().unwrap().price, Decimal::from_str("52690.7").unwrap());
+                assert_eq!(book.sequence, Some(136998124622));
+            }
+            other => panic!("expected order book, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_trade_detail() {
+        let (mut stream, mut rx, _out_rx) = stream();
+        let message = r#"{
+            "ch": "market.btcusdt.trade.detail",
+            "ts": 1630994963175,
+            "tick": {
+                "id": 137005445109,
+                "ts": 1630994963173,
+                "data": [{
+                    "id": 137005445109359286410323766,
+                    "ts": 1630994963173,
+                    "tradeId": 102523573486,
+                    "amount": 0.006754,
+                    "price": 52648.62,
+                    "direction": "buy"
+                }]
+            }
+        }"#;
+        stream.on_message(message).await.unwrap();
+
+        match rx.recv().await.unwrap() {
+            MarketData::Trade(trade) => {
+                assert_eq!(trade.side, Side::Bid);
+                assert_eq!(trade.trade_id, "102523573486");
+            }
+            other => panic!("expected trade, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_subscribe_messages() {
+        let (stream, _rx, _out_rx) = stream();
+        let messages = stream.subscribe_messages();
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].contains("market.btcusdt.depth.step0"));
+        assert!(messages[1].contains("market.btcusdt.trade.detail"));
+    }
+}
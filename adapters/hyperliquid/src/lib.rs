@@ -0,0 +1,423 @@
+//! Hyperliquid Exchange Adapter
+//!
+//! Public market data for Hyperliquid perpetuals: asset metadata, L2 books,
+//! trades, mark prices and funding from the `info` endpoint and WebSocket
+//! API.
+//!
+//! Perpetuals are quoted in USD and settled in USDC. A symbol's base is the
+//! Hyperliquid coin name, which is case-sensitive (`kPEPE`), so `BTC/USD` is
+//! the `BTC` perpetual; the quote is not part of the name.
+
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use arbfinder_exchange::{channel_stream, spawn_connection_with, DefaultExchangeConfig};
+use async_trait::async_trait;
+use reqwest::{Client, StatusCode};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+pub mod websocket;
+pub use websocket::HyperliquidMarketDataStream;
+
+const HYPERLIQUID_API_URL: &str = "https://api.hyperliquid.xyz";
+
+/// Perpetual prices carry at most this many decimals less the asset's size
+/// decimals.
+const MAX_PERP_DECIMALS: u32 = 6;
+
+/// The server drops connections that have sent nothing for a minute.
+const HYPERLIQUID_WS_PING_INTERVAL: Duration = Duration::from_secs(50);
+
+/// Hyperliquid coin for a symbol: its base, unchanged.
+pub fn hyperliquid_coin(symbol: &Symbol) -> String {
+    symbol.base().to_string()
+}
+
+/// Hyperliquid sends prices and sizes as decimal strings.
+fn value_to_decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::String(s) => Decimal::from_str(s).ok(),
+        Value::Number(n) => Decimal::from_str(&n.to_string()).ok(),
+        _ => None,
+    }
+}
+
+fn millis_to_datetime(value: &Value) -> DateTime<Utc> {
+    value
+        .as_i64()
+        .and_then(DateTime::<Utc>::from_timestamp_millis)
+        .unwrap_or_else(Utc::now)
+}
+
+/// Hourly rates from a `fundingHistory` request, oldest first.
+fn parse_funding_history(symbol: &Symbol, result: &Value) -> Vec<FundingRate> {
+    let mut rates: Vec<FundingRate> = result
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| {
+            let current_rate = value_to_decimal(&entry["fundingRate"])?;
+            Some(FundingRate {
+                symbol: symbol.clone(),
+                current_rate,
+                rate_8h: Some(current_rate * Decimal::from(8)),
+                timestamp: DateTime::<Utc>::from_timestamp_millis(entry["time"].as_i64()?)?,
+            })
+        })
+        .collect();
+    rates.sort_by_key(|rate| rate.timestamp);
+    rates
+}
+
+/// Builds instrument metadata from an entry of the `meta` universe.
+pub fn parse_perp(asset: &Value) -> Option<InstrumentInfo> {
+    let name = asset["name"].as_str()?.to_string();
+    let sz_decimals = asset["szDecimals"].as_u64()? as u32;
+    let price_decimals = MAX_PERP_DECIMALS.saturating_sub(sz_decimals);
+    let tick_size = Decimal::new(1, price_decimals);
+    let lot_size = Decimal::new(1, sz_decimals);
+    let delisted = asset["isDelisted"].as_bool().unwrap_or(false);
+
+    Some(InstrumentInfo {
+        name: name.clone(),
+        kind: InstrumentKind::Perpetual,
+        underlying: name.clone(),
+        settlement_currency: "USDC".to_string(),
+        contract_size: Decimal::ONE,
+        inverse: false,
+        symbol_info: SymbolInfo {
+            symbol: Symbol::new(name, "USD"),
+            status: if delisted { "closed" } else { "open" }.to_string(),
+            base_asset_precision: sz_decimals,
+            quote_asset_precision: price_decimals,
+            tick_size,
+            lot_size,
+            min_order_size: lot_size,
+            max_order_size: Decimal::new(1000000, 0),
+            min_notional: Decimal::from(10),
+            trading_fees: TradingFees {
+                maker_fee: Decimal::new(15, 5),   // 0.015%
+                taker_fee: Decimal::new(45, 5),   // 0.045%
+            },
+        },
+    })
+}
+
+pub struct HyperliquidAdapter {
+    client: Client,
+    base_url: String,
+    ws_url: String,
+    connected: bool,
+    subscriptions: Vec<Symbol>,
+}
+
+impl HyperliquidAdapter {
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+            base_url: HYPERLIQUID_API_URL.to_string(),
+            ws_url: websocket::HYPERLIQUID_WS_URL.to_string(),
+            connected: false,
+            subscriptions: Vec::new(),
+        }
+    }
+
+    /// Posts a request to the `info` endpoint. Every public query goes
+    /// through it, selected by the body's `type`.
+    async fn info_request(&self, body: Value) -> Result<Value> {
+        let url = format!("{}/info", self.base_url);
+        let response = self.client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(ArbFinderError::Http)?;
+
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(ArbFinderError::RateLimit("Hyperliquid info request limit".to_string()));
+        }
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(ArbFinderError::Exchange(format!("Hyperliquid API error {}: {}", status, text)));
+        }
+
+        response.json().await.map_err(ArbFinderError::Http)
+    }
+
+    /// Perpetual universe with each asset's current context (mark, oracle,
+    /// funding, volume), paired by index.
+    async fn get_meta_and_contexts(&self) -> Result<Vec<(Value, Value)>> {
+        let result = self
+            .info_request(serde_json::json!({ "type": "metaAndAssetCtxs" }))
+            .await?;
+        let universe = result[0]["universe"].as_array();
+        let contexts = result[1].as_array();
+        let (Some(universe), Some(contexts)) = (universe, contexts) else {
+            return Err(ArbFinderError::InvalidData("Missing asset contexts".to_string()));
+        };
+
+        Ok(universe.iter().cloned().zip(contexts.iter().cloned()).collect())
+    }
+
+    async fn get_asset_context(&self, symbol: &Symbol) -> Result<Value> {
+        let coin = hyperliquid_coin(symbol);
+        self.get_meta_and_contexts()
+            .await?
+            .into_iter()
+            .find(|(asset, _)| asset["name"].as_str() == Some(coin.as_str()))
+            .map(|(_, context)| context)
+            .ok_or(ArbFinderError::SymbolNotFound(coin))
+    }
+
+    pub async fn get_instruments(&self) -> Result<Vec<InstrumentInfo>> {
+        let meta = self.info_request(serde_json::json!({ "type": "meta" })).await?;
+        let universe = meta["universe"]
+            .as_array()
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing universe".to_string()))?;
+
+        Ok(universe.iter().filter_map(parse_perp).collect())
+    }
+
+    pub async fn get_instrument(&self, symbol: &Symbol) -> Result<InstrumentInfo> {
+        let coin = hyperliquid_coin(symbol);
+        self.get_instruments()
+            .await?
+            .into_iter()
+            .find(|instrument| instrument.name == coin)
+            .ok_or(ArbFinderError::SymbolNotFound(coin))
+    }
+
+    /// Fetch the ticker for a perpetual
+    pub async fn get_ticker(&self, symbol: &Symbol) -> Result<Ticker> {
+        let context = self.get_asset_context(symbol).await?;
+        let price = value_to_decimal(&context["midPx"])
+            .or_else(|| value_to_decimal(&context["markPx"]))
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing price".to_string()))?;
+        let change_24h = value_to_decimal(&context["prevDayPx"])
+            .map(|prev| price - prev)
+            .unwrap_or(Decimal::ZERO);
+
+        Ok(Ticker {
+            symbol: symbol.clone(),
+            price,
+            volume_24h: value_to_decimal(&context["dayBaseVlm"]).unwrap_or(Decimal::ZERO),
+            change_24h,
+            timestamp: Utc::now(),
+        })
+    }
+
+    pub async fn get_mark_price(&self, symbol: &Symbol) -> Result<MarkPrice> {
+        let context = self.get_asset_context(symbol).await?;
+        websocket::mark_price_from(symbol, &context, Utc::now())
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing mark price".to_string()))
+    }
+
+    /// Fetch the L2 book from Hyperliquid. The endpoint returns at most 20
+    /// levels per side.
+    pub async fn get_orderbook(&self, symbol: &Symbol) -> Result<OrderBook> {
+        let data = self
+            .info_request(serde_json::json!({ "type": "l2Book", "coin": hyperliquid_coin(symbol) }))
+            .await?;
+        if data.is_null() {
+            return Err(ArbFinderError::SymbolNotFound(hyperliquid_coin(symbol)));
+        }
+
+        Ok(websocket::book_from(symbol, &data))
+    }
+}
+
+impl Default for HyperliquidAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl MarketDataAdapter for HyperliquidAdapter {
+    fn venue_id(&self) -> VenueId {
+        VenueId::Hyperliquid
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        let _ = self.info_request(serde_json::json!({ "type": "allMids" })).await?;
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.connected = false;
+        Ok(())
+    }
+
+    async fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        // There is no time endpoint; book snapshots are stamped by the server
+        let data = self
+            .info_request(serde_json::json!({ "type": "l2Book", "coin": "BTC" }))
+            .await?;
+        data["time"]
+            .as_i64()
+            .and_then(DateTime::<Utc>::from_timestamp_millis)
+            .ok_or_else(|| ArbFinderError::InvalidData("Invalid timestamp".to_string()))
+    }
+
+    async fn ping(&self) -> Result<u64> {
+        let start = std::time::Instant::now();
+        let _ = self.info_request(serde_json::json!({ "type": "allMids" })).await?;
+        Ok(start.elapsed().as_millis() as u64)
+    }
+
+    async fn get_symbols(&self) -> Result<Vec<Symbol>> {
+        Ok(self
+            .get_instruments()
+            .await?
+            .into_iter()
+            .filter(|instrument| instrument.symbol_info.status == "open")
+            .map(|instrument| instrument.symbol_info.symbol)
+            .collect())
+    }
+
+    async fn get_symbol_info(&self, symbol: &Symbol) -> Result<SymbolInfo> {
+        Ok(self.get_instrument(symbol).await?.symbol_info)
+    }
+
+    async fn subscribe_orderbook(&mut self, symbol: &Symbol, _depth: Option<u32>) -> Result<()> {
+        if !self.subscriptions.contains(symbol) {
+            self.subscriptions.push(symbol.clone());
+        }
+        Ok(())
+    }
+
+    async fn subscribe_trades(&mut self, symbol: &Symbol) -> Result<()> {
+        // Book, trade and asset context feeds are subscribed together per coin
+        self.subscribe_orderbook(symbol, None).await
+    }
+
+    async fn subscribe_ticker(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscribe_orderbook(symbol, None).await
+    }
+
+    async fn unsubscribe_orderbook(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscriptions.retain(|s| s != symbol);
+        Ok(())
+    }
+
+    async fn unsubscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn market_data_stream(&self) -> Result<MarketDataStream> {
+        if self.subscriptions.is_empty() {
+            return Err(ArbFinderError::Exchange("No Hyperliquid coins subscribed".to_string()));
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let subscribe = websocket::subscribe_messages(&self.subscriptions);
+        let config = DefaultExchangeConfig {
+            base_url: self.base_url.clone(),
+            websocket_url: self.ws_url.clone(),
+            ..Default::default()
+        };
+        let symbols = self.subscriptions.clone();
+
+        spawn_connection_with(&config, &subscribe, move |outbound| {
+            let keepalive = outbound.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(HYPERLIQUID_WS_PING_INTERVAL);
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    if keepalive.send(websocket::ping_message()).is_err() {
+                        break;
+                    }
+                }
+            });
+            HyperliquidMarketDataStream::new(symbols, tx)
+        })
+        .await?;
+        Ok(channel_stream(rx))
+    }
+
+    async fn fetch_orderbook(&self, symbol: &Symbol, _depth: Option<u32>) -> Result<OrderBook> {
+        self.get_orderbook(symbol).await
+    }
+
+    /// Current hourly funding of a perpetual.
+    async fn get_funding_rate(&self, symbol: &Symbol) -> Result<FundingRate> {
+        let context = self.get_asset_context(symbol).await?;
+        websocket::funding_from(symbol, &context, Utc::now())
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing funding rate".to_string()))
+    }
+
+    async fn get_funding_history(&self, symbol: &Symbol, limit: u32) -> Result<Vec<FundingRate>> {
+        // Funding settles hourly, and a request returns at most 500
+        let limit = limit.clamp(1, 500);
+        let start = Utc::now() - chrono::Duration::hours(limit as i64);
+        let result = self
+            .info_request(serde_json::json!({
+                "type": "fundingHistory",
+                "coin": hyperliquid_coin(symbol),
+                "startTime": start.timestamp_millis(),
+            }))
+            .await?;
+
+        let mut rates = parse_funding_history(symbol, &result);
+        let skip = rates.len().saturating_sub(limit as usize);
+        rates.drain(..skip);
+        Ok(rates)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_funding_history() {
+        let result = serde_json::json!([
+            {"coin": "ETH", "fundingRate": "-0.00022196", "premium": "-0.00052196", "time": 1683849600076u64},
+            {"coin": "ETH", "fundingRate": "0.0000125", "premium": "0.0001", "time": 1683853200041u64}
+        ]);
+        let rates = parse_funding_history(&Symbol::new("ETH", "USD"), &result);
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0].current_rate, Decimal::from_str("-0.00022196").unwrap());
+        assert_eq!(rates[1].rate_8h, Some(Decimal::from_str("0.0001").unwrap()));
+        assert_eq!(rates[1].timestamp.timestamp_millis(), 1683853200041);
+    }
+
+    #[tokio::test]
+    async fn test_hyperliquid_adapter_creation() {
+        let adapter = HyperliquidAdapter::new();
+        assert_eq!(adapter.venue_id(), VenueId::Hyperliquid);
+        assert!(!adapter.is_connected().await);
+    }
+
+    #[test]
+    fn test_parse_perp() {
+        let asset: Value = serde_json::from_str(r#"{"name":"BTC","szDecimals":5,"maxLeverage":40}"#).unwrap();
+        let info = parse_perp(&asset).unwrap();
+        assert_eq!(info.kind, InstrumentKind::Perpetual);
+        assert_eq!(info.symbol_info.symbol, Symbol::new("BTC", "USD"));
+        assert_eq!(info.symbol_info.tick_size, Decimal::new(1, 1));
+        assert_eq!(info.symbol_info.lot_size, Decimal::new(1, 5));
+        assert_eq!(hyperliquid_coin(&info.symbol_info.symbol), "BTC");
+
+        let delisted: Value = serde_json::from_str(r#"{"name":"kPEPE","szDecimals":0,"maxLeverage":10,"isDelisted":true}"#).unwrap();
+        let info = parse_perp(&delisted).unwrap();
+        assert_eq!(info.symbol_info.status, "closed");
+        assert_eq!(info.symbol_info.tick_size, Decimal::new(1, 6));
+        assert_eq!(info.name, "kPEPE");
+    }
+}
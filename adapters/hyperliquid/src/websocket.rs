@@ -0,0 +1,277 @@
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use crate::{hyperliquid_coin, millis_to_datetime, value_to_decimal};
+
+pub const HYPERLIQUID_WS_URL: &str = "wss://api.hyperliquid.xyz/ws";
+
+/// One `subscribe` request per feed: L2 book, trades and the asset context
+/// carrying mark price and funding, for each symbol.
+pub fn subscribe_messages(symbols: &[Symbol]) -> Vec<String> {
+    symbols
+        .iter()
+        .flat_map(|symbol| {
+            let coin = hyperliquid_coin(symbol);
+            ["l2Book", "trades", "activeAssetCtx"].map(|kind| {
+                serde_json::json!({
+                    "method": "subscribe",
+                    "subscription": { "type": kind, "coin": coin },
+                })
+                .to_string()
+            })
+        })
+        .collect()
+}
+
+pub fn ping_message() -> String {
+    serde_json::json!({ "method": "ping" }).to_string()
+}
+
+/// Builds a book from an `l2Book` payload, `levels` being `[bids, asks]` of
+/// `{px, sz, n}` entries.
+pub(crate) fn book_from(symbol: &Symbol, data: &Value) -> OrderBook {
+    let mut book = OrderBook::new(symbol.clone());
+    for (side, levels) in [Side::Bid, Side::Ask].into_iter().zip([&data["levels"][0], &data["levels"][1]]) {
+        for level in levels.as_array().into_iter().flatten() {
+            let (Some(price), Some(size)) = (value_to_decimal(&level["px"]), value_to_decimal(&level["sz"])) else {
+                continue;
+            };
+            match side {
+                Side::Bid => book.update_bid(price, size),
+                Side::Ask => book.update_ask(price, size),
+            }
+        }
+    }
+    book.timestamp = millis_to_datetime(&data["time"]);
+    book
+}
+
+/// Mark price from a perpetual asset context; the oracle price is the index.
+pub(crate) fn mark_price_from(symbol: &Symbol, ctx: &Value, timestamp: DateTime<Utc>) -> Option<MarkPrice> {
+    Some(MarkPrice {
+        symbol: symbol.clone(),
+        mark_price: value_to_decimal(&ctx["markPx"])?,
+        index_price: value_to_decimal(&ctx["oraclePx"])?,
+        timestamp,
+    })
+}
+
+/// Funding from a perpetual asset context. Hyperliquid pays funding every
+/// hour, so the 8h figure is eight times the current rate.
+pub(crate) fn funding_from(symbol: &Symbol, ctx: &Value, timestamp: DateTime<Utc>) -> Option<FundingRate> {
+    let current_rate = value_to_decimal(&ctx["funding"])?;
+    Some(FundingRate {
+        symbol: symbol.clone(),
+        current_rate,
+        rate_8h: Some(current_rate * Decimal::from(8)),
+        timestamp,
+    })
+}
+
+/// Handles Hyperliquid WebSocket feeds.
+///
+/// Every `l2Book` message is a full snapshot of the top levels, so books are
+/// replaced rather than patched and there is no sequence to track.
+pub struct HyperliquidMarketDataStream {
+    symbols: HashMap<String, Symbol>,
+    books: HashMap<Symbol, OrderBook>,
+    update_tx: mpsc::UnboundedSender<MarketData>,
+}
+
+impl HyperliquidMarketDataStream {
+    pub fn new(symbols: Vec<Symbol>, update_tx: mpsc::UnboundedSender<MarketData>) -> Self {
+        Self {
+            symbols: symbols.into_iter().map(|s| (hyperliquid_coin(&s), s)).collect(),
+            books: HashMap::new(),
+            update_tx,
+        }
+    }
+
+    pub fn get_orderbook(&self, symbol: &Symbol) -> Option<&OrderBook> {
+        self.books.get(symbol)
+    }
+
+    fn symbol_for(&self, coin: Option<&str>) -> Option<Symbol> {
+        let coin = coin?;
+        let symbol = self.symbols.get(coin).cloned();
+        if symbol.is_none() {
+            debug!("Ignoring Hyperliquid message for {}", coin);
+        }
+        symbol
+    }
+
+    fn process_book(&mut self, data: &Value) {
+        let Some(symbol) = self.symbol_for(data["coin"].as_str()) else { return };
+        let book = book_from(&symbol, data);
+        self.books.insert(symbol, book.clone());
+        let _ = self.update_tx.send(MarketData::OrderBook(book));
+    }
+
+    fn process_trades(&self, data: &Value) {
+        for entry in data.as_array().into_iter().flatten() {
+            let Some(symbol) = self.symbol_for(entry["coin"].as_str()) else { continue };
+            let (Some(price), Some(size)) = (value_to_decimal(&entry["px"]), value_to_decimal(&entry["sz"])) else {
+                continue;
+            };
+            // "B" is a buy taker, "A" a sell taker
+            let side = match entry["side"].as_str() {
+                Some("B") => Side::Bid,
+                _ => Side::Ask,
+            };
+            let trade_id = entry["tid"].as_u64().map(|tid| tid.to_string()).unwrap_or_default();
+
+            let mut trade = Trade::new(symbol, price, size, side, trade_id);
+            trade.timestamp = millis_to_datetime(&entry["time"]);
+            let _ = self.update_tx.send(MarketData::Trade(trade));
+        }
+    }
+
+    fn process_asset_context(&self, data: &Value) {
+        let Some(symbol) = self.symbol_for(data["coin"].as_str()) else { return };
+        let ctx = &data["ctx"];
+        let now = Utc::now();
+        if let Some(mark) = mark_price_from(&symbol, ctx, now) {
+            let _ = self.update_tx.send(MarketData::MarkPrice(mark));
+        }
+        if let Some(funding) = funding_from(&symbol, ctx, now) {
+            let _ = self.update_tx.send(MarketData::Funding(funding));
+        }
+    }
+}
+
+#[async_trait]
+impl WebSocketHandler for HyperliquidMarketDataStream {
+    async fn on_message(&mut self, message: &str) -> Result<()> {
+        let data: Value = serde_json::from_str(message)?;
+        let payload = &data["data"];
+
+        match data["channel"].as_str().unwrap_or_default() {
+            "l2Book" => self.process_book(payload),
+            "trades" => self.process_trades(payload),
+            "activeAssetCtx" => self.process_asset_context(payload),
+            "pong" => debug!("Received Hyperliquid pong"),
+            "subscriptionResponse" => debug!("Hyperliquid subscribed: {}", payload),
+            "error" => warn!("Hyperliquid error: {}", payload),
+            _ => debug!("Ignoring Hyperliquid message: {}", message),
+        }
+        Ok(())
+    }
+
+    async fn on_connect(&mut self) -> Result<()> {
+        info!("Hyperliquid WebSocket connected for {} coins", self.symbols.len());
+        Ok(())
+    }
+
+    async fn on_disconnect(&mut self) -> Result<()> {
+        warn!("Hyperliquid WebSocket disconnected");
+        self.books.clear();
+        Ok(())
+    }
+
+    async fn on_error(&mut self, error: &ArbFinderError) -> Result<()> {
+        error!("Hyperliquid WebSocket error: {}", error);
+        Ok(())
+    }
+
+    async fn on_ping(&mut self) -> Result<()> {
+        debug!("Received Hyperliquid ping");
+        Ok(())
+    }
+
+    async fn on_pong(&mut self) -> Result<()> {
+        debug!("Received Hyperliquid pong");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn perpetual() -> Symbol {
+        Symbol::new("BTC", "USD")
+    }
+
+    fn stream() -> (HyperliquidMarketDataStream, mpsc::UnboundedReceiver<MarketData>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (HyperliquidMarketDataStream::new(vec![perpetual()], tx), rx)
+    }
+
+    #[test]
+    fn test_subscribe_messages() {
+        let messages = subscribe_messages(&[perpetual()]);
+        assert_eq!(messages.len(), 3);
+        let book: Value = serde_json::from_str(&messages[0]).unwrap();
+        assert_eq!(book["method"], "subscribe");
+        assert_eq!(book["subscription"]["type"], "l2Book");
+        assert_eq!(book["subscription"]["coin"], "BTC");
+    }
+
+    #[tokio::test]
+    async fn test_book_snapshots_replace() {
+        let (mut stream, mut rx) = stream();
+
+        let first = r#"{"channel":"l2Book","data":{"coin":"BTC","time":1700000000000,"levels":[[{"px":"37000.0","sz":"1.5","n":3}],[{"px":"37001.0","sz":"0.2","n":1},{"px":"37002.0","sz":"4.0","n":2}]]}}"#;
+        stream.on_message(first).await.unwrap();
+        match rx.recv().await.unwrap() {
+            MarketData::OrderBook(book) => {
+                assert_eq!(book.best_bid().unwrap().price, Decimal::from(37000));
+                assert_eq!(book.best_ask().unwrap().quantity, Decimal::from_str("0.2").unwrap());
+            }
+            other => panic!("expected order book, got {:?}", other),
+        }
+
+        // The 37001 ask is gone from the next snapshot and must not linger
+        let second = r#"{"channel":"l2Book","data":{"coin":"BTC","time":1700000000500,"levels":[[{"px":"37000.0","sz":"1.0","n":2}],[{"px":"37002.0","sz":"4.0","n":2}]]}}"#;
+        stream.on_message(second).await.unwrap();
+        let _ = rx.recv().await.unwrap();
+        let book = stream.get_orderbook(&perpetual()).unwrap();
+        assert_eq!(book.best_ask().unwrap().price, Decimal::from(37002));
+        assert_eq!(book.asks.len(), 1);
+
+        stream
+            .on_message(r#"{"channel":"l2Book","data":{"coin":"ETH","time":1,"levels":[[],[]]}}"#)
+            .await
+            .unwrap();
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_trades_and_asset_context() {
+        let (mut stream, mut rx) = stream();
+
+        let trades = r#"{"channel":"trades","data":[{"coin":"BTC","side":"A","px":"37000.5","sz":"0.01","time":1700000000000,"hash":"0xabc","tid":123456}]}"#;
+        stream.on_message(trades).await.unwrap();
+        match rx.recv().await.unwrap() {
+            MarketData::Trade(trade) => {
+                assert_eq!(trade.side, Side::Ask);
+                assert_eq!(trade.trade_id, "123456");
+            }
+            other => panic!("expected trade, got {:?}", other),
+        }
+
+        let ctx = r#"{"channel":"activeAssetCtx","data":{"coin":"BTC","ctx":{"funding":"0.0000125","openInterest":"1000.0","prevDayPx":"36500.0","dayNtlVlm":"1.0","premium":"0.0001","oraclePx":"36990.0","markPx":"37000.1","midPx":"37000.5"}}}"#;
+        stream.on_message(ctx).await.unwrap();
+        match rx.recv().await.unwrap() {
+            MarketData::MarkPrice(mark) => {
+                assert_eq!(mark.mark_price, Decimal::from_str("37000.1").unwrap());
+                assert_eq!(mark.index_price, Decimal::from(36990));
+            }
+            other => panic!("expected mark price, got {:?}", other),
+        }
+        match rx.recv().await.unwrap() {
+            MarketData::Funding(funding) => {
+                assert_eq!(funding.current_rate, Decimal::from_str("0.0000125").unwrap());
+                assert_eq!(funding.rate_8h, Some(Decimal::from_str("0.0001").unwrap()));
+            }
+            other => panic!("expected funding, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,444 @@
+//! Jupiter Adapter
+//!
+//! Quotes swaps through the Jupiter aggregator's quote API, which routes
+//! across Solana DEXes, and presents each configured pair as an order book
+//! built from quotes at a ladder of sizes (see
+//! [`arbfinder_exchange::synthetic`]), so Solana liquidity can be compared
+//! with exchange books.
+//!
+//! Tokens are identified by mint; SOL, USDC and USDT are known and others
+//! are added with [`JupiterAdapter::with_token`]. Quotes are net of the
+//! routed pools' fees, so no trading fee is reported on top of them.
+
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use arbfinder_exchange::channel_stream;
+use arbfinder_exchange::synthetic::{self, SwapQuote};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::{Client, StatusCode};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+const JUPITER_API_URL: &str = "https://lite-api.jup.ag/swap/v1";
+
+/// Each poll costs a request per size per side; this keeps one pair within
+/// the public endpoint's limit.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Notionals quoted on each side, in the quote currency.
+fn default_sizes() -> Vec<Decimal> {
+    [100, 1_000, 10_000, 100_000].into_iter().map(Decimal::from).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JupiterToken {
+    pub mint: String,
+    pub decimals: u8,
+}
+
+impl JupiterToken {
+    pub fn new(mint: impl Into<String>, decimals: u8) -> Self {
+        Self {
+            mint: mint.into(),
+            decimals,
+        }
+    }
+}
+
+fn default_tokens() -> HashMap<String, JupiterToken> {
+    HashMap::from([
+        ("SOL".to_string(), JupiterToken::new("So11111111111111111111111111111111111111112", 9)),
+        ("USDC".to_string(), JupiterToken::new("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v", 6)),
+        ("USDT".to_string(), JupiterToken::new("Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB", 6)),
+    ])
+}
+
+/// A quoted swap in whole tokens, with the pools it routes through.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JupiterQuote {
+    pub amount_in: Decimal,
+    pub amount_out: Decimal,
+    /// Price impact as a fraction, as Jupiter estimates it.
+    pub price_impact: Decimal,
+    /// DEX labels of each hop, e.g. `["Raydium", "Orca"]`.
+    pub route: Vec<String>,
+}
+
+/// Parses a quote response; raw amounts are converted with the input and
+/// output tokens' decimals.
+pub fn parse_quote(data: &Value, in_decimals: u8, out_decimals: u8) -> Option<JupiterQuote> {
+    let raw = |field: &str| data[field].as_str()?.parse::<u128>().ok();
+    let route = data["routePlan"]
+        .as_array()
+        .map(|hops| {
+            hops.iter()
+                .filter_map(|hop| hop["swapInfo"]["label"].as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(JupiterQuote {
+        amount_in: synthetic::from_raw(raw("inAmount")?, in_decimals)?,
+        amount_out: synthetic::from_raw(raw("outAmount")?, out_decimals)?,
+        price_impact: data["priceImpactPct"]
+            .as_str()
+            .and_then(|s| Decimal::from_str(s).ok())
+            .unwrap_or_default(),
+        route,
+    })
+}
+
+/// Quotes API access shared by the adapter and its polling task.
+#[derive(Clone)]
+struct QuoteClient {
+    client: Client,
+    base_url: String,
+}
+
+impl QuoteClient {
+    /// Quotes swapping `amount` whole tokens of `from` into `to`.
+    async fn quote(&self, from: &JupiterToken, to: &JupiterToken, amount: Decimal) -> Result<JupiterQuote> {
+        let raw = synthetic::to_raw(amount, from.decimals)
+            .ok_or_else(|| ArbFinderError::InvalidData(format!("Amount {} out of range", amount)))?;
+        let url = format!(
+            "{}/quote?inputMint={}&outputMint={}&amount={}&swapMode=ExactIn",
+            self.base_url, from.mint, to.mint, raw
+        );
+        let response = self.client.get(&url).send().await.map_err(ArbFinderError::Http)?;
+
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(ArbFinderError::RateLimit("Jupiter quote limit".to_string()));
+        }
+        let data: Value = response.json().await.map_err(ArbFinderError::Http)?;
+        if !status.is_success() {
+            return Err(ArbFinderError::Exchange(format!(
+                "Jupiter quote failed ({}): {}",
+                status,
+                data["error"].as_str().unwrap_or_default()
+            )));
+        }
+
+        parse_quote(&data, from.decimals, to.decimals)
+            .ok_or_else(|| ArbFinderError::InvalidData(format!("Unexpected Jupiter quote: {}", data)))
+    }
+
+    /// Quotes each amount in turn, stopping at the first one that cannot
+    /// be routed.
+    async fn ladder(&self, from: &JupiterToken, to: &JupiterToken, amounts: &[Decimal]) -> Vec<SwapQuote> {
+        let mut quotes = Vec::with_capacity(amounts.len());
+        for amount in amounts {
+            match self.quote(from, to, *amount).await {
+                Ok(quote) => {
+                    debug!("Jupiter routes {} {} via {:?}", amount, from.mint, quote.route);
+                    quotes.push(SwapQuote::new(quote.amount_in, quote.amount_out));
+                }
+                Err(e) => {
+                    debug!("Jupiter stopped quoting {} -> {}: {}", from.mint, to.mint, e);
+                    break;
+                }
+            }
+        }
+        quotes
+    }
+
+    /// Asks come from spending each notional of the quote token. Bids sell
+    /// the same notionals' worth of the base, valued at the smallest ask.
+    async fn book(&self, symbol: &Symbol, base: &JupiterToken, quote: &JupiterToken, sizes: &[Decimal]) -> Result<OrderBook> {
+        let asks = self.ladder(quote, base, sizes).await;
+        let reference = asks
+            .first()
+            .filter(|first| !first.amount_out.is_zero())
+            .map(|first| first.amount_in / first.amount_out)
+            .ok_or_else(|| ArbFinderError::Exchange(format!("Jupiter found no route for {}", symbol)))?;
+
+        let base_sizes: Vec<Decimal> = sizes
+            .iter()
+            .map(|size| (size / reference).round_dp(u32::from(base.decimals)))
+            .collect();
+        let bids = self.ladder(base, quote, &base_sizes).await;
+
+        Ok(synthetic::book_from_quotes(symbol.clone(), &bids, &asks))
+    }
+}
+
+pub struct JupiterAdapter {
+    quotes: QuoteClient,
+    tokens: HashMap<String, JupiterToken>,
+    pairs: Vec<Symbol>,
+    sizes: Vec<Decimal>,
+    poll_interval: Duration,
+    connected: bool,
+    subscriptions: Vec<Symbol>,
+}
+
+impl JupiterAdapter {
+    pub fn new() -> Self {
+        Self {
+            quotes: QuoteClient {
+                client: Client::new(),
+                base_url: JUPITER_API_URL.to_string(),
+            },
+            tokens: default_tokens(),
+            pairs: Vec::new(),
+            sizes: default_sizes(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            connected: false,
+            subscriptions: Vec::new(),
+        }
+    }
+
+    /// Quote API root, e.g. the keyed `https://api.jup.ag/swap/v1`.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.quotes.base_url = base_url.into();
+        self
+    }
+
+    /// Makes `asset` known by its mint.
+    pub fn with_token(mut self, asset: &str, mint: impl Into<String>, decimals: u8) -> Self {
+        self.tokens.insert(asset.to_uppercase(), JupiterToken::new(mint, decimals));
+        self
+    }
+
+    /// Quotes `symbol`, whose assets must both be known tokens.
+    pub fn with_pair(mut self, symbol: Symbol) -> Self {
+        if !self.pairs.contains(&symbol) {
+            self.pairs.push(symbol);
+        }
+        self
+    }
+
+    /// Notionals quoted on each book side, in the quote currency. Each size
+    /// is one more level.
+    pub fn with_sizes(mut self, mut sizes: Vec<Decimal>) -> Self {
+        sizes.retain(|size| *size > Decimal::ZERO);
+        sizes.sort();
+        sizes.dedup();
+        self.sizes = sizes;
+        self
+    }
+
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    pub fn token(&self, asset: &str) -> Result<&JupiterToken> {
+        self.tokens.get(&asset.to_uppercase()).ok_or_else(|| {
+            ArbFinderError::SymbolNotFound(format!("No Solana mint known for {}", asset))
+        })
+    }
+
+    /// Quotes selling `amount` of `symbol`'s base for its quote, or buying
+    /// the base with `amount` of the quote.
+    pub async fn get_quote(&self, symbol: &Symbol, side: OrderSide, amount: Decimal) -> Result<JupiterQuote> {
+        let base = self.token(symbol.base())?;
+        let quote = self.token(symbol.quote())?;
+        match side {
+            OrderSide::Sell => self.quotes.quote(base, quote, amount).await,
+            OrderSide::Buy => self.quotes.quote(quote, base, amount).await,
+        }
+    }
+
+    /// Book synthesized from current quotes.
+    pub async fn get_orderbook(&self, symbol: &Symbol) -> Result<OrderBook> {
+        let base = self.token(symbol.base())?;
+        let quote = self.token(symbol.quote())?;
+        self.quotes.book(symbol, base, quote, &self.sizes).await
+    }
+}
+
+impl Default for JupiterAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl MarketDataAdapter for JupiterAdapter {
+    fn venue_id(&self) -> VenueId {
+        VenueId::Jupiter
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        for symbol in &self.pairs {
+            self.token(symbol.base())?;
+            self.token(symbol.quote())?;
+        }
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.connected = false;
+        Ok(())
+    }
+
+    async fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// The quote API reports no time; quotes are for the current slot.
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        Ok(Utc::now())
+    }
+
+    async fn ping(&self) -> Result<u64> {
+        let start = std::time::Instant::now();
+        let usdc = self.token("USDC")?;
+        let sol = self.token("SOL")?;
+        self.quotes.quote(usdc, sol, Decimal::ONE).await?;
+        Ok(start.elapsed().as_millis() as u64)
+    }
+
+    async fn get_symbols(&self) -> Result<Vec<Symbol>> {
+        Ok(self.pairs.clone())
+    }
+
+    async fn get_symbol_info(&self, symbol: &Symbol) -> Result<SymbolInfo> {
+        let base = self.token(symbol.base())?;
+        let quote = self.token(symbol.quote())?;
+        let lot_size = Decimal::new(1, u32::from(base.decimals));
+
+        Ok(SymbolInfo {
+            symbol: symbol.clone(),
+            status: "open".to_string(),
+            base_asset_precision: u32::from(base.decimals),
+            quote_asset_precision: u32::from(quote.decimals),
+            tick_size: Decimal::new(1, 8),
+            lot_size,
+            min_order_size: lot_size,
+            max_order_size: Decimal::new(1000000, 0),
+            min_notional: Decimal::ZERO,
+            // Already taken out of the quotes
+            trading_fees: TradingFees {
+                maker_fee: Decimal::ZERO,
+                taker_fee: Decimal::ZERO,
+            },
+        })
+    }
+
+    async fn subscribe_orderbook(&mut self, symbol: &Symbol, _depth: Option<u32>) -> Result<()> {
+        self.token(symbol.base())?;
+        self.token(symbol.quote())?;
+        if !self.subscriptions.contains(symbol) {
+            self.subscriptions.push(symbol.clone());
+        }
+        Ok(())
+    }
+
+    async fn subscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+        Err(ArbFinderError::Exchange("Jupiter has no trade feed".to_string()))
+    }
+
+    async fn subscribe_ticker(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscribe_orderbook(symbol, None).await
+    }
+
+    async fn unsubscribe_orderbook(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscriptions.retain(|s| s != symbol);
+        Ok(())
+    }
+
+    async fn unsubscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    /// Re-quotes the subscribed pairs every poll interval.
+    async fn market_data_stream(&self) -> Result<MarketDataStream> {
+        if self.subscriptions.is_empty() {
+            return Err(ArbFinderError::Exchange("No Jupiter pairs subscribed".to_string()));
+        }
+        let pairs: Vec<(Symbol, JupiterToken, JupiterToken)> = self
+            .subscriptions
+            .iter()
+            .map(|symbol| {
+                Ok((
+                    symbol.clone(),
+                    self.token(symbol.base())?.clone(),
+                    self.token(symbol.quote())?.clone(),
+                ))
+            })
+            .collect::<Result<_>>()?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let quotes = self.quotes.clone();
+        let sizes = self.sizes.clone();
+        let mut interval = tokio::time::interval(self.poll_interval);
+        tokio::spawn(async move {
+            loop {
+                interval.tick().await;
+                for (symbol, base, quote) in &pairs {
+                    match quotes.book(symbol, base, quote, &sizes).await {
+                        Ok(book) => {
+                            if tx.send(MarketData::OrderBook(book)).is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => warn!("Failed to quote {} on Jupiter: {}", symbol, e),
+                    }
+                }
+            }
+        });
+        Ok(channel_stream(rx))
+    }
+
+    async fn fetch_orderbook(&self, symbol: &Symbol, _depth: Option<u32>) -> Result<OrderBook> {
+        self.get_orderbook(symbol).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn test_jupiter_adapter_creation() {
+        let mut adapter = JupiterAdapter::new()
+            .with_token("jup", "JUPyiwrYJFskUPiHa7hkeR8VUtAeFoSYbKedZNsDvCN", 6)
+            .with_pair(Symbol::new("SOL", "USDC"))
+            .with_pair(Symbol::new("JUP", "USDC"))
+            .with_sizes(vec![dec!(1000), dec!(100), dec!(-5)]);
+        assert_eq!(adapter.venue_id(), VenueId::Jupiter);
+        assert_eq!(adapter.get_symbols().await.unwrap().len(), 2);
+        assert_eq!(adapter.sizes, vec![dec!(100), dec!(1000)]);
+        assert_eq!(adapter.token("Jup").unwrap().decimals, 6);
+
+        adapter.subscribe_orderbook(&Symbol::new("SOL", "USDT"), None).await.unwrap();
+        assert!(adapter.subscribe_orderbook(&Symbol::new("BONK", "USDC"), None).await.is_err());
+    }
+
+    #[test]
+    fn test_parse_quote() {
+        let data = serde_json::json!({
+            "inputMint": "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v",
+            "inAmount": "1000000000",
+            "outputMint": "So11111111111111111111111111111111111111112",
+            "outAmount": "6493506493",
+            "swapMode": "ExactIn",
+            "priceImpactPct": "0.00012",
+            "routePlan": [
+                {"swapInfo": {"ammKey": "a", "label": "Raydium CLMM", "inAmount": "600000000"}, "percent": 60},
+                {"swapInfo": {"ammKey": "b", "label": "Orca", "inAmount": "400000000"}, "percent": 40}
+            ]
+        });
+        let quote = parse_quote(&data, 6, 9).unwrap();
+        assert_eq!(quote.amount_in, dec!(1000));
+        assert_eq!(quote.amount_out, dec!(6.493506493));
+        assert_eq!(quote.price_impact, dec!(0.00012));
+        assert_eq!(quote.route, vec!["Raydium CLMM", "Orca"]);
+
+        assert!(parse_quote(&serde_json::json!({"error": "No routes found"}), 6, 9).is_none());
+    }
+}
@@ -4,53 +4,498 @@
 
 use arbfinder_core::prelude::*;
 use arbfinder_exchange::prelude::*;
+use arbfinder_core::config::{ProxyConfig, RateLimitConfig, RateLimitScope, RateLimitWindow};
+use arbfinder_exchange::{channel_stream, http_client, retry_after, spawn_connection_with, DefaultExchangeConfig, SingleFlight, DEFAULT_SYMBOLS_TTL};
 use async_trait::async_trait;
-use reqwest::Client;
-use chrono::{DateTime, Utc};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use reqwest::{Client, Method};
+use chrono::{DateTime, TimeZone, Utc};
 use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::warn;
+
+pub mod normalizer;
+pub mod websocket;
+
+pub use normalizer::{altname_asset, normalize_asset, parse_pair, KrakenSymbolNormalizer};
+pub use websocket::{KrakenOrderbookStream, Precision};
 
 const KRAKEN_API_URL: &str = "https://api.kraken.com";
-const KRAKEN_WS_URL: &str = "wss://ws.kraken.com";
+const KRAKEN_ASSET_PAIRS: &str = "/0/public/AssetPairs";
+
+fn asset_pairs_cache(ttl: Duration) -> ResponseCache {
+    ResponseCache::new().with_ttl(KRAKEN_ASSET_PAIRS, ttl)
+}
+
+/// Kraken's private call counter: 15 calls, one decaying every three
+/// seconds. Orders draw on the matching engine's own limit instead, and
+/// public endpoints are limited per IP apart from the counter, so neither
+/// weighs anything against it.
+fn kraken_rate_limits() -> RateLimitConfig {
+    let orders = ["/0/private/AddOrder", "/0/private/CancelOrder", "/0/private/AmendOrder"];
+    let public = ["/0/public/Time", "/0/public/SystemStatus", KRAKEN_ASSET_PAIRS, "/0/public/Depth", "/0/public/OHLC", "/0/public/Trades"];
+    RateLimitConfig {
+        windows: vec![
+            RateLimitWindow { scope: RateLimitScope::Requests, limit: 15, window_secs: 45 },
+            RateLimitWindow { scope: RateLimitScope::Orders, limit: 60, window_secs: 60 },
+        ],
+        endpoint_weights: orders.iter().chain(&public).map(|endpoint| (endpoint.to_string(), 0)).collect(),
+        default_weight: 1,
+        order_endpoints: orders.iter().map(|endpoint| endpoint.to_string()).collect(),
+    }
+}
+
+/// `symbol` as the v2 API names it, e.g. `BTC/USD`.
+pub fn kraken_symbol(symbol: &Symbol) -> String {
+    format!("{}/{}", normalize_asset(symbol.base()), normalize_asset(symbol.quote()))
+}
+
+/// `symbol` as the REST API's pair altname, e.g. `XBTUSD`.
+pub fn kraken_pair(symbol: &Symbol) -> String {
+    format!("{}{}", altname_asset(symbol.base()), altname_asset(symbol.quote()))
+}
+
+/// Maps the errors in a REST response to ours.
+fn check_errors(response: &serde_json::Value) -> Result<()> {
+    let Some(error) = response["error"].as_array().and_then(|errors| errors.first()) else {
+        return Ok(());
+    };
+    let error = error.as_str().unwrap_or_default().to_string();
+    Err(match error.split(':').next().unwrap_or_default() {
+        _ if error.contains("Rate limit") || error.contains("Throttled") => ArbFinderError::RateLimit(error),
+        "EAPI" if error.contains("Invalid key")
+            || error.contains("Invalid signature")
+            || error.contains("Invalid nonce") =>
+        {
+            ArbFinderError::Authentication(error)
+        }
+        "EGeneral" if error.contains("Permission denied") => ArbFinderError::Authentication(error),
+        "EOrder" => ArbFinderError::InvalidOrder(error),
+        _ => ArbFinderError::Exchange(format!("Kraken API error: {}", error)),
+    })
+}
+
+/// `AddOrder` parameters for `request`, leaving out the nonce.
+fn order_params(request: &OrderRequest, client_order_id: &str) -> Result<Vec<(&'static str, String)>> {
+    if request.size_unit == SizeUnit::Quote {
+        return Err(ArbFinderError::InvalidOrder("Kraken orders are sized in base units".to_string()));
+    }
+    let price = || {
+        request
+            .price
+            .map(|price| price.normalize().to_string())
+            .ok_or_else(|| ArbFinderError::InvalidOrder(format!("{} order requires a price", request.order_type)))
+    };
+    let stop_price = || {
+        request
+            .stop_price
+            .map(|price| price.normalize().to_string())
+            .ok_or_else(|| ArbFinderError::InvalidOrder(format!("{} order requires a stop price", request.order_type)))
+    };
+
+    let mut params = vec![
+        ("pair", kraken_pair(&request.symbol)),
+        (
+            "type",
+            match request.side {
+                OrderSide::Buy => "buy",
+                OrderSide::Sell => "sell",
+            }
+            .to_string(),
+        ),
+        ("volume", request.quantity.normalize().to_string()),
+        ("cl_ord_id", client_order_id.to_string()),
+    ];
+    match request.order_type {
+        OrderType::Market => params.push(("ordertype", "market".to_string())),
+        OrderType::Limit | OrderType::PostOnly | OrderType::ImmediateOrCancel => {
+            params.push(("ordertype", "limit".to_string()));
+            params.push(("price", price()?));
+        }
+        // Stop orders trigger at `price`; stop limits then rest at `price2`
+        OrderType::StopMarket => {
+            params.push(("ordertype", "stop-loss".to_string()));
+            params.push(("price", stop_price()?));
+        }
+        OrderType::StopLimit => {
+            params.push(("ordertype", "stop-loss-limit".to_string()));
+            params.push(("price", stop_price()?));
+            params.push(("price2", price()?));
+        }
+        OrderType::FillOrKill => {
+            return Err(ArbFinderError::InvalidOrder("Kraken does not support fill-or-kill orders".to_string()))
+        }
+        OrderType::Oco => {
+            return Err(ArbFinderError::InvalidOrder("Kraken does not support OCO orders".to_string()))
+        }
+    }
+
+    let post_only = request.post_only
+        || request.order_type == OrderType::PostOnly
+        || request.time_in_force == TimeInForce::PostOnly;
+    if post_only {
+        params.push(("oflags", "post".to_string()));
+    } else if request.order_type == OrderType::ImmediateOrCancel
+        || request.time_in_force == TimeInForce::ImmediateOrCancel
+    {
+        params.push(("timeinforce", "IOC".to_string()));
+    } else if request.time_in_force == TimeInForce::FillOrKill {
+        return Err(ArbFinderError::InvalidOrder("Kraken does not support fill-or-kill orders".to_string()));
+    }
+    Ok(params)
+}
+
+/// `AmendOrder` parameters for `txid`. Kraken keeps the order's queue
+/// position unless its price changes or its quantity goes up.
+fn amend_params(txid: &str, new_price: Option<Decimal>, new_quantity: Option<Decimal>) -> Result<Vec<(&'static str, String)>> {
+    if new_price.is_none() && new_quantity.is_none() {
+        return Err(ArbFinderError::InvalidOrder("Amend needs a new price or quantity".to_string()));
+    }
+    let mut params = vec![("txid", txid.to_string())];
+    if let Some(quantity) = new_quantity {
+        params.push(("order_qty", quantity.normalize().to_string()));
+    }
+    if let Some(price) = new_price {
+        params.push(("limit_price", price.normalize().to_string()));
+    }
+    Ok(params)
+}
+
+/// Parses the `OHLC` result into candles, oldest first. Each row is the
+/// open time in seconds, then open, high, low, close, VWAP and volume as
+/// strings, and a trade count; alongside the pair's rows is `last`.
+fn parse_ohlc(symbol: &Symbol, interval: CandleInterval, result: &serde_json::Value) -> Vec<Candle> {
+    let rows = result
+        .as_object()
+        .and_then(|pairs| pairs.iter().find(|(key, _)| *key != "last"))
+        .and_then(|(_, rows)| rows.as_array());
+    rows.into_iter()
+        .flatten()
+        .filter_map(|row| {
+            let decimal = |index: usize| row[index].as_str()?.parse::<Decimal>().ok();
+            Some(Candle {
+                symbol: symbol.clone(),
+                open: decimal(1)?,
+                high: decimal(2)?,
+                low: decimal(3)?,
+                close: decimal(4)?,
+                volume: decimal(6)?,
+                timestamp: Utc.timestamp_opt(row[0].as_i64()?, 0).single()?,
+                interval: interval.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parses the `Trades` result, oldest first. Each row is the price and
+/// volume as strings, the time in fractional seconds, `b` or `s` for the
+/// taker's side, order type, misc flags and the trade id.
+fn parse_trades(symbol: &Symbol, result: &serde_json::Value) -> Vec<Trade> {
+    let rows = result
+        .as_object()
+        .and_then(|pairs| pairs.iter().find(|(key, _)| *key != "last"))
+        .and_then(|(_, rows)| rows.as_array());
+    rows.into_iter()
+        .flatten()
+        .filter_map(|row| {
+            let side = match row[3].as_str()? {
+                "b" => Side::Bid,
+                "s" => Side::Ask,
+                _ => return None,
+            };
+            let mut trade = Trade::new(
+                symbol.clone(),
+                row[0].as_str()?.parse().ok()?,
+                row[1].as_str()?.parse().ok()?,
+                side,
+                row[6].as_u64()?.to_string(),
+            );
+            trade.timestamp = Utc.timestamp_millis_opt((row[2].as_f64()? * 1000.0).round() as i64).single()?;
+            Some(trade)
+        })
+        .collect()
+}
+
+/// The fees of a `TradeVolume` result asked for one pair. Kraken quotes
+/// them in percent; pairs without a separate maker schedule pay the taker
+/// fee either way.
+fn parse_trade_volume(result: &serde_json::Value) -> Option<TradingFee> {
+    let percent = |fees: &serde_json::Value| -> Option<Decimal> {
+        let fee = fees.as_object()?.values().next()?;
+        Some(websocket::value_to_decimal(&fee["fee"])? / Decimal::ONE_HUNDRED)
+    };
+    let taker = percent(&result["fees"])?;
+    let maker = percent(&result["fees_maker"]).unwrap_or(taker);
+    let mut fee = TradingFee::new(maker, taker);
+    fee.tier = result["volume"]
+        .as_str()
+        .map(|volume| format!("30-day volume {} {}", volume, result["currency"].as_str().unwrap_or_default()));
+    Some(fee)
+}
+
+/// The first address of a `DepositAddresses` result. Chains that need one
+/// give the tag as `tag` or `memo`.
+fn parse_deposit_address(asset: &str, method: &str, result: &serde_json::Value) -> Option<DepositAddress> {
+    let entry = result.as_array()?.first()?;
+    let mut address = DepositAddress::new(asset, entry["address"].as_str()?);
+    address.tag = entry["tag"].as_str().or_else(|| entry["memo"].as_str()).map(str::to_string);
+    address.network = Some(method.to_string());
+    Some(address)
+}
+
+/// The key of the verified `WithdrawAddresses` entry for `address`, which
+/// Kraken withdraws to in place of a raw address.
+fn withdrawal_key(result: &serde_json::Value, address: &DepositAddress) -> Option<String> {
+    result
+        .as_array()?
+        .iter()
+        .find(|entry| {
+            entry["address"].as_str() == Some(address.address.as_str())
+                && entry["verified"].as_bool().unwrap_or(false)
+                && (address.tag.is_none() || entry["memo"].as_str() == address.tag.as_deref())
+        })
+        .and_then(|entry| entry["key"].as_str())
+        .map(str::to_string)
+}
+
+/// A funding entry's `status`: `Initial` and `Pending` are in hand,
+/// `Settled` is on chain, `Success` sent or credited. Canceled and returned
+/// entries are marked in `status-prop`.
+fn funding_status(entry: &serde_json::Value) -> Option<TransferStatus> {
+    if matches!(entry["status-prop"].as_str(), Some("canceled" | "return")) {
+        return Some(TransferStatus::Failed);
+    }
+    Some(match entry["status"].as_str()? {
+        "Success" => TransferStatus::Completed,
+        "Settled" => TransferStatus::Confirming,
+        "Failure" => TransferStatus::Failed,
+        _ => TransferStatus::Pending,
+    })
+}
+
+/// The `WithdrawStatus` entry for `refid`.
+fn parse_withdrawal_progress(refid: &str, result: &serde_json::Value) -> Option<WithdrawalProgress> {
+    let entry = result.as_array()?.iter().find(|entry| entry["refid"].as_str() == Some(refid))?;
+    Some(WithdrawalProgress {
+        status: funding_status(entry)?,
+        tx_id: entry["txid"].as_str().filter(|txid| !txid.is_empty()).map(str::to_string),
+    })
+}
+
+/// An entry of `DepositStatus`, under `asset` as we name it. Deposits are
+/// not credited until they succeed, so a settled one is still confirming.
+fn parse_deposit(asset: &str, entry: &serde_json::Value) -> Option<Deposit> {
+    let status = match funding_status(entry)? {
+        TransferStatus::Pending => TransferStatus::Confirming,
+        status => status,
+    };
+    Some(Deposit {
+        id: entry["refid"].as_str()?.to_string(),
+        venue: VenueId::Kraken,
+        asset: asset.to_string(),
+        amount: websocket::value_to_decimal(&entry["amount"])?,
+        tx_id: entry["txid"].as_str().filter(|txid| !txid.is_empty()).map(str::to_string),
+        status,
+        confirmations: None,
+        time: Utc.timestamp_opt(entry["time"].as_i64()?, 0).single()?,
+    })
+}
+
+/// Parses an entry of `OpenOrders`, keyed by its transaction id.
+fn parse_order(txid: &str, data: &serde_json::Value, symbol: Symbol) -> Option<Order> {
+    let descr = &data["descr"];
+    let side = match descr["type"].as_str()? {
+        "buy" => OrderSide::Buy,
+        "sell" => OrderSide::Sell,
+        _ => return None,
+    };
+    let quantity = websocket::value_to_decimal(&data["vol"])?;
+    let mut order = Order::new_market(VenueId::Kraken, symbol, side, quantity);
+    order.venue_order_id = Some(txid.to_string());
+    order.client_order_id = data["cl_ord_id"].as_str().map(str::to_string);
+
+    let price = websocket::value_to_decimal(&descr["price"]).filter(|price| !price.is_zero());
+    let price2 = websocket::value_to_decimal(&descr["price2"]).filter(|price| !price.is_zero());
+    let post_only = data["oflags"].as_str().is_some_and(|flags| flags.split(',').any(|flag| flag == "post"));
+    match descr["ordertype"].as_str()? {
+        "market" => {}
+        "limit" => {
+            order.order_type = if post_only { OrderType::PostOnly } else { OrderType::Limit };
+            order.price = price;
+        }
+        "stop-loss" => {
+            order.order_type = OrderType::StopMarket;
+            order.stop_price = price;
+        }
+        "stop-loss-limit" => {
+            order.order_type = OrderType::StopLimit;
+            order.stop_price = price;
+            order.price = price2;
+        }
+        _ => return None,
+    }
+    if post_only {
+        order.time_in_force = TimeInForce::PostOnly;
+    }
+
+    let filled = websocket::value_to_decimal(&data["vol_exec"]).unwrap_or(Decimal::ZERO);
+    order.filled_quantity = filled;
+    order.remaining_quantity = (quantity - filled).max(Decimal::ZERO);
+    order.average_fill_price = websocket::value_to_decimal(&data["price"]).filter(|_| !filled.is_zero());
+    order.status = match data["status"].as_str() {
+        Some("pending") => OrderStatus::Pending,
+        Some("closed") => OrderStatus::Filled,
+        Some("canceled") => OrderStatus::Canceled,
+        Some("expired") => OrderStatus::Expired,
+        _ if !filled.is_zero() => OrderStatus::PartiallyFilled,
+        _ => OrderStatus::Open,
+    };
+    if let Some(opened) = data["opentm"].as_f64() {
+        if let Some(created_at) = Utc.timestamp_millis_opt((opened * 1000.0) as i64).single() {
+            order.created_at = created_at;
+            order.updated_at = created_at;
+        }
+    }
+    Some(order)
+}
 
 pub struct KrakenAdapter {
     client: Client,
-    api_key: Option<String>,
-    api_secret: Option<String>,
+    tls_pins: Option<TlsPins>,
+    proxy: Option<ProxyConfig>,
+    /// Replaced whole on rotation; requests copy them out rather than hold
+    /// the lock while in flight.
+    credentials: RwLock<Option<VenueCredentials>>,
     base_url: String,
     ws_url: String,
     connected: bool,
+    /// Symbols subscribed to the book channel, with the depth asked for.
+    subscriptions: Vec<(Symbol, Option<u32>)>,
+    /// Transaction ids of orders placed through the adapter.
+    orders: RwLock<HashMap<OrderId, String>>,
+    /// Last nonce sent; Kraken rejects any not above the one before.
+    nonce: AtomicU64,
+    /// `AssetPairs`, read by every symbol lookup.
+    cache: ResponseCache,
+    /// `ping`s on the market data connection and the `pong`s answering
+    /// them.
+    heartbeat: HeartbeatManager,
+    /// Kraken's call counter, shared with whoever else sends it requests.
+    rate_limiter: SharedRateLimiter,
+    /// GETs being sent, which identical ones arriving meanwhile share.
+    in_flight: SingleFlight<String, serde_json::Value>,
 }
 
 impl KrakenAdapter {
     pub fn new() -> Self {
         Self {
             client: Client::new(),
-            api_key: None,
-            api_secret: None,
+            tls_pins: None,
+            proxy: None,
+            credentials: RwLock::new(None),
             base_url: KRAKEN_API_URL.to_string(),
-            ws_url: KRAKEN_WS_URL.to_string(),
+            ws_url: websocket::KRAKEN_WS_URL.to_string(),
             connected: false,
+            subscriptions: Vec::new(),
+            orders: RwLock::new(HashMap::new()),
+            nonce: AtomicU64::new(0),
+            cache: asset_pairs_cache(DEFAULT_SYMBOLS_TTL),
+            heartbeat: HeartbeatManager::default(),
+            rate_limiter: WeightedRateLimiter::new(&kraken_rate_limits()).shared(),
+            in_flight: SingleFlight::new(),
         }
     }
 
-    pub fn with_credentials(api_key: String, api_secret: String) -> Self {
+    pub fn with_credentials(api_key: impl Into<SecretString>, api_secret: impl Into<SecretString>) -> Self {
         Self {
             client: Client::new(),
-            api_key: Some(api_key),
-            api_secret: Some(api_secret),
+            tls_pins: None,
+            proxy: None,
+            credentials: RwLock::new(Some(VenueCredentials {
+                api_key: api_key.into(),
+                secret_key: api_secret.into(),
+                passphrase: None,
+                sandbox: false,
+            })),
             base_url: KRAKEN_API_URL.to_string(),
-            ws_url: KRAKEN_WS_URL.to_string(),
+            ws_url: websocket::KRAKEN_WS_URL.to_string(),
             connected: false,
+            subscriptions: Vec::new(),
+            orders: RwLock::new(HashMap::new()),
+            nonce: AtomicU64::new(0),
+            cache: asset_pairs_cache(DEFAULT_SYMBOLS_TTL),
+            heartbeat: HeartbeatManager::default(),
+            rate_limiter: WeightedRateLimiter::new(&kraken_rate_limits()).shared(),
+            in_flight: SingleFlight::new(),
         }
     }
 
+    /// Only talks to servers presenting one of the pinned keys, over REST
+    /// and WebSocket alike.
+    pub fn with_tls_pins(mut self, pins: TlsPins) -> Result<Self> {
+        self.tls_pins = Some(pins);
+        self.client = http_client(Client::builder(), self.proxy.as_ref(), self.tls_pins.as_ref())?;
+        Ok(self)
+    }
+
+    /// Sends REST requests and opens WebSocket connections through `proxy`.
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Result<Self> {
+        self.proxy = Some(proxy);
+        self.client = http_client(Client::builder(), self.proxy.as_ref(), self.tls_pins.as_ref())?;
+        Ok(self)
+    }
+
+    /// How long `AssetPairs`, and with it symbols and trading rules, is
+    /// kept before being fetched again. Zero fetches it every time.
+    pub fn with_symbols_ttl(mut self, ttl: Duration) -> Self {
+        self.cache = asset_pairs_cache(ttl);
+        self
+    }
+
+    /// Draws requests from `rate_limiter` instead of Kraken's default
+    /// limits, e.g. to share one budget between adapters on the same key.
+    pub fn with_rate_limiter(mut self, rate_limiter: SharedRateLimiter) -> Self {
+        self.rate_limiter = rate_limiter;
+        self
+    }
+
+    /// Waits for room in the rate limiter for a request to `endpoint`.
+    async fn acquire(&self, method: &Method, endpoint: &str) {
+        let places_order = *method != Method::GET && self.rate_limiter.is_order_endpoint(endpoint);
+        let priority = RequestPriority::for_request(method, places_order);
+        self.rate_limiter.acquire_with_priority(endpoint, places_order, priority).await;
+    }
+
+    /// `AssetPairs`, refetched behind other requests once stale.
+    async fn asset_pairs(&self) -> Result<serde_json::Value> {
+        self.cache
+            .get_or_fetch(KRAKEN_ASSET_PAIRS, || {
+                RequestPriority::Background.scope(self.get_request(KRAKEN_ASSET_PAIRS))
+            })
+            .await
+    }
+
+    /// Sends a public GET, or joins an identical one already in flight.
+    /// Private endpoints are POSTs, each with its own nonce, and are never
+    /// shared.
     async fn get_request(&self, endpoint: &str) -> Result<serde_json::Value> {
+        self.in_flight.run(endpoint.to_string(), || self.send_get(endpoint)).await
+    }
+
+    async fn send_get(&self, endpoint: &str) -> Result<serde_json::Value> {
+        self.acquire(&Method::GET, endpoint).await;
         let url = format!("{}{}", self.base_url, endpoint);
         let response = self.client
             .get(&url)
             .send()
             .await
             .map_err(|e| ArbFinderError::Http(e))?;
+        self.rate_limiter.record_response(response.status().as_u16(), retry_after(response.headers(), Utc::now()));
 
         if !response.status().is_success() {
             return Err(ArbFinderError::Exchange(format!(
@@ -61,6 +506,138 @@ impl KrakenAdapter {
 
         response.json().await.map_err(|e| ArbFinderError::Http(e))
     }
+
+    /// A nonce above every one sent before: microseconds since the epoch,
+    /// bumped past the last if the clock has not moved on.
+    fn next_nonce(&self) -> u64 {
+        let now = Utc::now().timestamp_micros() as u64;
+        let previous = self
+            .nonce
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| Some(now.max(last + 1)))
+            .unwrap_or_default();
+        now.max(previous + 1)
+    }
+
+    /// `API-Sign` of a private request: base64 HMAC-SHA512, keyed with the
+    /// decoded secret, of the path followed by the SHA-256 of the nonce and
+    /// the encoded post data.
+    fn sign(secret: &SecretString, path: &str, nonce: u64, post_data: &str) -> Result<String> {
+        use hmac::{Hmac, Mac};
+        use sha2::{Digest, Sha256, Sha512};
+
+        let secret = STANDARD
+            .decode(secret.expose())
+            .map_err(|e| ArbFinderError::Authentication(format!("Invalid Kraken API secret: {}", e)))?;
+
+        let digest = Sha256::digest(format!("{}{}", nonce, post_data).as_bytes());
+        let mut mac = Hmac::<Sha512>::new_from_slice(&secret)
+            .map_err(|e| ArbFinderError::Authentication(format!("Invalid secret key: {}", e)))?;
+        mac.update(path.as_bytes());
+        mac.update(&digest);
+
+        Ok(STANDARD.encode(mac.finalize().into_bytes()))
+    }
+
+    fn credentials(&self) -> Result<VenueCredentials> {
+        self.credentials
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+            .ok_or_else(|| ArbFinderError::Authentication("Kraken API key not configured".to_string()))
+    }
+
+    /// Transaction id of an order placed through the adapter.
+    fn txid(&self, order_id: &OrderId) -> Result<String> {
+        self.orders
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(order_id)
+            .cloned()
+            .ok_or_else(|| ArbFinderError::InvalidOrder(format!("Unknown Kraken order {}", order_id)))
+    }
+
+    /// POSTs `params` to a private endpoint, returning its `result`.
+    async fn private_request(&self, endpoint: &str, params: &[(&str, String)]) -> Result<serde_json::Value> {
+        let credentials = self.credentials()?;
+
+        // Waits before taking a nonce, so requests let through later don't
+        // overtake it with a higher one
+        self.acquire(&Method::POST, endpoint).await;
+        let nonce = self.next_nonce();
+        let body = {
+            let mut body = url::form_urlencoded::Serializer::new(String::new());
+            body.append_pair("nonce", &nonce.to_string());
+            for (key, value) in params {
+                body.append_pair(key, value);
+            }
+            body.finish()
+        };
+        let signature = Self::sign(&credentials.secret_key, endpoint, nonce, &body)?;
+
+        let response = self.client
+            .post(format!("{}{}", self.base_url, endpoint))
+            .header("API-Key", credentials.api_key.expose())
+            .header("API-Sign", signature)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(body)
+            .send()
+            .await
+            .map_err(ArbFinderError::Http)?;
+        self.rate_limiter.record_response(response.status().as_u16(), retry_after(response.headers(), Utc::now()));
+        if response.status() == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(ArbFinderError::RateLimit("Kraken rate limit exceeded".to_string()));
+        }
+        if !response.status().is_success() {
+            return Err(ArbFinderError::Exchange(format!("Kraken API error: {}", response.status())));
+        }
+
+        let mut response: serde_json::Value = response.json().await.map_err(ArbFinderError::Http)?;
+        if let Err(error) = check_errors(&response) {
+            // Kraken reports its call counter running over in the body, under a 200
+            if matches!(error, ArbFinderError::RateLimit(_)) {
+                self.rate_limiter.record_throttled(None);
+            }
+            return Err(error);
+        }
+        Ok(response["result"].take())
+    }
+
+    /// Symbols of every pair, by REST altname.
+    async fn pair_symbols(&self) -> Result<HashMap<String, Symbol>> {
+        let response = self.asset_pairs().await?;
+        let pairs = response["result"].as_object().ok_or_else(|| {
+            ArbFinderError::InvalidData("Expected result object".to_string())
+        })?;
+
+        Ok(pairs
+            .values()
+            .filter_map(|pair| {
+                let (base, quote) = pair["wsname"].as_str()?.split_once('/')?;
+                Some((pair["altname"].as_str()?.to_string(), Symbol::new(normalize_asset(base), normalize_asset(quote))))
+            })
+            .collect())
+    }
+
+    /// Price and quantity decimals of every pair, by v2 symbol, for
+    /// checking book checksums.
+    async fn precisions(&self) -> Result<HashMap<String, Precision>> {
+        let response = self.asset_pairs().await?;
+        let pairs = response["result"].as_object().ok_or_else(|| {
+            ArbFinderError::InvalidData("Expected result object".to_string())
+        })?;
+
+        Ok(pairs
+            .values()
+            .filter_map(|pair| {
+                let (base, quote) = pair["wsname"].as_str()?.split_once('/')?;
+                let precision = Precision {
+                    price: pair["pair_decimals"].as_u64()? as u32,
+                    quantity: pair["lot_decimals"].as_u64()? as u32,
+                };
+                Some((format!("{}/{}", normalize_asset(base), normalize_asset(quote)), precision))
+            })
+            .collect())
+    }
 }
 
 impl Default for KrakenAdapter {
@@ -70,7 +647,7 @@ impl Default for KrakenAdapter {
 }
 
 #[async_trait]
-impl ExchangeAdapter for KrakenAdapter {
+impl MarketDataAdapter for KrakenAdapter {
     fn venue_id(&self) -> VenueId {
         VenueId::Kraken
     }
@@ -109,39 +686,50 @@ impl ExchangeAdapter for KrakenAdapter {
     }
 
     async fn get_symbols(&self) -> Result<Vec<Symbol>> {
-        let response = self.get_request("/0/public/AssetPairs").await?;
+        let response = self.asset_pairs().await?;
         let pairs = response["result"]
             .as_object()
             .ok_or_else(|| ArbFinderError::InvalidData("Expected result object".to_string()))?;
 
         let mut result = Vec::new();
-        for (pair_name, pair_data) in pairs {
+        for pair_data in pairs.values() {
             if let (Some(base), Some(quote)) = (
                 pair_data["base"].as_str(),
                 pair_data["quote"].as_str(),
             ) {
-                result.push(Symbol::new(base, quote));
+                result.push(Symbol::new(normalize_asset(base), normalize_asset(quote)));
             }
         }
 
         Ok(result)
     }
 
+    fn venue_symbol(&self, symbol: &Symbol) -> String {
+        kraken_pair(symbol)
+    }
+
+    fn clear_response_cache(&self) {
+        self.cache.clear();
+    }
+
+    fn heartbeat(&self) -> Option<HeartbeatManager> {
+        Some(self.heartbeat.clone())
+    }
+
     async fn get_symbol_info(&self, symbol: &Symbol) -> Result<SymbolInfo> {
-        let response = self.get_request("/0/public/AssetPairs").await?;
+        let response = self.asset_pairs().await?;
         let pairs = response["result"].as_object().ok_or_else(|| {
             ArbFinderError::InvalidData("Expected result object".to_string())
         })?;
 
-        // Kraken uses various formats - try multiple
-        let possible_names = vec![
-            format!("{}{}", symbol.base(), symbol.quote()),
-            format!("X{}Z{}", symbol.base(), symbol.quote()),
-            format!("{}/{}", symbol.base(), symbol.quote()),
-        ];
-
-        for (pair_name, pair_data) in pairs {
-            if possible_names.iter().any(|n| pair_name.contains(n)) {
+        // Pairs are listed under their legacy asset codes
+        let wanted = Symbol::new(normalize_asset(symbol.base()), normalize_asset(symbol.quote()));
+        for pair_data in pairs.values() {
+            let listed = match (pair_data["base"].as_str(), pair_data["quote"].as_str()) {
+                (Some(base), Some(quote)) => Symbol::new(normalize_asset(base), normalize_asset(quote)),
+                _ => continue,
+            };
+            if listed == wanted {
                 return Ok(SymbolInfo {
                     symbol: symbol.clone(),
                     status: if pair_data["status"].as_str() == Some("online") {
@@ -167,7 +755,9 @@ impl ExchangeAdapter for KrakenAdapter {
         Err(ArbFinderError::SymbolNotFound(format!("{}/{}", symbol.base(), symbol.quote())))
     }
 
-    async fn subscribe_orderbook(&mut self, _symbol: &Symbol, _depth: Option<u32>) -> Result<()> {
+    async fn subscribe_orderbook(&mut self, symbol: &Symbol, depth: Option<u32>) -> Result<()> {
+        self.subscriptions.retain(|(subscribed, _)| subscribed != symbol);
+        self.subscriptions.push((symbol.clone(), depth));
         Ok(())
     }
 
@@ -179,7 +769,8 @@ impl ExchangeAdapter for KrakenAdapter {
         Ok(())
     }
 
-    async fn unsubscribe_orderbook(&mut self, _symbol: &Symbol) -> Result<()> {
+    async fn unsubscribe_orderbook(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscriptions.retain(|(subscribed, _)| subscribed != symbol);
         Ok(())
     }
 
@@ -192,22 +783,188 @@ impl ExchangeAdapter for KrakenAdapter {
     }
 
     async fn market_data_stream(&self) -> Result<MarketDataStream> {
-        Err(ArbFinderError::Exchange("Market data stream not implemented yet".to_string()))
+        if self.subscriptions.is_empty() {
+            return Err(ArbFinderError::Exchange("No Kraken symbols subscribed".to_string()));
+        }
+
+        // One subscription covers every symbol, at the deepest depth asked for
+        let depth = self
+            .subscriptions
+            .iter()
+            .map(|(_, depth)| websocket::book_depth(*depth))
+            .max()
+            .unwrap_or_else(|| websocket::book_depth(None));
+        let symbols: Vec<Symbol> = self.subscriptions.iter().map(|(symbol, _)| symbol.clone()).collect();
+        let precisions = self.precisions().await?;
+        for symbol in &symbols {
+            if !precisions.contains_key(&kraken_symbol(symbol)) {
+                warn!("No Kraken precision for {}; its book checksums won't be checked", symbol);
+            }
+        }
+
+        let config = DefaultExchangeConfig {
+            base_url: self.base_url.clone(),
+            websocket_url: self.ws_url.clone(),
+            tls_pins: self.tls_pins.clone(),
+            proxy: self.proxy.clone(),
+            ..Default::default()
+        };
+        let subscribe = vec![websocket::subscribe_message(&symbols, depth)];
+        let (tx, rx) = mpsc::unbounded_channel();
+        let heartbeat = self.heartbeat.clone();
+        let mut pings = None;
+        spawn_connection_with(&config, &subscribe, |outbound| {
+            pings = Some(outbound.clone());
+            KrakenOrderbookStream::new(symbols, precisions, depth, tx, outbound).with_heartbeat(heartbeat)
+        })
+        .await?;
+        if let Some(pings) = pings {
+            self.heartbeat.spawn_pings(move |req_id| {
+                pings
+                    .send(websocket::ping_message(req_id))
+                    .map_err(|_| ArbFinderError::WebSocket("Kraken connection closed".to_string()))
+            });
+        }
+        Ok(channel_stream(rx))
+    }
+
+    async fn fetch_orderbook(&self, symbol: &Symbol, depth: Option<u32>) -> Result<OrderBook> {
+        let mut endpoint = format!("/0/public/Depth?pair={}", kraken_symbol(symbol));
+        if let Some(count) = depth {
+            endpoint.push_str(&format!("&count={}", count));
+        }
+        let response = RequestPriority::Background.scope(self.get_request(&endpoint)).await?;
+        if let Some(error) = response["error"].as_array().and_then(|errors| errors.first()) {
+            return Err(ArbFinderError::Exchange(format!("Kraken API error: {}", error)));
+        }
+        // Keyed by Kraken's own name for the pair, which differs from ours
+        let book = response["result"]
+            .as_object()
+            .and_then(|pairs| pairs.values().next())
+            .ok_or_else(|| ArbFinderError::InvalidData("Expected result object".to_string()))?;
+
+        let mut orderbook = OrderBook::new(symbol.clone());
+        let levels = |side: &str| -> Vec<(Decimal, Decimal)> {
+            book[side]
+                .as_array()
+                .map(|levels| {
+                    levels
+                        .iter()
+                        .filter_map(|level| {
+                            let price = level[0].as_str()?.parse().ok()?;
+                            let quantity = level[1].as_str()?.parse().ok()?;
+                            Some((price, quantity))
+                        })
+                        .collect()
+                })
+                .unwrap_or_default()
+        };
+        for (price, quantity) in levels("bids") {
+            orderbook.update_bid(price, quantity);
+        }
+        for (price, quantity) in levels("asks") {
+            orderbook.update_ask(price, quantity);
+        }
+        Ok(orderbook)
+    }
+
+    async fn get_klines(&self, symbol: &Symbol, interval: CandleInterval, limit: u32) -> Result<Vec<Candle>> {
+        if interval == CandleInterval::SixHours {
+            return Err(ArbFinderError::InvalidData("Kraken has no 6h candles".to_string()));
+        }
+        // Kraken returns up to 720 candles whatever is asked for
+        let endpoint = format!("/0/public/OHLC?pair={}&interval={}", kraken_pair(symbol), interval.minutes());
+        let response = self.get_request(&endpoint).await?;
+        check_errors(&response)?;
+
+        let mut candles = parse_ohlc(symbol, interval, &response["result"]);
+        let skip = candles.len().saturating_sub(limit as usize);
+        candles.drain(..skip);
+        Ok(candles)
+    }
+
+    async fn get_recent_trades(&self, symbol: &Symbol, limit: u32) -> Result<Vec<Trade>> {
+        let endpoint = format!("/0/public/Trades?pair={}&count={}", kraken_pair(symbol), limit.clamp(1, 1000));
+        let response = self.get_request(&endpoint).await?;
+        check_errors(&response)?;
+        Ok(parse_trades(symbol, &response["result"]))
     }
+}
 
+#[async_trait]
+impl TradingAdapter for KrakenAdapter {
     async fn order_update_stream(&self) -> Result<OrderUpdateStream> {
-        Err(ArbFinderError::Exchange("Order update stream not implemented yet".to_string()))
+        Err(ArbFinderError::Unsupported("Kraken order update stream is not implemented yet".to_string()))
     }
 
-    async fn place_order(&mut self, _request: &OrderRequest) -> Result<Order> {
-        Err(ArbFinderError::Exchange("Order placement not implemented yet".to_string()))
+    async fn place_order(&self, request: &OrderRequest) -> Result<Order> {
+        let mut order = match request.price {
+            Some(price) if request.order_type != OrderType::Market => Order::new_limit(
+                VenueId::Kraken,
+                request.symbol.clone(),
+                request.side,
+                request.quantity,
+                price,
+            ),
+            _ => Order::new_market(VenueId::Kraken, request.symbol.clone(), request.side, request.quantity),
+        };
+        order.order_type = request.order_type;
+        order.time_in_force = request.time_in_force;
+        order.stop_price = request.stop_price;
+
+        let client_order_id = request
+            .client_order_id
+            .clone()
+            .unwrap_or_else(|| order.id.0.to_string());
+        let params = order_params(request, &client_order_id)?;
+        let result = self.private_request("/0/private/AddOrder", &params).await?;
+        let txid = result["txid"][0]
+            .as_str()
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing txid".to_string()))?;
+
+        order.venue_order_id = Some(txid.to_string());
+        order.client_order_id = Some(client_order_id);
+        order.status = OrderStatus::Open;
+        self.orders
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(order.id.clone(), txid.to_string());
+        Ok(order)
     }
 
-    async fn cancel_order(&mut self, _order_id: &OrderId) -> Result<()> {
-        Err(ArbFinderError::Exchange("Order cancellation not implemented yet".to_string()))
+    async fn cancel_order(&self, order_id: &OrderId) -> Result<()> {
+        let txid = self.txid(order_id)?;
+
+        let result = self.private_request("/0/private/CancelOrder", &[("txid", txid)]).await?;
+        if result["count"].as_u64() == Some(0) && result["pending"].as_bool() != Some(true) {
+            return Err(ArbFinderError::Exchange(format!("Kraken did not cancel {}", order_id)));
+        }
+        self.orders.write().unwrap_or_else(|e| e.into_inner()).remove(order_id);
+        Ok(())
     }
 
-    async fn cancel_all_orders(&mut self, _symbol: Option<&Symbol>) -> Result<Vec<OrderId>> {
+    fn supports_amend(&self) -> bool {
+        true
+    }
+
+    async fn amend_order(
+        &self,
+        order_id: &OrderId,
+        new_price: Option<Decimal>,
+        new_quantity: Option<Decimal>,
+    ) -> Result<()> {
+        let txid = self.txid(order_id)?;
+
+        let result = self
+            .private_request("/0/private/AmendOrder", &amend_params(&txid, new_price, new_quantity)?)
+            .await?;
+        if result["amend_id"].as_str().is_none() {
+            return Err(ArbFinderError::Exchange(format!("Kraken did not amend {}", order_id)));
+        }
+        Ok(())
+    }
+
+    async fn cancel_all_orders(&self, _symbol: Option<&Symbol>) -> Result<Vec<OrderId>> {
         Err(ArbFinderError::Exchange("Cancel all orders not implemented yet".to_string()))
     }
 
@@ -215,24 +972,149 @@ impl ExchangeAdapter for KrakenAdapter {
         Ok(None)
     }
 
-    async fn get_open_orders(&self, _symbol: Option<&Symbol>) -> Result<Vec<Order>> {
-        Ok(Vec::new())
+    async fn get_open_orders(&self, symbol: Option<&Symbol>) -> Result<Vec<Order>> {
+        let names = match symbol {
+            Some(symbol) => HashMap::from([(kraken_pair(symbol), symbol.clone())]),
+            None => self.pair_symbols().await?,
+        };
+        let result = self.private_request("/0/private/OpenOrders", &[]).await?;
+
+        let known = self.orders.read().unwrap_or_else(|e| e.into_inner());
+        let mut orders = Vec::new();
+        for (txid, data) in result["open"].as_object().into_iter().flatten() {
+            let Some(symbol) = data["descr"]["pair"].as_str().and_then(|pair| names.get(pair)) else {
+                continue;
+            };
+            let Some(mut order) = parse_order(txid, data, symbol.clone()) else {
+                continue;
+            };
+            // Keep the id of orders placed through this adapter
+            if let Some((id, _)) = known.iter().find(|(_, known)| *known == txid) {
+                order.id = id.clone();
+            }
+            orders.push(order);
+        }
+
+        Ok(orders)
     }
 
     async fn get_order_history(&self, _symbol: Option<&Symbol>, _limit: Option<u32>) -> Result<Vec<Order>> {
-        Ok(Vec::new())
+        Err(ArbFinderError::Unsupported("Kraken order history is not implemented yet".to_string()))
     }
 
     async fn get_balances(&self) -> Result<Vec<Balance>> {
-        Ok(Vec::new())
+        let result = self.private_request("/0/private/BalanceEx", &[]).await?;
+
+        Ok(result
+            .as_object()
+            .into_iter()
+            .flatten()
+            .filter_map(|(asset, balance)| {
+                let total = websocket::value_to_decimal(&balance["balance"])?;
+                let locked = websocket::value_to_decimal(&balance["hold_trade"]).unwrap_or(Decimal::ZERO);
+                Some(Balance::new(normalize_asset(asset), total, total - locked, locked))
+            })
+            .collect())
     }
 
-    async fn get_balance(&self, _asset: &str) -> Result<Option<Balance>> {
-        Ok(None)
+    async fn get_balance(&self, asset: &str) -> Result<Option<Balance>> {
+        let balances = self.get_balances().await?;
+        Ok(balances.into_iter().find(|balance| balance.asset.eq_ignore_ascii_case(&normalize_asset(asset))))
     }
 
     async fn get_trade_history(&self, _symbol: Option<&Symbol>, _limit: Option<u32>) -> Result<Vec<OrderFill>> {
-        Ok(Vec::new())
+        Err(ArbFinderError::Unsupported("Kraken trade history is not implemented yet".to_string()))
+    }
+
+    async fn get_trading_fees(&self, symbol: &Symbol) -> Result<TradingFee> {
+        let result = self.private_request("/0/private/TradeVolume", &[("pair", kraken_pair(symbol))]).await?;
+        parse_trade_volume(&result)
+            .ok_or_else(|| ArbFinderError::InvalidData(format!("Missing trade fees for {}", symbol)))
+    }
+
+    /// The address of the asset's first deposit method, generated if the
+    /// account has none yet.
+    async fn get_deposit_address(&self, asset: &str) -> Result<DepositAddress> {
+        let kraken_asset = altname_asset(asset);
+        let methods = self.private_request("/0/private/DepositMethods", &[("asset", kraken_asset.clone())]).await?;
+        let method = methods[0]["method"]
+            .as_str()
+            .ok_or_else(|| ArbFinderError::Exchange(format!("Kraken has no deposit method for {}", asset)))?;
+
+        let params = [("asset", kraken_asset.clone()), ("method", method.to_string())];
+        let mut result = self.private_request("/0/private/DepositAddresses", &params).await?;
+        if result.as_array().map(Vec::is_empty).unwrap_or(true) {
+            let params = [("asset", kraken_asset), ("method", method.to_string()), ("new", "true".to_string())];
+            result = self.private_request("/0/private/DepositAddresses", &params).await?;
+        }
+        parse_deposit_address(asset, method, &result)
+            .ok_or_else(|| ArbFinderError::InvalidData(format!("Malformed deposit address for {}", asset)))
+    }
+
+    /// Kraken only withdraws to addresses saved and verified on the
+    /// account, so `address` must be one of them.
+    async fn withdraw(&self, asset: &str, amount: Decimal, address: &DepositAddress) -> Result<Withdrawal> {
+        let kraken_asset = altname_asset(asset);
+        let saved = self.private_request("/0/private/WithdrawAddresses", &[("asset", kraken_asset.clone())]).await?;
+        let key = withdrawal_key(&saved, address).ok_or_else(|| {
+            ArbFinderError::Exchange(format!("{} is not a verified Kraken withdrawal address", address.address))
+        })?;
+
+        let params = [
+            ("asset", kraken_asset),
+            ("key", key),
+            ("amount", amount.normalize().to_string()),
+            ("address", address.address.clone()),
+        ];
+        let result = self.private_request("/0/private/Withdraw", &params).await?;
+        let id = result["refid"]
+            .as_str()
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing withdrawal refid".to_string()))?;
+
+        Ok(Withdrawal {
+            id: id.to_string(),
+            venue: VenueId::Kraken,
+            asset: asset.to_string(),
+            amount,
+            address: address.clone(),
+            requested_at: Utc::now(),
+        })
+    }
+
+    async fn get_withdrawal_progress(&self, withdrawal: &Withdrawal) -> Result<WithdrawalProgress> {
+        let result = self
+            .private_request("/0/private/WithdrawStatus", &[("asset", altname_asset(&withdrawal.asset))])
+            .await?;
+        parse_withdrawal_progress(&withdrawal.id, &result)
+            .ok_or_else(|| ArbFinderError::InvalidData(format!("Kraken has no withdrawal {}", withdrawal.id)))
+    }
+
+    async fn get_deposits(&self, asset: &str, since: DateTime<Utc>) -> Result<Vec<Deposit>> {
+        let params = [("asset", altname_asset(asset)), ("start", since.timestamp().to_string())];
+        let result = self.private_request("/0/private/DepositStatus", &params).await?;
+        Ok(result
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| parse_deposit(asset, entry))
+            .collect())
+    }
+
+    async fn rotate_credentials(&self, credentials: &VenueCredentials) -> Result<bool> {
+        let mut current = self.credentials.write().unwrap_or_else(|e| e.into_inner());
+        let unchanged = current.as_ref().is_some_and(|current| {
+            current.api_key == credentials.api_key && current.secret_key == credentials.secret_key
+        });
+        if unchanged {
+            return Ok(false);
+        }
+        // Nonces only have to keep rising for each key, which they still do
+        *current = Some(credentials.clone());
+        Ok(true)
+    }
+
+    async fn verify_credentials(&self) -> Result<()> {
+        self.private_request("/0/private/BalanceEx", &[]).await.map(|_| ())
     }
 
     async fn get_account_info(&self) -> Result<AccountInfo> {
@@ -254,6 +1136,100 @@ impl ExchangeAdapter for KrakenAdapter {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_parse_ohlc() {
+        let result = serde_json::json!({
+            "XXBTZUSD": [
+                [1688671200, "30306.1", "30306.2", "30305.7", "30305.7", "30306.1", "3.39243896", 23],
+                [1688671260, "30304.5", "30304.5", "30300.0", "30300.0", "30300.3", "4.42996871", 18]
+            ],
+            "last": 1688672160
+        });
+        let candles = parse_ohlc(&Symbol::new("BTC", "USD"), CandleInterval::OneMinute, &result);
+        assert_eq!(candles.len(), 2);
+        assert_eq!(candles[0].open, dec!(30306.1));
+        assert_eq!(candles[1].low, dec!(30300.0));
+        assert_eq!(candles[1].volume, dec!(4.42996871));
+        assert_eq!(candles[1].timestamp.timestamp(), 1688671260);
+        assert_eq!(candles[1].interval, "1m");
+    }
+
+    #[test]
+    fn test_parse_trades() {
+        let result = serde_json::json!({
+            "XXBTZUSD": [
+                ["30243.40000", "0.34507674", 1688669448.2346, "b", "m", "", 61044952],
+                ["30243.30000", "0.00376960", 1688669448.2347, "s", "l", "", 61044953]
+            ],
+            "last": "1688671969993150842"
+        });
+        let trades = parse_trades(&Symbol::new("BTC", "USD"), &result);
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].price, dec!(30243.4));
+        assert_eq!(trades[0].quantity, dec!(0.34507674));
+        assert_eq!(trades[0].side, Side::Bid);
+        assert_eq!(trades[1].side, Side::Ask);
+        assert_eq!(trades[1].trade_id, "61044953");
+        assert_eq!(trades[1].timestamp.timestamp_millis(), 1688669448235);
+    }
+
+    #[test]
+    fn test_parse_trade_volume() {
+        let result = serde_json::json!({
+            "currency": "ZUSD",
+            "volume": "200709587.4223",
+            "fees": {"XXBTZUSD": {"fee": "0.1000", "minfee": "0.1000", "maxfee": "0.2600", "tiervolume": "10000000.0000"}},
+            "fees_maker": {"XXBTZUSD": {"fee": "0.0000", "minfee": "0.0000", "maxfee": "0.1600", "tiervolume": "10000000.0000"}}
+        });
+        let fee = parse_trade_volume(&result).unwrap();
+        assert_eq!(fee.taker_fee, dec!(0.001));
+        assert_eq!(fee.maker_fee, Decimal::ZERO);
+        assert_eq!(fee.tier.as_deref(), Some("30-day volume 200709587.4223 ZUSD"));
+    }
+
+    #[test]
+    fn test_deposit_and_withdrawal_addresses() {
+        let result = serde_json::json!([
+            {"address": "rLHzPsX6oXkzU2qL12kHCH8G8cnZv1rBJh", "expiretm": "0", "new": true, "tag": "1361101127"}
+        ]);
+        let address = parse_deposit_address("XRP", "Ripple XRP", &result).unwrap();
+        assert_eq!(address.address, "rLHzPsX6oXkzU2qL12kHCH8G8cnZv1rBJh");
+        assert_eq!(address.tag.as_deref(), Some("1361101127"));
+        assert_eq!(address.network.as_deref(), Some("Ripple XRP"));
+        assert!(parse_deposit_address("XRP", "Ripple XRP", &serde_json::json!([])).is_none());
+
+        let saved = serde_json::json!([
+            {"address": "bc1qxdsh4sdd29h6ldehz0se5c61asq8cgwyjf2y3z", "asset": "XBT", "method": "Bitcoin", "key": "cold", "verified": true},
+            {"address": "bc1qunverified", "asset": "XBT", "method": "Bitcoin", "key": "new", "verified": false}
+        ]);
+        let cold = DepositAddress::new("BTC", "bc1qxdsh4sdd29h6ldehz0se5c61asq8cgwyjf2y3z");
+        assert_eq!(withdrawal_key(&saved, &cold).as_deref(), Some("cold"));
+        assert!(withdrawal_key(&saved, &DepositAddress::new("BTC", "bc1qunverified")).is_none());
+
+        let withdrawals = serde_json::json!([
+            {"method": "Bitcoin", "asset": "XXBT", "refid": "FTQcuak-V6Za8qrWnhzTx67yYHz8Tg", "txid": "",
+             "amount": "0.72485000", "fee": "0.00015000", "time": 1617014586, "status": "Pending"},
+            {"method": "Bitcoin", "asset": "XXBT", "refid": "FTQcuak-V6Za8qrPnhsTx47yYLz8Tg", "txid": "",
+             "amount": "0.72485000", "fee": "0.00015000", "time": 1617015423, "status": "Failure", "status-prop": "canceled"}
+        ]);
+        let progress = parse_withdrawal_progress("FTQcuak-V6Za8qrWnhzTx67yYHz8Tg", &withdrawals).unwrap();
+        assert_eq!(progress, WithdrawalProgress { status: TransferStatus::Pending, tx_id: None });
+        let progress = parse_withdrawal_progress("FTQcuak-V6Za8qrPnhsTx47yYLz8Tg", &withdrawals).unwrap();
+        assert_eq!(progress.status, TransferStatus::Failed);
+        assert!(parse_withdrawal_progress("unknown", &withdrawals).is_none());
+
+        let deposit = serde_json::json!({
+            "method": "Bitcoin", "asset": "XXBT", "refid": "FTQcuak-V6Za8qrWnhzTx67yYHz8Tg",
+            "txid": "6544b41b607d8b2512baf801755a3a87b6890eacdb451be8a94059fb11f0a8d9",
+            "amount": "0.78125000", "fee": "0.00000000", "time": 1688992722, "status": "Settled"
+        });
+        let deposit = parse_deposit("BTC", &deposit).unwrap();
+        assert_eq!(deposit.asset, "BTC");
+        assert_eq!(deposit.status, TransferStatus::Confirming);
+        assert_eq!(deposit.amount, Decimal::new(78125, 5));
+    }
 
     #[tokio::test]
     async fn test_kraken_adapter_creation() {
@@ -262,6 +1238,97 @@ mod tests {
         assert!(!adapter.is_connected().await);
     }
 
+    #[tokio::test]
+    async fn test_orderbook_subscriptions() {
+        let mut adapter = KrakenAdapter::new();
+        assert!(adapter.market_data_stream().await.is_err());
+
+        let btc = Symbol::new("XXBT", "ZUSD");
+        adapter.subscribe_orderbook(&btc, Some(25)).await.unwrap();
+        adapter.subscribe_orderbook(&Symbol::new("ETH", "EUR"), None).await.unwrap();
+        adapter.subscribe_orderbook(&btc, Some(100)).await.unwrap();
+        assert_eq!(adapter.subscriptions.len(), 2);
+        adapter.unsubscribe_orderbook(&btc).await.unwrap();
+        assert_eq!(adapter.subscriptions, vec![(Symbol::new("ETH", "EUR"), None)]);
+
+        assert_eq!(kraken_symbol(&btc), "BTC/USD");
+        assert_eq!(kraken_symbol(&Symbol::new("XDG", "USDT")), "DOGE/USDT");
+    }
+
+    #[test]
+    fn test_sign() {
+        // Example from Kraken's REST authentication docs
+        let adapter = KrakenAdapter::with_credentials(
+            "key",
+            "kQH5HW/8p1uGOVjbgWA7FunAmGO8lsSUXNsu3eow76sz84Q18fWxnyRzBHCd3pd5nE9qa99HAZtuZuj6F1huXg==",
+        );
+        let signature = KrakenAdapter::sign(
+            &adapter.credentials().unwrap().secret_key,
+            "/0/private/AddOrder",
+            1616492376594,
+            "nonce=1616492376594&ordertype=limit&pair=XBTUSD&price=37500&type=buy&volume=1.25",
+        )
+        .unwrap();
+        assert_eq!(
+            signature,
+            "4/dpxb3iT4tp/ZCVEwSnEsLxx0bqyhLpdfOpc6fn7OR8+UClSV5n9E6aSS8MPtnRfp32bAb0nmbRn6H8ndwLUQ=="
+        );
+
+        let first = adapter.next_nonce();
+        assert!(adapter.next_nonce() > first);
+    }
+
+    #[test]
+    fn test_orders() {
+        let btc = Symbol::new("BTC", "USD");
+        assert_eq!(kraken_pair(&btc), "XBTUSD");
+        assert_eq!(kraken_pair(&Symbol::new("XXDG", "ZEUR")), "XDGEUR");
+
+        let mut request = OrderRequest::new_limit(btc.clone(), OrderSide::Buy, dec!(1.25), dec!(37500));
+        request.post_only = true;
+        let params = order_params(&request, "id").unwrap();
+        assert!(params.contains(&("ordertype", "limit".to_string())));
+        assert!(params.contains(&("price", "37500".to_string())));
+        assert!(params.contains(&("oflags", "post".to_string())));
+        request.order_type = OrderType::FillOrKill;
+        assert!(order_params(&request, "id").is_err());
+
+        let params = amend_params("OQCLML-BW3P3-BUCMWZ", None, Some(dec!(0.50))).unwrap();
+        assert_eq!(params, vec![("txid", "OQCLML-BW3P3-BUCMWZ".to_string()), ("order_qty", "0.5".to_string())]);
+        assert!(amend_params("OQCLML-BW3P3-BUCMWZ", None, None).is_err());
+
+        let data = serde_json::json!({
+            "status": "open",
+            "opentm": 1616665496.7808,
+            "vol": "1.25000000",
+            "vol_exec": "0.50000000",
+            "price": "37400.0",
+            "oflags": "fciq",
+            "cl_ord_id": "id",
+            "descr": {
+                "pair": "XBTUSD",
+                "type": "sell",
+                "ordertype": "stop-loss-limit",
+                "price": "37000.0",
+                "price2": "36900.0",
+            },
+        });
+        let order = parse_order("OQCLML-BW3P3-BUCMWZ", &data, btc).unwrap();
+        assert_eq!(order.venue_order_id.as_deref(), Some("OQCLML-BW3P3-BUCMWZ"));
+        assert_eq!(order.side, OrderSide::Sell);
+        assert_eq!(order.order_type, OrderType::StopLimit);
+        assert_eq!(order.stop_price, Some(dec!(37000)));
+        assert_eq!(order.price, Some(dec!(36900)));
+        assert_eq!(order.remaining_quantity, dec!(0.75));
+        assert_eq!(order.average_fill_price, Some(dec!(37400)));
+        assert_eq!(order.status, OrderStatus::PartiallyFilled);
+
+        let error = serde_json::json!({ "error": ["EOrder:Insufficient funds"] });
+        assert!(matches!(check_errors(&error), Err(ArbFinderError::InvalidOrder(_))));
+        let error = serde_json::json!({ "error": ["EAPI:Invalid nonce"] });
+        assert!(matches!(check_errors(&error), Err(ArbFinderError::Authentication(_))));
+    }
+
     #[tokio::test]
     async fn test_kraken_connect() {
         let mut adapter = KrakenAdapter::new();
@@ -0,0 +1,139 @@
+//! Kraken asset and pair names
+//!
+//! Kraken's REST API still names its oldest assets with legacy ISO 4217-A3
+//! style codes: an `X` prefix for crypto and `Z` for fiat (`XXBT`, `ZUSD`),
+//! and `XBT`/`XDG` for bitcoin and dogecoin. Pairs come as those codes run
+//! together (`XXBTZUSD`), as altnames (`XBTUSD`) or as wsnames (`XBT/USD`).
+//! Every form normalizes to the names other venues use, e.g. `BTC/USD`.
+
+use arbfinder_core::{ArbFinderError, OrderSide, OrderType, Result, Symbol};
+use arbfinder_exchange::{DefaultSymbolNormalizer, SymbolNormalizer};
+
+/// Assets still listed under an `X`/`Z`-prefixed legacy code.
+const LEGACY_ASSETS: &[&str] = &[
+    "XXBT", "XXDG", "XETH", "XLTC", "XXRP", "XXLM", "XXMR", "XZEC", "XETC", "XREP", "XMLN", "ZUSD",
+    "ZEUR", "ZGBP", "ZCAD", "ZJPY", "ZAUD", "ZCHF",
+];
+
+/// Quotes tried, longest first, when splitting an altname like `XBTUSDT`.
+const QUOTES: &[&str] = &[
+    "USDT", "USDC", "PYUSD", "DAI", "USD", "EUR", "GBP", "CAD", "JPY", "AUD", "CHF", "XBT", "ETH", "DOT",
+];
+
+/// `asset` under the name other venues use: `XXBT` and `XBT` become `BTC`,
+/// `ZUSD` becomes `USD`. Suffixes marking staked or earn balances, such as
+/// `XBT.F`, are kept.
+pub fn normalize_asset(asset: &str) -> String {
+    let (code, suffix) = match asset.split_once('.') {
+        Some((code, suffix)) => (code, Some(suffix)),
+        None => (asset, None),
+    };
+    let code = if LEGACY_ASSETS.contains(&code) { &code[1..] } else { code };
+    let code = match code {
+        "XBT" => "BTC",
+        "XDG" => "DOGE",
+        _ => code,
+    };
+    match suffix {
+        Some(suffix) => format!("{}.{}", code, suffix),
+        None => code.to_string(),
+    }
+}
+
+/// `asset` as Kraken's altnames spell it, e.g. `XBT` for `BTC`.
+pub fn altname_asset(asset: &str) -> String {
+    match normalize_asset(asset).as_str() {
+        "BTC" => "XBT".to_string(),
+        "DOGE" => "XDG".to_string(),
+        asset => asset.to_string(),
+    }
+}
+
+/// Parses a pair in any of Kraken's forms: `XXBTZUSD`, `XBTUSD`, `XBT/USD`
+/// or `BTC/USD`.
+pub fn parse_pair(pair: &str) -> Option<Symbol> {
+    if let Some((base, quote)) = pair.split_once('/') {
+        return Some(Symbol::new(normalize_asset(base), normalize_asset(quote)));
+    }
+    // Two legacy codes run together
+    if pair.len() == 8 && LEGACY_ASSETS.contains(&&pair[..4]) && LEGACY_ASSETS.contains(&&pair[4..]) {
+        return Some(Symbol::new(normalize_asset(&pair[..4]), normalize_asset(&pair[4..])));
+    }
+    QUOTES
+        .iter()
+        .find(|quote| pair.len() > quote.len() && pair.ends_with(*quote))
+        .map(|quote| {
+            let base = &pair[..pair.len() - quote.len()];
+            Symbol::new(normalize_asset(base), normalize_asset(quote))
+        })
+}
+
+/// Symbol normalizer for Kraken, resolving its legacy asset codes. Sides
+/// and order types go through the default mappings.
+#[derive(Debug, Clone, Default)]
+pub struct KrakenSymbolNormalizer {
+    default: DefaultSymbolNormalizer,
+}
+
+impl KrakenSymbolNormalizer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SymbolNormalizer for KrakenSymbolNormalizer {
+    fn normalize_symbol(&self, exchange_symbol: &str) -> Result<Symbol> {
+        parse_pair(exchange_symbol)
+            .ok_or_else(|| ArbFinderError::InvalidData(format!("Unable to parse Kraken pair: {}", exchange_symbol)))
+    }
+
+    /// The pair's altname, accepted by every REST endpoint.
+    fn denormalize_symbol(&self, symbol: &Symbol) -> Result<String> {
+        Ok(format!("{}{}", altname_asset(symbol.base()), altname_asset(symbol.quote())))
+    }
+
+    fn normalize_side(&self, exchange_side: &str) -> Result<OrderSide> {
+        self.default.normalize_side(exchange_side)
+    }
+
+    fn denormalize_side(&self, side: OrderSide) -> String {
+        self.default.denormalize_side(side)
+    }
+
+    fn normalize_order_type(&self, exchange_type: &str) -> Result<OrderType> {
+        self.default.normalize_order_type(exchange_type)
+    }
+
+    fn denormalize_order_type(&self, order_type: OrderType) -> String {
+        self.default.denormalize_order_type(order_type)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_legacy_codes() {
+        assert_eq!(normalize_asset("XXBT"), "BTC");
+        assert_eq!(normalize_asset("XBT"), "BTC");
+        assert_eq!(normalize_asset("ZUSD"), "USD");
+        assert_eq!(normalize_asset("XXDG"), "DOGE");
+        assert_eq!(normalize_asset("XBT.F"), "BTC.F");
+        // Newer assets keep their names, X or not
+        assert_eq!(normalize_asset("XTZ"), "XTZ");
+        assert_eq!(normalize_asset("SOL"), "SOL");
+        assert_eq!(altname_asset("XXBT"), "XBT");
+
+        let btc_usd = Symbol::new("BTC", "USD");
+        let normalizer = KrakenSymbolNormalizer::new();
+        for pair in ["XXBTZUSD", "XBTUSD", "XBT/USD", "BTC/USD"] {
+            assert_eq!(normalizer.normalize_symbol(pair).unwrap(), btc_usd);
+        }
+        assert_eq!(normalizer.normalize_symbol("XETHXXBT").unwrap(), Symbol::new("ETH", "BTC"));
+        assert_eq!(normalizer.normalize_symbol("SOLUSDT").unwrap(), Symbol::new("SOL", "USDT"));
+        assert_eq!(normalizer.denormalize_symbol(&btc_usd).unwrap(), "XBTUSD");
+        assert_eq!(normalizer.denormalize_symbol(&Symbol::new("XXBT", "ZUSD")).unwrap(), "XBTUSD");
+        assert!(normalizer.normalize_symbol("???").is_err());
+    }
+}
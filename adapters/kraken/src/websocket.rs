@@ -0,0 +1,379 @@
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use crate::kraken_symbol;
+
+pub const KRAKEN_WS_URL: &str = "wss://ws.kraken.com/v2";
+
+/// Levels per side covered by the `book` channel checksum.
+const CHECKSUM_DEPTH: usize = 10;
+
+/// Book depths the `book` channel accepts.
+const BOOK_DEPTHS: [u32; 5] = [10, 25, 100, 500, 1000];
+
+/// The smallest depth Kraken offers covering `depth` levels; Kraken's
+/// default of 10 without one.
+pub fn book_depth(depth: Option<u32>) -> u32 {
+    let depth = depth.unwrap_or(BOOK_DEPTHS[0]);
+    BOOK_DEPTHS
+        .iter()
+        .copied()
+        .find(|allowed| *allowed >= depth)
+        .unwrap_or(BOOK_DEPTHS[BOOK_DEPTHS.len() - 1])
+}
+
+fn book_message(method: &str, symbols: &[Symbol], depth: u32) -> String {
+    let symbols: Vec<String> = symbols.iter().map(kraken_symbol).collect();
+    serde_json::json!({
+        "method": method,
+        "params": {
+            "channel": "book",
+            "symbol": symbols,
+            "depth": depth,
+            "snapshot": true,
+        },
+    })
+    .to_string()
+}
+
+/// One request subscribing `symbols` to the `book` channel at `depth`.
+pub fn subscribe_message(symbols: &[Symbol], depth: u32) -> String {
+    book_message("subscribe", symbols, depth)
+}
+
+/// An application-level ping, answered by a `pong` echoing `req_id`.
+pub fn ping_message(req_id: u64) -> String {
+    serde_json::json!({ "method": "ping", "req_id": req_id }).to_string()
+}
+
+/// v2 sends prices and quantities as JSON numbers, which serde_json prints
+/// back in their shortest exact form.
+pub(crate) fn value_to_decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::Number(n) => {
+            let n = n.to_string();
+            Decimal::from_str(&n).or_else(|_| Decimal::from_scientific(&n)).ok()
+        }
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Decimal places a pair's prices and quantities are quoted to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Precision {
+    pub price: u32,
+    pub quantity: u32,
+}
+
+/// `value` at `decimals` places with the point and leading zeros removed,
+/// as Kraken's checksum takes it: 0.05005 at 5 places is `5005`.
+fn checksum_field(value: Decimal, decimals: u32) -> String {
+    let formatted = format!("{:.*}", decimals as usize, value).replace('.', "");
+    let trimmed = formatted.trim_start_matches('0');
+    if trimmed.is_empty() { "0".to_string() } else { trimmed.to_string() }
+}
+
+/// CRC32 of the top ten asks, best first, then the top ten bids, each
+/// level as its price and quantity written by [`checksum_field`].
+pub fn book_checksum(book: &OrderBook, precision: Precision) -> u32 {
+    let asks = book.asks.values().take(CHECKSUM_DEPTH);
+    let bids = book.bids.values().rev().take(CHECKSUM_DEPTH);
+
+    let mut input = String::new();
+    for level in asks.chain(bids) {
+        input.push_str(&checksum_field(level.price, precision.price));
+        input.push_str(&checksum_field(level.quantity, precision.quantity));
+    }
+    crc32fast::hash(input.as_bytes())
+}
+
+/// Handles the Kraken v2 `book` channel.
+///
+/// Each symbol starts with a snapshot followed by updates of changed
+/// levels, a zero quantity removing one. Levels pushed past the subscribed
+/// depth are dropped, as Kraken no longer reports on them. Every message
+/// carries a checksum of the resulting top ten levels; a mismatch means the
+/// book has drifted, so it is dropped and the symbol resubscribed for a
+/// fresh snapshot.
+pub struct KrakenOrderbookStream {
+    /// Subscribed symbols by v2 symbol, e.g. `BTC/USD`.
+    symbols: HashMap<String, Symbol>,
+    precisions: HashMap<String, Precision>,
+    books: HashMap<String, OrderBook>,
+    depth: u32,
+    update_tx: mpsc::UnboundedSender<MarketData>,
+    outbound_tx: mpsc::UnboundedSender<String>,
+    /// Told of `pong`s to the pings sent on the connection.
+    heartbeat: Option<HeartbeatManager>,
+}
+
+impl KrakenOrderbookStream {
+    /// `precisions` holds each symbol's decimals by v2 symbol; symbols
+    /// without one are streamed unchecked.
+    pub fn new(
+        symbols: impl IntoIterator<Item = Symbol>,
+        precisions: HashMap<String, Precision>,
+        depth: u32,
+        update_tx: mpsc::UnboundedSender<MarketData>,
+        outbound_tx: mpsc::UnboundedSender<String>,
+    ) -> Self {
+        Self {
+            symbols: symbols.into_iter().map(|symbol| (kraken_symbol(&symbol), symbol)).collect(),
+            precisions,
+            books: HashMap::new(),
+            depth,
+            update_tx,
+            outbound_tx,
+            heartbeat: None,
+        }
+    }
+
+    pub fn with_heartbeat(mut self, heartbeat: HeartbeatManager) -> Self {
+        self.heartbeat = Some(heartbeat);
+        self
+    }
+
+    pub fn get_orderbook(&self, symbol: &Symbol) -> Option<&OrderBook> {
+        self.books.get(&kraken_symbol(symbol))
+    }
+
+    fn resubscribe(&mut self, id: &str) {
+        self.books.remove(id);
+        if let Some(symbol) = self.symbols.get(id) {
+            let symbols = [symbol.clone()];
+            for method in ["unsubscribe", "subscribe"] {
+                let _ = self.outbound_tx.send(book_message(method, &symbols, self.depth));
+            }
+        }
+    }
+
+    fn process_book(&mut self, kind: &str, entry: &Value) {
+        let Some(id) = entry["symbol"].as_str() else {
+            return;
+        };
+        let Some(symbol) = self.symbols.get(id).cloned() else {
+            return;
+        };
+
+        let book = match kind {
+            "snapshot" => {
+                self.books.insert(id.to_string(), OrderBook::new(symbol));
+                self.books.get_mut(id)
+            }
+            _ => self.books.get_mut(id),
+        };
+        let Some(book) = book else {
+            debug!("Kraken update for {} before snapshot", id);
+            return;
+        };
+
+        for (levels, side) in [(&entry["bids"], Side::Bid), (&entry["asks"], Side::Ask)] {
+            for level in levels.as_array().into_iter().flatten() {
+                let (Some(price), Some(quantity)) = (value_to_decimal(&level["price"]), value_to_decimal(&level["qty"]))
+                else {
+                    continue;
+                };
+                match side {
+                    Side::Bid => book.update_bid(price, quantity),
+                    Side::Ask => book.update_ask(price, quantity),
+                }
+            }
+        }
+
+        // Keep to the subscribed depth: worst bids first, worst asks last
+        while book.bids.len() > self.depth as usize {
+            book.bids.pop_first();
+        }
+        while book.asks.len() > self.depth as usize {
+            book.asks.pop_last();
+        }
+        if let Some(timestamp) = entry["timestamp"].as_str().and_then(|ts| ts.parse::<DateTime<Utc>>().ok()) {
+            book.timestamp = timestamp;
+        }
+
+        if let (Some(expected), Some(precision)) = (entry["checksum"].as_u64(), self.precisions.get(id)) {
+            let actual = book_checksum(book, *precision);
+            if u64::from(actual) != expected {
+                warn!(
+                    "Kraken checksum mismatch for {}: expected {}, computed {}; resubscribing",
+                    id, expected, actual
+                );
+                self.resubscribe(id);
+                return;
+            }
+        }
+
+        let _ = self.update_tx.send(MarketData::OrderBook(book.clone()));
+    }
+}
+
+#[async_trait]
+impl WebSocketHandler for KrakenOrderbookStream {
+    async fn on_message(&mut self, message: &str) -> Result<()> {
+        let message: Value = serde_json::from_str(message)?;
+        if message["method"] == "pong" {
+            if let (Some(heartbeat), Some(req_id)) = (&self.heartbeat, message["req_id"].as_u64()) {
+                heartbeat.record_pong_to(req_id).await;
+            }
+            return Ok(());
+        }
+        if message["method"].is_string() {
+            if message["success"].as_bool() == Some(false) {
+                warn!("Kraken {} failed: {}", message["method"], message["error"]);
+            }
+            return Ok(());
+        }
+
+        match message["channel"].as_str() {
+            Some("book") => {
+                let kind = message["type"].as_str().unwrap_or("update").to_string();
+                for entry in message["data"].as_array().into_iter().flatten() {
+                    self.process_book(&kind, entry);
+                }
+            }
+            Some("heartbeat") | Some("status") => {}
+            _ => debug!("Ignoring Kraken message: {}", message),
+        }
+        Ok(())
+    }
+
+    async fn on_connect(&mut self) -> Result<()> {
+        info!("Kraken WebSocket connected for {} symbols", self.symbols.len());
+        Ok(())
+    }
+
+    async fn on_disconnect(&mut self) -> Result<()> {
+        warn!("Kraken WebSocket disconnected");
+        self.books.clear();
+        Ok(())
+    }
+
+    async fn on_error(&mut self, error: &ArbFinderError) -> Result<()> {
+        error!("Kraken WebSocket error: {}", error);
+        Ok(())
+    }
+
+    async fn on_ping(&mut self) -> Result<()> {
+        debug!("Received Kraken ping");
+        Ok(())
+    }
+
+    async fn on_pong(&mut self) -> Result<()> {
+        debug!("Received Kraken pong");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PRECISION: Precision = Precision { price: 1, quantity: 8 };
+
+    fn book_message(kind: &str, bids: &str, asks: &str, checksum: u32) -> String {
+        format!(
+            r#"{{"channel":"book","type":"{}","data":[{{"symbol":"BTC/USD","bids":{},"asks":{},"checksum":{},"timestamp":"2023-10-06T17:35:55.440295Z"}}]}}"#,
+            kind, bids, asks, checksum
+        )
+    }
+
+    #[test]
+    fn test_checksum_string_layout() {
+        let mut book = OrderBook::new(Symbol::new("BTC", "USD"));
+        book.update_bid(Decimal::from_str("45283.5").unwrap(), Decimal::from_str("0.1").unwrap());
+        book.update_ask(Decimal::from_str("45285.2").unwrap(), Decimal::from_str("0.001").unwrap());
+        book.update_ask(Decimal::from_str("45286.4").unwrap(), Decimal::from_str("1.5").unwrap());
+
+        // Asks first, point and leading zeros dropped, trailing zeros kept
+        let expected = crc32fast::hash(b"45285210000045286415000000045283510000000");
+        assert_eq!(book_checksum(&book, PRECISION), expected);
+        assert_eq!(book_depth(None), 10);
+        assert_eq!(book_depth(Some(30)), 100);
+        assert_eq!(book_depth(Some(5000)), 1000);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_update_and_checksum() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let (outbound_tx, mut outbound_rx) = mpsc::unbounded_channel();
+        let symbol = Symbol::new("BTC", "USD");
+        let precisions = HashMap::from([("BTC/USD".to_string(), PRECISION)]);
+        let mut stream = KrakenOrderbookStream::new([symbol.clone()], precisions, 10, tx, outbound_tx);
+
+        let mut expected = OrderBook::new(symbol.clone());
+        expected.update_bid(Decimal::from_str("45283.5").unwrap(), Decimal::from_str("0.1").unwrap());
+        expected.update_ask(Decimal::from_str("45285.2").unwrap(), Decimal::from_str("0.001").unwrap());
+        stream
+            .on_message(&book_message(
+                "snapshot",
+                r#"[{"price":45283.5,"qty":0.1}]"#,
+                r#"[{"price":45285.2,"qty":0.001}]"#,
+                book_checksum(&expected, PRECISION),
+            ))
+            .await
+            .unwrap();
+        match rx.recv().await.unwrap() {
+            MarketData::OrderBook(book) => {
+                assert_eq!(book.best_bid().unwrap().price, Decimal::from_str("45283.5").unwrap());
+                assert_eq!(book.best_ask().unwrap().quantity, Decimal::from_str("0.001").unwrap());
+            }
+            other => panic!("expected order book, got {:?}", other),
+        }
+
+        expected.update_bid(Decimal::from_str("45283.5").unwrap(), Decimal::ZERO);
+        stream
+            .on_message(&book_message(
+                "update",
+                r#"[{"price":45283.5,"qty":0}]"#,
+                "[]",
+                book_checksum(&expected, PRECISION),
+            ))
+            .await
+            .unwrap();
+        match rx.recv().await.unwrap() {
+            MarketData::OrderBook(book) => assert!(book.bids.is_empty()),
+            other => panic!("expected order book, got {:?}", other),
+        }
+
+        // An update whose checksum doesn't match drops the book and resubscribes
+        stream
+            .on_message(&book_message("update", r#"[{"price":45280.0,"qty":1}]"#, "[]", 1))
+            .await
+            .unwrap();
+        assert!(stream.get_orderbook(&symbol).is_none());
+        assert!(outbound_rx.recv().await.unwrap().contains("unsubscribe"));
+        assert!(outbound_rx.recv().await.unwrap().contains(r#""method":"subscribe""#));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_pong_answers_ping() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let (outbound_tx, _outbound_rx) = mpsc::unbounded_channel();
+        let heartbeat = HeartbeatManager::default();
+        let mut stream = KrakenOrderbookStream::new([Symbol::new("BTC", "USD")], HashMap::new(), 10, tx, outbound_tx)
+            .with_heartbeat(heartbeat.clone());
+
+        let req_id = heartbeat.record_ping().await;
+        assert_eq!(ping_message(req_id), format!(r#"{{"method":"ping","req_id":{}}}"#, req_id));
+        stream
+            .on_message(&format!(
+                r#"{{"method":"pong","req_id":{},"time_in":"2023-09-24T14:10:23.799685Z","time_out":"2023-09-24T14:10:23.799703Z"}}"#,
+                req_id
+            ))
+            .await
+            .unwrap();
+        let status = heartbeat.get_status().await;
+        assert_eq!((status.pong_count, status.missed_pongs), (1, 0));
+        assert!(!heartbeat.pong_missed(req_id).await);
+    }
+}
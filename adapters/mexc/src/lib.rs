@@ -0,0 +1,299 @@
+//! MEXC Exchange Adapter
+//!
+//! Implementation of MarketDataAdapter for MEXC spot
+
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use arbfinder_exchange::{channel_stream, spawn_connection, DefaultExchangeConfig};
+use async_trait::async_trait;
+use reqwest::Client;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use tokio::sync::mpsc;
+
+pub mod websocket;
+pub use websocket::MexcMarketDataStream;
+
+const MEXC_API_URL: &str = "https://api.mexc.com";
+
+/// MEXC spot symbols are the uppercase base and quote run together, e.g. `BTCUSDT`.
+pub fn mexc_symbol(symbol: &Symbol) -> String {
+    format!("{}{}", symbol.base(), symbol.quote()).to_uppercase()
+}
+
+fn value_to_decimal(value: &serde_json::Value) -> Option<Decimal> {
+    match value {
+        serde_json::Value::String(s) => s.parse().ok(),
+        serde_json::Value::Number(n) => n.as_f64().and_then(Decimal::from_f64),
+        _ => None,
+    }
+}
+
+pub struct MexcAdapter {
+    client: Client,
+    base_url: String,
+    ws_url: String,
+    connected: bool,
+    subscriptions: Vec<Symbol>,
+}
+
+impl MexcAdapter {
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+            base_url: MEXC_API_URL.to_string(),
+            ws_url: websocket::MEXC_WS_URL.to_string(),
+            connected: false,
+            subscriptions: Vec::new(),
+        }
+    }
+
+    async fn get_request(&self, endpoint: &str) -> Result<serde_json::Value> {
+        let url = format!("{}{}", self.base_url, endpoint);
+        let response = self.client
+            .get(&url)
+            .send()
+            .await
+            .map_err(ArbFinderError::Http)?;
+
+        if !response.status().is_success() {
+            return Err(ArbFinderError::Exchange(format!(
+                "MEXC API error: {}",
+                response.status()
+            )));
+        }
+
+        response.json().await.map_err(ArbFinderError::Http)
+    }
+
+    /// Fetch the 24h rolling ticker for a symbol
+    pub async fn get_ticker(&self, symbol: &Symbol) -> Result<Ticker> {
+        let endpoint = format!("/api/v3/ticker/24hr?symbol={}", mexc_symbol(symbol));
+        let response = self.get_request(&endpoint).await?;
+
+        let price = value_to_decimal(&response["lastPrice"])
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing lastPrice".to_string()))?;
+
+        Ok(Ticker {
+            symbol: symbol.clone(),
+            price,
+            volume_24h: value_to_decimal(&response["volume"]).unwrap_or(Decimal::ZERO),
+            change_24h: value_to_decimal(&response["priceChange"]).unwrap_or(Decimal::ZERO),
+            timestamp: response["closeTime"]
+                .as_i64()
+                .and_then(DateTime::from_timestamp_millis)
+                .unwrap_or_else(Utc::now),
+        })
+    }
+
+    /// Fetch orderbook depth from MEXC
+    pub async fn get_orderbook(&self, symbol: &Symbol, limit: Option<u32>) -> Result<OrderBook> {
+        let limit = limit.unwrap_or(100).min(5000);
+        let endpoint = format!("/api/v3/depth?symbol={}&limit={}", mexc_symbol(symbol), limit);
+        let response = self.get_request(&endpoint).await?;
+
+        let mut orderbook = OrderBook::new(symbol.clone());
+        if let Some(bids) = response["bids"].as_array() {
+            for bid in bids {
+                if let (Some(price), Some(qty)) = (value_to_decimal(&bid[0]), value_to_decimal(&bid[1])) {
+                    orderbook.update_bid(price, qty);
+                }
+            }
+        }
+        if let Some(asks) = response["asks"].as_array() {
+            for ask in asks {
+                if let (Some(price), Some(qty)) = (value_to_decimal(&ask[0]), value_to_decimal(&ask[1])) {
+                    orderbook.update_ask(price, qty);
+                }
+            }
+        }
+        orderbook.sequence = response["lastUpdateId"].as_u64();
+
+        Ok(orderbook)
+    }
+}
+
+impl Default for MexcAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl MarketDataAdapter for MexcAdapter {
+    fn venue_id(&self) -> VenueId {
+        VenueId::Mexc
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        // Test connection with server time
+        let _ = self.get_server_time().await?;
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.connected = false;
+        Ok(())
+    }
+
+    async fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        let response = self.get_request("/api/v3/time").await?;
+        let server_time = response["serverTime"]
+            .as_i64()
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing serverTime".to_string()))?;
+
+        Ok(DateTime::from_timestamp_millis(server_time)
+            .unwrap_or_else(Utc::now))
+    }
+
+    async fn ping(&self) -> Result<u64> {
+        let start = std::time::Instant::now();
+        let _ = self.get_request("/api/v3/ping").await?;
+        Ok(start.elapsed().as_millis() as u64)
+    }
+
+    async fn get_symbols(&self) -> Result<Vec<Symbol>> {
+        let response = self.get_request("/api/v3/exchangeInfo").await?;
+        let symbols = response["symbols"]
+            .as_array()
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing symbols array".to_string()))?;
+
+        let mut result = Vec::new();
+        for symbol_data in symbols {
+            if !symbol_data["isSpotTradingAllowed"].as_bool().unwrap_or(true) {
+                continue;
+            }
+            if let (Some(base), Some(quote)) = (
+                symbol_data["baseAsset"].as_str(),
+                symbol_data["quoteAsset"].as_str(),
+            ) {
+                result.push(Symbol::new(base, quote));
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn get_symbol_info(&self, symbol: &Symbol) -> Result<SymbolInfo> {
+        let symbol_str = mexc_symbol(symbol);
+        let response = self
+            .get_request(&format!("/api/v3/exchangeInfo?symbol={}", symbol_str))
+            .await?;
+        let symbols = response["symbols"].as_array().ok_or_else(|| {
+            ArbFinderError::InvalidData("Missing symbols array".to_string())
+        })?;
+
+        for symbol_data in symbols {
+            if symbol_data["symbol"].as_str() == Some(&symbol_str) {
+                let base_precision = symbol_data["baseAssetPrecision"].as_u64().unwrap_or(8) as u32;
+                let quote_precision = symbol_data["quotePrecision"].as_u64().unwrap_or(8) as u32;
+                // MEXC reports status "1" for online pairs
+                let status = match symbol_data["status"].as_str() {
+                    Some("1") | Some("ENABLED") => "TRADING",
+                    _ => "INACTIVE",
+                };
+
+                return Ok(SymbolInfo {
+                    symbol: symbol.clone(),
+                    status: status.to_string(),
+                    base_asset_precision: base_precision,
+                    quote_asset_precision: quote_precision,
+                    tick_size: Decimal::new(1, quote_precision),
+                    lot_size: value_to_decimal(&symbol_data["baseSizePrecision"])
+                        .filter(|d| !d.is_zero())
+                        .unwrap_or(Decimal::new(1, base_precision)),
+                    min_order_size: value_to_decimal(&symbol_data["baseSizePrecision"])
+                        .filter(|d| !d.is_zero())
+                        .unwrap_or(Decimal::new(1, base_precision)),
+                    max_order_size: value_to_decimal(&symbol_data["maxQuoteAmount"])
+                        .unwrap_or(Decimal::new(1000000, 0)),
+                    min_notional: value_to_decimal(&symbol_data["quoteAmountPrecision"])
+                        .unwrap_or(Decimal::new(1, 0)),
+                    trading_fees: TradingFees {
+                        maker_fee: value_to_decimal(&symbol_data["makerCommission"])
+                            .unwrap_or(Decimal::ZERO),
+                        taker_fee: value_to_decimal(&symbol_data["takerCommission"])
+                            .unwrap_or(Decimal::new(5, 4)), // 0.05%
+                    },
+                });
+            }
+        }
+
+        Err(ArbFinderError::SymbolNotFound(symbol_str))
+    }
+
+    async fn subscribe_orderbook(&mut self, symbol: &Symbol, _depth: Option<u32>) -> Result<()> {
+        if !self.subscriptions.contains(symbol) {
+            self.subscriptions.push(symbol.clone());
+        }
+        Ok(())
+    }
+
+    async fn subscribe_trades(&mut self, symbol: &Symbol) -> Result<()> {
+        // Depth and deals channels are subscribed together per symbol
+        self.subscribe_orderbook(symbol, None).await
+    }
+
+    async fn subscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_orderbook(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscriptions.retain(|s| s != symbol);
+        Ok(())
+    }
+
+    async fn unsubscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn market_data_stream(&self) -> Result<MarketDataStream> {
+        if self.subscriptions.is_empty() {
+            return Err(ArbFinderError::Exchange("No MEXC symbols subscribed".to_string()));
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let handler = MexcMarketDataStream::new(self.base_url.clone(), self.subscriptions.clone(), tx);
+        let subscribe = handler.subscribe_message();
+        let config = DefaultExchangeConfig {
+            base_url: self.base_url.clone(),
+            websocket_url: self.ws_url.clone(),
+            ..Default::default()
+        };
+
+        spawn_connection(&config, &[subscribe], handler).await?;
+        Ok(channel_stream(rx))
+    }
+
+    async fn fetch_orderbook(&self, symbol: &Symbol, depth: Option<u32>) -> Result<OrderBook> {
+        self.get_orderbook(symbol, depth).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_mexc_adapter_creation() {
+        let adapter = MexcAdapter::new();
+        assert_eq!(adapter.venue_id(), VenueId::Mexc);
+        assert!(!adapter.is_connected().await);
+    }
+
+    #[test]
+    fn test_mexc_symbol_format() {
+        assert_eq!(mexc_symbol(&Symbol::new("btc", "usdt")), "BTCUSDT");
+        assert_eq!(VenueId::from("mexc"), VenueId::Mexc);
+    }
+}
@@ -0,0 +1,425 @@
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::str::FromStr;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use crate::mexc_symbol;
+
+pub const MEXC_WS_URL: &str = "wss://wbs.mexc.com/ws";
+
+const DEPTH_CHANNEL: &str = "spot@public.increase.depth.v3.api";
+const DEALS_CHANNEL: &str = "spot@public.deals.v3.api";
+
+#[derive(Debug, Clone, Deserialize)]
+struct MexcLevel {
+    p: String,
+    v: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MexcDepthUpdate {
+    #[serde(default)]
+    asks: Vec<MexcLevel>,
+    #[serde(default)]
+    bids: Vec<MexcLevel>,
+    /// Book version, sent as a string
+    r: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MexcDeal {
+    #[serde(rename = "S")]
+    side: u8, // 1 = buy, 2 = sell
+    p: String,
+    t: i64,
+    v: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct MexcDeals {
+    deals: Vec<MexcDeal>,
+}
+
+/// REST `/api/v3/depth` response used to seed the incremental stream.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MexcDepthSnapshot {
+    #[serde(rename = "lastUpdateId")]
+    pub last_update_id: u64,
+    pub bids: Vec<(String, String)>,
+    pub asks: Vec<(String, String)>,
+}
+
+enum BookState {
+    /// Waiting for a REST snapshot; updates are held until it arrives
+    Buffering(Vec<MexcDepthUpdate>),
+    Synced { book: OrderBook, version: u64 },
+}
+
+/// Handles MEXC spot depth and deals channels.
+///
+/// MEXC only streams increments, each tagged with a book version `r`. A local
+/// book is built by buffering increments, fetching a REST snapshot, dropping
+/// everything at or below its `lastUpdateId`, and then requiring every further
+/// version to be exactly one more than the last. Any gap starts over.
+pub struct MexcMarketDataStream {
+    client: Client,
+    base_url: String,
+    symbols: HashMap<String, Symbol>,
+    books: HashMap<String, BookState>,
+    update_tx: mpsc::UnboundedSender<MarketData>,
+}
+
+impl MexcMarketDataStream {
+    pub fn new(
+        base_url: String,
+        symbols: Vec<Symbol>,
+        update_tx: mpsc::UnboundedSender<MarketData>,
+    ) -> Self {
+        Self {
+            client: Client::new(),
+            base_url,
+            symbols: symbols.into_iter().map(|s| (mexc_symbol(&s), s)).collect(),
+            books: HashMap::new(),
+            update_tx,
+        }
+    }
+
+    pub fn subscribe_message(&self) -> String {
+        let mut params: Vec<String> = Vec::new();
+        for symbol in self.symbols.keys() {
+            params.push(format!("{}@{}", DEPTH_CHANNEL, symbol));
+            params.push(format!("{}@{}", DEALS_CHANNEL, symbol));
+        }
+        params.sort();
+
+        serde_json::json!({ "method": "SUBSCRIPTION", "params": params }).to_string()
+    }
+
+    pub fn get_orderbook(&self, symbol: &Symbol) -> Option<&OrderBook> {
+        match self.books.get(&mexc_symbol(symbol)) {
+            Some(BookState::Synced { book, .. }) => Some(book),
+            _ => None,
+        }
+    }
+
+    async fn fetch_snapshot(&self, symbol: &str) -> Result<MexcDepthSnapshot> {
+        let url = format!("{}/api/v3/depth?symbol={}&limit=1000", self.base_url, symbol);
+        let response = self.client.get(&url).send().await.map_err(ArbFinderError::Http)?;
+
+        if !response.status().is_success() {
+            return Err(ArbFinderError::Exchange(format!(
+                "MEXC depth snapshot error: {}",
+                response.status()
+            )));
+        }
+
+        response.json().await.map_err(ArbFinderError::Http)
+    }
+
+    /// Applies an increment. Returns true when the book needs a snapshot.
+    fn process_depth(&mut self, key: &str, update: MexcDepthUpdate) -> Result<bool> {
+        let version = u64::from_str(&update.r)
+            .map_err(|e| ArbFinderError::Parse(format!("Invalid MEXC depth version: {}", e)))?;
+
+        let state = self
+            .books
+            .entry(key.to_string())
+            .or_insert_with(|| BookState::Buffering(Vec::new()));
+
+        match state {
+            BookState::Buffering(pending) => {
+                pending.push(update);
+                Ok(true)
+            }
+            BookState::Synced { book, version: last } => {
+                if version <= *last {
+                    return Ok(false);
+                }
+                if version != *last + 1 {
+                    warn!(
+                        "MEXC {} depth gap: expected version {}, got {}; resyncing",
+                        key,
+                        *last + 1,
+                        version
+                    );
+                    *state = BookState::Buffering(vec![update]);
+                    return Ok(true);
+                }
+
+                apply_levels(book, &update);
+                *last = version;
+                book.sequence = Some(version);
+                let _ = self.update_tx.send(MarketData::OrderBook(book.clone()));
+                Ok(false)
+            }
+        }
+    }
+
+    /// Seeds the book from a snapshot and replays buffered increments on top.
+    /// If the buffer starts past the snapshot the snapshot is too old, so the
+    /// increments stay buffered and the caller should fetch again.
+    fn apply_snapshot(&mut self, key: &str, snapshot: MexcDepthSnapshot) -> Result<bool> {
+        let Some(symbol) = self.symbols.get(key).cloned() else {
+            return Ok(false);
+        };
+        let pending = match self.books.remove(key) {
+            Some(BookState::Buffering(pending)) => pending,
+            _ => Vec::new(),
+        };
+
+        let mut book = OrderBook::new(symbol);
+        for (price, quantity) in &snapshot.bids {
+            if let (Ok(price), Ok(quantity)) = (Decimal::from_str(price), Decimal::from_str(quantity)) {
+                book.update_bid(price, quantity);
+            }
+        }
+        for (price, quantity) in &snapshot.asks {
+            if let (Ok(price), Ok(quantity)) = (Decimal::from_str(price), Decimal::from_str(quantity)) {
+                book.update_ask(price, quantity);
+            }
+        }
+
+        let mut version = snapshot.last_update_id;
+        let mut replay = Vec::new();
+        for update in pending {
+            match u64::from_str(&update.r) {
+                Ok(r) if r > snapshot.last_update_id => replay.push((r, update)),
+                _ => {}
+            }
+        }
+
+        if let Some((first, _)) = replay.first() {
+            if *first != version + 1 {
+                debug!("MEXC {} snapshot {} older than buffered version {}", key, version, first);
+                let pending = replay.into_iter().map(|(_, update)| update).collect();
+                self.books.insert(key.to_string(), BookState::Buffering(pending));
+                return Ok(true);
+            }
+        }
+
+        for (r, update) in replay {
+            if r != version + 1 {
+                warn!("MEXC {} depth gap in buffered updates at version {}", key, r);
+                self.books.insert(key.to_string(), BookState::Buffering(Vec::new()));
+                return Ok(true);
+            }
+            apply_levels(&mut book, &update);
+            version = r;
+        }
+
+        book.sequence = Some(version);
+        let _ = self.update_tx.send(MarketData::OrderBook(book.clone()));
+        self.books.insert(key.to_string(), BookState::Synced { book, version });
+        Ok(false)
+    }
+
+    fn process_deals(&self, symbol: &Symbol, deals: MexcDeals) {
+        for deal in deals.deals {
+            let (Ok(price), Ok(quantity)) = (Decimal::from_str(&deal.p), Decimal::from_str(&deal.v)) else {
+                continue;
+            };
+            let side = if deal.side == 1 { Side::Bid } else { Side::Ask };
+            // MEXC deals carry no trade id
+            let trade_id = format!("{}-{}", deal.t, deal.p);
+            let mut update = Trade::new(symbol.clone(), price, quantity, side, trade_id);
+            update.timestamp = DateTime::<Utc>::from_timestamp_millis(deal.t).unwrap_or_else(Utc::now);
+
+            let _ = self.update_tx.send(MarketData::Trade(update));
+        }
+    }
+}
+
+fn apply_levels(book: &mut OrderBook, update: &MexcDepthUpdate) {
+    for level in &update.bids {
+        if let (Ok(price), Ok(quantity)) = (Decimal::from_str(&level.p), Decimal::from_str(&level.v)) {
+            book.update_bid(price, quantity);
+        }
+    }
+    for level in &update.asks {
+        if let (Ok(price), Ok(quantity)) = (Decimal::from_str(&level.p), Decimal::from_str(&level.v)) {
+            book.update_ask(price, quantity);
+        }
+    }
+}
+
+#[async_trait]
+impl WebSocketHandler for MexcMarketDataStream {
+    async fn on_message(&mut self, message: &str) -> Result<()> {
+        let value: serde_json::Value = serde_json::from_str(message)?;
+
+        // Subscription acks and PONGs: {"id":0,"code":0,"msg":"..."}
+        let Some(channel) = value.get("c").and_then(|c| c.as_str()) else {
+            if value["code"].as_i64().unwrap_or(0) != 0 {
+                warn!("MEXC WebSocket error: {}", message);
+            }
+            return Ok(());
+        };
+
+        let Some((kind, key)) = channel.rsplit_once('@') else {
+            return Ok(());
+        };
+        let Some(symbol) = self.symbols.get(key).cloned() else {
+            return Ok(());
+        };
+
+        let data = value["d"].clone();
+        if kind == DEPTH_CHANNEL {
+            let mut needs_snapshot = self.process_depth(key, serde_json::from_value(data)?)?;
+            // A stale snapshot can be refetched once straight away; after that
+            // wait for more increments rather than hammering the REST API
+            for _ in 0..2 {
+                if !needs_snapshot {
+                    break;
+                }
+                let snapshot = self.fetch_snapshot(key).await?;
+                needs_snapshot = self.apply_snapshot(key, snapshot)?;
+            }
+        } else if kind == DEALS_CHANNEL {
+            self.process_deals(&symbol, serde_json::from_value(data)?);
+        }
+
+        Ok(())
+    }
+
+    async fn on_connect(&mut self) -> Result<()> {
+        info!("MEXC WebSocket connected for {} symbols", self.symbols.len());
+        Ok(())
+    }
+
+    async fn on_disconnect(&mut self) -> Result<()> {
+        warn!("MEXC WebSocket disconnected");
+        // Versions are not replayed across connections
+        self.books.clear();
+        Ok(())
+    }
+
+    async fn on_error(&mut self, error: &ArbFinderError) -> Result<()> {
+        error!("MEXC WebSocket error: {}", error);
+        Ok(())
+    }
+
+    async fn on_ping(&mut self) -> Result<()> {
+        debug!("Received MEXC ping");
+        Ok(())
+    }
+
+    async fn on_pong(&mut self) -> Result<()> {
+        debug!("Received MEXC pong");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream() -> (MexcMarketDataStream, mpsc::UnboundedReceiver<MarketData>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let stream = MexcMarketDataStream::new(
+            "https://api.mexc.com".to_string(),
+            vec![Symbol::new("BTC", "USDT")],
+            tx,
+        );
+        (stream, rx)
+    }
+
+    fn update(version: u64, bids: &[(&str, &str)]) -> MexcDepthUpdate {
+        MexcDepthUpdate {
+            asks: Vec::new(),
+            bids: bids
+                .iter()
+                .map(|(p, v)| MexcLevel { p: p.to_string(), v: v.to_string() })
+                .collect(),
+            r: version.to_string(),
+        }
+    }
+
+    fn snapshot(last_update_id: u64) -> MexcDepthSnapshot {
+        MexcDepthSnapshot {
+            last_update_id,
+            bids: vec![("100.0".to_string(), "1".to_string())],
+            asks: vec![("101.0".to_string(), "1".to_string())],
+        }
+    }
+
+    #[test]
+    fn test_subscribe_message() {
+        let (stream, _rx) = stream();
+        let message: serde_json::Value = serde_json::from_str(&stream.subscribe_message()).unwrap();
+        assert_eq!(message["method"], "SUBSCRIPTION");
+        assert_eq!(message["params"][0], "spot@public.deals.v3.api@BTCUSDT");
+        assert_eq!(message["params"][1], "spot@public.increase.depth.v3.api@BTCUSDT");
+    }
+
+    #[test]
+    fn test_snapshot_replays_buffered_updates() {
+        let (mut stream, mut rx) = stream();
+        assert!(stream.process_depth("BTCUSDT", update(9, &[("99.0", "1")])).unwrap());
+        assert!(stream.process_depth("BTCUSDT", update(11, &[("100.5", "2")])).unwrap());
+
+        assert!(!stream.apply_snapshot("BTCUSDT", snapshot(10)).unwrap());
+
+        let book = stream.get_orderbook(&Symbol::new("BTC", "USDT")).unwrap();
+        assert_eq!(book.best_bid().unwrap().price, Decimal::from_str("100.5").unwrap());
+        // version 9 predates the snapshot and is dropped
+        assert_eq!(book.bids.len(), 2);
+        assert_eq!(book.sequence, Some(11));
+        assert!(matches!(rx.try_recv().unwrap(), MarketData::OrderBook(_)));
+
+        assert!(!stream.process_depth("BTCUSDT", update(12, &[("100.5", "0")])).unwrap());
+        let book = stream.get_orderbook(&Symbol::new("BTC", "USDT")).unwrap();
+        assert_eq!(book.best_bid().unwrap().price, Decimal::from_str("100.0").unwrap());
+    }
+
+    #[test]
+    fn test_stale_snapshot_keeps_buffering() {
+        let (mut stream, _rx) = stream();
+        stream.process_depth("BTCUSDT", update(20, &[])).unwrap();
+
+        assert!(stream.apply_snapshot("BTCUSDT", snapshot(10)).unwrap());
+        assert!(stream.get_orderbook(&Symbol::new("BTC", "USDT")).is_none());
+
+        assert!(!stream.apply_snapshot("BTCUSDT", snapshot(19)).unwrap());
+        assert!(stream.get_orderbook(&Symbol::new("BTC", "USDT")).is_some());
+    }
+
+    #[test]
+    fn test_gap_triggers_resync() {
+        let (mut stream, _rx) = stream();
+        stream.process_depth("BTCUSDT", update(11, &[])).unwrap();
+        stream.apply_snapshot("BTCUSDT", snapshot(10)).unwrap();
+
+        assert!(stream.process_depth("BTCUSDT", update(13, &[])).unwrap());
+        assert!(stream.get_orderbook(&Symbol::new("BTC", "USDT")).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_deals() {
+        let (mut stream, mut rx) = stream();
+        let message = r#"{
+            "c": "spot@public.deals.v3.api@BTCUSDT",
+            "d": {
+                "deals": [{"S": 2, "p": "20233.84", "t": 1661927587825, "v": "0.001028"}],
+                "e": "spot@public.deals.v3.api"
+            },
+            "s": "BTCUSDT",
+            "t": 1661927587836
+        }"#;
+        stream.on_message(message).await.unwrap();
+
+        match rx.recv().await.unwrap() {
+            MarketData::Trade(trade) => {
+                assert_eq!(trade.side, Side::Ask);
+                assert_eq!(trade.price, Decimal::from_str("20233.84").unwrap());
+            }
+            other => panic!("expected trade, got {:?}", other),
+        }
+    }
+}
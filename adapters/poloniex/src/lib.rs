@@ -0,0 +1,369 @@
+//! Poloniex Exchange Adapter
+//!
+//! Implementation of MarketDataAdapter for Poloniex spot (API v3): markets,
+//! order books and trades over REST and WebSocket. Market names are resolved
+//! through the symbol normalizer, seeded from the market list on connect and
+//! from any symbol mappings file.
+
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use arbfinder_exchange::{channel_stream, spawn_connection_with, DefaultExchangeConfig};
+use async_trait::async_trait;
+use reqwest::Client;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+pub mod websocket;
+pub use websocket::PoloniexMarketDataStream;
+
+const POLONIEX_API_URL: &str = "https://api.poloniex.com";
+
+/// Poloniex drops connections that send nothing for 30 seconds.
+const POLONIEX_WS_PING_INTERVAL: Duration = Duration::from_secs(20);
+
+/// Formats a symbol as a Poloniex market name, e.g. `BTC_USDT`.
+pub fn poloniex_symbol(symbol: &Symbol) -> String {
+    format!("{}_{}", symbol.base().to_uppercase(), symbol.quote().to_uppercase())
+}
+
+fn value_to_decimal(value: &serde_json::Value) -> Option<Decimal> {
+    match value {
+        serde_json::Value::String(s) => s.parse().ok(),
+        serde_json::Value::Number(n) => n.as_f64().and_then(Decimal::from_f64),
+        _ => None,
+    }
+}
+
+/// Applies a flat `[price, quantity, price, quantity, ...]` array, as the
+/// REST order book returns each side.
+fn apply_flat_levels(book: &mut OrderBook, levels: &serde_json::Value, side: Side) {
+    let Some(levels) = levels.as_array() else {
+        return;
+    };
+    for pair in levels.chunks_exact(2) {
+        if let (Some(price), Some(quantity)) = (value_to_decimal(&pair[0]), value_to_decimal(&pair[1])) {
+            match side {
+                Side::Bid => book.update_bid(price, quantity),
+                Side::Ask => book.update_ask(price, quantity),
+            }
+        }
+    }
+}
+
+pub struct PoloniexAdapter {
+    client: Client,
+    base_url: String,
+    ws_url: String,
+    connected: bool,
+    subscriptions: Vec<Symbol>,
+    normalizer: DefaultSymbolNormalizer,
+}
+
+impl PoloniexAdapter {
+    pub fn new() -> Self {
+        Self {
+            client: Client::new(),
+            base_url: POLONIEX_API_URL.to_string(),
+            ws_url: websocket::POLONIEX_WS_URL.to_string(),
+            connected: false,
+            subscriptions: Vec::new(),
+            normalizer: DefaultSymbolNormalizer::new(),
+        }
+    }
+
+    /// Symbol normalizer seeded with every market seen so far.
+    pub fn normalizer(&self) -> &DefaultSymbolNormalizer {
+        &self.normalizer
+    }
+
+    /// Registers `symbol` under its usual market name, unless it was
+    /// mapped to another already.
+    fn register_symbol(&mut self, symbol: &Symbol) {
+        let name = self.market_name(symbol);
+        self.normalizer.add_symbol_mapping(name, symbol.clone());
+    }
+
+    fn market_name(&self, symbol: &Symbol) -> String {
+        self.normalizer
+            .denormalize_symbol(symbol)
+            .ok()
+            .filter(|name| name.contains('_'))
+            .unwrap_or_else(|| poloniex_symbol(symbol))
+    }
+
+    async fn get_request(&self, endpoint: &str) -> Result<serde_json::Value> {
+        let url = format!("{}{}", self.base_url, endpoint);
+        let response = self.client
+            .get(&url)
+            .send()
+            .await
+            .map_err(ArbFinderError::Http)?;
+
+        if !response.status().is_success() {
+            return Err(ArbFinderError::Exchange(format!(
+                "Poloniex API error: {}",
+                response.status()
+            )));
+        }
+
+        response.json().await.map_err(ArbFinderError::Http)
+    }
+
+    /// Fetch the 24h ticker for a symbol
+    pub async fn get_ticker(&self, symbol: &Symbol) -> Result<Ticker> {
+        let ticker = self
+            .get_request(&format!("/markets/{}/ticker24h", self.market_name(symbol)))
+            .await?;
+
+        let price = value_to_decimal(&ticker["close"])
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing close price".to_string()))?;
+        let timestamp = ticker["ts"]
+            .as_i64()
+            .and_then(DateTime::<Utc>::from_timestamp_millis)
+            .unwrap_or_else(Utc::now);
+
+        Ok(Ticker {
+            symbol: symbol.clone(),
+            price,
+            volume_24h: value_to_decimal(&ticker["quantity"]).unwrap_or(Decimal::ZERO),
+            change_24h: value_to_decimal(&ticker["dailyChange"]).unwrap_or(Decimal::ZERO),
+            timestamp,
+        })
+    }
+
+    /// Fetch orderbook depth from Poloniex, which accepts 5, 10, 20, 50, 100
+    /// or 150 levels.
+    pub async fn get_orderbook(&self, symbol: &Symbol, limit: Option<u32>) -> Result<OrderBook> {
+        let limit = match limit.unwrap_or(20) {
+            0..=5 => 5,
+            6..=10 => 10,
+            11..=20 => 20,
+            21..=50 => 50,
+            51..=100 => 100,
+            _ => 150,
+        };
+        let response = self
+            .get_request(&format!("/markets/{}/orderBook?limit={}", self.market_name(symbol), limit))
+            .await?;
+
+        let mut orderbook = OrderBook::new(symbol.clone());
+        apply_flat_levels(&mut orderbook, &response["bids"], Side::Bid);
+        apply_flat_levels(&mut orderbook, &response["asks"], Side::Ask);
+        if let Some(ts) = response["ts"].as_i64().and_then(DateTime::<Utc>::from_timestamp_millis) {
+            orderbook.timestamp = ts;
+        }
+
+        Ok(orderbook)
+    }
+}
+
+impl Default for PoloniexAdapter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl MarketDataAdapter for PoloniexAdapter {
+    fn venue_id(&self) -> VenueId {
+        VenueId::Poloniex
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        for symbol in self.get_symbols().await? {
+            self.register_symbol(&symbol);
+        }
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.connected = false;
+        Ok(())
+    }
+
+    async fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        let response = self.get_request("/timestamp").await?;
+        response["serverTime"]
+            .as_i64()
+            .and_then(DateTime::<Utc>::from_timestamp_millis)
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing server time".to_string()))
+    }
+
+    async fn ping(&self) -> Result<u64> {
+        let start = std::time::Instant::now();
+        let _ = self.get_request("/timestamp").await?;
+        Ok(start.elapsed().as_millis() as u64)
+    }
+
+    async fn get_symbols(&self) -> Result<Vec<Symbol>> {
+        let response = self.get_request("/markets").await?;
+        let markets = response
+            .as_array()
+            .ok_or_else(|| ArbFinderError::InvalidData("Missing market list".to_string()))?;
+
+        Ok(markets
+            .iter()
+            .filter(|m| m["state"].as_str() == Some("NORMAL"))
+            .filter_map(|m| match (m["baseCurrencyName"].as_str(), m["quoteCurrencyName"].as_str()) {
+                (Some(base), Some(quote)) => Some(DefaultSymbolNormalizer::parse_symbol_from_parts(base, quote)),
+                _ => m["symbol"].as_str().and_then(|s| self.normalizer.normalize_symbol(s).ok()),
+            })
+            .collect())
+    }
+
+    fn venue_symbol(&self, symbol: &Symbol) -> String {
+        self.market_name(symbol)
+    }
+
+    fn add_symbol_mappings(&mut self, mappings: &SymbolMappings) {
+        mappings.register(&VenueId::Poloniex, &mut self.normalizer);
+    }
+
+    async fn get_symbol_info(&self, symbol: &Symbol) -> Result<SymbolInfo> {
+        let market = self.market_name(symbol);
+        let response = self.get_request(&format!("/markets/{}", market)).await?;
+        let info = response
+            .as_array()
+            .and_then(|markets| markets.first())
+            .ok_or_else(|| ArbFinderError::SymbolNotFound(market.clone()))?;
+        let limits = &info["symbolTradeLimit"];
+
+        let price_scale = limits["priceScale"].as_u64().unwrap_or(8) as u32;
+        let quantity_scale = limits["quantityScale"].as_u64().unwrap_or(8) as u32;
+
+        Ok(SymbolInfo {
+            symbol: symbol.clone(),
+            status: info["state"].as_str().unwrap_or("UNKNOWN").to_string(),
+            base_asset_precision: quantity_scale,
+            quote_asset_precision: price_scale,
+            tick_size: Decimal::new(1, price_scale),
+            lot_size: Decimal::new(1, quantity_scale),
+            min_order_size: value_to_decimal(&limits["minQuantity"]).unwrap_or(Decimal::new(1, quantity_scale)),
+            max_order_size: Decimal::new(1000000, 0),
+            min_notional: value_to_decimal(&limits["minAmount"]).unwrap_or(Decimal::ZERO),
+            trading_fees: TradingFees {
+                maker_fee: Decimal::new(2, 3), // 0.2%
+                taker_fee: Decimal::new(2, 3), // 0.2%
+            },
+        })
+    }
+
+    async fn subscribe_orderbook(&mut self, symbol: &Symbol, _depth: Option<u32>) -> Result<()> {
+        self.register_symbol(symbol);
+        if !self.subscriptions.contains(symbol) {
+            self.subscriptions.push(symbol.clone());
+        }
+        Ok(())
+    }
+
+    async fn subscribe_trades(&mut self, symbol: &Symbol) -> Result<()> {
+        // Book and trades channels are subscribed together per symbol
+        self.subscribe_orderbook(symbol, None).await
+    }
+
+    async fn subscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_orderbook(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscriptions.retain(|s| s != symbol);
+        Ok(())
+    }
+
+    async fn unsubscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn market_data_stream(&self) -> Result<MarketDataStream> {
+        if self.subscriptions.is_empty() {
+            return Err(ArbFinderError::Exchange("No Poloniex symbols subscribed".to_string()));
+        }
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let subscribe = vec![websocket::subscribe_message(&self.subscriptions)];
+        let config = DefaultExchangeConfig {
+            base_url: self.base_url.clone(),
+            websocket_url: self.ws_url.clone(),
+            ..Default::default()
+        };
+        let normalizer = self.normalizer.clone();
+        let symbols = self.subscriptions.clone();
+
+        spawn_connection_with(&config, &subscribe, move |outbound| {
+            let keepalive = outbound.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(POLONIEX_WS_PING_INTERVAL);
+                interval.tick().await;
+                loop {
+                    interval.tick().await;
+                    if keepalive.send(r#"{"event":"ping"}"#.to_string()).is_err() {
+                        break;
+                    }
+                }
+            });
+            PoloniexMarketDataStream::new(normalizer, symbols, tx, outbound)
+        })
+        .await?;
+        Ok(channel_stream(rx))
+    }
+
+    async fn fetch_orderbook(&self, symbol: &Symbol, depth: Option<u32>) -> Result<OrderBook> {
+        self.get_orderbook(symbol, depth).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[tokio::test]
+    async fn test_poloniex_adapter_creation() {
+        let adapter = PoloniexAdapter::new();
+        assert_eq!(adapter.venue_id(), VenueId::Poloniex);
+        assert!(!adapter.is_connected().await);
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_register_with_normalizer() {
+        let mut adapter = PoloniexAdapter::new();
+        adapter.subscribe_orderbook(&Symbol::new("TRX", "USDD"), None).await.unwrap();
+
+        // USDD is not a quote the generic parser knows without a separator
+        assert_eq!(adapter.normalizer().normalize_symbol("TRX_USDD").unwrap(), Symbol::new("TRX", "USDD"));
+        assert_eq!(adapter.market_name(&Symbol::new("TRX", "USDD")), "TRX_USDD");
+        assert_eq!(adapter.market_name(&Symbol::new("eth", "usdt")), "ETH_USDT");
+    }
+
+    #[test]
+    fn test_flat_levels() {
+        let response = serde_json::json!({
+            "time": 1692080077892u64,
+            "scale": "0.01",
+            "asks": ["29421.55", "0.162", "29421.56", "0.048"],
+            "bids": ["29421.54", "0.5", "29421.53"],
+            "ts": 1692080077896u64
+        });
+        let mut book = OrderBook::new(Symbol::new("BTC", "USDT"));
+        apply_flat_levels(&mut book, &response["bids"], Side::Bid);
+        apply_flat_levels(&mut book, &response["asks"], Side::Ask);
+
+        // The dangling price without a quantity is ignored
+        assert_eq!(book.bids.len(), 1);
+        assert_eq!(book.asks.len(), 2);
+        assert_eq!(book.best_ask().unwrap().price, Decimal::from_str("29421.55").unwrap());
+        assert_eq!(book.best_ask().unwrap().quantity, Decimal::from_str("0.162").unwrap());
+    }
+}
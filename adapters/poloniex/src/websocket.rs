@@ -0,0 +1,295 @@
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use crate::{poloniex_symbol, value_to_decimal};
+
+pub const POLONIEX_WS_URL: &str = "wss://ws.poloniex.com/ws/public";
+
+/// One message subscribes every symbol to both channels.
+pub fn subscribe_message(symbols: &[Symbol]) -> String {
+    let names: Vec<String> = symbols.iter().map(poloniex_symbol).collect();
+    serde_json::json!({
+        "event": "subscribe",
+        "channel": ["book_lv2", "trades"],
+        "symbols": names,
+    })
+    .to_string()
+}
+
+/// Applies `[price, quantity]` pairs; a zero quantity removes the level.
+fn apply_levels(book: &mut OrderBook, levels: &Value, side: Side) {
+    for level in levels.as_array().into_iter().flatten() {
+        if let (Some(price), Some(quantity)) = (value_to_decimal(&level[0]), value_to_decimal(&level[1])) {
+            match side {
+                Side::Bid => book.update_bid(price, quantity),
+                Side::Ask => book.update_ask(price, quantity),
+            }
+        }
+    }
+}
+
+/// Handles Poloniex v3 public `book_lv2` and `trades` channels.
+///
+/// `book_lv2` sends a snapshot followed by updates. Each update's `lastId`
+/// is the `id` of the one before it, so a mismatch means an update was
+/// missed; the book is dropped and the channel resubscribed to get a fresh
+/// snapshot. Market names are mapped back to symbols by the normalizer.
+pub struct PoloniexMarketDataStream {
+    normalizer: DefaultSymbolNormalizer,
+    symbols: Vec<Symbol>,
+    books: HashMap<Symbol, OrderBook>,
+    update_tx: mpsc::UnboundedSender<MarketData>,
+    outbound_tx: mpsc::UnboundedSender<String>,
+}
+
+impl PoloniexMarketDataStream {
+    pub fn new(
+        normalizer: DefaultSymbolNormalizer,
+        symbols: Vec<Symbol>,
+        update_tx: mpsc::UnboundedSender<MarketData>,
+        outbound_tx: mpsc::UnboundedSender<String>,
+    ) -> Self {
+        Self {
+            normalizer,
+            symbols,
+            books: HashMap::new(),
+            update_tx,
+            outbound_tx,
+        }
+    }
+
+    pub fn get_orderbook(&self, symbol: &Symbol) -> Option<&OrderBook> {
+        self.books.get(symbol)
+    }
+
+    fn resubscribe_book(&self, symbol: &Symbol) {
+        for event in ["unsubscribe", "subscribe"] {
+            let _ = self.outbound_tx.send(
+                serde_json::json!({
+                    "event": event,
+                    "channel": ["book_lv2"],
+                    "symbols": [poloniex_symbol(symbol)],
+                })
+                .to_string(),
+            );
+        }
+    }
+
+    fn process_book(&mut self, symbol: Symbol, action: &str, entry: &Value) {
+        let book = match action {
+            "snapshot" => {
+                let book = self.books.entry(symbol.clone()).or_insert_with(|| OrderBook::new(symbol.clone()));
+                *book = OrderBook::new(symbol.clone());
+                book
+            }
+            _ => match self.books.get_mut(&symbol) {
+                Some(book) => book,
+                None => {
+                    debug!("Poloniex update for {} before snapshot", symbol);
+                    return;
+                }
+            },
+        };
+
+        if action != "snapshot" && entry["lastId"].as_u64() != book.sequence {
+            warn!(
+                "Poloniex sequence gap for {}: expected {:?}, got {}; resubscribing",
+                symbol, book.sequence, entry["lastId"]
+            );
+            self.books.remove(&symbol);
+            self.resubscribe_book(&symbol);
+            return;
+        }
+
+        apply_levels(book, &entry["bids"], Side::Bid);
+        apply_levels(book, &entry["asks"], Side::Ask);
+        if let Some(ts) = entry["ts"].as_i64().and_then(DateTime::<Utc>::from_timestamp_millis) {
+            book.timestamp = ts;
+        }
+        book.sequence = entry["id"].as_u64();
+
+        let _ = self.update_tx.send(MarketData::OrderBook(book.clone()));
+    }
+
+    fn process_trade(&self, symbol: Symbol, entry: &Value) {
+        let (Some(price), Some(quantity)) = (value_to_decimal(&entry["price"]), value_to_decimal(&entry["quantity"])) else {
+            return;
+        };
+
+        // takerSide is the aggressor: a buy lifts the ask
+        let side = match entry["takerSide"].as_str().map(|side| self.normalizer.normalize_side(side)) {
+            Some(Ok(OrderSide::Buy)) => Side::Bid,
+            _ => Side::Ask,
+        };
+        let trade_id = match &entry["id"] {
+            Value::String(id) => id.clone(),
+            other => other.to_string(),
+        };
+
+        let mut trade = Trade::new(symbol, price, quantity, side, trade_id);
+        if let Some(ts) = entry["createTime"].as_i64().and_then(DateTime::<Utc>::from_timestamp_millis) {
+            trade.timestamp = ts;
+        }
+
+        let _ = self.update_tx.send(MarketData::Trade(trade));
+    }
+}
+
+#[async_trait]
+impl WebSocketHandler for PoloniexMarketDataStream {
+    async fn on_message(&mut self, message: &str) -> Result<()> {
+        let data: Value = serde_json::from_str(message)?;
+        match data["event"].as_str() {
+            Some("error") => {
+                warn!("Poloniex WebSocket error: {}", data["message"]);
+                return Ok(());
+            }
+            Some(event) => {
+                debug!("Poloniex {} event: {}", event, data["channel"]);
+                return Ok(());
+            }
+            None => {}
+        }
+
+        let action = data["action"].as_str().unwrap_or("update").to_string();
+        let channel = data["channel"].as_str().unwrap_or_default().to_string();
+        for entry in data["data"].as_array().into_iter().flatten() {
+            let Some(symbol) = entry["symbol"].as_str().and_then(|s| self.normalizer.normalize_symbol(s).ok()) else {
+                debug!("Ignoring Poloniex entry: {}", entry);
+                continue;
+            };
+            match channel.as_str() {
+                "book_lv2" => self.process_book(symbol, &action, entry),
+                "trades" => self.process_trade(symbol, entry),
+                _ => debug!("Ignoring Poloniex message: {}", message),
+            }
+        }
+        Ok(())
+    }
+
+    async fn on_connect(&mut self) -> Result<()> {
+        info!("Poloniex WebSocket connected for {} symbols", self.symbols.len());
+        Ok(())
+    }
+
+    async fn on_disconnect(&mut self) -> Result<()> {
+        warn!("Poloniex WebSocket disconnected");
+        self.books.clear();
+        Ok(())
+    }
+
+    async fn on_error(&mut self, error: &ArbFinderError) -> Result<()> {
+        error!("Poloniex WebSocket error: {}", error);
+        Ok(())
+    }
+
+    async fn on_ping(&mut self) -> Result<()> {
+        debug!("Received Poloniex ping");
+        Ok(())
+    }
+
+    async fn on_pong(&mut self) -> Result<()> {
+        debug!("Received Poloniex pong");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    type Channels = (
+        PoloniexMarketDataStream,
+        mpsc::UnboundedReceiver<MarketData>,
+        mpsc::UnboundedReceiver<String>,
+    );
+
+    fn stream() -> Channels {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (out_tx, out_rx) = mpsc::unbounded_channel();
+        let mut normalizer = DefaultSymbolNormalizer::new();
+        normalizer.add_symbol_mapping("BTC_USDT".to_string(), Symbol::new("BTC", "USDT"));
+        (
+            PoloniexMarketDataStream::new(normalizer, vec![Symbol::new("BTC", "USDT")], tx, out_tx),
+            rx,
+            out_rx,
+        )
+    }
+
+    fn book_message(action: &str, bids: &str, asks: &str, last_id: u64, id: u64) -> String {
+        format!(
+            r#"{{"channel":"book_lv2","action":"{}","data":[{{"symbol":"BTC_USDT","createTime":1692080077892,"asks":{},"bids":{},"lastId":{},"id":{},"ts":1692080077896}}]}}"#,
+            action, asks, bids, last_id, id
+        )
+    }
+
+    #[test]
+    fn test_subscribe_message() {
+        let message: Value = serde_json::from_str(&subscribe_message(&[Symbol::new("ETH", "USDT")])).unwrap();
+        assert_eq!(message["event"], "subscribe");
+        assert_eq!(message["channel"][0], "book_lv2");
+        assert_eq!(message["symbols"][0], "ETH_USDT");
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_update_and_gap() {
+        let (mut stream, mut rx, mut out_rx) = stream();
+        let symbol = Symbol::new("BTC", "USDT");
+
+        stream
+            .on_message(&book_message("snapshot", r#"[["29421.54","0.5"]]"#, r#"[["29421.55","0.162"]]"#, 0, 10))
+            .await
+            .unwrap();
+        stream
+            .on_message(&book_message("update", r#"[["29421.54","0"],["29421.50","1.2"]]"#, "[]", 10, 11))
+            .await
+            .unwrap();
+        rx.recv().await.unwrap();
+        match rx.recv().await.unwrap() {
+            MarketData::OrderBook(book) => {
+                assert_eq!(book.best_bid().unwrap().price, Decimal::from_str("29421.50").unwrap());
+                assert_eq!(book.sequence, Some(11));
+            }
+            other => panic!("expected order book, got {:?}", other),
+        }
+
+        // Update 12 went missing
+        stream
+            .on_message(&book_message("update", "[]", r#"[["29421.60","1"]]"#, 12, 13))
+            .await
+            .unwrap();
+        assert!(stream.get_orderbook(&symbol).is_none());
+        let unsubscribe: Value = serde_json::from_str(&out_rx.recv().await.unwrap()).unwrap();
+        assert_eq!(unsubscribe["event"], "unsubscribe");
+        assert_eq!(unsubscribe["symbols"][0], "BTC_USDT");
+        assert!(out_rx.recv().await.unwrap().contains("\"subscribe\""));
+    }
+
+    #[tokio::test]
+    async fn test_trade_message() {
+        let (mut stream, mut rx, _out_rx) = stream();
+        stream
+            .on_message(r#"{"channel":"trades","data":[{"symbol":"BTC_USDT","amount":"70.11","takerSide":"buy","quantity":"0.002383","createTime":1692080077892,"price":"29421.55","id":"60100220","ts":1692080077896}]}"#)
+            .await
+            .unwrap();
+
+        match rx.recv().await.unwrap() {
+            MarketData::Trade(trade) => {
+                assert_eq!(trade.symbol, Symbol::new("BTC", "USDT"));
+                assert_eq!(trade.side, Side::Bid);
+                assert_eq!(trade.quantity, Decimal::from_str("0.002383").unwrap());
+                assert_eq!(trade.trade_id, "60100220");
+                assert_eq!(trade.timestamp.timestamp_millis(), 1692080077892);
+            }
+            other => panic!("expected trade, got {:?}", other),
+        }
+    }
+}
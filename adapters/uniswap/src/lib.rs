@@ -0,0 +1,384 @@
+//! Uniswap V3 Adapter
+//!
+//! Reads Uniswap V3 pool state from an Ethereum JSON-RPC endpoint and
+//! presents each pool as an order book synthesized from its tick liquidity
+//! (see [`pool`]), so on-chain prices feed the same detection as exchange
+//! books.
+//!
+//! Pools are configured by address with [`UniswapV3Adapter::with_pool`] and
+//! resolved on `connect`. Book prices exclude the pool's swap fee, which is
+//! reported as the trading fee. Gas is a fixed cost per swap rather than a
+//! rate; [`UniswapV3Adapter::gas_cost`] prices it in the pool's quote
+//! currency for `CrossExchangeArbitrageDetector::set_fixed_cost`.
+
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use arbfinder_exchange::channel_stream;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::future::try_join_all;
+use rust_decimal::Decimal;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+pub mod pool;
+pub mod rpc;
+
+pub use pool::{PoolInfo, PoolState};
+use rpc::RpcClient;
+
+const DEFAULT_BOOK_LEVELS: usize = 20;
+
+/// Roughly one block.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(12);
+
+/// Gas for a single-pool swap through the router, with room for crossing a
+/// few initialized ticks.
+pub const SWAP_GAS_UNITS: u64 = 180_000;
+
+const WEI_PER_ETH: u128 = 1_000_000_000_000_000_000;
+
+/// Wrapped tokens trade as the asset they wrap.
+fn canonical_asset(token_symbol: &str) -> String {
+    match token_symbol.to_uppercase().as_str() {
+        "WETH" => "ETH".to_string(),
+        "WBTC" => "BTC".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Reads a pool's tokens, fee and tick spacing, and works out which token
+/// is the symbol's base.
+async fn load_pool(rpc: &RpcClient, symbol: &Symbol, address: &str) -> Result<PoolInfo> {
+    let token0 = rpc::word_to_address(rpc::word(&rpc.view(address, rpc::TOKEN0, &[]).await?, 0)?);
+    let token1 = rpc::word_to_address(rpc::word(&rpc.view(address, rpc::TOKEN1, &[]).await?, 0)?);
+    let fee = rpc::word_to_i64(rpc::word(&rpc.view(address, rpc::FEE, &[]).await?, 0)?);
+    let tick_spacing = rpc::word_to_i64(rpc::word(&rpc.view(address, rpc::TICK_SPACING, &[]).await?, 0)?);
+
+    let mut decimals = Vec::with_capacity(2);
+    let mut assets = Vec::with_capacity(2);
+    for token in [&token0, &token1] {
+        decimals.push(rpc::word_to_i64(rpc::word(&rpc.view(token, rpc::DECIMALS, &[]).await?, 0)?) as u8);
+        assets.push(canonical_asset(&rpc::decode_string(&rpc.view(token, rpc::SYMBOL, &[]).await?)?));
+    }
+
+    let base = canonical_asset(symbol.base());
+    let quote = canonical_asset(symbol.quote());
+    let base_is_token0 = if assets[0] == base && assets[1] == quote {
+        true
+    } else if assets[1] == base && assets[0] == quote {
+        false
+    } else {
+        return Err(ArbFinderError::InvalidData(format!(
+            "Pool {} holds {}/{}, not {}",
+            address, assets[0], assets[1], symbol
+        )));
+    };
+
+    Ok(PoolInfo {
+        symbol: symbol.clone(),
+        address: address.to_string(),
+        token0,
+        token1,
+        decimals0: decimals[0],
+        decimals1: decimals[1],
+        base_is_token0,
+        fee: fee as u32,
+        tick_spacing: tick_spacing as i32,
+    })
+}
+
+/// Reads the price, in-range liquidity and the initialized ticks within
+/// `levels` tick spacings of the price.
+async fn fetch_state(rpc: &RpcClient, pool: &PoolInfo, levels: usize) -> Result<PoolState> {
+    let slot0 = rpc.view(&pool.address, rpc::SLOT0, &[]).await?;
+    let sqrt_price = rpc::word_to_f64(rpc::word(&slot0, 0)?) / 2f64.powi(96);
+    let tick = rpc::word_to_i64(rpc::word(&slot0, 1)?) as i32;
+    let liquidity = rpc::word_to_f64(rpc::word(&rpc.view(&pool.address, rpc::LIQUIDITY, &[]).await?, 0)?);
+
+    // Each bitmap word flags 256 consecutive multiples of the tick spacing
+    let spacing = pool.tick_spacing;
+    let compressed = tick.div_euclid(spacing);
+    let span = levels as i32 + 1;
+    let (low, high) = (compressed - span, compressed + span);
+    let mut initialized = Vec::new();
+    for word_index in (low >> 8)..=(high >> 8) {
+        let bitmap = rpc.view(&pool.address, rpc::TICK_BITMAP, &[i64::from(word_index)]).await?;
+        let bitmap = rpc::word(&bitmap, 0)?;
+        for bit in 0..=255u8 {
+            let position = (word_index << 8) + i32::from(bit);
+            if rpc::bit_is_set(bitmap, bit) && (low..=high).contains(&position) {
+                initialized.push(position * spacing);
+            }
+        }
+    }
+
+    let nets = try_join_all(initialized.iter().map(|tick| async move {
+        let info = rpc.view(&pool.address, rpc::TICKS, &[i64::from(*tick)]).await?;
+        Ok::<_, ArbFinderError>((*tick, rpc::word_to_signed_f64(rpc::word(&info, 1)?)))
+    }))
+    .await?;
+
+    Ok(PoolState {
+        sqrt_price,
+        tick,
+        liquidity,
+        liquidity_net: nets.into_iter().collect::<BTreeMap<_, _>>(),
+    })
+}
+
+pub struct UniswapV3Adapter {
+    rpc: RpcClient,
+    pool_addresses: HashMap<Symbol, String>,
+    pools: HashMap<Symbol, PoolInfo>,
+    levels: usize,
+    poll_interval: Duration,
+    /// ETH price in quote currency, for pricing gas on pools without ETH.
+    native_price: Option<Decimal>,
+    connected: bool,
+    subscriptions: Vec<Symbol>,
+}
+
+impl UniswapV3Adapter {
+    pub fn new(rpc_url: impl Into<String>) -> Self {
+        Self {
+            rpc: RpcClient::new(rpc_url),
+            pool_addresses: HashMap::new(),
+            pools: HashMap::new(),
+            levels: DEFAULT_BOOK_LEVELS,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            native_price: None,
+            connected: false,
+            subscriptions: Vec::new(),
+        }
+    }
+
+    /// Trades `symbol` on the pool at `address`. The pool must hold the
+    /// symbol's two assets; WETH and WBTC stand in for ETH and BTC.
+    pub fn with_pool(mut self, symbol: Symbol, address: impl Into<String>) -> Self {
+        self.pool_addresses.insert(symbol, address.into().to_lowercase());
+        self
+    }
+
+    /// Tick spacings of depth per side in synthesized books.
+    pub fn with_levels(mut self, levels: usize) -> Self {
+        self.levels = levels;
+        self
+    }
+
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    pub fn set_native_price(&mut self, price: Decimal) {
+        self.native_price = Some(price);
+    }
+
+    pub fn pool(&self, symbol: &Symbol) -> Result<&PoolInfo> {
+        self.pools.get(symbol).ok_or_else(|| {
+            ArbFinderError::SymbolNotFound(format!("No resolved Uniswap pool for {}", symbol))
+        })
+    }
+
+    pub async fn get_pool_state(&self, symbol: &Symbol) -> Result<PoolState> {
+        fetch_state(&self.rpc, self.pool(symbol)?, self.levels).await
+    }
+
+    /// Book synthesized from the pool's current tick liquidity.
+    pub async fn get_orderbook(&self, symbol: &Symbol) -> Result<OrderBook> {
+        let pool = self.pool(symbol)?;
+        let state = fetch_state(&self.rpc, pool, self.levels).await?;
+        Ok(state.to_orderbook(pool, self.levels))
+    }
+
+    /// Current gas price in wei.
+    pub async fn gas_price(&self) -> Result<u128> {
+        self.rpc.quantity("eth_gasPrice", serde_json::json!([])).await
+    }
+
+    /// Expected gas cost of one swap on the pool, in `symbol`'s quote
+    /// currency.
+    pub async fn gas_cost(&self, symbol: &Symbol) -> Result<Decimal> {
+        let wei = u128::from(SWAP_GAS_UNITS) * self.gas_price().await?;
+        let eth = Decimal::from_i128_with_scale(wei as i128, 0) / Decimal::from_i128_with_scale(WEI_PER_ETH as i128, 0);
+
+        let native_price = if canonical_asset(symbol.quote()) == "ETH" {
+            Decimal::ONE
+        } else if canonical_asset(symbol.base()) == "ETH" {
+            self.get_orderbook(symbol).await?.mid_price().ok_or_else(|| {
+                ArbFinderError::InvalidData(format!("No liquidity in {} pool", symbol))
+            })?
+        } else {
+            self.native_price.ok_or_else(|| {
+                ArbFinderError::InvalidData(format!(
+                    "ETH price in {} is needed to price gas; call set_native_price",
+                    symbol.quote()
+                ))
+            })?
+        };
+
+        Ok(eth * native_price)
+    }
+}
+
+#[async_trait]
+impl MarketDataAdapter for UniswapV3Adapter {
+    fn venue_id(&self) -> VenueId {
+        VenueId::Uniswap
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        let chain_id = self.rpc.quantity("eth_chainId", serde_json::json!([])).await?;
+        for (symbol, address) in &self.pool_addresses {
+            let pool = load_pool(&self.rpc, symbol, address).await?;
+            debug!("Resolved Uniswap pool {} for {}: {:?}", address, symbol, pool);
+            self.pools.insert(symbol.clone(), pool);
+        }
+        info!("Uniswap V3 connected to chain {} with {} pools", chain_id, self.pools.len());
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.connected = false;
+        Ok(())
+    }
+
+    async fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        let block = self
+            .rpc
+            .call("eth_getBlockByNumber", serde_json::json!(["latest", false]))
+            .await?;
+        let timestamp = rpc::parse_quantity(&block["timestamp"])?;
+        DateTime::<Utc>::from_timestamp(timestamp as i64, 0)
+            .ok_or_else(|| ArbFinderError::InvalidData("Invalid block timestamp".to_string()))
+    }
+
+    async fn ping(&self) -> Result<u64> {
+        let start = std::time::Instant::now();
+        let _ = self.rpc.call("eth_blockNumber", serde_json::json!([])).await?;
+        Ok(start.elapsed().as_millis() as u64)
+    }
+
+    async fn get_symbols(&self) -> Result<Vec<Symbol>> {
+        Ok(self.pool_addresses.keys().cloned().collect())
+    }
+
+    async fn get_symbol_info(&self, symbol: &Symbol) -> Result<SymbolInfo> {
+        let pool = self.pool(symbol)?;
+        let base_decimals = if pool.base_is_token0 { pool.decimals0 } else { pool.decimals1 };
+        let lot_size = Decimal::new(1, u32::from(base_decimals.min(18)));
+
+        Ok(SymbolInfo {
+            symbol: symbol.clone(),
+            status: "open".to_string(),
+            base_asset_precision: u32::from(base_decimals),
+            quote_asset_precision: u32::from(if pool.base_is_token0 { pool.decimals1 } else { pool.decimals0 }),
+            tick_size: Decimal::new(1, 12),
+            lot_size,
+            min_order_size: lot_size,
+            max_order_size: Decimal::new(1000000, 0),
+            min_notional: Decimal::ZERO,
+            trading_fees: TradingFees {
+                maker_fee: pool.fee_rate(),
+                taker_fee: pool.fee_rate(),
+            },
+        })
+    }
+
+    async fn subscribe_orderbook(&mut self, symbol: &Symbol, _depth: Option<u32>) -> Result<()> {
+        if !self.pool_addresses.contains_key(symbol) {
+            return Err(ArbFinderError::SymbolNotFound(format!("No Uniswap pool configured for {}", symbol)));
+        }
+        if !self.subscriptions.contains(symbol) {
+            self.subscriptions.push(symbol.clone());
+        }
+        Ok(())
+    }
+
+    async fn subscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+        Err(ArbFinderError::Exchange("Uniswap swap feed not implemented yet".to_string()))
+    }
+
+    async fn subscribe_ticker(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscribe_orderbook(symbol, None).await
+    }
+
+    async fn unsubscribe_orderbook(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscriptions.retain(|s| s != symbol);
+        Ok(())
+    }
+
+    async fn unsubscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    /// Polls the subscribed pools every poll interval; there is no push feed
+    /// for pool state.
+    async fn market_data_stream(&self) -> Result<MarketDataStream> {
+        if self.subscriptions.is_empty() {
+            return Err(ArbFinderError::Exchange("No Uniswap pools subscribed".to_string()));
+        }
+        let pools: Vec<PoolInfo> = self
+            .subscriptions
+            .iter()
+            .map(|symbol| self.pool(symbol).cloned())
+            .collect::<Result<_>>()?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let rpc = self.rpc.clone();
+        let levels = self.levels;
+        let mut interval = tokio::time::interval(self.poll_interval);
+        tokio::spawn(async move {
+            loop {
+                interval.tick().await;
+                for pool in &pools {
+                    match fetch_state(&rpc, pool, levels).await {
+                        Ok(state) => {
+                            let book = state.to_orderbook(pool, levels);
+                            if tx.send(MarketData::OrderBook(book)).is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => warn!("Failed to read Uniswap pool {}: {}", pool.address, e),
+                    }
+                }
+            }
+        });
+        Ok(channel_stream(rx))
+    }
+
+    async fn fetch_orderbook(&self, symbol: &Symbol, _depth: Option<u32>) -> Result<OrderBook> {
+        self.get_orderbook(symbol).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_uniswap_adapter_creation() {
+        let mut adapter = UniswapV3Adapter::new("http://localhost:8545")
+            .with_pool(Symbol::new("ETH", "USDC"), "0x88E6A0c2dDD26FEEb64F039a2c41296FcB3f5640");
+        assert_eq!(adapter.venue_id(), VenueId::Uniswap);
+        assert!(!adapter.is_connected().await);
+        assert_eq!(adapter.get_symbols().await.unwrap(), vec![Symbol::new("ETH", "USDC")]);
+
+        // Unresolved until connect
+        assert!(adapter.pool(&Symbol::new("ETH", "USDC")).is_err());
+        assert!(adapter.subscribe_orderbook(&Symbol::new("BTC", "USDC"), None).await.is_err());
+        assert_eq!(canonical_asset("weth"), "ETH");
+    }
+}
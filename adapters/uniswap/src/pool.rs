@@ -0,0 +1,233 @@
+//! Uniswap V3 pool math.
+//!
+//! A pool holds concentrated liquidity between initialized ticks. Within a
+//! range of constant liquidity `L`, moving the price from `√a` to `√b`
+//! exchanges `L·(1/√a − 1/√b)` of token0 for `L·(√b − √a)` of token1, at an
+//! average price of `√a·√b`. Walking those ranges outward from the current
+//! price gives the depth a swap would meet, which is laid out here as book
+//! levels, one per tick spacing.
+//!
+//! Amounts are large and only read to present depth, so the math is done in
+//! `f64`.
+
+use std::collections::BTreeMap;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+
+use arbfinder_core::prelude::*;
+
+/// Significant digits kept when converting prices and sizes to decimals.
+const SIGNIFICANT_DIGITS: u32 = 12;
+
+/// `√(1.0001^tick)`.
+pub fn sqrt_price_at_tick(tick: i32) -> f64 {
+    (f64::from(tick) * 1.0001f64.ln() / 2.0).exp()
+}
+
+/// Static facts about a pool, read once from the chain.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolInfo {
+    pub symbol: Symbol,
+    pub address: String,
+    pub token0: String,
+    pub token1: String,
+    pub decimals0: u8,
+    pub decimals1: u8,
+    /// Whether the symbol's base is token0; otherwise it is token1.
+    pub base_is_token0: bool,
+    /// Swap fee in hundredths of a basis point (3000 = 0.3%).
+    pub fee: u32,
+    pub tick_spacing: i32,
+}
+
+impl PoolInfo {
+    pub fn fee_rate(&self) -> Decimal {
+        Decimal::new(i64::from(self.fee), 6)
+    }
+}
+
+/// Pool state at a block: the price, the liquidity in range, and the net
+/// liquidity change at each initialized tick near the price.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PoolState {
+    /// `sqrtPriceX96 / 2^96`, the square root of the raw token1/token0 price.
+    pub sqrt_price: f64,
+    pub tick: i32,
+    pub liquidity: f64,
+    /// Liquidity added when the price crosses the tick upward, removed when
+    /// it crosses downward.
+    pub liquidity_net: BTreeMap<i32, f64>,
+}
+
+/// One range walked from the current price: its average raw price
+/// (token1/token0) and the raw amounts of each token it holds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Band {
+    price: f64,
+    amount0: f64,
+    amount1: f64,
+}
+
+impl PoolState {
+    /// Ranges above the current price, where token0 leaves the pool.
+    fn bands_up(&self, tick_spacing: i32, count: usize) -> Vec<Band> {
+        let mut bands = Vec::with_capacity(count);
+        let mut liquidity = self.liquidity;
+        let mut lower = self.sqrt_price;
+        let mut boundary = self.tick.div_euclid(tick_spacing) * tick_spacing + tick_spacing;
+        while bands.len() < count && liquidity > 0.0 {
+            let upper = sqrt_price_at_tick(boundary);
+            if upper > lower {
+                bands.push(Band {
+                    price: lower * upper,
+                    amount0: liquidity * (1.0 / lower - 1.0 / upper),
+                    amount1: liquidity * (upper - lower),
+                });
+            }
+            liquidity += self.liquidity_net.get(&boundary).copied().unwrap_or(0.0);
+            lower = upper;
+            boundary += tick_spacing;
+        }
+        bands
+    }
+
+    /// Ranges below the current price, where token1 leaves the pool.
+    fn bands_down(&self, tick_spacing: i32, count: usize) -> Vec<Band> {
+        let mut bands = Vec::with_capacity(count);
+        let mut liquidity = self.liquidity;
+        let mut upper = self.sqrt_price;
+        let mut boundary = self.tick.div_euclid(tick_spacing) * tick_spacing;
+        while bands.len() < count && liquidity > 0.0 {
+            let lower = sqrt_price_at_tick(boundary);
+            if upper > lower {
+                bands.push(Band {
+                    price: lower * upper,
+                    amount0: liquidity * (1.0 / lower - 1.0 / upper),
+                    amount1: liquidity * (upper - lower),
+                });
+            }
+            liquidity -= self.liquidity_net.get(&boundary).copied().unwrap_or(0.0);
+            upper = lower;
+            boundary -= tick_spacing;
+        }
+        bands
+    }
+
+    /// Book of `levels` levels per side in the symbol's terms. Prices are
+    /// the pool's before its swap fee; see [`PoolInfo::fee_rate`].
+    pub fn to_orderbook(&self, pool: &PoolInfo, levels: usize) -> OrderBook {
+        let scale0 = 10f64.powi(i32::from(pool.decimals0));
+        let scale1 = 10f64.powi(i32::from(pool.decimals1));
+        let mut book = OrderBook::new(pool.symbol.clone());
+
+        let up = self.bands_up(pool.tick_spacing, levels);
+        let down = self.bands_down(pool.tick_spacing, levels);
+        // Buying the base takes it out of the pool
+        let (asks, bids) = if pool.base_is_token0 { (up, down) } else { (down, up) };
+
+        for (bands, is_bid) in [(bids, true), (asks, false)] {
+            for band in bands {
+                // token1 per token0, in whole tokens
+                let price01 = band.price * scale0 / scale1;
+                let (price, quantity) = if pool.base_is_token0 {
+                    (price01, band.amount0 / scale0)
+                } else {
+                    (1.0 / price01, band.amount1 / scale1)
+                };
+                let (Some(price), Some(quantity)) = (to_decimal(price), to_decimal(quantity)) else {
+                    continue;
+                };
+                if quantity.is_zero() {
+                    continue;
+                }
+                if is_bid {
+                    book.update_bid(price, quantity);
+                } else {
+                    book.update_ask(price, quantity);
+                }
+            }
+        }
+        book
+    }
+}
+
+fn to_decimal(value: f64) -> Option<Decimal> {
+    if !value.is_finite() {
+        return None;
+    }
+    Decimal::from_f64(value).and_then(|d| d.round_sf(SIGNIFICANT_DIGITS))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(base_is_token0: bool) -> PoolInfo {
+        PoolInfo {
+            symbol: Symbol::new("ETH", "USDC"),
+            address: "0x88e6a0c2ddd26feeb64f039a2c41296fcb3f5640".to_string(),
+            token0: "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48".to_string(),
+            token1: "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2".to_string(),
+            decimals0: 18,
+            decimals1: 18,
+            base_is_token0,
+            fee: 3000,
+            tick_spacing: 60,
+        }
+    }
+
+    /// Price 1.0 at tick 0, with liquidity only between ticks -120 and 120.
+    fn state() -> PoolState {
+        PoolState {
+            sqrt_price: 1.0,
+            tick: 0,
+            liquidity: 1e21,
+            liquidity_net: BTreeMap::from([(-120, 1e21), (120, -1e21)]),
+        }
+    }
+
+    #[test]
+    fn test_book_from_tick_liquidity() {
+        let book = state().to_orderbook(&pool(true), 10);
+
+        // Two spacings of liquidity each side, then nothing
+        assert_eq!(book.asks.len(), 2);
+        assert_eq!(book.bids.len(), 2);
+
+        let ask = book.best_ask().unwrap();
+        let bid = book.best_bid().unwrap();
+        assert!(ask.price > Decimal::ONE && bid.price < Decimal::ONE);
+        // 1e21 · (1 − 1/√1.0001^60) in 18-decimal units is about 2.995 tokens
+        let expected = 1e3 * (1.0 - 1.0 / sqrt_price_at_tick(60));
+        assert!((ask.quantity.to_string().parse::<f64>().unwrap() - expected).abs() < 1e-6);
+        assert_eq!(pool(true).fee_rate(), Decimal::new(3, 3));
+    }
+
+    #[test]
+    fn test_base_as_token1_inverts_prices() {
+        let mut state = state();
+        // Raw price 4: token1 is worth a quarter of token0
+        state.sqrt_price = 2.0;
+        state.tick = 13863;
+        state.liquidity_net = BTreeMap::new();
+
+        let book = state.to_orderbook(&pool(false), 3);
+        let ask = book.best_ask().unwrap().price;
+        let bid = book.best_bid().unwrap().price;
+        assert!(bid < ask);
+        assert!(ask > Decimal::new(249, 3) && ask < Decimal::new(251, 3));
+        assert_eq!(book.asks.len(), 3);
+    }
+
+    #[test]
+    fn test_ranges_stop_where_liquidity_ends() {
+        let mut state = state();
+        state.tick = 119;
+        state.sqrt_price = sqrt_price_at_tick(119);
+
+        let book = state.to_orderbook(&pool(true), 10);
+        // Only the sliver up to tick 120 is left above the price
+        assert_eq!(book.asks.len(), 1);
+        assert_eq!(book.bids.len(), 4);
+    }
+}
@@ -0,0 +1,203 @@
+//! Minimal Ethereum JSON-RPC client and ABI helpers for the handful of view
+//! calls the adapter makes. Every argument and return value used is a single
+//! 32-byte word, apart from ERC-20 `symbol()` strings.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use reqwest::Client;
+use serde_json::Value;
+
+use arbfinder_core::prelude::*;
+
+// Function selectors: the first four bytes of the keccak-256 hash of the
+// signature.
+pub const SLOT0: [u8; 4] = [0x38, 0x50, 0xc7, 0xbd]; // slot0()
+pub const LIQUIDITY: [u8; 4] = [0x1a, 0x68, 0x65, 0x02]; // liquidity()
+pub const FEE: [u8; 4] = [0xdd, 0xca, 0x3f, 0x43]; // fee()
+pub const TICK_SPACING: [u8; 4] = [0xd0, 0xc9, 0x3a, 0x7c]; // tickSpacing()
+pub const TOKEN0: [u8; 4] = [0x0d, 0xfe, 0x16, 0x81]; // token0()
+pub const TOKEN1: [u8; 4] = [0xd2, 0x12, 0x20, 0xa7]; // token1()
+pub const TICKS: [u8; 4] = [0xf3, 0x0d, 0xba, 0x93]; // ticks(int24)
+pub const TICK_BITMAP: [u8; 4] = [0x53, 0x39, 0xc2, 0x96]; // tickBitmap(int16)
+pub const DECIMALS: [u8; 4] = [0x31, 0x3c, 0xe5, 0x67]; // decimals()
+pub const SYMBOL: [u8; 4] = [0x95, 0xd8, 0x9b, 0x41]; // symbol()
+
+/// Calldata for `selector` with signed integer arguments, each sign-extended
+/// to a full word.
+pub fn encode_call(selector: [u8; 4], args: &[i64]) -> String {
+    let mut data = selector.to_vec();
+    for arg in args {
+        let fill = if *arg < 0 { 0xff } else { 0x00 };
+        data.extend([fill; 24]);
+        data.extend(arg.to_be_bytes());
+    }
+    format!("0x{}", hex::encode(data))
+}
+
+pub fn decode_hex(data: &str) -> Result<Vec<u8>> {
+    hex::decode(data.trim_start_matches("0x"))
+        .map_err(|e| ArbFinderError::Parse(format!("Invalid hex from RPC: {}", e)))
+}
+
+/// The `index`th 32-byte word of ABI-encoded return data.
+pub fn word(data: &[u8], index: usize) -> Result<&[u8]> {
+    data.get(index * 32..(index + 1) * 32)
+        .ok_or_else(|| ArbFinderError::InvalidData(format!("Return data has no word {}", index)))
+}
+
+pub fn word_to_f64(word: &[u8]) -> f64 {
+    word.iter().fold(0.0, |acc, byte| acc * 256.0 + f64::from(*byte))
+}
+
+/// Two's-complement signed word (int24, int128, ...) as `f64`.
+pub fn word_to_signed_f64(word: &[u8]) -> f64 {
+    if word[0] & 0x80 == 0 {
+        return word_to_f64(word);
+    }
+    let inverted: Vec<u8> = word.iter().map(|byte| !byte).collect();
+    -(word_to_f64(&inverted) + 1.0)
+}
+
+/// Signed word known to fit in an `i64`, e.g. an int24 tick.
+pub fn word_to_i64(word: &[u8]) -> i64 {
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&word[24..]);
+    i64::from_be_bytes(bytes)
+}
+
+pub fn word_to_address(word: &[u8]) -> String {
+    format!("0x{}", hex::encode(&word[12..]))
+}
+
+/// Whether bit `bit` (0 = least significant) of a uint256 word is set.
+pub fn bit_is_set(word: &[u8], bit: u8) -> bool {
+    let byte = word[31 - usize::from(bit / 8)];
+    byte & (1 << (bit % 8)) != 0
+}
+
+/// A dynamic `string` return value.
+pub fn decode_string(data: &[u8]) -> Result<String> {
+    let offset = word_to_i64(word(data, 0)?) as usize;
+    let length = data
+        .get(offset..offset + 32)
+        .map(word_to_i64)
+        .ok_or_else(|| ArbFinderError::InvalidData("Truncated string".to_string()))? as usize;
+    let bytes = data
+        .get(offset + 32..offset + 32 + length)
+        .ok_or_else(|| ArbFinderError::InvalidData("Truncated string".to_string()))?;
+    Ok(String::from_utf8_lossy(bytes).into_owned())
+}
+
+#[derive(Clone)]
+pub struct RpcClient {
+    client: Client,
+    url: String,
+    next_id: Arc<AtomicU64>,
+}
+
+impl RpcClient {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            url: url.into(),
+            next_id: Arc::new(AtomicU64::new(1)),
+        }
+    }
+
+    pub async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let request = serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params,
+        });
+        let response = self.client
+            .post(&self.url)
+            .json(&request)
+            .send()
+            .await
+            .map_err(ArbFinderError::Http)?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+            return Err(ArbFinderError::RateLimit(format!("RPC endpoint limited {}", method)));
+        }
+        let body: Value = response.json().await.map_err(ArbFinderError::Http)?;
+        if !body["error"].is_null() {
+            return Err(ArbFinderError::Exchange(format!(
+                "RPC {} failed: {}",
+                method, body["error"]["message"]
+            )));
+        }
+        if !status.is_success() {
+            return Err(ArbFinderError::Exchange(format!("RPC error: {}", status)));
+        }
+
+        Ok(body["result"].clone())
+    }
+
+    /// `eth_call` against the latest block, returning the raw return data.
+    pub async fn eth_call(&self, to: &str, data: String) -> Result<Vec<u8>> {
+        let result = self
+            .call("eth_call", serde_json::json!([{ "to": to, "data": data }, "latest"]))
+            .await?;
+        decode_hex(result.as_str().unwrap_or_default())
+    }
+
+    pub async fn view(&self, to: &str, selector: [u8; 4], args: &[i64]) -> Result<Vec<u8>> {
+        self.eth_call(to, encode_call(selector, args)).await
+    }
+
+    /// A hex quantity result (`eth_gasPrice`, `eth_chainId`, ...).
+    pub async fn quantity(&self, method: &str, params: Value) -> Result<u128> {
+        let result = self.call(method, params).await?;
+        parse_quantity(&result)
+    }
+}
+
+pub fn parse_quantity(value: &Value) -> Result<u128> {
+    let text = value
+        .as_str()
+        .ok_or_else(|| ArbFinderError::InvalidData(format!("Expected hex quantity, got {}", value)))?;
+    u128::from_str_radix(text.trim_start_matches("0x"), 16)
+        .map_err(|e| ArbFinderError::Parse(format!("Invalid quantity {}: {}", text, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_signed_arguments() {
+        let data = encode_call(TICKS, &[-60]);
+        assert!(data.starts_with("0xf30dba93ffffffff"));
+        assert!(data.ends_with("ffc4"));
+        assert_eq!(data.len(), 2 + 8 + 64);
+
+        let bytes = decode_hex(&data).unwrap();
+        assert_eq!(word_to_i64(word(&bytes[4..], 0).unwrap()), -60);
+    }
+
+    #[test]
+    fn test_decode_words() {
+        let mut negative = [0xffu8; 32];
+        negative[31] = 0x9c; // -100
+        assert_eq!(word_to_signed_f64(&negative), -100.0);
+
+        let mut bitmap = [0u8; 32];
+        bitmap[31] = 0b0000_0100;
+        bitmap[0] = 0x80;
+        assert!(bit_is_set(&bitmap, 2));
+        assert!(bit_is_set(&bitmap, 255));
+        assert!(!bit_is_set(&bitmap, 3));
+
+        // symbol() returning "WETH"
+        let encoded = decode_hex(concat!(
+            "0000000000000000000000000000000000000000000000000000000000000020",
+            "0000000000000000000000000000000000000000000000000000000000000004",
+            "5745544800000000000000000000000000000000000000000000000000000000",
+        ))
+        .unwrap();
+        assert_eq!(decode_string(&encoded).unwrap(), "WETH");
+    }
+}
@@ -0,0 +1,118 @@
+//! Upbit's v1 market codes and message formats.
+//!
+//! Bithumb's v1 API follows the same formats, so its adapter parses through
+//! this module rather than keeping a copy.
+
+use arbfinder_core::prelude::*;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::FromPrimitive;
+use serde_json::Value;
+
+/// Formats a symbol as an Upbit market code. Upbit puts the quote first:
+/// BTC/KRW is `KRW-BTC`.
+pub fn upbit_market(symbol: &Symbol) -> String {
+    format!("{}-{}", symbol.quote().to_uppercase(), symbol.base().to_uppercase())
+}
+
+/// Parses an Upbit market code such as `KRW-BTC` into a `Symbol`.
+pub fn parse_upbit_market(market: &str) -> Option<Symbol> {
+    let (quote, base) = market.split_once('-')?;
+    if base.is_empty() || quote.is_empty() {
+        return None;
+    }
+    Some(Symbol::new(base.to_uppercase(), quote.to_uppercase()))
+}
+
+/// Reads a decimal sent either as a JSON string or a JSON number.
+pub fn value_to_decimal(value: &Value) -> Option<Decimal> {
+    match value {
+        Value::String(s) => s.parse().ok(),
+        Value::Number(n) => n.as_f64().and_then(Decimal::from_f64),
+        _ => None,
+    }
+}
+
+/// Parses one entry of a `/v1/ticker` response.
+pub fn parse_ticker(symbol: &Symbol, ticker: &Value) -> Result<Ticker> {
+    let price = value_to_decimal(&ticker["trade_price"])
+        .ok_or_else(|| ArbFinderError::InvalidData("Missing trade price".to_string()))?;
+    let timestamp = ticker["timestamp"]
+        .as_i64()
+        .and_then(DateTime::<Utc>::from_timestamp_millis)
+        .unwrap_or_else(Utc::now);
+
+    Ok(Ticker {
+        symbol: symbol.clone(),
+        price,
+        volume_24h: value_to_decimal(&ticker["acc_trade_volume_24h"]).unwrap_or(Decimal::ZERO),
+        change_24h: value_to_decimal(&ticker["signed_change_price"]).unwrap_or(Decimal::ZERO),
+        timestamp,
+    })
+}
+
+/// Parses one entry of a `/v1/orderbook` response, keeping at most `limit`
+/// units.
+pub fn parse_orderbook(symbol: &Symbol, orderbook: &Value, limit: Option<u32>) -> Result<OrderBook> {
+    let units = orderbook["orderbook_units"]
+        .as_array()
+        .ok_or_else(|| ArbFinderError::InvalidData("Missing orderbook units".to_string()))?;
+
+    let mut book = OrderBook::new(symbol.clone());
+    let limit = limit.map(|l| l as usize).unwrap_or(units.len());
+    apply_orderbook_units(&mut book, units.iter().take(limit));
+
+    Ok(book)
+}
+
+/// Applies `orderbook_units`, each carrying one ask and one bid level.
+pub fn apply_orderbook_units<'a>(book: &mut OrderBook, units: impl IntoIterator<Item = &'a Value>) {
+    for unit in units {
+        if let (Some(price), Some(size)) = (value_to_decimal(&unit["bid_price"]), value_to_decimal(&unit["bid_size"])) {
+            book.update_bid(price, size);
+        }
+        if let (Some(price), Some(size)) = (value_to_decimal(&unit["ask_price"]), value_to_decimal(&unit["ask_size"])) {
+            book.update_ask(price, size);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_upbit_market_round_trip() {
+        assert_eq!(upbit_market(&Symbol::new("BTC", "KRW")), "KRW-BTC");
+        assert_eq!(upbit_market(&Symbol::new("eth", "usdt")), "USDT-ETH");
+
+        assert_eq!(parse_upbit_market("KRW-BTC"), Some(Symbol::new("BTC", "KRW")));
+        assert_eq!(parse_upbit_market("BTC-XRP"), Some(Symbol::new("XRP", "BTC")));
+        assert_eq!(parse_upbit_market("KRWBTC"), None);
+        assert_eq!(parse_upbit_market("KRW-"), None);
+    }
+
+    #[test]
+    fn test_parse_ticker_and_orderbook() {
+        let symbol = Symbol::new("BTC", "KRW");
+        let ticker: Value = serde_json::from_str(
+            r#"{"market":"KRW-BTC","trade_price":61965000.0,"acc_trade_volume_24h":"1523.5","signed_change_price":-35000,"timestamp":1704867306396}"#,
+        )
+        .unwrap();
+        let ticker = parse_ticker(&symbol, &ticker).unwrap();
+        assert_eq!(ticker.price, Decimal::from(61965000));
+        assert_eq!(ticker.volume_24h, Decimal::from_str("1523.5").unwrap());
+        assert_eq!(ticker.change_24h, Decimal::from(-35000));
+        assert!(parse_ticker(&symbol, &serde_json::json!({})).is_err());
+
+        let orderbook: Value = serde_json::from_str(
+            r#"{"market":"KRW-BTC","orderbook_units":[{"ask_price":61970000,"bid_price":61960000,"ask_size":0.12,"bid_size":0.5},{"ask_price":61980000,"bid_price":61950000,"ask_size":1.0,"bid_size":2.0}]}"#,
+        )
+        .unwrap();
+        let book = parse_orderbook(&symbol, &orderbook, Some(1)).unwrap();
+        assert_eq!(book.bids.len(), 1);
+        assert_eq!(book.best_ask().unwrap().price, Decimal::from(61970000));
+        assert_eq!(parse_orderbook(&symbol, &orderbook, None).unwrap().bids.len(), 2);
+    }
+}
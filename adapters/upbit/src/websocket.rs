@@ -0,0 +1,219 @@
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use tokio::sync::mpsc;
+use tracing::{debug, error, info, warn};
+
+use crate::format::{apply_orderbook_units, parse_upbit_market, upbit_market, value_to_decimal};
+
+pub const UPBIT_WS_URL: &str = "wss://api.upbit.com/websocket/v1";
+
+/// Handles Upbit `orderbook` and `trade` streams.
+///
+/// Upbit sends every message as a binary frame holding UTF-8 JSON, which the
+/// default `on_binary` forwards here. Orderbook messages are full snapshots
+/// of the top levels, so each one replaces the stored book. Bithumb's v1
+/// WebSocket uses the same format, so it runs this handler under its own
+/// venue through `for_venue`.
+pub struct UpbitMarketDataStream {
+    venue: VenueId,
+    symbols: Vec<Symbol>,
+    books: HashMap<Symbol, OrderBook>,
+    update_tx: mpsc::UnboundedSender<MarketData>,
+}
+
+impl UpbitMarketDataStream {
+    pub fn new(symbols: Vec<Symbol>, update_tx: mpsc::UnboundedSender<MarketData>) -> Self {
+        Self::for_venue(VenueId::Upbit, symbols, update_tx)
+    }
+
+    /// Builds the handler for another venue speaking Upbit's format; the
+    /// venue only labels log messages.
+    pub fn for_venue(venue: VenueId, symbols: Vec<Symbol>, update_tx: mpsc::UnboundedSender<MarketData>) -> Self {
+        Self {
+            venue,
+            symbols,
+            books: HashMap::new(),
+            update_tx,
+        }
+    }
+
+    /// Upbit takes a single array: a ticket, one object per stream type, and
+    /// an optional format selector.
+    pub fn subscribe_message(&self) -> String {
+        let codes: Vec<String> = self.symbols.iter().map(upbit_market).collect();
+        serde_json::json!([
+            { "ticket": uuid::Uuid::new_v4().to_string() },
+            { "type": "orderbook", "codes": codes },
+            { "type": "trade", "codes": codes },
+            { "format": "DEFAULT" },
+        ])
+        .to_string()
+    }
+
+    pub fn get_orderbook(&self, symbol: &Symbol) -> Option<&OrderBook> {
+        self.books.get(symbol)
+    }
+
+    fn process_orderbook(&mut self, symbol: Symbol, data: &Value) {
+        let Some(units) = data["orderbook_units"].as_array() else {
+            return;
+        };
+
+        let mut book = OrderBook::new(symbol.clone());
+        apply_orderbook_units(&mut book, units);
+        if let Some(ts) = data["timestamp"].as_i64().and_then(DateTime::<Utc>::from_timestamp_millis) {
+            book.timestamp = ts;
+        }
+
+        let _ = self.update_tx.send(MarketData::OrderBook(book.clone()));
+        self.books.insert(symbol, book);
+    }
+
+    fn process_trade(&self, symbol: Symbol, data: &Value) {
+        let (Some(price), Some(quantity)) = (
+            value_to_decimal(&data["trade_price"]),
+            value_to_decimal(&data["trade_volume"]),
+        ) else {
+            return;
+        };
+
+        // ask_bid is the taker's side: BID means an aggressive buy
+        let side = match data["ask_bid"].as_str() {
+            Some("BID") => Side::Bid,
+            _ => Side::Ask,
+        };
+        let trade_id = data["sequential_id"]
+            .as_u64()
+            .map(|id| id.to_string())
+            .unwrap_or_default();
+
+        let mut trade = Trade::new(symbol, price, quantity, side, trade_id);
+        if let Some(ts) = data["trade_timestamp"].as_i64().and_then(DateTime::<Utc>::from_timestamp_millis) {
+            trade.timestamp = ts;
+        }
+
+        let _ = self.update_tx.send(MarketData::Trade(trade));
+    }
+}
+
+#[async_trait]
+impl WebSocketHandler for UpbitMarketDataStream {
+    async fn on_message(&mut self, message: &str) -> Result<()> {
+        let data: Value = serde_json::from_str(message)?;
+
+        if let Some(err) = data.get("error") {
+            warn!("{} WebSocket error message: {}", self.venue, err);
+            return Ok(());
+        }
+
+        let Some(symbol) = data["code"].as_str().and_then(parse_upbit_market) else {
+            debug!("Ignoring {} message: {}", self.venue, message);
+            return Ok(());
+        };
+
+        match data["type"].as_str() {
+            Some("orderbook") => self.process_orderbook(symbol, &data),
+            Some("trade") => self.process_trade(symbol, &data),
+            _ => debug!("Ignoring {} message: {}", self.venue, message),
+        }
+        Ok(())
+    }
+
+    async fn on_connect(&mut self) -> Result<()> {
+        info!("{} WebSocket connected for {} symbols", self.venue, self.symbols.len());
+        Ok(())
+    }
+
+    async fn on_disconnect(&mut self) -> Result<()> {
+        warn!("{} WebSocket disconnected", self.venue);
+        self.books.clear();
+        Ok(())
+    }
+
+    async fn on_error(&mut self, error: &ArbFinderError) -> Result<()> {
+        error!("{} WebSocket error: {}", self.venue, error);
+        Ok(())
+    }
+
+    async fn on_ping(&mut self) -> Result<()> {
+        debug!("Received {} ping", self.venue);
+        Ok(())
+    }
+
+    async fn on_pong(&mut self) -> Result<()> {
+        debug!("Received {} pong", self.venue);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    fn stream() -> (UpbitMarketDataStream, mpsc::UnboundedReceiver<MarketData>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        (UpbitMarketDataStream::new(vec![Symbol::new("BTC", "KRW")], tx), rx)
+    }
+
+    #[test]
+    fn test_subscribe_message() {
+        let (stream, _rx) = stream();
+        let message: Value = serde_json::from_str(&stream.subscribe_message()).unwrap();
+
+        assert!(message[0]["ticket"].is_string());
+        assert_eq!(message[1]["type"], "orderbook");
+        assert_eq!(message[1]["codes"][0], "KRW-BTC");
+        assert_eq!(message[2]["type"], "trade");
+    }
+
+    #[tokio::test]
+    async fn test_orderbook_snapshot_from_binary_frame() {
+        let (mut stream, mut rx) = stream();
+        let frame = br#"{"type":"orderbook","code":"KRW-BTC","timestamp":1704867306396,"orderbook_units":[{"ask_price":61970000.0,"bid_price":61960000.0,"ask_size":0.12,"bid_size":0.5},{"ask_price":61980000.0,"bid_price":61950000.0,"ask_size":1.0,"bid_size":2.0}],"stream_type":"REALTIME"}"#;
+        stream.on_binary(frame).await.unwrap();
+
+        match rx.recv().await.unwrap() {
+            MarketData::OrderBook(book) => {
+                assert_eq!(book.symbol, Symbol::new("BTC", "KRW"));
+                assert_eq!(book.best_bid().unwrap().price, Decimal::from(61960000));
+                assert_eq!(book.best_ask().unwrap().quantity, Decimal::from_str("0.12").unwrap());
+                assert_eq!(book.bids.len(), 2);
+            }
+            other => panic!("expected order book, got {:?}", other),
+        }
+
+        // The next snapshot replaces the book rather than merging into it
+        stream
+            .on_message(r#"{"type":"orderbook","code":"KRW-BTC","orderbook_units":[{"ask_price":62000000,"bid_price":61990000,"ask_size":1,"bid_size":1}]}"#)
+            .await
+            .unwrap();
+        let book = stream.get_orderbook(&Symbol::new("BTC", "KRW")).unwrap();
+        assert_eq!(book.bids.len(), 1);
+        assert_eq!(book.best_bid().unwrap().price, Decimal::from(61990000));
+    }
+
+    #[tokio::test]
+    async fn test_trade_message() {
+        let (mut stream, mut rx) = stream();
+        stream
+            .on_message(r#"{"type":"trade","code":"KRW-BTC","trade_price":61965000.0,"trade_volume":0.015,"ask_bid":"BID","trade_timestamp":1704867306396,"sequential_id":17048673063960000}"#)
+            .await
+            .unwrap();
+
+        match rx.recv().await.unwrap() {
+            MarketData::Trade(trade) => {
+                assert_eq!(trade.side, Side::Bid);
+                assert_eq!(trade.price, Decimal::from(61965000));
+                assert_eq!(trade.trade_id, "17048673063960000");
+                assert_eq!(trade.timestamp.timestamp_millis(), 1704867306396);
+            }
+            other => panic!("expected trade, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,571 @@
+//! 0x Adapter
+//!
+//! Quotes swaps through the 0x Swap API, which routes across the DEXes of an
+//! EVM chain, and presents each configured pair as an order book built from
+//! quotes at a ladder of sizes (see [`arbfinder_exchange::synthetic`]).
+//!
+//! Books are built from each quote's minimum buy amount: the output after
+//! 0x's fee and the configured slippage tolerance, which is what a swap is
+//! guaranteed to deliver. No trading fee is reported on top. Gas is a fixed
+//! cost per swap; [`ZeroExAdapter::gas_cost`] prices it in the pair's quote
+//! currency for `CrossExchangeArbitrageDetector::set_fixed_cost`.
+//!
+//! Tokens are identified by contract address. Ethereum mainnet ETH (as
+//! WETH), BTC (as WBTC), USDC, USDT and DAI are known; other chains and
+//! tokens are set up with [`ZeroExAdapter::with_chain`] and
+//! [`ZeroExAdapter::with_token`].
+
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::prelude::*;
+use arbfinder_exchange::channel_stream;
+use arbfinder_exchange::synthetic::{self, SwapQuote};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use reqwest::{Client, StatusCode};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, warn};
+
+const ZEROEX_API_URL: &str = "https://api.0x.org/swap/allowance-holder";
+
+const ETHEREUM_CHAIN_ID: u64 = 1;
+
+/// 0x's own default tolerance.
+const DEFAULT_SLIPPAGE_BPS: u32 = 100;
+
+/// Each poll costs a request per size per side; this keeps one pair within
+/// the free tier's limit.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+const NATIVE_DECIMALS: u8 = 18;
+
+/// Notionals quoted on each side, in the quote currency.
+fn default_sizes() -> Vec<Decimal> {
+    [1_000, 10_000, 100_000, 1_000_000].into_iter().map(Decimal::from).collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ZeroExToken {
+    pub address: String,
+    pub decimals: u8,
+}
+
+impl ZeroExToken {
+    pub fn new(address: impl Into<String>, decimals: u8) -> Self {
+        Self {
+            address: address.into().to_lowercase(),
+            decimals,
+        }
+    }
+}
+
+fn ethereum_tokens() -> HashMap<String, ZeroExToken> {
+    HashMap::from([
+        ("ETH".to_string(), ZeroExToken::new("0xC02aaA39b223FE8D0A0e5C4F27eAD9083C756Cc2", 18)),
+        ("BTC".to_string(), ZeroExToken::new("0x2260FAC5E5542a773Aa44fBCfB927A3B2352C599", 8)),
+        ("USDC".to_string(), ZeroExToken::new("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48", 6)),
+        ("USDT".to_string(), ZeroExToken::new("0xdAC17F958D2ee523a2206206994597C13D831ec7", 6)),
+        ("DAI".to_string(), ZeroExToken::new("0x6B175474E89094C44Da98b954EedeAC495271d0F", 18)),
+    ])
+}
+
+/// A quoted swap in whole tokens.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ZeroExQuote {
+    pub amount_in: Decimal,
+    /// Expected output, net of 0x's fee.
+    pub amount_out: Decimal,
+    /// Output guaranteed within the slippage tolerance.
+    pub min_amount_out: Decimal,
+    /// 0x's fee in the output token, already taken out of `amount_out`.
+    pub router_fee: Decimal,
+    /// Gas for the swap in the chain's native token.
+    pub network_fee: Decimal,
+    /// Liquidity sources filled, e.g. `["Uniswap_V3", "Curve"]`.
+    pub sources: Vec<String>,
+}
+
+/// Parses a price response; raw amounts are converted with the input and
+/// output tokens' decimals. Responses without liquidity give `None`.
+pub fn parse_quote(data: &Value, in_decimals: u8, out_decimals: u8) -> Option<ZeroExQuote> {
+    if !data["liquidityAvailable"].as_bool().unwrap_or(false) {
+        return None;
+    }
+    let raw = |value: &Value| value.as_str()?.parse::<u128>().ok();
+    let buy_token = data["buyToken"].as_str().unwrap_or_default();
+    let fee = &data["fees"]["zeroExFee"];
+    let router_fee = match raw(&fee["amount"]) {
+        Some(amount) if fee["token"].as_str().is_some_and(|token| token.eq_ignore_ascii_case(buy_token)) => {
+            synthetic::from_raw(amount, out_decimals)?
+        }
+        _ => Decimal::ZERO,
+    };
+    let sources = data["route"]["fills"]
+        .as_array()
+        .map(|fills| {
+            fills
+                .iter()
+                .filter_map(|fill| fill["source"].as_str().map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Some(ZeroExQuote {
+        amount_in: synthetic::from_raw(raw(&data["sellAmount"])?, in_decimals)?,
+        amount_out: synthetic::from_raw(raw(&data["buyAmount"])?, out_decimals)?,
+        min_amount_out: synthetic::from_raw(raw(&data["minBuyAmount"])?, out_decimals)?,
+        router_fee,
+        network_fee: raw(&data["totalNetworkFee"])
+            .and_then(|wei| synthetic::from_raw(wei, NATIVE_DECIMALS))
+            .unwrap_or_default(),
+        sources,
+    })
+}
+
+/// Swap API access shared by the adapter and its polling task.
+#[derive(Clone)]
+struct QuoteClient {
+    client: Client,
+    base_url: String,
+    api_key: String,
+    chain_id: u64,
+    slippage_bps: u32,
+}
+
+impl QuoteClient {
+    /// Quotes selling `amount` whole tokens of `from` for `to`.
+    async fn quote(&self, from: &ZeroExToken, to: &ZeroExToken, amount: Decimal) -> Result<ZeroExQuote> {
+        let raw = synthetic::to_raw(amount, from.decimals)
+            .ok_or_else(|| ArbFinderError::InvalidData(format!("Amount {} out of range", amount)))?;
+        let url = format!(
+            "{}/price?chainId={}&sellToken={}&buyToken={}&sellAmount={}&slippageBps={}",
+            self.base_url, self.chain_id, from.address, to.address, raw, self.slippage_bps
+        );
+        let response = self
+            .client
+            .get(&url)
+            .header("0x-api-key", &self.api_key)
+            .header("0x-version", "v2")
+            .send()
+            .await
+            .map_err(ArbFinderError::Http)?;
+
+        let status = response.status();
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(ArbFinderError::RateLimit("0x quote limit".to_string()));
+        }
+        let data: Value = response.json().await.map_err(ArbFinderError::Http)?;
+        if !status.is_success() {
+            return Err(ArbFinderError::Exchange(format!(
+                "0x quote failed ({}): {}",
+                status,
+                data["message"].as_str().or_else(|| data["name"].as_str()).unwrap_or_default()
+            )));
+        }
+
+        parse_quote(&data, from.decimals, to.decimals).ok_or_else(|| {
+            ArbFinderError::Exchange(format!("0x has no liquidity for {} -> {}", from.address, to.address))
+        })
+    }
+
+    /// Quotes each amount in turn, stopping at the first one that cannot
+    /// be filled. Ladder entries pay out the minimum buy amount.
+    async fn ladder(&self, from: &ZeroExToken, to: &ZeroExToken, amounts: &[Decimal]) -> Vec<SwapQuote> {
+        let mut quotes = Vec::with_capacity(amounts.len());
+        for amount in amounts {
+            match self.quote(from, to, *amount).await {
+                Ok(quote) => {
+                    debug!("0x fills {} {} via {:?}", amount, from.address, quote.sources);
+                    quotes.push(SwapQuote::new(quote.amount_in, quote.min_amount_out));
+                }
+                Err(e) => {
+                    debug!("0x stopped quoting {} -> {}: {}", from.address, to.address, e);
+                    break;
+                }
+            }
+        }
+        quotes
+    }
+
+    /// Asks come from spending each notional of the quote token. Bids sell
+    /// the same notionals' worth of the base, valued at the smallest ask.
+    async fn book(&self, symbol: &Symbol, base: &ZeroExToken, quote: &ZeroExToken, sizes: &[Decimal]) -> Result<OrderBook> {
+        let asks = self.ladder(quote, base, sizes).await;
+        let reference = asks
+            .first()
+            .filter(|first| !first.amount_out.is_zero())
+            .map(|first| first.amount_in / first.amount_out)
+            .ok_or_else(|| ArbFinderError::Exchange(format!("0x found no route for {}", symbol)))?;
+
+        let base_sizes: Vec<Decimal> = sizes
+            .iter()
+            .map(|size| (size / reference).round_dp(u32::from(base.decimals)))
+            .collect();
+        let bids = self.ladder(base, quote, &base_sizes).await;
+
+        Ok(synthetic::book_from_quotes(symbol.clone(), &bids, &asks))
+    }
+}
+
+pub struct ZeroExAdapter {
+    quotes: QuoteClient,
+    tokens: HashMap<String, ZeroExToken>,
+    pairs: Vec<Symbol>,
+    sizes: Vec<Decimal>,
+    poll_interval: Duration,
+    /// Native token price in quote currency, for pricing gas on pairs
+    /// without it.
+    native_price: Option<Decimal>,
+    connected: bool,
+    subscriptions: Vec<Symbol>,
+}
+
+impl ZeroExAdapter {
+    /// Quotes Ethereum mainnet with the given 0x API key.
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            quotes: QuoteClient {
+                client: Client::new(),
+                base_url: ZEROEX_API_URL.to_string(),
+                api_key: api_key.into(),
+                chain_id: ETHEREUM_CHAIN_ID,
+                slippage_bps: DEFAULT_SLIPPAGE_BPS,
+            },
+            tokens: ethereum_tokens(),
+            pairs: Vec::new(),
+            sizes: default_sizes(),
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            native_price: None,
+            connected: false,
+            subscriptions: Vec::new(),
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.quotes.base_url = base_url.into();
+        self
+    }
+
+    /// Quotes chain `chain_id` instead. Token addresses differ between
+    /// chains, so the mainnet ones are forgotten unless `chain_id` is 1.
+    pub fn with_chain(mut self, chain_id: u64) -> Self {
+        if chain_id != self.quotes.chain_id {
+            self.tokens = if chain_id == ETHEREUM_CHAIN_ID {
+                ethereum_tokens()
+            } else {
+                HashMap::new()
+            };
+        }
+        self.quotes.chain_id = chain_id;
+        self
+    }
+
+    /// Makes `asset` known by its contract address.
+    pub fn with_token(mut self, asset: &str, address: impl Into<String>, decimals: u8) -> Self {
+        self.tokens.insert(asset.to_uppercase(), ZeroExToken::new(address, decimals));
+        self
+    }
+
+    /// Quotes `symbol`, whose assets must both be known tokens.
+    pub fn with_pair(mut self, symbol: Symbol) -> Self {
+        if !self.pairs.contains(&symbol) {
+            self.pairs.push(symbol);
+        }
+        self
+    }
+
+    /// Notionals quoted on each book side, in the quote currency. Each size
+    /// is one more level.
+    pub fn with_sizes(mut self, mut sizes: Vec<Decimal>) -> Self {
+        sizes.retain(|size| *size > Decimal::ZERO);
+        sizes.sort();
+        sizes.dedup();
+        self.sizes = sizes;
+        self
+    }
+
+    /// Slippage tolerance in basis points; book prices assume the worst
+    /// fill it allows.
+    pub fn with_slippage_bps(mut self, bps: u32) -> Self {
+        self.quotes.slippage_bps = bps;
+        self
+    }
+
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    pub fn set_native_price(&mut self, price: Decimal) {
+        self.native_price = Some(price);
+    }
+
+    pub fn chain_id(&self) -> u64 {
+        self.quotes.chain_id
+    }
+
+    pub fn token(&self, asset: &str) -> Result<&ZeroExToken> {
+        self.tokens.get(&asset.to_uppercase()).ok_or_else(|| {
+            ArbFinderError::SymbolNotFound(format!(
+                "No token address known for {} on chain {}",
+                asset, self.quotes.chain_id
+            ))
+        })
+    }
+
+    /// Quotes selling `amount` of `symbol`'s base for its quote, or buying
+    /// the base with `amount` of the quote.
+    pub async fn get_quote(&self, symbol: &Symbol, side: OrderSide, amount: Decimal) -> Result<ZeroExQuote> {
+        let base = self.token(symbol.base())?;
+        let quote = self.token(symbol.quote())?;
+        match side {
+            OrderSide::Sell => self.quotes.quote(base, quote, amount).await,
+            OrderSide::Buy => self.quotes.quote(quote, base, amount).await,
+        }
+    }
+
+    /// Book synthesized from current quotes.
+    pub async fn get_orderbook(&self, symbol: &Symbol) -> Result<OrderBook> {
+        let base = self.token(symbol.base())?;
+        let quote = self.token(symbol.quote())?;
+        self.quotes.book(symbol, base, quote, &self.sizes).await
+    }
+
+    /// Network fee of a swap of the smallest configured size, in `symbol`'s
+    /// quote currency.
+    pub async fn gas_cost(&self, symbol: &Symbol) -> Result<Decimal> {
+        let size = self.sizes.first().copied().unwrap_or(Decimal::ONE);
+        let quote = self.get_quote(symbol, OrderSide::Buy, size).await?;
+
+        let native = self.native_asset();
+        let native_price = if symbol.quote() == native {
+            Decimal::ONE
+        } else if symbol.base() == native && !quote.amount_out.is_zero() {
+            quote.amount_in / quote.amount_out
+        } else {
+            self.native_price.ok_or_else(|| {
+                ArbFinderError::InvalidData(format!(
+                    "{} price in {} is needed to price gas; call set_native_price",
+                    native,
+                    symbol.quote()
+                ))
+            })?
+        };
+
+        Ok(quote.network_fee * native_price)
+    }
+
+    /// Asset gas is paid in; only mainnet's is known.
+    fn native_asset(&self) -> &'static str {
+        match self.quotes.chain_id {
+            ETHEREUM_CHAIN_ID => "ETH",
+            _ => "",
+        }
+    }
+}
+
+#[async_trait]
+impl MarketDataAdapter for ZeroExAdapter {
+    fn venue_id(&self) -> VenueId {
+        VenueId::ZeroEx
+    }
+
+    async fn connect(&mut self) -> Result<()> {
+        if self.quotes.api_key.is_empty() {
+            return Err(ArbFinderError::Authentication("0x API key is required".to_string()));
+        }
+        for symbol in &self.pairs {
+            self.token(symbol.base())?;
+            self.token(symbol.quote())?;
+        }
+        self.connected = true;
+        Ok(())
+    }
+
+    async fn disconnect(&mut self) -> Result<()> {
+        self.connected = false;
+        Ok(())
+    }
+
+    async fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// The Swap API reports no time; quotes are for the latest block.
+    async fn get_server_time(&self) -> Result<DateTime<Utc>> {
+        Ok(Utc::now())
+    }
+
+    async fn ping(&self) -> Result<u64> {
+        let start = std::time::Instant::now();
+        let symbol = self
+            .pairs
+            .first()
+            .ok_or_else(|| ArbFinderError::Exchange("No 0x pairs configured".to_string()))?;
+        self.get_quote(symbol, OrderSide::Buy, Decimal::ONE).await?;
+        Ok(start.elapsed().as_millis() as u64)
+    }
+
+    async fn get_symbols(&self) -> Result<Vec<Symbol>> {
+        Ok(self.pairs.clone())
+    }
+
+    async fn get_symbol_info(&self, symbol: &Symbol) -> Result<SymbolInfo> {
+        let base = self.token(symbol.base())?;
+        let quote = self.token(symbol.quote())?;
+        let lot_size = Decimal::new(1, u32::from(base.decimals));
+
+        Ok(SymbolInfo {
+            symbol: symbol.clone(),
+            status: "open".to_string(),
+            base_asset_precision: u32::from(base.decimals),
+            quote_asset_precision: u32::from(quote.decimals),
+            tick_size: Decimal::new(1, 8),
+            lot_size,
+            min_order_size: lot_size,
+            max_order_size: Decimal::new(1000000, 0),
+            min_notional: Decimal::ZERO,
+            // Already taken out of the quotes
+            trading_fees: TradingFees {
+                maker_fee: Decimal::ZERO,
+                taker_fee: Decimal::ZERO,
+            },
+        })
+    }
+
+    async fn subscribe_orderbook(&mut self, symbol: &Symbol, _depth: Option<u32>) -> Result<()> {
+        self.token(symbol.base())?;
+        self.token(symbol.quote())?;
+        if !self.subscriptions.contains(symbol) {
+            self.subscriptions.push(symbol.clone());
+        }
+        Ok(())
+    }
+
+    async fn subscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+        Err(ArbFinderError::Exchange("0x has no trade feed".to_string()))
+    }
+
+    async fn subscribe_ticker(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscribe_orderbook(symbol, None).await
+    }
+
+    async fn unsubscribe_orderbook(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscriptions.retain(|s| s != symbol);
+        Ok(())
+    }
+
+    async fn unsubscribe_trades(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    async fn unsubscribe_ticker(&mut self, _symbol: &Symbol) -> Result<()> {
+        Ok(())
+    }
+
+    /// Re-quotes the subscribed pairs every poll interval.
+    async fn market_data_stream(&self) -> Result<MarketDataStream> {
+        if self.subscriptions.is_empty() {
+            return Err(ArbFinderError::Exchange("No 0x pairs subscribed".to_string()));
+        }
+        let pairs: Vec<(Symbol, ZeroExToken, ZeroExToken)> = self
+            .subscriptions
+            .iter()
+            .map(|symbol| {
+                Ok((
+                    symbol.clone(),
+                    self.token(symbol.base())?.clone(),
+                    self.token(symbol.quote())?.clone(),
+                ))
+            })
+            .collect::<Result<_>>()?;
+
+        let (tx, rx) = mpsc::unbounded_channel();
+        let quotes = self.quotes.clone();
+        let sizes = self.sizes.clone();
+        let mut interval = tokio::time::interval(self.poll_interval);
+        tokio::spawn(async move {
+            loop {
+                interval.tick().await;
+                for (symbol, base, quote) in &pairs {
+                    match quotes.book(symbol, base, quote, &sizes).await {
+                        Ok(book) => {
+                            if tx.send(MarketData::OrderBook(book)).is_err() {
+                                return;
+                            }
+                        }
+                        Err(e) => warn!("Failed to quote {} on 0x: {}", symbol, e),
+                    }
+                }
+            }
+        });
+        Ok(channel_stream(rx))
+    }
+
+    async fn fetch_orderbook(&self, symbol: &Symbol, _depth: Option<u32>) -> Result<OrderBook> {
+        self.get_orderbook(symbol).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn test_zeroex_adapter_creation() {
+        let mut adapter = ZeroExAdapter::new("key")
+            .with_pair(Symbol::new("ETH", "USDC"))
+            .with_sizes(vec![dec!(10000), dec!(1000), dec!(0)])
+            .with_slippage_bps(30);
+        assert_eq!(adapter.venue_id(), VenueId::ZeroEx);
+        assert_eq!(adapter.sizes, vec![dec!(1000), dec!(10000)]);
+        assert_eq!(adapter.quotes.slippage_bps, 30);
+        assert_eq!(adapter.token("btc").unwrap().decimals, 8);
+        adapter.connect().await.unwrap();
+
+        let mut base = ZeroExAdapter::new("key")
+            .with_chain(8453)
+            .with_token("USDC", "0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913", 6);
+        assert_eq!(base.chain_id(), 8453);
+        assert!(base.token("ETH").is_err());
+        assert!(base.subscribe_orderbook(&Symbol::new("ETH", "USDC"), None).await.is_err());
+
+        assert!(ZeroExAdapter::new("").connect().await.is_err());
+    }
+
+    #[test]
+    fn test_parse_quote() {
+        let data = serde_json::json!({
+            "blockNumber": "20114692",
+            "buyAmount": "384615384615384615",
+            "buyToken": "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2",
+            "fees": {
+                "integratorFee": null,
+                "zeroExFee": {"amount": "577500000000000", "token": "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2", "type": "volume"},
+                "gasFee": null
+            },
+            "liquidityAvailable": true,
+            "minBuyAmount": "380769230769230769",
+            "route": {"fills": [
+                {"source": "Uniswap_V3", "proportionBps": "7000"},
+                {"source": "Curve", "proportionBps": "3000"}
+            ]},
+            "sellAmount": "1000000000",
+            "sellToken": "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48",
+            "totalNetworkFee": "2034668056550000"
+        });
+        let quote = parse_quote(&data, 6, 18).unwrap();
+        assert_eq!(quote.amount_in, dec!(1000));
+        assert_eq!(quote.amount_out, dec!(0.384615384615384615));
+        assert_eq!(quote.min_amount_out, dec!(0.380769230769230769));
+        assert_eq!(quote.router_fee, dec!(0.0005775));
+        assert_eq!(quote.network_fee, dec!(0.00203466805655));
+        assert_eq!(quote.sources, vec!["Uniswap_V3", "Curve"]);
+
+        assert!(parse_quote(&serde_json::json!({"liquidityAvailable": false}), 6, 18).is_none());
+    }
+}
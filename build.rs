@@ -0,0 +1,56 @@
+//! Records what the binary is built from for `arbfinder::build_info`: the
+//! git commit and whether the tree was dirty, the build time and the
+//! enabled cargo features.
+//!
+//! Builds outside a git checkout, e.g. from a source tarball, can pass the
+//! commit in `ARBFINDER_GIT_COMMIT`. `SOURCE_DATE_EPOCH` fixes the build
+//! time for reproducible builds.
+
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn main() {
+    println!("cargo:rerun-if-env-changed=ARBFINDER_GIT_COMMIT");
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    // A commit moves HEAD or the branch it points at, and rewrites the index
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/index");
+    if let Some(branch) = git(&["symbolic-ref", "-q", "HEAD"]) {
+        println!("cargo:rerun-if-changed=.git/{}", branch);
+    }
+
+    let (commit, dirty) = match std::env::var("ARBFINDER_GIT_COMMIT") {
+        Ok(commit) if !commit.is_empty() => (commit, false),
+        _ => match git(&["rev-parse", "HEAD"]) {
+            Some(commit) => {
+                let dirty = git(&["status", "--porcelain", "--untracked-files=no"]).is_some_and(|s| !s.is_empty());
+                (commit, dirty)
+            }
+            None => ("unknown".to_string(), false),
+        },
+    };
+
+    let build_time = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse::<u64>().ok())
+        .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0));
+
+    let mut features: Vec<String> = std::env::vars()
+        .filter_map(|(key, _)| key.strip_prefix("CARGO_FEATURE_").map(|f| f.to_lowercase().replace('_', "-")))
+        .collect();
+    features.sort();
+
+    println!("cargo:rustc-env=ARBFINDER_GIT_COMMIT={}", commit);
+    println!("cargo:rustc-env=ARBFINDER_GIT_DIRTY={}", dirty);
+    println!("cargo:rustc-env=ARBFINDER_BUILD_TIME={}", build_time);
+    println!("cargo:rustc-env=ARBFINDER_FEATURES={}", features.join(","));
+    println!("cargo:rustc-env=ARBFINDER_PROFILE={}", std::env::var("PROFILE").unwrap_or_default());
+}
@@ -4,6 +4,7 @@ use std::collections::HashMap;
 use std::path::Path;
 
 use crate::types::{VenueCredentials, VenueId};
+use crate::utils::retry::RetryPolicy;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ArbFinderConfig {
@@ -26,18 +27,97 @@ pub struct VenueConfig {
     pub reconnect_delay_ms: u64,
     pub heartbeat_interval_ms: u64,
     pub order_book_depth: u32,
+    /// Overrides the retry policy derived from the reconnect settings.
+    #[serde(default)]
+    pub retry_policy: Option<RetryPolicy>,
+    /// Weighted request and order limits. Without them, requests are only
+    /// limited in number per second.
+    #[serde(default)]
+    pub rate_limits: Option<RateLimitConfig>,
+    /// Proxy REST requests and WebSocket connections go through.
+    #[serde(default)]
+    pub proxy: Option<ProxyConfig>,
+}
+
+/// An outbound proxy, e.g. `http://proxy.internal:3128` or
+/// `socks5h://127.0.0.1:1080`. Credentials may be given in the URL or
+/// separately.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProxyConfig {
+    pub url: String,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+impl ProxyConfig {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            username: None,
+            password: None,
+        }
+    }
+}
+
+/// What a venue's requests cost and how much it allows per window, e.g.
+/// Binance's 6000 weight a minute and 10 orders a second.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitConfig {
+    pub windows: Vec<RateLimitWindow>,
+    /// Weight of each endpoint path; endpoints not listed weigh
+    /// `default_weight`.
+    #[serde(default)]
+    pub endpoint_weights: HashMap<String, u32>,
+    #[serde(default = "default_endpoint_weight")]
+    pub default_weight: u32,
+    /// Endpoints that place an order when called with anything but GET,
+    /// counted once by `orders` windows on top of their weight.
+    #[serde(default)]
+    pub order_endpoints: Vec<String>,
+}
+
+fn default_endpoint_weight() -> u32 {
+    1
+}
+
+/// What a window counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateLimitScope {
+    /// The weight of every request.
+    Requests,
+    /// Orders placed, one each.
+    Orders,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RateLimitWindow {
+    pub scope: RateLimitScope,
+    /// Weight or orders allowed per `window_secs`.
+    pub limit: u32,
+    pub window_secs: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct StrategyConfig {
     pub enabled_strategies: Vec<String>,
     pub min_spread_bps: i32,
+    /// Smallest net profit worth taking, in quote currency, however good the
+    /// spread. Zero disables the check.
+    #[serde(default)]
+    pub min_profit_quote: rust_decimal::Decimal,
     pub max_position_size: rust_decimal::Decimal,
     pub position_timeout_ms: u64,
     pub confidence_threshold: f64,
     pub signal_strength_threshold: f64,
     pub max_opportunities_per_second: u32,
     pub min_volume_threshold: rust_decimal::Decimal,
+    /// Relative share of the order rate limit each strategy gets once the
+    /// limit is reached; strategies not listed weigh 1.
+    #[serde(default)]
+    pub priorities: HashMap<String, u32>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -165,6 +245,9 @@ impl ArbFinderConfig {
                 reconnect_delay_ms: 5000,
                 heartbeat_interval_ms: 30000,
                 order_book_depth: 20,
+                retry_policy: None,
+                rate_limits: None,
+                proxy: None,
             },
         );
 
@@ -179,6 +262,26 @@ impl ArbFinderConfig {
                 reconnect_delay_ms: 5000,
                 heartbeat_interval_ms: 30000,
                 order_book_depth: 20,
+                retry_policy: None,
+                rate_limits: None,
+                proxy: None,
+            },
+        );
+
+        venues.insert(
+            VenueId::Bitget,
+            VenueConfig {
+                enabled: true,
+                credentials: None,
+                symbols: vec!["BTC/USDT".to_string(), "ETH/USDT".to_string()],
+                rate_limit_buffer: 0.8,
+                reconnect_attempts: 10,
+                reconnect_delay_ms: 5000,
+                heartbeat_interval_ms: 30000,
+                order_book_depth: 20,
+                retry_policy: None,
+                rate_limits: None,
+                proxy: None,
             },
         );
 
@@ -191,12 +294,14 @@ impl StrategyConfig {
         Self {
             enabled_strategies: vec!["simple".to_string(), "cross_exchange".to_string()],
             min_spread_bps: 50,
+            min_profit_quote: rust_decimal::Decimal::ONE,
             max_position_size: rust_decimal::Decimal::from(1000),
             position_timeout_ms: 30000,
             confidence_threshold: 0.7,
             signal_strength_threshold: 0.6,
             max_opportunities_per_second: 100,
             min_volume_threshold: rust_decimal::Decimal::from(100),
+            priorities: HashMap::new(),
         }
     }
 
@@ -204,12 +309,14 @@ impl StrategyConfig {
         Self {
             enabled_strategies: vec!["simple".to_string(), "cross_exchange".to_string()],
             min_spread_bps: 20,
+            min_profit_quote: rust_decimal::Decimal::from(5),
             max_position_size: rust_decimal::Decimal::from(10000),
             position_timeout_ms: 15000,
             confidence_threshold: 0.8,
             signal_strength_threshold: 0.7,
             max_opportunities_per_second: 1000,
             min_volume_threshold: rust_decimal::Decimal::from(1000),
+            priorities: HashMap::new(),
         }
     }
 }
@@ -64,6 +64,9 @@ pub enum ArbFinderError {
     #[error("Parse error: {0}")]
     Parse(String),
 
+    #[error("Unsupported: {0}")]
+    Unsupported(String),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
\ No newline at end of file
@@ -0,0 +1,178 @@
+//! Runtime feature flags for rolling out risky subsystems gradually.
+//!
+//! A flag's rule comes from config and can be overridden at runtime through
+//! the admin API, which takes effect on the next check without a restart.
+//! A flag with no rule is on, leaving the subsystem's own settings to
+//! decide, so a flag only needs configuring to narrow or stop a rollout.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{ArbFinderError, Result, Symbol, VenueId};
+
+/// Post-only routing in maker-first execution mode.
+pub const MAKER_FIRST: &str = "maker_first";
+/// Dropping opportunities the ML model scores as unprofitable.
+pub const ML_GATING: &str = "ml_gating";
+/// Sending the same order to several venues and keeping the first fill.
+pub const LATENCY_RACING: &str = "latency_racing";
+
+/// Where a flag is on. Empty `venues` or `symbols` match all of them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlagRule {
+    pub enabled: bool,
+    /// Venue names, e.g. `binance`.
+    #[serde(default)]
+    pub venues: Vec<String>,
+    /// Pairs, e.g. `BTC/USDT`.
+    #[serde(default)]
+    pub symbols: Vec<String>,
+}
+
+impl FlagRule {
+    pub fn on() -> Self {
+        Self { enabled: true, venues: Vec::new(), symbols: Vec::new() }
+    }
+
+    pub fn off() -> Self {
+        Self { enabled: false, venues: Vec::new(), symbols: Vec::new() }
+    }
+
+    pub fn matches(&self, venue: &VenueId, symbol: &Symbol) -> bool {
+        let pair = symbol.to_pair();
+        self.enabled
+            && (self.venues.is_empty() || self.venues.iter().any(|v| VenueId::from(v.as_str()) == *venue))
+            && (self.symbols.is_empty() || self.symbols.iter().any(|s| s.eq_ignore_ascii_case(&pair)))
+    }
+}
+
+/// A flag's rule in effect and whether it is a runtime override.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FlagStatus {
+    pub name: String,
+    pub rule: FlagRule,
+    pub overridden: bool,
+}
+
+#[derive(Debug, Default)]
+struct FlagTable {
+    configured: HashMap<String, FlagRule>,
+    overrides: HashMap<String, FlagRule>,
+}
+
+/// Flag rules shared by every clone.
+#[derive(Debug, Clone, Default)]
+pub struct FeatureFlags {
+    table: Arc<RwLock<FlagTable>>,
+}
+
+impl FeatureFlags {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads a `[feature_flags]` table of rules by flag name.
+    pub fn from_toml(value: &toml::Value) -> Result<Self> {
+        let flags = Self::new();
+        let table = value
+            .as_table()
+            .ok_or_else(|| ArbFinderError::InvalidData("feature_flags must be a table".to_string()))?;
+        for (name, rule) in table {
+            let rule: FlagRule = rule
+                .clone()
+                .try_into()
+                .map_err(|e| ArbFinderError::InvalidData(format!("Invalid feature flag {}: {}", name, e)))?;
+            flags.configure(name, rule);
+        }
+        Ok(flags)
+    }
+
+    /// Sets the configured rule, which an override takes precedence over.
+    pub fn configure(&self, name: &str, rule: FlagRule) {
+        if let Ok(mut table) = self.table.write() {
+            table.configured.insert(name.to_string(), rule);
+        }
+    }
+
+    pub fn set_override(&self, name: &str, rule: FlagRule) {
+        if let Ok(mut table) = self.table.write() {
+            table.overrides.insert(name.to_string(), rule);
+        }
+    }
+
+    /// Drops the override, returning to the configured rule. Returns
+    /// whether there was one.
+    pub fn clear_override(&self, name: &str) -> bool {
+        self.table
+            .write()
+            .map(|mut table| table.overrides.remove(name).is_some())
+            .unwrap_or(false)
+    }
+
+    pub fn rule(&self, name: &str) -> Option<FlagRule> {
+        let table = self.table.read().ok()?;
+        table.overrides.get(name).or_else(|| table.configured.get(name)).cloned()
+    }
+
+    pub fn is_enabled(&self, name: &str, venue: &VenueId, symbol: &Symbol) -> bool {
+        match self.rule(name) {
+            Some(rule) => rule.matches(venue, symbol),
+            None => true,
+        }
+    }
+
+    /// Every flag with a rule, by name.
+    pub fn statuses(&self) -> Vec<FlagStatus> {
+        let Ok(table) = self.table.read() else {
+            return Vec::new();
+        };
+        // Overrides first, so a flag with both keeps its override
+        let mut statuses: Vec<FlagStatus> = table
+            .overrides
+            .iter()
+            .map(|(name, rule)| (name, rule, true))
+            .chain(table.configured.iter().map(|(name, rule)| (name, rule, false)))
+            .map(|(name, rule, overridden)| FlagStatus { name: name.clone(), rule: rule.clone(), overridden })
+            .collect();
+        statuses.sort_by(|a, b| a.name.cmp(&b.name).then(b.overridden.cmp(&a.overridden)));
+        statuses.dedup_by(|later, earlier| later.name == earlier.name);
+        statuses
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules_and_overrides() {
+        let config: toml::Value = toml::from_str(
+            r#"
+            [maker_first]
+            enabled = true
+            venues = ["binance"]
+            symbols = ["BTC/USDT"]
+            "#,
+        )
+        .unwrap();
+        let flags = FeatureFlags::from_toml(&config).unwrap();
+        let btc = Symbol::new("BTC", "USDT");
+        let eth = Symbol::new("ETH", "USDT");
+
+        assert!(flags.is_enabled(MAKER_FIRST, &VenueId::Binance, &btc));
+        assert!(!flags.is_enabled(MAKER_FIRST, &VenueId::Kraken, &btc));
+        assert!(!flags.is_enabled(MAKER_FIRST, &VenueId::Binance, &eth));
+        assert!(flags.is_enabled(LATENCY_RACING, &VenueId::Kraken, &eth));
+
+        let shared = flags.clone();
+        shared.set_override(MAKER_FIRST, FlagRule::off());
+        assert!(!flags.is_enabled(MAKER_FIRST, &VenueId::Binance, &btc));
+        assert!(flags.statuses()[0].overridden);
+
+        assert!(flags.clear_override(MAKER_FIRST));
+        assert!(!flags.clear_override(MAKER_FIRST));
+        assert!(flags.is_enabled(MAKER_FIRST, &VenueId::Binance, &btc));
+    }
+}
@@ -1,5 +1,6 @@
 pub mod config;
 pub mod error;
+pub mod flags;
 pub mod types;
 pub mod utils;
 pub mod prelude;
@@ -1,12 +1,19 @@
 //! Prelude module - re-exports commonly used types for convenience
 
 pub use crate::error::{ArbFinderError, Result};
+pub use crate::flags::{FeatureFlags, FlagRule};
 pub use crate::types::{
     arbitrage::*,
     market::*,
     order::*,
     venue::*,
+    symbol_id::*,
+    event_time::*,
+    record::*,
+    secret::*,
 };
+pub use crate::utils::retry::{ErrorClass, RetryPolicy};
+pub use crate::utils::skew::{ClockSkew, SharedClockSkew, SkewSample};
 
 // Re-export commonly used external types
 pub use rust_decimal::Decimal;
@@ -0,0 +1,148 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// When an event happened, on both clocks.
+///
+/// `wall` is what gets logged and exported. `mono` is what latencies are
+/// measured with: the wall clock can step backwards under NTP adjustment,
+/// which turns `later - earlier` into a negative latency. Ordering and
+/// durations use `mono` only.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EventTime {
+    wall: DateTime<Utc>,
+    mono: Instant,
+}
+
+impl EventTime {
+    pub fn now() -> Self {
+        Self {
+            wall: Utc::now(),
+            mono: Instant::now(),
+        }
+    }
+
+    /// Pairs a wall-clock time from elsewhere (an exchange timestamp, a
+    /// deserialized event) with the monotonic instant it corresponds to
+    /// under the current clock offset.
+    pub fn from_wall(wall: DateTime<Utc>) -> Self {
+        let now = Self::now();
+        let mono = match (now.wall - wall).to_std() {
+            Ok(age) => now.mono.checked_sub(age).unwrap_or(now.mono),
+            // In the future relative to our clock
+            Err(_) => (wall - now.wall)
+                .to_std()
+                .ok()
+                .and_then(|ahead| now.mono.checked_add(ahead))
+                .unwrap_or(now.mono),
+        };
+        Self { wall, mono }
+    }
+
+    pub fn wall(&self) -> DateTime<Utc> {
+        self.wall
+    }
+
+    pub fn mono(&self) -> Instant {
+        self.mono
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.mono.elapsed()
+    }
+
+    /// Time from `earlier` to `self`; zero if `earlier` is actually later.
+    pub fn duration_since(&self, earlier: EventTime) -> Duration {
+        self.mono.saturating_duration_since(earlier.mono)
+    }
+}
+
+impl Default for EventTime {
+    fn default() -> Self {
+        Self::now()
+    }
+}
+
+impl PartialOrd for EventTime {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for EventTime {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.mono.cmp(&other.mono).then(self.wall.cmp(&other.wall))
+    }
+}
+
+impl From<EventTime> for DateTime<Utc> {
+    fn from(time: EventTime) -> Self {
+        time.wall
+    }
+}
+
+impl From<DateTime<Utc>> for EventTime {
+    fn from(wall: DateTime<Utc>) -> Self {
+        EventTime::from_wall(wall)
+    }
+}
+
+impl fmt::Debug for EventTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventTime").field("wall", &self.wall).finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for EventTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.wall, f)
+    }
+}
+
+// Only the wall clock is meaningful outside this process
+impl Serialize for EventTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.wall.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for EventTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        DateTime::<Utc>::deserialize(deserializer).map(EventTime::from_wall)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_durations_use_monotonic_clock() {
+        let start = EventTime::now();
+        std::thread::sleep(Duration::from_millis(5));
+        let end = EventTime::now();
+
+        // A wall clock stepped back by NTP must not produce a negative latency
+        let stepped = EventTime {
+            wall: end.wall - chrono::Duration::seconds(30),
+            mono: end.mono,
+        };
+        assert!(stepped.duration_since(start) >= Duration::from_millis(5));
+        assert!(stepped > start);
+        assert_eq!(start.duration_since(end), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_from_wall_and_serde() {
+        let wall = Utc::now() - chrono::Duration::milliseconds(250);
+        let time = EventTime::from_wall(wall);
+        assert!(time.elapsed() >= Duration::from_millis(250));
+
+        let json = serde_json::to_string(&time).unwrap();
+        assert_eq!(json, serde_json::to_string(&wall).unwrap());
+        let back: EventTime = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.wall(), wall);
+    }
+}
@@ -5,6 +5,8 @@ use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::fmt;
 
+use crate::utils::clock::coarse_now;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct Symbol {
     pub base: String,
@@ -73,7 +75,7 @@ impl OrderBookLevel {
         Self {
             price,
             quantity,
-            timestamp: Utc::now(),
+            timestamp: coarse_now(),
         }
     }
 
@@ -128,6 +130,20 @@ impl OrderBook {
         }
     }
 
+    /// This book cut down to its best bid and best ask.
+    pub fn top_of_book(&self) -> OrderBook {
+        let mut top = OrderBook::new(self.symbol.clone());
+        if let Some((key, level)) = self.bids.iter().next_back() {
+            top.bids.insert(*key, level.clone());
+        }
+        if let Some((key, level)) = self.asks.iter().next() {
+            top.asks.insert(*key, level.clone());
+        }
+        top.timestamp = self.timestamp;
+        top.sequence = self.sequence;
+        top
+    }
+
     pub fn update_bid(&mut self, price: Decimal, quantity: Decimal) {
         let key = ordered_float::OrderedFloat(price.to_f64().unwrap_or(0.0));
         if quantity.is_zero() {
@@ -135,7 +151,7 @@ impl OrderBook {
         } else {
             self.bids.insert(key, OrderBookLevel::new(price, quantity));
         }
-        self.timestamp = Utc::now();
+        self.timestamp = coarse_now();
     }
 
     pub fn update_ask(&mut self, price: Decimal, quantity: Decimal) {
@@ -145,7 +161,7 @@ impl OrderBook {
         } else {
             self.asks.insert(key, OrderBookLevel::new(price, quantity));
         }
-        self.timestamp = Utc::now();
+        self.timestamp = coarse_now();
     }
 }
 
@@ -195,16 +211,149 @@ pub struct Candle {
     pub low: Decimal,
     pub close: Decimal,
     pub volume: Decimal,
+    /// When the candle opens.
     pub timestamp: DateTime<Utc>,
     pub interval: String,
 }
 
+/// Width of a candle. Not every venue offers every width.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CandleInterval {
+    OneMinute,
+    FiveMinutes,
+    FifteenMinutes,
+    ThirtyMinutes,
+    OneHour,
+    FourHours,
+    SixHours,
+    OneDay,
+}
+
+impl CandleInterval {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CandleInterval::OneMinute => "1m",
+            CandleInterval::FiveMinutes => "5m",
+            CandleInterval::FifteenMinutes => "15m",
+            CandleInterval::ThirtyMinutes => "30m",
+            CandleInterval::OneHour => "1h",
+            CandleInterval::FourHours => "4h",
+            CandleInterval::SixHours => "6h",
+            CandleInterval::OneDay => "1d",
+        }
+    }
+
+    pub fn minutes(&self) -> u32 {
+        match self {
+            CandleInterval::OneMinute => 1,
+            CandleInterval::FiveMinutes => 5,
+            CandleInterval::FifteenMinutes => 15,
+            CandleInterval::ThirtyMinutes => 30,
+            CandleInterval::OneHour => 60,
+            CandleInterval::FourHours => 240,
+            CandleInterval::SixHours => 360,
+            CandleInterval::OneDay => 1440,
+        }
+    }
+
+    pub fn duration(&self) -> chrono::Duration {
+        chrono::Duration::minutes(self.minutes() as i64)
+    }
+}
+
+impl fmt::Display for CandleInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for CandleInterval {
+    type Err = crate::ArbFinderError;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        [
+            CandleInterval::OneMinute,
+            CandleInterval::FiveMinutes,
+            CandleInterval::FifteenMinutes,
+            CandleInterval::ThirtyMinutes,
+            CandleInterval::OneHour,
+            CandleInterval::FourHours,
+            CandleInterval::SixHours,
+            CandleInterval::OneDay,
+        ]
+        .into_iter()
+        .find(|interval| interval.as_str().eq_ignore_ascii_case(s))
+        .ok_or_else(|| crate::ArbFinderError::InvalidData(format!("Unknown candle interval: {}", s)))
+    }
+}
+
+/// Mark and index price of a derivative instrument.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarkPrice {
+    pub symbol: Symbol,
+    pub mark_price: Decimal,
+    pub index_price: Decimal,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Funding of a perpetual contract, as fractions of notional.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FundingRate {
+    pub symbol: Symbol,
+    /// Rate currently accruing.
+    pub current_rate: Decimal,
+    /// Rate normalised to an 8 hour period, where the venue reports one.
+    pub rate_8h: Option<Decimal>,
+    pub timestamp: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum MarketDataType {
     OrderBook,
     Trade,
     Ticker,
     Candle,
+    MarkPrice,
+    Funding,
+}
+
+/// How much of each venue's book a deployment follows.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketDataMode {
+    /// Depth streams, kept as full books.
+    #[default]
+    Full,
+    /// Best bid and offer streams only, for small hosts: books are kept one
+    /// level deep and opportunities are found on the top of book alone.
+    BboOnly,
+}
+
+impl MarketDataMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MarketDataMode::Full => "full",
+            MarketDataMode::BboOnly => "bbo_only",
+        }
+    }
+}
+
+impl fmt::Display for MarketDataMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for MarketDataMode {
+    type Err = crate::ArbFinderError;
+
+    fn from_str(s: &str) -> crate::Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "full" => Ok(MarketDataMode::Full),
+            "bbo_only" | "bbo" => Ok(MarketDataMode::BboOnly),
+            _ => Err(crate::ArbFinderError::InvalidData(format!("Unknown market data mode: {}", s))),
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -213,6 +362,8 @@ pub enum MarketData {
     Trade(Trade),
     Ticker(Ticker),
     Candle(Candle),
+    MarkPrice(MarkPrice),
+    Funding(FundingRate),
 }
 
 impl MarketData {
@@ -222,6 +373,8 @@ impl MarketData {
             MarketData::Trade(data) => &data.symbol,
             MarketData::Ticker(data) => &data.symbol,
             MarketData::Candle(data) => &data.symbol,
+            MarketData::MarkPrice(data) => &data.symbol,
+            MarketData::Funding(data) => &data.symbol,
         }
     }
 
@@ -231,6 +384,8 @@ impl MarketData {
             MarketData::Trade(data) => data.timestamp,
             MarketData::Ticker(data) => data.timestamp,
             MarketData::Candle(data) => data.timestamp,
+            MarketData::MarkPrice(data) => data.timestamp,
+            MarketData::Funding(data) => data.timestamp,
         }
     }
 
@@ -240,6 +395,8 @@ impl MarketData {
             MarketData::Trade(_) => MarketDataType::Trade,
             MarketData::Ticker(_) => MarketDataType::Ticker,
             MarketData::Candle(_) => MarketDataType::Candle,
+            MarketData::MarkPrice(_) => MarketDataType::MarkPrice,
+            MarketData::Funding(_) => MarketDataType::Funding,
         }
     }
 }
\ No newline at end of file
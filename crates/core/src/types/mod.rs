@@ -2,8 +2,16 @@ pub mod arbitrage;
 pub mod market;
 pub mod order;
 pub mod venue;
+pub mod symbol_id;
+pub mod event_time;
+pub mod record;
+pub mod secret;
 
 pub use arbitrage::*;
 pub use market::*;
 pub use order::*;
-pub use venue::*;
\ No newline at end of file
+pub use venue::*;
+pub use symbol_id::*;
+pub use event_time::*;
+pub use record::*;
+pub use secret::*;
\ No newline at end of file
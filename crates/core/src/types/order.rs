@@ -4,6 +4,7 @@ use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
 use super::{Symbol, VenueId};
+use crate::{ArbFinderError, Result};
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct OrderId(pub Uuid);
@@ -54,6 +55,9 @@ pub enum OrderType {
     PostOnly,
     FillOrKill,
     ImmediateOrCancel,
+    /// A take-profit limit at `price` and a stop-loss at `stop_price`;
+    /// whichever fills first cancels the other.
+    Oco,
 }
 
 impl std::fmt::Display for OrderType {
@@ -66,6 +70,7 @@ impl std::fmt::Display for OrderType {
             OrderType::PostOnly => write!(f, "post_only"),
             OrderType::FillOrKill => write!(f, "fill_or_kill"),
             OrderType::ImmediateOrCancel => write!(f, "immediate_or_cancel"),
+            OrderType::Oco => write!(f, "oco"),
         }
     }
 }
@@ -278,6 +283,16 @@ pub struct OrderUpdate {
     pub reason: Option<String>,
 }
 
+/// What an order request's quantity counts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub enum SizeUnit {
+    /// Units of the base asset.
+    #[default]
+    Base,
+    /// Quote currency to spend on a buy or receive from a sell.
+    Quote,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OrderRequest {
     pub client_order_id: Option<String>,
@@ -285,8 +300,14 @@ pub struct OrderRequest {
     pub side: OrderSide,
     pub order_type: OrderType,
     pub quantity: Decimal,
+    #[serde(default)]
+    pub size_unit: SizeUnit,
     pub price: Option<Decimal>,
     pub stop_price: Option<Decimal>,
+    /// Limit price the stop-loss leg of an OCO order rests at once
+    /// triggered; without one it executes at market.
+    #[serde(default)]
+    pub stop_limit_price: Option<Decimal>,
     pub time_in_force: TimeInForce,
     pub post_only: bool,
     pub reduce_only: bool,
@@ -300,8 +321,10 @@ impl OrderRequest {
             side,
             order_type: OrderType::Market,
             quantity,
+            size_unit: SizeUnit::Base,
             price: None,
             stop_price: None,
+            stop_limit_price: None,
             time_in_force: TimeInForce::ImmediateOrCancel,
             post_only: false,
             reduce_only: false,
@@ -315,14 +338,85 @@ impl OrderRequest {
             side,
             order_type: OrderType::Limit,
             quantity,
+            size_unit: SizeUnit::Base,
             price: Some(price),
             stop_price: None,
+            stop_limit_price: None,
             time_in_force: TimeInForce::GoodTillCanceled,
             post_only: false,
             reduce_only: false,
         }
     }
 
+    /// Protects a position with a take-profit limit at `take_profit` and a
+    /// stop-loss triggered at `stop`, of which only one can fill. Selling,
+    /// the take profit is above the stop; buying back a short, below it.
+    pub fn new_oco(symbol: Symbol, side: OrderSide, quantity: Decimal, take_profit: Decimal, stop: Decimal) -> Self {
+        Self {
+            order_type: OrderType::Oco,
+            stop_price: Some(stop),
+            ..Self::new_limit(symbol, side, quantity, take_profit)
+        }
+    }
+
+    /// Makes the stop-loss leg of an OCO order a limit at `price`.
+    pub fn with_stop_limit(mut self, price: Decimal) -> Self {
+        self.stop_limit_price = Some(price);
+        self
+    }
+
+    /// Market order for `notional` of the quote currency, e.g. spend 1000
+    /// USDT on BTC.
+    pub fn new_market_quote(symbol: Symbol, side: OrderSide, notional: Decimal) -> Self {
+        Self::new_market(symbol, side, notional).in_quote()
+    }
+
+    /// Reads the quantity as an amount of the quote currency.
+    pub fn in_quote(mut self) -> Self {
+        self.size_unit = SizeUnit::Quote;
+        self
+    }
+
+    /// The quantity in base units at `price`, rounded down to a multiple of
+    /// `lot_size` (a zero lot size leaves it unrounded). Fails when that
+    /// comes to nothing.
+    pub fn base_quantity(&self, price: Decimal, lot_size: Decimal) -> Result<Decimal> {
+        let quantity = match self.size_unit {
+            SizeUnit::Base => return Ok(self.quantity),
+            SizeUnit::Quote if price > Decimal::ZERO => self.quantity / price,
+            SizeUnit::Quote => {
+                return Err(ArbFinderError::InvalidOrder(format!(
+                    "Cannot size {} {} at price {}",
+                    self.quantity,
+                    self.symbol.quote(),
+                    price
+                )))
+            }
+        };
+        let quantity = if lot_size > Decimal::ZERO {
+            (quantity / lot_size).floor() * lot_size
+        } else {
+            quantity
+        };
+        if quantity <= Decimal::ZERO {
+            return Err(ArbFinderError::InvalidOrder(format!(
+                "{} {} buys less than one lot of {} at {}",
+                self.quantity,
+                self.symbol.quote(),
+                self.symbol.base(),
+                price
+            )));
+        }
+        Ok(quantity.normalize())
+    }
+
+    /// This request sized in base units; see [`OrderRequest::base_quantity`].
+    pub fn into_base(mut self, price: Decimal, lot_size: Decimal) -> Result<Self> {
+        self.quantity = self.base_quantity(price, lot_size)?;
+        self.size_unit = SizeUnit::Base;
+        Ok(self)
+    }
+
     pub fn with_client_id(mut self, client_id: String) -> Self {
         self.client_order_id = Some(client_id);
         self
@@ -337,4 +431,47 @@ impl OrderRequest {
         self.post_only = true;
         self
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote_size_rounds_down_to_lot() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let spend = OrderRequest::new_market_quote(symbol.clone(), OrderSide::Buy, Decimal::from(1000));
+        assert_eq!(spend.size_unit, SizeUnit::Quote);
+
+        // 1000 / 30000 = 0.0333..., floored to 0.0001 lots
+        let base = spend.clone().into_base(Decimal::from(30000), Decimal::new(1, 4)).unwrap();
+        assert_eq!(base.quantity, Decimal::new(333, 4));
+        assert_eq!(base.size_unit, SizeUnit::Base);
+
+        assert!(spend.base_quantity(Decimal::from(30000), Decimal::ONE).is_err());
+        assert!(spend.base_quantity(Decimal::ZERO, Decimal::ZERO).is_err());
+
+        let plain = OrderRequest::new_market(symbol, OrderSide::Sell, Decimal::from(2));
+        assert_eq!(plain.base_quantity(Decimal::from(30000), Decimal::ONE).unwrap(), Decimal::from(2));
+    }
+
+    #[test]
+    fn test_size_unit_defaults_to_base() {
+        let request = OrderRequest::new_market(Symbol::new("ETH", "USDT"), OrderSide::Buy, Decimal::ONE);
+        let mut value = serde_json::to_value(&request).unwrap();
+        value.as_object_mut().unwrap().remove("size_unit");
+        let loaded: OrderRequest = serde_json::from_value(value).unwrap();
+        assert_eq!(loaded.size_unit, SizeUnit::Base);
+    }
+
+    #[test]
+    fn test_oco_request() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let oco = OrderRequest::new_oco(symbol, OrderSide::Sell, Decimal::ONE, Decimal::from(32000), Decimal::from(29000))
+            .with_stop_limit(Decimal::from(28900));
+        assert_eq!(oco.order_type, OrderType::Oco);
+        assert_eq!(oco.price, Some(Decimal::from(32000)));
+        assert_eq!(oco.stop_price, Some(Decimal::from(29000)));
+        assert_eq!(oco.stop_limit_price, Some(Decimal::from(28900)));
+        assert_eq!(oco.time_in_force, TimeInForce::GoodTillCanceled);
+    }
+}
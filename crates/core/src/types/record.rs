@@ -0,0 +1,174 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::{ArbitrageOpportunity, Side, Symbol, Trade, VenueId};
+use crate::error::{ArbFinderError, Result};
+
+/// Field carrying the schema version in every persisted record.
+pub const SCHEMA_VERSION_FIELD: &str = "schema_version";
+
+/// Schema version of a raw record. Records written before versioning was
+/// introduced have no field and are version 0.
+pub fn schema_version_of(record: &Value) -> u32 {
+    record
+        .get(SCHEMA_VERSION_FIELD)
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+        .unwrap_or(0)
+}
+
+/// A record type that is persisted or exported and must stay loadable after
+/// its layout changes.
+///
+/// Loading goes through raw JSON: the stored version is read, `upgrade` is
+/// applied one version at a time up to `SCHEMA_VERSION`, and only then is
+/// the value deserialized into the current type.
+pub trait Versioned: DeserializeOwned {
+    const SCHEMA_VERSION: u32;
+
+    /// Rewrites a record from version `from` to `from + 1`. The default is
+    /// the identity, which is correct while the only change between versions
+    /// is the addition of the `schema_version` field itself.
+    fn upgrade(record: Value, _from: u32) -> Result<Value> {
+        Ok(record)
+    }
+
+    fn from_versioned_value(mut record: Value) -> Result<Self> {
+        let stored = schema_version_of(&record);
+        if stored > Self::SCHEMA_VERSION {
+            return Err(ArbFinderError::InvalidData(format!(
+                "record has schema version {}, newest supported is {}",
+                stored,
+                Self::SCHEMA_VERSION
+            )));
+        }
+
+        for from in stored..Self::SCHEMA_VERSION {
+            record = Self::upgrade(record, from)?;
+        }
+        if let Some(fields) = record.as_object_mut() {
+            fields.insert(SCHEMA_VERSION_FIELD.to_string(), Self::SCHEMA_VERSION.into());
+        }
+
+        Ok(serde_json::from_value(record)?)
+    }
+
+    fn from_versioned_str(json: &str) -> Result<Self> {
+        Self::from_versioned_value(serde_json::from_str(json)?)
+    }
+}
+
+/// Persisted form of a detected opportunity.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpportunityRecord {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub id: String,
+    pub symbol: Symbol,
+    pub buy_venue: VenueId,
+    pub sell_venue: VenueId,
+    pub buy_price: Decimal,
+    pub sell_price: Decimal,
+    /// Net of fees, as a fraction (0.001 = 0.1%).
+    pub profit_percentage: Decimal,
+    pub max_quantity: Decimal,
+    pub estimated_profit: Decimal,
+    pub detected_at: DateTime<Utc>,
+}
+
+impl Versioned for OpportunityRecord {
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl From<&ArbitrageOpportunity> for OpportunityRecord {
+    fn from(opportunity: &ArbitrageOpportunity) -> Self {
+        Self {
+            schema_version: Self::SCHEMA_VERSION,
+            id: opportunity.id.clone(),
+            symbol: opportunity.symbol.clone(),
+            buy_venue: opportunity.buy_venue.clone(),
+            sell_venue: opportunity.sell_venue.clone(),
+            buy_price: opportunity.buy_price,
+            sell_price: opportunity.sell_price,
+            // spread_percentage is in percent
+            profit_percentage: opportunity.spread_percentage / Decimal::from(100),
+            max_quantity: opportunity.max_quantity,
+            estimated_profit: opportunity.estimated_profit,
+            detected_at: opportunity.created_at,
+        }
+    }
+}
+
+/// Persisted form of a public trade, tagged with the venue it printed on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradeRecord {
+    #[serde(default)]
+    pub schema_version: u32,
+    pub venue_id: VenueId,
+    pub symbol: Symbol,
+    pub trade_id: String,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub side: Side,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Versioned for TradeRecord {
+    const SCHEMA_VERSION: u32 = 1;
+}
+
+impl TradeRecord {
+    pub fn new(venue_id: VenueId, trade: &Trade) -> Self {
+        Self {
+            schema_version: Self::SCHEMA_VERSION,
+            venue_id,
+            symbol: trade.symbol.clone(),
+            trade_id: trade.trade_id.clone(),
+            price: trade.price,
+            quantity: trade.quantity,
+            side: trade.side,
+            timestamp: trade.timestamp,
+        }
+    }
+
+    pub fn to_trade(&self) -> Trade {
+        let mut trade = Trade::new(
+            self.symbol.clone(),
+            self.price,
+            self.quantity,
+            self.side,
+            self.trade_id.clone(),
+        );
+        trade.timestamp = self.timestamp;
+        trade
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unversioned_record_loads_as_current() {
+        let legacy = r#"{"venue_id":"Binance","symbol":{"base":"BTC","quote":"USDT"},"trade_id":"1","price":"100","quantity":"2","side":"Bid","timestamp":"2024-01-01T00:00:00Z"}"#;
+        let record = TradeRecord::from_versioned_str(legacy).unwrap();
+        assert_eq!(record.schema_version, TradeRecord::SCHEMA_VERSION);
+        assert_eq!(record.to_trade().price, Decimal::from(100));
+
+        let current = serde_json::to_value(&record).unwrap();
+        assert_eq!(schema_version_of(&current), 1);
+        assert_eq!(TradeRecord::from_versioned_value(current).unwrap(), record);
+    }
+
+    #[test]
+    fn test_newer_schema_is_rejected() {
+        let future = r#"{"schema_version":99,"venue_id":"Binance"}"#;
+        assert!(matches!(
+            TradeRecord::from_versioned_str(future),
+            Err(ArbFinderError::InvalidData(_))
+        ));
+    }
+}
@@ -0,0 +1,119 @@
+//! Strings that must not end up in logs or linger in memory: API keys,
+//! secrets and passphrases.
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use zeroize::Zeroize;
+
+/// What a [`SecretString`] prints as.
+pub const REDACTED_SECRET: &str = "[REDACTED]";
+
+/// A string printed as [`REDACTED_SECRET`] by `Debug` and `Display` and
+/// overwritten with zeros when dropped. [`SecretString::expose`] is the
+/// only way to read it. It serializes as the plain string, for writing
+/// config back out. Comparisons take the same time wherever the two
+/// strings first differ.
+#[derive(Clone, Default)]
+pub struct SecretString(String);
+
+impl SecretString {
+    pub fn new(secret: impl Into<String>) -> Self {
+        Self(secret.into())
+    }
+
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl PartialEq for SecretString {
+    fn eq(&self, other: &Self) -> bool {
+        let (a, b) = (self.0.as_bytes(), other.0.as_bytes());
+        if a.len() != b.len() {
+            return false;
+        }
+        a.iter().zip(b).fold(0u8, |diff, (x, y)| diff | (x ^ y)) == 0
+    }
+}
+
+impl Eq for SecretString {}
+
+impl Drop for SecretString {
+    fn drop(&mut self) {
+        self.0.zeroize();
+    }
+}
+
+impl From<String> for SecretString {
+    fn from(secret: String) -> Self {
+        Self(secret)
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(secret: &str) -> Self {
+        Self(secret.to_string())
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", REDACTED_SECRET)
+    }
+}
+
+impl fmt::Display for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", REDACTED_SECRET)
+    }
+}
+
+impl Serialize for SecretString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for SecretString {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        String::deserialize(deserializer).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::VenueCredentials;
+
+    #[test]
+    fn test_secrets_are_redacted() {
+        let credentials = VenueCredentials {
+            api_key: "key-123".into(),
+            secret_key: "secret-456".into(),
+            passphrase: Some("pass-789".into()),
+            sandbox: false,
+        };
+        let printed = format!("{:?} {}", credentials, credentials.secret_key);
+        assert!(!printed.contains("key-123"));
+        assert!(!printed.contains("secret-456"));
+        assert!(!printed.contains("pass-789"));
+        assert!(printed.contains(REDACTED_SECRET));
+
+        assert_eq!(credentials.secret_key.expose(), "secret-456");
+        let json = serde_json::to_string(&credentials).unwrap();
+        let parsed: VenueCredentials = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, credentials);
+    }
+
+    #[test]
+    fn test_secret_equality() {
+        assert_eq!(SecretString::from("secret"), SecretString::from("secret"));
+        assert_ne!(SecretString::from("secret"), SecretString::from("secreT"));
+        assert_ne!(SecretString::from("secret"), SecretString::from("secrets"));
+        assert_eq!(SecretString::default(), SecretString::from(""));
+    }
+}
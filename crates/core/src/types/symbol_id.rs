@@ -0,0 +1,175 @@
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::RwLock;
+
+use super::market::Symbol;
+
+/// Interned handle for a [`Symbol`].
+///
+/// Comparing and hashing a `SymbolId` is a single `u32` operation, so it is
+/// the key type for maps on the market data path. Interning takes a global
+/// lock and hashes the whole symbol, so it is done once where a symbol
+/// comes in, on subscribing or parsing a venue message, and the id passed
+/// on from there. Ids are process-local and handed out in first-seen order;
+/// serde goes through the symbol itself so nothing persisted depends on
+/// them.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SymbolId(u32);
+
+#[derive(Default)]
+struct SymbolRegistry {
+    ids: HashMap<Symbol, SymbolId>,
+    // Leaked so lookups can hand out `&'static Symbol`; the set of traded
+    // symbols is small and never shrinks.
+    symbols: Vec<&'static Symbol>,
+}
+
+lazy_static::lazy_static! {
+    static ref REGISTRY: RwLock<SymbolRegistry> = RwLock::new(SymbolRegistry::default());
+}
+
+impl SymbolId {
+    /// Returns the id for `symbol`, registering it on first use.
+    pub fn intern(symbol: &Symbol) -> Self {
+        if let Some(id) = Self::lookup(symbol) {
+            return id;
+        }
+
+        let mut registry = REGISTRY.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(id) = registry.ids.get(symbol) {
+            return *id;
+        }
+        let id = SymbolId(registry.symbols.len() as u32);
+        let leaked: &'static Symbol = Box::leak(Box::new(symbol.clone()));
+        registry.symbols.push(leaked);
+        registry.ids.insert(symbol.clone(), id);
+        id
+    }
+
+    /// Returns the id for `symbol` only if it has already been interned.
+    pub fn lookup(symbol: &Symbol) -> Option<Self> {
+        REGISTRY.read().unwrap_or_else(|e| e.into_inner()).ids.get(symbol).copied()
+    }
+
+    pub fn symbol(self) -> &'static Symbol {
+        REGISTRY.read().unwrap_or_else(|e| e.into_inner()).symbols[self.0 as usize]
+    }
+
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
+impl Symbol {
+    pub fn id(&self) -> SymbolId {
+        SymbolId::intern(self)
+    }
+}
+
+impl From<&Symbol> for SymbolId {
+    fn from(symbol: &Symbol) -> Self {
+        SymbolId::intern(symbol)
+    }
+}
+
+impl From<Symbol> for SymbolId {
+    fn from(symbol: Symbol) -> Self {
+        SymbolId::intern(&symbol)
+    }
+}
+
+impl From<SymbolId> for Symbol {
+    fn from(id: SymbolId) -> Self {
+        id.symbol().clone()
+    }
+}
+
+impl fmt::Debug for SymbolId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SymbolId({}: {})", self.0, self.symbol())
+    }
+}
+
+impl fmt::Display for SymbolId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.symbol(), f)
+    }
+}
+
+impl Serialize for SymbolId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.symbol().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SymbolId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Symbol::deserialize(deserializer).map(|symbol| SymbolId::intern(&symbol))
+    }
+}
+
+/// Serde for maps keyed by `SymbolId`, keyed by pair (`BTC/USDT`) instead,
+/// as formats such as JSON only take string keys.
+pub mod by_pair {
+    use super::*;
+    use serde::de::Error;
+
+    pub fn serialize<S: Serializer, V: Serialize>(map: &HashMap<SymbolId, V>, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(map.iter().map(|(id, value)| (id.symbol().to_pair(), value)))
+    }
+
+    pub fn deserialize<'de, D, V>(deserializer: D) -> Result<HashMap<SymbolId, V>, D::Error>
+    where
+        D: Deserializer<'de>,
+        V: Deserialize<'de>,
+    {
+        HashMap::<String, V>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(pair, value)| {
+                let symbol = Symbol::from_pair(&pair).ok_or_else(|| D::Error::custom(format!("invalid pair {}", pair)))?;
+                Ok((symbol.id(), value))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interning_is_stable() {
+        let btc = Symbol::new("BTC", "USDT");
+        let id = btc.id();
+
+        assert_eq!(id, SymbolId::intern(&Symbol::new("BTC", "USDT")));
+        assert_ne!(id, Symbol::new("ETH", "USDT").id());
+        assert_eq!(id.symbol(), &btc);
+        assert_eq!(id.to_string(), "BTC/USDT");
+        assert_eq!(SymbolId::lookup(&btc), Some(id));
+    }
+
+    #[test]
+    fn test_serde_uses_symbol() {
+        let id = Symbol::new("SOL", "USDC").id();
+        let json = serde_json::to_string(&id).unwrap();
+        assert_eq!(json, serde_json::to_string(&Symbol::new("SOL", "USDC")).unwrap());
+
+        let back: SymbolId = serde_json::from_str(&json).unwrap();
+        assert_eq!(back, id);
+    }
+
+    #[test]
+    fn test_maps_keyed_by_pair() {
+        #[derive(Serialize, Deserialize)]
+        struct Positions(#[serde(with = "by_pair")] HashMap<SymbolId, u32>);
+
+        let positions = Positions(HashMap::from([(Symbol::new("ETH", "USDC").id(), 3)]));
+        let json = serde_json::to_string(&positions).unwrap();
+        assert_eq!(json, r#"{"ETH/USDC":3}"#);
+
+        let back: Positions = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.0[&Symbol::new("ETH", "USDC").id()], 3);
+    }
+}
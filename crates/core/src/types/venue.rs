@@ -4,14 +4,28 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt;
 
+use super::secret::SecretString;
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum VenueId {
     Binance,
     Coinbase,
     Kraken,
     Bitfinex,
+    Poloniex,
     Huobi,
     OKX,
+    Gemini,
+    Mexc,
+    Upbit,
+    Bithumb,
+    Bitget,
+    Deribit,
+    Hyperliquid,
+    Uniswap,
+    Curve,
+    Jupiter,
+    ZeroEx,
     Custom(String),
 }
 
@@ -22,8 +36,20 @@ impl fmt::Display for VenueId {
             VenueId::Coinbase => write!(f, "coinbase"),
             VenueId::Kraken => write!(f, "kraken"),
             VenueId::Bitfinex => write!(f, "bitfinex"),
+            VenueId::Poloniex => write!(f, "poloniex"),
             VenueId::Huobi => write!(f, "huobi"),
             VenueId::OKX => write!(f, "okx"),
+            VenueId::Gemini => write!(f, "gemini"),
+            VenueId::Mexc => write!(f, "mexc"),
+            VenueId::Upbit => write!(f, "upbit"),
+            VenueId::Bithumb => write!(f, "bithumb"),
+            VenueId::Bitget => write!(f, "bitget"),
+            VenueId::Deribit => write!(f, "deribit"),
+            VenueId::Hyperliquid => write!(f, "hyperliquid"),
+            VenueId::Uniswap => write!(f, "uniswap"),
+            VenueId::Curve => write!(f, "curve"),
+            VenueId::Jupiter => write!(f, "jupiter"),
+            VenueId::ZeroEx => write!(f, "zeroex"),
             VenueId::Custom(name) => write!(f, "{}", name),
         }
     }
@@ -36,8 +62,20 @@ impl From<&str> for VenueId {
             "coinbase" => VenueId::Coinbase,
             "kraken" => VenueId::Kraken,
             "bitfinex" => VenueId::Bitfinex,
+            "poloniex" => VenueId::Poloniex,
             "huobi" => VenueId::Huobi,
             "okx" => VenueId::OKX,
+            "gemini" => VenueId::Gemini,
+            "mexc" => VenueId::Mexc,
+            "upbit" => VenueId::Upbit,
+            "bithumb" => VenueId::Bithumb,
+            "bitget" => VenueId::Bitget,
+            "deribit" => VenueId::Deribit,
+            "hyperliquid" => VenueId::Hyperliquid,
+            "uniswap" => VenueId::Uniswap,
+            "curve" => VenueId::Curve,
+            "jupiter" => VenueId::Jupiter,
+            "zeroex" | "0x" => VenueId::ZeroEx,
             name => VenueId::Custom(name.to_string()),
         }
     }
@@ -64,6 +102,96 @@ impl Balance {
     }
 }
 
+/// Where a venue credits deposits of an asset.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepositAddress {
+    pub asset: String,
+    pub address: String,
+    /// Memo, destination tag or payment id some chains need besides the
+    /// address.
+    pub tag: Option<String>,
+    /// Chain the address is on, as the venue names it; `None` for the
+    /// asset's default.
+    pub network: Option<String>,
+}
+
+impl DepositAddress {
+    pub fn new(asset: impl Into<String>, address: impl Into<String>) -> Self {
+        Self {
+            asset: asset.into(),
+            address: address.into(),
+            tag: None,
+            network: None,
+        }
+    }
+}
+
+/// A withdrawal a venue accepted. It settles later, on chain.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Withdrawal {
+    /// The venue's id for the withdrawal.
+    pub id: String,
+    pub venue: VenueId,
+    pub asset: String,
+    pub amount: Decimal,
+    pub address: DepositAddress,
+    pub requested_at: DateTime<Utc>,
+}
+
+/// How far a withdrawal or deposit has got.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferStatus {
+    /// Accepted, not yet on chain or not yet seen there.
+    Pending,
+    /// On chain, waiting for confirmations.
+    Confirming,
+    /// Sent, for withdrawals; credited, for deposits.
+    Completed,
+    /// Canceled, rejected or failed.
+    Failed,
+}
+
+impl TransferStatus {
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed)
+    }
+}
+
+impl fmt::Display for TransferStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Pending => write!(f, "pending"),
+            Self::Confirming => write!(f, "confirming"),
+            Self::Completed => write!(f, "completed"),
+            Self::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+/// A withdrawal's progress as the sending venue reports it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WithdrawalProgress {
+    pub status: TransferStatus,
+    /// The chain transaction, once the venue has sent it.
+    pub tx_id: Option<String>,
+}
+
+/// A deposit to an account, credited or on its way.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Deposit {
+    /// The venue's id for the deposit.
+    pub id: String,
+    pub venue: VenueId,
+    pub asset: String,
+    pub amount: Decimal,
+    pub tx_id: Option<String>,
+    pub status: TransferStatus,
+    /// Confirmations seen so far, for venues that report them.
+    pub confirmations: Option<u32>,
+    pub time: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TradingFee {
     pub maker_fee: Decimal,
@@ -87,6 +215,49 @@ impl TradingFee {
             tier: Some(tier),
         }
     }
+
+    /// A negative maker fee is a rebate paid for resting liquidity.
+    pub fn has_maker_rebate(&self) -> bool {
+        self.maker_fee < Decimal::ZERO
+    }
+
+    /// Fee rate for an order that adds (`maker`) or removes liquidity.
+    pub fn rate(&self, maker: bool) -> Decimal {
+        if maker {
+            self.maker_fee
+        } else {
+            self.taker_fee
+        }
+    }
+}
+
+/// Rates a venue charges instead when fees are paid from a balance of
+/// `asset`, such as BNB on Binance, rather than out of the assets traded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FeeDiscount {
+    pub asset: String,
+    pub maker_fee: Decimal,
+    pub taker_fee: Decimal,
+}
+
+impl FeeDiscount {
+    pub fn new(asset: impl Into<String>, maker_fee: Decimal, taker_fee: Decimal) -> Self {
+        Self {
+            asset: asset.into(),
+            maker_fee,
+            taker_fee,
+        }
+    }
+
+    /// Discounted rate for an order that adds (`maker`) or removes
+    /// liquidity.
+    pub fn rate(&self, maker: bool) -> Decimal {
+        if maker {
+            self.maker_fee
+        } else {
+            self.taker_fee
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -125,9 +296,9 @@ impl Default for RateLimits {
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VenueCredentials {
-    pub api_key: String,
-    pub secret_key: String,
-    pub passphrase: Option<String>,
+    pub api_key: SecretString,
+    pub secret_key: SecretString,
+    pub passphrase: Option<SecretString>,
     pub sandbox: bool,
 }
 
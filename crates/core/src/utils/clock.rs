@@ -0,0 +1,62 @@
+use chrono::{DateTime, Utc};
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Once;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// How often the background thread refreshes the coarse clock.
+pub const COARSE_CLOCK_RESOLUTION: Duration = Duration::from_millis(1);
+
+static COARSE_NOW_MS: AtomicI64 = AtomicI64::new(0);
+static START: Once = Once::new();
+
+fn system_now_ms() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as i64)
+        .unwrap_or(0)
+}
+
+/// Starts the thread backing [`coarse_now`]. Called lazily on first use, so
+/// calling it explicitly only moves the startup cost out of the hot path.
+pub fn start_coarse_clock() {
+    START.call_once(|| {
+        COARSE_NOW_MS.store(system_now_ms(), Ordering::Relaxed);
+        std::thread::Builder::new()
+            .name("coarse-clock".to_string())
+            .spawn(|| loop {
+                std::thread::sleep(COARSE_CLOCK_RESOLUTION);
+                COARSE_NOW_MS.store(system_now_ms(), Ordering::Relaxed);
+            })
+            .expect("failed to spawn coarse clock thread");
+    });
+}
+
+/// Millisecond timestamp refreshed roughly every [`COARSE_CLOCK_RESOLUTION`].
+///
+/// An atomic load instead of a clock syscall, for bookkeeping timestamps on
+/// hot paths such as order book level updates. It can lag real time by a few
+/// milliseconds under scheduler pressure, so use `Utc::now()` for anything
+/// that measures latency or is sent to an exchange.
+pub fn coarse_now_ms() -> i64 {
+    start_coarse_clock();
+    COARSE_NOW_MS.load(Ordering::Relaxed)
+}
+
+pub fn coarse_now() -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(coarse_now_ms()).unwrap_or_else(Utc::now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_coarse_clock_tracks_system_time() {
+        let coarse = coarse_now();
+        let precise = Utc::now();
+        assert!((precise - coarse).num_milliseconds().abs() < 1000);
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(coarse_now() > coarse);
+    }
+}
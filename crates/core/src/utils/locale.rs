@@ -0,0 +1,188 @@
+//! Number and time formatting for reports read by people.
+//!
+//! Reports and CSV exports render numbers with the operator's decimal
+//! separator and digit grouping, and timestamps in their timezone. Machine
+//! formats (JSON, recordings, logs) keep `.` decimals and UTC ISO 8601
+//! timestamps whatever is configured here.
+
+use std::fmt;
+use std::str::FromStr;
+
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use rust_decimal::{Decimal, RoundingStrategy};
+
+use crate::{ArbFinderError, Result};
+
+/// Timezone timestamps in reports are shown in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportTimezone {
+    #[default]
+    Utc,
+    /// The host's timezone, including its daylight saving changes.
+    Local,
+    Fixed(FixedOffset),
+}
+
+impl FromStr for ReportTimezone {
+    type Err = ArbFinderError;
+
+    /// `UTC`, `local`, or an offset such as `+02:00` or `-0530`.
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "utc" | "z" => Ok(ReportTimezone::Utc),
+            "local" => Ok(ReportTimezone::Local),
+            _ => {
+                let offset = DateTime::parse_from_str(&format!("2000-01-01 00:00 {}", s), "%Y-%m-%d %H:%M %z")
+                    .map_err(|_| ArbFinderError::InvalidData(format!("Unknown timezone: {}", s)))?;
+                Ok(ReportTimezone::Fixed(*offset.offset()))
+            }
+        }
+    }
+}
+
+impl fmt::Display for ReportTimezone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReportTimezone::Utc => write!(f, "UTC"),
+            ReportTimezone::Local => write!(f, "local"),
+            ReportTimezone::Fixed(offset) => write!(f, "{}", offset),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportLocale {
+    pub decimal_separator: char,
+    /// Separates groups of three integer digits; `None` leaves them
+    /// ungrouped.
+    pub thousands_separator: Option<char>,
+    pub timezone: ReportTimezone,
+}
+
+impl Default for ReportLocale {
+    fn default() -> Self {
+        Self {
+            decimal_separator: '.',
+            thousands_separator: None,
+            timezone: ReportTimezone::Utc,
+        }
+    }
+}
+
+impl ReportLocale {
+    /// Errors when numbers could not be read back: separators that are
+    /// digits or signs, or the same for decimals and thousands.
+    pub fn validate(&self) -> Result<()> {
+        let separators = std::iter::once(self.decimal_separator).chain(self.thousands_separator);
+        for separator in separators {
+            if separator.is_ascii_digit() || separator == '-' || separator == '+' {
+                return Err(ArbFinderError::InvalidData(format!("Invalid number separator: {:?}", separator)));
+            }
+        }
+        if self.thousands_separator == Some(self.decimal_separator) {
+            return Err(ArbFinderError::InvalidData(
+                "Decimal and thousands separators must differ".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn decimal(&self, value: Decimal) -> String {
+        self.localize(&value.normalize().to_string())
+    }
+
+    /// `value` rounded to exactly `dp` decimals, halves away from zero.
+    pub fn fixed(&self, value: Decimal, dp: u32) -> String {
+        let rounded = value.round_dp_with_strategy(dp, RoundingStrategy::MidpointAwayFromZero);
+        self.localize(&format!("{:.*}", dp as usize, rounded))
+    }
+
+    pub fn float(&self, value: f64) -> String {
+        self.localize(&value.to_string())
+    }
+
+    /// Rewrites a plain number such as `-1234.5` with this locale's
+    /// separators.
+    fn localize(&self, plain: &str) -> String {
+        let (sign, digits) = match plain.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", plain),
+        };
+        let (integer, fraction) = match digits.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (digits, None),
+        };
+
+        let mut out = String::with_capacity(plain.len() + integer.len() / 3);
+        out.push_str(sign);
+        for (i, digit) in integer.chars().enumerate() {
+            if i > 0 && (integer.len() - i) % 3 == 0 {
+                if let Some(separator) = self.thousands_separator {
+                    out.push(separator);
+                }
+            }
+            out.push(digit);
+        }
+        if let Some(fraction) = fraction {
+            out.push(self.decimal_separator);
+            out.push_str(fraction);
+        }
+        out
+    }
+
+    /// `time` in this locale's timezone, to the second, with the zone.
+    pub fn timestamp(&self, time: DateTime<Utc>) -> String {
+        match self.timezone {
+            ReportTimezone::Utc => time.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+            ReportTimezone::Local => time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S %:z").to_string(),
+            ReportTimezone::Fixed(offset) => time.with_timezone(&offset).format("%Y-%m-%d %H:%M:%S %:z").to_string(),
+        }
+    }
+
+    /// CSV field delimiter: `;` where `,` marks decimals, as spreadsheets
+    /// in those locales expect.
+    pub fn csv_delimiter(&self) -> u8 {
+        if self.decimal_separator == ',' {
+            b';'
+        } else {
+            b','
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numbers() {
+        let us = ReportLocale::default();
+        assert_eq!(us.decimal(Decimal::new(123456750, 2)), "1234567.5");
+        assert_eq!(us.fixed(Decimal::new(1, 1), 2), "0.10");
+
+        let de = ReportLocale {
+            decimal_separator: ',',
+            thousands_separator: Some('.'),
+            ..Default::default()
+        };
+        assert_eq!(de.decimal(Decimal::new(-1234567891, 3)), "-1.234.567,891");
+        assert_eq!(de.fixed(Decimal::new(999999, 3), 2), "1.000,00");
+        assert_eq!(de.float(45000.0), "45.000");
+        assert_eq!(de.csv_delimiter(), b';');
+        assert!(de.validate().is_ok());
+
+        let broken = ReportLocale { thousands_separator: Some('.'), ..Default::default() };
+        assert!(broken.validate().is_err());
+    }
+
+    #[test]
+    fn test_timestamps() {
+        let time: DateTime<Utc> = "2024-03-01T23:30:00Z".parse().unwrap();
+        assert_eq!(ReportLocale::default().timestamp(time), "2024-03-01 23:30:00 UTC");
+
+        let tokyo = ReportLocale { timezone: "+09:00".parse().unwrap(), ..Default::default() };
+        assert_eq!(tokyo.timestamp(time), "2024-03-02 08:30:00 +09:00");
+        assert_eq!("local".parse::<ReportTimezone>().unwrap(), ReportTimezone::Local);
+        assert!("Mars/Olympus".parse::<ReportTimezone>().is_err());
+    }
+}
@@ -1,9 +1,19 @@
 pub mod time;
+pub mod clock;
 pub mod validation;
 pub mod id_generator;
 pub mod math;
+pub mod retry;
+pub mod locale;
+pub mod stats;
+pub mod skew;
 
 pub use time::*;
+pub use clock::*;
 pub use validation::*;
 pub use id_generator::*;
-pub use math::*;
\ No newline at end of file
+pub use math::*;
+pub use retry::*;
+pub use locale::*;
+pub use stats::*;
+pub use skew::*;
\ No newline at end of file
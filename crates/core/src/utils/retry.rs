@@ -0,0 +1,217 @@
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+
+use crate::error::{ArbFinderError, Result};
+
+/// Coarse error classes a retry policy can opt into.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorClass {
+    Http,
+    WebSocket,
+    Timeout,
+    RateLimit,
+    Exchange,
+}
+
+impl ErrorClass {
+    /// Errors outside these classes (auth, invalid orders, parsing, ...) are
+    /// never worth retrying, so they have no class.
+    pub fn of(error: &ArbFinderError) -> Option<Self> {
+        match error {
+            ArbFinderError::Http(_) => Some(ErrorClass::Http),
+            ArbFinderError::WebSocket(_) => Some(ErrorClass::WebSocket),
+            ArbFinderError::Timeout(_) => Some(ErrorClass::Timeout),
+            ArbFinderError::RateLimit(_) => Some(ErrorClass::RateLimit),
+            ArbFinderError::Exchange(_) => Some(ErrorClass::Exchange),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one.
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+    pub multiplier: f64,
+    /// Fraction of the computed delay randomly added or removed, 0.0 - 1.0.
+    pub jitter: f64,
+    pub retry_on: Vec<ErrorClass>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::new(3, 1000)
+    }
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32, base_delay_ms: u64) -> Self {
+        Self {
+            max_attempts,
+            base_delay_ms,
+            max_delay_ms: 30_000,
+            multiplier: 2.0,
+            jitter: 0.1,
+            retry_on: vec![
+                ErrorClass::Http,
+                ErrorClass::WebSocket,
+                ErrorClass::Timeout,
+                ErrorClass::RateLimit,
+                ErrorClass::Exchange,
+            ],
+        }
+    }
+
+    /// Single attempt, no retries.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Order submission is not idempotent on every venue, so only retry when
+    /// the venue told us the request was rejected before it was processed.
+    pub fn orders() -> Self {
+        Self::new(3, 200).with_retry_on(vec![ErrorClass::RateLimit])
+    }
+
+    pub fn with_max_delay_ms(mut self, max_delay_ms: u64) -> Self {
+        self.max_delay_ms = max_delay_ms;
+        self
+    }
+
+    pub fn with_multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_retry_on(mut self, retry_on: Vec<ErrorClass>) -> Self {
+        self.retry_on = retry_on;
+        self
+    }
+
+    pub fn is_retryable(&self, error: &ArbFinderError) -> bool {
+        ErrorClass::of(error).is_some_and(|class| self.retry_on.contains(&class))
+    }
+
+    /// Whether another attempt should follow the failed `attempt` (1-based).
+    pub fn should_retry(&self, attempt: u32, error: &ArbFinderError) -> bool {
+        attempt < self.max_attempts && self.is_retryable(error)
+    }
+
+    /// Delay before the attempt following `attempt` (1-based), without jitter.
+    pub fn base_delay_for_attempt(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(32) as i32;
+        let delay = self.base_delay_ms as f64 * self.multiplier.max(1.0).powi(exponent);
+        Duration::from_millis(delay.min(self.max_delay_ms as f64) as u64)
+    }
+
+    pub fn delay_for_attempt(&self, attempt: u32) -> Duration {
+        let delay = self.base_delay_for_attempt(attempt);
+        if self.jitter <= 0.0 {
+            return delay;
+        }
+
+        // uniform in [-jitter, +jitter]
+        let unit = (uuid::Uuid::new_v4().as_u128() as u64) as f64 / u64::MAX as f64;
+        let factor = 1.0 + self.jitter * (2.0 * unit - 1.0);
+        Duration::from_millis((delay.as_millis() as f64 * factor) as u64)
+    }
+
+    /// Runs `operation` until it succeeds, fails with a non-retryable error,
+    /// or `max_attempts` is exhausted. The last error is returned.
+    pub async fn retry<T, F, Fut>(&self, mut operation: F) -> Result<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            match operation().await {
+                Ok(value) => return Ok(value),
+                Err(e) if self.should_retry(attempt, &e) => {
+                    tokio::time::sleep(self.delay_for_attempt(attempt)).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_exponential_delay_is_capped() {
+        let policy = RetryPolicy::new(10, 100).with_max_delay_ms(1000);
+        assert_eq!(policy.base_delay_for_attempt(1), Duration::from_millis(100));
+        assert_eq!(policy.base_delay_for_attempt(2), Duration::from_millis(200));
+        assert_eq!(policy.base_delay_for_attempt(4), Duration::from_millis(800));
+        assert_eq!(policy.base_delay_for_attempt(8), Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn test_jitter_stays_in_bounds() {
+        let policy = RetryPolicy::new(5, 1000).with_jitter(0.5);
+        for _ in 0..100 {
+            let delay = policy.delay_for_attempt(1).as_millis();
+            assert!((500..=1500).contains(&delay));
+        }
+    }
+
+    #[test]
+    fn test_retryable_classes() {
+        let policy = RetryPolicy::orders();
+        assert!(policy.is_retryable(&ArbFinderError::RateLimit("429".to_string())));
+        assert!(!policy.is_retryable(&ArbFinderError::Timeout("slow".to_string())));
+        assert!(!RetryPolicy::default().is_retryable(&ArbFinderError::InvalidOrder("bad".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_retry_until_success() {
+        let policy = RetryPolicy::new(3, 1).with_jitter(0.0);
+        let calls = AtomicU32::new(0);
+
+        let result = policy
+            .retry(|| async {
+                if calls.fetch_add(1, Ordering::SeqCst) < 2 {
+                    Err(ArbFinderError::Timeout("retry me".to_string()))
+                } else {
+                    Ok(42)
+                }
+            })
+            .await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(calls.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_non_retryable_error_fails_fast() {
+        let policy = RetryPolicy::new(5, 1);
+        let calls = AtomicU32::new(0);
+
+        let result: Result<()> = policy
+            .retry(|| async {
+                calls.fetch_add(1, Ordering::SeqCst);
+                Err(ArbFinderError::Authentication("bad key".to_string()))
+            })
+            .await;
+
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+    }
+}
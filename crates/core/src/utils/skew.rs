@@ -0,0 +1,130 @@
+//! How far a venue's clock is from ours.
+//!
+//! Venues stamp updates and check signed requests against their own
+//! clocks, which can be hundreds of milliseconds from ours and from each
+//! other's. A [`ClockSkew`] estimates a venue's offset from its server
+//! time, read halfway through a request as NTP does. The reading taken
+//! over the shortest round trip is the least uncertain, so that one is
+//! used.
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+/// A venue's clock estimate, shared between whoever measures it and the
+/// adapter signing requests with it.
+pub type SharedClockSkew = Arc<Mutex<ClockSkew>>;
+
+/// Server times the estimate is taken from, the latest first out.
+const DEFAULT_SKEW_SAMPLES: usize = 8;
+
+/// One reading of a venue's server time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SkewSample {
+    /// Venue clock minus ours.
+    pub offset: chrono::Duration,
+    pub round_trip: Duration,
+}
+
+impl SkewSample {
+    /// The reading from a request sent at `sent` and answered at
+    /// `received` with `server_time`.
+    pub fn measure(sent: DateTime<Utc>, server_time: DateTime<Utc>, received: DateTime<Utc>) -> Self {
+        let round_trip = (received - sent).max(chrono::Duration::zero());
+        Self {
+            offset: server_time - (sent + round_trip / 2),
+            round_trip: round_trip.to_std().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ClockSkew {
+    capacity: usize,
+    samples: VecDeque<SkewSample>,
+}
+
+impl Default for ClockSkew {
+    fn default() -> Self {
+        Self::new(DEFAULT_SKEW_SAMPLES)
+    }
+}
+
+impl ClockSkew {
+    /// Estimates from the last `capacity` readings, at least one.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Wraps this estimate to be shared.
+    pub fn shared(self) -> SharedClockSkew {
+        Arc::new(Mutex::new(self))
+    }
+
+    pub fn record(&mut self, sample: SkewSample) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// The reading taken over the shortest round trip.
+    pub fn best(&self) -> Option<SkewSample> {
+        self.samples.iter().min_by_key(|sample| sample.round_trip).copied()
+    }
+
+    /// Venue clock minus ours; zero until the venue is measured.
+    pub fn offset(&self) -> chrono::Duration {
+        self.best().map(|sample| sample.offset).unwrap_or_else(chrono::Duration::zero)
+    }
+
+    /// Shortest recent round trip to the venue's time endpoint.
+    pub fn round_trip(&self) -> Option<Duration> {
+        self.best().map(|sample| sample.round_trip)
+    }
+
+    /// The time now on the venue's clock, for stamping signed requests.
+    pub fn venue_now(&self) -> DateTime<Utc> {
+        Utc::now() + self.offset()
+    }
+
+    /// `venue_time`, stamped by the venue, on our clock, for comparing
+    /// with other venues' timestamps and our own.
+    pub fn to_local(&self, venue_time: DateTime<Utc>) -> DateTime<Utc> {
+        venue_time - self.offset()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_skew_from_shortest_round_trip() {
+        let sent = Utc::now();
+        let ms = chrono::Duration::milliseconds;
+        let mut skew = ClockSkew::new(2);
+        assert_eq!(skew.offset(), chrono::Duration::zero());
+
+        // Server 500ms ahead, read 100ms into a 200ms round trip
+        let sample = SkewSample::measure(sent, sent + ms(600), sent + ms(200));
+        assert_eq!(sample.offset, ms(500));
+        assert_eq!(sample.round_trip, Duration::from_millis(200));
+        skew.record(sample);
+
+        // A slower reading doesn't displace it
+        skew.record(SkewSample::measure(sent, sent + ms(1_000), sent + ms(900)));
+        assert_eq!(skew.offset(), ms(500));
+        assert_eq!(skew.to_local(sent + ms(500)), sent);
+
+        // Until it ages out
+        skew.record(SkewSample::measure(sent, sent + ms(450), sent + ms(300)));
+        assert_eq!(skew.offset(), ms(300));
+        assert_eq!(skew.round_trip(), Some(Duration::from_millis(300)));
+    }
+}
@@ -0,0 +1,266 @@
+//! Rolling statistics over recent samples, e.g. request or heartbeat
+//! latencies.
+//!
+//! A [`RollingStats`] keeps the last `capacity` samples, and with a maximum
+//! age set drops samples older than that too, so its percentiles follow
+//! the recent past rather than the whole run. The window can be written out
+//! as a Prometheus summary.
+
+use std::collections::VecDeque;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+/// Quantiles a window is summarized and exported by.
+pub const SUMMARY_QUANTILES: [f64; 3] = [0.5, 0.95, 0.99];
+
+/// Statistics shared between whoever records samples and whoever reads or
+/// exports them.
+pub type SharedStats = Arc<Mutex<RollingStats>>;
+
+#[derive(Debug, Clone)]
+pub struct RollingStats {
+    capacity: usize,
+    max_age: Option<Duration>,
+    samples: VecDeque<(Instant, f64)>,
+}
+
+/// A window's statistics at one point in time.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StatsSummary {
+    pub count: usize,
+    pub min: f64,
+    pub max: f64,
+    pub mean: f64,
+    pub sum: f64,
+    pub p50: f64,
+    pub p95: f64,
+    pub p99: f64,
+}
+
+impl RollingStats {
+    /// Keeps the last `capacity` samples, at least one.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            max_age: None,
+            samples: VecDeque::new(),
+        }
+    }
+
+    /// Also forgets samples older than `max_age`.
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Wraps these statistics to be shared.
+    pub fn shared(self) -> SharedStats {
+        Arc::new(Mutex::new(self))
+    }
+
+    pub fn record(&mut self, value: f64) {
+        self.record_at(value, Instant::now());
+    }
+
+    /// Records a duration in seconds.
+    pub fn record_duration(&mut self, duration: Duration) {
+        self.record(duration.as_secs_f64());
+    }
+
+    pub fn record_at(&mut self, value: f64, at: Instant) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((at, value));
+        self.expire(at);
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Samples in the window, as of now.
+    pub fn len(&self) -> usize {
+        self.values_at(Instant::now()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drops samples that have aged out of the window by `now`.
+    pub fn expire(&mut self, now: Instant) {
+        if let Some(max_age) = self.max_age {
+            while self
+                .samples
+                .front()
+                .is_some_and(|(at, _)| now.saturating_duration_since(*at) > max_age)
+            {
+                self.samples.pop_front();
+            }
+        }
+    }
+
+    fn values_at(&self, now: Instant) -> Vec<f64> {
+        self.samples
+            .iter()
+            .filter(|(at, _)| self.max_age.is_none_or(|max_age| now.saturating_duration_since(*at) <= max_age))
+            .map(|(_, value)| *value)
+            .collect()
+    }
+
+    /// The `quantile` (0 to 1) of the window, by nearest rank.
+    pub fn percentile(&self, quantile: f64) -> Option<f64> {
+        let mut values = self.values_at(Instant::now());
+        values.sort_by(f64::total_cmp);
+        nearest_rank(&values, quantile)
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        self.summary().map(|summary| summary.mean)
+    }
+
+    pub fn summary(&self) -> Option<StatsSummary> {
+        self.summary_at(Instant::now())
+    }
+
+    /// The window's statistics as of `now`; `None` if it is empty.
+    pub fn summary_at(&self, now: Instant) -> Option<StatsSummary> {
+        let mut values = self.values_at(now);
+        values.sort_by(f64::total_cmp);
+        let sum: f64 = values.iter().sum();
+        Some(StatsSummary {
+            count: values.len(),
+            min: *values.first()?,
+            max: *values.last()?,
+            mean: sum / values.len() as f64,
+            sum,
+            p50: nearest_rank(&values, 0.5)?,
+            p95: nearest_rank(&values, 0.95)?,
+            p99: nearest_rank(&values, 0.99)?,
+        })
+    }
+
+    /// Appends the window as the samples of Prometheus summary `name` with
+    /// `labels`: one per quantile in [`SUMMARY_QUANTILES`], then `_sum` and
+    /// `_count`. The `# HELP` and `# TYPE` lines come from
+    /// [`write_prometheus_header`], once for every series of `name`.
+    pub fn write_prometheus(&self, out: &mut String, name: &str, labels: &[(&str, &str)]) {
+        let mut values = self.values_at(Instant::now());
+        values.sort_by(f64::total_cmp);
+
+        for quantile in SUMMARY_QUANTILES {
+            let value = nearest_rank(&values, quantile).unwrap_or(f64::NAN);
+            let _ = writeln!(
+                out,
+                "{}{} {}",
+                name,
+                prometheus_labels(labels, Some(("quantile", &quantile.to_string()))),
+                value
+            );
+        }
+        let _ = writeln!(out, "{}_sum{} {}", name, prometheus_labels(labels, None), values.iter().sum::<f64>());
+        let _ = writeln!(out, "{}_count{} {}", name, prometheus_labels(labels, None), values.len());
+    }
+}
+
+impl Default for RollingStats {
+    fn default() -> Self {
+        Self::new(1000)
+    }
+}
+
+/// `# HELP` and `# TYPE` lines of a Prometheus summary.
+pub fn write_prometheus_header(out: &mut String, name: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help.replace('\\', "\\\\").replace('\n', "\\n"));
+    let _ = writeln!(out, "# TYPE {} summary", name);
+}
+
+fn prometheus_labels(labels: &[(&str, &str)], extra: Option<(&str, &str)>) -> String {
+    let pairs: Vec<String> = labels
+        .iter()
+        .copied()
+        .chain(extra)
+        .map(|(key, value)| {
+            let value = value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+            format!("{}=\"{}\"", key, value)
+        })
+        .collect();
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+/// The value at `quantile` of `sorted`, the smallest with at least that
+/// fraction of values at or below it.
+fn nearest_rank(sorted: &[f64], quantile: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (quantile.clamp(0.0, 1.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary_and_capacity() {
+        let mut stats = RollingStats::new(100);
+        assert!(stats.summary().is_none());
+
+        // 101 to 200; the first sample is pushed out
+        for value in 100..=200 {
+            stats.record(value as f64);
+        }
+        let summary = stats.summary().unwrap();
+        assert_eq!(summary.count, 100);
+        assert_eq!((summary.min, summary.max), (101.0, 200.0));
+        assert_eq!(summary.mean, 150.5);
+        assert_eq!((summary.p50, summary.p95, summary.p99), (150.0, 195.0, 199.0));
+        assert_eq!(stats.percentile(0.0), Some(101.0));
+        assert_eq!(stats.percentile(1.0), Some(200.0));
+    }
+
+    #[test]
+    fn test_old_samples_decay() {
+        let mut stats = RollingStats::new(10).with_max_age(Duration::from_secs(60));
+        let start = Instant::now();
+        stats.record_at(5.0, start);
+        stats.record_at(1.0, start + Duration::from_secs(30));
+
+        assert_eq!(stats.summary_at(start + Duration::from_secs(45)).unwrap().max, 5.0);
+        let later = stats.summary_at(start + Duration::from_secs(75)).unwrap();
+        assert_eq!((later.count, later.max), (1, 1.0));
+
+        stats.record_at(2.0, start + Duration::from_secs(100));
+        assert_eq!(stats.samples.len(), 1);
+    }
+
+    #[test]
+    fn test_write_prometheus() {
+        let mut stats = RollingStats::new(10);
+        stats.record_duration(Duration::from_millis(250));
+        stats.record_duration(Duration::from_millis(750));
+
+        let mut out = String::new();
+        write_prometheus_header(&mut out, "arbfinder_ping_seconds", "Ping round trips");
+        stats.write_prometheus(&mut out, "arbfinder_ping_seconds", &[("exchange", "binance")]);
+        assert_eq!(
+            out,
+            "# HELP arbfinder_ping_seconds Ping round trips\n\
+             # TYPE arbfinder_ping_seconds summary\n\
+             arbfinder_ping_seconds{exchange=\"binance\",quantile=\"0.5\"} 0.25\n\
+             arbfinder_ping_seconds{exchange=\"binance\",quantile=\"0.95\"} 0.75\n\
+             arbfinder_ping_seconds{exchange=\"binance\",quantile=\"0.99\"} 0.75\n\
+             arbfinder_ping_seconds_sum{exchange=\"binance\"} 1\n\
+             arbfinder_ping_seconds_count{exchange=\"binance\"} 2\n"
+        );
+    }
+}
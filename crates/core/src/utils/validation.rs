@@ -66,7 +66,7 @@ pub fn validate_order_request(
                 return Err(ArbFinderError::InvalidOrder("Market orders should not have a price".to_string()));
             }
         }
-        OrderType::Limit | OrderType::StopLimit | OrderType::PostOnly => {
+        OrderType::Limit | OrderType::StopLimit | OrderType::PostOnly | OrderType::Oco => {
             match price {
                 Some(p) => validate_price(p)?,
                 None => return Err(ArbFinderError::InvalidOrder(format!("{} orders must have a price", order_type))),
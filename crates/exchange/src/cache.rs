@@ -0,0 +1,144 @@
+//! Caching REST responses that rarely change.
+//!
+//! Symbol lists and trading rules are fetched for every symbol looked up,
+//! and on some venues cost a large share of the request weight. A
+//! [`ResponseCache`] keeps the responses of the endpoints given a TTL, so
+//! lookups within it are answered locally. Other endpoints pass through.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use arbfinder_core::Result;
+use serde_json::Value;
+
+/// How long symbol lists and trading rules are kept by the venues that
+/// cache them by default.
+pub const DEFAULT_SYMBOLS_TTL: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Default)]
+pub struct ResponseCache {
+    /// TTLs by endpoint prefix.
+    ttls: Vec<(String, Duration)>,
+    /// Responses by endpoint and query, with when each was fetched.
+    entries: Mutex<HashMap<String, (Instant, Value)>>,
+    hits: AtomicU64,
+}
+
+impl ResponseCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps responses from endpoints starting with `prefix` for `ttl`.
+    /// The longest matching prefix wins.
+    pub fn with_ttl(mut self, prefix: impl Into<String>, ttl: Duration) -> Self {
+        self.ttls.push((prefix.into(), ttl));
+        self
+    }
+
+    /// How long responses from `endpoint` are kept, if they are.
+    pub fn ttl(&self, endpoint: &str) -> Option<Duration> {
+        self.ttls
+            .iter()
+            .filter(|(prefix, _)| endpoint.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, ttl)| *ttl)
+    }
+
+    /// The cached response from `endpoint`, if it is younger than its TTL.
+    pub fn get(&self, endpoint: &str) -> Option<Value> {
+        self.get_at(endpoint, Instant::now())
+    }
+
+    fn get_at(&self, endpoint: &str, now: Instant) -> Option<Value> {
+        let ttl = self.ttl(endpoint)?;
+        let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let (fetched, value) = entries.get(endpoint)?;
+        (now.saturating_duration_since(*fetched) < ttl).then(|| value.clone())
+    }
+
+    /// Keeps `value` as the response from `endpoint`, if it has a TTL.
+    pub fn insert(&self, endpoint: &str, value: &Value) {
+        self.insert_at(endpoint, value, Instant::now());
+    }
+
+    fn insert_at(&self, endpoint: &str, value: &Value, now: Instant) {
+        if self.ttl(endpoint).is_some() {
+            self.entries
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(endpoint.to_string(), (now, value.clone()));
+        }
+    }
+
+    /// The cached response from `endpoint`, or else the one `fetch` gets,
+    /// kept if it succeeds.
+    pub async fn get_or_fetch<F, Fut>(&self, endpoint: &str, fetch: F) -> Result<Value>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Value>>,
+    {
+        if let Some(value) = self.get(endpoint) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(value);
+        }
+        let value = fetch().await?;
+        self.insert(endpoint, &value);
+        Ok(value)
+    }
+
+    /// Drops the responses from endpoints starting with `prefix`, so they
+    /// are fetched afresh.
+    pub fn invalidate(&self, prefix: &str) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|endpoint, _| !endpoint.starts_with(prefix));
+    }
+
+    pub fn clear(&self) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    /// Number of requests answered from the cache.
+    pub fn hit_count(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_response_cache() {
+        let cache = ResponseCache::new()
+            .with_ttl("/products", Duration::from_secs(60))
+            .with_ttl("/products/BTC-USD", Duration::from_secs(5));
+        assert_eq!(cache.ttl("/products?type=SPOT"), Some(Duration::from_secs(60)));
+        assert_eq!(cache.ttl("/products/BTC-USD"), Some(Duration::from_secs(5)));
+        assert_eq!(cache.ttl("/time"), None);
+
+        let start = Instant::now();
+        cache.insert_at("/products", &json!([1]), start);
+        cache.insert_at("/products/BTC-USD", &json!({"id": 1}), start);
+        cache.insert_at("/time", &json!(0), start);
+        assert_eq!(cache.get_at("/products", start + Duration::from_secs(30)), Some(json!([1])));
+        assert_eq!(cache.get_at("/products/BTC-USD", start + Duration::from_secs(30)), None);
+        assert_eq!(cache.get_at("/time", start), None);
+
+        let fetched = cache.get_or_fetch("/products", || async { Ok(json!([2])) }).await.unwrap();
+        assert_eq!(fetched, json!([1]));
+        assert_eq!(cache.hit_count(), 1);
+
+        // Forced refresh
+        cache.invalidate("/products");
+        let fetched = cache.get_or_fetch("/products", || async { Ok(json!([2])) }).await.unwrap();
+        assert_eq!(fetched, json!([2]));
+        assert_eq!(cache.get("/products"), Some(json!([2])));
+    }
+}
@@ -0,0 +1,178 @@
+//! Circuit breaking for venue connections that keep failing.
+//!
+//! Reconnects back off exponentially, with jitter, under the connection's
+//! retry policy. Once `failure_threshold` attempts in a row have failed the
+//! circuit opens: nothing is tried until `open_duration` has passed, after
+//! which one attempt is let through half-open. Its success closes the
+//! circuit; its failure opens it again for twice as long, up to
+//! `max_open_duration`. While open, the venue counts as failing in its
+//! `ConnectionStatus`, which is what alerts are raised on.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CircuitBreakerConfig {
+    /// Failed attempts in a row that open the circuit.
+    pub failure_threshold: u32,
+    /// How long the circuit first stays open.
+    pub open_duration: Duration,
+    /// Longest it stays open, however often it reopens.
+    pub max_open_duration: Duration,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 10,
+            open_duration: Duration::from_secs(60),
+            max_open_duration: Duration::from_secs(900),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum CircuitState {
+    Closed,
+    /// No attempts until `until`.
+    Open { until: DateTime<Utc> },
+    /// One attempt is being let through to test the connection.
+    HalfOpen,
+}
+
+impl CircuitState {
+    pub fn is_open(&self) -> bool {
+        matches!(self, CircuitState::Open { .. })
+    }
+}
+
+/// A circuit shared by a connection and whoever reports on it.
+pub type SharedCircuitBreaker = Arc<Mutex<CircuitBreaker>>;
+
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    config: CircuitBreakerConfig,
+    state: CircuitState,
+    consecutive_failures: u32,
+    /// Times opened since the circuit last closed.
+    trips: u32,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            state: CircuitState::Closed,
+            consecutive_failures: 0,
+            trips: 0,
+        }
+    }
+
+    pub fn shared(self) -> SharedCircuitBreaker {
+        Arc::new(Mutex::new(self))
+    }
+
+    pub fn config(&self) -> &CircuitBreakerConfig {
+        &self.config
+    }
+
+    pub fn state(&self) -> CircuitState {
+        self.state
+    }
+
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    /// How long to hold off before attempting at `now`; `None` if an
+    /// attempt may go ahead. An open circuit whose time is up turns
+    /// half-open.
+    pub fn wait(&mut self, now: DateTime<Utc>) -> Option<Duration> {
+        match self.state {
+            CircuitState::Open { until } if until > now => (until - now).to_std().ok(),
+            CircuitState::Open { .. } => {
+                self.state = CircuitState::HalfOpen;
+                None
+            }
+            _ => None,
+        }
+    }
+
+    pub fn record_success(&mut self) {
+        if self.state != CircuitState::Closed {
+            info!("Circuit closed after {} failed attempts", self.consecutive_failures);
+        }
+        self.state = CircuitState::Closed;
+        self.consecutive_failures = 0;
+        self.trips = 0;
+    }
+
+    /// Counts a failed attempt at `now`. Returns true if it opened the
+    /// circuit.
+    pub fn record_failure(&mut self, now: DateTime<Utc>) -> bool {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+        let trip = self.state == CircuitState::HalfOpen
+            || (self.state == CircuitState::Closed && self.consecutive_failures >= self.config.failure_threshold.max(1));
+        if !trip {
+            return false;
+        }
+
+        let factor = 2u32.saturating_pow(self.trips.min(16));
+        let open_for = self.config.open_duration.saturating_mul(factor).min(self.config.max_open_duration);
+        self.trips += 1;
+        self.state = CircuitState::Open {
+            until: now + chrono::Duration::from_std(open_for).unwrap_or_default(),
+        };
+        error!(
+            "Circuit opened after {} failed attempts, next attempt in {:?}",
+            self.consecutive_failures, open_for
+        );
+        true
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(CircuitBreakerConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opens_after_threshold_and_backs_off() {
+        let mut circuit = CircuitBreaker::new(CircuitBreakerConfig {
+            failure_threshold: 3,
+            open_duration: Duration::from_secs(60),
+            max_open_duration: Duration::from_secs(100),
+        });
+        let start = Utc::now();
+        let at = |secs| start + chrono::Duration::seconds(secs);
+
+        assert!(!circuit.record_failure(at(0)));
+        assert!(!circuit.record_failure(at(1)));
+        assert!(circuit.record_failure(at(2)));
+        assert!(circuit.state().is_open());
+        assert_eq!(circuit.wait(at(32)), Some(Duration::from_secs(30)));
+
+        // Half-open lets one attempt through; failing it reopens for
+        // twice as long, within the cap
+        assert_eq!(circuit.wait(at(62)), None);
+        assert_eq!(circuit.state(), CircuitState::HalfOpen);
+        assert!(circuit.record_failure(at(62)));
+        assert_eq!(circuit.state(), CircuitState::Open { until: at(162) });
+
+        assert_eq!(circuit.wait(at(162)), None);
+        circuit.record_success();
+        assert_eq!(circuit.state(), CircuitState::Closed);
+        assert_eq!(circuit.consecutive_failures(), 0);
+        assert!(!circuit.record_failure(at(200)));
+    }
+}
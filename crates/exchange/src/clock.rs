@@ -0,0 +1,27 @@
+//! Measuring venues' clocks.
+//!
+//! `ExchangeManager::sync_clocks` reads each connected venue's server time
+//! every interval and folds it into the venue's `ClockSkew`, which the
+//! adapter signs requests with and the fan-out orders updates by.
+
+use std::time::Duration;
+
+use chrono::Utc;
+
+use arbfinder_core::utils::SkewSample;
+use arbfinder_core::Result;
+
+use crate::traits::MarketDataAdapter;
+
+/// How often venues' clocks are read by default.
+pub const DEFAULT_CLOCK_SYNC_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// Skew past which a venue's clock is logged as off.
+pub const CLOCK_SKEW_WARNING: Duration = Duration::from_secs(1);
+
+/// Reads `adapter`'s server time once.
+pub async fn measure_clock_skew(adapter: &dyn MarketDataAdapter) -> Result<SkewSample> {
+    let sent = Utc::now();
+    let server_time = adapter.get_server_time().await?;
+    Ok(SkewSample::measure(sent, server_time, Utc::now()))
+}
@@ -0,0 +1,155 @@
+//! Payload compression on WebSocket frames.
+//!
+//! Only compression a venue applies to its payloads itself is handled here,
+//! decoded per the venue's `ExchangeConfig::frame_encoding` before frames
+//! reach its handler. Negotiated permessage-deflate is not: tungstenite
+//! can't receive it.
+
+use arbfinder_core::{ArbFinderError, Result};
+use flate2::read::{DeflateDecoder, GzDecoder, ZlibDecoder};
+use std::borrow::Cow;
+use std::io::Read;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Compression a venue applies to each binary frame's payload itself. HTX
+/// gzips every message; OKX's older endpoints send raw deflate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FrameEncoding {
+    #[default]
+    Plain,
+    Gzip,
+    Zlib,
+    /// Raw deflate, without a zlib or gzip header.
+    Deflate,
+    /// Gzip or zlib when the payload starts with their header, anything
+    /// else as it is.
+    Detect,
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+fn is_zlib_header(data: &[u8]) -> bool {
+    // CMF of deflate with a window of at most 32K, and a check over CMF/FLG
+    data.len() >= 2 && data[0] & 0x0f == 8 && data[0] >> 4 <= 7 && u16::from_be_bytes([data[0], data[1]]).is_multiple_of(31)
+}
+
+fn read_all(mut reader: impl Read, encoding: &str) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    reader
+        .read_to_end(&mut out)
+        .map_err(|e| ArbFinderError::WebSocket(format!("Failed to decode {} frame: {}", encoding, e)))?;
+    Ok(out)
+}
+
+impl FrameEncoding {
+    /// The payload of a frame sent with this encoding.
+    pub fn decode<'a>(&self, data: &'a [u8]) -> Result<Cow<'a, [u8]>> {
+        let encoding = match self {
+            FrameEncoding::Detect if data.starts_with(&GZIP_MAGIC) => FrameEncoding::Gzip,
+            FrameEncoding::Detect if is_zlib_header(data) => FrameEncoding::Zlib,
+            FrameEncoding::Detect => FrameEncoding::Plain,
+            other => *other,
+        };
+        Ok(match encoding {
+            FrameEncoding::Gzip => Cow::Owned(read_all(GzDecoder::new(data), "gzip")?),
+            FrameEncoding::Zlib => Cow::Owned(read_all(ZlibDecoder::new(data), "zlib")?),
+            FrameEncoding::Deflate => Cow::Owned(read_all(DeflateDecoder::new(data), "deflate")?),
+            _ => Cow::Borrowed(data),
+        })
+    }
+}
+
+/// Wire vs decoded byte counters for a single connection's binary frames.
+#[derive(Debug, Default)]
+pub struct CompressionStats {
+    compressed_bytes: AtomicU64,
+    uncompressed_bytes: AtomicU64,
+    messages: AtomicU64,
+}
+
+impl CompressionStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, wire_bytes: usize, decoded_bytes: usize) {
+        self.compressed_bytes.fetch_add(wire_bytes as u64, Ordering::Relaxed);
+        self.uncompressed_bytes.fetch_add(decoded_bytes as u64, Ordering::Relaxed);
+        self.messages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> CompressionSnapshot {
+        CompressionSnapshot {
+            compressed_bytes: self.compressed_bytes.load(Ordering::Relaxed),
+            uncompressed_bytes: self.uncompressed_bytes.load(Ordering::Relaxed),
+            messages: self.messages.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CompressionSnapshot {
+    pub compressed_bytes: u64,
+    pub uncompressed_bytes: u64,
+    pub messages: u64,
+}
+
+impl CompressionSnapshot {
+    /// Decoded bytes per wire byte; 1.0 when nothing was compressed.
+    pub fn ratio(&self) -> f64 {
+        if self.compressed_bytes == 0 {
+            1.0
+        } else {
+            self.uncompressed_bytes as f64 / self.compressed_bytes as f64
+        }
+    }
+
+    pub fn bytes_saved(&self) -> u64 {
+        self.uncompressed_bytes.saturating_sub(self.compressed_bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+
+    #[test]
+    fn test_frame_encodings() {
+        use flate2::write::{DeflateEncoder, GzEncoder, ZlibEncoder};
+        use std::io::Write;
+
+        let message = br#"{"ch":"market.btcusdt.depth.step0"}"#;
+        let mut gzip = GzEncoder::new(Vec::new(), Compression::default());
+        gzip.write_all(message).unwrap();
+        let gzip = gzip.finish().unwrap();
+        let mut zlib = ZlibEncoder::new(Vec::new(), Compression::default());
+        zlib.write_all(message).unwrap();
+        let zlib = zlib.finish().unwrap();
+        let mut deflate = DeflateEncoder::new(Vec::new(), Compression::default());
+        deflate.write_all(message).unwrap();
+        let deflate = deflate.finish().unwrap();
+
+        assert_eq!(FrameEncoding::Gzip.decode(&gzip).unwrap().as_ref(), message);
+        assert_eq!(FrameEncoding::Zlib.decode(&zlib).unwrap().as_ref(), message);
+        assert_eq!(FrameEncoding::Deflate.decode(&deflate).unwrap().as_ref(), message);
+        assert!(FrameEncoding::Gzip.decode(message).is_err());
+
+        // Detection passes frames already decoded, or never encoded, through
+        for frame in [&gzip[..], &zlib[..], &message[..]] {
+            assert_eq!(FrameEncoding::Detect.decode(frame).unwrap().as_ref(), message);
+        }
+    }
+
+    #[test]
+    fn test_compression_stats() {
+        let stats = CompressionStats::new();
+        stats.record(100, 400);
+        stats.record(50, 200);
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.messages, 2);
+        assert_eq!(snapshot.bytes_saved(), 450);
+        assert!((snapshot.ratio() - 4.0).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,323 @@
+//! Falling back to REST polling while a venue's stream is down.
+//!
+//! A venue whose WebSocket feed drops, or goes quiet, usually still answers
+//! REST requests. Rather than losing the venue until the stream returns, its
+//! books are polled over REST at a cadence well inside its rate limits, the
+//! symbols most worth it first, and the stream is retried in the background.
+//! Every update says which path it came from and how old it is, so
+//! consumers can tell degraded data apart.
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use futures::{Stream, StreamExt};
+use std::pin::Pin;
+use tokio::sync::{mpsc, Mutex};
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info, warn};
+
+use arbfinder_core::{MarketData, Symbol, VenueId};
+
+use crate::polling::PollScheduler;
+use crate::traits::{MarketDataStream, VenueAdapter};
+
+pub type FeedStream = Pin<Box<dyn Stream<Item = FeedUpdate> + Send>>;
+
+#[derive(Debug, Clone)]
+pub struct FallbackConfig {
+    /// Stream silence after which the venue is polled instead.
+    pub stale_after: Duration,
+    /// REST book requests allowed per minute, across all symbols. Each
+    /// request polls whichever symbol the scheduler has most overdue.
+    pub requests_per_minute: u32,
+    /// Fastest a symbol is polled, when its gap nears profitability.
+    pub min_poll_interval: Duration,
+    /// Slowest a symbol is polled, once its book has gone quiet.
+    pub max_poll_interval: Duration,
+    /// How often a dead or silent stream is reopened.
+    pub reconnect_interval: Duration,
+    /// Levels asked for per polled book.
+    pub depth: Option<u32>,
+}
+
+impl Default for FallbackConfig {
+    fn default() -> Self {
+        Self {
+            stale_after: Duration::from_secs(10),
+            requests_per_minute: 60,
+            min_poll_interval: Duration::from_secs(1),
+            max_poll_interval: Duration::from_secs(30),
+            reconnect_interval: Duration::from_secs(30),
+            depth: Some(20),
+        }
+    }
+}
+
+impl FallbackConfig {
+    /// Time between REST requests.
+    pub fn poll_interval(&self) -> Duration {
+        Duration::from_secs(60) / self.requests_per_minute.max(1)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeedSource {
+    Stream,
+    /// Polled over REST while the stream is down.
+    Polling,
+}
+
+/// Market data from a venue, tagged with how it arrived.
+#[derive(Debug, Clone)]
+pub struct FeedUpdate {
+    pub venue: VenueId,
+    pub data: MarketData,
+    pub source: FeedSource,
+    pub received_at: DateTime<Utc>,
+}
+
+impl FeedUpdate {
+    pub fn is_degraded(&self) -> bool {
+        self.source == FeedSource::Polling
+    }
+
+    /// How old the data is as of `now`.
+    pub fn age(&self, now: DateTime<Utc>) -> Duration {
+        (now - self.data.timestamp()).to_std().unwrap_or_default()
+    }
+}
+
+/// Which path a venue's data takes, from when its stream last spoke.
+#[derive(Debug)]
+struct FeedHealth {
+    config: FallbackConfig,
+    source: FeedSource,
+    /// When the current stream was opened, `None` while there is none.
+    opened_at: Option<Instant>,
+    last_message: Option<Instant>,
+}
+
+impl FeedHealth {
+    fn new(config: FallbackConfig) -> Self {
+        Self {
+            config,
+            source: FeedSource::Stream,
+            opened_at: None,
+            last_message: None,
+        }
+    }
+
+    fn on_open(&mut self, now: Instant) {
+        self.opened_at = Some(now);
+        self.last_message = None;
+    }
+
+    fn on_close(&mut self) {
+        self.opened_at = None;
+    }
+
+    /// Returns true if the message brings the venue back from polling.
+    fn on_message(&mut self, now: Instant) -> bool {
+        self.last_message = Some(now);
+        let recovered = self.source == FeedSource::Polling;
+        self.source = FeedSource::Stream;
+        recovered
+    }
+
+    /// How long the stream has gone without a message, `None` if there is
+    /// no stream.
+    fn silence(&self, now: Instant) -> Option<Duration> {
+        let since = self.last_message.or(self.opened_at)?;
+        Some(now.saturating_duration_since(since))
+    }
+
+    /// Whether to poll now, switching to polling if the stream is down.
+    /// Returns the source change alongside.
+    fn should_poll(&mut self, now: Instant) -> (bool, bool) {
+        let down = match self.silence(now) {
+            Some(silence) => silence >= self.config.stale_after,
+            None => true,
+        };
+        let switched = down && self.source == FeedSource::Stream;
+        if down {
+            self.source = FeedSource::Polling;
+        }
+        (down, switched)
+    }
+
+    fn should_reconnect(&self, now: Instant) -> bool {
+        match self.silence(now) {
+            Some(silence) => silence >= self.config.reconnect_interval,
+            None => true,
+        }
+    }
+}
+
+async fn open_stream(adapter: &Mutex<VenueAdapter>) -> Option<MarketDataStream> {
+    let adapter = adapter.lock().await;
+    match adapter.market_data_stream().await {
+        Ok(stream) => Some(stream),
+        Err(e) => {
+            debug!("Could not open {} market data stream: {}", adapter.venue_id(), e);
+            None
+        }
+    }
+}
+
+/// Market data for `symbols` from `adapter`'s stream, polling their books
+/// over REST whenever the stream is down and going back to it once it
+/// recovers. The stream ends when the returned stream is dropped.
+pub fn resilient_market_data(
+    adapter: Arc<Mutex<VenueAdapter>>,
+    symbols: Vec<Symbol>,
+    config: FallbackConfig,
+) -> FeedStream {
+    let scheduler = PollScheduler::new(symbols, config.min_poll_interval, config.max_poll_interval);
+    resilient_market_data_with(adapter, scheduler, config)
+}
+
+/// As `resilient_market_data`, polling the symbols `scheduler` holds in the
+/// order it picks. Keep a clone of it to report cross-venue gaps.
+pub fn resilient_market_data_with(
+    adapter: Arc<Mutex<VenueAdapter>>,
+    scheduler: PollScheduler,
+    config: FallbackConfig,
+) -> FeedStream {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let venue = adapter.lock().await.venue_id();
+        let mut health = FeedHealth::new(config.clone());
+        let mut stream = open_stream(&adapter).await;
+        if stream.is_some() {
+            health.on_open(Instant::now());
+        }
+
+        let mut poll = tokio::time::interval(config.poll_interval());
+        poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut reconnect = tokio::time::interval(config.reconnect_interval);
+        reconnect.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        reconnect.tick().await;
+
+        loop {
+            let update = tokio::select! {
+                item = async {
+                    match stream.as_mut() {
+                        Some(stream) => stream.next().await,
+                        None => std::future::pending().await,
+                    }
+                } => {
+                    match item {
+                        Some(Ok(data)) => {
+                            if health.on_message(Instant::now()) {
+                                info!("{} stream recovered, leaving REST polling", venue);
+                            }
+                            Some((data, FeedSource::Stream))
+                        }
+                        Some(Err(e)) => {
+                            warn!("{} stream error: {}", venue, e);
+                            None
+                        }
+                        None => {
+                            warn!("{} stream closed", venue);
+                            stream = None;
+                            health.on_close();
+                            None
+                        }
+                    }
+                }
+                _ = reconnect.tick() => {
+                    if health.should_reconnect(Instant::now()) {
+                        stream = open_stream(&adapter).await;
+                        match stream {
+                            Some(_) => health.on_open(Instant::now()),
+                            None => health.on_close(),
+                        }
+                    }
+                    None
+                }
+                _ = poll.tick() => {
+                    let (due, switched) = health.should_poll(Instant::now());
+                    if switched {
+                        warn!(
+                            "{} stream down, polling books over REST every {}ms",
+                            venue,
+                            config.poll_interval().as_millis()
+                        );
+                    }
+                    match scheduler.next(Instant::now()).filter(|_| due) {
+                        None => None,
+                        Some(symbol) => {
+                            let requested_at = Utc::now();
+                            match adapter.lock().await.fetch_orderbook(&symbol, config.depth).await {
+                                Ok(mut book) => {
+                                    scheduler.on_poll(&symbol, &book, Instant::now());
+                                    // No fresher than the request that fetched it
+                                    book.timestamp = book.timestamp.min(requested_at);
+                                    Some((MarketData::OrderBook(book), FeedSource::Polling))
+                                }
+                                Err(e) => {
+                                    scheduler.on_failure(&symbol, Instant::now());
+                                    warn!("Polling {} book on {} failed: {}", symbol, venue, e);
+                                    None
+                                }
+                            }
+                        }
+                    }
+                }
+            };
+
+            if let Some((data, source)) = update {
+                let update = FeedUpdate {
+                    venue: venue.clone(),
+                    data,
+                    source,
+                    received_at: Utc::now(),
+                };
+                if tx.send(update).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    Box::pin(futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|update| (update, rx))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fallback_and_recovery() {
+        let config = FallbackConfig::default();
+        assert_eq!(config.poll_interval(), Duration::from_secs(1));
+        let start = Instant::now();
+        let mut health = FeedHealth::new(config);
+
+        // No stream at all: poll straight away
+        assert_eq!(health.should_poll(start), (true, true));
+        assert!(health.should_reconnect(start));
+
+        // A fresh stream gets until `stale_after` to speak
+        health.on_open(start);
+        assert!(health.on_message(start + Duration::from_secs(1)));
+        assert_eq!(health.should_poll(start + Duration::from_secs(5)), (false, false));
+        assert!(!health.should_reconnect(start + Duration::from_secs(5)));
+
+        // Silent past `stale_after`: poll, but give it until
+        // `reconnect_interval` before reopening
+        let silent = start + Duration::from_secs(12);
+        assert_eq!(health.should_poll(silent), (true, true));
+        assert_eq!(health.should_poll(silent), (true, false));
+        assert!(!health.should_reconnect(silent));
+        assert!(health.should_reconnect(start + Duration::from_secs(31)));
+
+        // Back on the stream with its next message
+        assert!(health.on_message(silent + Duration::from_secs(1)));
+        assert!(!health.on_message(silent + Duration::from_secs(2)));
+        assert_eq!(health.should_poll(silent + Duration::from_secs(3)), (false, false));
+    }
+}
@@ -0,0 +1,220 @@
+//! One market data feed across every venue, shared by many consumers.
+//!
+//! Each venue's stream is forwarded into a broadcast channel, tagged with
+//! the venue it came from. Every subscriber gets a receiver of its own, so
+//! strategies, recorders and the aggregator read the same updates at their
+//! own pace. A subscriber that falls more than the channel's capacity behind
+//! skips what it missed rather than holding the others back. Updates from
+//! venues whose clock skew is known carry their timestamp on our clock too,
+//! so updates from different venues can be ordered.
+
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::Mutex;
+
+use futures::{Stream, StreamExt};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use arbfinder_core::utils::SharedClockSkew;
+use arbfinder_core::{MarketData, VenueId};
+use chrono::{DateTime, Utc};
+
+use crate::traits::MarketDataStream;
+
+/// Updates buffered for the slowest subscriber before it starts skipping.
+pub const DEFAULT_FANOUT_CAPACITY: usize = 4096;
+
+/// Market data tagged with the venue it came from.
+#[derive(Debug, Clone)]
+pub struct VenueMarketData {
+    pub venue: VenueId,
+    pub data: MarketData,
+    /// The venue's timestamp on our clock. The venue's own if its skew is
+    /// unknown.
+    pub local_time: DateTime<Utc>,
+}
+
+pub type VenueMarketDataStream = Pin<Box<dyn Stream<Item = VenueMarketData> + Send>>;
+
+pub struct MarketDataFanout {
+    sender: broadcast::Sender<VenueMarketData>,
+    feeds: Mutex<HashMap<VenueId, JoinHandle<()>>>,
+    clocks: Mutex<HashMap<VenueId, SharedClockSkew>>,
+}
+
+impl MarketDataFanout {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self {
+            sender,
+            feeds: Mutex::new(HashMap::new()),
+            clocks: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Puts the timestamps of `venue`'s feeds added from now on onto our
+    /// clock with `skew`.
+    pub fn set_clock_skew(&self, venue: VenueId, skew: SharedClockSkew) {
+        self.clocks.lock().unwrap_or_else(|e| e.into_inner()).insert(venue, skew);
+    }
+
+    /// Forwards `stream` to every subscriber as `venue`'s feed, replacing
+    /// the feed it had. Errors on the stream are logged and skipped.
+    pub fn add_feed(&self, venue: VenueId, mut stream: MarketDataStream) {
+        let sender = self.sender.clone();
+        let tag = venue.clone();
+        let clock = self.clocks.lock().unwrap_or_else(|e| e.into_inner()).get(&venue).cloned();
+        let task = tokio::spawn(async move {
+            while let Some(update) = stream.next().await {
+                match update {
+                    // No subscribers is not an error; the update is dropped
+                    Ok(data) => {
+                        let local_time = match &clock {
+                            Some(clock) => clock.lock().unwrap_or_else(|e| e.into_inner()).to_local(data.timestamp()),
+                            None => data.timestamp(),
+                        };
+                        let _ = sender.send(VenueMarketData { venue: tag.clone(), data, local_time });
+                    }
+                    Err(e) => warn!("Market data error from {}: {}", tag, e),
+                }
+            }
+            info!("Market data feed from {} ended", tag);
+        });
+
+        let mut feeds = self.feeds.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(previous) = feeds.insert(venue, task) {
+            previous.abort();
+        }
+    }
+
+    pub fn remove_feed(&self, venue: &VenueId) {
+        let mut feeds = self.feeds.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(task) = feeds.remove(venue) {
+            task.abort();
+        }
+    }
+
+    /// Whether `venue`'s feed is still being forwarded.
+    pub fn has_feed(&self, venue: &VenueId) -> bool {
+        let feeds = self.feeds.lock().unwrap_or_else(|e| e.into_inner());
+        feeds.get(venue).is_some_and(|task| !task.is_finished())
+    }
+
+    /// Venues whose feeds are still being forwarded.
+    pub fn venues(&self) -> Vec<VenueId> {
+        let feeds = self.feeds.lock().unwrap_or_else(|e| e.into_inner());
+        feeds
+            .iter()
+            .filter(|(_, task)| !task.is_finished())
+            .map(|(venue, _)| venue.clone())
+            .collect()
+    }
+
+    /// A new subscriber's view of every feed, from now on. It ends once the
+    /// fan-out is dropped.
+    pub fn subscribe(&self) -> VenueMarketDataStream {
+        let receiver = self.sender.subscribe();
+        Box::pin(futures::stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(update) => return Some((update, receiver)),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Market data subscriber fell behind, skipped {} updates", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        }))
+    }
+
+    pub fn subscriber_count(&self) -> usize {
+        self.sender.receiver_count()
+    }
+}
+
+impl Default for MarketDataFanout {
+    fn default() -> Self {
+        Self::new(DEFAULT_FANOUT_CAPACITY)
+    }
+}
+
+impl Drop for MarketDataFanout {
+    fn drop(&mut self) {
+        let feeds = self.feeds.get_mut().unwrap_or_else(|e| e.into_inner());
+        for (_, task) in feeds.drain() {
+            task.abort();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arbfinder_core::{OrderBook, Symbol};
+
+    fn feed(symbols: &[&str]) -> MarketDataStream {
+        let updates: Vec<_> = symbols
+            .iter()
+            .map(|base| Ok(MarketData::OrderBook(OrderBook::new(Symbol::new(*base, "USDT")))))
+            .collect();
+        // Kept open, as a live venue's stream would be
+        Box::pin(futures::stream::iter(updates).chain(futures::stream::pending()))
+    }
+
+    #[tokio::test]
+    async fn test_every_subscriber_sees_every_venue() {
+        let fanout = MarketDataFanout::new(16);
+        let mut strategy = fanout.subscribe();
+        let mut recorder = fanout.subscribe();
+        assert_eq!(fanout.subscriber_count(), 2);
+
+        fanout.add_feed(VenueId::Binance, feed(&["BTC", "ETH"]));
+        fanout.add_feed(VenueId::Kraken, feed(&["BTC"]));
+
+        for subscriber in [&mut strategy, &mut recorder] {
+            let mut seen = Vec::new();
+            for _ in 0..3 {
+                let update = subscriber.next().await.unwrap();
+                seen.push((update.venue, update.data.symbol().base().to_string()));
+            }
+            seen.sort_by_key(|(venue, base)| (venue.to_string(), base.clone()));
+            assert_eq!(
+                seen,
+                [
+                    (VenueId::Binance, "BTC".to_string()),
+                    (VenueId::Binance, "ETH".to_string()),
+                    (VenueId::Kraken, "BTC".to_string()),
+                ]
+            );
+        }
+
+        let mut venues = fanout.venues();
+        venues.sort_by_key(|venue| venue.to_string());
+        assert_eq!(venues, [VenueId::Binance, VenueId::Kraken]);
+        fanout.remove_feed(&VenueId::Kraken);
+        assert!(!fanout.has_feed(&VenueId::Kraken));
+    }
+
+    #[tokio::test]
+    async fn test_timestamps_on_our_clock() {
+        use arbfinder_core::utils::{ClockSkew, SkewSample};
+
+        let fanout = MarketDataFanout::new(16);
+        let mut updates = fanout.subscribe();
+        let now = Utc::now();
+        let mut skew = ClockSkew::default();
+        // Kraken's clock 500ms ahead of ours
+        skew.record(SkewSample::measure(now, now + chrono::Duration::milliseconds(500), now));
+        fanout.set_clock_skew(VenueId::Kraken, skew.shared());
+
+        fanout.add_feed(VenueId::Kraken, feed(&["BTC"]));
+        let update = updates.next().await.unwrap();
+        assert_eq!(update.local_time, update.data.timestamp() - chrono::Duration::milliseconds(500));
+
+        fanout.add_feed(VenueId::Binance, feed(&["BTC"]));
+        let update = updates.next().await.unwrap();
+        assert_eq!(update.local_time, update.data.timestamp());
+    }
+}
@@ -0,0 +1,338 @@
+//! FIX messages on the wire: `tag=value` fields separated by SOH, framed
+//! by BeginString and BodyLength and closed by a checksum.
+
+use std::fmt;
+use std::str::FromStr;
+
+use arbfinder_core::{ArbFinderError, Result};
+
+/// Field separator.
+pub const SOH: u8 = 0x01;
+
+pub mod tags {
+    // Header and trailer
+    pub const BEGIN_STRING: u32 = 8;
+    pub const BODY_LENGTH: u32 = 9;
+    pub const CHECKSUM: u32 = 10;
+    pub const MSG_SEQ_NUM: u32 = 34;
+    pub const MSG_TYPE: u32 = 35;
+    pub const POSS_DUP_FLAG: u32 = 43;
+    pub const SENDER_COMP_ID: u32 = 49;
+    pub const SENDING_TIME: u32 = 52;
+    pub const TARGET_COMP_ID: u32 = 56;
+    pub const ORIG_SENDING_TIME: u32 = 122;
+
+    // Session
+    pub const BEGIN_SEQ_NO: u32 = 7;
+    pub const END_SEQ_NO: u32 = 16;
+    pub const NEW_SEQ_NO: u32 = 36;
+    pub const REF_SEQ_NUM: u32 = 45;
+    pub const TEXT: u32 = 58;
+    pub const ENCRYPT_METHOD: u32 = 98;
+    pub const HEART_BT_INT: u32 = 108;
+    pub const TEST_REQ_ID: u32 = 112;
+    pub const GAP_FILL_FLAG: u32 = 123;
+    pub const RESET_SEQ_NUM_FLAG: u32 = 141;
+    pub const USERNAME: u32 = 553;
+    pub const PASSWORD: u32 = 554;
+
+    // Order entry
+    pub const AVG_PX: u32 = 6;
+    pub const CL_ORD_ID: u32 = 11;
+    pub const CUM_QTY: u32 = 14;
+    pub const EXEC_ID: u32 = 17;
+    pub const HANDL_INST: u32 = 21;
+    pub const LAST_PX: u32 = 31;
+    pub const LAST_QTY: u32 = 32;
+    pub const ORDER_ID: u32 = 37;
+    pub const ORDER_QTY: u32 = 38;
+    pub const ORD_STATUS: u32 = 39;
+    pub const ORD_TYPE: u32 = 40;
+    pub const ORIG_CL_ORD_ID: u32 = 41;
+    pub const PRICE: u32 = 44;
+    pub const SIDE: u32 = 54;
+    pub const SYMBOL: u32 = 55;
+    pub const TIME_IN_FORCE: u32 = 59;
+    pub const TRANSACT_TIME: u32 = 60;
+    pub const STOP_PX: u32 = 99;
+    pub const EXEC_INST: u32 = 18;
+    pub const ORD_REJ_REASON: u32 = 103;
+    pub const EXEC_TYPE: u32 = 150;
+    pub const LEAVES_QTY: u32 = 151;
+
+    // Market data
+    pub const NO_RELATED_SYM: u32 = 146;
+    pub const MD_REQ_ID: u32 = 262;
+    pub const SUBSCRIPTION_REQUEST_TYPE: u32 = 263;
+    pub const MARKET_DEPTH: u32 = 264;
+    pub const MD_UPDATE_TYPE: u32 = 265;
+    pub const NO_MD_ENTRY_TYPES: u32 = 267;
+    pub const NO_MD_ENTRIES: u32 = 268;
+    pub const MD_ENTRY_TYPE: u32 = 269;
+    pub const MD_ENTRY_PX: u32 = 270;
+    pub const MD_ENTRY_SIZE: u32 = 271;
+    pub const MD_UPDATE_ACTION: u32 = 279;
+}
+
+pub mod msg_type {
+    pub const HEARTBEAT: &str = "0";
+    pub const TEST_REQUEST: &str = "1";
+    pub const RESEND_REQUEST: &str = "2";
+    pub const REJECT: &str = "3";
+    pub const SEQUENCE_RESET: &str = "4";
+    pub const LOGOUT: &str = "5";
+    pub const EXECUTION_REPORT: &str = "8";
+    pub const ORDER_CANCEL_REJECT: &str = "9";
+    pub const LOGON: &str = "A";
+    pub const NEW_ORDER_SINGLE: &str = "D";
+    pub const ORDER_CANCEL_REQUEST: &str = "F";
+    pub const ORDER_CANCEL_REPLACE_REQUEST: &str = "G";
+    pub const MARKET_DATA_REQUEST: &str = "V";
+    pub const MARKET_DATA_SNAPSHOT: &str = "W";
+    pub const MARKET_DATA_INCREMENTAL: &str = "X";
+    pub const MARKET_DATA_REQUEST_REJECT: &str = "Y";
+}
+
+/// A message's fields in order, from MsgType on, without the framing
+/// BeginString, BodyLength and CheckSum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FixMessage {
+    fields: Vec<(u32, String)>,
+}
+
+impl FixMessage {
+    pub fn new(msg_type: &str) -> Self {
+        Self {
+            fields: vec![(tags::MSG_TYPE, msg_type.to_string())],
+        }
+    }
+
+    pub fn with(mut self, tag: u32, value: impl ToString) -> Self {
+        self.push(tag, value);
+        self
+    }
+
+    /// Appends a field, repeating `tag` if it is already present, as in
+    /// repeating groups.
+    pub fn push(&mut self, tag: u32, value: impl ToString) {
+        self.fields.push((tag, value.to_string()));
+    }
+
+    /// Sets the first `tag` field, or appends one.
+    pub fn set(&mut self, tag: u32, value: impl ToString) {
+        match self.fields.iter_mut().find(|(t, _)| *t == tag) {
+            Some(field) => field.1 = value.to_string(),
+            None => self.push(tag, value),
+        }
+    }
+
+    pub fn remove(&mut self, tag: u32) {
+        self.fields.retain(|(t, _)| *t != tag);
+    }
+
+    pub fn msg_type(&self) -> &str {
+        self.get(tags::MSG_TYPE).unwrap_or_default()
+    }
+
+    /// The first `tag` field's value.
+    pub fn get(&self, tag: u32) -> Option<&str> {
+        self.fields.iter().find(|(t, _)| *t == tag).map(|(_, value)| value.as_str())
+    }
+
+    pub fn get_parsed<T: FromStr>(&self, tag: u32) -> Option<T> {
+        self.get(tag).and_then(|value| value.parse().ok())
+    }
+
+    /// Whether the `tag` flag is `Y`.
+    pub fn flag(&self, tag: u32) -> bool {
+        self.get(tag) == Some("Y")
+    }
+
+    pub fn seq_num(&self) -> Option<u64> {
+        self.get_parsed(tags::MSG_SEQ_NUM)
+    }
+
+    pub fn fields(&self) -> &[(u32, String)] {
+        &self.fields
+    }
+
+    /// Entries of the repeating group counted by `count_tag`, each starting
+    /// at its `first_tag` field.
+    pub fn group(&self, count_tag: u32, first_tag: u32) -> Vec<Vec<(u32, &str)>> {
+        let Some(start) = self.fields.iter().position(|(t, _)| *t == count_tag) else {
+            return Vec::new();
+        };
+        let count: usize = self.fields[start].1.parse().unwrap_or(0);
+        // The count is off the wire; each entry takes at least one field
+        let rest = &self.fields[start + 1..];
+        let mut entries: Vec<Vec<(u32, &str)>> = Vec::with_capacity(count.min(rest.len()));
+        for (tag, value) in rest {
+            if *tag == first_tag {
+                if entries.len() == count {
+                    break;
+                }
+                entries.push(Vec::new());
+            }
+            match entries.last_mut() {
+                Some(entry) => entry.push((*tag, value.as_str())),
+                None => break,
+            }
+        }
+        entries
+    }
+
+    /// The message framed for the wire under `begin_string`.
+    pub fn encode(&self, begin_string: &str) -> Vec<u8> {
+        let mut body = Vec::new();
+        for (tag, value) in &self.fields {
+            body.extend_from_slice(format!("{}={}", tag, value).as_bytes());
+            body.push(SOH);
+        }
+
+        let mut frame = format!("8={}\u{1}9={}\u{1}", begin_string, body.len()).into_bytes();
+        frame.extend_from_slice(&body);
+        let checksum = checksum(&frame);
+        frame.extend_from_slice(format!("10={:03}\u{1}", checksum).as_bytes());
+        frame
+    }
+
+    /// Parses one framed message, checking its body length and checksum.
+    pub fn decode(frame: &[u8]) -> Result<Self> {
+        let trailer = frame
+            .len()
+            .checked_sub(7)
+            .filter(|&at| frame[at..].starts_with(b"10=") && frame.ends_with(&[SOH]))
+            .ok_or_else(|| invalid("missing checksum"))?;
+        let expected: u8 = std::str::from_utf8(&frame[trailer + 3..trailer + 6])
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .ok_or_else(|| invalid("malformed checksum"))?;
+        if checksum(&frame[..trailer]) != expected {
+            return Err(invalid("checksum mismatch"));
+        }
+
+        let text = std::str::from_utf8(&frame[..trailer]).map_err(|_| invalid("not UTF-8"))?;
+        let mut fields = Vec::new();
+        for field in text.split('\u{1}').filter(|field| !field.is_empty()) {
+            let (tag, value) = field.split_once('=').ok_or_else(|| invalid("field without '='"))?;
+            let tag: u32 = tag.parse().map_err(|_| invalid("non-numeric tag"))?;
+            fields.push((tag, value.to_string()));
+        }
+
+        match fields.as_slice() {
+            [(tags::BEGIN_STRING, _), (tags::BODY_LENGTH, length), (tags::MSG_TYPE, _), ..] => {
+                let header = text.find("\u{1}35=").map(|at| at + 1).unwrap_or_default();
+                if length.parse::<usize>().ok() != Some(trailer - header) {
+                    return Err(invalid("body length mismatch"));
+                }
+            }
+            _ => return Err(invalid("header out of order")),
+        }
+        fields.drain(..2);
+        Ok(Self { fields })
+    }
+}
+
+impl fmt::Display for FixMessage {
+    /// Fields separated by `|`, as FIX logs are usually read.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (tag, value)) in self.fields.iter().enumerate() {
+            if i > 0 {
+                f.write_str("|")?;
+            }
+            match *tag {
+                tags::PASSWORD => write!(f, "{}=***", tag)?,
+                _ => write!(f, "{}={}", tag, value)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Sum of the bytes modulo 256.
+fn checksum(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte))
+}
+
+fn invalid(reason: &str) -> ArbFinderError {
+    ArbFinderError::Parse(format!("Invalid FIX message: {}", reason))
+}
+
+/// Takes the first complete message off the front of `buffer`, leaving
+/// whatever follows. Garbage before a BeginString is discarded.
+pub fn take_frame(buffer: &mut Vec<u8>) -> Option<Vec<u8>> {
+    match buffer.windows(2).position(|window| window == b"8=") {
+        Some(0) => {}
+        Some(at) => {
+            buffer.drain(..at);
+        }
+        None => {
+            buffer.clear();
+            return None;
+        }
+    }
+
+    // 8=FIX.4.4|9=123|
+    let begin_end = buffer.iter().position(|&b| b == SOH)?;
+    let length_end = begin_end + 1 + buffer[begin_end + 1..].iter().position(|&b| b == SOH)?;
+    let length: usize = std::str::from_utf8(buffer[begin_end + 1..length_end].strip_prefix(b"9=")?)
+        .ok()?
+        .parse()
+        .ok()?;
+    let end = length_end + 1 + length + 7;
+    if buffer.len() < end {
+        return None;
+    }
+    Some(buffer.drain(..end).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_decode_and_framing() {
+        let message = FixMessage::new(msg_type::LOGON)
+            .with(tags::MSG_SEQ_NUM, 1)
+            .with(tags::ENCRYPT_METHOD, 0)
+            .with(tags::HEART_BT_INT, 30);
+        let frame = message.encode("FIX.4.4");
+        let text = String::from_utf8(frame.clone()).unwrap().replace('\u{1}', "|");
+        assert_eq!(text, "8=FIX.4.4|9=22|35=A|34=1|98=0|108=30|10=210|");
+        assert_eq!(FixMessage::decode(&frame).unwrap(), message);
+
+        let mut corrupt = frame.clone();
+        corrupt[20] = b'9';
+        assert!(FixMessage::decode(&corrupt).is_err());
+
+        // Split across reads, with a second message behind it
+        let mut buffer = frame[..10].to_vec();
+        assert_eq!(take_frame(&mut buffer), None);
+        buffer.extend_from_slice(&frame[10..]);
+        buffer.extend_from_slice(&frame[..5]);
+        assert_eq!(take_frame(&mut buffer), Some(frame.clone()));
+        assert_eq!(buffer, frame[..5]);
+    }
+
+    #[test]
+    fn test_repeating_group() {
+        let message = FixMessage::new(msg_type::MARKET_DATA_SNAPSHOT)
+            .with(tags::SYMBOL, "BTC-USD")
+            .with(tags::NO_MD_ENTRIES, 2)
+            .with(tags::MD_ENTRY_TYPE, 0)
+            .with(tags::MD_ENTRY_PX, "100.5")
+            .with(tags::MD_ENTRY_SIZE, "2")
+            .with(tags::MD_ENTRY_TYPE, 1)
+            .with(tags::MD_ENTRY_PX, "101");
+        let entries = message.group(tags::NO_MD_ENTRIES, tags::MD_ENTRY_TYPE);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0], [(269, "0"), (270, "100.5"), (271, "2")]);
+        assert_eq!(entries[1], [(269, "1"), (270, "101")]);
+
+        // An oversized count yields only the entries actually present
+        let message = FixMessage::new(msg_type::MARKET_DATA_SNAPSHOT)
+            .with(tags::NO_MD_ENTRIES, usize::MAX)
+            .with(tags::MD_ENTRY_TYPE, 0);
+        assert_eq!(message.group(tags::NO_MD_ENTRIES, tags::MD_ENTRY_TYPE), [vec![(269, "0")]]);
+    }
+}
@@ -0,0 +1,344 @@
+//! Market data and order entry messages of FIX 4.4.
+//!
+//! Symbols are the venue's own, as the caller's symbol mapping gives them.
+
+use std::str::FromStr;
+
+use arbfinder_core::{
+    ArbFinderError, OrderBook, OrderId, OrderRequest, OrderSide, OrderStatus, OrderType, OrderUpdate, Result,
+    SizeUnit, TimeInForce,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use rust_decimal::Decimal;
+
+use super::message::{msg_type, tags, FixMessage};
+
+/// Subscribes to `symbols`' books `depth` levels deep (0 for the full
+/// book), as a snapshot followed by incremental refreshes.
+pub fn market_data_request(md_req_id: &str, symbols: &[&str], depth: u32) -> FixMessage {
+    let mut request = FixMessage::new(msg_type::MARKET_DATA_REQUEST)
+        .with(tags::MD_REQ_ID, md_req_id)
+        .with(tags::SUBSCRIPTION_REQUEST_TYPE, 1)
+        .with(tags::MARKET_DEPTH, depth)
+        .with(tags::MD_UPDATE_TYPE, 1)
+        .with(tags::NO_MD_ENTRY_TYPES, 2)
+        .with(tags::MD_ENTRY_TYPE, 0)
+        .with(tags::MD_ENTRY_TYPE, 1)
+        .with(tags::NO_RELATED_SYM, symbols.len());
+    for symbol in symbols {
+        request.push(tags::SYMBOL, symbol);
+    }
+    request
+}
+
+/// Ends the subscription made under `md_req_id`.
+pub fn market_data_unsubscribe(md_req_id: &str) -> FixMessage {
+    FixMessage::new(msg_type::MARKET_DATA_REQUEST)
+        .with(tags::MD_REQ_ID, md_req_id)
+        .with(tags::SUBSCRIPTION_REQUEST_TYPE, 2)
+        .with(tags::MARKET_DEPTH, 0)
+}
+
+/// Applies a snapshot (W), which replaces `book`, or an incremental
+/// refresh (X) to `book`, skipping entries for symbols other than
+/// `symbol`. Returns whether anything applied.
+pub fn apply_market_data(book: &mut OrderBook, symbol: &str, message: &FixMessage) -> Result<bool> {
+    let snapshot = match message.msg_type() {
+        msg_type::MARKET_DATA_SNAPSHOT => true,
+        msg_type::MARKET_DATA_INCREMENTAL => false,
+        other => {
+            return Err(ArbFinderError::InvalidData(format!("Not a FIX market data message: {}", other)));
+        }
+    };
+    if snapshot && message.get(tags::SYMBOL) != Some(symbol) {
+        return Ok(false);
+    }
+
+    let first_tag = if snapshot { tags::MD_ENTRY_TYPE } else { tags::MD_UPDATE_ACTION };
+    let entries = message.group(tags::NO_MD_ENTRIES, first_tag);
+    if snapshot {
+        book.bids.clear();
+        book.asks.clear();
+    }
+
+    let mut applied = snapshot;
+    for entry in entries {
+        let field = |tag: u32| entry.iter().find(|(t, _)| *t == tag).map(|(_, value)| *value);
+        if field(tags::SYMBOL).is_some_and(|entry_symbol| entry_symbol != symbol) {
+            continue;
+        }
+        let price = decimal(field(tags::MD_ENTRY_PX), tags::MD_ENTRY_PX)?;
+        let quantity = match field(tags::MD_UPDATE_ACTION) {
+            // Delete
+            Some("2") => Decimal::ZERO,
+            _ => decimal(field(tags::MD_ENTRY_SIZE), tags::MD_ENTRY_SIZE)?,
+        };
+        match field(tags::MD_ENTRY_TYPE) {
+            Some("0") => book.update_bid(price, quantity),
+            Some("1") => book.update_ask(price, quantity),
+            // Trades, indices and the like
+            _ => continue,
+        }
+        applied = true;
+    }
+    Ok(applied)
+}
+
+/// A NewOrderSingle for `order` on `symbol`.
+pub fn new_order_single(cl_ord_id: &str, symbol: &str, order: &OrderRequest) -> Result<FixMessage> {
+    if order.size_unit == SizeUnit::Quote {
+        return Err(ArbFinderError::InvalidOrder(
+            "FIX orders are sized in the base asset".to_string(),
+        ));
+    }
+
+    let (ord_type, time_in_force) = match order.order_type {
+        OrderType::Market => ("1", order.time_in_force),
+        OrderType::Limit => ("2", order.time_in_force),
+        OrderType::StopMarket => ("3", order.time_in_force),
+        OrderType::StopLimit => ("4", order.time_in_force),
+        OrderType::PostOnly => ("2", TimeInForce::PostOnly),
+        OrderType::FillOrKill => ("2", TimeInForce::FillOrKill),
+        OrderType::ImmediateOrCancel => ("2", TimeInForce::ImmediateOrCancel),
+        OrderType::Oco => {
+            return Err(ArbFinderError::InvalidOrder(
+                "OCO orders are not supported over FIX".to_string(),
+            ));
+        }
+    };
+
+    let mut message = FixMessage::new(msg_type::NEW_ORDER_SINGLE)
+        .with(tags::CL_ORD_ID, cl_ord_id)
+        .with(tags::HANDL_INST, 1)
+        .with(tags::SYMBOL, symbol)
+        .with(tags::SIDE, side(order.side))
+        .with(tags::TRANSACT_TIME, Utc::now().format("%Y%m%d-%H:%M:%S%.3f"))
+        .with(tags::ORDER_QTY, order.quantity)
+        .with(tags::ORD_TYPE, ord_type);
+
+    if matches!(ord_type, "2" | "4") {
+        let price = order
+            .price
+            .ok_or_else(|| ArbFinderError::InvalidOrder("Limit order without a price".to_string()))?;
+        message.push(tags::PRICE, price);
+    }
+    if matches!(ord_type, "3" | "4") {
+        let stop_price = order
+            .stop_price
+            .ok_or_else(|| ArbFinderError::InvalidOrder("Stop order without a stop price".to_string()))?;
+        message.push(tags::STOP_PX, stop_price);
+    }
+
+    let time_in_force = match time_in_force {
+        TimeInForce::GoodTillCanceled | TimeInForce::PostOnly => "1",
+        TimeInForce::ImmediateOrCancel => "3",
+        TimeInForce::FillOrKill => "4",
+    };
+    // Market orders take the venue's default
+    if ord_type != "1" {
+        message.push(tags::TIME_IN_FORCE, time_in_force);
+    }
+    if order.post_only || matches!(order.order_type, OrderType::PostOnly) || order.time_in_force == TimeInForce::PostOnly {
+        // Participate, don't initiate
+        message.push(tags::EXEC_INST, "6");
+    }
+    Ok(message)
+}
+
+/// An OrderCancelRequest for the order placed as `orig_cl_ord_id`.
+pub fn order_cancel_request(
+    cl_ord_id: &str,
+    orig_cl_ord_id: &str,
+    order_id: Option<&str>,
+    symbol: &str,
+    order_side: OrderSide,
+) -> FixMessage {
+    let mut message = FixMessage::new(msg_type::ORDER_CANCEL_REQUEST)
+        .with(tags::ORIG_CL_ORD_ID, orig_cl_ord_id)
+        .with(tags::CL_ORD_ID, cl_ord_id);
+    if let Some(order_id) = order_id {
+        message.push(tags::ORDER_ID, order_id);
+    }
+    message
+        .with(tags::SYMBOL, symbol)
+        .with(tags::SIDE, side(order_side))
+        .with(tags::TRANSACT_TIME, Utc::now().format("%Y%m%d-%H:%M:%S%.3f"))
+}
+
+fn side(side: OrderSide) -> &'static str {
+    match side {
+        OrderSide::Buy => "1",
+        OrderSide::Sell => "2",
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionReport {
+    pub order_id: String,
+    pub cl_ord_id: Option<String>,
+    pub orig_cl_ord_id: Option<String>,
+    pub exec_id: String,
+    /// ExecType as sent, e.g. `F` for a trade.
+    pub exec_type: String,
+    pub status: OrderStatus,
+    pub symbol: String,
+    pub side: Option<OrderSide>,
+    pub last_qty: Option<Decimal>,
+    pub last_px: Option<Decimal>,
+    pub cum_qty: Decimal,
+    pub leaves_qty: Decimal,
+    pub avg_px: Option<Decimal>,
+    pub text: Option<String>,
+    pub transact_time: Option<DateTime<Utc>>,
+}
+
+impl ExecutionReport {
+    /// Whether this report is of a fill.
+    pub fn is_trade(&self) -> bool {
+        self.exec_type == "F"
+    }
+
+    /// The report as an update to the order tracked as `order_id`.
+    pub fn to_order_update(&self, order_id: OrderId) -> OrderUpdate {
+        OrderUpdate {
+            order_id,
+            venue_order_id: Some(self.order_id.clone()),
+            status: self.status,
+            filled_quantity: self.cum_qty,
+            remaining_quantity: self.leaves_qty,
+            average_fill_price: self.avg_px.filter(|price| !price.is_zero()),
+            timestamp: self.transact_time.unwrap_or_else(Utc::now),
+            reason: self.text.clone(),
+        }
+    }
+}
+
+/// Reads an ExecutionReport (8).
+pub fn parse_execution_report(message: &FixMessage) -> Result<ExecutionReport> {
+    if message.msg_type() != msg_type::EXECUTION_REPORT {
+        return Err(ArbFinderError::InvalidData(format!(
+            "Not a FIX execution report: {}",
+            message.msg_type()
+        )));
+    }
+    let required = |tag: u32| {
+        message
+            .get(tag)
+            .map(str::to_string)
+            .ok_or_else(|| ArbFinderError::InvalidData(format!("FIX execution report without tag {}", tag)))
+    };
+    let optional_decimal = |tag: u32| message.get(tag).map(|value| decimal(Some(value), tag)).transpose();
+
+    let status = match required(tags::ORD_STATUS)?.as_str() {
+        "A" => OrderStatus::Pending,
+        // New, pending cancel or replace, and replaced orders rest on the book
+        "0" | "5" | "6" | "E" => OrderStatus::Open,
+        "1" => OrderStatus::PartiallyFilled,
+        "2" => OrderStatus::Filled,
+        "4" => OrderStatus::Canceled,
+        "8" => OrderStatus::Rejected,
+        "3" | "C" => OrderStatus::Expired,
+        other => {
+            return Err(ArbFinderError::InvalidData(format!("Unknown FIX OrdStatus: {}", other)));
+        }
+    };
+
+    Ok(ExecutionReport {
+        order_id: required(tags::ORDER_ID)?,
+        cl_ord_id: message.get(tags::CL_ORD_ID).map(str::to_string),
+        orig_cl_ord_id: message.get(tags::ORIG_CL_ORD_ID).map(str::to_string),
+        exec_id: required(tags::EXEC_ID)?,
+        exec_type: required(tags::EXEC_TYPE)?,
+        status,
+        symbol: required(tags::SYMBOL)?,
+        side: match message.get(tags::SIDE) {
+            Some("1") => Some(OrderSide::Buy),
+            Some("2") => Some(OrderSide::Sell),
+            _ => None,
+        },
+        last_qty: optional_decimal(tags::LAST_QTY)?,
+        last_px: optional_decimal(tags::LAST_PX)?,
+        cum_qty: decimal(message.get(tags::CUM_QTY), tags::CUM_QTY)?,
+        leaves_qty: decimal(message.get(tags::LEAVES_QTY), tags::LEAVES_QTY)?,
+        avg_px: optional_decimal(tags::AVG_PX)?,
+        text: message.get(tags::TEXT).map(str::to_string),
+        transact_time: message
+            .get(tags::TRANSACT_TIME)
+            .and_then(|value| NaiveDateTime::parse_from_str(value, "%Y%m%d-%H:%M:%S%.f").ok())
+            .map(|time| time.and_utc()),
+    })
+}
+
+fn decimal(value: Option<&str>, tag: u32) -> Result<Decimal> {
+    let value = value.ok_or_else(|| ArbFinderError::InvalidData(format!("FIX message without tag {}", tag)))?;
+    Decimal::from_str(value).map_err(|e| ArbFinderError::Parse(format!("FIX tag {} = {}: {}", tag, value, e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arbfinder_core::Symbol;
+
+    #[test]
+    fn test_market_data_and_execution_reports() {
+        let mut book = OrderBook::new(Symbol::new("BTC", "USD"));
+        let snapshot = FixMessage::new(msg_type::MARKET_DATA_SNAPSHOT)
+            .with(tags::SYMBOL, "BTC-USD")
+            .with(tags::NO_MD_ENTRIES, 2)
+            .with(tags::MD_ENTRY_TYPE, 0)
+            .with(tags::MD_ENTRY_PX, "100")
+            .with(tags::MD_ENTRY_SIZE, "1.5")
+            .with(tags::MD_ENTRY_TYPE, 1)
+            .with(tags::MD_ENTRY_PX, "101")
+            .with(tags::MD_ENTRY_SIZE, "2");
+        assert!(apply_market_data(&mut book, "BTC-USD", &snapshot).unwrap());
+        assert_eq!(book.bids.len(), 1);
+
+        let refresh = FixMessage::new(msg_type::MARKET_DATA_INCREMENTAL)
+            .with(tags::NO_MD_ENTRIES, 2)
+            .with(tags::MD_UPDATE_ACTION, 2)
+            .with(tags::MD_ENTRY_TYPE, 0)
+            .with(tags::SYMBOL, "BTC-USD")
+            .with(tags::MD_ENTRY_PX, "100")
+            .with(tags::MD_UPDATE_ACTION, 0)
+            .with(tags::MD_ENTRY_TYPE, 0)
+            .with(tags::SYMBOL, "ETH-USD")
+            .with(tags::MD_ENTRY_PX, "3000")
+            .with(tags::MD_ENTRY_SIZE, "1");
+        assert!(apply_market_data(&mut book, "BTC-USD", &refresh).unwrap());
+        assert!(book.bids.is_empty());
+        assert_eq!(book.asks.len(), 1);
+
+        let order = OrderRequest {
+            order_type: OrderType::PostOnly,
+            price: Some(Decimal::from(100)),
+            ..OrderRequest::new_market(Symbol::new("BTC", "USD"), OrderSide::Buy, Decimal::new(5, 1))
+        };
+        let message = new_order_single("c1", "BTC-USD", &order).unwrap();
+        assert_eq!(message.get(tags::ORD_TYPE), Some("2"));
+        assert_eq!(message.get(tags::PRICE), Some("100"));
+        assert_eq!(message.get(tags::EXEC_INST), Some("6"));
+
+        let report = FixMessage::new(msg_type::EXECUTION_REPORT)
+            .with(tags::ORDER_ID, "v1")
+            .with(tags::CL_ORD_ID, "c1")
+            .with(tags::EXEC_ID, "e1")
+            .with(tags::EXEC_TYPE, "F")
+            .with(tags::ORD_STATUS, "1")
+            .with(tags::SYMBOL, "BTC-USD")
+            .with(tags::SIDE, "1")
+            .with(tags::LAST_QTY, "0.2")
+            .with(tags::LAST_PX, "100")
+            .with(tags::CUM_QTY, "0.2")
+            .with(tags::LEAVES_QTY, "0.3")
+            .with(tags::AVG_PX, "100")
+            .with(tags::TRANSACT_TIME, "20240102-03:04:05.678");
+        let report = parse_execution_report(&report).unwrap();
+        assert!(report.is_trade());
+        assert_eq!(report.status, OrderStatus::PartiallyFilled);
+        assert_eq!(report.leaves_qty, Decimal::new(3, 1));
+        let update = report.to_order_update(OrderId::new());
+        assert_eq!(update.venue_order_id.as_deref(), Some("v1"));
+        assert_eq!(update.timestamp.timestamp_subsec_millis(), 678);
+    }
+}
@@ -0,0 +1,365 @@
+//! FIX 4.4 connectivity, for venues that offer FIX rather than (or as well
+//! as) REST and WebSocket APIs, such as Coinbase's FIX gateways and LMAX.
+//!
+//! [`message`] frames and parses messages, [`session`] runs the session
+//! layer (logon, heartbeats, sequence numbers and resends) without I/O,
+//! and [`messages`] builds and reads market data and order entry
+//! messages. [`FixClient`] runs a session over TCP, optionally inside TLS
+//! and through a proxy, and reports what arrives as [`FixEvent`]s.
+
+pub mod message;
+pub mod messages;
+pub mod session;
+
+pub use message::{msg_type, tags, FixMessage};
+pub use messages::{
+    apply_market_data, market_data_request, market_data_unsubscribe, new_order_single, order_cancel_request,
+    parse_execution_report, ExecutionReport,
+};
+pub use session::{FixEvent, FixSession, FixSessionConfig, SessionState};
+
+use std::time::{Duration, Instant};
+
+use arbfinder_core::config::ProxyConfig;
+use arbfinder_core::{ArbFinderError, Result};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::sync::{mpsc, oneshot};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tokio_native_tls::native_tls;
+use tracing::{info, warn};
+
+use crate::pinning::TlsPins;
+use crate::proxy::Proxy;
+
+/// How long the venue has to answer a Logon by default.
+pub const DEFAULT_FIX_LOGON_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone)]
+pub struct FixConfig {
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+    /// Keys the venue's certificate must carry; requires `tls`.
+    pub tls_pins: Option<TlsPins>,
+    pub proxy: Option<ProxyConfig>,
+    pub logon_timeout: Duration,
+    pub session: FixSessionConfig,
+}
+
+impl FixConfig {
+    pub fn new(host: impl Into<String>, port: u16, session: FixSessionConfig) -> Self {
+        Self {
+            host: host.into(),
+            port,
+            tls: true,
+            tls_pins: None,
+            proxy: None,
+            logon_timeout: DEFAULT_FIX_LOGON_TIMEOUT,
+            session,
+        }
+    }
+}
+
+trait FixStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> FixStream for T {}
+
+enum Command {
+    Send(FixMessage, oneshot::Sender<Result<u64>>),
+    Logout,
+}
+
+/// A logged-on FIX session. Dropping it logs out.
+pub struct FixClient {
+    target_comp_id: String,
+    commands: mpsc::UnboundedSender<Command>,
+    task: JoinHandle<()>,
+}
+
+impl FixClient {
+    /// Connects and logs on, returning the client and the events that
+    /// follow the logon.
+    pub async fn connect(config: FixConfig) -> Result<(Self, mpsc::UnboundedReceiver<FixEvent>)> {
+        let stream = open(&config).await?;
+        let target_comp_id = config.session.target_comp_id.clone();
+        let mut session = FixSession::new(config.session);
+        session.logon(Instant::now());
+
+        let (commands, command_rx) = mpsc::unbounded_channel();
+        let (events, mut event_rx) = mpsc::unbounded_channel();
+        let task = tokio::spawn(run(stream, session, command_rx, events));
+        let client = Self {
+            target_comp_id,
+            commands,
+            task,
+        };
+
+        match tokio::time::timeout(config.logon_timeout, event_rx.recv()).await {
+            Ok(Some(FixEvent::LoggedOn)) => {
+                info!("FIX session with {} logged on", client.target_comp_id);
+                Ok((client, event_rx))
+            }
+            Ok(Some(FixEvent::LoggedOut(text))) => Err(ArbFinderError::Authentication(format!(
+                "FIX logon to {} refused: {}",
+                client.target_comp_id,
+                text.unwrap_or_default()
+            ))),
+            Ok(Some(event)) => Err(ArbFinderError::Exchange(format!(
+                "FIX logon to {} failed: {:?}",
+                client.target_comp_id, event
+            ))),
+            Ok(None) => Err(ArbFinderError::Exchange(format!(
+                "FIX connection to {} closed during logon",
+                client.target_comp_id
+            ))),
+            Err(_) => Err(ArbFinderError::Timeout(format!(
+                "FIX logon to {} timed out",
+                client.target_comp_id
+            ))),
+        }
+    }
+
+    /// Sends an application message, returning its sequence number.
+    pub async fn send(&self, message: FixMessage) -> Result<u64> {
+        let (reply, sent) = oneshot::channel();
+        self.commands
+            .send(Command::Send(message, reply))
+            .map_err(|_| self.closed())?;
+        sent.await.map_err(|_| self.closed())?
+    }
+
+    /// Asks the venue to end the session; events end once it has.
+    pub fn logout(&self) {
+        let _ = self.commands.send(Command::Logout);
+    }
+
+    pub fn is_connected(&self) -> bool {
+        !self.task.is_finished()
+    }
+
+    fn closed(&self) -> ArbFinderError {
+        ArbFinderError::Exchange(format!("FIX session with {} is closed", self.target_comp_id))
+    }
+}
+
+impl Drop for FixClient {
+    fn drop(&mut self) {
+        self.logout();
+    }
+}
+
+/// Opens the connection, through the proxy if configured, and the TLS
+/// session inside it, checking the certificate against any pins.
+async fn open(config: &FixConfig) -> Result<Box<dyn FixStream>> {
+    if config.tls_pins.is_some() && !config.tls {
+        return Err(ArbFinderError::InvalidData(
+            "FIX certificate pins require TLS".to_string(),
+        ));
+    }
+
+    let tcp = match &config.proxy {
+        Some(proxy) => Proxy::from_config(proxy)?.connect(&config.host, config.port).await?,
+        None => TcpStream::connect((config.host.as_str(), config.port)).await?,
+    };
+    let _ = tcp.set_nodelay(true);
+    if !config.tls {
+        return Ok(Box::new(tcp));
+    }
+
+    let connector = native_tls::TlsConnector::new()
+        .map_err(|e| ArbFinderError::Exchange(format!("TLS setup failed: {}", e)))?;
+    let tls = tokio_native_tls::TlsConnector::from(connector)
+        .connect(&config.host, tcp)
+        .await
+        .map_err(|e| ArbFinderError::Exchange(format!("TLS handshake with {} failed: {}", config.host, e)))?;
+
+    if let Some(pins) = &config.tls_pins {
+        let certificate = tls
+            .get_ref()
+            .peer_certificate()
+            .ok()
+            .flatten()
+            .and_then(|certificate| certificate.to_der().ok())
+            .unwrap_or_default();
+        pins.check(&config.host, &certificate)?;
+    }
+    Ok(Box::new(tls))
+}
+
+/// Drives `session` over `stream` until it ends.
+async fn run(
+    mut stream: Box<dyn FixStream>,
+    mut session: FixSession,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    events: mpsc::UnboundedSender<FixEvent>,
+) {
+    let target = session.config().target_comp_id.clone();
+    let mut buffer = Vec::new();
+    let mut chunk = vec![0u8; 16 * 1024];
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    let mut commands_open = true;
+
+    // None once logged out cleanly
+    let reason = loop {
+        if let Err(e) = flush(&mut stream, &mut session).await {
+            break Some(e.to_string());
+        }
+        if session.state() == SessionState::Disconnected {
+            break None;
+        }
+
+        tokio::select! {
+            read = stream.read(&mut chunk) => {
+                let n = match read {
+                    Ok(0) => break Some("connection closed".to_string()),
+                    Ok(n) => n,
+                    Err(e) => break Some(e.to_string()),
+                };
+                buffer.extend_from_slice(&chunk[..n]);
+                while let Some(frame) = message::take_frame(&mut buffer) {
+                    let message = match FixMessage::decode(&frame) {
+                        Ok(message) => message,
+                        Err(e) => {
+                            // Garbled messages are ignored; the gap they
+                            // leave is recovered by a resend
+                            warn!("Dropping FIX message from {}: {}", target, e);
+                            continue;
+                        }
+                    };
+                    match session.receive(message, Instant::now()) {
+                        Ok(Some(event)) => {
+                            let _ = events.send(event);
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            let _ = flush(&mut stream, &mut session).await;
+                            session.disconnected();
+                            let _ = events.send(FixEvent::Disconnected(e.to_string()));
+                            return;
+                        }
+                    }
+                }
+            }
+            command = commands.recv(), if commands_open => match command {
+                Some(Command::Send(message, reply)) => {
+                    let _ = reply.send(session.send(message, Instant::now()));
+                }
+                Some(Command::Logout) => {
+                    if session.is_active() {
+                        session.logout(None, Instant::now());
+                    }
+                }
+                None => {
+                    commands_open = false;
+                    if session.is_active() {
+                        session.logout(None, Instant::now());
+                    } else if session.state() == SessionState::LogonSent {
+                        // Given up on during logon
+                        break Some("client dropped".to_string());
+                    }
+                }
+            },
+            _ = ticker.tick() => {
+                if let Err(e) = session.tick(Instant::now()) {
+                    break Some(e.to_string());
+                }
+            }
+        }
+    };
+
+    if let Some(reason) = reason {
+        warn!("FIX session with {} ended: {}", target, reason);
+        session.disconnected();
+        let _ = events.send(FixEvent::Disconnected(reason));
+    }
+    let _ = stream.shutdown().await;
+}
+
+async fn flush(stream: &mut Box<dyn FixStream>, session: &mut FixSession) -> std::io::Result<()> {
+    let frames = session.take_outbound();
+    if frames.is_empty() {
+        return Ok(());
+    }
+    for frame in frames {
+        stream.write_all(&frame).await?;
+    }
+    stream.flush().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_client_logs_on_and_out() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // A venue that accepts the logon, acknowledges an order and
+        // confirms the logout
+        let venue = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buffer = Vec::new();
+            let mut chunk = [0u8; 4096];
+            let mut seq = 0;
+            let mut received = Vec::new();
+            loop {
+                let n = socket.read(&mut chunk).await.unwrap();
+                if n == 0 {
+                    return received;
+                }
+                buffer.extend_from_slice(&chunk[..n]);
+                while let Some(frame) = message::take_frame(&mut buffer) {
+                    let message = FixMessage::decode(&frame).unwrap();
+                    let body = match message.msg_type() {
+                        msg_type::LOGON => FixMessage::new(msg_type::LOGON).with(tags::HEART_BT_INT, 30),
+                        msg_type::NEW_ORDER_SINGLE => FixMessage::new(msg_type::EXECUTION_REPORT)
+                            .with(tags::CL_ORD_ID, message.get(tags::CL_ORD_ID).unwrap()),
+                        msg_type::LOGOUT => FixMessage::new(msg_type::LOGOUT),
+                        _ => continue,
+                    };
+                    received.push(message.msg_type().to_string());
+                    seq += 1;
+                    let mut reply = FixMessage::new(body.msg_type())
+                        .with(tags::SENDER_COMP_ID, "VENUE")
+                        .with(tags::TARGET_COMP_ID, "ARB")
+                        .with(tags::MSG_SEQ_NUM, seq)
+                        .with(tags::SENDING_TIME, "20240101-00:00:00.000");
+                    for (tag, value) in &body.fields()[1..] {
+                        reply.push(*tag, value);
+                    }
+                    socket.write_all(&reply.encode("FIX.4.4")).await.unwrap();
+                }
+            }
+        });
+
+        let mut config = FixConfig::new(
+            "127.0.0.1",
+            port,
+            FixSessionConfig {
+                sender_comp_id: "ARB".to_string(),
+                target_comp_id: "VENUE".to_string(),
+                ..Default::default()
+            },
+        );
+        config.tls = false;
+        let (client, mut events) = FixClient::connect(config).await.unwrap();
+
+        let order = FixMessage::new(msg_type::NEW_ORDER_SINGLE).with(tags::CL_ORD_ID, "c1");
+        assert_eq!(client.send(order).await.unwrap(), 2);
+        match events.recv().await.unwrap() {
+            FixEvent::Message(report) => assert_eq!(report.get(tags::CL_ORD_ID), Some("c1")),
+            other => panic!("unexpected event {:?}", other),
+        }
+
+        client.logout();
+        assert_eq!(events.recv().await.unwrap(), FixEvent::LoggedOut(None));
+        assert_eq!(events.recv().await, None);
+        assert_eq!(venue.await.unwrap(), ["A", "D", "5"]);
+    }
+}
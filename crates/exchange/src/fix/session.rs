@@ -0,0 +1,568 @@
+//! The FIX session layer, without I/O.
+//!
+//! [`FixSession`] numbers and stamps outgoing messages, checks incoming
+//! sequence numbers, and answers the session's own messages: heartbeats
+//! and test requests keep the line alive, resend requests are served from
+//! the messages kept here, and gaps on our side are asked to be resent.
+//! Frames to write are queued for the transport to take.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use arbfinder_core::{ArbFinderError, Result};
+use chrono::Utc;
+use tracing::{debug, warn};
+
+use super::message::{msg_type, tags, FixMessage};
+
+/// Application messages kept for resending, the oldest dropped first.
+/// Requests for dropped messages are answered with a gap fill.
+const MAX_STORED_MESSAGES: usize = 10_000;
+
+#[derive(Clone)]
+pub struct FixSessionConfig {
+    pub begin_string: String,
+    pub sender_comp_id: String,
+    pub target_comp_id: String,
+    pub heartbeat_interval: Duration,
+    /// Start both sides' sequence numbers over at 1 on every logon, as
+    /// most crypto venues require.
+    pub reset_on_logon: bool,
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+impl fmt::Debug for FixSessionConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FixSessionConfig")
+            .field("begin_string", &self.begin_string)
+            .field("sender_comp_id", &self.sender_comp_id)
+            .field("target_comp_id", &self.target_comp_id)
+            .field("heartbeat_interval", &self.heartbeat_interval)
+            .field("reset_on_logon", &self.reset_on_logon)
+            .field("username", &self.username)
+            .finish()
+    }
+}
+
+impl Default for FixSessionConfig {
+    fn default() -> Self {
+        Self {
+            begin_string: "FIX.4.4".to_string(),
+            sender_comp_id: String::new(),
+            target_comp_id: String::new(),
+            heartbeat_interval: Duration::from_secs(30),
+            reset_on_logon: true,
+            username: None,
+            password: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionState {
+    Disconnected,
+    LogonSent,
+    Active,
+    LogoutSent,
+}
+
+/// What an incoming message meant to whoever drives the session.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FixEvent {
+    LoggedOn,
+    /// An application message, in sequence.
+    Message(FixMessage),
+    /// A session-level Reject of one of our messages.
+    Rejected(FixMessage),
+    LoggedOut(Option<String>),
+    Disconnected(String),
+}
+
+struct StoredMessage {
+    body: FixMessage,
+    sending_time: String,
+}
+
+pub struct FixSession {
+    config: FixSessionConfig,
+    state: SessionState,
+    next_outgoing: u64,
+    next_incoming: u64,
+    sent: BTreeMap<u64, StoredMessage>,
+    outbox: Vec<Vec<u8>>,
+    logon_sent: Option<Instant>,
+    last_sent: Instant,
+    last_received: Instant,
+    /// Id and send time of the test request awaiting its heartbeat.
+    test_request: Option<(String, Instant)>,
+    /// Last sequence number covered by our outstanding resend request.
+    resend_until: Option<u64>,
+}
+
+impl FixSession {
+    pub fn new(config: FixSessionConfig) -> Self {
+        let now = Instant::now();
+        Self {
+            config,
+            state: SessionState::Disconnected,
+            next_outgoing: 1,
+            next_incoming: 1,
+            sent: BTreeMap::new(),
+            outbox: Vec::new(),
+            logon_sent: None,
+            last_sent: now,
+            last_received: now,
+            test_request: None,
+            resend_until: None,
+        }
+    }
+
+    pub fn config(&self) -> &FixSessionConfig {
+        &self.config
+    }
+
+    pub fn state(&self) -> SessionState {
+        self.state
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.state == SessionState::Active
+    }
+
+    pub fn next_outgoing(&self) -> u64 {
+        self.next_outgoing
+    }
+
+    pub fn next_incoming(&self) -> u64 {
+        self.next_incoming
+    }
+
+    /// Frames queued for the wire since last taken.
+    pub fn take_outbound(&mut self) -> Vec<Vec<u8>> {
+        std::mem::take(&mut self.outbox)
+    }
+
+    /// Queues a Logon.
+    pub fn logon(&mut self, now: Instant) {
+        if self.config.reset_on_logon {
+            self.next_outgoing = 1;
+            self.next_incoming = 1;
+            self.sent.clear();
+        }
+        self.resend_until = None;
+        self.test_request = None;
+        self.last_received = now;
+
+        let mut logon = FixMessage::new(msg_type::LOGON)
+            .with(tags::ENCRYPT_METHOD, 0)
+            .with(tags::HEART_BT_INT, self.config.heartbeat_interval.as_secs());
+        if self.config.reset_on_logon {
+            logon.push(tags::RESET_SEQ_NUM_FLAG, "Y");
+        }
+        if let Some(username) = &self.config.username {
+            logon.push(tags::USERNAME, username);
+        }
+        if let Some(password) = &self.config.password {
+            logon.push(tags::PASSWORD, password);
+        }
+        self.send_admin(logon, now);
+        self.state = SessionState::LogonSent;
+        self.logon_sent = Some(now);
+    }
+
+    /// Queues a Logout; the session ends when the venue confirms it.
+    pub fn logout(&mut self, text: Option<&str>, now: Instant) {
+        let mut logout = FixMessage::new(msg_type::LOGOUT);
+        if let Some(text) = text {
+            logout.push(tags::TEXT, text);
+        }
+        self.send_admin(logout, now);
+        self.state = SessionState::LogoutSent;
+    }
+
+    /// Marks the connection lost. Sequence numbers carry over to the next
+    /// logon unless it resets them.
+    pub fn disconnected(&mut self) {
+        self.state = SessionState::Disconnected;
+        self.logon_sent = None;
+        self.test_request = None;
+        self.resend_until = None;
+    }
+
+    /// Queues an application message, kept for resending.
+    pub fn send(&mut self, body: FixMessage, now: Instant) -> Result<u64> {
+        if !self.is_active() {
+            return Err(ArbFinderError::Exchange(format!(
+                "FIX session with {} is not logged on",
+                self.config.target_comp_id
+            )));
+        }
+        let seq = self.next_outgoing;
+        let sending_time = sending_time();
+        self.queue(&body, seq, &sending_time, None, now);
+        self.sent.insert(seq, StoredMessage { body, sending_time });
+        while self.sent.len() > MAX_STORED_MESSAGES {
+            self.sent.pop_first();
+        }
+        self.next_outgoing += 1;
+        Ok(seq)
+    }
+
+    fn send_admin(&mut self, body: FixMessage, now: Instant) {
+        let seq = self.next_outgoing;
+        self.queue(&body, seq, &sending_time(), None, now);
+        self.next_outgoing += 1;
+    }
+
+    /// Frames `body` under the standard header, with PossDupFlag and
+    /// OrigSendingTime when `orig_sending_time` is given.
+    fn queue(&mut self, body: &FixMessage, seq: u64, sending_time: &str, orig_sending_time: Option<&str>, now: Instant) {
+        let mut message = FixMessage::new(body.msg_type())
+            .with(tags::SENDER_COMP_ID, &self.config.sender_comp_id)
+            .with(tags::TARGET_COMP_ID, &self.config.target_comp_id)
+            .with(tags::MSG_SEQ_NUM, seq);
+        if let Some(orig_sending_time) = orig_sending_time {
+            message.push(tags::POSS_DUP_FLAG, "Y");
+            message.push(tags::ORIG_SENDING_TIME, orig_sending_time);
+        }
+        message.push(tags::SENDING_TIME, sending_time);
+        for (tag, value) in &body.fields()[1..] {
+            message.push(*tag, value);
+        }
+
+        debug!("FIX out {}: {}", self.config.target_comp_id, message);
+        self.outbox.push(message.encode(&self.config.begin_string));
+        self.last_sent = now;
+    }
+
+    /// Processes one incoming message, returning what it means for the
+    /// application, if anything. An error means the session can't go on.
+    pub fn receive(&mut self, message: FixMessage, now: Instant) -> Result<Option<FixEvent>> {
+        self.last_received = now;
+        debug!("FIX in {}: {}", self.config.target_comp_id, message);
+
+        if message.get(tags::SENDER_COMP_ID) != Some(self.config.target_comp_id.as_str())
+            || message.get(tags::TARGET_COMP_ID) != Some(self.config.sender_comp_id.as_str())
+        {
+            return Err(self.fail(format!("FIX message for another session: {}", message), now));
+        }
+        let Some(seq) = message.seq_num() else {
+            return Err(self.fail(format!("FIX message without MsgSeqNum: {}", message), now));
+        };
+
+        match message.msg_type() {
+            msg_type::LOGON if message.flag(tags::RESET_SEQ_NUM_FLAG) => self.next_incoming = seq,
+            // Reset mode ignores the message's own sequence number
+            msg_type::SEQUENCE_RESET if !message.flag(tags::GAP_FILL_FLAG) => {
+                let new_seq: u64 = message.get_parsed(tags::NEW_SEQ_NO).unwrap_or(0);
+                if new_seq < self.next_incoming {
+                    return Err(self.fail(format!("FIX SequenceReset back to {}", new_seq), now));
+                }
+                self.next_incoming = new_seq;
+                self.resend_caught_up();
+                return Ok(None);
+            }
+            _ => {}
+        }
+
+        if seq < self.next_incoming {
+            if message.flag(tags::POSS_DUP_FLAG) {
+                return Ok(None);
+            }
+            return Err(self.fail(
+                format!("MsgSeqNum too low, expecting {} but received {}", self.next_incoming, seq),
+                now,
+            ));
+        }
+
+        if seq > self.next_incoming {
+            if self.resend_until.is_none() {
+                warn!(
+                    "FIX gap from {}: expected {}, received {}; requesting resend",
+                    self.config.target_comp_id, self.next_incoming, seq
+                );
+                let request = FixMessage::new(msg_type::RESEND_REQUEST)
+                    .with(tags::BEGIN_SEQ_NO, self.next_incoming)
+                    .with(tags::END_SEQ_NO, 0);
+                self.send_admin(request, now);
+            }
+            self.resend_until = Some(self.resend_until.unwrap_or(0).max(seq));
+
+            // These can't wait for the gap to be filled
+            return match message.msg_type() {
+                msg_type::LOGON => self.on_logon(&message),
+                msg_type::LOGOUT => Ok(self.on_logout(&message, now)),
+                msg_type::RESEND_REQUEST => {
+                    self.on_resend_request(&message, now);
+                    Ok(None)
+                }
+                _ => Ok(None),
+            };
+        }
+
+        self.next_incoming += 1;
+        let event = match message.msg_type() {
+            msg_type::HEARTBEAT => {
+                if let Some((id, _)) = &self.test_request {
+                    if message.get(tags::TEST_REQ_ID) == Some(id.as_str()) {
+                        self.test_request = None;
+                    }
+                }
+                None
+            }
+            msg_type::TEST_REQUEST => {
+                let mut heartbeat = FixMessage::new(msg_type::HEARTBEAT);
+                if let Some(id) = message.get(tags::TEST_REQ_ID) {
+                    heartbeat.push(tags::TEST_REQ_ID, id);
+                }
+                self.send_admin(heartbeat, now);
+                None
+            }
+            msg_type::RESEND_REQUEST => {
+                self.on_resend_request(&message, now);
+                None
+            }
+            msg_type::SEQUENCE_RESET => {
+                let new_seq: u64 = message.get_parsed(tags::NEW_SEQ_NO).unwrap_or(0);
+                if new_seq > self.next_incoming {
+                    self.next_incoming = new_seq;
+                }
+                None
+            }
+            msg_type::REJECT => Some(FixEvent::Rejected(message)),
+            msg_type::LOGON => self.on_logon(&message)?,
+            msg_type::LOGOUT => self.on_logout(&message, now),
+            _ => Some(FixEvent::Message(message)),
+        };
+        self.resend_caught_up();
+        Ok(event)
+    }
+
+    fn on_logon(&mut self, _message: &FixMessage) -> Result<Option<FixEvent>> {
+        match self.state {
+            SessionState::LogonSent => {
+                self.state = SessionState::Active;
+                self.logon_sent = None;
+                Ok(Some(FixEvent::LoggedOn))
+            }
+            SessionState::Active => Ok(None),
+            _ => Err(ArbFinderError::Exchange(format!(
+                "Unexpected FIX Logon from {}",
+                self.config.target_comp_id
+            ))),
+        }
+    }
+
+    fn on_logout(&mut self, message: &FixMessage, now: Instant) -> Option<FixEvent> {
+        if self.state != SessionState::LogoutSent {
+            self.send_admin(FixMessage::new(msg_type::LOGOUT), now);
+        }
+        self.state = SessionState::Disconnected;
+        Some(FixEvent::LoggedOut(message.get(tags::TEXT).map(str::to_string)))
+    }
+
+    /// Resends what we still have of the requested range, with runs of
+    /// session messages and dropped ones replaced by gap fills.
+    fn on_resend_request(&mut self, message: &FixMessage, now: Instant) {
+        let last_sent = self.next_outgoing.saturating_sub(1);
+        let begin: u64 = message.get_parsed(tags::BEGIN_SEQ_NO).unwrap_or(1).max(1);
+        let end = match message.get_parsed::<u64>(tags::END_SEQ_NO).unwrap_or(0) {
+            0 => last_sent,
+            end => end.min(last_sent),
+        };
+        debug!("FIX resending {}..={} to {}", begin, end, self.config.target_comp_id);
+
+        let resend: Vec<(u64, FixMessage, String)> = self
+            .sent
+            .range(begin..=end)
+            .map(|(seq, stored)| (*seq, stored.body.clone(), stored.sending_time.clone()))
+            .collect();
+        let mut next = begin;
+        for (seq, body, orig_sending_time) in resend {
+            if seq > next {
+                self.gap_fill(next, seq, now);
+            }
+            self.queue(&body, seq, &sending_time(), Some(&orig_sending_time), now);
+            next = seq + 1;
+        }
+        if next <= end {
+            self.gap_fill(next, end + 1, now);
+        }
+    }
+
+    /// Tells the venue to skip from `seq` to `new_seq`.
+    fn gap_fill(&mut self, seq: u64, new_seq: u64, now: Instant) {
+        let sending_time = sending_time();
+        let fill = FixMessage::new(msg_type::SEQUENCE_RESET)
+            .with(tags::GAP_FILL_FLAG, "Y")
+            .with(tags::NEW_SEQ_NO, new_seq);
+        self.queue(&fill, seq, &sending_time, Some(&sending_time), now);
+    }
+
+    fn resend_caught_up(&mut self) {
+        if self.resend_until.is_some_and(|until| self.next_incoming > until) {
+            self.resend_until = None;
+        }
+    }
+
+    /// Sends a Logout explaining why the session is being dropped.
+    fn fail(&mut self, reason: String, now: Instant) -> ArbFinderError {
+        warn!("FIX session with {} failed: {}", self.config.target_comp_id, reason);
+        self.logout(Some(&reason), now);
+        ArbFinderError::Exchange(reason)
+    }
+
+    /// Keeps the line alive: a heartbeat after an interval without
+    /// sending, a test request after one without hearing from the venue,
+    /// and an error if that goes unanswered or the logon does.
+    pub fn tick(&mut self, now: Instant) -> Result<()> {
+        let interval = self.config.heartbeat_interval;
+        match self.state {
+            SessionState::Disconnected => return Ok(()),
+            SessionState::LogonSent | SessionState::LogoutSent => {
+                if self.logon_sent.is_some_and(|sent| now.duration_since(sent) > interval)
+                    || (self.state == SessionState::LogoutSent && now.duration_since(self.last_sent) > interval)
+                {
+                    self.state = SessionState::Disconnected;
+                    return Err(ArbFinderError::Timeout(format!(
+                        "No FIX response from {}",
+                        self.config.target_comp_id
+                    )));
+                }
+                return Ok(());
+            }
+            SessionState::Active => {}
+        }
+
+        match &self.test_request {
+            Some((_, sent)) if now.duration_since(*sent) > interval => {
+                self.state = SessionState::Disconnected;
+                return Err(ArbFinderError::Timeout(format!(
+                    "FIX test request to {} went unanswered",
+                    self.config.target_comp_id
+                )));
+            }
+            Some(_) => {}
+            // A fifth over the interval, for heartbeats in flight
+            None if now.duration_since(self.last_received) > interval + interval / 5 => {
+                let id = format!("TEST{}", self.next_outgoing);
+                self.send_admin(FixMessage::new(msg_type::TEST_REQUEST).with(tags::TEST_REQ_ID, &id), now);
+                self.test_request = Some((id, now));
+            }
+            None => {}
+        }
+
+        if now.duration_since(self.last_sent) >= interval {
+            self.send_admin(FixMessage::new(msg_type::HEARTBEAT), now);
+        }
+        Ok(())
+    }
+}
+
+/// UTC now in the SendingTime format.
+fn sending_time() -> String {
+    Utc::now().format("%Y%m%d-%H:%M:%S%.3f").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session() -> FixSession {
+        FixSession::new(FixSessionConfig {
+            sender_comp_id: "ARB".to_string(),
+            target_comp_id: "VENUE".to_string(),
+            ..Default::default()
+        })
+    }
+
+    fn from_venue(msg_type: &str, seq: u64) -> FixMessage {
+        FixMessage::new(msg_type)
+            .with(tags::SENDER_COMP_ID, "VENUE")
+            .with(tags::TARGET_COMP_ID, "ARB")
+            .with(tags::MSG_SEQ_NUM, seq)
+    }
+
+    fn sent(session: &mut FixSession) -> Vec<FixMessage> {
+        session
+            .take_outbound()
+            .iter()
+            .map(|frame| FixMessage::decode(frame).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_logon_and_gap_recovery() {
+        let now = Instant::now();
+        let mut session = session();
+        session.logon(now);
+        let logon = sent(&mut session);
+        assert_eq!(logon[0].msg_type(), msg_type::LOGON);
+        assert_eq!(logon[0].get(tags::RESET_SEQ_NUM_FLAG), Some("Y"));
+        assert!(session.send(FixMessage::new(msg_type::NEW_ORDER_SINGLE), now).is_err());
+
+        let event = session.receive(from_venue(msg_type::LOGON, 1).with(tags::RESET_SEQ_NUM_FLAG, "Y"), now);
+        assert_eq!(event.unwrap(), Some(FixEvent::LoggedOn));
+        assert_eq!(session.send(FixMessage::new(msg_type::NEW_ORDER_SINGLE), now).unwrap(), 2);
+        sent(&mut session);
+
+        // 2 and 3 went missing
+        let event = session.receive(from_venue(msg_type::EXECUTION_REPORT, 4), now).unwrap();
+        assert_eq!(event, None);
+        let request = sent(&mut session);
+        assert_eq!(request[0].msg_type(), msg_type::RESEND_REQUEST);
+        assert_eq!(request[0].get(tags::BEGIN_SEQ_NO), Some("2"));
+
+        let resent = from_venue(msg_type::EXECUTION_REPORT, 2).with(tags::POSS_DUP_FLAG, "Y");
+        assert!(matches!(session.receive(resent.clone(), now).unwrap(), Some(FixEvent::Message(_))));
+        let fill = from_venue(msg_type::SEQUENCE_RESET, 3)
+            .with(tags::GAP_FILL_FLAG, "Y")
+            .with(tags::NEW_SEQ_NO, 4);
+        session.receive(fill, now).unwrap();
+        assert!(matches!(
+            session.receive(from_venue(msg_type::EXECUTION_REPORT, 4), now).unwrap(),
+            Some(FixEvent::Message(_))
+        ));
+        assert_eq!(session.next_incoming(), 5);
+
+        // Duplicates are dropped, but a low number without PossDup is fatal
+        assert_eq!(session.receive(resent, now).unwrap(), None);
+        assert!(session.receive(from_venue(msg_type::HEARTBEAT, 3), now).is_err());
+    }
+
+    #[test]
+    fn test_resend_and_heartbeats() {
+        let now = Instant::now();
+        let mut session = session();
+        session.logon(now);
+        session.receive(from_venue(msg_type::LOGON, 1), now).unwrap();
+        session.send(FixMessage::new(msg_type::NEW_ORDER_SINGLE).with(tags::CL_ORD_ID, "a"), now).unwrap();
+        session.send(FixMessage::new(msg_type::NEW_ORDER_SINGLE).with(tags::CL_ORD_ID, "b"), now).unwrap();
+        sent(&mut session);
+
+        // The logon is gap-filled, the orders resent as possible duplicates
+        let request = from_venue(msg_type::RESEND_REQUEST, 2)
+            .with(tags::BEGIN_SEQ_NO, 1)
+            .with(tags::END_SEQ_NO, 0);
+        session.receive(request, now).unwrap();
+        let resent = sent(&mut session);
+        assert_eq!(resent.len(), 3);
+        assert_eq!(resent[0].msg_type(), msg_type::SEQUENCE_RESET);
+        assert_eq!(resent[0].get(tags::NEW_SEQ_NO), Some("2"));
+        assert_eq!(resent[1].get(tags::CL_ORD_ID), Some("a"));
+        assert_eq!(resent[1].seq_num(), Some(2));
+        assert!(resent[2].flag(tags::POSS_DUP_FLAG));
+
+        let interval = session.config().heartbeat_interval;
+        session.tick(now + interval).unwrap();
+        assert_eq!(sent(&mut session)[0].msg_type(), msg_type::HEARTBEAT);
+
+        // Silence draws a test request, and then a timeout
+        session.tick(now + interval * 2).unwrap();
+        let test_request = sent(&mut session);
+        assert!(test_request.iter().any(|m| m.msg_type() == msg_type::TEST_REQUEST));
+        assert!(session.tick(now + interval * 4).is_err());
+    }
+}
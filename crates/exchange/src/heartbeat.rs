@@ -1,7 +1,20 @@
+//! Ping/pong heartbeats and the round trips they measure.
+//!
+//! A [`HeartbeatManager`] is shared by every connection of a venue; clones
+//! share its state. Pings carry ids, so pongs from several connections
+//! are each matched to their own ping. Venues answer in their own way:
+//! WebSocket pong frames (see `ExchangeConfig::heartbeat`), application
+//! messages such as Kraken's `pong`, or heartbeats stamped with the
+//! venue's send time, as on Coinbase.
+
+use arbfinder_core::utils::{RollingStats, SharedStats};
 use arbfinder_core::Result;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::{Mutex, RwLock};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
 use tokio::time::{interval, sleep, Instant, MissedTickBehavior};
 use tracing::{debug, error, info, warn};
 
@@ -30,14 +43,25 @@ impl Default for HeartbeatStatus {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct HeartbeatManager {
     status: Arc<RwLock<HeartbeatStatus>>,
     ping_interval: Duration,
     max_missed_pongs: u32,
     timeout_duration: Duration,
-    latency_samples: Arc<Mutex<Vec<Duration>>>,
-    max_latency_samples: usize,
+    /// Round trips of the last 100 pongs.
+    latency: SharedStats,
+    /// Send times of pings awaiting their pong, by id.
+    outstanding: Arc<std::sync::Mutex<HashMap<u64, Instant>>>,
+    next_ping: Arc<AtomicU64>,
+}
+
+impl Default for HeartbeatManager {
+    /// A ping every 30 seconds, answered within 10, unhealthy after three
+    /// go unanswered.
+    fn default() -> Self {
+        Self::new(Duration::from_secs(30), 3, Duration::from_secs(10))
+    }
 }
 
 impl HeartbeatManager {
@@ -51,107 +75,163 @@ impl HeartbeatManager {
             ping_interval,
             max_missed_pongs,
             timeout_duration,
-            latency_samples: Arc::new(Mutex::new(Vec::new())),
-            max_latency_samples: 100,
+            latency: RollingStats::new(100).shared(),
+            outstanding: Arc::default(),
+            next_ping: Arc::default(),
         }
     }
 
-    pub async fn start<F, Fut>(&self, ping_sender: F) -> Result<()>
+    pub fn ping_interval(&self) -> Duration {
+        self.ping_interval
+    }
+
+    /// Pings `ping_sender` every interval, counting those not answered
+    /// through `record_pong` within the timeout.
+    pub async fn start<F, Fut>(&self, ping_sender: F) -> Result<JoinHandle<()>>
     where
         F: Fn() -> Fut + Send + Sync + 'static,
         Fut: std::future::Future<Output = Result<()>> + Send + 'static,
     {
-        let status = Arc::clone(&self.status);
-        let latency_samples = Arc::clone(&self.latency_samples);
-        let ping_interval = self.ping_interval;
-        let max_missed_pongs = self.max_missed_pongs;
-        let timeout_duration = self.timeout_duration;
-        let max_latency_samples = self.max_latency_samples;
-
-        tokio::spawn(async move {
-            let mut ping_ticker = interval(ping_interval);
+        let manager = self.clone();
+        Ok(tokio::spawn(async move {
+            let mut ping_ticker = interval(manager.ping_interval);
             ping_ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
             loop {
                 ping_ticker.tick().await;
 
                 debug!("Sending heartbeat ping");
-                let ping_time = Instant::now();
-
-                // Send ping
-                match ping_sender().await {
-                    Ok(_) => {
-                        let mut status_guard = status.write().await;
-                        status_guard.last_ping = Some(ping_time);
-                        status_guard.ping_count += 1;
-                        debug!("Heartbeat ping sent, count: {}", status_guard.ping_count);
-                    }
-                    Err(e) => {
-                        error!("Failed to send heartbeat ping: {}", e);
-                        continue;
-                    }
+                if let Err(e) = ping_sender().await {
+                    error!("Failed to send heartbeat ping: {}", e);
+                    continue;
                 }
+                let id = manager.record_ping().await;
 
-                // Wait for pong response
-                sleep(timeout_duration).await;
-
-                // Check if we received a pong
-                let mut status_guard = status.write().await;
-                if let (Some(last_ping), Some(last_pong)) = (status_guard.last_ping, status_guard.last_pong) {
-                    if last_pong >= last_ping {
-                        // Received pong for this ping
-                        let latency = last_pong - last_ping;
-                        status_guard.missed_pongs = 0;
-                        status_guard.is_healthy = true;
-
-                        // Update latency statistics
-                        drop(status_guard);
-                        let mut samples = latency_samples.lock().await;
-                        samples.push(latency);
-                        if samples.len() > max_latency_samples {
-                            samples.remove(0);
-                        }
+                sleep(manager.timeout_duration).await;
+                manager.pong_missed(id).await;
+            }
+        }))
+    }
 
-                        // Calculate average latency
-                        let avg_latency = if !samples.is_empty() {
-                            let total: Duration = samples.iter().sum();
-                            Some(total / samples.len() as u32)
-                        } else {
-                            None
-                        };
-
-                        let mut status_guard = status.write().await;
-                        status_guard.average_latency = avg_latency;
-
-                        debug!("Heartbeat pong received, latency: {:?}, avg: {:?}", latency, avg_latency);
-                    } else {
-                        // No pong received for this ping
-                        status_guard.missed_pongs += 1;
-                        warn!("Missed heartbeat pong, count: {}", status_guard.missed_pongs);
-
-                        if status_guard.missed_pongs >= max_missed_pongs {
-                            status_guard.is_healthy = false;
-                            error!("Connection unhealthy: too many missed pongs ({})", status_guard.missed_pongs);
-                        }
-                    }
-                } else {
-                    // First ping or no pong received yet
-                    status_guard.missed_pongs += 1;
-                    if status_guard.missed_pongs >= max_missed_pongs {
-                        status_guard.is_healthy = false;
-                    }
+    /// Pings every interval with `send_ping(id)`, for venues that echo the
+    /// id back, until it fails, such as once the connection it writes to
+    /// has closed. Answers go to `record_pong_to`.
+    pub fn spawn_pings<F>(&self, send_ping: F) -> JoinHandle<()>
+    where
+        F: Fn(u64) -> Result<()> + Send + 'static,
+    {
+        let manager = self.clone();
+        tokio::spawn(async move {
+            let mut ping_ticker = interval(manager.ping_interval);
+            ping_ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            // The first tick is immediate; give the connection an interval
+            ping_ticker.tick().await;
+
+            loop {
+                ping_ticker.tick().await;
+                let id = manager.record_ping().await;
+                if send_ping(id).is_err() {
+                    manager.outstanding().remove(&id);
+                    debug!("Heartbeat pings stopped: connection closed");
+                    return;
                 }
+
+                sleep(manager.timeout_duration).await;
+                manager.pong_missed(id).await;
             }
-        });
+        })
+    }
 
-        Ok(())
+    fn outstanding(&self) -> std::sync::MutexGuard<'_, HashMap<u64, Instant>> {
+        self.outstanding.lock().unwrap_or_else(|e| e.into_inner())
     }
 
+    /// Notes a ping sent now, returning its id.
+    pub async fn record_ping(&self) -> u64 {
+        let id = self.next_ping.fetch_add(1, Ordering::Relaxed) + 1;
+        let now = Instant::now();
+        self.outstanding().insert(id, now);
+
+        let mut status = self.status.write().await;
+        status.last_ping = Some(now);
+        status.ping_count += 1;
+        debug!("Heartbeat ping {} sent, count: {}", id, status.ping_count);
+        id
+    }
+
+    /// A pong that doesn't say which ping it answers, taken to answer the
+    /// latest.
     pub async fn record_pong(&self) {
+        let now = Instant::now();
+        self.outstanding().clear();
+
         let mut status = self.status.write().await;
-        status.last_pong = Some(Instant::now());
+        let answered = match (status.last_ping, status.last_pong) {
+            (Some(ping), Some(pong)) if pong >= ping => None,
+            (Some(ping), _) => Some(now - ping),
+            (None, _) => None,
+        };
+        status.last_pong = Some(now);
         status.pong_count += 1;
         debug!("Heartbeat pong recorded, count: {}", status.pong_count);
+        if let Some(latency) = answered {
+            self.observe(&mut status, latency);
+        }
+    }
+
+    /// The pong to ping `id`, returning its round trip; `None` if the ping
+    /// is unknown or already counted as missed.
+    pub async fn record_pong_to(&self, id: u64) -> Option<Duration> {
+        let sent = self.outstanding().remove(&id)?;
+        let now = Instant::now();
+        let latency = now - sent;
+
+        let mut status = self.status.write().await;
+        status.last_pong = Some(now);
+        status.pong_count += 1;
+        self.observe(&mut status, latency);
+        Some(latency)
+    }
+
+    /// A heartbeat the venue sent unprompted, `latency` after it stamped
+    /// it.
+    pub async fn record_latency(&self, latency: Duration) {
+        let mut status = self.status.write().await;
+        status.last_pong = Some(Instant::now());
+        status.pong_count += 1;
+        self.observe(&mut status, latency);
+    }
+
+    fn observe(&self, status: &mut HeartbeatStatus, latency: Duration) {
+        status.missed_pongs = 0;
+        status.is_healthy = true;
+
+        let avg_latency = {
+            let mut stats = self.latency.lock().unwrap_or_else(|e| e.into_inner());
+            stats.record_duration(latency);
+            stats.mean().map(Duration::from_secs_f64)
+        };
+        status.average_latency = avg_latency;
+        debug!("Heartbeat latency: {:?}, avg: {:?}", latency, avg_latency);
+    }
+
+    /// Counts ping `id` as missed if it is still unanswered, returning
+    /// whether it was.
+    pub async fn pong_missed(&self, id: u64) -> bool {
+        if self.outstanding().remove(&id).is_none() {
+            return false;
+        }
+
+        let mut status = self.status.write().await;
+        status.missed_pongs += 1;
+        warn!("Missed heartbeat pong, count: {}", status.missed_pongs);
+        if status.missed_pongs >= self.max_missed_pongs {
+            if status.is_healthy {
+                error!("Connection unhealthy: too many missed pongs ({})", status.missed_pongs);
+            }
+            status.is_healthy = false;
+        }
+        true
     }
 
     pub async fn get_status(&self) -> HeartbeatStatus {
@@ -170,32 +250,29 @@ impl HeartbeatManager {
         let mut status = self.status.write().await;
         *status = HeartbeatStatus::default();
         
-        let mut samples = self.latency_samples.lock().await;
-        samples.clear();
+        self.latency.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        self.outstanding().clear();
         
         info!("Heartbeat status reset");
     }
 
     pub async fn get_latency_percentiles(&self) -> Option<LatencyStats> {
-        let samples = self.latency_samples.lock().await;
-        if samples.is_empty() {
-            return None;
-        }
-
-        let mut sorted_samples: Vec<Duration> = samples.clone();
-        sorted_samples.sort();
-
-        let len = sorted_samples.len();
+        let summary = self.latency.lock().unwrap_or_else(|e| e.into_inner()).summary()?;
         Some(LatencyStats {
-            min: sorted_samples[0],
-            max: sorted_samples[len - 1],
-            p50: sorted_samples[len / 2],
-            p95: sorted_samples[(len * 95) / 100],
-            p99: sorted_samples[(len * 99) / 100],
-            avg: sorted_samples.iter().sum::<Duration>() / len as u32,
-            count: len,
+            min: Duration::from_secs_f64(summary.min),
+            max: Duration::from_secs_f64(summary.max),
+            p50: Duration::from_secs_f64(summary.p50),
+            p95: Duration::from_secs_f64(summary.p95),
+            p99: Duration::from_secs_f64(summary.p99),
+            avg: Duration::from_secs_f64(summary.mean),
+            count: summary.count,
         })
     }
+
+    /// Pong round trips in seconds, e.g. to export as a metric.
+    pub fn latency_stats(&self) -> SharedStats {
+        Arc::clone(&self.latency)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -248,12 +325,8 @@ impl ConnectionHealthMonitor {
         // Start heartbeat manager
         self.heartbeat_manager.start(ping_sender).await?;
 
-        // Start health monitoring
-        let heartbeat_manager = HeartbeatManager::new(
-            self.heartbeat_manager.ping_interval,
-            self.heartbeat_manager.max_missed_pongs,
-            self.heartbeat_manager.timeout_duration,
-        );
+        // Start health monitoring, of the pings just started
+        let heartbeat_manager = self.heartbeat_manager.clone();
         let reconnect_threshold = self.reconnect_threshold;
         let health_check_interval = self.health_check_interval;
         let is_monitoring = Arc::clone(&self.is_monitoring);
@@ -392,6 +465,44 @@ mod tests {
         assert!(latency.unwrap() >= Duration::from_millis(10));
     }
 
+    #[tokio::test]
+    async fn test_tagged_pongs() {
+        let manager = HeartbeatManager::new(Duration::from_secs(1), 2, Duration::from_millis(100));
+
+        // Pings from two connections, answered out of order
+        let first = manager.record_ping().await;
+        let second = manager.record_ping().await;
+        sleep(Duration::from_millis(10)).await;
+        assert!(manager.record_pong_to(second).await.unwrap() >= Duration::from_millis(10));
+        assert!(manager.record_pong_to(first).await.is_some());
+        assert_eq!(manager.record_pong_to(first).await, None);
+        assert!(!manager.pong_missed(first).await);
+
+        // Two unanswered pings make it unhealthy, an answer healthy again
+        for _ in 0..2 {
+            let id = manager.record_ping().await;
+            assert!(manager.pong_missed(id).await);
+        }
+        assert!(!manager.is_healthy().await);
+        manager.record_latency(Duration::from_millis(5)).await;
+        let status = manager.get_status().await;
+        assert!(status.is_healthy);
+        assert_eq!((status.ping_count, status.pong_count, status.missed_pongs), (4, 3, 0));
+        assert_eq!(manager.get_latency_percentiles().await.unwrap().count, 3);
+
+        // Pings stop with the connection they are written to
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let fast = HeartbeatManager::new(Duration::from_millis(20), 2, Duration::from_millis(10));
+        let pings = fast.spawn_pings(move |id| {
+            tx.send(id)
+                .map_err(|_| arbfinder_core::ArbFinderError::Internal("closed".to_string()))
+        });
+        let id = rx.recv().await.unwrap();
+        fast.record_pong_to(id).await.unwrap();
+        drop(rx);
+        tokio::time::timeout(Duration::from_secs(1), pings).await.unwrap().unwrap();
+    }
+
     #[tokio::test]
     async fn test_health_monitoring() {
         let monitor = ConnectionHealthMonitor::new(
@@ -1,16 +1,54 @@
 pub mod traits;
+pub mod cache;
+pub mod circuit;
+pub mod clock;
 pub mod websocket;
+pub mod compression;
+pub mod pinning;
+pub mod proxy;
 pub mod rest;
 pub mod normalizer;
 pub mod heartbeat;
 pub mod manager;
 pub mod rate_limiter;
+pub mod request_queue;
+pub mod singleflight;
+pub mod status;
+pub mod symbol_map;
+pub mod synthetic;
+pub mod fallback;
+pub mod fanout;
+pub mod fix;
+pub mod polling;
+pub mod secrets;
+pub mod supervisor;
+pub mod network;
+pub mod transfer;
+pub mod uptime;
 pub mod prelude;
 
 pub use traits::*;
+pub use cache::*;
+pub use circuit::*;
+pub use clock::*;
 pub use websocket::*;
+pub use compression::*;
+pub use pinning::*;
+pub use proxy::*;
 pub use rest::*;
 pub use normalizer::*;
 pub use heartbeat::*;
 pub use manager::*;
 pub use rate_limiter::*;
+pub use request_queue::*;
+pub use singleflight::*;
+pub use status::*;
+pub use symbol_map::*;
+pub use fallback::*;
+pub use fanout::*;
+pub use polling::*;
+pub use secrets::*;
+pub use supervisor::*;
+pub use network::*;
+pub use transfer::*;
+pub use uptime::*;
@@ -1,16 +1,47 @@
-use arbfinder_core::{ArbFinderError, Result, Symbol, VenueId};
+use arbfinder_core::utils::{ClockSkew, SharedClockSkew, SkewSample};
+use arbfinder_core::{ArbFinderError, EventTime, MarketDataMode, Result, Symbol, SymbolId, VenueId};
 use async_trait::async_trait;
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::{Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Mutex, RwLock};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
 use tracing::{debug, error, info, warn};
 
-use crate::traits::{ExchangeAdapter, ConnectionStatus, SubscriptionInfo};
+use crate::circuit::{CircuitState, SharedCircuitBreaker};
+use crate::clock::{measure_clock_skew, CLOCK_SKEW_WARNING};
+use crate::fallback::{resilient_market_data, FallbackConfig, FeedStream};
+use crate::fanout::{MarketDataFanout, VenueMarketDataStream};
+use crate::heartbeat::HeartbeatManager;
+use crate::status::{VenueHealth, VenueStatusBoard, VenueStatusEvent};
+use crate::supervisor::{Restarts, SupervisorConfig, SupervisorEvent};
+use crate::traits::{ExchangeAdapter, MarketDataAdapter, ConnectionStatus, SubscriptionInfo, VenueAdapter};
+use crate::uptime::VenueUptime;
+
+/// A subscription the manager keeps up on a venue. The venue's live
+/// subscriptions are lost when its connection drops; these are replayed
+/// each time it connects again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DesiredSubscription {
+    pub symbol: SymbolId,
+    /// `orderbook`, `trades` or `bbo`, as in [`SubscriptionInfo`].
+    pub data_type: String,
+    /// Depth asked for, for `orderbook` subscriptions.
+    pub depth: Option<u32>,
+}
 
 pub struct ExchangeManager {
-    adapters: Arc<RwLock<HashMap<VenueId, Arc<Mutex<Box<dyn ExchangeAdapter>>>>>>,
+    adapters: Arc<RwLock<HashMap<VenueId, Arc<Mutex<VenueAdapter>>>>>,
     connections: Arc<RwLock<HashMap<VenueId, ConnectionStatus>>>,
     subscriptions: Arc<RwLock<HashMap<VenueId, Vec<SubscriptionInfo>>>>,
+    desired: Arc<RwLock<HashMap<VenueId, Vec<DesiredSubscription>>>>,
+    circuits: Arc<RwLock<HashMap<VenueId, SharedCircuitBreaker>>>,
+    fanout: Arc<MarketDataFanout>,
+    uptime: Arc<RwLock<HashMap<VenueId, VenueUptime>>>,
+    clocks: Arc<RwLock<HashMap<VenueId, SharedClockSkew>>>,
+    heartbeats: Arc<RwLock<HashMap<VenueId, HeartbeatManager>>>,
+    venue_status: VenueStatusBoard,
 }
 
 impl ExchangeManager {
@@ -19,17 +50,63 @@ impl ExchangeManager {
             adapters: Arc::new(RwLock::new(HashMap::new())),
             connections: Arc::new(RwLock::new(HashMap::new())),
             subscriptions: Arc::new(RwLock::new(HashMap::new())),
+            desired: Arc::new(RwLock::new(HashMap::new())),
+            circuits: Arc::new(RwLock::new(HashMap::new())),
+            fanout: Arc::new(MarketDataFanout::default()),
+            uptime: Arc::new(RwLock::new(HashMap::new())),
+            clocks: Arc::new(RwLock::new(HashMap::new())),
+            heartbeats: Arc::new(RwLock::new(HashMap::new())),
+            venue_status: VenueStatusBoard::default(),
         }
     }
 
+    /// Uses `board` for venue status reports, e.g. one the execution
+    /// engine checks before sending orders.
+    pub fn with_venue_status(mut self, board: VenueStatusBoard) -> Self {
+        self.venue_status = board;
+        self
+    }
+
+    /// Adds a venue with market data and trading.
     pub async fn add_adapter(&self, adapter: Box<dyn ExchangeAdapter>) -> Result<()> {
+        self.insert_adapter(VenueAdapter::from(adapter)).await
+    }
+
+    /// Adds a venue that only serves market data. Its books and streams are
+    /// managed like any other venue's, but `can_trade` is false for it.
+    pub async fn add_market_data_adapter(&self, adapter: Box<dyn MarketDataAdapter>) -> Result<()> {
+        self.insert_adapter(VenueAdapter::from(adapter)).await
+    }
+
+    async fn insert_adapter(&self, adapter: VenueAdapter) -> Result<()> {
         let venue_id = adapter.venue_id();
-        info!("Adding adapter for venue: {}", venue_id);
+        info!(
+            "Adding {} adapter for venue: {}",
+            if adapter.can_trade() { "trading" } else { "market data" },
+            venue_id
+        );
 
         let mut adapters = self.adapters.write().await;
         let mut connections = self.connections.write().await;
         let mut subscriptions = self.subscriptions.write().await;
 
+        let mut circuits = self.circuits.write().await;
+        match adapter.circuit_breaker() {
+            Some(circuit) => circuits.insert(venue_id.clone(), circuit),
+            None => circuits.remove(&venue_id),
+        };
+
+        let mut heartbeats = self.heartbeats.write().await;
+        match adapter.heartbeat() {
+            Some(heartbeat) => heartbeats.insert(venue_id.clone(), heartbeat),
+            None => heartbeats.remove(&venue_id),
+        };
+        drop(heartbeats);
+
+        let clock = adapter.clock_skew().unwrap_or_else(|| ClockSkew::default().shared());
+        self.fanout.set_clock_skew(venue_id.clone(), Arc::clone(&clock));
+        self.clocks.write().await.insert(venue_id.clone(), clock);
+
         adapters.insert(venue_id.clone(), Arc::new(Mutex::new(adapter)));
         connections.insert(venue_id.clone(), ConnectionStatus {
             connected: false,
@@ -37,8 +114,13 @@ impl ExchangeManager {
             reconnect_count: 0,
             error_count: 0,
             last_error: None,
+            circuit: CircuitState::Closed,
+            latency: None,
+            missed_pongs: 0,
+            venue_health: VenueHealth::Operational,
         });
-        subscriptions.insert(venue_id, Vec::new());
+        subscriptions.insert(venue_id.clone(), Vec::new());
+        self.uptime.write().await.insert(venue_id, VenueUptime::new());
 
         Ok(())
     }
@@ -58,6 +140,11 @@ impl ExchangeManager {
         adapters.remove(venue_id);
         connections.remove(venue_id);
         subscriptions.remove(venue_id);
+        self.desired.write().await.remove(venue_id);
+        self.circuits.write().await.remove(venue_id);
+        self.uptime.write().await.remove(venue_id);
+        self.clocks.write().await.remove(venue_id);
+        self.heartbeats.write().await.remove(venue_id);
 
         Ok(())
     }
@@ -82,8 +169,14 @@ impl ExchangeManager {
                     status.last_ping = None;
                     status.last_error = None;
                 }
+                drop(connections);
+                self.observe_uptime(venue_id, true).await;
+                if let Err(e) = self.sync_clock(venue_id).await {
+                    warn!("Could not read {} server time: {}", venue_id, e);
+                }
 
                 info!("Successfully connected to venue: {}", venue_id);
+                self.resubscribe(venue_id).await;
                 Ok(())
             }
             Err(e) => {
@@ -117,13 +210,17 @@ impl ExchangeManager {
                 drop(adapter_guard);
                 drop(adapters);
 
+                self.fanout.remove_feed(venue_id);
                 let mut connections = self.connections.write().await;
                 if let Some(status) = connections.get_mut(venue_id) {
                     status.connected = false;
                     status.last_ping = None;
                 }
+                drop(connections);
+                self.observe_uptime(venue_id, false).await;
 
-                // Clear subscriptions
+                // Clear live subscriptions; the desired ones are replayed
+                // on the next connect
                 let mut subscriptions = self.subscriptions.write().await;
                 if let Some(subs) = subscriptions.get_mut(venue_id) {
                     subs.clear();
@@ -191,11 +288,54 @@ impl ExchangeManager {
 
     pub async fn get_connection_status(&self, venue_id: &VenueId) -> Option<ConnectionStatus> {
         let connections = self.connections.read().await;
-        connections.get(venue_id).cloned()
+        let mut status = connections.get(venue_id).cloned()?;
+        self.fill_status(venue_id, &mut status).await;
+        Some(status)
     }
 
     pub async fn get_all_connection_statuses(&self) -> HashMap<VenueId, ConnectionStatus> {
-        self.connections.read().await.clone()
+        let mut statuses = self.connections.read().await.clone();
+        for (venue_id, status) in statuses.iter_mut() {
+            self.fill_status(venue_id, status).await;
+        }
+        statuses
+    }
+
+    /// Brings `status` up to date with the venue's circuit and heartbeat.
+    async fn fill_status(&self, venue_id: &VenueId, status: &mut ConnectionStatus) {
+        status.circuit = self.circuit_state(venue_id).await;
+
+        let heartbeat = self.heartbeats.read().await.get(venue_id).cloned();
+        if let Some(heartbeat) = heartbeat {
+            let beat = heartbeat.get_status().await;
+            // The venue's last answer is what shows the connection alive
+            let answered = beat
+                .last_pong
+                .and_then(|pong| chrono::Duration::from_std(pong.elapsed()).ok())
+                .map(|ago| chrono::Utc::now() - ago);
+            status.last_ping = status.last_ping.max(answered);
+            status.missed_pongs = beat.missed_pongs;
+            status.latency = heartbeat.get_latency_percentiles().await;
+        }
+        status.venue_health = self.venue_status.health(venue_id, chrono::Utc::now());
+    }
+
+    /// Venues whose connection circuit is open, having failed to reconnect
+    /// too many times in a row.
+    pub async fn failing_venues(&self) -> Vec<VenueId> {
+        let circuits = self.circuits.read().await;
+        circuits
+            .iter()
+            .filter(|(_, circuit)| circuit.lock().unwrap_or_else(|e| e.into_inner()).state().is_open())
+            .map(|(venue_id, _)| venue_id.clone())
+            .collect()
+    }
+
+    async fn circuit_state(&self, venue_id: &VenueId) -> CircuitState {
+        let circuits = self.circuits.read().await;
+        circuits
+            .get(venue_id)
+            .map_or(CircuitState::Closed, |circuit| circuit.lock().unwrap_or_else(|e| e.into_inner()).state())
     }
 
     pub async fn subscribe_orderbook(&self, venue_id: &VenueId, symbol: &Symbol, depth: Option<u32>) -> Result<()> {
@@ -212,12 +352,20 @@ impl ExchangeManager {
                 drop(adapter_guard);
                 drop(adapters);
 
+                let id = symbol.id();
+                self.remember(venue_id, DesiredSubscription {
+                    symbol: id,
+                    data_type: "orderbook".to_string(),
+                    depth,
+                })
+                .await;
+
                 let mut subscriptions = self.subscriptions.write().await;
                 if let Some(subs) = subscriptions.get_mut(venue_id) {
                     subs.push(SubscriptionInfo {
-                        symbol: symbol.clone(),
+                        symbol: id,
                         data_type: "orderbook".to_string(),
-                        subscribed_at: chrono::Utc::now(),
+                        subscribed_at: EventTime::now(),
                         message_count: 0,
                         last_message: None,
                     });
@@ -247,12 +395,20 @@ impl ExchangeManager {
                 drop(adapter_guard);
                 drop(adapters);
 
+                let id = symbol.id();
+                self.remember(venue_id, DesiredSubscription {
+                    symbol: id,
+                    data_type: "trades".to_string(),
+                    depth: None,
+                })
+                .await;
+
                 let mut subscriptions = self.subscriptions.write().await;
                 if let Some(subs) = subscriptions.get_mut(venue_id) {
                     subs.push(SubscriptionInfo {
-                        symbol: symbol.clone(),
+                        symbol: id,
                         data_type: "trades".to_string(),
-                        subscribed_at: chrono::Utc::now(),
+                        subscribed_at: EventTime::now(),
                         message_count: 0,
                         last_message: None,
                     });
@@ -268,6 +424,64 @@ impl ExchangeManager {
         }
     }
 
+    /// Subscribes to `symbol`'s book as `mode` asks for: the full depth
+    /// stream, or only its best bid and offer.
+    pub async fn subscribe_book(
+        &self,
+        venue_id: &VenueId,
+        symbol: &Symbol,
+        mode: MarketDataMode,
+        depth: Option<u32>,
+    ) -> Result<()> {
+        match mode {
+            MarketDataMode::Full => self.subscribe_orderbook(venue_id, symbol, depth).await,
+            MarketDataMode::BboOnly => self.subscribe_bbo(venue_id, symbol).await,
+        }
+    }
+
+    pub async fn subscribe_bbo(&self, venue_id: &VenueId, symbol: &Symbol) -> Result<()> {
+        debug!("Subscribing to best bid and offer for {} on {}", symbol, venue_id);
+
+        let adapters = self.adapters.read().await;
+        let adapter = adapters
+            .get(venue_id)
+            .ok_or_else(|| ArbFinderError::Exchange(format!("Adapter not found for venue: {}", venue_id)))?;
+
+        let mut adapter_guard = adapter.lock().await;
+        match adapter_guard.subscribe_bbo(symbol).await {
+            Ok(_) => {
+                drop(adapter_guard);
+                drop(adapters);
+
+                let id = symbol.id();
+                self.remember(venue_id, DesiredSubscription {
+                    symbol: id,
+                    data_type: "bbo".to_string(),
+                    depth: None,
+                })
+                .await;
+
+                let mut subscriptions = self.subscriptions.write().await;
+                if let Some(subs) = subscriptions.get_mut(venue_id) {
+                    subs.push(SubscriptionInfo {
+                        symbol: id,
+                        data_type: "bbo".to_string(),
+                        subscribed_at: EventTime::now(),
+                        message_count: 0,
+                        last_message: None,
+                    });
+                }
+
+                debug!("Successfully subscribed to best bid and offer for {} on {}", symbol, venue_id);
+                Ok(())
+            }
+            Err(e) => {
+                error!("Failed to subscribe to best bid and offer for {} on {}: {}", symbol, venue_id, e);
+                Err(e)
+            }
+        }
+    }
+
     pub async fn unsubscribe_orderbook(&self, venue_id: &VenueId, symbol: &Symbol) -> Result<()> {
         debug!("Unsubscribing from orderbook for {} on {}", symbol, venue_id);
 
@@ -282,9 +496,14 @@ impl ExchangeManager {
                 drop(adapter_guard);
                 drop(adapters);
 
+                let id = symbol.id();
                 let mut subscriptions = self.subscriptions.write().await;
                 if let Some(subs) = subscriptions.get_mut(venue_id) {
-                    subs.retain(|sub| !(sub.symbol == *symbol && sub.data_type == "orderbook"));
+                    subs.retain(|sub| !(sub.symbol == id && sub.data_type == "orderbook"));
+                }
+                let mut desired = self.desired.write().await;
+                if let Some(desired) = desired.get_mut(venue_id) {
+                    desired.retain(|sub| !(sub.symbol == id && sub.data_type == "orderbook"));
                 }
 
                 debug!("Successfully unsubscribed from orderbook for {} on {}", symbol, venue_id);
@@ -297,6 +516,57 @@ impl ExchangeManager {
         }
     }
 
+    /// Subscriptions replayed on `venue_id` whenever it connects.
+    pub async fn get_desired_subscriptions(&self, venue_id: &VenueId) -> Vec<DesiredSubscription> {
+        let desired = self.desired.read().await;
+        desired.get(venue_id).cloned().unwrap_or_default()
+    }
+
+    async fn remember(&self, venue_id: &VenueId, subscription: DesiredSubscription) {
+        let mut desired = self.desired.write().await;
+        let desired = desired.entry(venue_id.clone()).or_default();
+        desired.retain(|sub| !(sub.symbol == subscription.symbol && sub.data_type == subscription.data_type));
+        desired.push(subscription);
+    }
+
+    /// Replays the desired subscriptions of `venue_id` not live on it, as
+    /// after its connection dropped. Failures are logged and left for the
+    /// next connect. Returns how many were replayed.
+    pub async fn resubscribe(&self, venue_id: &VenueId) -> usize {
+        let live = self.get_subscriptions(venue_id).await;
+        let missing: Vec<DesiredSubscription> = self
+            .get_desired_subscriptions(venue_id)
+            .await
+            .into_iter()
+            .filter(|desired| !live.iter().any(|sub| sub.symbol == desired.symbol && sub.data_type == desired.data_type))
+            .collect();
+        if missing.is_empty() {
+            return 0;
+        }
+
+        info!("Resubscribing to {} streams on {}", missing.len(), venue_id);
+        let mut replayed = 0;
+        for desired in missing {
+            let result = match desired.data_type.as_str() {
+                "orderbook" => self.subscribe_orderbook(venue_id, desired.symbol.symbol(), desired.depth).await,
+                "trades" => self.subscribe_trades(venue_id, desired.symbol.symbol()).await,
+                "bbo" => self.subscribe_bbo(venue_id, desired.symbol.symbol()).await,
+                other => {
+                    warn!("Not resubscribing to unknown {} stream for {} on {}", other, desired.symbol, venue_id);
+                    continue;
+                }
+            };
+            match result {
+                Ok(()) => replayed += 1,
+                Err(e) => warn!(
+                    "Failed to resubscribe to {} for {} on {}: {}",
+                    desired.data_type, desired.symbol, venue_id, e
+                ),
+            }
+        }
+        replayed
+    }
+
     pub async fn get_subscriptions(&self, venue_id: &VenueId) -> Vec<SubscriptionInfo> {
         let subscriptions = self.subscriptions.read().await;
         subscriptions.get(venue_id).cloned().unwrap_or_default()
@@ -306,11 +576,97 @@ impl ExchangeManager {
         self.subscriptions.read().await.clone()
     }
 
-    pub async fn get_adapter(&self, venue_id: &VenueId) -> Option<Arc<Mutex<Box<dyn ExchangeAdapter>>>> {
+    pub async fn get_adapter(&self, venue_id: &VenueId) -> Option<Arc<Mutex<VenueAdapter>>> {
         let adapters = self.adapters.read().await;
         adapters.get(venue_id).cloned()
     }
 
+    /// Whether `venue_id` was added with a trading adapter.
+    pub async fn can_trade(&self, venue_id: &VenueId) -> bool {
+        match self.get_adapter(venue_id).await {
+            Some(adapter) => adapter.lock().await.can_trade(),
+            None => false,
+        }
+    }
+
+    /// Venues added with a trading adapter.
+    pub async fn get_trading_venues(&self) -> Vec<VenueId> {
+        let adapters: Vec<_> = self
+            .adapters
+            .read()
+            .await
+            .iter()
+            .map(|(venue_id, adapter)| (venue_id.clone(), Arc::clone(adapter)))
+            .collect();
+        let mut venues = Vec::new();
+        for (venue_id, adapter) in adapters {
+            if adapter.lock().await.can_trade() {
+                venues.push(venue_id);
+            }
+        }
+        venues
+    }
+
+    /// Market data for every book, full or best bid and offer, subscribed
+    /// on `venue_id`, polled over REST while the venue's stream is down.
+    pub async fn market_data_feed(&self, venue_id: &VenueId, config: FallbackConfig) -> Result<FeedStream> {
+        let adapter = self
+            .get_adapter(venue_id)
+            .await
+            .ok_or_else(|| ArbFinderError::Exchange(format!("Adapter not found for venue: {}", venue_id)))?;
+        let symbols = self
+            .get_subscriptions(venue_id)
+            .await
+            .into_iter()
+            .filter(|subscription| subscription.data_type == "orderbook" || subscription.data_type == "bbo")
+            .map(|subscription| subscription.symbol.symbol().clone())
+            .collect();
+        Ok(resilient_market_data(adapter, symbols, config))
+    }
+
+    /// Every connected venue's market data in one stream, each update
+    /// tagged with its venue. Each call adds a subscriber with its own
+    /// buffer to a single shared feed; venues connected since the last call
+    /// are added to the feed. Venues whose stream cannot be opened are
+    /// logged and left out, unless none can be.
+    pub async fn market_data_stream(&self) -> Result<VenueMarketDataStream> {
+        // Subscribed first so that nothing sent as feeds open is missed
+        let stream = self.fanout.subscribe();
+
+        let mut failed = Vec::new();
+        for venue_id in self.get_connected_venues().await {
+            if self.fanout.has_feed(&venue_id) {
+                continue;
+            }
+            let Some(adapter) = self.get_adapter(&venue_id).await else {
+                continue;
+            };
+            let opened = adapter.lock().await.market_data_stream().await;
+            match opened {
+                Ok(feed) => self.fanout.add_feed(venue_id, feed),
+                Err(e) => {
+                    warn!("Failed to open market data stream for {}: {}", venue_id, e);
+                    failed.push(e.to_string());
+                }
+            }
+        }
+
+        if self.fanout.venues().is_empty() {
+            return Err(ArbFinderError::Exchange(if failed.is_empty() {
+                "No connected venues to stream market data from".to_string()
+            } else {
+                format!("No market data streams could be opened: {}", failed.join("; "))
+            }));
+        }
+        Ok(stream)
+    }
+
+    /// The feed behind [`Self::market_data_stream`], to subscribe to
+    /// without opening streams for newly connected venues.
+    pub fn market_data_fanout(&self) -> Arc<MarketDataFanout> {
+        Arc::clone(&self.fanout)
+    }
+
     pub async fn get_available_venues(&self) -> Vec<VenueId> {
         let adapters = self.adapters.read().await;
         adapters.keys().cloned().collect()
@@ -325,13 +681,13 @@ impl ExchangeManager {
             .collect()
     }
 
-    pub async fn record_message(&self, venue_id: &VenueId, symbol: &Symbol, data_type: &str) {
+    pub async fn record_message(&self, venue_id: &VenueId, symbol: SymbolId, data_type: &str) {
         let mut subscriptions = self.subscriptions.write().await;
         if let Some(subs) = subscriptions.get_mut(venue_id) {
             for sub in subs.iter_mut() {
-                if sub.symbol == *symbol && sub.data_type == data_type {
+                if sub.symbol == symbol && sub.data_type == data_type {
                     sub.message_count += 1;
-                    sub.last_message = Some(chrono::Utc::now());
+                    sub.last_message = Some(EventTime::now());
                     break;
                 }
             }
@@ -367,10 +723,27 @@ impl ExchangeManager {
             };
             health_status.insert(venue_id.clone(), is_healthy);
         }
+        drop(adapters);
+
+        // A dropped connection counts as down from when it is noticed
+        let now = Instant::now();
+        let mut uptime = self.uptime.write().await;
+        for (venue_id, is_healthy) in &health_status {
+            if let Some(venue_uptime) = uptime.get_mut(venue_id) {
+                venue_uptime.observe(*is_healthy, now);
+            }
+        }
+        drop(uptime);
 
         health_status
     }
 
+    async fn observe_uptime(&self, venue_id: &VenueId, connected: bool) {
+        if let Some(uptime) = self.uptime.write().await.get_mut(venue_id) {
+            uptime.observe(connected, Instant::now());
+        }
+    }
+
     pub async fn restart_adapter(&self, venue_id: &VenueId) -> Result<()> {
         info!("Restarting adapter for venue: {}", venue_id);
 
@@ -394,6 +767,186 @@ impl ExchangeManager {
         info!("Successfully restarted adapter for venue: {}", venue_id);
         Ok(())
     }
+
+    /// Reads `venue_id`'s server time once, updating its clock skew.
+    pub async fn sync_clock(&self, venue_id: &VenueId) -> Result<SkewSample> {
+        let adapter = self
+            .get_adapter(venue_id)
+            .await
+            .ok_or_else(|| ArbFinderError::Exchange(format!("Adapter not found for venue: {}", venue_id)))?;
+        let sample = measure_clock_skew(&**adapter.lock().await).await?;
+
+        let clocks = self.clocks.read().await;
+        if let Some(clock) = clocks.get(venue_id) {
+            clock.lock().unwrap_or_else(|e| e.into_inner()).record(sample);
+        }
+        let off_by = sample.offset.abs().to_std().unwrap_or_default();
+        if off_by > CLOCK_SKEW_WARNING {
+            warn!("{} clock is {}ms off ours", venue_id, sample.offset.num_milliseconds());
+        } else {
+            debug!(
+                "{} clock offset {}ms over a {}ms round trip",
+                venue_id,
+                sample.offset.num_milliseconds(),
+                sample.round_trip.as_millis()
+            );
+        }
+        Ok(sample)
+    }
+
+    /// Reads the server time of every connected venue each `interval`,
+    /// until the returned task is aborted.
+    pub fn sync_clocks(self: &Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        let manager = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut syncs = tokio::time::interval(interval);
+            syncs.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                syncs.tick().await;
+                for (venue, status) in manager.get_all_connection_statuses().await {
+                    if !status.connected {
+                        continue;
+                    }
+                    if let Err(e) = manager.sync_clock(&venue).await {
+                        warn!("Could not read {} server time: {}", venue, e);
+                    }
+                }
+            }
+        })
+    }
+
+    /// Reads `venue_id`'s status page once, returning the change in its
+    /// health if there was one.
+    pub async fn check_venue_status(&self, venue_id: &VenueId) -> Result<Option<VenueStatusEvent>> {
+        let adapter = self
+            .get_adapter(venue_id)
+            .await
+            .ok_or_else(|| ArbFinderError::Exchange(format!("Adapter not found for venue: {}", venue_id)))?;
+        let report = adapter.lock().await.venue_status().await?;
+        Ok(self.venue_status.on_report(venue_id, report, chrono::Utc::now()))
+    }
+
+    /// Reads every venue's status page each `interval`, sending changes in
+    /// their health to `events`, until `events` is closed.
+    pub fn watch_venue_status(
+        self: &Arc<Self>,
+        interval: Duration,
+        events: mpsc::UnboundedSender<VenueStatusEvent>,
+    ) -> JoinHandle<()> {
+        let manager = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut checks = tokio::time::interval(interval);
+            checks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            while !events.is_closed() {
+                checks.tick().await;
+                let venues: Vec<VenueId> = manager.adapters.read().await.keys().cloned().collect();
+                for venue in venues {
+                    match manager.check_venue_status(&venue).await {
+                        Ok(Some(event)) => {
+                            info!("{}", event);
+                            let _ = events.send(event);
+                        }
+                        Ok(None) => {}
+                        Err(e) => debug!("Could not read {} status: {}", venue, e),
+                    }
+                }
+            }
+        })
+    }
+
+    /// Shared board of venue status reports.
+    pub fn venue_status(&self) -> VenueStatusBoard {
+        self.venue_status.clone()
+    }
+
+    /// `venue_id`'s clock estimate, if it has an adapter.
+    pub async fn clock_skew(&self, venue_id: &VenueId) -> Option<ClockSkew> {
+        let clocks = self.clocks.read().await;
+        let clock = clocks.get(venue_id)?;
+        let skew = clock.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        Some(skew)
+    }
+
+    /// `venue_time`, stamped by `venue_id`, on our clock, for ordering it
+    /// against other venues' timestamps.
+    pub async fn local_time(&self, venue_id: &VenueId, venue_time: chrono::DateTime<chrono::Utc>) -> chrono::DateTime<chrono::Utc> {
+        match self.clock_skew(venue_id).await {
+            Some(skew) => skew.to_local(venue_time),
+            None => venue_time,
+        }
+    }
+
+    /// Restarts venues that lose their connection, or stop being pinged,
+    /// until `events` is dropped. Only venues connected through the manager
+    /// are watched, so ones disconnected on purpose are left alone.
+    pub fn supervise(
+        self: &Arc<Self>,
+        config: SupervisorConfig,
+        events: mpsc::UnboundedSender<SupervisorEvent>,
+    ) -> JoinHandle<()> {
+        let manager = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut restarts = Restarts::default();
+            let mut checks = tokio::time::interval(config.check_interval);
+            checks.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            while !events.is_closed() {
+                checks.tick().await;
+                let connections = manager.get_all_connection_statuses().await;
+                for (venue, connected) in manager.health_check().await {
+                    let Some(status) = connections.get(&venue) else {
+                        continue;
+                    };
+                    if !status.connected && !restarts.is_failing(&venue) {
+                        continue;
+                    }
+
+                    let ping_age = status
+                        .last_ping
+                        .and_then(|ping| (chrono::Utc::now() - ping).to_std().ok());
+                    let reason = match (connected, ping_age, config.stale_after) {
+                        (false, _, _) => "connection lost".to_string(),
+                        (true, Some(age), Some(stale_after)) if age > stale_after => {
+                            format!("no ping for {}s", age.as_secs())
+                        }
+                        _ => {
+                            if restarts.healthy(&venue).is_some() {
+                                let _ = events.send(SupervisorEvent::Recovered { venue });
+                            }
+                            continue;
+                        }
+                    };
+
+                    let (newly, attempt) = restarts.unhealthy(&venue, std::time::Instant::now());
+                    if newly {
+                        warn!("{} is unhealthy: {}", venue, reason);
+                        let _ = events.send(SupervisorEvent::Unhealthy { venue: venue.clone(), reason });
+                    }
+                    let Some(attempt) = attempt else {
+                        continue;
+                    };
+                    let event = match manager.restart_adapter(&venue).await {
+                        Ok(()) => {
+                            restarts.healthy(&venue);
+                            SupervisorEvent::Restarted { venue, attempts: attempt }
+                        }
+                        Err(e) => {
+                            let error = e.to_string();
+                            match restarts.failed(&venue, std::time::Instant::now(), &config.backoff) {
+                                Some(retry_in) => SupervisorEvent::RestartFailed { venue, attempt, error, retry_in },
+                                None => SupervisorEvent::GaveUp { venue, attempts: attempt, error },
+                            }
+                        }
+                    };
+                    if event.is_critical() {
+                        error!("{}", event);
+                    } else {
+                        info!("{}", event);
+                    }
+                    let _ = events.send(event);
+                }
+            }
+        })
+    }
 }
 
 impl Default for ExchangeManager {
@@ -415,7 +968,14 @@ pub struct MarketDataStats {
     pub messages_per_second: f64,
     pub last_message_time: Option<chrono::DateTime<chrono::Utc>>,
     pub symbols_subscribed: usize,
+    /// Share of the time since the venue first connected that it has been
+    /// connected.
     pub uptime_percentage: f64,
+    /// Disconnections since the venue first connected.
+    pub gaps: u64,
+    pub longest_gap: Duration,
+    /// How long the venue has been down, if it is.
+    pub current_gap: Option<Duration>,
 }
 
 #[async_trait]
@@ -450,7 +1010,7 @@ impl ExchangeManagerExt for ExchangeManager {
 
     async fn get_market_data_stats(&self) -> HashMap<VenueId, MarketDataStats> {
         let subscriptions = self.subscriptions.read().await;
-        let connections = self.connections.read().await;
+        let uptime = self.uptime.read().await;
         let mut stats = HashMap::new();
 
         for (venue_id, subs) in subscriptions.iter() {
@@ -463,9 +1023,9 @@ impl ExchangeManagerExt for ExchangeManager {
             
             // Calculate messages per second (rough estimate)
             let messages_per_second = if let Some(last_msg) = last_message_time {
-                let elapsed = chrono::Utc::now().signed_duration_since(last_msg);
-                if elapsed.num_seconds() > 0 {
-                    total_messages as f64 / elapsed.num_seconds() as f64
+                let elapsed = last_msg.elapsed().as_secs();
+                if elapsed > 0 {
+                    total_messages as f64 / elapsed as f64
                 } else {
                     0.0
                 }
@@ -473,29 +1033,18 @@ impl ExchangeManagerExt for ExchangeManager {
                 0.0
             };
 
-            // Calculate uptime percentage based on connection status
-            let uptime_percentage = if let Some(status) = connections.get(venue_id) {
-                if status.connected {
-                    // If currently connected, consider uptime based on error rate
-                    let error_rate = if total_messages > 0 {
-                        (status.error_count as f64 / total_messages as f64) * 100.0
-                    } else {
-                        0.0
-                    };
-                    (100.0 - error_rate).max(0.0)
-                } else {
-                    0.0 // Disconnected means 0% uptime
-                }
-            } else {
-                0.0
-            };
+            let now = Instant::now();
+            let venue_uptime = uptime.get(venue_id).cloned().unwrap_or_default();
 
             stats.insert(venue_id.clone(), MarketDataStats {
                 total_messages,
                 messages_per_second,
-                last_message_time,
+                last_message_time: last_message_time.map(|t| t.wall()),
                 symbols_subscribed,
-                uptime_percentage,
+                uptime_percentage: venue_uptime.uptime_percentage(now),
+                gaps: venue_uptime.gaps(),
+                longest_gap: venue_uptime.longest_gap(now),
+                current_gap: venue_uptime.current_gap(now),
             });
         }
 
@@ -510,6 +1059,7 @@ mod tests {
     use async_trait::async_trait;
     use futures::Stream;
     use std::pin::Pin;
+    use crate::traits::TradingAdapter;
 
     // Mock adapter for testing
     struct MockAdapter {
@@ -527,7 +1077,7 @@ mod tests {
     }
 
     #[async_trait]
-    impl ExchangeAdapter for MockAdapter {
+    impl MarketDataAdapter for MockAdapter {
         fn venue_id(&self) -> VenueId {
             self.venue_id.clone()
         }
@@ -600,12 +1150,15 @@ mod tests {
         async fn market_data_stream(&self) -> Result<Pin<Box<dyn Stream<Item = Result<MarketData>> + Send>>> {
             Err(ArbFinderError::Exchange("Mock adapter does not support streaming".to_string()))
         }
+    }
 
+    #[async_trait]
+    impl TradingAdapter for MockAdapter {
         async fn order_update_stream(&self) -> Result<Pin<Box<dyn Stream<Item = Result<OrderUpdate>> + Send>>> {
             Err(ArbFinderError::Exchange("Mock adapter does not support streaming".to_string()))
         }
 
-        async fn place_order(&mut self, _request: &OrderRequest) -> Result<Order> {
+        async fn place_order(&self, _request: &OrderRequest) -> Result<Order> {
             Ok(Order {
                 id: OrderId::new("mock_order_123"),
                 symbol: Symbol::new("BTC", "USDT"),
@@ -619,11 +1172,11 @@ mod tests {
             })
         }
 
-        async fn cancel_order(&mut self, _order_id: &OrderId) -> Result<()> {
+        async fn cancel_order(&self, _order_id: &OrderId) -> Result<()> {
             Ok(())
         }
 
-        async fn cancel_all_orders(&mut self, _symbol: Option<&Symbol>) -> Result<Vec<OrderId>> {
+        async fn cancel_all_orders(&self, _symbol: Option<&Symbol>) -> Result<Vec<OrderId>> {
             Ok(vec![])
         }
 
@@ -715,4 +1268,24 @@ mod tests {
         let subscriptions = manager.get_subscriptions(&venue_id).await;
         assert_eq!(subscriptions.len(), 1);
     }
+
+    #[tokio::test]
+    async fn test_market_data_only_venue() {
+        let manager = ExchangeManager::new();
+        manager.add_adapter(Box::new(MockAdapter::new(VenueId::Binance))).await.unwrap();
+        manager
+            .add_market_data_adapter(Box::new(MockAdapter::new(VenueId::Poloniex)))
+            .await
+            .unwrap();
+
+        manager.connect(&VenueId::Poloniex).await.unwrap();
+        manager.subscribe_orderbook(&VenueId::Poloniex, &Symbol::new("BTC", "USDT"), None).await.unwrap();
+        assert!(manager.is_connected(&VenueId::Poloniex).await);
+
+        assert!(manager.can_trade(&VenueId::Binance).await);
+        assert!(!manager.can_trade(&VenueId::Poloniex).await);
+        assert_eq!(manager.get_trading_venues().await, vec![VenueId::Binance]);
+        let adapter = manager.get_adapter(&VenueId::Poloniex).await.unwrap();
+        assert!(adapter.lock().await.trading().is_none());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,132 @@
+//! Chain names across venues.
+//!
+//! A deposit address carries its chain as the receiving venue names it,
+//! but the withdrawal is requested from the sending venue, which names the
+//! same chain differently (`ETH`, `ethereum`, `ERC20`, ...). Sending the
+//! address on the wrong chain loses the funds, so the name is translated
+//! here, and a transfer whose chain can't be translated is refused.
+
+use arbfinder_core::{ArbFinderError, DepositAddress, Result, VenueId};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Chain {
+    Bitcoin,
+    Ethereum,
+    Tron,
+    BnbSmartChain,
+    Solana,
+    Ripple,
+    Polygon,
+    Arbitrum,
+    Optimism,
+    Base,
+    Litecoin,
+}
+
+/// Each venue's names for each chain; the first is the one it is sent.
+/// Kraken names deposit methods per asset, so it has several per chain.
+const NETWORK_NAMES: &[(Chain, &str, &[&str])] = &[
+    (Chain::Bitcoin, "binance", &["BTC"]),
+    (Chain::Bitcoin, "coinbase", &["bitcoin"]),
+    (Chain::Bitcoin, "bitget", &["BTC"]),
+    (Chain::Bitcoin, "kraken", &["Bitcoin"]),
+    (Chain::Ethereum, "binance", &["ETH"]),
+    (Chain::Ethereum, "coinbase", &["ethereum"]),
+    (Chain::Ethereum, "bitget", &["ERC20"]),
+    (Chain::Ethereum, "kraken", &["Ether (Hex)", "Tether USD (ERC20)", "USDC (ERC20)"]),
+    (Chain::Tron, "binance", &["TRX"]),
+    (Chain::Tron, "bitget", &["TRC20"]),
+    (Chain::Tron, "kraken", &["Tether USD (TRC20)"]),
+    (Chain::BnbSmartChain, "binance", &["BSC"]),
+    (Chain::BnbSmartChain, "bitget", &["BEP20"]),
+    (Chain::Solana, "binance", &["SOL"]),
+    (Chain::Solana, "coinbase", &["solana"]),
+    (Chain::Solana, "bitget", &["SOL"]),
+    (Chain::Solana, "kraken", &["Solana", "Tether USD (SPL)", "USDC (SPL)"]),
+    (Chain::Ripple, "binance", &["XRP"]),
+    (Chain::Ripple, "coinbase", &["ripple"]),
+    (Chain::Ripple, "bitget", &["XRP"]),
+    (Chain::Ripple, "kraken", &["Ripple XRP"]),
+    (Chain::Polygon, "binance", &["MATIC"]),
+    (Chain::Polygon, "coinbase", &["polygon"]),
+    (Chain::Polygon, "bitget", &["Polygon"]),
+    (Chain::Arbitrum, "binance", &["ARBITRUM"]),
+    (Chain::Arbitrum, "coinbase", &["arbitrum"]),
+    (Chain::Arbitrum, "bitget", &["ArbitrumOne"]),
+    (Chain::Optimism, "binance", &["OPTIMISM"]),
+    (Chain::Optimism, "coinbase", &["optimism"]),
+    (Chain::Optimism, "bitget", &["Optimism"]),
+    (Chain::Base, "binance", &["BASE"]),
+    (Chain::Base, "coinbase", &["base"]),
+    (Chain::Base, "bitget", &["BASE"]),
+    (Chain::Litecoin, "binance", &["LTC"]),
+    (Chain::Litecoin, "coinbase", &["litecoin"]),
+    (Chain::Litecoin, "bitget", &["LTC"]),
+    (Chain::Litecoin, "kraken", &["Litecoin"]),
+];
+
+impl Chain {
+    /// The chain `venue` calls `network`.
+    pub fn from_network(venue: &VenueId, network: &str) -> Option<Self> {
+        let venue = venue.to_string();
+        NETWORK_NAMES
+            .iter()
+            .find(|(_, v, names)| *v == venue && names.iter().any(|name| name.eq_ignore_ascii_case(network)))
+            .map(|(chain, _, _)| *chain)
+    }
+
+    /// What `venue` calls the chain.
+    pub fn network(&self, venue: &VenueId) -> Option<&'static str> {
+        let venue = venue.to_string();
+        NETWORK_NAMES
+            .iter()
+            .find(|(chain, v, _)| chain == self && *v == venue)
+            .map(|(_, _, names)| names[0])
+    }
+}
+
+/// `address`, handed out by `to`, with its network renamed for `from` to
+/// withdraw to. Errors when `to` didn't say which chain the address is on,
+/// or either venue's name for it is unknown.
+pub fn address_for_sender(address: &DepositAddress, to: &VenueId, from: &VenueId) -> Result<DepositAddress> {
+    let received = address.network.as_deref().ok_or_else(|| {
+        ArbFinderError::Execution(format!("{} did not say which network its {} address is on", to, address.asset))
+    })?;
+    let chain = Chain::from_network(to, received).ok_or_else(|| {
+        ArbFinderError::Execution(format!("Unknown {} network {} for {}", to, received, address.asset))
+    })?;
+    let sent = chain.network(from).ok_or_else(|| {
+        ArbFinderError::Execution(format!("{} has no name for the {:?} network of {}", from, chain, address.asset))
+    })?;
+
+    let mut address = address.clone();
+    address.network = Some(sent.to_string());
+    Ok(address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_address_for_sender() {
+        let mut address = DepositAddress::new("USDT", "0x8c4B0E07D6Bc1CC3a8C9fA22f2C0aD0fb5C4C8e1");
+        assert!(address_for_sender(&address, &VenueId::Bitget, &VenueId::Binance).is_err());
+
+        address.network = Some("ERC20".to_string());
+        let sent = address_for_sender(&address, &VenueId::Bitget, &VenueId::Binance).unwrap();
+        assert_eq!(sent.network.as_deref(), Some("ETH"));
+        assert_eq!(sent.address, address.address);
+        let sent = address_for_sender(&address, &VenueId::Bitget, &VenueId::Coinbase).unwrap();
+        assert_eq!(sent.network.as_deref(), Some("ethereum"));
+
+        address.network = Some("Tether USD (TRC20)".to_string());
+        let sent = address_for_sender(&address, &VenueId::Kraken, &VenueId::Bitget).unwrap();
+        assert_eq!(sent.network.as_deref(), Some("TRC20"));
+        // Coinbase doesn't send on Tron
+        assert!(address_for_sender(&address, &VenueId::Kraken, &VenueId::Coinbase).is_err());
+
+        address.network = Some("Lightning".to_string());
+        assert!(address_for_sender(&address, &VenueId::Kraken, &VenueId::Binance).is_err());
+    }
+}
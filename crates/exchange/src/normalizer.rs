@@ -61,6 +61,8 @@ impl DefaultSymbolNormalizer {
         self.add_type_mapping("IMMEDIATE_OR_CANCEL", OrderType::ImmediateOrCancel);
         self.add_type_mapping("ioc", OrderType::ImmediateOrCancel);
         self.add_type_mapping("IOC", OrderType::ImmediateOrCancel);
+        self.add_type_mapping("oco", OrderType::Oco);
+        self.add_type_mapping("OCO", OrderType::Oco);
     }
 
     pub fn add_symbol_mapping(&mut self, exchange_symbol: String, normalized_symbol: Symbol) {
@@ -0,0 +1,210 @@
+//! TLS public key pinning per venue.
+//!
+//! A pin is the SHA-256 of a certificate's SubjectPublicKeyInfo in base64,
+//! written `sha256/...` as HPKP had it. It survives certificate renewals
+//! that keep the key, and is printed for a venue by:
+//!
+//! ```text
+//! openssl s_client -connect api.binance.com:443 </dev/null 2>/dev/null \
+//!   | openssl x509 -pubkey -noout | openssl pkey -pubin -outform der \
+//!   | openssl dgst -sha256 -binary | base64
+//! ```
+//!
+//! The server's own certificate is checked after the usual chain
+//! validation: during the handshake for REST clients, and for WebSockets
+//! once TLS is up but before the upgrade request is sent. Pins that are not
+//! enforced only log mismatches, for riding out a venue rotating its keys.
+
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use arbfinder_core::{ArbFinderError, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls::{Certificate, ClientConfig, OwnedTrustAnchor, RootCertStore, ServerName};
+use sha2::{Digest, Sha256};
+use tracing::{error, warn};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsPins {
+    pins: Vec<[u8; 32]>,
+    /// Refuse connections to servers whose key is not pinned. Otherwise
+    /// they are only logged.
+    pub enforce: bool,
+}
+
+impl TlsPins {
+    /// Enforced pins from their `sha256/<base64>` forms.
+    pub fn new<S: AsRef<str>>(pins: &[S]) -> Result<Self> {
+        if pins.is_empty() {
+            return Err(ArbFinderError::InvalidData("No certificate pins given".to_string()));
+        }
+        let pins = pins
+            .iter()
+            .map(|pin| {
+                let pin = pin.as_ref();
+                let hash = pin
+                    .strip_prefix("sha256/")
+                    .and_then(|hash| STANDARD.decode(hash).ok())
+                    .and_then(|hash| <[u8; 32]>::try_from(hash).ok());
+                hash.ok_or_else(|| ArbFinderError::InvalidData(format!("Invalid certificate pin: {}", pin)))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { pins, enforce: true })
+    }
+
+    /// Pins that only log mismatches.
+    pub fn report_only(mut self) -> Self {
+        self.enforce = false;
+        self
+    }
+
+    /// Checks the DER certificate `host` presented against the pins.
+    pub fn check(&self, host: &str, certificate: &[u8]) -> Result<()> {
+        let hash = spki_sha256(certificate)?;
+        if self.pins.contains(&hash) {
+            return Ok(());
+        }
+
+        let message = format!(
+            "{} presented key sha256/{}, which is not pinned; if the venue rotated its certificate, update the pins or set enforce = false",
+            host,
+            STANDARD.encode(hash)
+        );
+        if self.enforce {
+            error!("{}", message);
+            Err(ArbFinderError::Authentication(message))
+        } else {
+            warn!("{}", message);
+            Ok(())
+        }
+    }
+
+    /// TLS settings for a REST client that verifies these pins, passed to
+    /// `reqwest::ClientBuilder::use_preconfigured_tls`.
+    pub fn client_config(&self) -> ClientConfig {
+        let mut roots = RootCertStore::empty();
+        roots.add_trust_anchors(webpki_roots::TLS_SERVER_ROOTS.iter().map(|anchor| {
+            OwnedTrustAnchor::from_subject_spki_name_constraints(anchor.subject, anchor.spki, anchor.name_constraints)
+        }));
+        let verifier = PinnedVerifier {
+            pins: self.clone(),
+            inner: WebPkiVerifier::new(roots, None),
+        };
+
+        ClientConfig::builder()
+            .with_safe_defaults()
+            .with_custom_certificate_verifier(Arc::new(verifier))
+            .with_no_client_auth()
+    }
+
+    /// A REST client that verifies these pins.
+    pub fn http_client(&self, builder: reqwest::ClientBuilder) -> Result<reqwest::Client> {
+        builder
+            .use_preconfigured_tls(self.client_config())
+            .build()
+            .map_err(ArbFinderError::Http)
+    }
+}
+
+/// Validates the chain as usual, then the server's key against the pins.
+struct PinnedVerifier {
+    pins: TlsPins,
+    inner: WebPkiVerifier,
+}
+
+impl ServerCertVerifier for PinnedVerifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> std::result::Result<ServerCertVerified, rustls::Error> {
+        let verified = self
+            .inner
+            .verify_server_cert(end_entity, intermediates, server_name, scts, ocsp_response, now)?;
+        let host = match server_name {
+            ServerName::DnsName(name) => name.as_ref().to_string(),
+            ServerName::IpAddress(ip) => ip.to_string(),
+            _ => "server".to_string(),
+        };
+        self.pins
+            .check(&host, &end_entity.0)
+            .map_err(|e| rustls::Error::General(e.to_string()))?;
+        Ok(verified)
+    }
+}
+
+/// SHA-256 of the SubjectPublicKeyInfo of a DER certificate.
+pub fn spki_sha256(certificate: &[u8]) -> Result<[u8; 32]> {
+    let malformed = || ArbFinderError::InvalidData("Malformed certificate".to_string());
+
+    // Certificate ::= SEQUENCE { tbsCertificate, signatureAlgorithm, signature }
+    let (_, certificate, _) = der_element(certificate, 0x30).ok_or_else(malformed)?;
+    let (_, mut tbs, _) = der_element(certificate, 0x30).ok_or_else(malformed)?;
+    // The explicitly tagged version is absent from v1 certificates
+    if tbs.first() == Some(&0xa0) {
+        tbs = der_element(tbs, 0xa0).ok_or_else(malformed)?.2;
+    }
+    // serialNumber, signature, issuer, validity and subject precede it
+    for tag in [0x02, 0x30, 0x30, 0x30, 0x30] {
+        tbs = der_element(tbs, tag).ok_or_else(malformed)?.2;
+    }
+    let (spki, _, _) = der_element(tbs, 0x30).ok_or_else(malformed)?;
+
+    Ok(Sha256::digest(spki).into())
+}
+
+/// Splits the DER element with `tag` at the start of `der` into the whole
+/// element, its contents and what follows it.
+fn der_element(der: &[u8], tag: u8) -> Option<(&[u8], &[u8], &[u8])> {
+    if *der.first()? != tag {
+        return None;
+    }
+    let (length, header) = match *der.get(1)? {
+        short @ 0..=0x7f => (short as usize, 2),
+        long @ 0x81..=0x84 => {
+            let bytes = (long & 0x7f) as usize;
+            let length = der.get(2..2 + bytes)?.iter().fold(0usize, |length, &b| (length << 8) | b as usize);
+            (length, 2 + bytes)
+        }
+        _ => return None,
+    };
+    let end = header.checked_add(length)?;
+    Some((der.get(..end)?, der.get(header..end)?, der.get(end..)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Self-signed P-256 certificate for api.example.com
+    const CERTIFICATE: &str = "MIIBiTCCAS+gAwIBAgIUJBeUEuvPGddHj1+1OsZ78KxbECMwCgYIKoZIzj0EAwIwGjEYMBYGA1UEAwwPYXBpLmV4YW1wbGUuY29tMB4XDTI2MTAxNjE1MDg1MFoXDTM2MTAxMzE1MDg1MFowGjEYMBYGA1UEAwwPYXBpLmV4YW1wbGUuY29tMFkwEwYHKoZIzj0CAQYIKoZIzj0DAQcDQgAE+zGgSVsEIyU1uMzgMXiOa1MFyQ3YR73Q7zGBT6WUK9H/eUTyOOprfLnxTcRoOBPa2E96capZ4p6hQJ0Loz2fUaNTMFEwHQYDVR0OBBYEFDduqXW4fZAppUxJ+F5pqf5jXiM+MB8GA1UdIwQYMBaAFDduqXW4fZAppUxJ+F5pqf5jXiM+MA8GA1UdEwEB/wQFMAMBAf8wCgYIKoZIzj0EAwIDSAAwRQIgMooZeL2CCjzAuKWbLz+UV3Eie3jRz3oZPwA/uSoT3Q0CIQDlWcJ9yA3luwrsIW/E8creN2GhRg8EWkT8v59jBxoMhA==";
+    // As printed by openssl for the certificate above
+    const PIN: &str = "sha256/S/9pPQ++Ulm1yfjf33CbhT7fERrH8bVRfSjXL9MKbPo=";
+    const OTHER_PIN: &str = "sha256/47DEQpj8HBSa+/TImW+5JCeuQeRkm5NMpJWZG3hSuFU=";
+
+    #[test]
+    fn test_check() {
+        let certificate = STANDARD.decode(CERTIFICATE).unwrap();
+        assert!(TlsPins::new(&[OTHER_PIN, PIN]).unwrap().check("api.example.com", &certificate).is_ok());
+
+        let error = TlsPins::new(&[OTHER_PIN]).unwrap().check("api.example.com", &certificate).unwrap_err();
+        assert!(error.to_string().contains(&PIN[7..]), "{}", error);
+        assert!(TlsPins::new(&[OTHER_PIN]).unwrap().report_only().check("api.example.com", &certificate).is_ok());
+
+        assert!(TlsPins::new(&[PIN]).unwrap().check("api.example.com", &certificate[..100]).is_err());
+    }
+
+    #[test]
+    fn test_parse_pins() {
+        assert!(TlsPins::new::<&str>(&[]).is_err());
+        assert!(TlsPins::new(&["S/9pPQ++Ulm1yfjf33CbhT7fERrH8bVRfSjXL9MKbPo="]).is_err());
+        assert!(TlsPins::new(&["sha256/AAAA"]).is_err());
+        assert!(TlsPins::new(&[PIN]).unwrap().enforce);
+    }
+}
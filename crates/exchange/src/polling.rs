@@ -0,0 +1,206 @@
+//! Scheduling REST book polls.
+//!
+//! Polling spends rate limit, so it goes where a poll is most likely to
+//! matter. A symbol whose cross-venue gap is close to the profitability
+//! threshold is polled at the fastest interval; a symbol whose book has
+//! stopped moving backs off, doubling its interval each quiet poll up to
+//! the slowest.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use rust_decimal::Decimal;
+
+use arbfinder_core::{OrderBook, Symbol};
+
+#[derive(Debug, Clone)]
+struct SymbolSchedule {
+    due: Instant,
+    last_poll: Option<Instant>,
+    /// Interval from how quiet the book has been.
+    backoff: Duration,
+    /// How close the symbol's best gap is to profitable, from 0 (nowhere
+    /// near) to 1 (at or past the threshold).
+    closeness: f64,
+    /// Best bid and ask at the last poll.
+    top: Option<(Option<Decimal>, Option<Decimal>)>,
+}
+
+#[derive(Debug)]
+struct SchedulerState {
+    min_interval: Duration,
+    max_interval: Duration,
+    symbols: HashMap<Symbol, SymbolSchedule>,
+}
+
+impl SymbolSchedule {
+    /// The faster of the backoff and what closeness to the threshold calls
+    /// for.
+    fn interval(&self, min_interval: Duration, max_interval: Duration) -> Duration {
+        let range = max_interval.saturating_sub(min_interval);
+        let by_closeness = max_interval.saturating_sub(range.mul_f64(self.closeness));
+        self.backoff.min(by_closeness).max(min_interval)
+    }
+
+    fn reschedule(&mut self, min_interval: Duration, max_interval: Duration) {
+        if let Some(last_poll) = self.last_poll {
+            self.due = last_poll + self.interval(min_interval, max_interval);
+        }
+    }
+}
+
+/// Which symbol to poll next. Cloning shares the schedule, so whatever
+/// watches cross-venue gaps can report them to a polling task.
+#[derive(Debug, Clone)]
+pub struct PollScheduler {
+    state: Arc<Mutex<SchedulerState>>,
+}
+
+impl PollScheduler {
+    /// Schedules `symbols`, all due now, polling each between every
+    /// `min_interval` and every `max_interval`.
+    pub fn new(symbols: impl IntoIterator<Item = Symbol>, min_interval: Duration, max_interval: Duration) -> Self {
+        let now = Instant::now();
+        let max_interval = max_interval.max(min_interval);
+        let symbols = symbols
+            .into_iter()
+            .map(|symbol| {
+                let schedule = SymbolSchedule {
+                    due: now,
+                    last_poll: None,
+                    backoff: min_interval,
+                    closeness: 0.0,
+                    top: None,
+                };
+                (symbol, schedule)
+            })
+            .collect();
+        Self {
+            state: Arc::new(Mutex::new(SchedulerState {
+                min_interval,
+                max_interval,
+                symbols,
+            })),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, SchedulerState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The most overdue symbol as of `now`, if any is due. Among equally
+    /// overdue symbols, the one closest to profitable goes first.
+    pub fn next(&self, now: Instant) -> Option<Symbol> {
+        let state = self.state();
+        state
+            .symbols
+            .iter()
+            .filter(|(_, schedule)| schedule.due <= now)
+            .min_by(|(_, a), (_, b)| a.due.cmp(&b.due).then(b.closeness.total_cmp(&a.closeness)))
+            .map(|(symbol, _)| symbol.clone())
+    }
+
+    /// Records a poll of `symbol` returning `book` at `now`, backing off if
+    /// its top of book has not moved since the last poll, and schedules the
+    /// next.
+    pub fn on_poll(&self, symbol: &Symbol, book: &OrderBook, now: Instant) {
+        let mut state = self.state();
+        let (min_interval, max_interval) = (state.min_interval, state.max_interval);
+        let Some(schedule) = state.symbols.get_mut(symbol) else {
+            return;
+        };
+        let top = Some((book.best_bid().map(|level| level.price), book.best_ask().map(|level| level.price)));
+        schedule.backoff = if schedule.top == top {
+            (schedule.backoff * 2).min(max_interval)
+        } else {
+            min_interval
+        };
+        schedule.top = top;
+        schedule.last_poll = Some(now);
+        schedule.reschedule(min_interval, max_interval);
+    }
+
+    /// Records a failed poll of `symbol`, retrying it no sooner than the
+    /// slowest interval.
+    pub fn on_failure(&self, symbol: &Symbol, now: Instant) {
+        let mut state = self.state();
+        let max_interval = state.max_interval;
+        if let Some(schedule) = state.symbols.get_mut(symbol) {
+            schedule.due = now + max_interval;
+        }
+    }
+
+    /// Reports `symbol`'s best cross-venue gap as a fraction of the
+    /// profitability threshold, 1 or more being at it, and reschedules its
+    /// next poll to match.
+    pub fn report_gap(&self, symbol: &Symbol, gap_to_threshold: f64) {
+        let mut state = self.state();
+        let (min_interval, max_interval) = (state.min_interval, state.max_interval);
+        if let Some(schedule) = state.symbols.get_mut(symbol) {
+            schedule.closeness = if gap_to_threshold.is_finite() { gap_to_threshold.clamp(0.0, 1.0) } else { 0.0 };
+            schedule.reschedule(min_interval, max_interval);
+        }
+    }
+
+    /// Interval `symbol` is currently polled at.
+    pub fn interval(&self, symbol: &Symbol) -> Option<Duration> {
+        let state = self.state();
+        state
+            .symbols
+            .get(symbol)
+            .map(|schedule| schedule.interval(state.min_interval, state.max_interval))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(symbol: &Symbol, bid: i64, ask: i64) -> OrderBook {
+        let mut book = OrderBook::new(symbol.clone());
+        book.update_bid(Decimal::from(bid), Decimal::ONE);
+        book.update_ask(Decimal::from(ask), Decimal::ONE);
+        book
+    }
+
+    #[test]
+    fn test_adaptive_intervals() {
+        let (btc, eth) = (Symbol::new("BTC", "USD"), Symbol::new("ETH", "USD"));
+        let scheduler = PollScheduler::new(
+            [btc.clone(), eth.clone()],
+            Duration::from_secs(1),
+            Duration::from_secs(16),
+        );
+        let start = Instant::now();
+        assert!(scheduler.next(start).is_some());
+
+        // A book that stops moving backs off to the slowest interval
+        let mut now = start;
+        for expected in [1, 2, 4, 8, 16, 16] {
+            scheduler.on_poll(&btc, &book(&btc, 100, 101), now);
+            assert_eq!(scheduler.interval(&btc), Some(Duration::from_secs(expected)));
+            now += Duration::from_secs(expected);
+        }
+        scheduler.on_poll(&eth, &book(&eth, 10, 11), start);
+        scheduler.on_poll(&eth, &book(&eth, 10, 12), start);
+        assert_eq!(scheduler.interval(&eth), Some(Duration::from_secs(1)));
+
+        // A gap nearing the threshold brings a quiet symbol forward, ahead
+        // of others due at the same time
+        let last = now - Duration::from_secs(16);
+        assert_eq!(scheduler.next(last + Duration::from_secs(2)), Some(eth.clone()));
+        scheduler.on_poll(&eth, &book(&eth, 10, 13), last);
+        scheduler.report_gap(&btc, 0.75);
+        assert_eq!(scheduler.interval(&btc), Some(Duration::from_millis(4750)));
+        scheduler.report_gap(&btc, 1.5);
+        assert_eq!(scheduler.interval(&btc), Some(Duration::from_secs(1)));
+        assert_eq!(scheduler.next(last + Duration::from_secs(1)), Some(btc.clone()));
+
+        // A failed poll waits out the slowest interval
+        scheduler.on_failure(&btc, now);
+        scheduler.on_poll(&eth, &book(&eth, 10, 14), now);
+        assert_eq!(scheduler.next(now + Duration::from_millis(500)), None);
+        assert_eq!(scheduler.next(now + Duration::from_secs(15)), Some(eth));
+    }
+}
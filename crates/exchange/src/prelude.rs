@@ -4,6 +4,9 @@
 
 pub use crate::traits::{
     ExchangeAdapter,
+    MarketDataAdapter,
+    TradingAdapter,
+    VenueAdapter,
     RestClient,
     ExchangeConfig,
     SymbolNormalizer,
@@ -11,15 +14,37 @@ pub use crate::traits::{
     ConnectionStatus,
     SubscriptionInfo,
     SymbolInfo,
+    InstrumentInfo,
+    InstrumentKind,
+    OptionKind,
     AccountInfo,
     TradingFees,
     MarketDataStream,
     OrderUpdateStream,
 };
 
-pub use crate::manager::ExchangeManager;
+pub use crate::cache::ResponseCache;
+pub use crate::circuit::{CircuitBreaker, CircuitBreakerConfig, CircuitState, SharedCircuitBreaker};
+pub use crate::clock::measure_clock_skew;
+pub use crate::compression::FrameEncoding;
+pub use crate::pinning::TlsPins;
+pub use crate::proxy::{Proxy, ProxyKind};
+pub use crate::fallback::{FallbackConfig, FeedSource, FeedStream, FeedUpdate};
+pub use crate::fix::{FixClient, FixConfig, FixEvent, FixMessage, FixSessionConfig};
+pub use crate::heartbeat::{HeartbeatManager, HeartbeatStatus, LatencyStats};
+pub use crate::status::{VenueHealth, VenueStatusBoard, VenueStatusConfig, VenueStatusEvent, VenueStatusReport};
+pub use crate::fanout::{MarketDataFanout, VenueMarketData, VenueMarketDataStream};
+pub use crate::manager::{DesiredSubscription, ExchangeManager, ExchangeManagerExt, MarketDataStats};
+pub use crate::polling::PollScheduler;
+pub use crate::secrets::{EnvSecrets, KeyringSecrets, SecretsProvider, VaultSecrets};
+pub use crate::supervisor::{SupervisorConfig, SupervisorEvent};
+pub use crate::network::{address_for_sender, Chain};
+pub use crate::transfer::{Transfer, TransferConfig, TransferManager, TransferStage};
+pub use crate::uptime::VenueUptime;
 pub use crate::normalizer::{DefaultSymbolNormalizer, SymbolFormat};
-pub use crate::rate_limiter::RateLimiter;
+pub use crate::symbol_map::SymbolMappings;
+pub use crate::rate_limiter::{RateLimiter, SharedRateLimiter, WeightedRateLimiter};
+pub use crate::request_queue::{RequestPriority, RequestQueue};
 
 // Re-export common types from core
 pub use arbfinder_core::prelude::*;
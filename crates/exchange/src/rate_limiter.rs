@@ -1,8 +1,12 @@
+use arbfinder_core::config::{RateLimitConfig, RateLimitScope, RateLimitWindow, VenueConfig};
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{Mutex, Semaphore};
 use tokio::time::{sleep, Instant};
-use tracing::debug;
+use tracing::{debug, warn};
+
+use crate::request_queue::{RequestPriority, RequestQueue};
 
 #[derive(Debug)]
 pub struct RateLimiter {
@@ -235,6 +239,316 @@ impl AdaptiveRateLimiter {
     }
 }
 
+/// A venue's limiter, shared by every client and task sending it
+/// requests so they draw on one budget.
+pub type SharedRateLimiter = Arc<WeightedRateLimiter>;
+
+/// Lowest share of its limits a limiter backs off to.
+const MIN_RATE_SCALE: f64 = 0.1;
+
+/// Share of the current budget kept after the venue rejects a request for
+/// its rate.
+const THROTTLE_BACKOFF: f64 = 0.5;
+
+/// Share of the current budget kept after a server error.
+const SERVER_ERROR_BACKOFF: f64 = 0.8;
+
+/// Share of the limits each successful request wins back.
+const RECOVERY_STEP: f64 = 0.02;
+
+/// How long requests stop after a 429 without a Retry-After.
+const DEFAULT_THROTTLE_PAUSE: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+struct WeightWindow {
+    scope: RateLimitScope,
+    capacity: f64,
+    refill_rate: f64, // weight per second
+    tokens: f64,
+}
+
+#[derive(Debug)]
+struct WeightWindows {
+    windows: Vec<WeightWindow>,
+    last_refill: Instant,
+    /// Share of each window's limit currently allowed, cut when the venue
+    /// pushes back and grown again as requests succeed.
+    scale: f64,
+    /// No requests go out before this, after a 429.
+    paused_until: Option<Instant>,
+}
+
+/// Token buckets for several windows at once, e.g. weight per minute and
+/// orders per second, where each endpoint costs its own weight. A request
+/// waits until every window it counts against has room for it.
+///
+/// The limits are ceilings the venue documents; what it actually allows
+/// varies with its load. Responses are fed back: a 429 halves the budget
+/// and stops every request until its Retry-After, a 5xx trims it, and each
+/// success wins a little back, up to the configured limits.
+#[derive(Debug)]
+pub struct WeightedRateLimiter {
+    state: std::sync::Mutex<WeightWindows>,
+    endpoint_weights: HashMap<String, u32>,
+    default_weight: u32,
+    order_endpoints: HashSet<String>,
+    /// Requests waiting for room, by priority.
+    queue: RequestQueue,
+}
+
+impl WeightedRateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self::with_buffer(config, 1.0)
+    }
+
+    /// Allows only `buffer` (0 to 1) of each window's limit, leaving room
+    /// for requests the venue counts that this limiter does not see.
+    pub fn with_buffer(config: &RateLimitConfig, buffer: f64) -> Self {
+        let buffer = if buffer > 0.0 { buffer.min(1.0) } else { 1.0 };
+        let windows = config
+            .windows
+            .iter()
+            .filter(|window| window.limit > 0 && window.window_secs > 0)
+            .map(|window| {
+                let capacity = (window.limit as f64 * buffer).max(1.0);
+                WeightWindow {
+                    scope: window.scope,
+                    capacity,
+                    refill_rate: capacity / window.window_secs as f64,
+                    tokens: capacity,
+                }
+            })
+            .collect();
+
+        Self {
+            state: std::sync::Mutex::new(WeightWindows {
+                windows,
+                last_refill: Instant::now(),
+                scale: 1.0,
+                paused_until: None,
+            }),
+            endpoint_weights: config.endpoint_weights.clone(),
+            default_weight: config.default_weight,
+            order_endpoints: config.order_endpoints.iter().cloned().collect(),
+            queue: RequestQueue::new(),
+        }
+    }
+
+    /// A flat `requests` a second, every endpoint weighing one.
+    pub fn per_second(requests: u32) -> Self {
+        Self::new(&RateLimitConfig {
+            windows: vec![RateLimitWindow {
+                scope: RateLimitScope::Requests,
+                limit: requests.max(1),
+                window_secs: 1,
+            }],
+            endpoint_weights: HashMap::new(),
+            default_weight: 1,
+            order_endpoints: Vec::new(),
+        })
+    }
+
+    /// Wraps this limiter to be shared.
+    pub fn shared(self) -> SharedRateLimiter {
+        Arc::new(self)
+    }
+
+    /// The venue's weighted limits within its `rate_limit_buffer`, if it
+    /// has any.
+    pub fn from_venue_config(config: &VenueConfig) -> Option<Self> {
+        config
+            .rate_limits
+            .as_ref()
+            .map(|limits| Self::with_buffer(limits, config.rate_limit_buffer))
+    }
+
+    /// Weight of a request to `endpoint`, query string aside.
+    pub fn weight(&self, endpoint: &str) -> u32 {
+        let path = endpoint.split('?').next().unwrap_or(endpoint);
+        self.endpoint_weights.get(path).copied().unwrap_or(self.default_weight)
+    }
+
+    /// Whether a request to `endpoint` other than a GET places an order.
+    pub fn is_order_endpoint(&self, endpoint: &str) -> bool {
+        let path = endpoint.split('?').next().unwrap_or(endpoint);
+        self.order_endpoints.contains(path)
+    }
+
+    /// Waits until a request to `endpoint` fits in every window, then
+    /// counts it.
+    pub async fn acquire(&self, endpoint: &str, places_order: bool) {
+        self.acquire_with_priority(endpoint, places_order, RequestPriority::Normal).await;
+    }
+
+    /// Like `acquire`, letting requests of higher `priority` through
+    /// first while they wait. Critical requests don't wait for room, only
+    /// out a 429's pause.
+    pub async fn acquire_with_priority(&self, endpoint: &str, places_order: bool, priority: RequestPriority) {
+        self.acquire_weight_with_priority(self.weight(endpoint), places_order, priority).await;
+    }
+
+    pub async fn acquire_weight(&self, weight: u32, places_order: bool) {
+        self.acquire_weight_with_priority(weight, places_order, RequestPriority::Normal).await;
+    }
+
+    pub async fn acquire_weight_with_priority(&self, weight: u32, places_order: bool, priority: RequestPriority) {
+        if priority == RequestPriority::Critical {
+            while let Err(wait) = self.preempt_at(weight, places_order, Instant::now()) {
+                debug!("Weighted rate limiter: critical request waiting {:?} out a 429", wait);
+                sleep(wait).await;
+            }
+            return;
+        }
+        self.queue
+            .wait_turn(priority, || {
+                let acquired = self.try_acquire_at(weight, places_order, Instant::now());
+                if let Err(wait) = acquired {
+                    debug!("Weighted rate limiter: waiting {:?} for {} weight at {} priority", wait, weight, priority);
+                }
+                acquired
+            })
+            .await;
+    }
+
+    /// Requests waiting for room.
+    pub fn waiting(&self) -> usize {
+        self.queue.waiting()
+    }
+
+    pub fn try_acquire(&self, endpoint: &str, places_order: bool) -> bool {
+        self.try_acquire_at(self.weight(endpoint), places_order, Instant::now()).is_ok()
+    }
+
+    /// Counts the request against its windows if all of them have room at
+    /// `now`; otherwise how long until they will. A request weighing more
+    /// than a window holds waits for it to be full.
+    fn try_acquire_at(&self, weight: u32, places_order: bool, now: Instant) -> std::result::Result<(), Duration> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(paused_until) = state.paused_until {
+            if paused_until > now {
+                return Err(paused_until - now);
+            }
+            state.paused_until = None;
+        }
+        state.refill(now);
+
+        let scale = state.scale;
+        let mut wait: f64 = 0.0;
+        for window in &mut state.windows {
+            let cost = match window.scope {
+                RateLimitScope::Requests => weight as f64,
+                RateLimitScope::Orders if places_order => 1.0,
+                RateLimitScope::Orders => continue,
+            };
+            let short = cost.min(window.capacity * scale) - window.tokens;
+            if short > 0.0 {
+                wait = wait.max(short / (window.refill_rate * scale));
+            }
+        }
+        if wait > 0.0 {
+            return Err(Duration::from_secs_f64(wait));
+        }
+
+        for window in &mut state.windows {
+            match window.scope {
+                RateLimitScope::Requests => window.tokens = (window.tokens - weight as f64).max(0.0),
+                RateLimitScope::Orders if places_order => window.tokens -= 1.0,
+                RateLimitScope::Orders => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Counts the request whether or not there is room, leaving windows in
+    /// debt that later requests wait out. Only a 429's pause holds it.
+    fn preempt_at(&self, weight: u32, places_order: bool, now: Instant) -> std::result::Result<(), Duration> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(paused_until) = state.paused_until.filter(|paused_until| *paused_until > now) {
+            return Err(paused_until - now);
+        }
+        state.refill(now);
+        for window in &mut state.windows {
+            match window.scope {
+                RateLimitScope::Requests => window.tokens -= weight as f64,
+                RateLimitScope::Orders if places_order => window.tokens -= 1.0,
+                RateLimitScope::Orders => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// Share of the configured limits currently allowed.
+    pub fn scale(&self) -> f64 {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).scale
+    }
+
+    /// Grows the budget back a step after a request went through.
+    pub fn record_success(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.scale = (state.scale + RECOVERY_STEP).min(1.0);
+    }
+
+    /// Halves the budget after a 429, and holds every request until
+    /// `retry_after` has passed.
+    pub fn record_throttled(&self, retry_after: Option<Duration>) {
+        self.record_throttled_at(retry_after, Instant::now());
+    }
+
+    fn record_throttled_at(&self, retry_after: Option<Duration>, now: Instant) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.refill(now);
+        state.backoff(THROTTLE_BACKOFF);
+        // The venue thinks the budget is spent, whatever we counted
+        for window in &mut state.windows {
+            window.tokens = 0.0;
+        }
+        let until = now + retry_after.unwrap_or(DEFAULT_THROTTLE_PAUSE);
+        state.paused_until = Some(state.paused_until.map_or(until, |paused| paused.max(until)));
+        warn!(
+            "Rate limited; pausing requests until Retry-After and cutting budget to {:.0}%",
+            state.scale * 100.0
+        );
+    }
+
+    /// Trims the budget after a 5xx, which venues return under load.
+    pub fn record_server_error(&self) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.refill(Instant::now());
+        state.backoff(SERVER_ERROR_BACKOFF);
+        debug!("Server error; budget cut to {:.0}%", state.scale * 100.0);
+    }
+
+    /// Feeds a response's status back into the budget.
+    pub fn record_response(&self, status: u16, retry_after: Option<Duration>) {
+        match status {
+            // Binance answers repeated 429s with 418
+            429 | 418 => self.record_throttled(retry_after),
+            500..=599 => self.record_server_error(),
+            200..=399 => self.record_success(),
+            _ => {}
+        }
+    }
+}
+
+impl WeightWindows {
+    /// Tops the windows up for the time since the last refill, at the
+    /// current scale.
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_refill).as_secs_f64();
+        self.last_refill = self.last_refill.max(now);
+        for window in &mut self.windows {
+            window.tokens = (window.tokens + elapsed * window.refill_rate * self.scale).min(window.capacity * self.scale);
+        }
+    }
+
+    fn backoff(&mut self, factor: f64) {
+        self.scale = (self.scale * factor).max(MIN_RATE_SCALE);
+        for window in &mut self.windows {
+            window.tokens = window.tokens.min(window.capacity * self.scale);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -296,4 +610,69 @@ mod tests {
         // Rate should be reduced
         assert!(limiter.current_rate().await < 5);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_weighted_rate_limiter() {
+        let config = RateLimitConfig {
+            windows: vec![
+                RateLimitWindow { scope: RateLimitScope::Requests, limit: 10, window_secs: 1 },
+                RateLimitWindow { scope: RateLimitScope::Requests, limit: 30, window_secs: 60 },
+                RateLimitWindow { scope: RateLimitScope::Orders, limit: 2, window_secs: 1 },
+            ],
+            endpoint_weights: HashMap::from([("/api/v3/depth".to_string(), 5)]),
+            default_weight: 1,
+            order_endpoints: vec!["/api/v3/order".to_string()],
+        };
+        let limiter = WeightedRateLimiter::new(&config);
+        assert_eq!(limiter.weight("/api/v3/depth?limit=100"), 5);
+        assert!(limiter.is_order_endpoint("/api/v3/order"));
+
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        assert!(limiter.try_acquire_at(5, false, at(0)).is_ok());
+        assert!(limiter.try_acquire_at(5, false, at(0)).is_ok());
+        // The second is spent; 5 weight comes back in half a second
+        assert_eq!(limiter.try_acquire_at(5, false, at(0)), Err(Duration::from_millis(500)));
+
+        // Orders count once against the order window as well as by weight
+        assert!(limiter.try_acquire_at(1, true, at(1000)).is_ok());
+        assert!(limiter.try_acquire_at(1, true, at(1000)).is_ok());
+        assert_eq!(limiter.try_acquire_at(1, true, at(1000)), Err(Duration::from_millis(500)));
+        assert!(limiter.try_acquire_at(1, false, at(1000)).is_ok());
+
+        // 22 of the minute's 30 are used a second later, 1.5 back since;
+        // a full second's worth waits on the minute
+        assert!(limiter.try_acquire_at(10, false, at(2000)).is_ok());
+        assert_eq!(limiter.try_acquire_at(10, false, at(3000)), Err(Duration::from_secs(3)));
+        assert!(limiter.try_acquire_at(8, false, at(3000)).is_ok());
+    }
+
+    #[test]
+    fn test_adaptive_budget() {
+        let limiter = WeightedRateLimiter::per_second(10);
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+
+        // A 429 stops everything until Retry-After, then allows half
+        limiter.record_throttled_at(Some(Duration::from_secs(2)), at(0));
+        assert_eq!(limiter.try_acquire_at(1, false, at(500)), Err(Duration::from_millis(1500)));
+        assert!((limiter.scale() - 0.5).abs() < 1e-9);
+        for _ in 0..5 {
+            assert!(limiter.try_acquire_at(1, false, at(3000)).is_ok());
+        }
+        assert_eq!(limiter.try_acquire_at(1, false, at(3000)), Err(Duration::from_millis(200)));
+
+        // Successes win it back slowly, never past the limit
+        for _ in 0..10 {
+            limiter.record_success();
+        }
+        assert!((limiter.scale() - 0.7).abs() < 1e-9);
+        for _ in 0..100 {
+            limiter.record_success();
+        }
+        assert_eq!(limiter.scale(), 1.0);
+
+        limiter.record_response(503, None);
+        assert!((limiter.scale() - 0.8).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,220 @@
+//! Ordering requests that wait on a venue's rate limit.
+//!
+//! When a venue's budget runs short, requests line up for it. Without an
+//! order, a cancel can sit behind a burst of book pulls and symbol
+//! refreshes. A [`RequestQueue`] lets waiting requests through by
+//! [`RequestPriority`], first come first served within one.
+//! `RequestPriority::Critical` requests, such as kill-switch cancels, skip
+//! the line altogether; see `WeightedRateLimiter::acquire_with_priority`.
+//!
+//! Callers that reach a venue through an adapter's trait methods can't pass
+//! a priority along. They run the call in `RequestPriority::scope` instead,
+//! and every request the adapter sends within it goes out at that priority.
+
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use reqwest::Method;
+use tokio::sync::Notify;
+use tokio::time::sleep;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum RequestPriority {
+    /// Symbol refreshes, book pulls and other reads that can wait.
+    Background,
+    #[default]
+    Normal,
+    /// Order placements and cancels.
+    Order,
+    /// Sent ahead of everything, borrowing from the budget if it has to,
+    /// e.g. cancels when a kill switch trips.
+    Critical,
+}
+
+tokio::task_local! {
+    static SCOPED_PRIORITY: RequestPriority;
+}
+
+impl RequestPriority {
+    /// Runs `future` with the requests it sends at this priority, e.g.
+    /// cancels on the way out of a failed trade at `Critical`. Within
+    /// another scope, the higher of the two holds.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        let priority = Self::current().map_or(self, |current| current.max(self));
+        SCOPED_PRIORITY.scope(priority, future).await
+    }
+
+    /// Priority set by the `scope` the current task is running in.
+    pub fn current() -> Option<Self> {
+        SCOPED_PRIORITY.try_with(|priority| *priority).ok()
+    }
+
+    /// Priority a request gets unless its caller says otherwise: that of
+    /// the enclosing `scope`, or else what its method and endpoint call for.
+    pub fn for_request(method: &Method, places_order: bool) -> Self {
+        if let Some(priority) = Self::current() {
+            return priority;
+        }
+        if places_order || *method == Method::DELETE {
+            RequestPriority::Order
+        } else {
+            RequestPriority::Normal
+        }
+    }
+}
+
+impl std::fmt::Display for RequestPriority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RequestPriority::Background => write!(f, "background"),
+            RequestPriority::Normal => write!(f, "normal"),
+            RequestPriority::Order => write!(f, "order"),
+            RequestPriority::Critical => write!(f, "critical"),
+        }
+    }
+}
+
+type Place = (RequestPriority, Reverse<u64>);
+
+#[derive(Debug, Default)]
+pub struct RequestQueue {
+    line: Mutex<BinaryHeap<Place>>,
+    next_ticket: AtomicU64,
+    /// Woken whenever the line changes.
+    changed: Notify,
+}
+
+/// A place in the line, given up when dropped, whether served or not.
+struct Ticket<'a> {
+    queue: &'a RequestQueue,
+    place: Place,
+}
+
+impl Drop for Ticket<'_> {
+    fn drop(&mut self) {
+        self.queue
+            .line
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|place| *place != self.place);
+        self.queue.changed.notify_waiters();
+    }
+}
+
+impl RequestQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Waits in line at `priority` until first, then until `try_acquire`
+    /// succeeds. `try_acquire` gives how long until it might when it
+    /// doesn't; a request that joins ahead meanwhile goes first.
+    pub async fn wait_turn<F>(&self, priority: RequestPriority, mut try_acquire: F)
+    where
+        F: FnMut() -> std::result::Result<(), Duration>,
+    {
+        let place = (priority, Reverse(self.next_ticket.fetch_add(1, Ordering::Relaxed)));
+        self.line.lock().unwrap_or_else(|e| e.into_inner()).push(place);
+        self.changed.notify_waiters();
+        let _ticket = Ticket { queue: self, place };
+
+        loop {
+            let changed = self.changed.notified();
+            tokio::pin!(changed);
+            changed.as_mut().enable();
+
+            let first = self.line.lock().unwrap_or_else(|e| e.into_inner()).peek() == Some(&place);
+            if !first {
+                changed.await;
+                continue;
+            }
+            match try_acquire() {
+                Ok(()) => return,
+                Err(wait) => {
+                    tokio::select! {
+                        _ = sleep(wait) => {}
+                        _ = &mut changed => {}
+                    }
+                }
+            }
+        }
+    }
+
+    /// Number of requests waiting.
+    pub fn waiting(&self) -> usize {
+        self.line.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    /// Number of requests waiting at `priority`.
+    pub fn waiting_at(&self, priority: RequestPriority) -> usize {
+        self.line
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .filter(|(p, _)| *p == priority)
+            .count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rate_limiter::WeightedRateLimiter;
+    use std::sync::Arc;
+    use tokio::sync::mpsc;
+
+    #[tokio::test]
+    async fn test_orders_jump_the_line() {
+        // 10 a second, spent
+        let limiter = Arc::new(WeightedRateLimiter::per_second(10));
+        while limiter.try_acquire("/depth", false) {}
+
+        let (done, mut order) = mpsc::unbounded_channel();
+        let spawn = |priority: RequestPriority| {
+            let limiter = Arc::clone(&limiter);
+            let done = done.clone();
+            tokio::spawn(async move {
+                limiter.acquire_with_priority("/x", false, priority).await;
+                done.send(priority).unwrap();
+            })
+        };
+        spawn(RequestPriority::Background);
+        tokio::task::yield_now().await;
+        spawn(RequestPriority::Order);
+        tokio::task::yield_now().await;
+        assert_eq!(limiter.waiting(), 2);
+
+        // A kill-switch cancel doesn't wait at all
+        spawn(RequestPriority::Critical);
+        assert_eq!(order.recv().await, Some(RequestPriority::Critical));
+        assert_eq!(order.recv().await, Some(RequestPriority::Order));
+        assert_eq!(order.recv().await, Some(RequestPriority::Background));
+        assert_eq!(limiter.waiting(), 0);
+
+        assert_eq!(RequestPriority::for_request(&Method::DELETE, false), RequestPriority::Order);
+        assert_eq!(RequestPriority::for_request(&Method::GET, false), RequestPriority::Normal);
+    }
+
+    #[tokio::test]
+    async fn test_scoped_priority() {
+        let cancel = RequestPriority::Critical
+            .scope(async { RequestPriority::for_request(&Method::DELETE, false) })
+            .await;
+        assert_eq!(cancel, RequestPriority::Critical);
+        let refresh = RequestPriority::Background
+            .scope(async { RequestPriority::for_request(&Method::GET, false) })
+            .await;
+        assert_eq!(refresh, RequestPriority::Background);
+
+        // A symbol lookup a critical cancel needs stays critical
+        let nested = RequestPriority::Critical
+            .scope(RequestPriority::Background.scope(async { RequestPriority::current() }))
+            .await;
+        assert_eq!(nested, Some(RequestPriority::Critical));
+        assert_eq!(RequestPriority::current(), None);
+    }
+}
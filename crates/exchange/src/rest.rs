@@ -1,14 +1,20 @@
+use arbfinder_core::utils::{RetryPolicy, RollingStats, SharedStats};
 use arbfinder_core::{ArbFinderError, Result};
 use async_trait::async_trait;
 use reqwest::{Client, Method, Response};
 use serde_json::Value;
 use std::collections::HashMap;
-use std::time::Duration;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use tracing::{debug, error, warn};
 use url::Url;
 
-use crate::rate_limiter::RateLimiter;
+use crate::cache::ResponseCache;
+use crate::proxy::http_client;
+use crate::rate_limiter::{SharedRateLimiter, WeightedRateLimiter};
+use crate::request_queue::RequestPriority;
+use crate::singleflight::SingleFlight;
 use crate::traits::{ExchangeConfig, RestClient};
 
 #[derive(Debug)]
@@ -18,21 +24,35 @@ pub struct RestClientImpl {
     api_key: Option<String>,
     secret_key: Option<String>,
     passphrase: Option<String>,
-    rate_limiter: RateLimiter,
+    /// The venue's budget, shared with its other clients when the config
+    /// gives one.
+    rate_limiter: SharedRateLimiter,
     request_timeout: Duration,
+    retry_policy: RetryPolicy,
+    in_flight: SingleFlight<String, Value>,
+    cache: ResponseCache,
+    /// Seconds from sending each request to its response, over the last
+    /// five minutes.
+    latency: SharedStats,
 }
 
 impl RestClientImpl {
     pub fn new<C: ExchangeConfig>(config: &C) -> Result<Self> {
-        let client = Client::builder()
-            .timeout(Duration::from_millis(config.request_timeout_ms()))
-            .build()
-            .map_err(|e| ArbFinderError::Http(e))?;
-
-        let rate_limiter = RateLimiter::new(
-            config.rate_limit_requests_per_second(),
-            Duration::from_secs(1),
-        );
+        let builder = Client::builder().timeout(Duration::from_millis(config.request_timeout_ms()));
+        let client = http_client(builder, config.proxy(), config.tls_pins())?;
+
+        let rate_limiter = config.rate_limiter().unwrap_or_else(|| {
+            config
+                .rate_limits()
+                .map(WeightedRateLimiter::new)
+                .unwrap_or_else(|| WeightedRateLimiter::per_second(config.rate_limit_requests_per_second()))
+                .shared()
+        });
+
+        let cache = config
+            .response_cache_ttls()
+            .iter()
+            .fold(ResponseCache::new(), |cache, (prefix, ttl)| cache.with_ttl(prefix.clone(), *ttl));
 
         Ok(Self {
             client,
@@ -42,6 +62,10 @@ impl RestClientImpl {
             passphrase: config.passphrase().map(|s| s.to_string()),
             rate_limiter,
             request_timeout: Duration::from_millis(config.request_timeout_ms()),
+            retry_policy: config.retry_policy(),
+            in_flight: SingleFlight::new(),
+            cache,
+            latency: RollingStats::default().with_max_age(Duration::from_secs(300)).shared(),
         })
     }
 
@@ -52,11 +76,56 @@ impl RestClientImpl {
         params: Option<&HashMap<String, String>>,
         body: Option<&Value>,
         signed: bool,
+    ) -> Result<Value> {
+        let places_order = method != Method::GET && self.rate_limiter.is_order_endpoint(endpoint);
+        let priority = RequestPriority::for_request(&method, places_order);
+        self.request_with_priority(method, endpoint, params, body, signed, priority).await
+    }
+
+    /// Like `request`, waiting on the rate limit at `priority`: orders
+    /// and cancels ahead of reads, background refreshes behind them, and
+    /// critical requests not at all.
+    pub async fn request_with_priority(
+        &self,
+        method: Method,
+        endpoint: &str,
+        params: Option<&HashMap<String, String>>,
+        body: Option<&Value>,
+        signed: bool,
+        priority: RequestPriority,
+    ) -> Result<Value> {
+        // Identical concurrent GETs share one request, and one rate limit
+        // token. Those with a TTL are answered from the cache while fresh.
+        if method == Method::GET {
+            let url = self.build_url(endpoint, params)?;
+            let key = cache_key(endpoint, params);
+            return self
+                .cache
+                .get_or_fetch(&key, || {
+                    self.in_flight
+                        .run(url, || self.send(method, endpoint, params, body, signed, priority))
+                })
+                .await;
+        }
+
+        self.send(method, endpoint, params, body, signed, priority).await
+    }
+
+    async fn send(
+        &self,
+        method: Method,
+        endpoint: &str,
+        params: Option<&HashMap<String, String>>,
+        body: Option<&Value>,
+        signed: bool,
+        priority: RequestPriority,
     ) -> Result<Value> {
         // Apply rate limiting
-        self.rate_limiter.acquire().await;
+        let places_order = method != Method::GET && self.rate_limiter.is_order_endpoint(endpoint);
+        self.rate_limiter.acquire_with_priority(endpoint, places_order, priority).await;
 
         let url = self.build_url(endpoint, params)?;
+
         debug!("Making {} request to: {}", method, url);
 
         let mut request = self.client.request(method.clone(), &url);
@@ -76,10 +145,12 @@ impl RestClientImpl {
             request = request.json(body);
         }
 
+        let sent = Instant::now();
         let response = request
             .send()
             .await
             .map_err(|e| ArbFinderError::Http(e))?;
+        self.latency.lock().unwrap_or_else(|e| e.into_inner()).record_duration(sent.elapsed());
 
         self.handle_response(response).await
     }
@@ -155,18 +226,12 @@ impl RestClientImpl {
 
         debug!("Response status: {}", status);
 
-        // Handle rate limiting
+        // Every request to the venue waits out a 429 on the shared limiter,
+        // rather than each retrying on its own
+        let retry_after = retry_after(&headers, chrono::Utc::now());
+        self.rate_limiter.record_response(status.as_u16(), retry_after);
         if status == 429 {
-            warn!("Rate limit exceeded, waiting before retry");
-            
-            // Extract retry-after header if present
-            let retry_after = headers
-                .get("retry-after")
-                .and_then(|h| h.to_str().ok())
-                .and_then(|s| s.parse::<u64>().ok())
-                .unwrap_or(1);
-
-            sleep(Duration::from_secs(retry_after)).await;
+            warn!("Rate limit exceeded, retry after {:?}", retry_after);
             return Err(ArbFinderError::RateLimit("Rate limit exceeded".to_string()));
         }
 
@@ -184,65 +249,106 @@ impl RestClientImpl {
         serde_json::from_str(&text).map_err(|e| ArbFinderError::Json(e))
     }
 
+    /// Number of GET requests answered by an identical request already in flight.
+    pub fn coalesced_requests(&self) -> u64 {
+        self.in_flight.coalesced_count()
+    }
+
+    /// Number of GET requests answered from the response cache.
+    pub fn cached_responses(&self) -> u64 {
+        self.cache.hit_count()
+    }
+
+    /// Drops cached responses from endpoints starting with `prefix`, so the
+    /// next request for them goes to the venue.
+    pub fn refresh(&self, prefix: &str) {
+        self.cache.invalidate(prefix);
+    }
+
+    pub fn clear_cache(&self) {
+        self.cache.clear();
+    }
+
+    /// The venue's rate limiter, to share with other clients of it.
+    pub fn rate_limiter(&self) -> SharedRateLimiter {
+        Arc::clone(&self.rate_limiter)
+    }
+
+    /// Response times of recent requests, in seconds.
+    pub fn latency_stats(&self) -> SharedStats {
+        Arc::clone(&self.latency)
+    }
+
+    pub fn retry_policy(&self) -> &RetryPolicy {
+        &self.retry_policy
+    }
+
     pub async fn get_with_retry(
         &self,
         endpoint: &str,
         params: Option<&HashMap<String, String>>,
-        max_retries: u32,
     ) -> Result<Value> {
-        let mut attempts = 0;
-        let mut last_error = None;
-
-        while attempts < max_retries {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
             match self.get(endpoint, params).await {
                 Ok(response) => return Ok(response),
-                Err(e) => {
-                    attempts += 1;
-                    last_error = Some(e);
-                    
-                    if attempts < max_retries {
-                        let delay = Duration::from_millis(1000 * attempts as u64);
-                        warn!("Request failed, retrying in {:?}", delay);
-                        sleep(delay).await;
-                    }
+                Err(e) if self.retry_policy.should_retry(attempt, &e) => {
+                    let delay = self.retry_policy.delay_for_attempt(attempt);
+                    warn!("GET {} failed ({}), retrying in {:?}", endpoint, e, delay);
+                    sleep(delay).await;
                 }
+                Err(e) => return Err(e),
             }
         }
-
-        Err(last_error.unwrap_or_else(|| {
-            ArbFinderError::Internal("Max retries exceeded".to_string())
-        }))
     }
 
     pub async fn post_with_retry(
         &self,
         endpoint: &str,
         body: Option<&Value>,
-        max_retries: u32,
     ) -> Result<Value> {
-        let mut attempts = 0;
-        let mut last_error = None;
-
-        while attempts < max_retries {
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
             match self.post(endpoint, body).await {
                 Ok(response) => return Ok(response),
-                Err(e) => {
-                    attempts += 1;
-                    last_error = Some(e);
-                    
-                    if attempts < max_retries {
-                        let delay = Duration::from_millis(1000 * attempts as u64);
-                        warn!("Request failed, retrying in {:?}", delay);
-                        sleep(delay).await;
-                    }
+                Err(e) if self.retry_policy.should_retry(attempt, &e) => {
+                    let delay = self.retry_policy.delay_for_attempt(attempt);
+                    warn!("POST {} failed ({}), retrying in {:?}", endpoint, e, delay);
+                    sleep(delay).await;
                 }
+                Err(e) => return Err(e),
             }
         }
+    }
+}
 
-        Err(last_error.unwrap_or_else(|| {
-            ArbFinderError::Internal("Max retries exceeded".to_string())
-        }))
+/// `endpoint` with `params` sorted into its query, so the same request
+/// always hits the same cache entry.
+fn cache_key(endpoint: &str, params: Option<&HashMap<String, String>>) -> String {
+    let mut pairs: Vec<String> = params
+        .into_iter()
+        .flatten()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect();
+    if pairs.is_empty() {
+        return endpoint.to_string();
     }
+    pairs.sort();
+    let separator = if endpoint.contains('?') { '&' } else { '?' };
+    format!("{}{}{}", endpoint, separator, pairs.join("&"))
+}
+
+/// How long a response's Retry-After asks to wait, given as seconds or an
+/// HTTP date.
+pub fn retry_after(headers: &reqwest::header::HeaderMap, now: chrono::DateTime<chrono::Utc>) -> Option<Duration> {
+    let value = headers.get(reqwest::header::RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<f64>() {
+        return (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds));
+    }
+    let at = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    (at.with_timezone(&chrono::Utc) - now).to_std().ok()
 }
 
 #[async_trait]
@@ -324,4 +430,28 @@ mod tests {
         assert!(!signature.is_empty());
         assert_eq!(signature.len(), 64); // SHA256 hex string length
     }
+
+    #[test]
+    fn test_cache_key() {
+        let mut params = HashMap::new();
+        params.insert("b".to_string(), "2".to_string());
+        params.insert("a".to_string(), "1".to_string());
+
+        assert_eq!(cache_key("/symbols", None), "/symbols");
+        assert_eq!(cache_key("/symbols", Some(&params)), "/symbols?a=1&b=2");
+        assert_eq!(cache_key("/symbols?type=spot", Some(&params)), "/symbols?type=spot&a=1&b=2");
+    }
+
+    #[test]
+    fn test_retry_after() {
+        use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+
+        let now = chrono::DateTime::parse_from_rfc2822("Wed, 21 Oct 2015 07:28:00 GMT").unwrap().to_utc();
+        let mut headers = HeaderMap::new();
+        assert_eq!(retry_after(&headers, now), None);
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("1.5"));
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_millis(1500)));
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("Wed, 21 Oct 2015 07:28:30 GMT"));
+        assert_eq!(retry_after(&headers, now), Some(Duration::from_secs(30)));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,284 @@
+//! Where venue API credentials come from. Rereading them from a provider
+//! lets keys be rotated while the process keeps running.
+//!
+//! Besides the config file, credentials can be kept in environment
+//! variables ([`EnvSecrets`]), the OS keyring ([`KeyringSecrets`]) or
+//! HashiCorp Vault ([`VaultSecrets`]). Each holds them as
+//! [`SecretString`]s, which never print and are zeroed when dropped.
+
+use async_trait::async_trait;
+use arbfinder_core::{ArbFinderError, Result, SecretString, VenueCredentials, VenueId};
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+
+#[async_trait]
+pub trait SecretsProvider: Send + Sync {
+    /// `venue`'s credentials as they stand now, or `None` if it has none.
+    async fn credentials(&self, venue: &VenueId) -> Result<Option<VenueCredentials>>;
+}
+
+/// Credentials from their parts, `None` without a key and secret. Half a
+/// pair is an error, not a venue without credentials.
+fn credentials_from(
+    venue: &VenueId,
+    source: &str,
+    api_key: Option<SecretString>,
+    secret_key: Option<SecretString>,
+    passphrase: Option<SecretString>,
+    sandbox: bool,
+) -> Result<Option<VenueCredentials>> {
+    match (api_key, secret_key) {
+        (None, None) => Ok(None),
+        (Some(api_key), Some(secret_key)) => Ok(Some(VenueCredentials { api_key, secret_key, passphrase, sandbox })),
+        (Some(_), None) => Err(ArbFinderError::InvalidData(format!(
+            "{} has an API key but no secret in {}",
+            venue, source
+        ))),
+        (None, Some(_)) => Err(ArbFinderError::InvalidData(format!(
+            "{} has an API secret but no key in {}",
+            venue, source
+        ))),
+    }
+}
+
+/// Credentials in environment variables named `{prefix}_{VENUE}_API_KEY`,
+/// `_API_SECRET`, `_PASSPHRASE` and `_SANDBOX`, e.g.
+/// `ARBFINDER_BINANCE_API_KEY`.
+#[derive(Debug, Clone)]
+pub struct EnvSecrets {
+    prefix: String,
+}
+
+impl Default for EnvSecrets {
+    fn default() -> Self {
+        Self::new("ARBFINDER")
+    }
+}
+
+impl EnvSecrets {
+    pub fn new(prefix: impl Into<String>) -> Self {
+        Self { prefix: prefix.into() }
+    }
+
+    fn var(&self, venue: &VenueId, name: &str) -> Option<SecretString> {
+        let key = format!("{}_{}_{}", self.prefix, venue.to_string().to_uppercase(), name);
+        std::env::var(key).ok().filter(|value| !value.is_empty()).map(SecretString::from)
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for EnvSecrets {
+    async fn credentials(&self, venue: &VenueId) -> Result<Option<VenueCredentials>> {
+        let sandbox = self
+            .var(venue, "SANDBOX")
+            .is_some_and(|sandbox| matches!(sandbox.expose(), "1" | "true"));
+        credentials_from(
+            venue,
+            "the environment",
+            self.var(venue, "API_KEY"),
+            self.var(venue, "API_SECRET"),
+            self.var(venue, "PASSPHRASE"),
+            sandbox,
+        )
+    }
+}
+
+/// Credentials in the OS keyring, stored under `service` with accounts
+/// `{venue}_api_key`, `{venue}_api_secret` and `{venue}_passphrase`. Read
+/// with `security` on macOS and `secret-tool` (libsecret) elsewhere, e.g.
+/// stored with `secret-tool store --label=arbfinder service arbfinder
+/// account binance_api_key`.
+#[derive(Debug, Clone)]
+pub struct KeyringSecrets {
+    service: String,
+}
+
+impl Default for KeyringSecrets {
+    fn default() -> Self {
+        Self::new("arbfinder")
+    }
+}
+
+impl KeyringSecrets {
+    pub fn new(service: impl Into<String>) -> Self {
+        Self { service: service.into() }
+    }
+
+    async fn entry(&self, venue: &VenueId, name: &str) -> Result<Option<SecretString>> {
+        let account = format!("{}_{}", venue, name);
+        let mut command = if cfg!(target_os = "macos") {
+            let mut command = tokio::process::Command::new("security");
+            command.args(["find-generic-password", "-s", &self.service, "-a", &account, "-w"]);
+            command
+        } else if cfg!(unix) {
+            let mut command = tokio::process::Command::new("secret-tool");
+            command.args(["lookup", "service", &self.service, "account", &account]);
+            command
+        } else {
+            return Err(ArbFinderError::InvalidData("No OS keyring support on this platform".to_string()));
+        };
+        let output = command
+            .kill_on_drop(true)
+            .output()
+            .await
+            .map_err(|e| ArbFinderError::Internal(format!("Could not read the OS keyring: {}", e)))?;
+        // Both exit non-zero, without output, for entries that don't exist
+        if !output.status.success() || output.stdout.is_empty() {
+            return Ok(None);
+        }
+        let mut secret = String::from_utf8(output.stdout)
+            .map_err(|_| ArbFinderError::InvalidData(format!("Keyring entry {} is not UTF-8", account)))?;
+        let len = secret.trim_end_matches(['\r', '\n']).len();
+        secret.truncate(len);
+        Ok(Some(SecretString::from(secret)))
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for KeyringSecrets {
+    async fn credentials(&self, venue: &VenueId) -> Result<Option<VenueCredentials>> {
+        credentials_from(
+            venue,
+            "the OS keyring",
+            self.entry(venue, "api_key").await?,
+            self.entry(venue, "api_secret").await?,
+            self.entry(venue, "passphrase").await?,
+            false,
+        )
+    }
+}
+
+/// Credentials in a HashiCorp Vault KV version 2 engine, one secret per
+/// venue at `{mount}/{path}/{venue}` with fields `api_key`, `api_secret`
+/// and, where the venue needs them, `passphrase` and `sandbox`.
+pub struct VaultSecrets {
+    client: Client,
+    addr: String,
+    token: SecretString,
+    mount: String,
+    path: String,
+}
+
+impl std::fmt::Debug for VaultSecrets {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("VaultSecrets")
+            .field("addr", &self.addr)
+            .field("token", &self.token)
+            .field("mount", &self.mount)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl VaultSecrets {
+    pub fn new(addr: impl Into<String>, token: SecretString, mount: impl Into<String>, path: impl Into<String>) -> Self {
+        Self {
+            client: Client::new(),
+            addr: addr.into().trim_end_matches('/').to_string(),
+            token,
+            mount: mount.into().trim_matches('/').to_string(),
+            path: path.into().trim_matches('/').to_string(),
+        }
+    }
+
+    /// Vault at `VAULT_ADDR`, authenticating with `VAULT_TOKEN`.
+    pub fn from_env(mount: impl Into<String>, path: impl Into<String>) -> Result<Self> {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .filter(|value| !value.is_empty())
+                .ok_or_else(|| ArbFinderError::Authentication(format!("{} is not set", name)))
+        };
+        Ok(Self::new(var("VAULT_ADDR")?, SecretString::from(var("VAULT_TOKEN")?), mount, path))
+    }
+
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = client;
+        self
+    }
+}
+
+#[async_trait]
+impl SecretsProvider for VaultSecrets {
+    async fn credentials(&self, venue: &VenueId) -> Result<Option<VenueCredentials>> {
+        let url = format!("{}/v1/{}/data/{}/{}", self.addr, self.mount, self.path, venue);
+        let response = self
+            .client
+            .get(&url)
+            .header("X-Vault-Token", self.token.expose())
+            .send()
+            .await
+            .map_err(ArbFinderError::Http)?;
+        match response.status() {
+            StatusCode::NOT_FOUND => return Ok(None),
+            StatusCode::FORBIDDEN | StatusCode::UNAUTHORIZED => {
+                return Err(ArbFinderError::Authentication(format!(
+                    "Vault refused the token for {}",
+                    url
+                )))
+            }
+            status if !status.is_success() => {
+                return Err(ArbFinderError::Exchange(format!("Vault error reading {}: {}", url, status)))
+            }
+            _ => {}
+        }
+        let body = response.json::<Value>().await.map_err(ArbFinderError::Http)?;
+        parse_vault_secret(venue, &body)
+    }
+}
+
+/// Credentials from a KV version 2 read, whose fields are under
+/// `data.data`. A deleted latest version reads as no credentials.
+fn parse_vault_secret(venue: &VenueId, body: &Value) -> Result<Option<VenueCredentials>> {
+    let fields = &body["data"]["data"];
+    let field = |name: &str| fields[name].as_str().filter(|value| !value.is_empty()).map(SecretString::from);
+    let sandbox = match &fields["sandbox"] {
+        Value::Bool(sandbox) => *sandbox,
+        Value::String(sandbox) => sandbox == "true",
+        _ => false,
+    };
+    credentials_from(venue, "Vault", field("api_key"), field("api_secret"), field("passphrase"), sandbox)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_env_secrets() {
+        let secrets = EnvSecrets::new("ARBFINDER_TEST_ENV");
+        assert!(secrets.credentials(&VenueId::Bitget).await.unwrap().is_none());
+
+        std::env::set_var("ARBFINDER_TEST_ENV_BITGET_API_KEY", "key");
+        assert!(secrets.credentials(&VenueId::Bitget).await.is_err());
+
+        std::env::set_var("ARBFINDER_TEST_ENV_BITGET_API_SECRET", "secret");
+        std::env::set_var("ARBFINDER_TEST_ENV_BITGET_PASSPHRASE", "phrase");
+        let credentials = secrets.credentials(&VenueId::Bitget).await.unwrap().unwrap();
+        assert_eq!(credentials.api_key.expose(), "key");
+        assert_eq!(credentials.secret_key.expose(), "secret");
+        assert_eq!(credentials.passphrase.map(|p| p.expose().to_string()).as_deref(), Some("phrase"));
+        assert!(!credentials.sandbox);
+    }
+
+    #[test]
+    fn test_parse_vault_secret() {
+        let body = json!({
+            "data": {
+                "data": { "api_key": "key", "api_secret": "secret", "sandbox": "true" },
+                "metadata": { "version": 3 }
+            }
+        });
+        let credentials = parse_vault_secret(&VenueId::Binance, &body).unwrap().unwrap();
+        assert_eq!(credentials.api_key.expose(), "key");
+        assert!(credentials.passphrase.is_none());
+        assert!(credentials.sandbox);
+
+        let deleted = json!({ "data": { "data": null, "metadata": { "deletion_time": "2024-01-01T00:00:00Z" } } });
+        assert!(parse_vault_secret(&VenueId::Binance, &deleted).unwrap().is_none());
+
+        let printed = format!("{:?}", VaultSecrets::new("http://vault:8200/", "s.token".into(), "secret", "arbfinder"));
+        assert!(!printed.contains("s.token"));
+    }
+}
@@ -0,0 +1,252 @@
+use arbfinder_core::{ArbFinderError, Result};
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+type SharedResult<V> = std::result::Result<V, Arc<ArbFinderError>>;
+
+/// Coalesces identical concurrent calls into a single in-flight operation.
+///
+/// The first caller for a key runs the operation; everyone arriving while it
+/// is in flight waits for and receives a copy of the same result. Nothing is
+/// cached: once the leader finishes, the next call for the key runs again.
+pub struct SingleFlight<K, V> {
+    in_flight: Mutex<HashMap<K, broadcast::Sender<SharedResult<V>>>>,
+    coalesced: AtomicU64,
+}
+
+enum Role<V> {
+    Leader,
+    Follower(broadcast::Receiver<SharedResult<V>>),
+}
+
+/// Removes the key if the leader is dropped before completing, so waiting
+/// followers see the channel close and one of them takes over.
+struct LeaderGuard<'a, K: Eq + Hash, V> {
+    flight: &'a SingleFlight<K, V>,
+    key: Option<K>,
+}
+
+impl<K: Eq + Hash, V> Drop for LeaderGuard<'_, K, V> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.flight.in_flight.lock().unwrap().remove(&key);
+        }
+    }
+}
+
+impl<K, V> SingleFlight<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    pub fn new() -> Self {
+        Self {
+            in_flight: Mutex::new(HashMap::new()),
+            coalesced: AtomicU64::new(0),
+        }
+    }
+
+    pub async fn run<F, Fut>(&self, key: K, operation: F) -> Result<V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V>>,
+    {
+        let mut operation = Some(operation);
+        loop {
+            match self.join(&key) {
+                Role::Follower(mut rx) => match rx.recv().await {
+                    Ok(Ok(value)) => return Ok(value),
+                    Ok(Err(e)) => return Err(duplicate_error(&e)),
+                    // Leader was cancelled; race to become the new leader
+                    Err(_) => continue,
+                },
+                Role::Leader => {
+                    let mut guard = LeaderGuard { flight: self, key: Some(key.clone()) };
+                    let operation = operation.take().expect("leader runs the operation once");
+                    let result = operation().await;
+
+                    let tx = self.in_flight.lock().unwrap().remove(&key);
+                    guard.key = None;
+
+                    return match result {
+                        Ok(value) => {
+                            if let Some(tx) = tx {
+                                let _ = tx.send(Ok(value.clone()));
+                            }
+                            Ok(value)
+                        }
+                        Err(e) => {
+                            let shared = Arc::new(e);
+                            if let Some(tx) = tx {
+                                let _ = tx.send(Err(shared.clone()));
+                            }
+                            Err(Arc::try_unwrap(shared).unwrap_or_else(|e| duplicate_error(&e)))
+                        }
+                    };
+                }
+            }
+        }
+    }
+
+    fn join(&self, key: &K) -> Role<V> {
+        let mut in_flight = self.in_flight.lock().unwrap();
+        match in_flight.get(key) {
+            Some(tx) => {
+                self.coalesced.fetch_add(1, Ordering::Relaxed);
+                Role::Follower(tx.subscribe())
+            }
+            None => {
+                let (tx, _) = broadcast::channel(1);
+                in_flight.insert(key.clone(), tx);
+                Role::Leader
+            }
+        }
+    }
+
+    /// Number of calls that were served by another caller's request.
+    pub fn coalesced_count(&self) -> u64 {
+        self.coalesced.load(Ordering::Relaxed)
+    }
+
+    pub fn in_flight_count(&self) -> usize {
+        self.in_flight.lock().unwrap().len()
+    }
+}
+
+impl<K, V> Default for SingleFlight<K, V>
+where
+    K: Eq + Hash + Clone,
+    V: Clone,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, V> fmt::Debug for SingleFlight<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SingleFlight")
+            .field("in_flight", &self.in_flight.lock().map(|m| m.len()).unwrap_or(0))
+            .field("coalesced", &self.coalesced.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+/// `ArbFinderError` is not `Clone`, so followers get an equivalent error that
+/// keeps the variant (and therefore the retry class) wherever possible.
+fn duplicate_error(error: &ArbFinderError) -> ArbFinderError {
+    match error {
+        ArbFinderError::Exchange(s) => ArbFinderError::Exchange(s.clone()),
+        ArbFinderError::WebSocket(s) => ArbFinderError::WebSocket(s.clone()),
+        ArbFinderError::Http(e) if e.is_timeout() => ArbFinderError::Timeout(e.to_string()),
+        ArbFinderError::Http(e) => ArbFinderError::Exchange(e.to_string()),
+        ArbFinderError::Json(e) => ArbFinderError::Parse(e.to_string()),
+        ArbFinderError::Config(e) => ArbFinderError::Internal(e.to_string()),
+        ArbFinderError::Database(s) => ArbFinderError::Database(s.clone()),
+        ArbFinderError::OrderBook(s) => ArbFinderError::OrderBook(s.clone()),
+        ArbFinderError::Strategy(s) => ArbFinderError::Strategy(s.clone()),
+        ArbFinderError::Risk(s) => ArbFinderError::Risk(s.clone()),
+        ArbFinderError::Execution(s) => ArbFinderError::Execution(s.clone()),
+        ArbFinderError::Authentication(s) => ArbFinderError::Authentication(s.clone()),
+        ArbFinderError::RateLimit(s) => ArbFinderError::RateLimit(s.clone()),
+        ArbFinderError::InvalidData(s) => ArbFinderError::InvalidData(s.clone()),
+        ArbFinderError::Timeout(s) => ArbFinderError::Timeout(s.clone()),
+        ArbFinderError::InsufficientBalance(s) => ArbFinderError::InsufficientBalance(s.clone()),
+        ArbFinderError::InvalidOrder(s) => ArbFinderError::InvalidOrder(s.clone()),
+        ArbFinderError::MarketClosed(s) => ArbFinderError::MarketClosed(s.clone()),
+        ArbFinderError::SymbolNotFound(s) => ArbFinderError::SymbolNotFound(s.clone()),
+        ArbFinderError::Io(e) => ArbFinderError::Io(std::io::Error::new(e.kind(), e.to_string())),
+        ArbFinderError::Parse(s) => ArbFinderError::Parse(s.clone()),
+        ArbFinderError::Unsupported(s) => ArbFinderError::Unsupported(s.clone()),
+        ArbFinderError::Internal(s) => ArbFinderError::Internal(s.clone()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU32;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_concurrent_calls_are_coalesced() {
+        let flight: Arc<SingleFlight<String, u32>> = Arc::new(SingleFlight::new());
+        let calls = Arc::new(AtomicU32::new(0));
+
+        let mut handles = Vec::new();
+        for _ in 0..5 {
+            let flight = flight.clone();
+            let calls = calls.clone();
+            handles.push(tokio::spawn(async move {
+                flight
+                    .run("/api/v3/exchangeInfo".to_string(), || async {
+                        calls.fetch_add(1, Ordering::SeqCst);
+                        tokio::time::sleep(Duration::from_millis(50)).await;
+                        Ok(7)
+                    })
+                    .await
+            }));
+        }
+
+        for handle in handles {
+            assert_eq!(handle.await.unwrap().unwrap(), 7);
+        }
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert_eq!(flight.coalesced_count(), 4);
+        assert_eq!(flight.in_flight_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_shared() {
+        let flight: SingleFlight<&str, u32> = SingleFlight::new();
+        let slow = flight.run("key", || async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Err(ArbFinderError::RateLimit("429".to_string()))
+        });
+        let waiting = flight.run("key", || async { Ok(1) });
+
+        let (a, b) = tokio::join!(slow, waiting);
+        assert!(matches!(a, Err(ArbFinderError::RateLimit(_))));
+        assert!(matches!(b, Err(ArbFinderError::RateLimit(_))));
+    }
+
+    #[tokio::test]
+    async fn test_sequential_calls_are_not_cached() {
+        let flight: SingleFlight<&str, u32> = SingleFlight::new();
+        assert_eq!(flight.run("key", || async { Ok(1) }).await.unwrap(), 1);
+        assert_eq!(flight.run("key", || async { Ok(2) }).await.unwrap(), 2);
+        assert_eq!(flight.coalesced_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_follower_takes_over_cancelled_leader() {
+        let flight: Arc<SingleFlight<&str, u32>> = Arc::new(SingleFlight::new());
+
+        let leader = {
+            let flight = flight.clone();
+            tokio::spawn(async move {
+                flight
+                    .run("key", || async {
+                        tokio::time::sleep(Duration::from_secs(60)).await;
+                        Ok(1)
+                    })
+                    .await
+            })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let follower = {
+            let flight = flight.clone();
+            tokio::spawn(async move { flight.run("key", || async { Ok(2) }).await })
+        };
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        leader.abort();
+
+        assert_eq!(follower.await.unwrap().unwrap(), 2);
+    }
+}
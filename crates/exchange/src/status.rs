@@ -0,0 +1,350 @@
+//! Venue status pages and scheduled maintenance.
+//!
+//! Venues announce outages and maintenance on status endpoints: Binance on
+//! `/sapi/v1/system/status`, Coinbase on its Statuspage site. Adapters read
+//! theirs into a [`VenueStatusReport`], and a [`VenueStatusBoard`] keeps the
+//! latest report of each venue so orders stop going to one shortly before
+//! its maintenance starts, not when its API starts failing.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use arbfinder_core::{ArbFinderError, Result, VenueId};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+pub const DEFAULT_STATUS_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+pub const DEFAULT_MAINTENANCE_LEAD: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum VenueHealth {
+    #[default]
+    Operational,
+    /// Up but impaired, e.g. a partial outage or slow order entry. Orders
+    /// still go through.
+    Degraded(String),
+    /// Down outside of a scheduled window.
+    Outage(String),
+    /// In scheduled maintenance, or about to be.
+    Maintenance(String),
+}
+
+impl VenueHealth {
+    /// Whether orders may be sent to the venue.
+    pub fn accepts_orders(&self) -> bool {
+        matches!(self, VenueHealth::Operational | VenueHealth::Degraded(_))
+    }
+}
+
+impl std::fmt::Display for VenueHealth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VenueHealth::Operational => write!(f, "operational"),
+            VenueHealth::Degraded(reason) => write!(f, "degraded: {}", reason),
+            VenueHealth::Outage(reason) => write!(f, "down: {}", reason),
+            VenueHealth::Maintenance(reason) => write!(f, "in maintenance: {}", reason),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaintenanceWindow {
+    pub title: String,
+    pub starts_at: DateTime<Utc>,
+    /// `None` when the venue didn't say.
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+impl MaintenanceWindow {
+    fn ended(&self, now: DateTime<Utc>) -> bool {
+        matches!(self.ends_at, Some(end) if end <= now)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct VenueStatusReport {
+    /// Health as the venue reports it, before upcoming windows are counted.
+    pub health: VenueHealth,
+    /// Maintenance scheduled or under way.
+    pub maintenance: Vec<MaintenanceWindow>,
+}
+
+impl VenueStatusReport {
+    pub fn new(health: VenueHealth) -> Self {
+        Self { health, maintenance: Vec::new() }
+    }
+
+    /// Health at `now`, counting maintenance that starts within `lead` as
+    /// already begun.
+    pub fn health_at(&self, now: DateTime<Utc>, lead: Duration) -> VenueHealth {
+        if !self.health.accepts_orders() {
+            return self.health.clone();
+        }
+        let horizon = now + chrono::Duration::from_std(lead).unwrap_or_else(|_| chrono::Duration::zero());
+        let window = self
+            .maintenance
+            .iter()
+            .filter(|window| window.starts_at <= horizon && !window.ended(now))
+            .min_by_key(|window| window.starts_at);
+        match window {
+            Some(window) => VenueHealth::Maintenance(match window.ends_at {
+                Some(end) => format!("{} ({} to {})", window.title, window.starts_at, end),
+                None => format!("{} (from {})", window.title, window.starts_at),
+            }),
+            None => self.health.clone(),
+        }
+    }
+}
+
+/// Reads a Statuspage `summary.json`. Only components whose names contain
+/// one of `components`, ignoring case, count; when none do, all of them
+/// count, so a renamed component doesn't hide the page.
+pub fn parse_statuspage(summary: &Value, components: &[String]) -> Result<VenueStatusReport> {
+    let listed = summary["components"]
+        .as_array()
+        .ok_or_else(|| ArbFinderError::InvalidData("Status page has no components".to_string()))?;
+    let matches = |name: &str| {
+        let name = name.to_lowercase();
+        components.iter().any(|c| name.contains(&c.to_lowercase()))
+    };
+    let mut relevant: Vec<&Value> = listed
+        .iter()
+        .filter(|c| c["name"].as_str().is_some_and(matches))
+        .collect();
+    if relevant.is_empty() {
+        relevant = listed.iter().collect();
+    }
+
+    let mut health = VenueHealth::Operational;
+    for component in &relevant {
+        let name = component["name"].as_str().unwrap_or("unknown");
+        let component_health = match component["status"].as_str().unwrap_or("operational") {
+            "under_maintenance" => VenueHealth::Maintenance(name.to_string()),
+            "major_outage" => VenueHealth::Outage(format!("{} major outage", name)),
+            "partial_outage" => VenueHealth::Degraded(format!("{} partial outage", name)),
+            "degraded_performance" => VenueHealth::Degraded(format!("{} degraded performance", name)),
+            _ => continue,
+        };
+        if severity(&component_health) > severity(&health) {
+            health = component_health;
+        }
+    }
+
+    let names: Vec<&str> = relevant.iter().filter_map(|c| c["name"].as_str()).collect();
+    let mut maintenance = Vec::new();
+    for scheduled in summary["scheduled_maintenances"].as_array().into_iter().flatten() {
+        if scheduled["status"].as_str() == Some("completed") {
+            continue;
+        }
+        // Maintenance of components that don't count, e.g. the mobile app
+        let affected = scheduled["components"].as_array();
+        if affected.is_some_and(|affected| {
+            !affected.is_empty()
+                && !affected.iter().any(|c| c["name"].as_str().is_some_and(|n| names.contains(&n)))
+        }) {
+            continue;
+        }
+        let Some(starts_at) = scheduled["scheduled_for"].as_str().and_then(parse_time) else {
+            continue;
+        };
+        maintenance.push(MaintenanceWindow {
+            title: scheduled["name"].as_str().unwrap_or("Scheduled maintenance").to_string(),
+            starts_at,
+            ends_at: scheduled["scheduled_until"].as_str().and_then(parse_time),
+        });
+    }
+
+    Ok(VenueStatusReport { health, maintenance })
+}
+
+fn parse_time(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s).ok().map(|t| t.with_timezone(&Utc))
+}
+
+fn severity(health: &VenueHealth) -> u8 {
+    match health {
+        VenueHealth::Operational => 0,
+        VenueHealth::Degraded(_) => 1,
+        VenueHealth::Outage(_) => 2,
+        VenueHealth::Maintenance(_) => 3,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct VenueStatusConfig {
+    /// How often status pages are read.
+    pub check_interval: Duration,
+    /// How long before scheduled maintenance orders stop.
+    pub maintenance_lead: Duration,
+}
+
+impl Default for VenueStatusConfig {
+    fn default() -> Self {
+        Self {
+            check_interval: DEFAULT_STATUS_CHECK_INTERVAL,
+            maintenance_lead: DEFAULT_MAINTENANCE_LEAD,
+        }
+    }
+}
+
+/// A venue's health changing from one report to the next.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VenueStatusEvent {
+    pub venue: VenueId,
+    pub previous: VenueHealth,
+    pub current: VenueHealth,
+}
+
+impl VenueStatusEvent {
+    /// Whether the venue stopped taking orders with this change.
+    pub fn stopped(&self) -> bool {
+        self.previous.accepts_orders() && !self.current.accepts_orders()
+    }
+}
+
+impl std::fmt::Display for VenueStatusEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} is {} (was {})", self.venue, self.current, self.previous)
+    }
+}
+
+#[derive(Debug, Default)]
+struct BoardEntry {
+    report: VenueStatusReport,
+    /// Health last announced through [`VenueStatusBoard::on_report`].
+    health: VenueHealth,
+}
+
+/// Latest status report of each venue, shared between whoever polls the
+/// status pages and whoever sends orders. Venues never reported on are
+/// taken to be operational.
+#[derive(Debug, Clone)]
+pub struct VenueStatusBoard {
+    lead: Duration,
+    venues: Arc<RwLock<HashMap<VenueId, BoardEntry>>>,
+}
+
+impl Default for VenueStatusBoard {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAINTENANCE_LEAD)
+    }
+}
+
+impl VenueStatusBoard {
+    pub fn new(maintenance_lead: Duration) -> Self {
+        Self { lead: maintenance_lead, venues: Arc::new(RwLock::new(HashMap::new())) }
+    }
+
+    /// Records `report` for `venue`, returning the change in its health at
+    /// `now` if there was one.
+    pub fn on_report(
+        &self,
+        venue: &VenueId,
+        report: VenueStatusReport,
+        now: DateTime<Utc>,
+    ) -> Option<VenueStatusEvent> {
+        let current = report.health_at(now, self.lead);
+        let mut venues = self.venues.write().unwrap_or_else(|e| e.into_inner());
+        let entry = venues.entry(venue.clone()).or_default();
+        entry.report = report;
+        if entry.health == current {
+            return None;
+        }
+        let previous = std::mem::replace(&mut entry.health, current.clone());
+        Some(VenueStatusEvent { venue: venue.clone(), previous, current })
+    }
+
+    /// Health of `venue` at `now`, from its last report.
+    pub fn health(&self, venue: &VenueId, now: DateTime<Utc>) -> VenueHealth {
+        self.venues
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(venue)
+            .map(|entry| entry.report.health_at(now, self.lead))
+            .unwrap_or_default()
+    }
+
+    pub fn accepts_orders(&self, venue: &VenueId, now: DateTime<Utc>) -> bool {
+        self.health(venue, now).accepts_orders()
+    }
+
+    pub fn report(&self, venue: &VenueId) -> Option<VenueStatusReport> {
+        self.venues
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(venue)
+            .map(|entry| entry.report.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn summary() -> Value {
+        json!({
+            "status": { "indicator": "minor", "description": "Minor Service Outage" },
+            "components": [
+                { "name": "Advanced Trade API", "status": "degraded_performance" },
+                { "name": "Mobile App", "status": "major_outage" }
+            ],
+            "scheduled_maintenances": [
+                {
+                    "name": "Advanced Trade database upgrade",
+                    "status": "scheduled",
+                    "scheduled_for": "2024-03-02T06:00:00.000Z",
+                    "scheduled_until": "2024-03-02T07:00:00.000Z",
+                    "components": [{ "name": "Advanced Trade API" }]
+                },
+                {
+                    "name": "Mobile App release",
+                    "status": "scheduled",
+                    "scheduled_for": "2024-03-01T06:00:00.000Z",
+                    "components": [{ "name": "Mobile App" }]
+                }
+            ]
+        })
+    }
+
+    fn at(s: &str) -> DateTime<Utc> {
+        parse_time(s).unwrap()
+    }
+
+    #[test]
+    fn test_statuspage_counts_only_listed_components() {
+        let report = parse_statuspage(&summary(), &["advanced trade".to_string()]).unwrap();
+        assert_eq!(report.health, VenueHealth::Degraded("Advanced Trade API degraded performance".to_string()));
+        assert_eq!(report.maintenance.len(), 1);
+        assert_eq!(report.maintenance[0].starts_at, at("2024-03-02T06:00:00Z"));
+
+        // Nothing matches, so everything counts
+        let report = parse_statuspage(&summary(), &["exchange".to_string()]).unwrap();
+        assert_eq!(report.health, VenueHealth::Outage("Mobile App major outage".to_string()));
+        assert_eq!(report.maintenance.len(), 2);
+
+        assert!(parse_statuspage(&json!({}), &[]).is_err());
+    }
+
+    #[test]
+    fn test_board_stops_orders_ahead_of_maintenance() {
+        let venue = VenueId::Coinbase;
+        let board = VenueStatusBoard::new(Duration::from_secs(600));
+        let report = parse_statuspage(&summary(), &["advanced trade".to_string()]).unwrap();
+
+        let event = board.on_report(&venue, report.clone(), at("2024-03-02T05:00:00Z")).unwrap();
+        assert!(!event.stopped());
+        assert!(board.accepts_orders(&venue, at("2024-03-02T05:00:00Z")));
+        assert!(board.on_report(&venue, report.clone(), at("2024-03-02T05:10:00Z")).is_none());
+
+        // Ten minutes out
+        let event = board.on_report(&venue, report.clone(), at("2024-03-02T05:51:00Z")).unwrap();
+        assert!(event.stopped());
+        assert!(!board.accepts_orders(&venue, at("2024-03-02T06:30:00Z")));
+
+        // Over
+        assert!(board.accepts_orders(&venue, at("2024-03-02T07:00:00Z")));
+        assert!(board.accepts_orders(&VenueId::Kraken, at("2024-03-02T06:30:00Z")));
+    }
+}
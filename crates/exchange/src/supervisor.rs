@@ -0,0 +1,176 @@
+//! Restarting venues whose connection has died.
+//!
+//! `ExchangeManager::supervise` checks every venue it has connected each
+//! `check_interval`. One that has lost its connection, or gone without a
+//! ping for longer than `stale_after`, is restarted, waiting longer after
+//! each failed attempt. Each step is sent as a [`SupervisorEvent`] for the
+//! caller to log or raise as an alert.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use arbfinder_core::utils::RetryPolicy;
+use arbfinder_core::VenueId;
+
+#[derive(Debug, Clone)]
+pub struct SupervisorConfig {
+    /// How often venues' health is checked.
+    pub check_interval: Duration,
+    /// Ping age past which a connected venue is taken to be dead. Venues
+    /// never pinged are judged by their connection alone.
+    pub stale_after: Option<Duration>,
+    /// Delays between restarts of a venue. After `max_attempts` failures
+    /// in a row it is given up on until it is connected again by hand.
+    pub backoff: RetryPolicy,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            check_interval: Duration::from_secs(10),
+            stale_after: Some(Duration::from_secs(60)),
+            backoff: RetryPolicy::new(10, 1_000).with_max_delay_ms(300_000),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SupervisorEvent {
+    /// Found unhealthy; restarts follow.
+    Unhealthy { venue: VenueId, reason: String },
+    Restarted { venue: VenueId, attempts: u32 },
+    RestartFailed { venue: VenueId, attempt: u32, error: String, retry_in: Duration },
+    /// Out of restart attempts.
+    GaveUp { venue: VenueId, attempts: u32, error: String },
+    /// Healthy again without a restart.
+    Recovered { venue: VenueId },
+}
+
+impl SupervisorEvent {
+    pub fn venue(&self) -> &VenueId {
+        match self {
+            Self::Unhealthy { venue, .. }
+            | Self::Restarted { venue, .. }
+            | Self::RestartFailed { venue, .. }
+            | Self::GaveUp { venue, .. }
+            | Self::Recovered { venue } => venue,
+        }
+    }
+
+    /// Whether the venue is left down for an operator to look at.
+    pub fn is_critical(&self) -> bool {
+        matches!(self, Self::GaveUp { .. })
+    }
+
+    /// Whether the venue is back up.
+    pub fn is_recovery(&self) -> bool {
+        matches!(self, Self::Restarted { .. } | Self::Recovered { .. })
+    }
+}
+
+impl fmt::Display for SupervisorEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unhealthy { venue, reason } => write!(f, "{} is unhealthy ({}), restarting", venue, reason),
+            Self::Restarted { venue, attempts } => write!(f, "{} restarted after {} attempt(s)", venue, attempts),
+            Self::RestartFailed { venue, attempt, error, retry_in } => write!(
+                f,
+                "{} restart attempt {} failed: {}; retrying in {}s",
+                venue,
+                attempt,
+                error,
+                retry_in.as_secs()
+            ),
+            Self::GaveUp { venue, attempts, error } => {
+                write!(f, "{} still down after {} restart attempts, giving up: {}", venue, attempts, error)
+            }
+            Self::Recovered { venue } => write!(f, "{} recovered without a restart", venue),
+        }
+    }
+}
+
+/// Venues being restarted, and when each may be tried next.
+#[derive(Debug, Default)]
+pub(crate) struct Restarts {
+    failing: HashMap<VenueId, Failing>,
+}
+
+#[derive(Debug)]
+struct Failing {
+    attempts: u32,
+    next_attempt: Instant,
+    given_up: bool,
+}
+
+impl Restarts {
+    pub(crate) fn is_failing(&self, venue: &VenueId) -> bool {
+        self.failing.contains_key(venue)
+    }
+
+    /// Notes `venue` unhealthy at `now`. Returns whether it was healthy
+    /// before, and the attempt to restart it with if one is due.
+    pub(crate) fn unhealthy(&mut self, venue: &VenueId, now: Instant) -> (bool, Option<u32>) {
+        let newly = !self.failing.contains_key(venue);
+        let failing = self.failing.entry(venue.clone()).or_insert(Failing {
+            attempts: 0,
+            next_attempt: now,
+            given_up: false,
+        });
+        if failing.given_up || now < failing.next_attempt {
+            return (newly, None);
+        }
+        (newly, Some(failing.attempts + 1))
+    }
+
+    /// Notes `venue` healthy, returning the attempts it took if it was
+    /// failing.
+    pub(crate) fn healthy(&mut self, venue: &VenueId) -> Option<u32> {
+        self.failing.remove(venue).map(|failing| failing.attempts)
+    }
+
+    /// Notes a failed restart of `venue` at `now`. Returns how long until
+    /// the next attempt, or `None` once `backoff` has run out.
+    pub(crate) fn failed(&mut self, venue: &VenueId, now: Instant, backoff: &RetryPolicy) -> Option<Duration> {
+        let failing = self.failing.get_mut(venue)?;
+        failing.attempts += 1;
+        if failing.attempts >= backoff.max_attempts {
+            failing.given_up = true;
+            return None;
+        }
+        let delay = backoff.delay_for_attempt(failing.attempts);
+        failing.next_attempt = now + delay;
+        Some(delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restart_backoff() {
+        let backoff = RetryPolicy::new(3, 1_000).with_jitter(0.0);
+        let mut restarts = Restarts::default();
+        let venue = VenueId::Kraken;
+        let start = Instant::now();
+
+        assert_eq!(restarts.unhealthy(&venue, start), (true, Some(1)));
+        assert_eq!(restarts.failed(&venue, start, &backoff), Some(Duration::from_secs(1)));
+        // Not due again until the delay has passed
+        assert_eq!(restarts.unhealthy(&venue, start), (false, None));
+        let later = start + Duration::from_secs(1);
+        assert_eq!(restarts.unhealthy(&venue, later), (false, Some(2)));
+        assert_eq!(restarts.failed(&venue, later, &backoff), Some(Duration::from_secs(2)));
+
+        let later = later + Duration::from_secs(2);
+        assert_eq!(restarts.unhealthy(&venue, later), (false, Some(3)));
+        assert_eq!(restarts.failed(&venue, later, &backoff), None);
+        assert_eq!(restarts.unhealthy(&venue, later + Duration::from_secs(600)), (false, None));
+
+        // Connected again by hand
+        assert_eq!(restarts.healthy(&venue), Some(3));
+        assert!(!restarts.is_failing(&venue));
+        assert_eq!(restarts.healthy(&venue), None);
+    }
+}
@@ -0,0 +1,148 @@
+//! Explicit venue symbol names, read from a mappings file.
+//!
+//! `DefaultSymbolNormalizer` guesses a venue's symbol names from their
+//! separators and common quote assets, which goes wrong for names such as
+//! Kraken's `XXBTZUSD` or tokens whose symbol ends in a quote asset. A
+//! mappings file lists each venue's names and the pairs they stand for:
+//!
+//! ```toml
+//! [kraken]
+//! XXBTZUSD = "BTC/USD"
+//!
+//! [poloniex]
+//! BTC_USDT = "BTC/USDT"
+//! ```
+//!
+//! The same table may be given as JSON. `arbfinder symbols` regenerates it
+//! from each venue's listed symbols.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use arbfinder_core::{ArbFinderError, Result, Symbol, VenueId};
+
+use crate::normalizer::DefaultSymbolNormalizer;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SymbolMappings {
+    /// Venue symbol names to pairs, by venue.
+    venues: BTreeMap<String, BTreeMap<String, Symbol>>,
+}
+
+impl SymbolMappings {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads `path`, as JSON if it ends in `.json` and TOML otherwise.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        let table: BTreeMap<String, BTreeMap<String, String>> = if is_json(path) {
+            serde_json::from_str(&contents)?
+        } else {
+            toml::from_str(&contents).map_err(|e| {
+                ArbFinderError::Parse(format!("Invalid symbol mappings in {}: {}", path.display(), e))
+            })?
+        };
+
+        let mut mappings = Self::new();
+        for (venue, names) in table {
+            for (name, pair) in names {
+                let symbol = Symbol::from_pair(&pair).ok_or_else(|| {
+                    ArbFinderError::Parse(format!("Invalid pair {} for {} {} in {}", pair, venue, name, path.display()))
+                })?;
+                mappings.insert(&VenueId::from(venue.as_str()), name, symbol);
+            }
+        }
+        Ok(mappings)
+    }
+
+    /// Writes the mappings to `path`, in the format `load` reads it in.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let table: BTreeMap<&String, BTreeMap<&String, String>> = self
+            .venues
+            .iter()
+            .map(|(venue, names)| (venue, names.iter().map(|(name, symbol)| (name, symbol.to_pair())).collect()))
+            .collect();
+        let contents = if is_json(path) {
+            serde_json::to_string_pretty(&table)?
+        } else {
+            toml::to_string(&table).map_err(|e| ArbFinderError::Internal(format!("Symbol mappings: {}", e)))?
+        };
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    pub fn insert(&mut self, venue: &VenueId, name: impl Into<String>, symbol: Symbol) {
+        self.venues.entry(venue.to_string()).or_default().insert(name.into(), symbol);
+    }
+
+    /// Replaces `venue`'s table with `names`.
+    pub fn set_venue(&mut self, venue: &VenueId, names: impl IntoIterator<Item = (String, Symbol)>) {
+        self.venues.insert(venue.to_string(), names.into_iter().collect());
+    }
+
+    /// `venue`'s names and the pairs they stand for, if it has any.
+    pub fn venue(&self, venue: &VenueId) -> Option<&BTreeMap<String, Symbol>> {
+        self.venues.get(&venue.to_string())
+    }
+
+    pub fn venues(&self) -> impl Iterator<Item = VenueId> + '_ {
+        self.venues.keys().map(|venue| VenueId::from(venue.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        self.venues.values().map(BTreeMap::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Registers `venue`'s names with `normalizer`, ahead of its guesses.
+    /// Returns how many were registered.
+    pub fn register(&self, venue: &VenueId, normalizer: &mut DefaultSymbolNormalizer) -> usize {
+        let Some(names) = self.venue(venue) else {
+            return 0;
+        };
+        for (name, symbol) in names {
+            normalizer.add_symbol_mapping(name.clone(), symbol.clone());
+        }
+        names.len()
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::traits::SymbolNormalizer;
+
+    #[test]
+    fn test_round_trips_and_registers() {
+        let mut mappings = SymbolMappings::new();
+        mappings.insert(&VenueId::Kraken, "XXBTZUSD", Symbol::new("BTC", "USD"));
+        mappings.insert(&VenueId::Poloniex, "BTC_USDT", Symbol::new("BTC", "USDT"));
+
+        let dir = std::env::temp_dir().join(format!("arbfinder-symbols-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in ["symbols.toml", "symbols.json"] {
+            let path = dir.join(file);
+            mappings.save(&path).unwrap();
+            assert_eq!(SymbolMappings::load(&path).unwrap(), mappings);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // Guessing would split XXBTZUSD as XXBTZ/USD
+        let mut normalizer = DefaultSymbolNormalizer::new();
+        assert_eq!(mappings.register(&VenueId::Kraken, &mut normalizer), 1);
+        assert_eq!(normalizer.normalize_symbol("XXBTZUSD").unwrap(), Symbol::new("BTC", "USD"));
+        assert_eq!(normalizer.denormalize_symbol(&Symbol::new("BTC", "USD")).unwrap(), "XXBTZUSD");
+        assert_eq!(mappings.register(&VenueId::Binance, &mut normalizer), 0);
+    }
+}
@@ -0,0 +1,146 @@
+//! Order books synthesized from swap quotes.
+//!
+//! Venues without a book, such as AMM pools and aggregators, can still say
+//! what a swap of a given size would pay out. Quoting a ladder of sizes in
+//! each direction and taking the difference between neighbouring quotes
+//! gives the marginal price of each slice, so sweeping the resulting book
+//! reproduces the venue's quotes.
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+use arbfinder_core::prelude::*;
+
+/// Decimal places kept in synthesized prices.
+const PRICE_DECIMALS: u32 = 8;
+
+/// A swap of `amount_in` for `amount_out`, both in whole tokens.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SwapQuote {
+    pub amount_in: Decimal,
+    pub amount_out: Decimal,
+}
+
+impl SwapQuote {
+    pub fn new(amount_in: Decimal, amount_out: Decimal) -> Self {
+        Self { amount_in, amount_out }
+    }
+}
+
+/// `amount` whole tokens in the token's smallest unit.
+pub fn to_raw(amount: Decimal, decimals: u8) -> Option<u128> {
+    let scale = Decimal::from_i128_with_scale(10i128.checked_pow(u32::from(decimals))?, 0);
+    amount.checked_mul(scale)?.trunc().to_u128()
+}
+
+/// `raw` smallest units as whole tokens.
+pub fn from_raw(raw: u128, decimals: u8) -> Option<Decimal> {
+    Decimal::try_from_i128_with_scale(i128::try_from(raw).ok()?, u32::from(decimals)).ok()
+}
+
+/// Book for `symbol` from quotes selling the base (`bids`, base in and quote
+/// out) and buying it (`asks`, quote in and base out), each ladder in
+/// increasing size.
+pub fn book_from_quotes(symbol: Symbol, bids: &[SwapQuote], asks: &[SwapQuote]) -> OrderBook {
+    let mut book = OrderBook::new(symbol);
+
+    // (price, quantity) of each slice between neighbouring quotes
+    let slices = |quotes: &[SwapQuote], selling_base: bool| {
+        let mut previous = SwapQuote::new(Decimal::ZERO, Decimal::ZERO);
+        let mut slices = Vec::with_capacity(quotes.len());
+        for quote in quotes {
+            let paid = quote.amount_in - previous.amount_in;
+            let received = quote.amount_out - previous.amount_out;
+            previous = *quote;
+            if paid <= Decimal::ZERO || received <= Decimal::ZERO {
+                continue;
+            }
+            let (price, quantity) = if selling_base {
+                (received / paid, paid)
+            } else {
+                (paid / received, received)
+            };
+            slices.push((price.round_dp(PRICE_DECIMALS), quantity));
+        }
+        slices
+    };
+
+    // Rounding can leave a deeper slice marginally better than the one
+    // before it; such slices join the level above rather than crossing it.
+    let mut last: Option<(Decimal, Decimal)> = None;
+    for (price, quantity) in slices(bids, true) {
+        let (price, quantity) = match last {
+            Some((best, total)) if price >= best => (best, total + quantity),
+            _ => (price, quantity),
+        };
+        book.update_bid(price, quantity);
+        last = Some((price, quantity));
+    }
+
+    let mut last: Option<(Decimal, Decimal)> = None;
+    for (price, quantity) in slices(asks, false) {
+        let (price, quantity) = match last {
+            Some((best, total)) if price <= best => (best, total + quantity),
+            _ => (price, quantity),
+        };
+        book.update_ask(price, quantity);
+        last = Some((price, quantity));
+    }
+
+    book
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn dec(value: &str) -> Decimal {
+        Decimal::from_str(value).unwrap()
+    }
+
+    #[test]
+    fn test_slices_are_marginal_prices() {
+        let bids = [
+            SwapQuote::new(dec("1000"), dec("999.9")),
+            SwapQuote::new(dec("10000"), dec("9998.1")),
+        ];
+        let asks = [
+            SwapQuote::new(dec("1000"), dec("999.8")),
+            SwapQuote::new(dec("10000"), dec("9996.2")),
+        ];
+        let book = book_from_quotes(Symbol::new("USDC", "USDT"), &bids, &asks);
+
+        let bid = book.best_bid().unwrap();
+        assert_eq!(bid.price, dec("0.9999"));
+        assert_eq!(bid.quantity, dec("1000"));
+        // 8998.2 USDT for the next 9000 USDC
+        assert_eq!(book.bids.values().next().unwrap().price, dec("0.9998"));
+
+        let ask = book.best_ask().unwrap();
+        assert_eq!(ask.quantity, dec("999.8"));
+        assert_eq!(ask.price, dec("1.00020004"));
+        assert_eq!(book.asks.len(), 2);
+    }
+
+    #[test]
+    fn test_rounding_never_crosses_levels() {
+        // The second slice rounds to the same price as the first
+        let bids = [
+            SwapQuote::new(dec("1000"), dec("999.9")),
+            SwapQuote::new(dec("2000"), dec("1999.8")),
+        ];
+        let book = book_from_quotes(Symbol::new("DAI", "USDC"), &bids, &[]);
+        assert_eq!(book.bids.len(), 1);
+        assert_eq!(book.best_bid().unwrap().quantity, dec("2000"));
+        assert!(book.best_ask().is_none());
+    }
+
+    #[test]
+    fn test_raw_amounts() {
+        assert_eq!(to_raw(dec("1000"), 6), Some(1_000_000_000));
+        assert_eq!(to_raw(dec("1.5"), 18), Some(1_500_000_000_000_000_000));
+        assert_eq!(from_raw(999_800_000, 6), Some(dec("999.8")));
+        assert_eq!(from_raw(u128::MAX, 18), None);
+    }
+}
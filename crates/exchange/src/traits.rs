@@ -1,19 +1,35 @@
 use async_trait::async_trait;
 use arbfinder_core::{
-    ArbFinderError, Result, Balance, MarketData, Order, OrderFill, OrderId, OrderRequest,
-    OrderUpdate, Symbol, VenueId,
+    ArbFinderError, Result, Balance, Candle, CandleInterval, Deposit, DepositAddress, MarketData, Order, OrderBook, OrderFill, OrderId, OrderRequest,
+    EventTime, FundingRate, OrderUpdate, SizeUnit, Symbol, SymbolId, Trade, TradingFee, VenueCredentials, VenueId, Withdrawal,
+    WithdrawalProgress,
 };
+use arbfinder_core::config::{ProxyConfig, RateLimitConfig};
+use arbfinder_core::utils::{RetryPolicy, SharedClockSkew};
 use chrono::{DateTime, Utc};
 use futures::Stream;
+use rust_decimal::Decimal;
 use serde_json::Value;
 use std::collections::HashMap;
 use std::pin::Pin;
+use std::time::Duration;
+
+use crate::circuit::{CircuitState, SharedCircuitBreaker};
+use crate::compression::FrameEncoding;
+use crate::heartbeat::{HeartbeatManager, LatencyStats};
+use crate::pinning::TlsPins;
+use crate::rate_limiter::SharedRateLimiter;
+use crate::status::{VenueHealth, VenueStatusReport};
+use crate::symbol_map::SymbolMappings;
 
 pub type MarketDataStream = Pin<Box<dyn Stream<Item = Result<MarketData>> + Send>>;
 pub type OrderUpdateStream = Pin<Box<dyn Stream<Item = Result<OrderUpdate>> + Send>>;
 
+/// A venue's market data: symbols, public REST endpoints and streams.
+/// Read-only integrations, such as DEX quotes and recorders, implement only
+/// this.
 #[async_trait]
-pub trait ExchangeAdapter: Send + Sync {
+pub trait MarketDataAdapter: Send + Sync {
     fn venue_id(&self) -> VenueId;
     
     async fn connect(&mut self) -> Result<()>;
@@ -25,6 +41,19 @@ pub trait ExchangeAdapter: Send + Sync {
     
     async fn get_symbols(&self) -> Result<Vec<Symbol>>;
     async fn get_symbol_info(&self, symbol: &Symbol) -> Result<SymbolInfo>;
+
+    /// `symbol` as the venue names it.
+    fn venue_symbol(&self, symbol: &Symbol) -> String {
+        symbol.to_pair()
+    }
+
+    /// Registers venue symbol names that could not be guessed, as read from
+    /// a mappings file. Venues naming every symbol by rule ignore them.
+    fn add_symbol_mappings(&mut self, _mappings: &SymbolMappings) {}
+
+    /// Drops cached REST responses, so symbols and trading rules are
+    /// fetched afresh. Venues that cache nothing ignore it.
+    fn clear_response_cache(&self) {}
     
     async fn subscribe_orderbook(&mut self, symbol: &Symbol, depth: Option<u32>) -> Result<()>;
     async fn subscribe_trades(&mut self, symbol: &Symbol) -> Result<()>;
@@ -32,13 +61,130 @@ pub trait ExchangeAdapter: Send + Sync {
     async fn unsubscribe_orderbook(&mut self, symbol: &Symbol) -> Result<()>;
     async fn unsubscribe_trades(&mut self, symbol: &Symbol) -> Result<()>;
     async fn unsubscribe_ticker(&mut self, symbol: &Symbol) -> Result<()>;
+
+    /// Streams only the best bid and offer of `symbol`, as one-level
+    /// books. Venues without a dedicated stream use the shallowest depth
+    /// stream they have.
+    async fn subscribe_bbo(&mut self, symbol: &Symbol) -> Result<()> {
+        self.subscribe_orderbook(symbol, Some(1)).await
+    }
+
+    async fn unsubscribe_bbo(&mut self, symbol: &Symbol) -> Result<()> {
+        self.unsubscribe_orderbook(symbol).await
+    }
     
     async fn market_data_stream(&self) -> Result<MarketDataStream>;
+
+    /// A snapshot of `symbol`'s book over REST, polled in place of the
+    /// stream while it is down.
+    async fn fetch_orderbook(&self, symbol: &Symbol, _depth: Option<u32>) -> Result<OrderBook> {
+        Err(ArbFinderError::Exchange(format!(
+            "{} has no REST order book for {}",
+            self.venue_id(),
+            symbol
+        )))
+    }
+
+    /// The last `limit` closed or forming candles of `symbol`, oldest
+    /// first.
+    async fn get_klines(&self, symbol: &Symbol, interval: CandleInterval, _limit: u32) -> Result<Vec<Candle>> {
+        Err(ArbFinderError::Exchange(format!(
+            "{} has no {} candles for {}",
+            self.venue_id(),
+            interval,
+            symbol
+        )))
+    }
+
+    /// The venue's last `limit` public trades of `symbol`, oldest first.
+    async fn get_recent_trades(&self, symbol: &Symbol, _limit: u32) -> Result<Vec<Trade>> {
+        Err(ArbFinderError::Exchange(format!(
+            "{} has no recent trades for {}",
+            self.venue_id(),
+            symbol
+        )))
+    }
+
+    /// Funding currently accruing on the perpetual `symbol`. Spot venues
+    /// have none.
+    async fn get_funding_rate(&self, symbol: &Symbol) -> Result<FundingRate> {
+        Err(ArbFinderError::Exchange(format!(
+            "{} has no funding rate for {}",
+            self.venue_id(),
+            symbol
+        )))
+    }
+
+    /// The last `limit` funding rates `symbol` settled at, oldest first.
+    async fn get_funding_history(&self, symbol: &Symbol, _limit: u32) -> Result<Vec<FundingRate>> {
+        Err(ArbFinderError::Exchange(format!(
+            "{} has no funding history for {}",
+            self.venue_id(),
+            symbol
+        )))
+    }
+
+    /// Circuit of the adapter's market data connection, for reporting
+    /// whether the venue keeps failing to connect.
+    fn circuit_breaker(&self) -> Option<SharedCircuitBreaker> {
+        None
+    }
+
+    /// Estimate of the venue's clock the adapter stamps signed requests
+    /// with, kept up to date by whoever measures it.
+    fn clock_skew(&self) -> Option<SharedClockSkew> {
+        None
+    }
+
+    /// Heartbeat of the adapter's market data connections, for reporting
+    /// their round trips and missed pongs.
+    fn heartbeat(&self) -> Option<HeartbeatManager> {
+        None
+    }
+
+    /// Reads the venue's status page for outages and scheduled
+    /// maintenance.
+    async fn venue_status(&self) -> Result<VenueStatusReport> {
+        Err(ArbFinderError::Exchange(format!("{} has no status endpoint", self.venue_id())))
+    }
+}
+
+/// Orders, balances and account endpoints of a venue that can be traded
+/// on.
+#[async_trait]
+pub trait TradingAdapter: MarketDataAdapter {
     async fn order_update_stream(&self) -> Result<OrderUpdateStream>;
     
-    async fn place_order(&mut self, request: &OrderRequest) -> Result<Order>;
-    async fn cancel_order(&mut self, order_id: &OrderId) -> Result<()>;
-    async fn cancel_all_orders(&mut self, symbol: Option<&Symbol>) -> Result<Vec<OrderId>>;
+    async fn place_order(&self, request: &OrderRequest) -> Result<Order>;
+
+    /// Whether the venue takes `request`'s quote-currency size as it is,
+    /// e.g. for market buys. Otherwise the caller converts it to base units
+    /// before `place_order`.
+    fn accepts_quote_size(&self, _request: &OrderRequest) -> bool {
+        false
+    }
+
+    async fn cancel_order(&self, order_id: &OrderId) -> Result<()>;
+
+    /// Whether the venue can amend resting orders in place. Where it can't,
+    /// callers cancel and place the order again, losing its queue position.
+    fn supports_amend(&self) -> bool {
+        false
+    }
+
+    /// Changes a resting order's price and/or quantity in place, keeping
+    /// its id and, where the venue allows, its queue position. Venues may
+    /// refuse some changes with `InvalidOrder`, e.g. raising the quantity.
+    async fn amend_order(
+        &self,
+        _order_id: &OrderId,
+        _new_price: Option<Decimal>,
+        _new_quantity: Option<Decimal>,
+    ) -> Result<()> {
+        Err(ArbFinderError::Exchange(format!("{} does not support amending orders", self.venue_id())))
+    }
+
+    async fn cancel_all_orders(&self, symbol: Option<&Symbol>) -> Result<Vec<OrderId>>;
     async fn get_order(&self, order_id: &OrderId) -> Result<Option<Order>>;
     async fn get_open_orders(&self, symbol: Option<&Symbol>) -> Result<Vec<Order>>;
     async fn get_order_history(&self, symbol: Option<&Symbol>, limit: Option<u32>) -> Result<Vec<Order>>;
@@ -47,13 +193,151 @@ pub trait ExchangeAdapter: Send + Sync {
     async fn get_balance(&self, asset: &str) -> Result<Option<Balance>>;
     
     async fn get_trade_history(&self, symbol: Option<&Symbol>, limit: Option<u32>) -> Result<Vec<OrderFill>>;
+
+    /// The account's maker and taker fee rates on `symbol`, at its current
+    /// volume tier.
+    async fn get_trading_fees(&self, symbol: &Symbol) -> Result<TradingFee> {
+        Err(ArbFinderError::Exchange(format!(
+            "{} has no account fee rates for {}",
+            self.venue_id(),
+            symbol
+        )))
+    }
+
+    /// Where to send `asset` for it to be credited to this account.
+    async fn get_deposit_address(&self, asset: &str) -> Result<DepositAddress> {
+        Err(ArbFinderError::Exchange(format!(
+            "{} has no deposit addresses for {}",
+            self.venue_id(),
+            asset
+        )))
+    }
+
+    /// Sends `amount` of `asset` to `address`, which venues that only pay
+    /// out to allowlisted addresses need to have on file already.
+    async fn withdraw(&self, asset: &str, _amount: Decimal, _address: &DepositAddress) -> Result<Withdrawal> {
+        Err(ArbFinderError::Exchange(format!(
+            "{} has no withdrawals of {}",
+            self.venue_id(),
+            asset
+        )))
+    }
+
+    /// Where `withdrawal`, made through this adapter, has got to.
+    async fn get_withdrawal_progress(&self, withdrawal: &Withdrawal) -> Result<WithdrawalProgress> {
+        Err(ArbFinderError::Exchange(format!(
+            "{} does not report the status of withdrawals of {}",
+            self.venue_id(),
+            withdrawal.asset
+        )))
+    }
+
+    /// Deposits of `asset` made since `since`, in any order.
+    async fn get_deposits(&self, asset: &str, _since: DateTime<Utc>) -> Result<Vec<Deposit>> {
+        Err(ArbFinderError::Exchange(format!(
+            "{} does not report deposits of {}",
+            self.venue_id(),
+            asset
+        )))
+    }
+
+    /// Signs later requests with `credentials` and reopens authenticated
+    /// streams under them; market data streams carry on untouched. Returns
+    /// whether they differ from the credentials in use.
+    async fn rotate_credentials(&self, _credentials: &VenueCredentials) -> Result<bool> {
+        Err(ArbFinderError::Exchange(format!(
+            "{} cannot rotate credentials",
+            self.venue_id()
+        )))
+    }
+
+    /// Sends a signed request the venue refuses unless the credentials in
+    /// use are valid, e.g. to check those just rotated in.
+    async fn verify_credentials(&self) -> Result<()> {
+        Err(ArbFinderError::Exchange(format!(
+            "{} cannot verify credentials",
+            self.venue_id()
+        )))
+    }
     
     async fn get_account_info(&self) -> Result<AccountInfo>;
 }
 
+/// A venue with both market data and trading, as the execution engine
+/// needs. Implemented for every `TradingAdapter`.
+pub trait ExchangeAdapter: TradingAdapter {}
+
+impl<T: TradingAdapter + ?Sized> ExchangeAdapter for T {}
+
+/// A venue's adapter as `ExchangeManager` holds it: market data only, or
+/// with trading too. Derefs to its market data side either way.
+pub enum VenueAdapter {
+    MarketData(Box<dyn MarketDataAdapter>),
+    Trading(Box<dyn TradingAdapter>),
+}
+
+impl VenueAdapter {
+    pub fn can_trade(&self) -> bool {
+        matches!(self, Self::Trading(_))
+    }
+
+    /// The trading side, for venues that have one.
+    pub fn trading(&self) -> Option<&dyn TradingAdapter> {
+        match self {
+            Self::MarketData(_) => None,
+            Self::Trading(adapter) => Some(adapter.as_ref()),
+        }
+    }
+
+    pub fn trading_mut(&mut self) -> Option<&mut (dyn TradingAdapter + 'static)> {
+        match self {
+            Self::MarketData(_) => None,
+            Self::Trading(adapter) => Some(adapter.as_mut()),
+        }
+    }
+}
+
+impl std::ops::Deref for VenueAdapter {
+    type Target = dyn MarketDataAdapter;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            Self::MarketData(adapter) => adapter.as_ref(),
+            Self::Trading(adapter) => adapter.as_ref(),
+        }
+    }
+}
+
+impl std::ops::DerefMut for VenueAdapter {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        match self {
+            Self::MarketData(adapter) => adapter.as_mut(),
+            Self::Trading(adapter) => adapter.as_mut(),
+        }
+    }
+}
+
+impl From<Box<dyn MarketDataAdapter>> for VenueAdapter {
+    fn from(adapter: Box<dyn MarketDataAdapter>) -> Self {
+        Self::MarketData(adapter)
+    }
+}
+
+impl From<Box<dyn ExchangeAdapter>> for VenueAdapter {
+    fn from(adapter: Box<dyn ExchangeAdapter>) -> Self {
+        Self::Trading(adapter)
+    }
+}
+
 #[async_trait]
 pub trait WebSocketHandler: Send + Sync {
     async fn on_message(&mut self, message: &str) -> Result<()>;
+    /// Binary frames, decoded per the config's `frame_encoding`. Venues
+    /// with their own payload format override this.
+    async fn on_binary(&mut self, data: &[u8]) -> Result<()> {
+        let text = String::from_utf8_lossy(data);
+        self.on_message(&text).await
+    }
     async fn on_connect(&mut self) -> Result<()>;
     async fn on_disconnect(&mut self) -> Result<()>;
     async fn on_error(&mut self, error: &ArbFinderError) -> Result<()>;
@@ -103,6 +387,84 @@ pub struct SymbolInfo {
     pub trading_fees: TradingFees,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OptionKind {
+    Call,
+    Put,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InstrumentKind {
+    Spot,
+    Perpetual,
+    Future {
+        expiry: DateTime<Utc>,
+    },
+    Option {
+        expiry: DateTime<Utc>,
+        strike: rust_decimal::Decimal,
+        option_kind: OptionKind,
+    },
+}
+
+impl InstrumentKind {
+    pub fn expiry(&self) -> Option<DateTime<Utc>> {
+        match self {
+            InstrumentKind::Future { expiry } | InstrumentKind::Option { expiry, .. } => Some(*expiry),
+            InstrumentKind::Spot | InstrumentKind::Perpetual => None,
+        }
+    }
+
+    pub fn is_derivative(&self) -> bool {
+        !matches!(self, InstrumentKind::Spot)
+    }
+}
+
+/// Metadata for instruments that `SymbolInfo` cannot describe on its own:
+/// derivatives with an expiry, strike or contract multiplier.
+#[derive(Debug, Clone)]
+pub struct InstrumentInfo {
+    /// The venue's instrument name, e.g. `BTC-27DEC24-60000-C`.
+    pub name: String,
+    pub kind: InstrumentKind,
+    pub underlying: String,
+    pub settlement_currency: String,
+    /// Underlying units (or quote units for inverse contracts) per contract.
+    pub contract_size: rust_decimal::Decimal,
+    /// Inverse contracts are quoted in USD and margined in the underlying.
+    pub inverse: bool,
+    pub symbol_info: SymbolInfo,
+}
+
+impl InstrumentInfo {
+    /// `request`'s size in the unit the venue takes orders in, rounded down
+    /// to the lot size: quote currency for inverse contracts, the underlying
+    /// otherwise. `price` converts between the two.
+    pub fn order_amount(&self, request: &OrderRequest, price: rust_decimal::Decimal) -> Result<rust_decimal::Decimal> {
+        if !self.inverse {
+            return request.base_quantity(price, self.symbol_info.lot_size);
+        }
+
+        let amount = match request.size_unit {
+            SizeUnit::Quote => request.quantity,
+            SizeUnit::Base => request.quantity * price,
+        };
+        let lot_size = self.symbol_info.lot_size;
+        let amount = if lot_size > rust_decimal::Decimal::ZERO {
+            (amount / lot_size).floor() * lot_size
+        } else {
+            amount
+        };
+        if amount <= rust_decimal::Decimal::ZERO {
+            return Err(ArbFinderError::InvalidOrder(format!(
+                "{} is less than one contract of {} at {}",
+                request.quantity, self.name, price
+            )));
+        }
+        Ok(amount.normalize())
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TradingFees {
     pub maker_fee: rust_decimal::Decimal,
@@ -127,15 +489,23 @@ pub struct ConnectionStatus {
     pub reconnect_count: u32,
     pub error_count: u32,
     pub last_error: Option<String>,
+    /// Open while reconnects keep failing; see [`crate::circuit`].
+    pub circuit: CircuitState,
+    /// Heartbeat round trips, for adapters that report a heartbeat.
+    pub latency: Option<LatencyStats>,
+    /// Heartbeat pings gone unanswered since the last answer.
+    pub missed_pongs: u32,
+    /// What the venue's status page last said; see [`crate::status`].
+    pub venue_health: VenueHealth,
 }
 
 #[derive(Debug, Clone)]
 pub struct SubscriptionInfo {
-    pub symbol: Symbol,
+    pub symbol: SymbolId,
     pub data_type: String,
-    pub subscribed_at: DateTime<Utc>,
+    pub subscribed_at: EventTime,
     pub message_count: u64,
-    pub last_message: Option<DateTime<Utc>>,
+    pub last_message: Option<EventTime>,
 }
 
 pub trait ExchangeConfig {
@@ -151,6 +521,59 @@ pub trait ExchangeConfig {
     fn reconnect_delay_ms(&self) -> u64;
     fn heartbeat_interval_ms(&self) -> u64;
     fn request_timeout_ms(&self) -> u64;
+
+    /// Shared by REST retries and WebSocket reconnects.
+    fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy::new(self.reconnect_attempts(), self.reconnect_delay_ms())
+    }
+
+    /// Keys the venue's servers must present, if pinned.
+    fn tls_pins(&self) -> Option<&TlsPins> {
+        None
+    }
+
+    /// Weighted limits REST requests wait for, in place of the flat
+    /// requests per second.
+    fn rate_limits(&self) -> Option<&RateLimitConfig> {
+        None
+    }
+
+    /// Proxy REST requests and WebSocket connections go through, if any.
+    fn proxy(&self) -> Option<&ProxyConfig> {
+        None
+    }
+
+    /// Compression the venue applies to binary frames itself, undone
+    /// before they reach the handler.
+    fn frame_encoding(&self) -> FrameEncoding {
+        FrameEncoding::Plain
+    }
+
+    /// Circuit WebSocket reconnects go through, shared with whoever reports
+    /// on it. Without one, each connection opens its own after the retry
+    /// policy's attempts.
+    fn circuit_breaker(&self) -> Option<SharedCircuitBreaker> {
+        None
+    }
+
+    /// How long GET responses are cached, by endpoint prefix. Endpoints
+    /// not listed are always fetched.
+    fn response_cache_ttls(&self) -> &[(String, Duration)] {
+        &[]
+    }
+
+    /// Rate limiter shared by every REST client of the venue. Without one,
+    /// each client builds its own from `rate_limits`.
+    fn rate_limiter(&self) -> Option<SharedRateLimiter> {
+        None
+    }
+
+    /// Heartbeat WebSocket connections ping with ping frames, matching
+    /// the pong frames that answer them. Without one, they only answer the
+    /// venue's pings.
+    fn heartbeat(&self) -> Option<HeartbeatManager> {
+        None
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -167,6 +590,15 @@ pub struct DefaultExchangeConfig {
     pub reconnect_delay_ms: u64,
     pub heartbeat_interval_ms: u64,
     pub request_timeout_ms: u64,
+    pub retry_policy: Option<RetryPolicy>,
+    pub tls_pins: Option<TlsPins>,
+    pub rate_limits: Option<RateLimitConfig>,
+    pub proxy: Option<ProxyConfig>,
+    pub circuit_breaker: Option<SharedCircuitBreaker>,
+    pub frame_encoding: FrameEncoding,
+    pub response_cache_ttls: Vec<(String, Duration)>,
+    pub rate_limiter: Option<SharedRateLimiter>,
+    pub heartbeat: Option<HeartbeatManager>,
 }
 
 impl ExchangeConfig for DefaultExchangeConfig {
@@ -182,6 +614,19 @@ impl ExchangeConfig for DefaultExchangeConfig {
     fn reconnect_delay_ms(&self) -> u64 { self.reconnect_delay_ms }
     fn heartbeat_interval_ms(&self) -> u64 { self.heartbeat_interval_ms }
     fn request_timeout_ms(&self) -> u64 { self.request_timeout_ms }
+    fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy.clone().unwrap_or_else(|| {
+            RetryPolicy::new(self.reconnect_attempts, self.reconnect_delay_ms)
+        })
+    }
+    fn tls_pins(&self) -> Option<&TlsPins> { self.tls_pins.as_ref() }
+    fn rate_limits(&self) -> Option<&RateLimitConfig> { self.rate_limits.as_ref() }
+    fn proxy(&self) -> Option<&ProxyConfig> { self.proxy.as_ref() }
+    fn circuit_breaker(&self) -> Option<SharedCircuitBreaker> { self.circuit_breaker.clone() }
+    fn frame_encoding(&self) -> FrameEncoding { self.frame_encoding }
+    fn response_cache_ttls(&self) -> &[(String, Duration)] { &self.response_cache_ttls }
+    fn rate_limiter(&self) -> Option<SharedRateLimiter> { self.rate_limiter.clone() }
+    fn heartbeat(&self) -> Option<HeartbeatManager> { self.heartbeat.clone() }
 }
 
 impl Default for DefaultExchangeConfig {
@@ -199,6 +644,15 @@ impl Default for DefaultExchangeConfig {
             reconnect_delay_ms: 5000,
             heartbeat_interval_ms: 30000,
             request_timeout_ms: 10000,
+            retry_policy: None,
+            tls_pins: None,
+            rate_limits: None,
+            proxy: None,
+            circuit_breaker: None,
+            frame_encoding: FrameEncoding::Plain,
+            response_cache_ttls: Vec::new(),
+            rate_limiter: None,
+            heartbeat: None,
         }
     }
 }
\ No newline at end of file
@@ -0,0 +1,334 @@
+//! Moving inventory between venues.
+//!
+//! A transfer is a withdrawal from one venue and a deposit on another, and
+//! neither venue knows about the other half. The manager requests the
+//! withdrawal to the receiving venue's deposit address, then polls the
+//! sending venue until the chain transaction is out and the receiving venue
+//! until a matching deposit is credited, giving up after a timeout.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::Utc;
+use rust_decimal::Decimal;
+use tokio::time::MissedTickBehavior;
+use tracing::{info, warn};
+
+use arbfinder_core::{ArbFinderError, Deposit, Result, TransferStatus, VenueId, Withdrawal};
+
+use crate::network::address_for_sender;
+use crate::traits::ExchangeAdapter;
+
+/// How much earlier than the withdrawal request deposits are looked for,
+/// for venue clocks running behind ours.
+const CLOCK_SKEW: chrono::Duration = chrono::Duration::minutes(5);
+
+pub type SharedAdapter = Arc<dyn ExchangeAdapter>;
+
+#[derive(Debug, Clone)]
+pub struct TransferConfig {
+    /// How often both venues are checked while a transfer is under way.
+    pub poll_interval: Duration,
+    /// How long after the withdrawal request a transfer is given up on.
+    pub timeout: Duration,
+    /// Share of the amount a deposit may fall short by, to withdrawal fees,
+    /// and still be taken for the transfer when the chain transaction is
+    /// not known on both sides.
+    pub fee_tolerance: Decimal,
+    /// Refuse to start transfers, which would otherwise be real
+    /// withdrawals.
+    pub paper_trading: bool,
+}
+
+impl Default for TransferConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(30),
+            timeout: Duration::from_secs(2 * 60 * 60),
+            fee_tolerance: Decimal::new(2, 2),
+            paper_trading: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransferStage {
+    /// Withdrawal requested; the sending venue has not sent it yet.
+    Withdrawing,
+    /// Sent on chain, not yet seen by the receiving venue.
+    InTransit,
+    /// Seen by the receiving venue, waiting for confirmations.
+    Confirming,
+    /// Credited to the receiving account.
+    Completed,
+    Failed,
+    /// Not credited within `TransferConfig::timeout`. The funds may still
+    /// arrive, so the transfer is left to an operator.
+    TimedOut,
+}
+
+impl TransferStage {
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::TimedOut)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    pub asset: String,
+    pub amount: Decimal,
+    pub from: VenueId,
+    pub to: VenueId,
+    pub withdrawal: Withdrawal,
+    /// The chain transaction, once either venue reports it.
+    pub tx_id: Option<String>,
+    /// The receiving venue's deposit, once one is matched to the transfer.
+    pub deposit: Option<Deposit>,
+    pub stage: TransferStage,
+    /// Why the transfer failed, for failed transfers.
+    pub error: Option<String>,
+}
+
+impl Transfer {
+    /// Confirmations the receiving venue reports so far.
+    pub fn confirmations(&self) -> Option<u32> {
+        self.deposit.as_ref().and_then(|deposit| deposit.confirmations)
+    }
+
+    fn fail(&mut self, error: String) {
+        self.stage = TransferStage::Failed;
+        self.error = Some(error);
+    }
+}
+
+/// Runs withdraw-then-deposit transfers between the venues added to it.
+pub struct TransferManager {
+    config: TransferConfig,
+    venues: HashMap<VenueId, SharedAdapter>,
+    /// Deposits already matched to a transfer, by venue and deposit id, so
+    /// two transfers of the same amount don't both take one.
+    claimed: std::sync::Mutex<HashSet<(VenueId, String)>>,
+}
+
+impl TransferManager {
+    pub fn new(config: TransferConfig) -> Self {
+        Self {
+            config,
+            venues: HashMap::new(),
+            claimed: std::sync::Mutex::new(HashSet::new()),
+        }
+    }
+
+    pub fn add_venue(&mut self, venue: VenueId, adapter: SharedAdapter) {
+        self.venues.insert(venue, adapter);
+    }
+
+    fn venue(&self, venue: &VenueId) -> Result<&SharedAdapter> {
+        self.venues
+            .get(venue)
+            .ok_or_else(|| ArbFinderError::Exchange(format!("No adapter registered for {}", venue)))
+    }
+
+    /// Withdraws `amount` of `asset` from `from` to the deposit address of
+    /// `to`, on the chain the address is on as `from` names it. Transfers
+    /// over a chain either venue's name is unknown for, and any transfer
+    /// while paper trading, are refused.
+    pub async fn start(&self, asset: &str, from: &VenueId, to: &VenueId, amount: Decimal) -> Result<Transfer> {
+        if self.config.paper_trading {
+            return Err(ArbFinderError::Execution(format!(
+                "Paper trading: not transferring {} {} from {} to {}",
+                amount, asset, from, to
+            )));
+        }
+        let sender = self.venue(from)?;
+        let address = self.venue(to)?.get_deposit_address(asset).await?;
+        let address = address_for_sender(&address, to, from)?;
+
+        let withdrawal = sender.withdraw(asset, amount, &address).await?;
+        info!("Transfer of {} {} from {} to {} started as withdrawal {}", amount, asset, from, to, withdrawal.id);
+        Ok(Transfer {
+            asset: asset.to_string(),
+            amount,
+            from: from.clone(),
+            to: to.clone(),
+            withdrawal,
+            tx_id: None,
+            deposit: None,
+            stage: TransferStage::Withdrawing,
+            error: None,
+        })
+    }
+
+    /// Checks both venues once and moves `transfer` on, returning its new
+    /// stage. After an error it can simply be polled again.
+    pub async fn poll(&self, transfer: &mut Transfer) -> Result<TransferStage> {
+        if transfer.stage.is_final() {
+            return Ok(transfer.stage);
+        }
+        let timeout = chrono::Duration::from_std(self.config.timeout).unwrap_or(chrono::Duration::MAX);
+        if Utc::now() - transfer.withdrawal.requested_at > timeout {
+            transfer.stage = TransferStage::TimedOut;
+            return Ok(transfer.stage);
+        }
+
+        if transfer.stage == TransferStage::Withdrawing {
+            let progress = self
+                .venue(&transfer.from)?
+                .get_withdrawal_progress(&transfer.withdrawal)
+                .await?;
+            if progress.tx_id.is_some() {
+                transfer.tx_id = progress.tx_id;
+            }
+            match progress.status {
+                TransferStatus::Failed => {
+                    transfer.fail(format!("{} withdrawal {} failed", transfer.from, transfer.withdrawal.id));
+                    return Ok(transfer.stage);
+                }
+                TransferStatus::Confirming | TransferStatus::Completed => transfer.stage = TransferStage::InTransit,
+                TransferStatus::Pending => {}
+            }
+        }
+
+        let since = transfer.withdrawal.requested_at - CLOCK_SKEW;
+        let deposits = self.venue(&transfer.to)?.get_deposits(&transfer.asset, since).await?;
+        let deposit = match &transfer.deposit {
+            Some(known) => deposits.into_iter().find(|deposit| deposit.id == known.id),
+            None => self.claim(transfer, deposits),
+        };
+        let Some(deposit) = deposit else {
+            return Ok(transfer.stage);
+        };
+
+        if transfer.tx_id.is_none() {
+            transfer.tx_id = deposit.tx_id.clone();
+        }
+        match deposit.status {
+            TransferStatus::Completed => transfer.stage = TransferStage::Completed,
+            TransferStatus::Failed => {
+                transfer.fail(format!("{} deposit {} failed", transfer.to, deposit.id));
+            }
+            TransferStatus::Pending | TransferStatus::Confirming => transfer.stage = TransferStage::Confirming,
+        }
+        transfer.deposit = Some(deposit);
+        Ok(transfer.stage)
+    }
+
+    /// Takes the first of `deposits` that matches `transfer` and no other
+    /// transfer has taken.
+    fn claim(&self, transfer: &Transfer, deposits: Vec<Deposit>) -> Option<Deposit> {
+        let mut claimed = self.claimed.lock().unwrap_or_else(|e| e.into_inner());
+        let deposit = deposits.into_iter().find(|deposit| {
+            !claimed.contains(&(deposit.venue.clone(), deposit.id.clone()))
+                && deposit_matches(transfer, deposit, self.config.fee_tolerance)
+        })?;
+        claimed.insert((deposit.venue.clone(), deposit.id.clone()));
+        Some(deposit)
+    }
+
+    /// Polls `transfer` every `poll_interval` until it completes, fails or
+    /// times out, logging each stage it reaches.
+    pub async fn wait(&self, mut transfer: Transfer) -> Transfer {
+        let mut poll = tokio::time::interval(self.config.poll_interval);
+        poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        while !transfer.stage.is_final() {
+            poll.tick().await;
+            let stage = transfer.stage;
+            if let Err(e) = self.poll(&mut transfer).await {
+                warn!("Could not check transfer {} of {}: {}", transfer.withdrawal.id, transfer.asset, e);
+                continue;
+            }
+            if transfer.stage != stage {
+                info!(
+                    "Transfer {} of {} {} from {} to {}: {:?}",
+                    transfer.withdrawal.id, transfer.amount, transfer.asset, transfer.from, transfer.to, transfer.stage
+                );
+            }
+        }
+        if let Some(error) = &transfer.error {
+            warn!("Transfer {} failed: {}", transfer.withdrawal.id, error);
+        }
+        transfer
+    }
+
+    /// Starts a transfer and waits for it to finish.
+    pub async fn execute(&self, asset: &str, from: &VenueId, to: &VenueId, amount: Decimal) -> Result<Transfer> {
+        let transfer = self.start(asset, from, to, amount).await?;
+        Ok(self.wait(transfer).await)
+    }
+}
+
+/// Whether `deposit` is `transfer` arriving: the same chain transaction
+/// when both are known, otherwise the asset and the amount less at most
+/// `fee_tolerance` of it.
+fn deposit_matches(transfer: &Transfer, deposit: &Deposit, fee_tolerance: Decimal) -> bool {
+    if !deposit.asset.eq_ignore_ascii_case(&transfer.asset) {
+        return false;
+    }
+    match (&transfer.tx_id, &deposit.tx_id) {
+        (Some(sent), Some(received)) => sent.eq_ignore_ascii_case(received),
+        _ => deposit.amount <= transfer.amount && deposit.amount >= transfer.amount * (Decimal::ONE - fee_tolerance),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arbfinder_core::DepositAddress;
+
+    #[test]
+    fn test_deposit_matching() {
+        let tolerance = Decimal::new(2, 2);
+        let mut transfer = Transfer {
+            asset: "BTC".to_string(),
+            amount: Decimal::ONE,
+            from: VenueId::Binance,
+            to: VenueId::Kraken,
+            withdrawal: Withdrawal {
+                id: "w1".to_string(),
+                venue: VenueId::Binance,
+                asset: "BTC".to_string(),
+                amount: Decimal::ONE,
+                address: DepositAddress::new("BTC", "bc1q"),
+                requested_at: Utc::now(),
+            },
+            tx_id: None,
+            deposit: None,
+            stage: TransferStage::InTransit,
+            error: None,
+        };
+        let mut deposit = Deposit {
+            id: "d1".to_string(),
+            venue: VenueId::Kraken,
+            asset: "BTC".to_string(),
+            amount: Decimal::new(9995, 4),
+            tx_id: Some("ab12".to_string()),
+            status: TransferStatus::Confirming,
+            confirmations: Some(1),
+            time: Utc::now(),
+        };
+
+        // Without the transaction, by amount net of fees
+        assert!(deposit_matches(&transfer, &deposit, tolerance));
+        deposit.amount = Decimal::new(5, 1);
+        assert!(!deposit_matches(&transfer, &deposit, tolerance));
+
+        // With it, by transaction alone
+        transfer.tx_id = Some("AB12".to_string());
+        assert!(deposit_matches(&transfer, &deposit, tolerance));
+        transfer.tx_id = Some("cd34".to_string());
+        assert!(!deposit_matches(&transfer, &deposit, tolerance));
+
+        deposit.asset = "ETH".to_string();
+        transfer.tx_id = Some("ab12".to_string());
+        assert!(!deposit_matches(&transfer, &deposit, tolerance));
+    }
+
+    #[tokio::test]
+    async fn test_paper_trading_refuses_transfers() {
+        let config = TransferConfig { paper_trading: true, ..Default::default() };
+        let transfers = TransferManager::new(config);
+        let error = transfers.start("BTC", &VenueId::Binance, &VenueId::Kraken, Decimal::ONE).await.unwrap_err();
+        assert!(error.to_string().contains("Paper trading"));
+    }
+}
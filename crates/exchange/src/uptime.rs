@@ -0,0 +1,109 @@
+//! How long each venue has actually been connected.
+
+use std::time::{Duration, Instant};
+
+/// A venue's connected time and the gaps in it, from its first connection.
+/// Time before that counts as neither.
+#[derive(Debug, Clone, Default)]
+pub struct VenueUptime {
+    first_connected: Option<Instant>,
+    /// When the current connection, or the current gap, began.
+    since: Option<Instant>,
+    connected: bool,
+    connected_total: Duration,
+    gaps: u64,
+    longest_gap: Duration,
+}
+
+impl VenueUptime {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records whether the venue is connected at `now`. Only changes from
+    /// the last observation matter, so it may be called as often as liked.
+    pub fn observe(&mut self, connected: bool, now: Instant) {
+        if connected == self.connected {
+            return;
+        }
+        match (connected, self.since) {
+            (true, None) => self.first_connected = Some(now),
+            (true, Some(gap_start)) => {
+                self.longest_gap = self.longest_gap.max(now.saturating_duration_since(gap_start));
+            }
+            (false, Some(connected_at)) => {
+                self.connected_total += now.saturating_duration_since(connected_at);
+                self.gaps += 1;
+            }
+            (false, None) => {}
+        }
+        self.connected = connected;
+        self.since = Some(now);
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// Share of the time since the first connection spent connected, in
+    /// percent. Zero for a venue never connected.
+    pub fn uptime_percentage(&self, now: Instant) -> f64 {
+        let Some(first) = self.first_connected else {
+            return 0.0;
+        };
+        let elapsed = now.saturating_duration_since(first);
+        if elapsed.is_zero() {
+            return if self.connected { 100.0 } else { 0.0 };
+        }
+        let mut connected = self.connected_total;
+        if let (true, Some(connected_at)) = (self.connected, self.since) {
+            connected += now.saturating_duration_since(connected_at);
+        }
+        (connected.as_secs_f64() / elapsed.as_secs_f64() * 100.0).min(100.0)
+    }
+
+    /// Disconnections since the first connection, including a current one.
+    pub fn gaps(&self) -> u64 {
+        self.gaps
+    }
+
+    /// How long the venue has been disconnected, if it is.
+    pub fn current_gap(&self, now: Instant) -> Option<Duration> {
+        match (self.connected, self.since) {
+            (false, Some(gap_start)) => Some(now.saturating_duration_since(gap_start)),
+            _ => None,
+        }
+    }
+
+    /// The longest disconnection so far, including a current one.
+    pub fn longest_gap(&self, now: Instant) -> Duration {
+        self.longest_gap.max(self.current_gap(now).unwrap_or_default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uptime_and_gaps() {
+        let start = Instant::now();
+        let at = |secs: u64| start + Duration::from_secs(secs);
+        let mut uptime = VenueUptime::new();
+        assert_eq!(uptime.uptime_percentage(at(10)), 0.0);
+
+        // Up 0-60, down 60-75, up 75-90, down 90-100
+        uptime.observe(true, at(0));
+        uptime.observe(true, at(30));
+        uptime.observe(false, at(60));
+        assert_eq!(uptime.current_gap(at(70)), Some(Duration::from_secs(10)));
+        uptime.observe(true, at(75));
+        assert_eq!(uptime.current_gap(at(80)), None);
+        uptime.observe(false, at(90));
+
+        assert_eq!(uptime.uptime_percentage(at(100)), 75.0);
+        assert_eq!(uptime.gaps(), 2);
+        assert_eq!(uptime.longest_gap(at(100)), Duration::from_secs(15));
+        assert_eq!(uptime.longest_gap(at(120)), Duration::from_secs(30));
+    }
+}
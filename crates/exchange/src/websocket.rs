@@ -1,12 +1,25 @@
+use arbfinder_core::config::ProxyConfig;
+use arbfinder_core::utils::RetryPolicy;
 use arbfinder_core::{ArbFinderError, Result};
-use futures::{SinkExt, StreamExt};
+use futures::{SinkExt, Stream, StreamExt};
+use std::pin::Pin;
 use std::sync::Arc;
 use tokio::sync::{mpsc, Mutex, RwLock};
 use tokio::time::{sleep, Duration, Instant};
-use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::error::{Error as WsError, UrlError};
+use tokio_tungstenite::{
+    client_async, client_async_tls, connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream,
+};
+use tokio_native_tls::native_tls;
 use tracing::{debug, error, info, warn};
 use url::Url;
 
+use crate::circuit::{CircuitBreaker, CircuitBreakerConfig, SharedCircuitBreaker};
+use crate::compression::{CompressionSnapshot, CompressionStats, FrameEncoding};
+use crate::heartbeat::HeartbeatManager;
+use crate::pinning::TlsPins;
+use crate::proxy::Proxy;
 use crate::traits::{ExchangeConfig, WebSocketHandler};
 
 pub type WsStream = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
@@ -17,27 +30,54 @@ pub struct WebSocketConnection {
     stream: Option<WsStream>,
     is_connected: Arc<RwLock<bool>>,
     reconnect_attempts: u32,
-    max_reconnect_attempts: u32,
-    reconnect_delay: Duration,
+    retry_policy: RetryPolicy,
+    circuit: SharedCircuitBreaker,
     last_ping: Arc<Mutex<Option<Instant>>>,
     last_pong: Arc<Mutex<Option<Instant>>>,
     message_tx: Option<mpsc::UnboundedSender<String>>,
+    message_rx: Option<mpsc::UnboundedReceiver<String>>,
     close_tx: Option<mpsc::UnboundedSender<()>>,
+    /// Subscribe messages resent after every reconnect.
+    subscriptions: Vec<String>,
+    frame_encoding: FrameEncoding,
+    /// Wire and decoded sizes of binary frames.
+    compression_stats: Arc<CompressionStats>,
+    tls_pins: Option<TlsPins>,
+    proxy: Option<ProxyConfig>,
+    /// Sends ping frames tagged with the heartbeat's ping ids.
+    heartbeat: Option<HeartbeatManager>,
 }
 
 impl WebSocketConnection {
     pub fn new<C: ExchangeConfig>(config: &C) -> Self {
+        let (message_tx, message_rx) = mpsc::unbounded_channel::<String>();
+        let retry_policy = config.retry_policy();
+        let circuit = config.circuit_breaker().unwrap_or_else(|| {
+            CircuitBreaker::new(CircuitBreakerConfig {
+                failure_threshold: retry_policy.max_attempts,
+                ..Default::default()
+            })
+            .shared()
+        });
+
         Self {
             url: config.websocket_url().to_string(),
             stream: None,
             is_connected: Arc::new(RwLock::new(false)),
             reconnect_attempts: 0,
-            max_reconnect_attempts: config.reconnect_attempts(),
-            reconnect_delay: Duration::from_millis(config.reconnect_delay_ms()),
+            retry_policy,
+            circuit,
             last_ping: Arc::new(Mutex::new(None)),
             last_pong: Arc::new(Mutex::new(None)),
-            message_tx: None,
+            message_tx: Some(message_tx),
+            message_rx: Some(message_rx),
             close_tx: None,
+            subscriptions: Vec::new(),
+            frame_encoding: config.frame_encoding(),
+            compression_stats: Arc::new(CompressionStats::new()),
+            tls_pins: config.tls_pins().cloned(),
+            proxy: config.proxy().cloned(),
+            heartbeat: config.heartbeat(),
         }
     }
 
@@ -47,7 +87,18 @@ impl WebSocketConnection {
         let url = Url::parse(&self.url)
             .map_err(|e| ArbFinderError::WebSocket(format!("Invalid WebSocket URL: {}", e)))?;
 
-        match connect_async(url).await {
+        // No permessage-deflate offer: no tungstenite release implements it,
+        // and all reject the RSV1 frames a venue accepting it would send
+        let request = url
+            .into_client_request()
+            .map_err(|e| ArbFinderError::WebSocket(format!("Invalid WebSocket request: {}", e)))?;
+
+        let proxy = self.proxy.as_ref().map(Proxy::from_config).transpose()?;
+        let connected = match (&proxy, &self.tls_pins) {
+            (None, None) => connect_async(request).await,
+            (proxy, pins) => connect_through(request, proxy.as_ref(), pins.as_ref()).await,
+        };
+        match connected {
             Ok((ws_stream, response)) => {
                 info!("WebSocket connected. Response: {:?}", response.status());
                 self.stream = Some(ws_stream);
@@ -96,6 +147,19 @@ impl WebSocketConnection {
         }
     }
 
+    /// Sends `message` and resends it each time the connection is
+    /// reestablished.
+    pub async fn subscribe(&mut self, message: &str) -> Result<()> {
+        if !self.subscriptions.iter().any(|sent| sent == message) {
+            self.subscriptions.push(message.to_string());
+        }
+        self.send_message(message).await
+    }
+
+    pub fn subscriptions(&self) -> &[String] {
+        &self.subscriptions
+    }
+
     pub async fn send_ping(&mut self) -> Result<()> {
         if let Some(stream) = &mut self.stream {
             let ping_data = Vec::new();
@@ -112,6 +176,19 @@ impl WebSocketConnection {
         }
     }
 
+    /// The circuit reconnects go through.
+    pub fn circuit(&self) -> SharedCircuitBreaker {
+        self.circuit.clone()
+    }
+
+    pub fn compression_stats(&self) -> Arc<CompressionStats> {
+        self.compression_stats.clone()
+    }
+
+    pub fn compression_snapshot(&self) -> CompressionSnapshot {
+        self.compression_stats.snapshot()
+    }
+
     pub async fn get_last_pong_latency(&self) -> Option<Duration> {
         let last_ping = *self.last_ping.lock().await;
         let last_pong = *self.last_pong.lock().await;
@@ -126,24 +203,61 @@ impl WebSocketConnection {
     where
         H: WebSocketHandler + 'static,
     {
-        let (message_tx, mut message_rx) = mpsc::unbounded_channel::<String>();
+        let mut message_rx = self.message_rx.take().ok_or_else(|| {
+            ArbFinderError::WebSocket("Connection is already running".to_string())
+        })?;
         let (close_tx, mut close_rx) = mpsc::unbounded_channel::<()>();
         
-        self.message_tx = Some(message_tx);
         self.close_tx = Some(close_tx);
 
+        // Ping ids, sent as ping frames by the loop below, which owns the
+        // stream; the pings stop once it returns
+        let (ping_tx, mut ping_rx) = mpsc::unbounded_channel::<u64>();
+        if let Some(heartbeat) = &self.heartbeat {
+            heartbeat.spawn_pings(move |id| {
+                ping_tx
+                    .send(id)
+                    .map_err(|_| ArbFinderError::WebSocket("Connection closed".to_string()))
+            });
+        }
+
         loop {
             if !self.is_connected().await {
-                if let Err(e) = self.reconnect(handler.clone()).await {
-                    error!("Failed to reconnect: {}", e);
-                    if self.reconnect_attempts >= self.max_reconnect_attempts {
-                        return Err(ArbFinderError::WebSocket(
-                            "Max reconnection attempts reached".to_string(),
-                        ));
+                // Held off while the circuit is open, however many attempts
+                // have failed; a venue that keeps failing is retried rather
+                // than given up on
+                let wait = self.circuit.lock().unwrap_or_else(|e| e.into_inner()).wait(chrono::Utc::now());
+                if let Some(wait) = wait {
+                    tokio::select! {
+                        _ = sleep(wait) => {}
+                        _ = close_rx.recv() => {
+                            info!("Received close signal");
+                            break;
+                        }
                     }
-                    sleep(self.reconnect_delay).await;
                     continue;
                 }
+
+                match self.reconnect(handler.clone()).await {
+                    Ok(()) => self.circuit.lock().unwrap_or_else(|e| e.into_inner()).record_success(),
+                    Err(e) => {
+                        error!("Failed to reconnect: {}", e);
+                        if !self.retry_policy.is_retryable(&e) {
+                            return Err(e);
+                        }
+                        let opened = self
+                            .circuit
+                            .lock()
+                            .unwrap_or_else(|e| e.into_inner())
+                            .record_failure(chrono::Utc::now());
+                        if opened {
+                            self.reconnect_attempts = 0;
+                        } else {
+                            sleep(self.retry_policy.delay_for_attempt(self.reconnect_attempts)).await;
+                        }
+                        continue;
+                    }
+                }
             }
 
             if let Some(stream) = &mut self.stream {
@@ -177,6 +291,12 @@ impl WebSocketConnection {
                             }
                         }
                     }
+
+                    Some(id) = ping_rx.recv() => {
+                        if let Err(e) = stream.send(Message::Ping(id.to_be_bytes().to_vec())).await {
+                            error!("Failed to send heartbeat ping: {}", e);
+                        }
+                    }
                     
                     _ = close_rx.recv() => {
                         info!("Received close signal");
@@ -201,9 +321,10 @@ impl WebSocketConnection {
                 handler.lock().await.on_message(&text).await?;
             }
             Message::Binary(data) => {
-                let text = String::from_utf8_lossy(&data);
-                debug!("Received binary WebSocket message: {}", text);
-                handler.lock().await.on_message(&text).await?;
+                let decoded = self.frame_encoding.decode(&data)?;
+                self.compression_stats.record(data.len(), decoded.len());
+                debug!("Received binary WebSocket message: {} bytes", decoded.len());
+                handler.lock().await.on_binary(&decoded).await?;
             }
             Message::Ping(data) => {
                 debug!("Received WebSocket ping");
@@ -214,9 +335,12 @@ impl WebSocketConnection {
                 }
                 handler.lock().await.on_ping().await?;
             }
-            Message::Pong(_) => {
+            Message::Pong(data) => {
                 debug!("Received WebSocket pong");
                 *self.last_pong.lock().await = Some(Instant::now());
+                if let (Some(heartbeat), Ok(id)) = (&self.heartbeat, <[u8; 8]>::try_from(&data[..])) {
+                    heartbeat.record_pong_to(u64::from_be_bytes(id)).await;
+                }
                 handler.lock().await.on_pong().await?;
             }
             Message::Close(frame) => {
@@ -238,7 +362,7 @@ impl WebSocketConnection {
         self.reconnect_attempts += 1;
         warn!(
             "Attempting to reconnect ({}/{})",
-            self.reconnect_attempts, self.max_reconnect_attempts
+            self.reconnect_attempts, self.retry_policy.max_attempts
         );
 
         if let Some(mut stream) = self.stream.take() {
@@ -248,6 +372,12 @@ impl WebSocketConnection {
         match self.connect().await {
             Ok(_) => {
                 info!("Reconnected successfully");
+                for message in self.subscriptions.clone() {
+                    self.send_message(&message).await?;
+                }
+                if !self.subscriptions.is_empty() {
+                    info!("Resent {} subscriptions", self.subscriptions.len());
+                }
                 handler.lock().await.on_connect().await?;
                 Ok(())
             }
@@ -263,6 +393,109 @@ impl WebSocketConnection {
     }
 }
 
+/// Connects using `config`, sends each subscription message and then drives
+/// `handler` on a background task until the connection is closed. The
+/// subscriptions are sent again whenever the connection is reestablished.
+pub async fn spawn_connection<C, H>(
+    config: &C,
+    subscriptions: &[String],
+    handler: H,
+) -> Result<tokio::task::JoinHandle<Result<()>>>
+where
+    C: ExchangeConfig,
+    H: WebSocketHandler + 'static,
+{
+    spawn_connection_with(config, subscriptions, |_| handler).await
+}
+
+/// Opens a connection to the request's host, through `proxy` if given,
+/// and checks its certificate against `pins` if given before sending the
+/// upgrade request.
+async fn connect_through(
+    request: tokio_tungstenite::tungstenite::handshake::client::Request,
+    proxy: Option<&Proxy>,
+    pins: Option<&TlsPins>,
+) -> std::result::Result<(WsStream, tokio_tungstenite::tungstenite::handshake::client::Response), WsError> {
+    let uri = request.uri();
+    let host = uri.host().ok_or(WsError::Url(UrlError::NoHostName))?.trim_matches(['[', ']']).to_string();
+    let secure = match uri.scheme_str() {
+        Some("wss") => true,
+        Some("ws") if pins.is_none() => false,
+        _ => return Err(WsError::Url(UrlError::UnsupportedUrlScheme)),
+    };
+    let port = uri.port_u16().unwrap_or(if secure { 443 } else { 80 });
+
+    let tcp = match proxy {
+        Some(proxy) => proxy.connect(&host, port).await?,
+        None => tokio::net::TcpStream::connect((host.as_str(), port)).await?,
+    };
+    let _ = tcp.set_nodelay(true);
+    let Some(pins) = pins else {
+        return client_async_tls(request, tcp).await;
+    };
+
+    let connector = native_tls::TlsConnector::new().map_err(|e| WsError::Tls(e.into()))?;
+    let tls = tokio_native_tls::TlsConnector::from(connector)
+        .connect(&host, tcp)
+        .await
+        .map_err(|e| WsError::Tls(e.into()))?;
+
+    let certificate = tls
+        .get_ref()
+        .peer_certificate()
+        .ok()
+        .flatten()
+        .and_then(|certificate| certificate.to_der().ok())
+        .unwrap_or_default();
+    pins.check(&host, &certificate).map_err(|e| {
+        WsError::Io(std::io::Error::new(std::io::ErrorKind::PermissionDenied, e.to_string()))
+    })?;
+
+    client_async(request, MaybeTlsStream::NativeTls(tls)).await
+}
+
+/// Like `spawn_connection`, for handlers that need to write back to the
+/// socket (e.g. answering application-level pings). `make_handler` receives
+/// the connection's outbound message sender.
+pub async fn spawn_connection_with<C, H, F>(
+    config: &C,
+    subscriptions: &[String],
+    make_handler: F,
+) -> Result<tokio::task::JoinHandle<Result<()>>>
+where
+    C: ExchangeConfig,
+    H: WebSocketHandler + 'static,
+    F: FnOnce(mpsc::UnboundedSender<String>) -> H,
+{
+    let mut connection = WebSocketConnection::new(config);
+    let outbound = connection.get_message_sender().ok_or_else(|| {
+        ArbFinderError::WebSocket("Connection has no message sender".to_string())
+    })?;
+    let handler = make_handler(outbound);
+    connection.connect().await?;
+    for message in subscriptions {
+        connection.subscribe(message).await?;
+    }
+
+    let handler = Arc::new(Mutex::new(handler));
+    handler.lock().await.on_connect().await?;
+
+    Ok(tokio::spawn(async move { connection.run_with_handler(handler).await }))
+}
+
+/// Adapts the receiving half of a handler's channel into a boxed stream, the
+/// shape expected by `market_data_stream` and `order_update_stream`.
+pub fn channel_stream<T>(
+    rx: mpsc::UnboundedReceiver<T>,
+) -> Pin<Box<dyn Stream<Item = Result<T>> + Send>>
+where
+    T: Send + 'static,
+{
+    Box::pin(futures::stream::unfold(rx, |mut rx| async move {
+        rx.recv().await.map(|item| (Ok(item), rx))
+    }))
+}
+
 #[derive(Debug)]
 pub struct WebSocketManager {
     connections: std::collections::HashMap<String, WebSocketConnection>,
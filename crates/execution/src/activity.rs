@@ -0,0 +1,405 @@
+//! Account activity this instance did not originate.
+//!
+//! Every order the engine places is recorded here. An order update from a
+//! venue about any other order, or a balance that moved further than the
+//! engine's own fills explain, means someone else is trading the account:
+//! a leaked key, or another bot sharing it.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use arbfinder_core::prelude::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActivityConfig {
+    /// How often balances are fetched and checked against fills.
+    pub balance_interval: Duration,
+    /// How long an update for an unknown order waits for the order to be
+    /// recorded, as a venue may report an order before acknowledging it.
+    pub unknown_order_grace: Duration,
+    /// Fraction of a fill's value its fee may take, on top of the fill.
+    pub fee_tolerance: Decimal,
+    /// Fraction of a balance it may drift by between checks unexplained,
+    /// e.g. through rounding or interest.
+    pub dust_tolerance: Decimal,
+    /// How long finished orders are kept after their last update. Longer
+    /// than a reconciliation period, which compares against them.
+    pub order_retention: Duration,
+}
+
+impl Default for ActivityConfig {
+    fn default() -> Self {
+        Self {
+            balance_interval: Duration::from_secs(60),
+            unknown_order_grace: Duration::from_secs(5),
+            fee_tolerance: Decimal::new(1, 2),
+            dust_tolerance: Decimal::new(1, 4),
+            order_retention: Duration::from_secs(2 * 24 * 60 * 60),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub enum AccountAnomaly {
+    /// An update for an order this instance did not place.
+    UnknownOrder {
+        venue: VenueId,
+        venue_order_id: Option<String>,
+        status: OrderStatus,
+    },
+    /// A balance that changed by more than the instance's fills account for.
+    UnexpectedBalanceChange {
+        venue: VenueId,
+        asset: String,
+        previous: Decimal,
+        current: Decimal,
+        /// Change the instance's fills since the last check explain.
+        expected_change: Decimal,
+    },
+}
+
+impl AccountAnomaly {
+    pub fn venue(&self) -> &VenueId {
+        match self {
+            AccountAnomaly::UnknownOrder { venue, .. } => venue,
+            AccountAnomaly::UnexpectedBalanceChange { venue, .. } => venue,
+        }
+    }
+}
+
+impl fmt::Display for AccountAnomaly {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountAnomaly::UnknownOrder { venue, venue_order_id, status } => write!(
+                f,
+                "{} order {} ({}) was not placed by this instance",
+                venue,
+                venue_order_id.as_deref().unwrap_or("without an id"),
+                status
+            ),
+            AccountAnomaly::UnexpectedBalanceChange { venue, asset, previous, current, expected_change } => write!(
+                f,
+                "{} {} balance went from {} to {}; fills explain a change of {}",
+                venue, asset, previous, current, expected_change
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ActivityState {
+    orders: HashMap<OrderId, Order>,
+    /// Our orders by venue and venue order id.
+    venue_orders: HashMap<(VenueId, String), OrderId>,
+    /// Total balances per venue and asset as of the last check.
+    balances: HashMap<VenueId, HashMap<String, Decimal>>,
+    /// Change per venue and asset our fills made since the last check.
+    expected: HashMap<VenueId, HashMap<String, Decimal>>,
+    /// Updates for orders not recorded yet, with when they arrived.
+    unmatched: Vec<(VenueId, OrderUpdate, DateTime<Utc>)>,
+}
+
+/// Orders and balances this instance knows about, shared by every clone.
+#[derive(Debug, Clone, Default)]
+pub struct AccountActivity {
+    config: ActivityConfig,
+    state: Arc<parking_lot::Mutex<ActivityState>>,
+}
+
+impl AccountActivity {
+    pub fn new(config: ActivityConfig) -> Self {
+        Self {
+            config,
+            state: Arc::default(),
+        }
+    }
+
+    pub fn config(&self) -> &ActivityConfig {
+        &self.config
+    }
+
+    /// Notes an order as placed by this instance, applying any updates for
+    /// it that arrived first.
+    pub fn record_order(&self, order: &Order) {
+        let mut state = self.state.lock();
+        if let Some(venue_order_id) = &order.venue_order_id {
+            state
+                .venue_orders
+                .insert((order.venue_id.clone(), venue_order_id.clone()), order.id.clone());
+        }
+        state.orders.insert(order.id.clone(), order.clone());
+
+        let (matched, unmatched) = std::mem::take(&mut state.unmatched)
+            .into_iter()
+            .partition(|(venue, update, _)| Self::known_order(&state, venue, update).is_some());
+        state.unmatched = unmatched;
+        for (venue, update, _) in matched {
+            Self::apply_update(&mut state, &venue, &update);
+        }
+    }
+
+    /// One of our orders, as last updated.
+    pub fn order(&self, order_id: &OrderId) -> Option<Order> {
+        self.state.lock().orders.get(order_id).cloned()
+    }
+
+    /// Our orders on `venue`, as last updated.
+    pub fn orders(&self, venue: &VenueId) -> Vec<Order> {
+        self.state
+            .lock()
+            .orders
+            .values()
+            .filter(|order| order.venue_id == *venue)
+            .cloned()
+            .collect()
+    }
+
+    /// Which of our orders `update` is about, by our id or the venue's.
+    fn known_order(state: &ActivityState, venue: &VenueId, update: &OrderUpdate) -> Option<OrderId> {
+        if state.orders.contains_key(&update.order_id) {
+            return Some(update.order_id.clone());
+        }
+        update
+            .venue_order_id
+            .as_ref()
+            .and_then(|venue_order_id| state.venue_orders.get(&(venue.clone(), venue_order_id.clone())))
+            .cloned()
+    }
+
+    /// Takes in an order update from `venue`. A new fill of one of our
+    /// orders counts toward the balance changes expected; an update for any
+    /// other order is held until [`Self::unknown_orders`] reports it.
+    pub fn on_order_update(&self, venue: &VenueId, update: &OrderUpdate, now: DateTime<Utc>) {
+        let mut state = self.state.lock();
+        if Self::known_order(&state, venue, update).is_some() {
+            Self::apply_update(&mut state, venue, update);
+        } else {
+            state.unmatched.push((venue.clone(), update.clone(), now));
+        }
+    }
+
+    fn apply_update(state: &mut ActivityState, venue: &VenueId, update: &OrderUpdate) {
+        let Some(order) = Self::known_order(state, venue, update).and_then(|id| state.orders.get_mut(&id)) else {
+            return;
+        };
+
+        let filled = (update.filled_quantity - order.filled_quantity).max(Decimal::ZERO);
+        let price = update.average_fill_price.or(order.price).unwrap_or_default();
+        order.filled_quantity = order.filled_quantity.max(update.filled_quantity);
+        order.remaining_quantity = update.remaining_quantity;
+        order.average_fill_price = update.average_fill_price.or(order.average_fill_price);
+        order.status = update.status;
+        order.updated_at = order.updated_at.max(update.timestamp);
+        if filled.is_zero() {
+            return;
+        }
+
+        let (base, quote) = match order.side {
+            OrderSide::Buy => (filled, -filled * price),
+            OrderSide::Sell => (-filled, filled * price),
+        };
+        let (base_asset, quote_asset) = (order.symbol.base().to_string(), order.symbol.quote().to_string());
+        let expected = state.expected.entry(venue.clone()).or_default();
+        *expected.entry(base_asset).or_default() += base;
+        *expected.entry(quote_asset).or_default() += quote;
+    }
+
+    /// Forgets finished orders last updated `order_retention` before `now`,
+    /// returning how many. A late update for one is reported as unknown.
+    pub fn prune_orders(&self, now: DateTime<Utc>) -> usize {
+        let retention = chrono::Duration::from_std(self.config.order_retention).unwrap_or(chrono::Duration::MAX);
+        let mut state = self.state.lock();
+        let before = state.orders.len();
+        state.orders.retain(|_, order| order.is_active() || now - order.updated_at < retention);
+        let ActivityState { orders, venue_orders, .. } = &mut *state;
+        venue_orders.retain(|_, order_id| orders.contains_key(order_id));
+        before - state.orders.len()
+    }
+
+    /// Updates for orders still not recorded a grace period after they
+    /// arrived, one anomaly per order.
+    pub fn unknown_orders(&self, now: DateTime<Utc>) -> Vec<AccountAnomaly> {
+        let grace = chrono::Duration::from_std(self.config.unknown_order_grace).unwrap_or_default();
+        let mut state = self.state.lock();
+        let (overdue, waiting): (Vec<_>, Vec<_>) = std::mem::take(&mut state.unmatched)
+            .into_iter()
+            .partition(|(_, _, arrived)| now - *arrived >= grace);
+        state.unmatched = waiting;
+
+        // Report each order once, at its latest status
+        let mut anomalies: Vec<AccountAnomaly> = Vec::new();
+        let mut reported: HashMap<(VenueId, Option<String>), usize> = HashMap::new();
+        for (venue, update, _) in overdue {
+            let key = (venue.clone(), update.venue_order_id.clone());
+            let anomaly = AccountAnomaly::UnknownOrder {
+                venue,
+                venue_order_id: update.venue_order_id,
+                status: update.status,
+            };
+            match reported.get(&key) {
+                Some(&index) => anomalies[index] = anomaly,
+                None => {
+                    reported.insert(key, anomalies.len());
+                    anomalies.push(anomaly);
+                }
+            }
+        }
+        anomalies
+    }
+
+    /// How far each asset on `venue` may move from fills of our orders
+    /// still working, which can land between an update and a balance
+    /// check. `None` for an asset a working order has no price to bound.
+    fn working_exposure(state: &ActivityState, venue: &VenueId) -> HashMap<String, Option<Decimal>> {
+        let mut exposure: HashMap<String, Option<Decimal>> = HashMap::new();
+        for order in state.orders.values().filter(|order| order.venue_id == *venue && order.is_active()) {
+            let remaining = (order.quantity - order.filled_quantity).max(Decimal::ZERO);
+            let base = exposure.entry(order.symbol.base().to_string()).or_insert(Some(Decimal::ZERO));
+            *base = base.map(|base| base + remaining);
+            let price = order.price.or(order.average_fill_price);
+            let quote = exposure.entry(order.symbol.quote().to_string()).or_insert(Some(Decimal::ZERO));
+            *quote = quote.zip(price).map(|(quote, price)| quote + remaining * price);
+        }
+        exposure
+    }
+
+    /// Checks `venue`'s balances against the last ones seen, allowing for
+    /// our fills since then and orders still working. Assets missing from
+    /// `balances` are taken as unchanged; the first balances of a venue
+    /// are only remembered.
+    pub fn on_balances(&self, venue: &VenueId, balances: &[Balance]) -> Vec<AccountAnomaly> {
+        let mut state = self.state.lock();
+        let exposure = Self::working_exposure(&state, venue);
+        let expected = state.expected.remove(venue).unwrap_or_default();
+        let first = !state.balances.contains_key(venue);
+        let previous_balances = state.balances.entry(venue.clone()).or_default();
+
+        let mut anomalies = Vec::new();
+        for balance in balances {
+            let previous = previous_balances.insert(balance.asset.clone(), balance.total);
+            if first {
+                continue;
+            }
+            let previous = previous.unwrap_or_default();
+            let expected_change = expected.get(&balance.asset).copied().unwrap_or_default();
+            let Some(working) = exposure.get(&balance.asset).copied().unwrap_or(Some(Decimal::ZERO)) else {
+                continue;
+            };
+
+            let allowed = expected_change.abs() * self.config.fee_tolerance
+                + previous.abs() * self.config.dust_tolerance
+                + working;
+            if (balance.total - previous - expected_change).abs() > allowed {
+                anomalies.push(AccountAnomaly::UnexpectedBalanceChange {
+                    venue: venue.clone(),
+                    asset: balance.asset.clone(),
+                    previous,
+                    current: balance.total,
+                    expected_change,
+                });
+            }
+        }
+        anomalies
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn update(order_id: OrderId, venue_order_id: &str, status: OrderStatus, filled: Decimal) -> OrderUpdate {
+        OrderUpdate {
+            order_id,
+            venue_order_id: Some(venue_order_id.to_string()),
+            status,
+            filled_quantity: filled,
+            remaining_quantity: dec!(1) - filled,
+            average_fill_price: Some(dec!(50000)).filter(|_| !filled.is_zero()),
+            timestamp: Utc::now(),
+            reason: None,
+        }
+    }
+
+    #[test]
+    fn test_unknown_orders_and_balance_changes() {
+        let activity = AccountActivity::new(ActivityConfig::default());
+        let venue = VenueId::Binance;
+        activity.on_balances(&venue, &[
+            Balance::new("BTC".to_string(), dec!(1), dec!(1), dec!(0)),
+            Balance::new("USDT".to_string(), dec!(100000), dec!(100000), dec!(0)),
+        ]);
+
+        let mut order = Order::new_limit(venue.clone(), Symbol::new("BTC", "USDT"), OrderSide::Buy, dec!(1), dec!(50000));
+        order.venue_order_id = Some("123".to_string());
+
+        // Updates for our order, by either id, are fine, even one that
+        // arrives before the order is recorded
+        let now = Utc::now();
+        activity.on_order_update(&venue, &update(OrderId::new(), "123", OrderStatus::Open, dec!(0)), now);
+        activity.record_order(&order);
+        activity.on_order_update(&venue, &update(order.id.clone(), "123", OrderStatus::Filled, dec!(1)), now);
+
+        activity.on_order_update(&venue, &update(OrderId::new(), "999", OrderStatus::Open, dec!(0)), now);
+        activity.on_order_update(&venue, &update(OrderId::new(), "999", OrderStatus::Canceled, dec!(0)), now);
+        assert!(activity.unknown_orders(now).is_empty());
+        assert_eq!(
+            activity.unknown_orders(now + chrono::Duration::seconds(5)),
+            vec![AccountAnomaly::UnknownOrder {
+                venue: venue.clone(),
+                venue_order_id: Some("999".to_string()),
+                status: OrderStatus::Canceled,
+            }]
+        );
+
+        // The fill, less a 0.1% fee, explains the new balances
+        assert!(activity.on_balances(&venue, &[
+            Balance::new("BTC".to_string(), dec!(1.999), dec!(1.999), dec!(0)),
+            Balance::new("USDT".to_string(), dec!(50000), dec!(50000), dec!(0)),
+        ]).is_empty());
+
+        // Nothing explains half the USDT going
+        let anomalies = activity.on_balances(&venue, &[
+            Balance::new("USDT".to_string(), dec!(25000), dec!(25000), dec!(0)),
+        ]);
+        assert_eq!(
+            anomalies,
+            vec![AccountAnomaly::UnexpectedBalanceChange {
+                venue: venue.clone(),
+                asset: "USDT".to_string(),
+                previous: dec!(50000),
+                current: dec!(25000),
+                expected_change: dec!(0),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_prune_orders() {
+        let activity = AccountActivity::new(ActivityConfig::default());
+        let venue = VenueId::Binance;
+        let now = Utc::now();
+        let mut filled = Order::new_limit(venue.clone(), Symbol::new("BTC", "USDT"), OrderSide::Buy, dec!(1), dec!(50000));
+        filled.venue_order_id = Some("1".to_string());
+        let mut working = filled.clone();
+        working.id = OrderId::new();
+        working.venue_order_id = Some("2".to_string());
+        activity.record_order(&filled);
+        activity.record_order(&working);
+        activity.on_order_update(&venue, &update(filled.id.clone(), "1", OrderStatus::Filled, dec!(1)), now);
+
+        assert_eq!(activity.prune_orders(now + chrono::Duration::days(1)), 0);
+        assert_eq!(activity.prune_orders(now + chrono::Duration::days(3)), 1);
+        assert!(activity.order(&filled.id).is_none());
+        assert!(activity.order(&working.id).is_some());
+
+        // A late update for the pruned order is no longer ours
+        activity.on_order_update(&venue, &update(OrderId::new(), "1", OrderStatus::Filled, dec!(1)), now);
+        assert_eq!(activity.unknown_orders(now + chrono::Duration::seconds(5)).len(), 1);
+    }
+}
@@ -0,0 +1,111 @@
+//! Admin HTTP routes backed by the execution engine. They are mounted on the
+//! monitoring server next to `/metrics` and `/health`.
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{get, post, put},
+    Json, Router,
+};
+use rust_decimal::Decimal;
+use serde::Deserialize;
+
+use arbfinder_core::prelude::*;
+
+use crate::cooldown::CooldownList;
+use crate::simulation::{SimulationRequest, TradeSimulator};
+
+/// Body of `POST /simulate`, e.g.
+/// `{"venue": "binance", "symbol": "BTC/USDT", "side": "buy", "size": 0.5}`.
+#[derive(Debug, Deserialize)]
+pub struct SimulateBody {
+    pub venue: String,
+    pub symbol: String,
+    pub side: String,
+    pub size: Decimal,
+}
+
+impl TryFrom<SimulateBody> for SimulationRequest {
+    type Error = ArbFinderError;
+
+    fn try_from(body: SimulateBody) -> Result<Self> {
+        let symbol = Symbol::from_pair(&body.symbol).ok_or_else(|| {
+            ArbFinderError::InvalidData(format!("Expected BASE/QUOTE symbol, got {}", body.symbol))
+        })?;
+        let side = match body.side.to_lowercase().as_str() {
+            "buy" => OrderSide::Buy,
+            "sell" => OrderSide::Sell,
+            other => return Err(ArbFinderError::InvalidData(format!("Unknown side: {}", other))),
+        };
+        Ok(SimulationRequest::new(VenueId::from(body.venue.as_str()), symbol, side, body.size))
+    }
+}
+
+pub fn admin_routes(simulator: TradeSimulator, flags: FeatureFlags, cooldowns: CooldownList) -> Router {
+    let flag_routes = Router::new()
+        .route("/flags", get(list_flags_handler))
+        .route("/flags/:name", put(set_flag_handler).delete(clear_flag_handler))
+        .with_state(flags);
+    let cooldown_routes = Router::new()
+        .route("/cooldowns", get(list_cooldowns_handler))
+        .with_state(cooldowns);
+
+    Router::new()
+        .route("/simulate", post(simulate_handler))
+        .with_state(simulator)
+        .merge(flag_routes)
+        .merge(cooldown_routes)
+}
+
+/// Routes on cooldown after failed executions, ending soonest first.
+async fn list_cooldowns_handler(State(cooldowns): State<CooldownList>) -> impl IntoResponse {
+    Json(serde_json::json!(cooldowns.active(chrono::Utc::now())))
+}
+
+async fn list_flags_handler(State(flags): State<FeatureFlags>) -> impl IntoResponse {
+    Json(serde_json::json!(flags.statuses()))
+}
+
+/// Overrides a flag's configured rule until the override is deleted or the
+/// process restarts, e.g. `{"enabled": false}` to stop a rollout.
+async fn set_flag_handler(
+    State(flags): State<FeatureFlags>,
+    Path(name): Path<String>,
+    Json(rule): Json<FlagRule>,
+) -> impl IntoResponse {
+    flags.set_override(&name, rule);
+    Json(serde_json::json!(flags.rule(&name)))
+}
+
+async fn clear_flag_handler(State(flags): State<FeatureFlags>, Path(name): Path<String>) -> impl IntoResponse {
+    if flags.clear_override(&name) {
+        (StatusCode::OK, Json(serde_json::json!(flags.rule(&name))))
+    } else {
+        (StatusCode::NOT_FOUND, Json(serde_json::json!({ "error": format!("No override for {}", name) })))
+    }
+}
+
+async fn simulate_handler(
+    State(simulator): State<TradeSimulator>,
+    Json(body): Json<SimulateBody>,
+) -> impl IntoResponse {
+    let result = match SimulationRequest::try_from(body) {
+        Ok(request) => simulator.simulate(&request).await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(result) => (StatusCode::OK, Json(serde_json::json!(result))),
+        Err(e) => {
+            let status = match e {
+                ArbFinderError::SymbolNotFound(_) => StatusCode::NOT_FOUND,
+                ArbFinderError::InvalidData(_)
+                | ArbFinderError::InvalidOrder(_)
+                | ArbFinderError::OrderBook(_) => StatusCode::BAD_REQUEST,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            };
+            (status, Json(serde_json::json!({ "error": e.to_string() })))
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Which currency arbitrage profit is kept in.
+//!
+//! Buying and selling the same quantity leaves the base balance unchanged
+//! and banks the spread in quote currency. Selling less than was bought, so
+//! the sell proceeds just cover the buy cost, banks it in base currency
+//! instead. A ratio splits the difference.
+
+use rust_decimal::Decimal;
+
+use arbfinder_core::prelude::*;
+use arbfinder_core::utils::round_to_lot_size;
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum ProfitAllocation {
+    /// Equal legs; profit accumulates in quote currency.
+    #[default]
+    Quote,
+    /// Sell only enough to cover the buy; profit accumulates in base.
+    Base,
+    /// Keep this fraction of the profit in base and the rest in quote.
+    Ratio(Decimal),
+}
+
+impl ProfitAllocation {
+    /// Fraction of the profit kept in base currency.
+    pub fn base_share(&self) -> Decimal {
+        match self {
+            ProfitAllocation::Quote => Decimal::ZERO,
+            ProfitAllocation::Base => Decimal::ONE,
+            ProfitAllocation::Ratio(share) => (*share).clamp(Decimal::ZERO, Decimal::ONE),
+        }
+    }
+
+    /// Sizes both legs for buying `quantity`. `buy_cost` and
+    /// `sell_proceeds` are per-unit prices after fees. The buy leg is
+    /// rounded down to `lot_size`; a smaller sell leg is rounded up, so the
+    /// quote balance never ends below where it started on a profitable
+    /// trade, and is never larger than the buy.
+    pub fn leg_sizes(
+        &self,
+        quantity: Decimal,
+        buy_cost: Decimal,
+        sell_proceeds: Decimal,
+        lot_size: Decimal,
+    ) -> LegSizes {
+        let buy = round_to_lot_size(quantity, lot_size);
+        let share = self.base_share();
+
+        let sell = if share.is_zero() || sell_proceeds.is_zero() {
+            buy
+        } else {
+            let covering = buy * buy_cost / sell_proceeds;
+            let sell = buy - share * (buy - covering);
+            let sell = if lot_size.is_zero() {
+                sell
+            } else {
+                (sell / lot_size).ceil() * lot_size
+            };
+            sell.min(buy)
+        };
+
+        LegSizes {
+            buy,
+            sell,
+            quote_profit: sell * sell_proceeds - buy * buy_cost,
+            base_profit: buy - sell,
+        }
+    }
+}
+
+impl std::str::FromStr for ProfitAllocation {
+    type Err = ArbFinderError;
+
+    /// Accepts `quote`, `base`, or `ratio:<base share>`, e.g. `ratio:0.5`.
+    fn from_str(s: &str) -> Result<Self> {
+        let s = s.to_lowercase();
+        match s.split_once(':') {
+            Some(("ratio", share)) => {
+                let share: Decimal = share.trim().parse().map_err(|_| {
+                    ArbFinderError::InvalidData(format!("Invalid profit allocation ratio: {}", share))
+                })?;
+                if share < Decimal::ZERO || share > Decimal::ONE {
+                    return Err(ArbFinderError::InvalidData(format!(
+                        "Profit allocation ratio must be between 0 and 1, got {}",
+                        share
+                    )));
+                }
+                Ok(ProfitAllocation::Ratio(share))
+            }
+            _ => match s.as_str() {
+                "quote" => Ok(ProfitAllocation::Quote),
+                "base" => Ok(ProfitAllocation::Base),
+                other => Err(ArbFinderError::InvalidData(format!("Unknown profit allocation: {}", other))),
+            },
+        }
+    }
+}
+
+/// Quantities for the two legs of an arbitrage and what they are expected
+/// to leave behind.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LegSizes {
+    pub buy: Decimal,
+    pub sell: Decimal,
+    /// Change in quote balance after fees.
+    pub quote_profit: Decimal,
+    /// Change in base balance.
+    pub base_profit: Decimal,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_leg_sizes_by_allocation() {
+        // Buy at 100, sell at 101
+        let quote = ProfitAllocation::Quote.leg_sizes(dec!(1.05), dec!(100), dec!(101), dec!(0.1));
+        assert_eq!((quote.buy, quote.sell), (dec!(1.0), dec!(1.0)));
+        assert_eq!(quote.quote_profit, dec!(1));
+        assert_eq!(quote.base_profit, dec!(0));
+
+        // 100 / 101 = 0.990..., rounded up to 0.991
+        let base = ProfitAllocation::Base.leg_sizes(dec!(1), dec!(100), dec!(101), dec!(0.001));
+        assert_eq!(base.sell, dec!(0.991));
+        assert_eq!(base.base_profit, dec!(0.009));
+        assert!(base.quote_profit >= Decimal::ZERO);
+
+        let half = ProfitAllocation::Ratio(dec!(0.5)).leg_sizes(dec!(1), dec!(100), dec!(101), dec!(0.001));
+        assert_eq!(half.sell, dec!(0.996));
+        assert!(half.quote_profit > base.quote_profit && half.quote_profit < quote.quote_profit);
+
+        // A losing spread never sells more than was bought
+        let losing = ProfitAllocation::Base.leg_sizes(dec!(1), dec!(101), dec!(100), dec!(0.001));
+        assert_eq!(losing.sell, dec!(1));
+    }
+
+    #[test]
+    fn test_parse_allocation() {
+        assert_eq!("Base".parse::<ProfitAllocation>().unwrap(), ProfitAllocation::Base);
+        assert_eq!("quote".parse::<ProfitAllocation>().unwrap(), ProfitAllocation::Quote);
+        assert_eq!("ratio:0.25".parse::<ProfitAllocation>().unwrap(), ProfitAllocation::Ratio(dec!(0.25)));
+        assert!("ratio:2".parse::<ProfitAllocation>().is_err());
+        assert!("stack".parse::<ProfitAllocation>().is_err());
+    }
+}
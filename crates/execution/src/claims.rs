@@ -0,0 +1,212 @@
+//! Opportunity claims shared between executor processes.
+//!
+//! A sharded deployment runs several executors on the same stream of
+//! opportunities, and each opportunity must be acted on by exactly one of
+//! them. Before placing an opportunity's orders an executor claims its id in
+//! a shared store that only sets a key if it is absent: the first claim wins
+//! and every other executor skips the opportunity. Claims expire after a
+//! while, well past the opportunity's own expiry, so the store stays small.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+
+use arbfinder_core::prelude::*;
+use arbfinder_core::types::ArbitrageOpportunity;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClaimsConfig {
+    pub nats_url: String,
+    /// JetStream key-value bucket holding the claims.
+    pub bucket: String,
+    /// How long a claim is kept.
+    pub ttl: Duration,
+    /// Names this executor in its claims; unique per process.
+    pub executor_id: String,
+}
+
+impl Default for ClaimsConfig {
+    fn default() -> Self {
+        Self {
+            nats_url: "nats://localhost:4222".to_string(),
+            bucket: "arbfinder_claims".to_string(),
+            ttl: Duration::from_secs(300),
+            executor_id: format!("executor-{}", std::process::id()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ClaimOutcome {
+    Won,
+    /// Another executor holds the claim.
+    Lost { owner: String },
+}
+
+/// Shared store of claims, setting a key only if it is absent.
+#[async_trait]
+pub trait ClaimStore: Send + Sync {
+    /// Claims `key` for `owner`. Claiming a key `owner` already holds wins
+    /// again, so a retry after a lost reply is safe.
+    async fn claim(&self, key: &str, owner: &str) -> Result<ClaimOutcome>;
+}
+
+/// Claims held in memory, for a single process and for tests.
+#[derive(Debug)]
+pub struct LocalClaimStore {
+    ttl: Duration,
+    claims: parking_lot::Mutex<HashMap<String, (String, Instant)>>,
+}
+
+impl LocalClaimStore {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            claims: parking_lot::Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl ClaimStore for LocalClaimStore {
+    async fn claim(&self, key: &str, owner: &str) -> Result<ClaimOutcome> {
+        let now = Instant::now();
+        let mut claims = self.claims.lock();
+        claims.retain(|_, (_, claimed_at)| now.duration_since(*claimed_at) < self.ttl);
+        let (holder, _) = claims
+            .entry(key.to_string())
+            .or_insert_with(|| (owner.to_string(), now));
+        Ok(outcome(holder, owner))
+    }
+}
+
+fn outcome(holder: &str, owner: &str) -> ClaimOutcome {
+    if holder == owner {
+        ClaimOutcome::Won
+    } else {
+        ClaimOutcome::Lost { owner: holder.to_string() }
+    }
+}
+
+/// Claims in a NATS JetStream key-value bucket whose entries expire after
+/// the claim TTL.
+pub struct NatsClaimStore {
+    store: async_nats::jetstream::kv::Store,
+}
+
+impl NatsClaimStore {
+    /// Connects to `config.nats_url`, creating the claims bucket if it does
+    /// not exist yet.
+    pub async fn connect(config: &ClaimsConfig) -> Result<Self> {
+        let client = async_nats::connect(config.nats_url.as_str())
+            .await
+            .map_err(|e| ArbFinderError::Internal(format!("Failed to connect to NATS at {}: {}", config.nats_url, e)))?;
+        let jetstream = async_nats::jetstream::new(client);
+        let store = match jetstream.get_key_value(config.bucket.as_str()).await {
+            Ok(store) => store,
+            Err(_) => jetstream
+                .create_key_value(async_nats::jetstream::kv::Config {
+                    bucket: config.bucket.clone(),
+                    description: "Opportunities claimed by executors".to_string(),
+                    history: 1,
+                    max_age: config.ttl,
+                    ..Default::default()
+                })
+                .await
+                .map_err(|e| {
+                    ArbFinderError::Internal(format!("Failed to create claims bucket {}: {}", config.bucket, e))
+                })?,
+        };
+        Ok(Self { store })
+    }
+}
+
+#[async_trait]
+impl ClaimStore for NatsClaimStore {
+    async fn claim(&self, key: &str, owner: &str) -> Result<ClaimOutcome> {
+        // Revision 0 only succeeds while the key has never been written
+        match self.store.update(key, owner.to_string().into(), 0).await {
+            Ok(_) => Ok(ClaimOutcome::Won),
+            Err(update_error) => match self.store.get(key).await {
+                Ok(Some(holder)) => Ok(outcome(&String::from_utf8_lossy(&holder), owner)),
+                Ok(None) => Err(ArbFinderError::Internal(format!("Failed to claim {}: {}", key, update_error))),
+                Err(e) => Err(ArbFinderError::Internal(format!("Failed to read claim on {}: {}", key, e))),
+            },
+        }
+    }
+}
+
+/// This executor's claims on opportunities. Cloning shares the store.
+#[derive(Clone)]
+pub struct OpportunityClaims {
+    store: Arc<dyn ClaimStore>,
+    owner: String,
+}
+
+impl OpportunityClaims {
+    pub fn new(store: Arc<dyn ClaimStore>, owner: impl Into<String>) -> Self {
+        Self {
+            store,
+            owner: owner.into(),
+        }
+    }
+
+    pub fn owner(&self) -> &str {
+        &self.owner
+    }
+
+    pub async fn claim(&self, opportunity: &ArbitrageOpportunity) -> Result<ClaimOutcome> {
+        self.store.claim(&claim_key(&opportunity.id), &self.owner).await
+    }
+}
+
+/// `id` as a key-value key, with anything but letters, digits, `-` and `_`
+/// replaced.
+fn claim_key(id: &str) -> String {
+    id.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arbfinder_core::types::ArbitrageStrategy;
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn test_one_executor_wins() {
+        let store: Arc<dyn ClaimStore> = Arc::new(LocalClaimStore::new(Duration::from_secs(60)));
+        let a = OpportunityClaims::new(Arc::clone(&store), "executor-a");
+        let b = OpportunityClaims::new(Arc::clone(&store), "executor-b");
+        let opportunity = ArbitrageOpportunity::new(
+            Symbol::new("BTC", "USDT"),
+            VenueId::Binance,
+            VenueId::Coinbase,
+            Decimal::from(50000),
+            Decimal::from(50100),
+            Decimal::ONE,
+            ArbitrageStrategy::CrossExchange,
+        );
+
+        assert_eq!(a.claim(&opportunity).await.unwrap(), ClaimOutcome::Won);
+        assert_eq!(
+            b.claim(&opportunity).await.unwrap(),
+            ClaimOutcome::Lost { owner: "executor-a".to_string() }
+        );
+        // Retrying a claim already held is safe
+        assert_eq!(a.claim(&opportunity).await.unwrap(), ClaimOutcome::Won);
+
+        assert_eq!(claim_key("opp:1.2/x"), "opp_1_2_x");
+    }
+
+    #[tokio::test]
+    async fn test_claims_expire() {
+        let store = LocalClaimStore::new(Duration::from_millis(10));
+        assert_eq!(store.claim("opp", "a").await.unwrap(), ClaimOutcome::Won);
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert_eq!(store.claim("opp", "b").await.unwrap(), ClaimOutcome::Won);
+    }
+}
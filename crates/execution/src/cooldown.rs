@@ -0,0 +1,199 @@
+//! Cooldowns on arbitrage routes whose executions failed.
+//!
+//! A route is a symbol bought on one venue and sold on another. Each failure
+//! on it (a rejected leg, one leg placed without the other, fills slipping
+//! past the quoted prices) keeps it from being traded for twice as long as
+//! the last, up to a maximum. A successful execution clears the route, as
+//! does a quiet spell of the maximum cooldown after the last one ends.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use arbfinder_core::prelude::*;
+use arbfinder_core::types::ArbitrageOpportunity;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CooldownConfig {
+    /// Cooldown after a route's first failure.
+    pub base: Duration,
+    /// Longest cooldown, however many failures in a row.
+    pub max: Duration,
+}
+
+impl Default for CooldownConfig {
+    fn default() -> Self {
+        Self {
+            base: Duration::from_secs(30),
+            max: Duration::from_secs(30 * 60),
+        }
+    }
+}
+
+impl CooldownConfig {
+    /// Cooldown after the `failures`th failure in a row.
+    pub fn backoff(&self, failures: u32) -> Duration {
+        let factor = 2u32.saturating_pow(failures.saturating_sub(1));
+        self.base.saturating_mul(factor).min(self.max)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureKind {
+    /// A venue, or the risk checks, refused a leg.
+    Rejected,
+    /// One leg was placed and the other was not.
+    LegImbalance,
+    /// The legs filled too far from the opportunity's prices.
+    Slippage,
+}
+
+impl fmt::Display for FailureKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FailureKind::Rejected => write!(f, "rejection"),
+            FailureKind::LegImbalance => write!(f, "leg imbalance"),
+            FailureKind::Slippage => write!(f, "slippage"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct CooldownRoute {
+    pub symbol: Symbol,
+    pub buy_venue: VenueId,
+    pub sell_venue: VenueId,
+}
+
+impl CooldownRoute {
+    pub fn new(symbol: Symbol, buy_venue: VenueId, sell_venue: VenueId) -> Self {
+        Self { symbol, buy_venue, sell_venue }
+    }
+
+    pub fn of(opportunity: &ArbitrageOpportunity) -> Self {
+        Self::new(
+            opportunity.symbol.clone(),
+            opportunity.buy_venue.clone(),
+            opportunity.sell_venue.clone(),
+        )
+    }
+}
+
+impl fmt::Display for CooldownRoute {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} -> {}", self.symbol, self.buy_venue, self.sell_venue)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Cooldown {
+    pub route: CooldownRoute,
+    /// Failures in a row, which set the cooldown's length.
+    pub failures: u32,
+    pub last_failure: FailureKind,
+    pub until: DateTime<Utc>,
+}
+
+/// Routes on cooldown, shared by every clone.
+#[derive(Debug, Clone, Default)]
+pub struct CooldownList {
+    config: CooldownConfig,
+    entries: Arc<parking_lot::RwLock<HashMap<CooldownRoute, Cooldown>>>,
+}
+
+fn chrono_duration(duration: Duration) -> chrono::Duration {
+    chrono::Duration::milliseconds(duration.as_millis().min(i64::MAX as u128) as i64)
+}
+
+impl CooldownList {
+    pub fn new(config: CooldownConfig) -> Self {
+        Self {
+            config,
+            entries: Arc::default(),
+        }
+    }
+
+    pub fn config(&self) -> &CooldownConfig {
+        &self.config
+    }
+
+    /// Puts `route` on cooldown, for longer than last time if its previous
+    /// cooldown ended less than the maximum cooldown ago.
+    pub fn record_failure(&self, route: CooldownRoute, kind: FailureKind, now: DateTime<Utc>) -> Cooldown {
+        let mut entries = self.entries.write();
+        let failures = match entries.get(&route) {
+            Some(previous) if now - previous.until <= chrono_duration(self.config.max) => previous.failures + 1,
+            _ => 1,
+        };
+        let cooldown = Cooldown {
+            route: route.clone(),
+            failures,
+            last_failure: kind,
+            until: now + chrono_duration(self.config.backoff(failures)),
+        };
+        entries.insert(route, cooldown.clone());
+        cooldown
+    }
+
+    pub fn record_success(&self, route: &CooldownRoute) {
+        self.entries.write().remove(route);
+    }
+
+    /// The cooldown `route` is on at `now`, if any.
+    pub fn cooldown(&self, route: &CooldownRoute, now: DateTime<Utc>) -> Option<Cooldown> {
+        self.entries.read().get(route).filter(|cooldown| cooldown.until > now).cloned()
+    }
+
+    /// Routes on cooldown at `now`, ending soonest first.
+    pub fn active(&self, now: DateTime<Utc>) -> Vec<Cooldown> {
+        let mut active: Vec<Cooldown> = self
+            .entries
+            .read()
+            .values()
+            .filter(|cooldown| cooldown.until > now)
+            .cloned()
+            .collect();
+        active.sort_by_key(|cooldown| cooldown.until);
+        active
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_and_reset() {
+        let cooldowns = CooldownList::new(CooldownConfig {
+            base: Duration::from_secs(10),
+            max: Duration::from_secs(60),
+        });
+        let route = CooldownRoute::new(Symbol::new("BTC", "USDT"), VenueId::Binance, VenueId::Kraken);
+        let start = Utc::now();
+        let secs = chrono::Duration::seconds;
+
+        let first = cooldowns.record_failure(route.clone(), FailureKind::Rejected, start);
+        assert_eq!(first.until, start + secs(10));
+        assert!(cooldowns.cooldown(&route, start + secs(9)).is_some());
+        assert!(cooldowns.cooldown(&route, start + secs(10)).is_none());
+
+        let mut now = start;
+        for expected in [20, 40, 60, 60] {
+            now += secs(50);
+            let cooldown = cooldowns.record_failure(route.clone(), FailureKind::LegImbalance, now);
+            assert_eq!(cooldown.until, now + secs(expected));
+        }
+        assert_eq!(cooldowns.active(now)[0].failures, 5);
+
+        // Long after the last cooldown ended, the route starts over
+        let later = now + secs(1000);
+        assert_eq!(cooldowns.record_failure(route.clone(), FailureKind::Slippage, later).failures, 1);
+
+        cooldowns.record_success(&route);
+        assert!(cooldowns.active(later).is_empty());
+    }
+}
@@ -0,0 +1,107 @@
+//! Rotating venue API keys without a restart. A reload rereads each
+//! venue's credentials from the secrets provider and hands those that
+//! changed to its adapter, which signs later requests and reopens its user
+//! data streams with them; market data connections stay up.
+
+use std::fmt;
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::post, Json, Router};
+use serde::Serialize;
+use tokio::sync::{mpsc, oneshot};
+
+use arbfinder_core::prelude::*;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "outcome", content = "error", rename_all = "snake_case")]
+pub enum RotationOutcome {
+    /// The adapter took new credentials and the venue accepted them.
+    Rotated,
+    /// The provider holds the credentials already in use.
+    Unchanged,
+    /// The provider has no credentials for the venue.
+    NotConfigured,
+    Failed(String),
+}
+
+/// What reloading did for one venue.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CredentialRotation {
+    pub venue: VenueId,
+    #[serde(flatten)]
+    pub outcome: RotationOutcome,
+}
+
+impl CredentialRotation {
+    pub fn new(venue: VenueId, outcome: RotationOutcome) -> Self {
+        Self { venue, outcome }
+    }
+
+    pub fn failed(&self) -> bool {
+        matches!(self.outcome, RotationOutcome::Failed(_))
+    }
+}
+
+impl fmt::Display for CredentialRotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.outcome {
+            RotationOutcome::Rotated => write!(f, "{} credentials rotated", self.venue),
+            RotationOutcome::Unchanged => write!(f, "{} credentials unchanged", self.venue),
+            RotationOutcome::NotConfigured => write!(f, "{} has no credentials configured", self.venue),
+            RotationOutcome::Failed(e) => write!(f, "{} credential reload failed: {}", self.venue, e),
+        }
+    }
+}
+
+/// A request to reload credentials, answered with what happened to each
+/// venue.
+pub type ReloadRequest = oneshot::Sender<Vec<CredentialRotation>>;
+
+/// `POST /credentials/reload`, passed on to whoever owns the engine and
+/// answered once every venue is done.
+pub fn credential_routes(requests: mpsc::UnboundedSender<ReloadRequest>) -> Router {
+    Router::new()
+        .route("/credentials/reload", post(reload_handler))
+        .with_state(requests)
+}
+
+async fn reload_handler(State(requests): State<mpsc::UnboundedSender<ReloadRequest>>) -> impl IntoResponse {
+    let (tx, rx) = oneshot::channel();
+    let rotations = match requests.send(tx) {
+        Ok(()) => rx.await.ok(),
+        Err(_) => None,
+    };
+
+    match rotations {
+        Some(rotations) => {
+            let status = if rotations.iter().any(CredentialRotation::failed) {
+                StatusCode::INTERNAL_SERVER_ERROR
+            } else {
+                StatusCode::OK
+            };
+            (status, Json(serde_json::json!(rotations)))
+        }
+        None => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(serde_json::json!({ "error": "The application is shutting down" })),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotation_serializes_flat() {
+        let rotation = CredentialRotation::new(VenueId::Binance, RotationOutcome::Failed("Invalid API-key".to_string()));
+        assert_eq!(
+            serde_json::to_value(&rotation).unwrap(),
+            serde_json::json!({ "venue": "Binance", "outcome": "failed", "error": "Invalid API-key" })
+        );
+        assert_eq!(
+            serde_json::to_value(CredentialRotation::new(VenueId::Kraken, RotationOutcome::Unchanged)).unwrap(),
+            serde_json::json!({ "venue": "Kraken", "outcome": "unchanged" })
+        );
+        assert!(rotation.failed());
+    }
+}
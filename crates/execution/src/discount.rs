@@ -0,0 +1,104 @@
+//! Balances of fee discount assets, such as BNB on Binance.
+//!
+//! A venue discounting fees paid in its own token takes them from a balance
+//! of that token instead of from the assets traded, so a discounted buy
+//! receives all of the base asset it paid for. Once the balance runs out
+//! the venue charges full fees in the traded assets again. Each check
+//! routes at the discounted rates only while the balance lasts, and buys
+//! the asset back up to its target once it falls below the floor.
+
+use std::fmt;
+use std::time::Duration;
+use rust_decimal::Decimal;
+
+use arbfinder_core::prelude::*;
+
+/// A discount asset kept on one venue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscountAsset {
+    pub venue: VenueId,
+    pub discount: FeeDiscount,
+    /// Asset the discount asset is bought with, e.g. USDT for BNB/USDT.
+    pub quote_asset: String,
+    /// Balance below which the asset is topped up.
+    pub min_balance: Decimal,
+    /// Balance a top-up brings it back to.
+    pub target_balance: Decimal,
+}
+
+impl DiscountAsset {
+    pub fn symbol(&self) -> Symbol {
+        Symbol::new(&self.discount.asset, &self.quote_asset)
+    }
+
+    /// How much to buy to bring `balance` back to the target, if it fell
+    /// below the floor.
+    pub fn top_up(&self, balance: Decimal) -> Option<Decimal> {
+        (balance < self.min_balance && self.target_balance > balance).then(|| self.target_balance - balance)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FeeDiscountConfig {
+    pub assets: Vec<DiscountAsset>,
+    pub check_interval: Duration,
+    /// Buy the discount asset when it falls below its floor; otherwise
+    /// only report it.
+    pub auto_top_up: bool,
+}
+
+impl Default for FeeDiscountConfig {
+    fn default() -> Self {
+        Self {
+            assets: Vec::new(),
+            check_interval: Duration::from_secs(300),
+            auto_top_up: true,
+        }
+    }
+}
+
+/// A discount asset's balance at a check and what was done about it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiscountBalance {
+    pub venue: VenueId,
+    pub asset: String,
+    pub balance: Decimal,
+    /// Whether the venue is routed at the discounted rates.
+    pub discounted: bool,
+    /// Quantity bought to top the balance up, if it was.
+    pub topped_up: Option<Decimal>,
+}
+
+impl fmt::Display for DiscountBalance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} on {}", self.balance, self.asset, self.venue)?;
+        if !self.discounted {
+            write!(f, ", paying full fees")?;
+        }
+        if let Some(quantity) = self.topped_up {
+            write!(f, ", bought {}", quantity)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_top_up_below_floor() {
+        let bnb = DiscountAsset {
+            venue: VenueId::Binance,
+            discount: FeeDiscount::new("BNB", dec!(0.00075), dec!(0.00075)),
+            quote_asset: "USDT".to_string(),
+            min_balance: dec!(0.5),
+            target_balance: dec!(2),
+        };
+        assert_eq!(bnb.symbol(), Symbol::new("BNB", "USDT"));
+        assert_eq!(bnb.top_up(dec!(0.6)), None);
+        assert_eq!(bnb.top_up(dec!(0.2)), Some(dec!(1.8)));
+        assert_eq!(bnb.top_up(Decimal::ZERO), Some(dec!(2)));
+    }
+}
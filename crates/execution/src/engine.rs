@@ -1,15 +1,33 @@
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 use tokio::sync::{RwLock, mpsc, Mutex};
-use tokio::time::{Duration, Instant};
 use rust_decimal::Decimal;
-use tracing::{info, warn};
+use futures::StreamExt;
+use tracing::{debug, error, info, warn};
+
+use arbfinder_core::flags;
+use arbfinder_core::utils::{generate_client_order_id, RollingStats, SharedStats};
 
 use arbfinder_core::prelude::*;
 use arbfinder_exchange::prelude::*;
+use arbfinder_orderbook::{PriceSanityFilter, VenueFlag};
 use arbfinder_strategy::prelude::*;
+use arbfinder_core::types::ArbitrageOpportunity;
 
-use crate::{ExecutionConfig, ExecutionEvent, Portfolio, RiskManager};
+use crate::{ExecutionConfig, ExecutionEvent, Portfolio, RiskManager, TimedExecutionEvent};
+use crate::activity::{AccountActivity, AccountAnomaly};
+use crate::allocation::LegSizes;
+use crate::claims::{ClaimOutcome, OpportunityClaims};
+use crate::cooldown::{CooldownList, CooldownRoute, FailureKind};
+use crate::credentials::{CredentialRotation, RotationOutcome};
+use crate::discount::DiscountBalance;
+use crate::inventory::{RebalanceAction, RebalancePlan};
+use crate::lifecycle::{PairEvent, PairLifecycle, PairState, UnwindPolicy};
+use crate::reconcile::{reconcile, ReconciliationReport};
+use crate::router::{ExecutionMode, RouteDecision, VenueQuote, VenueRouter};
+use crate::simulation::{SimulationRequest, SimulationResult, TradeSimulator};
+use crate::sizing::{AdaptiveSizer, FillQuality, PairSizing, VenuePair};
+use crate::throttle::{OrderThrottle, MANUAL_STRATEGY};
 
 pub struct ExecutionEngine {
     config: ExecutionConfig,
@@ -17,15 +35,43 @@ pub struct ExecutionEngine {
     strategies: Vec<Box<dyn Strategy>>,
     portfolio: Arc<RwLock<Portfolio>>,
     risk_manager: Arc<RiskManager>,
-    event_sender: mpsc::UnboundedSender<ExecutionEvent>,
-    event_receiver: Arc<Mutex<mpsc::UnboundedReceiver<ExecutionEvent>>>,
-    order_rate_limiter: Arc<RwLock<HashMap<String, Vec<Instant>>>>,
+    event_sender: mpsc::UnboundedSender<TimedExecutionEvent>,
+    event_receiver: Arc<Mutex<mpsc::UnboundedReceiver<TimedExecutionEvent>>>,
+    throttle: OrderThrottle,
+    router: Arc<parking_lot::RwLock<VenueRouter>>,
+    books: Arc<RwLock<HashMap<(VenueId, Symbol), OrderBook>>>,
+    sanity: Option<parking_lot::Mutex<PriceSanityFilter>>,
+    cooldowns: CooldownList,
+    activity: AccountActivity,
+    lifecycle: PairLifecycle,
+    venue_status: VenueStatusBoard,
+    metrics: OnceLock<Arc<arbfinder_monitoring::MetricsCollector>>,
+    claims: Option<OpportunityClaims>,
+    sizing: Option<AdaptiveSizer>,
+    /// Seconds from submitting each venue's orders to their
+    /// acknowledgement, over the last five minutes.
+    order_acks: HashMap<VenueId, SharedStats>,
 }
 
 impl ExecutionEngine {
     pub fn new(config: ExecutionConfig) -> Self {
         let (event_sender, event_receiver) = mpsc::unbounded_channel();
-        
+        let router = Arc::new(parking_lot::RwLock::new(VenueRouter::new(config.mode)));
+        let sanity = config
+            .price_sanity
+            .clone()
+            .map(|sanity| parking_lot::Mutex::new(PriceSanityFilter::new(sanity)));
+        let cooldowns = CooldownList::new(config.cooldown.clone());
+        let activity = AccountActivity::new(config.activity.clone());
+        let lifecycle = PairLifecycle::new(config.lifecycle.clone());
+        let venue_status = VenueStatusBoard::new(config.venue_status.maintenance_lead);
+        let throttle = OrderThrottle::new(config.max_orders_per_second, config.throttle.clone());
+        let sizing = config.sizing.clone().map(AdaptiveSizer::new);
+        // Discount assets are taken to be held until a check finds otherwise
+        for asset in config.fee_discounts.iter().flat_map(|discounts| &discounts.assets) {
+            router.write().set_fee_discount(asset.venue.clone(), Some(asset.discount.clone()));
+        }
+
         Self {
             config,
             exchanges: HashMap::new(),
@@ -34,18 +80,159 @@ impl ExecutionEngine {
             risk_manager: Arc::new(RiskManager::new()),
             event_sender,
             event_receiver: Arc::new(Mutex::new(event_receiver)),
-            order_rate_limiter: Arc::new(RwLock::new(HashMap::new())),
+            throttle,
+            router,
+            books: Arc::new(RwLock::new(HashMap::new())),
+            sanity,
+            cooldowns,
+            activity,
+            lifecycle,
+            venue_status,
+            metrics: OnceLock::new(),
+            claims: None,
+            sizing,
+            order_acks: HashMap::new(),
         }
     }
 
     pub fn add_exchange(&mut self, name: String, exchange: Arc<dyn ExchangeAdapter>) {
+        let venue = VenueId::from(name.as_str());
+        let acks = RollingStats::default().with_max_age(std::time::Duration::from_secs(300)).shared();
+        if let Some(metrics) = self.metrics.get() {
+            Self::register_order_acks(metrics, &venue, &acks);
+        }
+        self.order_acks.insert(venue, acks);
         self.exchanges.insert(name, exchange);
     }
 
+    fn register_order_acks(metrics: &arbfinder_monitoring::MetricsCollector, venue: &VenueId, acks: &SharedStats) {
+        metrics.register_summary(
+            "arbfinder_order_ack_seconds",
+            "Time from submitting an order to its acknowledgement",
+            &[("exchange", &venue.to_string())],
+            Arc::clone(acks),
+        );
+    }
+
+    /// Recent order acknowledgement times on `venue`, in seconds.
+    pub fn order_ack_stats(&self, venue: &VenueId) -> Option<SharedStats> {
+        self.order_acks.get(venue).cloned()
+    }
+
     pub fn add_strategy(&mut self, strategy: Box<dyn Strategy>) {
         self.strategies.push(strategy);
     }
 
+    /// Maker and taker fees the router weighs venues by; a negative maker
+    /// fee is a rebate.
+    pub fn set_venue_fees(&mut self, venue_id: VenueId, fees: TradingFee) {
+        self.router.write().set_fees(venue_id, fees);
+    }
+
+    /// Replaces the router's fees with each registered venue's account
+    /// rates on `symbol`. Venues that fail keep the fees they had, with the
+    /// error logged; the rates fetched are returned.
+    pub async fn refresh_venue_fees(&self, symbol: &Symbol) -> HashMap<VenueId, TradingFee> {
+        let mut fetched = HashMap::new();
+        for (name, exchange) in &self.exchanges {
+            let venue = VenueId::from(name.as_str());
+            match exchange.get_trading_fees(symbol).await {
+                Ok(fees) => {
+                    self.router.write().set_fees(venue.clone(), fees.clone());
+                    fetched.insert(venue, fees);
+                }
+                Err(e) => warn!("Keeping default fees for {}: {}", venue, e),
+            }
+        }
+        fetched
+    }
+
+    /// Rereads each registered venue's credentials from `secrets` and
+    /// rotates the adapters whose credentials changed, checking the new
+    /// ones with a signed request the venue refuses if they are invalid.
+    /// Requests already signed finish under the old credentials.
+    pub async fn rotate_credentials(&self, secrets: &dyn SecretsProvider) -> Vec<CredentialRotation> {
+        let mut rotations = Vec::new();
+        for (name, exchange) in &self.exchanges {
+            let venue = VenueId::from(name.as_str());
+            let outcome = match secrets.credentials(&venue).await {
+                Ok(Some(credentials)) => match exchange.rotate_credentials(&credentials).await {
+                    Ok(true) => match exchange.verify_credentials().await {
+                        Ok(_) => RotationOutcome::Rotated,
+                        Err(e) => RotationOutcome::Failed(format!("New credentials refused: {}", e)),
+                    },
+                    Ok(false) => RotationOutcome::Unchanged,
+                    Err(e) => RotationOutcome::Failed(e.to_string()),
+                },
+                Ok(None) => RotationOutcome::NotConfigured,
+                Err(e) => RotationOutcome::Failed(e.to_string()),
+            };
+            rotations.push(CredentialRotation::new(venue, outcome));
+        }
+        rotations.sort_by_key(|rotation| rotation.venue.to_string());
+        rotations
+    }
+
+    /// Records the risk manager's decisions and order throttling to
+    /// `metrics`.
+    pub fn set_metrics(&self, metrics: Arc<arbfinder_monitoring::MetricsCollector>) {
+        self.risk_manager.set_metrics(Arc::clone(&metrics));
+        if self.metrics.get().is_none() {
+            for (venue, acks) in &self.order_acks {
+                Self::register_order_acks(&metrics, venue, acks);
+            }
+        }
+        let _ = self.metrics.set(metrics);
+    }
+
+    /// Claims each opportunity before executing it, so that only one of
+    /// the executors sharing `claims`' store acts on it.
+    pub fn set_claims(&mut self, claims: OpportunityClaims) {
+        self.claims = Some(claims);
+    }
+
+    pub fn route_order(&self, side: OrderSide, quotes: &[VenueQuote]) -> Option<RouteDecision> {
+        self.router.read().select(side, quotes)
+    }
+
+    /// Replaces the engine's view of `venue_id`'s book for `book.symbol`.
+    /// With a price sanity check configured, a book far from the other
+    /// venues' prices is dropped and the previous one kept. In BBO-only
+    /// mode just the top of the book is kept.
+    pub async fn update_orderbook(&self, venue_id: VenueId, book: OrderBook) -> Result<()> {
+        if let Some(sanity) = &self.sanity {
+            sanity.lock().check_book(&venue_id, &book)?;
+        }
+        let book = match self.config.market_data {
+            MarketDataMode::Full => book,
+            MarketDataMode::BboOnly => book.top_of_book(),
+        };
+        self.books.write().await.insert((venue_id, book.symbol.clone()), book);
+        Ok(())
+    }
+
+    /// Venues that sent a price rejected by the sanity check recently.
+    pub fn flagged_venues(&self) -> Vec<(VenueId, VenueFlag)> {
+        self.sanity
+            .as_ref()
+            .map(|sanity| sanity.lock().flagged_venues())
+            .unwrap_or_default()
+    }
+
+    pub async fn simulate(&self, request: &SimulationRequest) -> Result<SimulationResult> {
+        self.simulator().simulate(request).await
+    }
+
+    /// A handle that keeps seeing the engine's latest books, fees and
+    /// portfolio after it is handed out.
+    pub fn simulator(&self) -> TradeSimulator {
+        TradeSimulator::new(
+            Arc::clone(&self.books),
+            Arc::clone(&self.router),
+            Arc::clone(&self.portfolio),
+        )
+    }
+
     pub async fn start(&mut self) -> Result<()> {
         info!("Starting execution engine");
         
@@ -77,8 +264,9 @@ impl ExecutionEngine {
                 let event_sender = self.event_sender.clone();
                 
                 // Process market data for each symbol
-                // In a real implementation, this would subscribe to websocket feeds
-                info!("Would start market data processing for {}", symbol_clone.to_pair());
+                // In a real implementation, this would subscribe to websocket feeds,
+                // depth or best bid and offer as the market data mode says
+                info!("Would start {} market data processing for {}", self.config.market_data, symbol_clone.to_pair());
             }
         }
         
@@ -87,11 +275,13 @@ impl ExecutionEngine {
 
 
     async fn handle_event(
-        event: ExecutionEvent,
+        event: TimedExecutionEvent,
         portfolio: &Arc<RwLock<Portfolio>>,
         risk_manager: &Arc<RiskManager>,
     ) {
-        match event {
+        debug!("Execution event queued for {:?}", event.time.elapsed());
+
+        match event.event {
             ExecutionEvent::OrderPlaced(order) => {
                 info!("Order placed: {:?}", order);
                 portfolio.write().await.add_pending_order(order);
@@ -119,22 +309,6 @@ impl ExecutionEngine {
         }
     }
 
-    async fn check_rate_limit(&self, exchange: &str) -> bool {
-        let mut rate_limiter = self.order_rate_limiter.write().await;
-        let now = Instant::now();
-        let window_start = now - Duration::from_secs(1);
-        
-        let orders = rate_limiter.entry(exchange.to_string()).or_insert_with(Vec::new);
-        orders.retain(|&time| time > window_start);
-        
-        if orders.len() >= self.config.max_orders_per_second as usize {
-            false
-        } else {
-            orders.push(now);
-            true
-        }
-    }
-
     pub async fn place_order(
         &self,
         venue_id: VenueId,
@@ -143,48 +317,789 @@ impl ExecutionEngine {
         quantity: Decimal,
         price: Option<Decimal>,
     ) -> Result<OrderId> {
-        // Check rate limits
-        let exchange_str = format!("{:?}", venue_id);
-        if !self.check_rate_limit(&exchange_str).await {
-            return Err(ArbFinderError::RateLimit("Rate limit exceeded".to_string()));
+        let request = match price {
+            Some(p) => OrderRequest::new_limit(symbol, side, quantity, p),
+            None => OrderRequest::new_market(symbol, side, quantity),
+        };
+        self.submit_order(MANUAL_STRATEGY, venue_id, request).await
+    }
+
+    pub fn feature_flags(&self) -> &FeatureFlags {
+        &self.config.feature_flags
+    }
+
+    /// Routes kept from trading after failed executions. Callers that
+    /// measure fills report slippage through it.
+    pub fn cooldowns(&self) -> &CooldownList {
+        &self.cooldowns
+    }
+
+    /// Per venue pair scales of arbitrage sizes, if adaptive sizing is on.
+    pub fn sizing(&self) -> Option<&AdaptiveSizer> {
+        self.sizing.as_ref()
+    }
+
+    /// Orders this instance placed, which account activity is checked
+    /// against.
+    pub fn activity(&self) -> &AccountActivity {
+        &self.activity
+    }
+
+    /// Watches each venue's order updates and, every balance interval, its
+    /// balances, sending `anomalies` any activity this instance did not
+    /// originate. A venue without an order update stream is not watched at
+    /// all, as its balances can't be checked against our fills, and one that
+    /// can't report balances only for orders. Nothing is watched in paper
+    /// trading.
+    pub async fn watch_account_activity(&self, anomalies: mpsc::UnboundedSender<AccountAnomaly>) -> Result<()> {
+        if self.config.enable_paper_trading {
+            return Ok(());
+        }
+
+        let (activity, unknown_tx) = (self.activity.clone(), anomalies.clone());
+        tokio::spawn(async move {
+            let mut checks = tokio::time::interval(activity.config().unknown_order_grace);
+            while !unknown_tx.is_closed() {
+                checks.tick().await;
+                let now = chrono::Utc::now();
+                for anomaly in activity.unknown_orders(now) {
+                    let _ = unknown_tx.send(anomaly);
+                }
+                activity.prune_orders(now);
+            }
+        });
+
+        for (name, exchange) in &self.exchanges {
+            let venue = VenueId::from(name.as_str());
+
+            match exchange.order_update_stream().await {
+                Ok(mut updates) => {
+                    let (venue, activity) = (venue.clone(), self.activity.clone());
+                    tokio::spawn(async move {
+                        while let Some(update) = updates.next().await {
+                            match update {
+                                Ok(update) => activity.on_order_update(&venue, &update, chrono::Utc::now()),
+                                Err(e) => warn!("{} order update stream error: {}", venue, e),
+                            }
+                        }
+                        warn!("{} order update stream ended", venue);
+                    });
+                }
+                Err(e) => {
+                    // Without our fills every balance change looks unexplained
+                    warn!("Not watching {} orders or balances: {}", venue, e);
+                    continue;
+                }
+            }
+
+            let (exchange, activity, anomalies) = (Arc::clone(exchange), self.activity.clone(), anomalies.clone());
+            tokio::spawn(async move {
+                let mut checks = tokio::time::interval(activity.config().balance_interval);
+                loop {
+                    checks.tick().await;
+                    if anomalies.is_closed() {
+                        break;
+                    }
+                    let balances = exchange.get_balances().await;
+                    match balances {
+                        Ok(balances) => {
+                            for anomaly in activity.on_balances(&venue, &balances) {
+                                let _ = anomalies.send(anomaly);
+                            }
+                        }
+                        Err(ArbFinderError::Unsupported(e)) => {
+                            warn!("Not watching {} balances: {}", venue, e);
+                            break;
+                        }
+                        Err(e) => error!("Failed to check {} balances: {}", venue, e),
+                    }
+                }
+            });
+        }
+        Ok(())
+    }
+
+    /// Once a day at the configured time, compares the orders placed on
+    /// each venue since the last run with the venue's order and trade
+    /// history, writing a report per venue and sending it to `reports`
+    /// until that is closed. Venues that can't list their order history are
+    /// skipped. Nothing runs in paper trading or without a reconciliation
+    /// config.
+    pub fn watch_reconciliation(&self, reports: mpsc::UnboundedSender<ReconciliationReport>) {
+        let Some(config) = self.config.reconciliation.clone() else {
+            return;
+        };
+        if self.config.enable_paper_trading {
+            return;
+        }
+
+        let (exchanges, activity) = (self.exchanges.clone(), self.activity.clone());
+        tokio::spawn(async move {
+            // Orders are only known from startup on
+            let mut since = chrono::Utc::now();
+            while !reports.is_closed() {
+                let next = config.next_run(chrono::Utc::now());
+                tokio::time::sleep((next - chrono::Utc::now()).to_std().unwrap_or_default()).await;
+                let now = chrono::Utc::now();
+
+                for (name, exchange) in &exchanges {
+                    let venue = VenueId::from(name.as_str());
+                    let (orders, fills) = (
+                        exchange.get_order_history(None, Some(config.history_limit)).await,
+                        exchange.get_trade_history(None, Some(config.history_limit)).await,
+                    );
+                    let orders = match orders {
+                        Ok(orders) => orders,
+                        // Nothing to compare with, rather than an empty history
+                        Err(ArbFinderError::Unsupported(e)) => {
+                            warn!("Not reconciling {}: {}", venue, e);
+                            continue;
+                        }
+                        Err(e) => {
+                            error!("Cannot reconcile {}: order history failed: {}", venue, e);
+                            continue;
+                        }
+                    };
+                    let fills = fills
+                        .map_err(|e| warn!("Reconciling {} without fills: {}", venue, e))
+                        .ok();
+
+                    let report = reconcile(venue.clone(), since, &activity.orders(&venue), &orders, fills.as_deref(), now);
+                    match report.write(&config.report_dir) {
+                        Ok(path) => info!("{}; written to {}", report, path.display()),
+                        Err(e) => error!("Failed to write {} reconciliation report: {}", venue, e),
+                    }
+                    let _ = reports.send(report);
+                }
+                since = now;
+            }
+        });
+    }
+
+    /// Known trading state of each pair on each venue.
+    pub fn lifecycle(&self) -> &PairLifecycle {
+        &self.lifecycle
+    }
+
+    /// Checks the status of `symbols` on every venue each check interval,
+    /// sending changes to `events` until it is closed.
+    pub fn watch_pair_lifecycle(&self, symbols: Vec<Symbol>, events: mpsc::UnboundedSender<PairEvent>) {
+        for (name, exchange) in &self.exchanges {
+            let venue = VenueId::from(name.as_str());
+            let (exchange, lifecycle, events, symbols) =
+                (Arc::clone(exchange), self.lifecycle.clone(), events.clone(), symbols.clone());
+            tokio::spawn(async move {
+                let mut checks = tokio::time::interval(lifecycle.config().check_interval);
+                loop {
+                    checks.tick().await;
+                    if events.is_closed() {
+                        break;
+                    }
+                    for symbol in &symbols {
+                        let info = exchange.get_symbol_info(symbol).await;
+                        let Some(state) = PairState::from_lookup(&info) else {
+                            if let Err(e) = info {
+                                debug!("Could not check {} on {}: {}", symbol, venue, e);
+                            }
+                            continue;
+                        };
+                        if let Some(event) = lifecycle.on_state(&venue, symbol, state) {
+                            let _ = events.send(event);
+                        }
+                    }
+                }
+            });
+        }
+    }
+
+    /// Last reported status of each venue, shared with whoever else reads
+    /// status pages, e.g. an `ExchangeManager`.
+    pub fn venue_status(&self) -> &VenueStatusBoard {
+        &self.venue_status
+    }
+
+    /// Reads every venue's status page each check interval, sending
+    /// changes in their health to `events` until it is closed. Orders stop
+    /// going to a venue while it is down or in maintenance, and from the
+    /// configured lead time before scheduled maintenance.
+    pub fn watch_venue_status(&self, events: mpsc::UnboundedSender<VenueStatusEvent>) {
+        for (name, exchange) in &self.exchanges {
+            let venue = VenueId::from(name.as_str());
+            let (exchange, board, events) = (Arc::clone(exchange), self.venue_status.clone(), events.clone());
+            let interval = self.config.venue_status.check_interval;
+            tokio::spawn(async move {
+                let mut checks = tokio::time::interval(interval);
+                loop {
+                    checks.tick().await;
+                    if events.is_closed() {
+                        break;
+                    }
+                    match exchange.venue_status().await {
+                        Ok(report) => {
+                            if let Some(event) = board.on_report(&venue, report, chrono::Utc::now()) {
+                                let _ = events.send(event);
+                            }
+                        }
+                        Err(e) => debug!("Could not read {} status: {}", venue, e),
+                    }
+                }
+            });
+        }
+    }
+
+    /// Acts on a pair that stopped trading: cancels this instance's open
+    /// orders in it on that venue, ahead of anything else waiting on the
+    /// venue's rate limit, then unwinds its position per the configured
+    /// policy. Returns the orders canceled.
+    pub async fn handle_pair_event(&self, event: &PairEvent) -> Result<Vec<OrderId>> {
+        if !event.stopped() {
+            return Ok(Vec::new());
+        }
+
+        let mut canceled = Vec::new();
+        if !self.config.enable_paper_trading {
+            if let Some(exchange) = self.exchanges.get(&event.venue.to_string()) {
+                RequestPriority::Critical
+                    .scope(async {
+                        for order in exchange.get_open_orders(Some(&event.symbol)).await? {
+                            match exchange.cancel_order(&order.id).await {
+                                Ok(()) => canceled.push(order.id),
+                                Err(e) => error!("Failed to cancel {} on {}: {}", order.id, event.venue, e),
+                            }
+                        }
+                        Ok::<_, ArbFinderError>(())
+                    })
+                    .await?;
+            }
         }
 
+        if self.config.lifecycle.unwind == UnwindPolicy::Close {
+            let position = self
+                .portfolio
+                .read()
+                .await
+                .positions
+                .get(&event.symbol.id())
+                .map(|position| (position.side, position.size))
+                .filter(|(_, size)| !size.is_zero());
+            if let Some((side, size)) = position {
+                let venue = self
+                    .exchanges
+                    .keys()
+                    .map(|name| VenueId::from(name.as_str()))
+                    .find(|venue| {
+                        *venue != event.venue
+                            && self.lifecycle.is_tradable(venue, &event.symbol)
+                            && self.venue_status.accepts_orders(venue, chrono::Utc::now())
+                    })
+                    .ok_or_else(|| {
+                        ArbFinderError::Execution(format!("No venue left to unwind {} on", event.symbol))
+                    })?;
+                let close = match side {
+                    OrderSide::Buy => OrderSide::Sell,
+                    OrderSide::Sell => OrderSide::Buy,
+                };
+                info!("Unwinding {} {} {} on {}", close, size, event.symbol, venue);
+                let request = OrderRequest::new_market(event.symbol.clone(), close, size);
+                self.submit_order("lifecycle", venue, request).await?;
+            }
+        }
+        Ok(canceled)
+    }
+
+    /// Places a limit order on the venue the router picks from `quotes`. In
+    /// maker-first mode the order is post-only at the quoted price, unless
+    /// the `maker_first` flag is off for that venue and symbol, in which
+    /// case it is routed as a taker order. `strategy` is who the order is
+    /// for, which sets its share of the venue's order rate limit.
+    pub async fn place_routed_order(
+        &self,
+        strategy: &str,
+        symbol: Symbol,
+        side: OrderSide,
+        quantity: Decimal,
+        quotes: &[VenueQuote],
+    ) -> Result<OrderId> {
+        let mut route = self.route_order(side, quotes);
+        if let Some(maker) = route.as_ref().filter(|route| route.post_only) {
+            if !self.config.feature_flags.is_enabled(flags::MAKER_FIRST, &maker.venue, &symbol) {
+                route = self.router.read().select_as(ExecutionMode::Taker, side, quotes);
+            }
+        }
+        let route = route.ok_or_else(|| {
+            ArbFinderError::Execution(format!("No venue quotes to route {} {}", side, symbol.to_pair()))
+        })?;
+        debug!(
+            "Routing {} {} to {} at {} (fee {}, effective {})",
+            side, symbol.to_pair(), route.venue, route.price, route.fee_rate, route.effective_price
+        );
+
+        let mut request = OrderRequest::new_limit(symbol, side, quantity, route.price);
+        if route.post_only {
+            request = request.post_only();
+        }
+        self.submit_order(strategy, route.venue, request).await
+    }
+
+    /// Leg sizes for taking `quantity` of `opportunity`, split by the
+    /// configured profit allocation. Prices are weighed after each venue's
+    /// fees and both legs are rounded to the coarser of the two venues' lot
+    /// sizes.
+    pub async fn arbitrage_legs(&self, opportunity: &ArbitrageOpportunity, quantity: Decimal) -> Result<LegSizes> {
+        let (buy_cost, sell_proceeds) = {
+            let router = self.router.read();
+            (
+                router.effective_price(&opportunity.buy_venue, OrderSide::Buy, opportunity.buy_price),
+                router.effective_price(&opportunity.sell_venue, OrderSide::Sell, opportunity.sell_price),
+            )
+        };
+
+        let mut lot_size = Decimal::ZERO;
+        for venue_id in [&opportunity.buy_venue, &opportunity.sell_venue] {
+            if let Some(exchange) = self.exchanges.get(&venue_id.to_string()) {
+                let info = exchange.get_symbol_info(&opportunity.symbol).await?;
+                lot_size = lot_size.max(info.lot_size);
+            }
+        }
+
+        Ok(self
+            .config
+            .profit_allocation
+            .leg_sizes(quantity, buy_cost, sell_proceeds, lot_size))
+    }
+
+    /// Places both legs of `opportunity` as limit orders at its prices,
+    /// sized by [`Self::arbitrage_legs`] after scaling `quantity` by the
+    /// venue pair's recent fills, with adaptive sizing on. Returns the buy
+    /// and sell order ids.
+    /// The legs go out together. When one fails the other is canceled,
+    /// rather than left to fill unhedged.
+    /// A route on cooldown is refused; a rejected leg puts it on one. With
+    /// claims set, an opportunity another executor claimed is refused too.
+    /// `strategy` found the opportunity, and sets the legs' share of the
+    /// venues' order rate limits.
+    pub async fn execute_opportunity(
+        &self,
+        strategy: &str,
+        opportunity: &ArbitrageOpportunity,
+        quantity: Decimal,
+    ) -> Result<(OrderId, OrderId)> {
+        let route = CooldownRoute::of(opportunity);
+        if let Some(cooldown) = self.cooldowns.cooldown(&route, chrono::Utc::now()) {
+            return Err(ArbFinderError::Execution(format!(
+                "{} is on cooldown until {} after {} failures",
+                route, cooldown.until, cooldown.failures
+            )));
+        }
+
+        let quantity = match &self.sizing {
+            Some(sizing) => sizing.size(&VenuePair::of(opportunity), quantity),
+            None => quantity,
+        };
+        let legs = self.arbitrage_legs(opportunity, quantity).await?;
+        if legs.buy.is_zero() || legs.sell.is_zero() {
+            return Err(ArbFinderError::InvalidOrder(format!(
+                "{} of {} is less than one lot",
+                quantity, opportunity.symbol
+            )));
+        }
+        self.claim(opportunity).await?;
+        debug!(
+            "Executing {} {} -> {}: buy {}, sell {} (expected {} quote, {} base)",
+            opportunity.symbol,
+            opportunity.buy_venue,
+            opportunity.sell_venue,
+            legs.buy,
+            legs.sell,
+            legs.quote_profit,
+            legs.base_profit
+        );
+
+        let buy = OrderRequest::new_limit(opportunity.symbol.clone(), OrderSide::Buy, legs.buy, opportunity.buy_price);
+        let sell = OrderRequest::new_limit(opportunity.symbol.clone(), OrderSide::Sell, legs.sell, opportunity.sell_price);
+        let (buy, sell) = tokio::join!(
+            self.place(strategy, opportunity.buy_venue.clone(), buy),
+            self.place(strategy, opportunity.sell_venue.clone(), sell),
+        );
+        match (buy, sell) {
+            (Ok(buy), Ok(sell)) => {
+                self.cooldowns.record_success(&route);
+                Ok((buy.id, sell.id))
+            }
+            (Ok(placed), Err(e)) | (Err(e), Ok(placed)) => {
+                self.cancel_leg(placed).await;
+                Err(self.execution_failed(&route, FailureKind::LegImbalance, e))
+            }
+            (Err(e), Err(_)) => Err(self.execution_failed(&route, FailureKind::Rejected, e)),
+        }
+    }
+
+    /// Cancels `order`, a leg whose other half failed, ahead of anything
+    /// else waiting on the venue's rate limit. One that can't be canceled,
+    /// most likely because it already filled, is left to the position
+    /// limits and reported.
+    async fn cancel_leg(&self, order: Order) {
+        let (side, id, venue) = (order.side, order.id.clone(), order.venue_id.clone());
+        match RequestPriority::Critical.scope(self.cancel(order)).await {
+            Ok(()) => warn!("Canceled {} leg {} on {} after the other leg failed", side, id, venue),
+            Err(e) => error!("Failed to cancel {} leg {} on {}, leaving it unhedged: {}", side, id, venue, e),
+        }
+    }
+
+    /// Cancels `order` on its venue, or only in the portfolio when paper
+    /// trading.
+    async fn cancel(&self, mut order: Order) -> Result<()> {
+        if !self.config.enable_paper_trading {
+            let exchange = self.exchanges.get(&order.venue_id.to_string()).ok_or_else(|| {
+                ArbFinderError::Execution(format!("No adapter registered for {}", order.venue_id))
+            })?;
+            exchange.cancel_order(&order.id).await?;
+        }
+        order.status = OrderStatus::Canceled;
+        let _ = self.event_sender.send(ExecutionEvent::OrderCanceled(order).stamped());
+        Ok(())
+    }
+
+    /// Feeds how the `buy` and `sell` legs of `opportunity` filled into
+    /// adaptive sizing, once they are done. Returns the venue pair's new
+    /// sizing, or `None` with adaptive sizing off.
+    pub fn record_fills(&self, opportunity: &ArbitrageOpportunity, buy: &Order, sell: &Order) -> Option<PairSizing> {
+        let sizing = self.sizing.as_ref()?;
+        let quality = FillQuality::of(opportunity, buy, sell);
+        let pair = sizing.record(VenuePair::of(opportunity), quality);
+        debug!(
+            "{} filled {} at {} bps slippage; over {} executions {} at {} bps, scaling sizes by {}",
+            pair.pair,
+            quality.fill_ratio,
+            quality.slippage_bps.round_dp(2),
+            pair.executions,
+            pair.mean_fill_ratio.round_dp(4),
+            pair.mean_slippage_bps.round_dp(2),
+            pair.scale.round_dp(4)
+        );
+        Some(pair)
+    }
+
+    /// Claims `opportunity` for this executor, if claims are set. A claim
+    /// that cannot be made refuses the opportunity rather than risk two
+    /// executors acting on it.
+    async fn claim(&self, opportunity: &ArbitrageOpportunity) -> Result<()> {
+        let Some(claims) = &self.claims else {
+            return Ok(());
+        };
+        let started = EventTime::now();
+        let outcome = claims.claim(opportunity).await;
+        if let Some(metrics) = self.metrics.get() {
+            let result = match &outcome {
+                Ok(ClaimOutcome::Won) => "won",
+                Ok(ClaimOutcome::Lost { .. }) => "lost",
+                Err(_) => "error",
+            };
+            metrics.record_opportunity_claim(result, started.elapsed().as_secs_f64());
+        }
+        match outcome? {
+            ClaimOutcome::Won => Ok(()),
+            ClaimOutcome::Lost { owner } => Err(ArbFinderError::Execution(format!(
+                "Opportunity {} was claimed by {}",
+                opportunity.id, owner
+            ))),
+        }
+    }
+
+    /// Puts `route` on cooldown for `error`, unless the engine's own rate
+    /// limit refused the order, and hands the error back.
+    fn execution_failed(&self, route: &CooldownRoute, kind: FailureKind, error: ArbFinderError) -> ArbFinderError {
+        if !matches!(error, ArbFinderError::RateLimit(_)) {
+            let cooldown = self.cooldowns.record_failure(route.clone(), kind, chrono::Utc::now());
+            warn!("Execution of {} failed ({}): {}; cooling down until {}", route, kind, error, cooldown.until);
+        }
+        error
+    }
+
+    async fn submit_order(&self, strategy: &str, venue_id: VenueId, request: OrderRequest) -> Result<OrderId> {
+        self.place(strategy, venue_id, request).await.map(|order| order.id)
+    }
+
+    async fn place(&self, strategy: &str, venue_id: VenueId, request: OrderRequest) -> Result<Order> {
+        if !self.lifecycle.is_tradable(&venue_id, &request.symbol) {
+            return Err(ArbFinderError::InvalidOrder(format!(
+                "{} is not trading on {}",
+                request.symbol, venue_id
+            )));
+        }
+        let health = self.venue_status.health(&venue_id, chrono::Utc::now());
+        if !health.accepts_orders() {
+            return Err(ArbFinderError::MarketClosed(format!("{} is {}", venue_id, health)));
+        }
+
+        // Wait for room under the venue's order rate limit
+        let exchange = venue_id.to_string();
+        let throttled = self.throttle.acquire(&exchange, strategy).await;
+        if let Some(metrics) = self.metrics.get() {
+            match &throttled {
+                Ok(waited) => metrics.record_throttle_wait(strategy, &exchange, waited.as_secs_f64()),
+                Err(_) => metrics.record_throttle_timeout(strategy, &exchange),
+            }
+        }
+        if let Err(e) = throttled {
+            warn!("Order for {} on {} throttled out: {}", strategy, exchange, e);
+            return Err(e);
+        }
+
+        let base = self.in_base(&venue_id, &request).await?;
+
         // Check risk limits
-        if !self.risk_manager.check_order_risk(&symbol.to_pair(), side, price.unwrap_or_default(), quantity).await {
+        let price = base.price.unwrap_or_default();
+        if !self.risk_manager.check_order_risk(&base.symbol.to_pair(), base.side, price, base.quantity).await {
             return Err(ArbFinderError::InvalidOrder("Risk limits exceeded".to_string()));
         }
 
         if self.config.enable_paper_trading {
             // Paper trading mode
-            let order = if let Some(p) = price {
-                Order::new_limit(venue_id, symbol, side, quantity, p)
+            let mut order = if let Some(p) = base.price {
+                Order::new_limit(venue_id, base.symbol, base.side, base.quantity, p)
             } else {
-                Order::new_market(venue_id, symbol, side, quantity)
+                Order::new_market(venue_id, base.symbol, base.side, base.quantity)
             };
+            order.time_in_force = base.time_in_force;
 
-            let order_id = order.id.clone();
-            self.event_sender.send(ExecutionEvent::OrderPlaced(order))
+            self.event_sender.send(ExecutionEvent::OrderPlaced(order.clone()).stamped())
                 .map_err(|e| ArbFinderError::Internal(e.to_string()))?;
 
-            Ok(order_id)
+            Ok(order)
         } else {
-            // Real trading mode would use adapter methods here
-            Err(ArbFinderError::Exchange("Real trading not implemented yet".to_string()))
+            let exchange = self.exchanges.get(&venue_id.to_string()).cloned().ok_or_else(|| {
+                ArbFinderError::Execution(format!("No adapter registered for {}", venue_id))
+            })?;
+
+            // Venues that take quote sizes natively fill the notional exactly
+            let request = if exchange.accepts_quote_size(&request) {
+                request
+            } else {
+                base
+            };
+
+            // Reuse one client id across attempts so a venue that did accept
+            // an earlier attempt can reject the duplicate.
+            let request = request.with_client_id(generate_client_order_id(&venue_id.to_string()));
+
+            let submitted = EventTime::now();
+            let order = self
+                .config
+                .order_retry
+                .retry(|| async {
+                    exchange.place_order(&request).await
+                })
+                .await?;
+            let acknowledged = submitted.elapsed();
+            debug!("Order {} acknowledged after {:?}", order.id, acknowledged);
+            if let Some(acks) = self.order_acks.get(&venue_id) {
+                acks.lock().unwrap_or_else(|e| e.into_inner()).record_duration(acknowledged);
+            }
+            self.activity.record_order(&order);
+
+            self.event_sender.send(ExecutionEvent::OrderPlaced(order.clone()).stamped())
+                .map_err(|e| ArbFinderError::Internal(e.to_string()))?;
+
+            Ok(order)
+        }
+    }
+
+    /// `request` sized in base units. A quote-currency size is converted at
+    /// the limit price, or else at the touch of the engine's book for the
+    /// venue, and rounded down to the venue's lot size.
+    async fn in_base(&self, venue_id: &VenueId, request: &OrderRequest) -> Result<OrderRequest> {
+        if request.size_unit == SizeUnit::Base {
+            return Ok(request.clone());
         }
+
+        let price = match request.price {
+            Some(price) => price,
+            None => {
+                let books = self.books.read().await;
+                let book = books.get(&(venue_id.clone(), request.symbol.clone()));
+                let touch = match request.side {
+                    OrderSide::Buy => book.and_then(|book| book.best_ask()),
+                    OrderSide::Sell => book.and_then(|book| book.best_bid()),
+                };
+                touch.map(|level| level.price).ok_or_else(|| {
+                    ArbFinderError::InvalidOrder(format!(
+                        "No {} book on {} to size a quote-denominated order",
+                        request.symbol, venue_id
+                    ))
+                })?
+            }
+        };
+
+        let lot_size = match self.exchanges.get(&venue_id.to_string()) {
+            Some(exchange) => exchange.get_symbol_info(&request.symbol).await?.lot_size,
+            None => Decimal::ZERO,
+        };
+        request.clone().into_base(price, lot_size)
     }
 
+    /// Cancels an order this instance placed, on the venue it was placed
+    /// on.
     pub async fn cancel_order(&self, order_id: &OrderId) -> Result<()> {
         if self.config.enable_paper_trading {
             // Paper trading mode - just mark as canceled
             info!("Paper trading: Canceling order {}", order_id);
-            Ok(())
-        } else {
-            // Real trading mode would use adapter methods here
-            Err(ArbFinderError::Exchange("Real trading not implemented yet".to_string()))
+            return Ok(());
         }
+        let order = self
+            .activity
+            .order(order_id)
+            .ok_or_else(|| ArbFinderError::Execution(format!("Order {} was not placed by this instance", order_id)))?;
+        self.cancel(order).await
     }
 
     pub async fn get_portfolio(&self) -> Portfolio {
         self.portfolio.read().await.clone()
     }
+
+    /// Total holdings by asset on each registered venue. Venues that can't
+    /// report balances are left out.
+    pub async fn venue_balances(&self) -> Result<HashMap<VenueId, HashMap<String, Decimal>>> {
+        let mut balances = HashMap::new();
+        for (name, exchange) in &self.exchanges {
+            let held = match exchange.get_balances().await {
+                Ok(held) => held,
+                Err(ArbFinderError::Unsupported(e)) => {
+                    warn!("Leaving {} out of venue balances: {}", name, e);
+                    continue;
+                }
+                Err(e) => return Err(e),
+            };
+            balances.insert(
+                VenueId::from(name.as_str()),
+                held.into_iter().map(|b| (b.asset, b.total)).collect(),
+            );
+        }
+        Ok(balances)
+    }
+
+    /// Plans a rebalance toward the configured inventory targets. With
+    /// `auto_execute` set and a band breached, the plan's trades are placed
+    /// as market orders, and with `auto_transfer` too its transfers are
+    /// withdrawn to the receiving venue. Other transfers are logged for an
+    /// operator.
+    pub async fn rebalance_inventory(&self) -> Result<RebalancePlan> {
+        let inventory = self.config.inventory.as_ref().ok_or_else(|| {
+            ArbFinderError::Execution("No inventory targets configured".to_string())
+        })?;
+        let plan = inventory.plan(&self.venue_balances().await?);
+
+        if inventory.auto_execute && plan.needs_rebalance() {
+            for action in &plan.actions {
+                match action {
+                    RebalanceAction::Trade { venue, symbol, side, quantity } => {
+                        info!("Rebalancing: {}", action);
+                        let request = OrderRequest::new_market(symbol.clone(), *side, *quantity);
+                        self.submit_order("rebalance", venue.clone(), request).await?;
+                    }
+                    RebalanceAction::Transfer { asset, from, to, amount } if inventory.auto_transfer => {
+                        info!("Rebalancing: {}", action);
+                        self.transfer(asset, from, to, *amount).await?;
+                    }
+                    RebalanceAction::Transfer { .. } => {
+                        warn!("Rebalancing needs a manual {}", action);
+                    }
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// Checks the balance of each configured fee discount asset. Venues
+    /// whose balance has run out are routed at their full fees until it is
+    /// back; one below its floor is topped up with a market buy, with
+    /// `auto_top_up` set. An asset whose venue can't report balances, or
+    /// reports none at all, is skipped rather than taken to be at zero and
+    /// bought again every check.
+    pub async fn maintain_fee_discounts(&self) -> Result<Vec<DiscountBalance>> {
+        let discounts = self.config.fee_discounts.as_ref().ok_or_else(|| {
+            ArbFinderError::Execution("No fee discount assets configured".to_string())
+        })?;
+
+        let mut checked = Vec::new();
+        for asset in &discounts.assets {
+            let exchange = self.exchanges.get(&asset.venue.to_string()).ok_or_else(|| {
+                ArbFinderError::Exchange(format!("Exchange {} not found", asset.venue))
+            })?;
+            let balances = match exchange.get_balances().await {
+                Ok(balances) if !balances.is_empty() => balances,
+                Ok(_) => {
+                    warn!("{} reported no balances; not checking {}", asset.venue, asset.discount.asset);
+                    continue;
+                }
+                Err(e) => {
+                    warn!("Cannot read {} balances, not checking {}: {}", asset.venue, asset.discount.asset, e);
+                    continue;
+                }
+            };
+            let balance = balances
+                .into_iter()
+                .find(|balance| balance.asset.eq_ignore_ascii_case(&asset.discount.asset))
+                .map(|balance| balance.available)
+                .unwrap_or_default();
+
+            let discounted = balance > Decimal::ZERO;
+            self.router
+                .write()
+                .set_fee_discount(asset.venue.clone(), discounted.then(|| asset.discount.clone()));
+
+            let mut topped_up = None;
+            if let Some(quantity) = asset.top_up(balance).filter(|_| discounts.auto_top_up) {
+                info!("Topping up {} on {} with {}", asset.discount.asset, asset.venue, quantity);
+                let request = OrderRequest::new_market(asset.symbol(), OrderSide::Buy, quantity);
+                self.submit_order("fee_discount", asset.venue.clone(), request).await?;
+                topped_up = Some(quantity);
+            }
+
+            checked.push(DiscountBalance {
+                venue: asset.venue.clone(),
+                asset: asset.discount.asset.clone(),
+                balance,
+                discounted,
+                topped_up,
+            });
+        }
+        Ok(checked)
+    }
+
+    /// Withdraws `amount` of `asset` from `from` to the deposit address of
+    /// `to`, on the chain the address is on as `from` names it. A transfer
+    /// over a chain either venue's name is unknown for is refused.
+    pub async fn transfer(&self, asset: &str, from: &VenueId, to: &VenueId, amount: Decimal) -> Result<Option<Withdrawal>> {
+        let adapter = |venue: &VenueId| {
+            self.exchanges
+                .get(&venue.to_string())
+                .cloned()
+                .ok_or_else(|| ArbFinderError::Execution(format!("No adapter registered for {}", venue)))
+        };
+        let sender = adapter(from)?;
+        let address = adapter(to)?.get_deposit_address(asset).await?;
+        let address = address_for_sender(&address, to, from)?;
+
+        if self.config.enable_paper_trading {
+            info!("Paper trading: Withdrawing {} {} from {} to {}", amount, asset, from, address.address);
+            return Ok(None);
+        }
+        let withdrawal = sender.withdraw(asset, amount, &address).await?;
+        info!("Withdrawal {} of {} {} from {} to {} requested", withdrawal.id, amount, asset, from, to);
+        Ok(Some(withdrawal))
+    }
+
+    /// A transfer manager over the registered venues, to follow transfers
+    /// through to the receiving venue's deposit. While paper trading it
+    /// refuses to start transfers.
+    pub fn transfer_manager(&self, mut config: TransferConfig) -> TransferManager {
+        config.paper_trading |= self.config.enable_paper_trading;
+        let mut transfers = TransferManager::new(config);
+        for (name, exchange) in &self.exchanges {
+            transfers.add_venue(VenueId::from(name.as_str()), Arc::clone(exchange));
+        }
+        transfers
+    }
 }
\ No newline at end of file
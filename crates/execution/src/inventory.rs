@@ -0,0 +1,333 @@
+//! Target inventory per asset per venue.
+//!
+//! Each target carries a band, the drift from target tolerated before the
+//! venue counts as out of balance. Planning moves surplus from venues above
+//! target to venues below it; what the venues are short or long of in total
+//! after that is bought or sold against the quote asset where it is needed.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+use arbfinder_core::prelude::*;
+use arbfinder_core::utils::ReportLocale;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InventoryTarget {
+    pub venue: VenueId,
+    pub asset: String,
+    pub target: Decimal,
+    /// Allowed drift as a fraction of the target, e.g. 0.1 for ±10%.
+    pub band: Decimal,
+}
+
+impl InventoryTarget {
+    pub fn new(venue: VenueId, asset: impl Into<String>, target: Decimal, band: Decimal) -> Self {
+        Self {
+            venue,
+            asset: asset.into(),
+            target,
+            band,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InventoryConfig {
+    pub targets: Vec<InventoryTarget>,
+    /// Asset traded against when the venues together hold too much or too
+    /// little of another asset.
+    pub quote_asset: String,
+    pub report_interval: Duration,
+    /// Place the plan's trades when a band is breached.
+    pub auto_execute: bool,
+    /// Also withdraw the plan's transfers to the receiving venue's deposit
+    /// address, on the chain that address is on. Otherwise transfers are
+    /// only reported.
+    pub auto_transfer: bool,
+}
+
+impl Default for InventoryConfig {
+    fn default() -> Self {
+        Self {
+            targets: Vec::new(),
+            quote_asset: "USDT".to_string(),
+            report_interval: Duration::from_secs(3600),
+            auto_execute: false,
+            auto_transfer: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InventoryDrift {
+    pub venue: VenueId,
+    pub asset: String,
+    pub target: Decimal,
+    pub actual: Decimal,
+    /// `actual - target` as a fraction of the target.
+    pub drift: Decimal,
+    pub within_band: bool,
+}
+
+impl InventoryDrift {
+    fn new(target: &InventoryTarget, actual: Decimal) -> Self {
+        let drift = if target.target.is_zero() {
+            if actual.is_zero() { Decimal::ZERO } else { Decimal::ONE }
+        } else {
+            (actual - target.target) / target.target
+        };
+        Self {
+            venue: target.venue.clone(),
+            asset: target.asset.clone(),
+            target: target.target,
+            actual,
+            drift,
+            within_band: drift.abs() <= target.band,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RebalanceAction {
+    Transfer {
+        asset: String,
+        from: VenueId,
+        to: VenueId,
+        amount: Decimal,
+    },
+    Trade {
+        venue: VenueId,
+        symbol: Symbol,
+        side: OrderSide,
+        quantity: Decimal,
+    },
+}
+
+impl RebalanceAction {
+    pub fn render(&self, locale: &ReportLocale) -> String {
+        match self {
+            RebalanceAction::Transfer { asset, from, to, amount } => {
+                format!("transfer {} {} from {} to {}", locale.decimal(*amount), asset, from, to)
+            }
+            RebalanceAction::Trade { venue, symbol, side, quantity } => {
+                format!("{} {} {} on {}", side, locale.decimal(*quantity), symbol, venue)
+            }
+        }
+    }
+}
+
+impl fmt::Display for RebalanceAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(&ReportLocale::default()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RebalancePlan {
+    pub created_at: DateTime<Utc>,
+    pub drifts: Vec<InventoryDrift>,
+    pub actions: Vec<RebalanceAction>,
+}
+
+impl RebalancePlan {
+    pub fn needs_rebalance(&self) -> bool {
+        self.drifts.iter().any(|drift| !drift.within_band)
+    }
+
+    /// The report with numbers and its time as `locale` has them.
+    pub fn render(&self, locale: &ReportLocale) -> String {
+        let mut out = format!("Inventory report {}", locale.timestamp(self.created_at));
+        for drift in &self.drifts {
+            let percent = drift.drift * Decimal::from(100);
+            out.push_str(&format!(
+                "\n  {} {}: {} / {} ({}{}%){}",
+                drift.venue,
+                drift.asset,
+                locale.decimal(drift.actual),
+                locale.decimal(drift.target),
+                if percent.is_sign_negative() { "" } else { "+" },
+                locale.fixed(percent, 2),
+                if drift.within_band { "" } else { " OUT OF BAND" }
+            ));
+        }
+        if self.actions.is_empty() {
+            out.push_str("\n  No rebalancing needed");
+        } else {
+            out.push_str("\n  Plan:");
+            for action in &self.actions {
+                out.push_str(&format!("\n    {}", action.render(locale)));
+            }
+        }
+        out
+    }
+}
+
+impl fmt::Display for RebalancePlan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(&ReportLocale::default()))
+    }
+}
+
+impl InventoryConfig {
+    /// Compares `balances` (total holdings by venue and asset) with the
+    /// targets. Assets with every venue inside its band get no actions.
+    pub fn plan(&self, balances: &HashMap<VenueId, HashMap<String, Decimal>>) -> RebalancePlan {
+        let drifts: Vec<InventoryDrift> = self
+            .targets
+            .iter()
+            .map(|target| {
+                let actual = balances
+                    .get(&target.venue)
+                    .and_then(|assets| assets.get(&target.asset))
+                    .copied()
+                    .unwrap_or_default();
+                InventoryDrift::new(target, actual)
+            })
+            .collect();
+
+        let mut assets: Vec<&str> = Vec::new();
+        for drift in &drifts {
+            if !assets.contains(&drift.asset.as_str()) {
+                assets.push(&drift.asset);
+            }
+        }
+
+        let mut actions = Vec::new();
+        for asset in assets {
+            let venues: Vec<&InventoryDrift> = drifts.iter().filter(|d| d.asset == asset).collect();
+            if venues.iter().all(|d| d.within_band) {
+                continue;
+            }
+            actions.extend(self.plan_asset(asset, &venues));
+        }
+
+        RebalancePlan {
+            created_at: Utc::now(),
+            drifts,
+            actions,
+        }
+    }
+
+    fn plan_asset(&self, asset: &str, venues: &[&InventoryDrift]) -> Vec<RebalanceAction> {
+        let mut surpluses: Vec<(VenueId, Decimal)> = Vec::new();
+        let mut deficits: Vec<(VenueId, Decimal)> = Vec::new();
+        for drift in venues {
+            let deviation = drift.actual - drift.target;
+            if deviation > Decimal::ZERO {
+                surpluses.push((drift.venue.clone(), deviation));
+            } else if deviation < Decimal::ZERO {
+                deficits.push((drift.venue.clone(), -deviation));
+            }
+        }
+        // Largest first keeps the number of transfers down
+        surpluses.sort_by_key(|(_, amount)| std::cmp::Reverse(*amount));
+        deficits.sort_by_key(|(_, amount)| std::cmp::Reverse(*amount));
+
+        let mut actions = Vec::new();
+        let (mut s, mut d) = (0, 0);
+        while s < surpluses.len() && d < deficits.len() {
+            let amount = surpluses[s].1.min(deficits[d].1);
+            actions.push(RebalanceAction::Transfer {
+                asset: asset.to_string(),
+                from: surpluses[s].0.clone(),
+                to: deficits[d].0.clone(),
+                amount,
+            });
+            surpluses[s].1 -= amount;
+            deficits[d].1 -= amount;
+            if surpluses[s].1.is_zero() {
+                s += 1;
+            }
+            if deficits[d].1.is_zero() {
+                d += 1;
+            }
+        }
+
+        // The quote asset is what the others trade against; an overall
+        // shortfall of it can only be reported.
+        if asset == self.quote_asset {
+            return actions;
+        }
+        let symbol = Symbol::new(asset, &self.quote_asset);
+        let residual = surpluses[s..]
+            .iter()
+            .map(|(venue, amount)| (venue, OrderSide::Sell, *amount))
+            .chain(deficits[d..].iter().map(|(venue, amount)| (venue, OrderSide::Buy, *amount)));
+        for (venue, side, quantity) in residual {
+            if quantity > Decimal::ZERO {
+                actions.push(RebalanceAction::Trade {
+                    venue: venue.clone(),
+                    symbol: symbol.clone(),
+                    side,
+                    quantity,
+                });
+            }
+        }
+        actions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn config() -> InventoryConfig {
+        InventoryConfig {
+            targets: vec![
+                InventoryTarget::new(VenueId::Binance, "BTC", dec!(0.5), dec!(0.1)),
+                InventoryTarget::new(VenueId::Coinbase, "BTC", dec!(0.5), dec!(0.1)),
+            ],
+            ..InventoryConfig::default()
+        }
+    }
+
+    fn balances(binance: Decimal, coinbase: Decimal) -> HashMap<VenueId, HashMap<String, Decimal>> {
+        HashMap::from([
+            (VenueId::Binance, HashMap::from([("BTC".to_string(), binance)])),
+            (VenueId::Coinbase, HashMap::from([("BTC".to_string(), coinbase)])),
+        ])
+    }
+
+    #[test]
+    fn test_within_band_needs_nothing() {
+        let plan = config().plan(&balances(dec!(0.54), dec!(0.46)));
+        assert!(!plan.needs_rebalance());
+        assert!(plan.actions.is_empty());
+        assert_eq!(plan.drifts[0].drift, dec!(0.08));
+    }
+
+    #[test]
+    fn test_drift_is_moved_by_transfer() {
+        let plan = config().plan(&balances(dec!(0.8), dec!(0.2)));
+        assert!(plan.needs_rebalance());
+        assert_eq!(
+            plan.actions,
+            vec![RebalanceAction::Transfer {
+                asset: "BTC".to_string(),
+                from: VenueId::Binance,
+                to: VenueId::Coinbase,
+                amount: dec!(0.3),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_overall_shortfall_is_bought() {
+        let plan = config().plan(&balances(dec!(0.6), dec!(0.1)));
+        assert_eq!(plan.actions.len(), 2);
+        assert_eq!(
+            plan.actions[1],
+            RebalanceAction::Trade {
+                venue: VenueId::Coinbase,
+                symbol: Symbol::new("BTC", "USDT"),
+                side: OrderSide::Buy,
+                quantity: dec!(0.3),
+            }
+        );
+        assert!(plan.to_string().contains("OUT OF BAND"));
+    }
+}
@@ -1,14 +1,44 @@
 use rust_decimal::Decimal;
 
 use arbfinder_core::prelude::*;
+use arbfinder_exchange::VenueStatusConfig;
+use arbfinder_orderbook::PriceSanityConfig;
 
+pub mod activity;
+pub mod admin;
+pub mod allocation;
+pub mod claims;
+pub mod cooldown;
+pub mod credentials;
+pub mod discount;
 pub mod engine;
+pub mod inventory;
+pub mod lifecycle;
 pub mod portfolio;
+pub mod reconcile;
 pub mod risk;
+pub mod router;
+pub mod simulation;
+pub mod sizing;
+pub mod throttle;
 
+pub use activity::{AccountActivity, AccountAnomaly, ActivityConfig};
+pub use admin::admin_routes;
+pub use allocation::{LegSizes, ProfitAllocation};
+pub use claims::{ClaimOutcome, ClaimStore, ClaimsConfig, LocalClaimStore, NatsClaimStore, OpportunityClaims};
+pub use cooldown::{Cooldown, CooldownConfig, CooldownList, CooldownRoute, FailureKind};
+pub use credentials::{credential_routes, CredentialRotation, ReloadRequest, RotationOutcome};
+pub use discount::{DiscountAsset, DiscountBalance, FeeDiscountConfig};
 pub use engine::ExecutionEngine;
+pub use inventory::{InventoryConfig, InventoryDrift, InventoryTarget, RebalanceAction, RebalancePlan};
+pub use lifecycle::{LifecycleConfig, PairEvent, PairLifecycle, PairState, UnwindPolicy};
 pub use portfolio::Portfolio;
-pub use risk::RiskManager;
+pub use reconcile::{Discrepancy, ReconcileConfig, ReconciliationReport};
+pub use risk::{RiskCheck, RiskConfig, RiskManager, RiskRule};
+pub use router::{ExecutionMode, RouteDecision, VenueQuote, VenueRouter};
+pub use simulation::{SimulationRequest, SimulationResult, TradeSimulator};
+pub use sizing::{AdaptiveSizer, FillQuality, PairSizing, SizingConfig, VenuePair};
+pub use throttle::{OrderThrottle, ThrottleConfig};
 
 #[derive(Debug, Clone)]
 pub struct ExecutionConfig {
@@ -16,6 +46,45 @@ pub struct ExecutionConfig {
     pub max_daily_loss: Decimal,
     pub max_orders_per_second: u32,
     pub enable_paper_trading: bool,
+    pub order_retry: RetryPolicy,
+    pub mode: ExecutionMode,
+    /// Currency arbitrage profit is kept in, which sets the legs' sizes.
+    pub profit_allocation: ProfitAllocation,
+    /// Target balances to report on and rebalance toward; `None` disables
+    /// inventory management.
+    pub inventory: Option<InventoryConfig>,
+    /// Cross-venue check that book updates must pass before the engine
+    /// uses them; `None` accepts every update.
+    pub price_sanity: Option<PriceSanityConfig>,
+    /// Rollout rules consulted by maker-first routing, shared with the
+    /// admin API so they can be changed at runtime.
+    pub feature_flags: FeatureFlags,
+    /// Backoff for routes whose arbitrage executions failed.
+    pub cooldown: CooldownConfig,
+    /// Checks for orders and balance changes this instance did not make.
+    pub activity: ActivityConfig,
+    /// Checks for pairs halted or delisted, and what to do about them.
+    pub lifecycle: LifecycleConfig,
+    /// How often venue status pages are read, and how long before
+    /// scheduled maintenance orders to the venue stop.
+    pub venue_status: VenueStatusConfig,
+    /// How the per-venue order rate limit is shared between strategies
+    /// once it is reached.
+    pub throttle: ThrottleConfig,
+    /// Claims opportunities in NATS before acting on them, so that only one
+    /// of several executors does; `None` runs alone.
+    pub claims: Option<ClaimsConfig>,
+    /// Daily comparison of the orders placed with each venue's records;
+    /// `None` disables it.
+    pub reconciliation: Option<ReconcileConfig>,
+    /// Full books, or only the best bid and offer of each venue.
+    pub market_data: MarketDataMode,
+    /// Scales arbitrage sizes by how well each venue pair has been filling;
+    /// `None` trades the quantities asked for.
+    pub sizing: Option<SizingConfig>,
+    /// Fee discount assets to keep balances of and route at discounted
+    /// rates while they last; `None` pays every venue's standard fees.
+    pub fee_discounts: Option<FeeDiscountConfig>,
 }
 
 impl Default for ExecutionConfig {
@@ -25,6 +94,22 @@ impl Default for ExecutionConfig {
             max_daily_loss: Decimal::from(500),
             max_orders_per_second: 10,
             enable_paper_trading: true,
+            order_retry: RetryPolicy::orders(),
+            mode: ExecutionMode::Taker,
+            profit_allocation: ProfitAllocation::Quote,
+            inventory: None,
+            price_sanity: None,
+            feature_flags: FeatureFlags::new(),
+            cooldown: CooldownConfig::default(),
+            activity: ActivityConfig::default(),
+            lifecycle: LifecycleConfig::default(),
+            venue_status: VenueStatusConfig::default(),
+            throttle: ThrottleConfig::default(),
+            claims: None,
+            reconciliation: None,
+            market_data: MarketDataMode::Full,
+            sizing: None,
+            fee_discounts: None,
         }
     }
 }
@@ -43,6 +128,24 @@ pub enum ExecutionEvent {
     },
 }
 
+impl ExecutionEvent {
+    /// Stamps the event with the current time before it is queued.
+    pub fn stamped(self) -> TimedExecutionEvent {
+        TimedExecutionEvent {
+            time: EventTime::now(),
+            event: self,
+        }
+    }
+}
+
+/// An [`ExecutionEvent`] with the time it was raised, so queueing delay is
+/// measured on the monotonic clock.
+#[derive(Debug, Clone)]
+pub struct TimedExecutionEvent {
+    pub time: EventTime,
+    pub event: ExecutionEvent,
+}
+
 #[derive(Debug, Clone)]
 pub struct TradingSignal {
     pub side: OrderSide,
@@ -53,5 +156,18 @@ pub struct TradingSignal {
 }
 
 pub mod prelude {
-    pub use super::{ExecutionEngine, Portfolio, RiskManager, ExecutionConfig, ExecutionEvent, TradingSignal};
+    pub use super::{ExecutionEngine, Portfolio, RiskManager, RiskCheck, RiskRule, ExecutionConfig, ExecutionEvent, TimedExecutionEvent, TradingSignal};
+    pub use super::activity::{AccountActivity, AccountAnomaly, ActivityConfig};
+    pub use super::allocation::{LegSizes, ProfitAllocation};
+    pub use super::claims::{ClaimOutcome, ClaimStore, ClaimsConfig, LocalClaimStore, NatsClaimStore, OpportunityClaims};
+    pub use super::cooldown::{Cooldown, CooldownConfig, CooldownList, CooldownRoute, FailureKind};
+    pub use super::credentials::{CredentialRotation, ReloadRequest, RotationOutcome};
+    pub use super::discount::{DiscountAsset, DiscountBalance, FeeDiscountConfig};
+    pub use super::inventory::{InventoryConfig, InventoryDrift, InventoryTarget, RebalanceAction, RebalancePlan};
+    pub use super::lifecycle::{LifecycleConfig, PairEvent, PairLifecycle, PairState, UnwindPolicy};
+    pub use super::reconcile::{Discrepancy, ReconcileConfig, ReconciliationReport};
+    pub use super::router::{ExecutionMode, RouteDecision, VenueQuote, VenueRouter};
+    pub use super::simulation::{SimulationRequest, SimulationResult, TradeSimulator};
+    pub use super::sizing::{AdaptiveSizer, FillQuality, PairSizing, SizingConfig, VenuePair};
+    pub use super::throttle::{OrderThrottle, ThrottleConfig};
 }
\ No newline at end of file
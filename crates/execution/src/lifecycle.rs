@@ -0,0 +1,183 @@
+//! Trading pair lifecycle: halts and delistings.
+//!
+//! Venues halt pairs for maintenance and delist them for good, usually with
+//! little notice. Each pair's status is polled; once a pair stops trading
+//! on a venue, the engine stops sending it orders there, cancels the orders
+//! it has open, and unwinds its position per the configured policy.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+use arbfinder_core::prelude::*;
+use arbfinder_exchange::SymbolInfo;
+
+/// What to do with a position in a pair that stops trading on a venue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum UnwindPolicy {
+    /// Keep the position and leave it to the operator.
+    #[default]
+    Hold,
+    /// Close the position at market on another venue still trading the
+    /// pair.
+    Close,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LifecycleConfig {
+    /// How often each venue's pair statuses are checked.
+    pub check_interval: Duration,
+    pub unwind: UnwindPolicy,
+}
+
+impl Default for LifecycleConfig {
+    fn default() -> Self {
+        Self {
+            check_interval: Duration::from_secs(300),
+            unwind: UnwindPolicy::Hold,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PairState {
+    Trading,
+    /// Listed, with the venue's status for it.
+    Halted(String),
+    Delisted,
+}
+
+impl PairState {
+    /// The state `info` describes, from a symbol info lookup. Lookups that
+    /// failed for any reason other than the symbol being gone say nothing.
+    pub fn from_lookup(info: &Result<SymbolInfo>) -> Option<Self> {
+        match info {
+            Ok(info) if info.status.eq_ignore_ascii_case("TRADING") => Some(PairState::Trading),
+            Ok(info) => Some(PairState::Halted(info.status.clone())),
+            Err(ArbFinderError::SymbolNotFound(_)) => Some(PairState::Delisted),
+            Err(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for PairState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PairState::Trading => write!(f, "trading"),
+            PairState::Halted(status) => write!(f, "halted ({})", status),
+            PairState::Delisted => write!(f, "delisted"),
+        }
+    }
+}
+
+/// A pair that changed state on a venue.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PairEvent {
+    pub venue: VenueId,
+    pub symbol: Symbol,
+    /// `None` the first time the pair is seen.
+    pub previous: Option<PairState>,
+    pub current: PairState,
+}
+
+impl PairEvent {
+    /// Whether the pair has stopped trading.
+    pub fn stopped(&self) -> bool {
+        self.current != PairState::Trading
+    }
+}
+
+impl fmt::Display for PairEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.previous {
+            Some(previous) => write!(
+                f,
+                "{} on {} went from {} to {}",
+                self.symbol, self.venue, previous, self.current
+            ),
+            None => write!(f, "{} on {} is {}", self.symbol, self.venue, self.current),
+        }
+    }
+}
+
+/// Last known state of each pair on each venue. Cloning shares the states.
+#[derive(Debug, Clone)]
+pub struct PairLifecycle {
+    config: LifecycleConfig,
+    states: Arc<parking_lot::RwLock<HashMap<(VenueId, Symbol), PairState>>>,
+}
+
+impl PairLifecycle {
+    pub fn new(config: LifecycleConfig) -> Self {
+        Self {
+            config,
+            states: Arc::new(parking_lot::RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn config(&self) -> &LifecycleConfig {
+        &self.config
+    }
+
+    /// Records `symbol`'s state on `venue`, returning the change if there
+    /// was one. A pair first seen trading is not a change.
+    pub fn on_state(&self, venue: &VenueId, symbol: &Symbol, state: PairState) -> Option<PairEvent> {
+        let previous = self.states.write().insert((venue.clone(), symbol.clone()), state.clone());
+        match &previous {
+            Some(previous) if *previous == state => None,
+            None if state == PairState::Trading => None,
+            _ => Some(PairEvent {
+                venue: venue.clone(),
+                symbol: symbol.clone(),
+                previous,
+                current: state,
+            }),
+        }
+    }
+
+    /// Whether `symbol` trades on `venue`, as far as is known.
+    pub fn is_tradable(&self, venue: &VenueId, symbol: &Symbol) -> bool {
+        match self.states.read().get(&(venue.clone(), symbol.clone())) {
+            Some(state) => *state == PairState::Trading,
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_halt_and_resume() {
+        let lifecycle = PairLifecycle::new(LifecycleConfig::default());
+        let (venue, symbol) = (VenueId::Binance, Symbol::new("LUNA", "USDT"));
+
+        assert!(lifecycle.on_state(&venue, &symbol, PairState::Trading).is_none());
+        assert!(lifecycle.on_state(&venue, &symbol, PairState::Trading).is_none());
+        assert!(lifecycle.is_tradable(&venue, &symbol));
+
+        let halt = lifecycle
+            .on_state(&venue, &symbol, PairState::Halted("BREAK".to_string()))
+            .unwrap();
+        assert!(halt.stopped());
+        assert_eq!(halt.previous, Some(PairState::Trading));
+        assert!(!lifecycle.is_tradable(&venue, &symbol));
+        assert!(lifecycle.is_tradable(&VenueId::Kraken, &symbol));
+
+        let resumed = lifecycle.on_state(&venue, &symbol, PairState::Trading).unwrap();
+        assert!(!resumed.stopped());
+        let delisted = lifecycle.on_state(&venue, &symbol, PairState::Delisted).unwrap();
+        assert_eq!(delisted.to_string(), "LUNA/USDT on binance went from trading to delisted");
+
+        // A pair already delisted when first checked is reported too
+        let gone = Symbol::new("FTT", "USDT");
+        assert!(lifecycle.on_state(&venue, &gone, PairState::Delisted).is_some());
+        assert_eq!(
+            PairState::from_lookup(&Err(ArbFinderError::SymbolNotFound("FTTUSDT".to_string()))),
+            Some(PairState::Delisted)
+        );
+        assert_eq!(PairState::from_lookup(&Err(ArbFinderError::Exchange("timeout".to_string()))), None);
+    }
+}
@@ -8,7 +8,8 @@ use arbfinder_core::prelude::*;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Portfolio {
     pub balances: HashMap<String, Balance>,
-    pub positions: HashMap<String, Position>,
+    #[serde(with = "arbfinder_core::types::symbol_id::by_pair")]
+    pub positions: HashMap<SymbolId, Position>,
     pub pending_orders: HashMap<String, Order>,
     pub trades: Vec<Trade>,
     pub pnl: Decimal,
@@ -182,8 +183,8 @@ impl Portfolio {
         self.last_updated = Utc::now();
     }
 
-    pub fn update_position_price(&mut self, symbol: &str, current_price: Decimal) {
-        if let Some(position) = self.positions.get_mut(symbol) {
+    pub fn update_position_price(&mut self, symbol: SymbolId, current_price: Decimal) {
+        if let Some(position) = self.positions.get_mut(&symbol) {
             position.current_price = current_price;
             // Calculate PnL directly to avoid borrowing issues
             let pnl = match position.side {
@@ -196,6 +197,21 @@ impl Portfolio {
         self.last_updated = Utc::now();
     }
 
+    /// Signed size of the open position in `symbol`, short being negative.
+    /// Without a tracked position this is the spot holding of the base asset.
+    pub fn net_position(&self, symbol: SymbolId) -> Decimal {
+        match self.positions.get(&symbol) {
+            Some(position) => match position.side {
+                OrderSide::Buy => position.size,
+                OrderSide::Sell => -position.size,
+            },
+            None => self
+                .get_balance(symbol.symbol().base())
+                .map(|balance| balance.total)
+                .unwrap_or_default(),
+        }
+    }
+
     pub fn get_total_value(&self, prices: &HashMap<String, Decimal>) -> Decimal {
         let mut total = Decimal::ZERO;
         
@@ -290,7 +306,7 @@ impl Portfolio {
             arbfinder_core::Side::Bid => OrderSide::Buy,
             arbfinder_core::Side::Ask => OrderSide::Sell,
         };
-        let position = self.positions.entry(trade.symbol.to_pair()).or_insert(Position {
+        let position = self.positions.entry(trade.symbol.id()).or_insert(Position {
             symbol: trade.symbol.to_pair(),
             side: trade_side,
             size: Decimal::ZERO,
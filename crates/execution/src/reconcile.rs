@@ -0,0 +1,351 @@
+//! Reconciliation against venue records.
+//!
+//! The engine knows its orders from what it placed and the updates it saw
+//! since it started. Once a day each venue's own order and trade history
+//! is pulled and compared with that: an order or fill the venue has that
+//! the engine doesn't, an order the engine placed that the venue never
+//! reports, or fills adding up to a different quantity than recorded each
+//! become a discrepancy. Every run writes a report, and one with
+//! discrepancies is alerted on.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use chrono::{DateTime, NaiveTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use arbfinder_core::prelude::*;
+use arbfinder_core::utils::ReportLocale;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReconcileConfig {
+    /// Time of day, in UTC, the daily run starts.
+    pub run_at: NaiveTime,
+    /// Where reports are written.
+    pub report_dir: PathBuf,
+    /// Most orders and trades asked of each venue per run.
+    pub history_limit: u32,
+}
+
+impl Default for ReconcileConfig {
+    fn default() -> Self {
+        Self {
+            run_at: NaiveTime::from_hms_opt(0, 30, 0).expect("valid time"),
+            report_dir: PathBuf::from("reports/reconciliation"),
+            history_limit: 500,
+        }
+    }
+}
+
+impl ReconcileConfig {
+    /// When the first run after `now` starts.
+    pub fn next_run(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        let today = now.date_naive().and_time(self.run_at).and_utc();
+        if today > now {
+            today
+        } else {
+            today + chrono::Duration::days(1)
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Discrepancy {
+    /// An order on the venue this instance did not place.
+    UnknownOrder {
+        venue_order_id: Option<String>,
+        symbol: Symbol,
+        side: OrderSide,
+        status: OrderStatus,
+    },
+    /// A fill on the venue of an order this instance did not place.
+    UnknownFill {
+        fill_id: String,
+        venue_order_id: String,
+        quantity: Decimal,
+    },
+    /// An order this instance placed that the venue does not report.
+    MissingOrder {
+        order_id: OrderId,
+        venue_order_id: Option<String>,
+        symbol: Symbol,
+    },
+    /// An order the venue reports a different filled quantity for than
+    /// this instance recorded.
+    FillMismatch {
+        order_id: OrderId,
+        venue_order_id: Option<String>,
+        recorded: Decimal,
+        reported: Decimal,
+    },
+}
+
+impl Discrepancy {
+    pub fn render(&self, locale: &ReportLocale) -> String {
+        let id = |id: &Option<String>| id.clone().unwrap_or_else(|| "without an id".to_string());
+        match self {
+            Discrepancy::UnknownOrder { venue_order_id, symbol, side, status } => format!(
+                "{} {} order {} ({}) was not placed by this instance",
+                side,
+                symbol,
+                id(venue_order_id),
+                status
+            ),
+            Discrepancy::UnknownFill { fill_id, venue_order_id, quantity } => format!(
+                "fill {} of {} on order {} is not of an order this instance placed",
+                fill_id,
+                locale.decimal(*quantity),
+                venue_order_id
+            ),
+            Discrepancy::MissingOrder { order_id, venue_order_id, symbol } => format!(
+                "{} order {} (venue id {}) is not in the venue's history",
+                symbol,
+                order_id,
+                id(venue_order_id)
+            ),
+            Discrepancy::FillMismatch { order_id, recorded, reported, .. } => format!(
+                "order {} filled {} by the venue's records but {} by ours",
+                order_id,
+                locale.decimal(*reported),
+                locale.decimal(*recorded)
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Discrepancy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(&ReportLocale::default()))
+    }
+}
+
+/// One venue's reconciliation.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReconciliationReport {
+    pub venue: VenueId,
+    /// Start of the period compared.
+    pub since: DateTime<Utc>,
+    pub generated_at: DateTime<Utc>,
+    pub orders_checked: usize,
+    pub fills_checked: usize,
+    pub discrepancies: Vec<Discrepancy>,
+}
+
+impl ReconciliationReport {
+    pub fn is_clean(&self) -> bool {
+        self.discrepancies.is_empty()
+    }
+
+    /// Writes the report as JSON into `dir`, returning the file's path.
+    pub fn write(&self, dir: &Path) -> Result<PathBuf> {
+        std::fs::create_dir_all(dir)?;
+        let path = dir.join(format!(
+            "reconciliation-{}-{}.json",
+            self.venue,
+            self.generated_at.format("%Y%m%dT%H%M%SZ")
+        ));
+        let json = serde_json::to_vec_pretty(self).map_err(|e| ArbFinderError::Internal(e.to_string()))?;
+        std::fs::write(&path, json)?;
+        Ok(path)
+    }
+
+    /// The summary line, with the period's start as `locale` has it.
+    pub fn render(&self, locale: &ReportLocale) -> String {
+        format!(
+            "{} reconciliation since {}: {} orders and {} fills checked, {} discrepancies",
+            self.venue,
+            locale.timestamp(self.since),
+            self.orders_checked,
+            self.fills_checked,
+            self.discrepancies.len()
+        )
+    }
+}
+
+impl fmt::Display for ReconciliationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.render(&ReportLocale::default()))
+    }
+}
+
+/// Compares the orders this instance recorded on `venue` with the venue's
+/// orders and fills, all from `since` on. Without the venue's fills, filled
+/// quantities are compared with its orders'.
+pub fn reconcile(
+    venue: VenueId,
+    since: DateTime<Utc>,
+    recorded: &[Order],
+    venue_orders: &[Order],
+    venue_fills: Option<&[OrderFill]>,
+    now: DateTime<Utc>,
+) -> ReconciliationReport {
+    let recorded: Vec<&Order> = recorded.iter().filter(|order| order.created_at >= since).collect();
+    let venue_orders: Vec<&Order> = venue_orders.iter().filter(|order| order.created_at >= since).collect();
+    let venue_fills: Option<Vec<&OrderFill>> =
+        venue_fills.map(|fills| fills.iter().filter(|fill| fill.timestamp >= since).collect());
+
+    // Ours by the venue's id and by the client id sent with them
+    let by_venue_id: HashMap<&str, &Order> = recorded
+        .iter()
+        .filter_map(|order| Some((order.venue_order_id.as_deref()?, *order)))
+        .collect();
+    let by_client_id: HashMap<&str, &Order> = recorded
+        .iter()
+        .filter_map(|order| Some((order.client_order_id.as_deref()?, *order)))
+        .collect();
+
+    let mut discrepancies = Vec::new();
+    let mut matched: HashMap<&OrderId, &Order> = HashMap::new();
+    for order in &venue_orders {
+        let ours = order
+            .venue_order_id
+            .as_deref()
+            .and_then(|id| by_venue_id.get(id))
+            .or_else(|| order.client_order_id.as_deref().and_then(|id| by_client_id.get(id)));
+        match ours {
+            Some(ours) => {
+                matched.insert(&ours.id, order);
+            }
+            None => discrepancies.push(Discrepancy::UnknownOrder {
+                venue_order_id: order.venue_order_id.clone(),
+                symbol: order.symbol.clone(),
+                side: order.side,
+                status: order.status,
+            }),
+        }
+    }
+
+    let mut filled: HashMap<&str, Decimal> = HashMap::new();
+    for fill in venue_fills.iter().flatten() {
+        if by_venue_id.contains_key(fill.venue_order_id.as_str()) {
+            *filled.entry(fill.venue_order_id.as_str()).or_default() += fill.quantity;
+        } else {
+            discrepancies.push(Discrepancy::UnknownFill {
+                fill_id: fill.id.clone(),
+                venue_order_id: fill.venue_order_id.clone(),
+                quantity: fill.quantity,
+            });
+        }
+    }
+
+    let mut seen = HashSet::new();
+    for order in &recorded {
+        if !seen.insert(&order.id) {
+            continue;
+        }
+        let venue_order = matched.get(&order.id);
+        if venue_order.is_none() && order.venue_order_id.is_none() && order.filled_quantity.is_zero() {
+            // Never acknowledged, so the venue has nothing to report
+            continue;
+        }
+        let reported = match (&venue_fills, venue_order) {
+            (Some(_), _) => order
+                .venue_order_id
+                .as_deref()
+                .and_then(|id| filled.get(id).copied())
+                .unwrap_or_default(),
+            (None, Some(venue_order)) => venue_order.filled_quantity,
+            (None, None) => order.filled_quantity,
+        };
+        if venue_order.is_none() && reported.is_zero() {
+            discrepancies.push(Discrepancy::MissingOrder {
+                order_id: order.id.clone(),
+                venue_order_id: order.venue_order_id.clone(),
+                symbol: order.symbol.clone(),
+            });
+        } else if reported != order.filled_quantity {
+            discrepancies.push(Discrepancy::FillMismatch {
+                order_id: order.id.clone(),
+                venue_order_id: order.venue_order_id.clone(),
+                recorded: order.filled_quantity,
+                reported,
+            });
+        }
+    }
+
+    ReconciliationReport {
+        venue,
+        since,
+        generated_at: now,
+        orders_checked: venue_orders.len(),
+        fills_checked: venue_fills.map(|fills| fills.len()).unwrap_or_default(),
+        discrepancies,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn order(venue_order_id: &str, filled: Decimal) -> Order {
+        let mut order = Order::new_limit(VenueId::Binance, Symbol::new("BTC", "USDT"), OrderSide::Buy, dec!(1), dec!(30000));
+        order.venue_order_id = Some(venue_order_id.to_string());
+        order.filled_quantity = filled;
+        order
+    }
+
+    fn fill(id: &str, venue_order_id: &str, quantity: Decimal) -> OrderFill {
+        OrderFill {
+            id: id.to_string(),
+            order_id: OrderId::new(),
+            venue_order_id: venue_order_id.to_string(),
+            price: dec!(30000),
+            quantity,
+            fee: None,
+            timestamp: Utc::now(),
+            is_maker: false,
+        }
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let since = Utc::now() - chrono::Duration::days(1);
+        let recorded = vec![order("1", dec!(1)), order("2", dec!(0.5)), order("3", Decimal::ZERO)];
+        let venue_orders = vec![order("1", dec!(1)), order("2", dec!(1)), order("9", dec!(1))];
+        let fills = vec![fill("a", "1", dec!(1)), fill("b", "2", dec!(1)), fill("c", "9", dec!(1))];
+
+        let report = reconcile(VenueId::Binance, since, &recorded, &venue_orders, Some(&fills), Utc::now());
+        assert_eq!(report.orders_checked, 3);
+        assert_eq!(report.fills_checked, 3);
+        let kinds: Vec<_> = report
+            .discrepancies
+            .iter()
+            .map(|d| serde_json::to_value(d).unwrap()["kind"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(kinds, ["unknown_order", "unknown_fill", "fill_mismatch", "missing_order"]);
+        assert_eq!(
+            report.discrepancies[2],
+            Discrepancy::FillMismatch {
+                order_id: recorded[1].id.clone(),
+                venue_order_id: Some("2".to_string()),
+                recorded: dec!(0.5),
+                reported: dec!(1),
+            }
+        );
+
+        // Everything accounted for, and orders from before `since` ignored
+        let mut old = order("0", dec!(1));
+        old.created_at = since - chrono::Duration::hours(1);
+        let report = reconcile(
+            VenueId::Binance,
+            since,
+            &[recorded[0].clone(), old],
+            &venue_orders[..1],
+            None,
+            Utc::now(),
+        );
+        assert!(report.is_clean(), "{:?}", report.discrepancies);
+    }
+
+    #[test]
+    fn test_next_run() {
+        let config = ReconcileConfig::default();
+        let before = "2024-03-01T00:10:00Z".parse().unwrap();
+        assert_eq!(config.next_run(before), "2024-03-01T00:30:00Z".parse::<DateTime<Utc>>().unwrap());
+        let after = "2024-03-01T00:30:00Z".parse().unwrap();
+        assert_eq!(config.next_run(after), "2024-03-02T00:30:00Z".parse::<DateTime<Utc>>().unwrap());
+    }
+}
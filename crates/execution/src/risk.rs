@@ -1,4 +1,6 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, OnceLock};
 use rust_decimal::Decimal;
 use rust_decimal::prelude::ToPrimitive;
 use chrono::{DateTime, Utc};
@@ -6,6 +8,7 @@ use chrono::Duration as ChronoDuration;
 use tracing::warn;
 
 use arbfinder_core::prelude::*;
+use arbfinder_monitoring::MetricsCollector;
 
 #[derive(Debug, Clone)]
 pub struct RiskConfig {
@@ -36,8 +39,58 @@ impl Default for RiskConfig {
     }
 }
 
+/// A pre-trade check an order can fail.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RiskRule {
+    SymbolAllowed,
+    MaxOrderSize,
+    MinOrderSize,
+    PositionSize,
+    DailyLoss,
+    Drawdown,
+    OrderRate,
+}
+
+impl RiskRule {
+    /// The rule's metric label.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RiskRule::SymbolAllowed => "symbol_allowed",
+            RiskRule::MaxOrderSize => "max_order_size",
+            RiskRule::MinOrderSize => "min_order_size",
+            RiskRule::PositionSize => "position_size",
+            RiskRule::DailyLoss => "daily_loss",
+            RiskRule::Drawdown => "drawdown",
+            RiskRule::OrderRate => "order_rate",
+        }
+    }
+}
+
+impl fmt::Display for RiskRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// The outcome of the pre-trade checks on an order.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RiskCheck {
+    /// The first rule the order failed, `None` if it passed.
+    pub rejected_by: Option<RiskRule>,
+    /// How much of each upper limit the order would use, as a fraction of
+    /// the limit; above 1 breaks it.
+    pub utilization: Vec<(RiskRule, f64)>,
+}
+
+impl RiskCheck {
+    pub fn passed(&self) -> bool {
+        self.rejected_by.is_none()
+    }
+}
+
 pub struct RiskManager {
     config: RiskConfig,
+    metrics: OnceLock<Arc<MetricsCollector>>,
     daily_pnl: Decimal,
     daily_reset_time: DateTime<Utc>,
     order_history: Vec<(DateTime<Utc>, String)>, // (timestamp, symbol)
@@ -53,6 +106,7 @@ impl RiskManager {
     pub fn with_config(config: RiskConfig) -> Self {
         Self {
             config,
+            metrics: OnceLock::new(),
             daily_pnl: Decimal::ZERO,
             daily_reset_time: Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc(),
             order_history: Vec::new(),
@@ -61,6 +115,16 @@ impl RiskManager {
         }
     }
 
+    pub fn config(&self) -> &RiskConfig {
+        &self.config
+    }
+
+    /// Records every check's outcome, and how close orders came to each
+    /// limit, to `metrics`. Only the first collector set is kept.
+    pub fn set_metrics(&self, metrics: Arc<MetricsCollector>) {
+        let _ = self.metrics.set(metrics);
+    }
+
     pub async fn check_order_risk(
         &self,
         symbol: &str,
@@ -68,49 +132,86 @@ impl RiskManager {
         price: Decimal,
         amount: Decimal,
     ) -> bool {
-        // Check if symbol is allowed
-        if !self.is_symbol_allowed(symbol) {
-            warn!("Symbol {} is not allowed for trading", symbol);
-            return false;
-        }
-
-        // Check order size limits
-        let order_value = price * amount;
-        if order_value > self.config.max_order_size {
-            warn!("Order size {} exceeds maximum allowed {}", order_value, self.config.max_order_size);
-            return false;
-        }
-
-        if order_value < self.config.min_order_size {
-            warn!("Order size {} below minimum required {}", order_value, self.config.min_order_size);
-            return false;
+        let check = self.evaluate_order(symbol, side, price, amount);
+        if let Some(rule) = check.rejected_by {
+            let order_value = price * amount;
+            match rule {
+                RiskRule::SymbolAllowed => warn!("Symbol {} is not allowed for trading", symbol),
+                RiskRule::MaxOrderSize => warn!("Order size {} exceeds maximum allowed {}", order_value, self.config.max_order_size),
+                RiskRule::MinOrderSize => warn!("Order size {} below minimum required {}", order_value, self.config.min_order_size),
+                RiskRule::PositionSize => warn!("Position size limit exceeded for {}", symbol),
+                RiskRule::DailyLoss => warn!("Daily loss limit exceeded"),
+                RiskRule::Drawdown => warn!("Maximum drawdown limit exceeded"),
+                RiskRule::OrderRate => warn!("Order rate limit exceeded for {}", symbol),
+            }
         }
 
-        // Check position size limits
-        if !self.check_position_size_limit(symbol, side, amount) {
-            warn!("Position size limit exceeded for {}", symbol);
-            return false;
+        if let Some(metrics) = self.metrics.get() {
+            metrics.record_risk_check(check.rejected_by.map(|rule| rule.as_str()));
+            for (rule, utilization) in &check.utilization {
+                metrics.record_risk_utilization(rule.as_str(), *utilization);
+            }
         }
 
-        // Check daily loss limit
-        if !self.check_daily_loss_limit() {
-            warn!("Daily loss limit exceeded");
-            return false;
-        }
+        check.passed()
+    }
 
-        // Check drawdown limit
-        if !self.check_drawdown_limit() {
-            warn!("Maximum drawdown limit exceeded");
-            return false;
-        }
+    /// Runs the pre-trade checks on an order, in order, stopping at the
+    /// first failed. Utilization covers every limit checked, including the
+    /// one failed.
+    pub fn evaluate_order(&self, symbol: &str, side: OrderSide, price: Decimal, amount: Decimal) -> RiskCheck {
+        let mut check = RiskCheck {
+            rejected_by: None,
+            utilization: Vec::new(),
+        };
+        let mut utilize = |rule: RiskRule, value: Decimal, limit: Decimal| {
+            // A zero limit has no meaningful fraction
+            if let Some(fraction) = value.checked_div(limit).and_then(|fraction| fraction.to_f64()) {
+                check.utilization.push((rule, fraction));
+            }
+        };
 
-        // Check order rate limit
-        if !self.check_order_rate_limit(symbol) {
-            warn!("Order rate limit exceeded for {}", symbol);
-            return false;
-        }
+        // Check if symbol is allowed
+        let rejected_by = if !self.is_symbol_allowed(symbol) {
+            Some(RiskRule::SymbolAllowed)
+        } else {
+            // Check order size limits
+            let order_value = price * amount;
+            utilize(RiskRule::MaxOrderSize, order_value, self.config.max_order_size);
+            if order_value > self.config.max_order_size {
+                Some(RiskRule::MaxOrderSize)
+            } else if order_value < self.config.min_order_size {
+                Some(RiskRule::MinOrderSize)
+            } else {
+                // Check position size limits
+                utilize(RiskRule::PositionSize, self.new_position_size(symbol, side, amount), self.config.max_position_size);
+                // Check daily loss limit
+                utilize(RiskRule::DailyLoss, -self.daily_pnl.min(Decimal::ZERO), self.config.max_daily_loss);
+                // Check drawdown limit
+                utilize(RiskRule::Drawdown, -self.max_drawdown_reached.min(Decimal::ZERO), self.config.max_drawdown);
+                // Check order rate limit, counting this order
+                utilize(
+                    RiskRule::OrderRate,
+                    Decimal::from(self.recent_orders(symbol) + 1),
+                    Decimal::from(self.config.max_orders_per_minute),
+                );
+
+                if !self.check_position_size_limit(symbol, side, amount) {
+                    Some(RiskRule::PositionSize)
+                } else if !self.check_daily_loss_limit() {
+                    Some(RiskRule::DailyLoss)
+                } else if !self.check_drawdown_limit() {
+                    Some(RiskRule::Drawdown)
+                } else if !self.check_order_rate_limit(symbol) {
+                    Some(RiskRule::OrderRate)
+                } else {
+                    None
+                }
+            }
+        };
 
-        true
+        check.rejected_by = rejected_by;
+        check
     }
 
     pub fn update_daily_pnl(&mut self, pnl_change: Decimal) {
@@ -166,15 +267,17 @@ impl RiskManager {
             .replace('-', "")
     }
 
-    fn check_position_size_limit(&self, symbol: &str, side: OrderSide, amount: Decimal) -> bool {
+    fn new_position_size(&self, symbol: &str, side: OrderSide, amount: Decimal) -> Decimal {
         let current_size = self.position_sizes.get(symbol).copied().unwrap_or(Decimal::ZERO);
         
-        let new_size = match side {
+        match side {
             OrderSide::Buy => current_size + amount,
             OrderSide::Sell => (current_size - amount).abs(),
-        };
+        }
+    }
 
-        new_size <= self.config.max_position_size
+    fn check_position_size_limit(&self, symbol: &str, side: OrderSide, amount: Decimal) -> bool {
+        self.new_position_size(symbol, side, amount) <= self.config.max_position_size
     }
 
     fn check_daily_loss_limit(&self) -> bool {
@@ -185,15 +288,17 @@ impl RiskManager {
         self.max_drawdown_reached >= -self.config.max_drawdown
     }
 
-    fn check_order_rate_limit(&self, symbol: &str) -> bool {
+    fn recent_orders(&self, symbol: &str) -> usize {
         let cutoff = Utc::now() - ChronoDuration::minutes(1);
-        let recent_orders = self.order_history.iter()
+        self.order_history.iter()
             .filter(|(timestamp, order_symbol)| {
                 *timestamp > cutoff && order_symbol == symbol
             })
-            .count();
+            .count()
+    }
 
-        recent_orders < self.config.max_orders_per_minute as usize
+    fn check_order_rate_limit(&self, symbol: &str) -> bool {
+        self.recent_orders(symbol) < self.config.max_orders_per_minute as usize
     }
 
     fn reset_daily_if_needed(&mut self) {
@@ -279,4 +384,44 @@ impl Default for RiskManager {
     fn default() -> Self {
         Self::new()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[tokio::test]
+    async fn test_decision_metrics() {
+        let mut risk = RiskManager::with_config(RiskConfig {
+            max_order_size: dec!(1000),
+            max_position_size: dec!(10),
+            blocked_symbols: vec!["LUNA/USDT".to_string()],
+            ..RiskConfig::default()
+        });
+        let metrics = Arc::new(MetricsCollector::new());
+        risk.set_metrics(Arc::clone(&metrics));
+
+        let check = risk.evaluate_order("BTC/USDT", OrderSide::Buy, dec!(100), dec!(5));
+        assert!(check.passed());
+        assert!(check.utilization.contains(&(RiskRule::MaxOrderSize, 0.5)));
+        assert!(check.utilization.contains(&(RiskRule::PositionSize, 0.5)));
+        assert!(check.utilization.contains(&(RiskRule::DailyLoss, 0.0)));
+
+        risk.update_position_size("BTC/USDT", dec!(8));
+        let check = risk.evaluate_order("BTC/USDT", OrderSide::Buy, dec!(100), dec!(5));
+        assert_eq!(check.rejected_by, Some(RiskRule::PositionSize));
+        assert!(check.utilization.contains(&(RiskRule::PositionSize, 1.3)));
+
+        assert!(risk.check_order_risk("BTC/USDT", OrderSide::Sell, dec!(100), dec!(5)).await);
+        assert!(!risk.check_order_risk("BTC/USDT", OrderSide::Buy, dec!(300), dec!(5)).await);
+        assert!(!risk.check_order_risk("LUNA_USDT", OrderSide::Buy, dec!(1), dec!(5)).await);
+
+        let checks = |result: &str, rule: &str| metrics.risk_checks.with_label_values(&[result, rule]).get();
+        assert_eq!(checks("passed", "none"), 1);
+        assert_eq!(checks("rejected", "max_order_size"), 1);
+        assert_eq!(checks("rejected", "symbol_allowed"), 1);
+        // A blocked symbol goes no further, so no limits were approached
+        assert_eq!(metrics.risk_limit_utilization.with_label_values(&["max_order_size"]).get_sample_count(), 2);
+    }
+}
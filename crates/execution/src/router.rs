@@ -0,0 +1,225 @@
+//! Venue selection for a single order.
+//!
+//! Quotes are compared on their price after fees for the liquidity the order
+//! will take. In maker-first mode that is the maker fee, which is negative on
+//! venues paying rebates, so a rebate venue wins whenever its quote is as
+//! good as the others before fees. Venues whose fees are being paid in a
+//! discount asset are weighed at the discounted rates.
+
+use std::collections::HashMap;
+use rust_decimal::Decimal;
+
+use arbfinder_core::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExecutionMode {
+    /// Cross the spread immediately.
+    #[default]
+    Taker,
+    /// Rest a post-only limit order and let it be filled.
+    MakerFirst,
+}
+
+impl ExecutionMode {
+    pub fn is_maker(&self) -> bool {
+        matches!(self, ExecutionMode::MakerFirst)
+    }
+}
+
+impl std::str::FromStr for ExecutionMode {
+    type Err = ArbFinderError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "taker" => Ok(ExecutionMode::Taker),
+            "maker_first" | "maker" => Ok(ExecutionMode::MakerFirst),
+            other => Err(ArbFinderError::InvalidData(format!("Unknown execution mode: {}", other))),
+        }
+    }
+}
+
+/// A price a venue is offering for the order being routed: the ask to join
+/// or lift for a buy, the bid for a sell.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VenueQuote {
+    pub venue: VenueId,
+    pub price: Decimal,
+}
+
+impl VenueQuote {
+    pub fn new(venue: VenueId, price: Decimal) -> Self {
+        Self { venue, price }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteDecision {
+    pub venue: VenueId,
+    pub price: Decimal,
+    pub post_only: bool,
+    pub fee_rate: Decimal,
+    /// Price per unit after fees: what a buy costs or a sell yields.
+    pub effective_price: Decimal,
+}
+
+pub struct VenueRouter {
+    mode: ExecutionMode,
+    fees: HashMap<VenueId, TradingFee>,
+    default_fee: TradingFee,
+    discounts: HashMap<VenueId, FeeDiscount>,
+}
+
+impl VenueRouter {
+    pub fn new(mode: ExecutionMode) -> Self {
+        Self {
+            mode,
+            fees: HashMap::new(),
+            default_fee: TradingFee::new(Decimal::new(1, 3), Decimal::new(1, 3)), // 0.1%
+            discounts: HashMap::new(),
+        }
+    }
+
+    pub fn mode(&self) -> ExecutionMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: ExecutionMode) {
+        self.mode = mode;
+    }
+
+    pub fn set_fees(&mut self, venue: VenueId, fees: TradingFee) {
+        self.fees.insert(venue, fees);
+    }
+
+    pub fn fees(&self, venue: &VenueId) -> &TradingFee {
+        self.fees.get(venue).unwrap_or(&self.default_fee)
+    }
+
+    /// Rates `venue` charges while its fees are paid in a discount asset;
+    /// `None` once that asset runs out and full fees apply again.
+    pub fn set_fee_discount(&mut self, venue: VenueId, discount: Option<FeeDiscount>) {
+        match discount {
+            Some(discount) => self.discounts.insert(venue, discount),
+            None => self.discounts.remove(&venue),
+        };
+    }
+
+    /// Venues currently paying a maker rebate.
+    pub fn rebate_venues(&self) -> Vec<VenueId> {
+        self.fees
+            .iter()
+            .filter(|(_, fees)| fees.has_maker_rebate())
+            .map(|(venue, _)| venue.clone())
+            .collect()
+    }
+
+    pub fn fee_rate(&self, venue: &VenueId) -> Decimal {
+        self.fee_rate_as(self.mode, venue)
+    }
+
+    fn fee_rate_as(&self, mode: ExecutionMode, venue: &VenueId) -> Decimal {
+        match self.discounts.get(venue) {
+            Some(discount) => discount.rate(mode.is_maker()),
+            None => self.fees(venue).rate(mode.is_maker()),
+        }
+    }
+
+    pub fn effective_price(&self, venue: &VenueId, side: OrderSide, price: Decimal) -> Decimal {
+        self.effective_price_as(self.mode, venue, side, price)
+    }
+
+    fn effective_price_as(&self, mode: ExecutionMode, venue: &VenueId, side: OrderSide, price: Decimal) -> Decimal {
+        let fee_rate = self.fee_rate_as(mode, venue);
+        match side {
+            OrderSide::Buy => price * (Decimal::ONE + fee_rate),
+            OrderSide::Sell => price * (Decimal::ONE - fee_rate),
+        }
+    }
+
+    /// Picks the quote with the best price after fees. Ties go to the venue
+    /// with the lower fee, so a rebate venue is preferred over an equal
+    /// quote elsewhere.
+    pub fn select(&self, side: OrderSide, quotes: &[VenueQuote]) -> Option<RouteDecision> {
+        self.select_as(self.mode, side, quotes)
+    }
+
+    /// [`Self::select`] as if the router were in `mode`.
+    pub fn select_as(&self, mode: ExecutionMode, side: OrderSide, quotes: &[VenueQuote]) -> Option<RouteDecision> {
+        quotes
+            .iter()
+            .map(|quote| RouteDecision {
+                venue: quote.venue.clone(),
+                price: quote.price,
+                post_only: mode.is_maker(),
+                fee_rate: self.fee_rate_as(mode, &quote.venue),
+                effective_price: self.effective_price_as(mode, &quote.venue, side, quote.price),
+            })
+            .min_by(|a, b| {
+                let by_price = match side {
+                    OrderSide::Buy => a.effective_price.cmp(&b.effective_price),
+                    OrderSide::Sell => b.effective_price.cmp(&a.effective_price),
+                };
+                by_price.then(a.fee_rate.cmp(&b.fee_rate))
+            })
+    }
+}
+
+impl Default for VenueRouter {
+    fn default() -> Self {
+        Self::new(ExecutionMode::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn router(mode: ExecutionMode) -> VenueRouter {
+        let mut router = VenueRouter::new(mode);
+        router.set_fees(VenueId::Binance, TradingFee::new(dec!(0.001), dec!(0.001)));
+        // Rebate for resting orders, pricier to take
+        router.set_fees(VenueId::Kraken, TradingFee::new(dec!(-0.0001), dec!(0.002)));
+        router
+    }
+
+    #[test]
+    fn test_maker_first_prefers_rebate_venue_on_equal_quotes() {
+        let quotes = [
+            VenueQuote::new(VenueId::Binance, dec!(100)),
+            VenueQuote::new(VenueId::Kraken, dec!(100)),
+        ];
+
+        let maker = router(ExecutionMode::MakerFirst).select(OrderSide::Buy, &quotes).unwrap();
+        assert_eq!(maker.venue, VenueId::Kraken);
+        assert!(maker.post_only);
+        assert_eq!(maker.effective_price, dec!(99.99));
+
+        // Taking liquidity, the rebate venue's higher taker fee loses
+        let taker = router(ExecutionMode::Taker).select(OrderSide::Buy, &quotes).unwrap();
+        assert_eq!(taker.venue, VenueId::Binance);
+        assert!(!taker.post_only);
+
+        let forced = router(ExecutionMode::MakerFirst).select_as(ExecutionMode::Taker, OrderSide::Buy, &quotes);
+        assert_eq!(forced, Some(taker));
+    }
+
+    #[test]
+    fn test_better_price_beats_rebate() {
+        let router = router(ExecutionMode::MakerFirst);
+        let quotes = [
+            VenueQuote::new(VenueId::Binance, dec!(101)),
+            VenueQuote::new(VenueId::Kraken, dec!(100)),
+        ];
+        assert_eq!(router.select(OrderSide::Sell, &quotes).unwrap().venue, VenueId::Binance);
+        assert_eq!(router.rebate_venues(), vec![VenueId::Kraken]);
+        assert!(router.select(OrderSide::Sell, &[]).is_none());
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        assert_eq!("maker-first".parse::<ExecutionMode>().unwrap(), ExecutionMode::MakerFirst);
+        assert_eq!("taker".parse::<ExecutionMode>().unwrap(), ExecutionMode::Taker);
+        assert!("aggressive".parse::<ExecutionMode>().is_err());
+    }
+}
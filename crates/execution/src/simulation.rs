@@ -0,0 +1,230 @@
+//! Dry runs of an order against the venue's current book.
+//!
+//! The order walks the opposite side of the book level by level, so the
+//! result reflects the depth actually resting there rather than the top of
+//! book alone. Nothing is sent to the venue.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use parking_lot::RwLock as SyncRwLock;
+use rust_decimal::Decimal;
+use serde::Serialize;
+use tokio::sync::RwLock;
+
+use arbfinder_core::prelude::*;
+
+use crate::{Portfolio, VenueRouter};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulationRequest {
+    pub venue: VenueId,
+    pub symbol: Symbol,
+    pub side: OrderSide,
+    pub quantity: Decimal,
+}
+
+impl SimulationRequest {
+    pub fn new(venue: VenueId, symbol: Symbol, side: OrderSide, quantity: Decimal) -> Self {
+        Self { venue, symbol, side, quantity }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SimulationResult {
+    pub venue: String,
+    pub symbol: String,
+    pub side: OrderSide,
+    pub requested_quantity: Decimal,
+    /// Less than requested when the book runs out of depth.
+    pub filled_quantity: Decimal,
+    pub levels_consumed: usize,
+    pub best_price: Decimal,
+    pub vwap: Decimal,
+    /// Distance from the best price to the VWAP, in basis points. Always
+    /// non-negative: it is what the order gives up by walking the book.
+    pub slippage_bps: Decimal,
+    pub notional: Decimal,
+    pub fee_rate: Decimal,
+    pub fees: Decimal,
+    /// Net base position before and after the fill; negative is short.
+    pub position_before: Decimal,
+    pub position_after: Decimal,
+    /// Post-trade position valued at the book's mid price.
+    pub exposure: Decimal,
+}
+
+impl SimulationResult {
+    pub fn fully_filled(&self) -> bool {
+        self.filled_quantity >= self.requested_quantity
+    }
+}
+
+/// Fills `request` against `book` as a taker paying `fees`, starting from a
+/// net base position of `position`.
+pub fn simulate_fill(
+    request: &SimulationRequest,
+    book: &OrderBook,
+    fees: &TradingFee,
+    position: Decimal,
+) -> Result<SimulationResult> {
+    if request.quantity <= Decimal::ZERO {
+        return Err(ArbFinderError::InvalidOrder(format!(
+            "Quantity must be positive, got {}",
+            request.quantity
+        )));
+    }
+
+    let levels: Vec<&OrderBookLevel> = match request.side {
+        OrderSide::Buy => book.asks.values().collect(),
+        OrderSide::Sell => book.bids.values().rev().collect(),
+    };
+    let best_price = levels.first().map(|level| level.price).ok_or_else(|| {
+        ArbFinderError::OrderBook(format!(
+            "No {} liquidity for {} on {}",
+            match request.side {
+                OrderSide::Buy => "ask",
+                OrderSide::Sell => "bid",
+            },
+            request.symbol.to_pair(),
+            request.venue
+        ))
+    })?;
+
+    let mut remaining = request.quantity;
+    let mut notional = Decimal::ZERO;
+    let mut levels_consumed = 0;
+    for level in levels {
+        if remaining <= Decimal::ZERO {
+            break;
+        }
+        let take = remaining.min(level.quantity);
+        notional += take * level.price;
+        remaining -= take;
+        levels_consumed += 1;
+    }
+
+    let filled_quantity = request.quantity - remaining;
+    let vwap = notional / filled_quantity;
+    let slippage = match request.side {
+        OrderSide::Buy => vwap - best_price,
+        OrderSide::Sell => best_price - vwap,
+    };
+    let slippage_bps = slippage / best_price * Decimal::from(10_000);
+
+    let fee_rate = fees.rate(false);
+    let position_after = match request.side {
+        OrderSide::Buy => position + filled_quantity,
+        OrderSide::Sell => position - filled_quantity,
+    };
+    let mark = book.mid_price().unwrap_or(vwap);
+
+    Ok(SimulationResult {
+        venue: request.venue.to_string(),
+        symbol: request.symbol.to_pair(),
+        side: request.side,
+        requested_quantity: request.quantity,
+        filled_quantity,
+        levels_consumed,
+        best_price,
+        vwap,
+        slippage_bps,
+        notional,
+        fee_rate,
+        fees: notional * fee_rate,
+        position_before: position,
+        position_after,
+        exposure: position_after * mark,
+    })
+}
+
+/// Handle on the engine's books, fee schedules and portfolio for running
+/// simulations from outside the engine, e.g. the admin API. Cheap to clone.
+#[derive(Clone)]
+pub struct TradeSimulator {
+    books: Arc<RwLock<HashMap<(VenueId, Symbol), OrderBook>>>,
+    router: Arc<SyncRwLock<VenueRouter>>,
+    portfolio: Arc<RwLock<Portfolio>>,
+}
+
+impl TradeSimulator {
+    pub(crate) fn new(
+        books: Arc<RwLock<HashMap<(VenueId, Symbol), OrderBook>>>,
+        router: Arc<SyncRwLock<VenueRouter>>,
+        portfolio: Arc<RwLock<Portfolio>>,
+    ) -> Self {
+        Self { books, router, portfolio }
+    }
+
+    pub async fn simulate(&self, request: &SimulationRequest) -> Result<SimulationResult> {
+        let books = self.books.read().await;
+        let book = books
+            .get(&(request.venue.clone(), request.symbol.clone()))
+            .ok_or_else(|| {
+                ArbFinderError::SymbolNotFound(format!(
+                    "No book for {} on {}",
+                    request.symbol.to_pair(),
+                    request.venue
+                ))
+            })?;
+        let fees = self.router.read().fees(&request.venue).clone();
+        let position = self.portfolio.read().await.net_position(request.symbol.id());
+
+        simulate_fill(request, book, &fees, position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn book() -> OrderBook {
+        let mut book = OrderBook::new(Symbol::new("BTC", "USDT"));
+        book.update_bid(dec!(99), dec!(1));
+        book.update_bid(dec!(98), dec!(2));
+        book.update_ask(dec!(101), dec!(1));
+        book.update_ask(dec!(102), dec!(2));
+        book
+    }
+
+    fn request(side: OrderSide, quantity: Decimal) -> SimulationRequest {
+        SimulationRequest::new(VenueId::Binance, Symbol::new("BTC", "USDT"), side, quantity)
+    }
+
+    #[test]
+    fn test_buy_walks_asks() {
+        let fees = TradingFee::new(dec!(0.0005), dec!(0.001));
+        let result = simulate_fill(&request(OrderSide::Buy, dec!(2)), &book(), &fees, dec!(0.5)).unwrap();
+
+        assert_eq!(result.levels_consumed, 2);
+        assert_eq!(result.notional, dec!(203));
+        assert_eq!(result.vwap, dec!(101.5));
+        assert_eq!(result.best_price, dec!(101));
+        assert_eq!(result.fee_rate, dec!(0.001));
+        assert_eq!(result.fees, dec!(0.203));
+        assert_eq!(result.position_after, dec!(2.5));
+        assert_eq!(result.exposure, dec!(250));
+        assert!(result.slippage_bps > dec!(49) && result.slippage_bps < dec!(50));
+        assert!(result.fully_filled());
+    }
+
+    #[test]
+    fn test_sell_beyond_depth_is_partial() {
+        let fees = TradingFee::new(dec!(0), dec!(0));
+        let result = simulate_fill(&request(OrderSide::Sell, dec!(5)), &book(), &fees, dec!(0)).unwrap();
+
+        assert_eq!(result.filled_quantity, dec!(3));
+        assert_eq!(result.best_price, dec!(99));
+        assert_eq!(result.position_after, dec!(-3));
+        assert!(!result.fully_filled());
+        assert!(result.slippage_bps > Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_rejects_empty_side_and_bad_quantity() {
+        let fees = TradingFee::new(dec!(0), dec!(0));
+        let empty = OrderBook::new(Symbol::new("BTC", "USDT"));
+        assert!(simulate_fill(&request(OrderSide::Buy, dec!(1)), &empty, &fees, dec!(0)).is_err());
+        assert!(simulate_fill(&request(OrderSide::Buy, dec!(0)), &book(), &fees, dec!(0)).is_err());
+    }
+}
@@ -0,0 +1,286 @@
+//! Arbitrage sizes scaled by how well each venue pair has been filling.
+//!
+//! Every execution reports how much of its legs filled and how far the
+//! fills slipped past the opportunity's prices. Over the last `window`
+//! executions of a pair, a mean fill ratio at or above the target with
+//! slippage within bounds grows the pair's scale by `step`. Otherwise the
+//! scale shrinks in proportion to how far the pair fell short. The scale
+//! multiplies the quantity asked of every later execution on the pair,
+//! within `min_scale` and `max_scale`.
+
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::Arc;
+use rust_decimal::Decimal;
+use serde::Serialize;
+
+use arbfinder_core::prelude::*;
+use arbfinder_core::types::ArbitrageOpportunity;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SizingConfig {
+    /// Recent executions per venue pair the fill quality is taken over.
+    pub window: usize,
+    /// Smallest and largest fraction of the requested quantity traded.
+    pub min_scale: Decimal,
+    pub max_scale: Decimal,
+    /// Scale added after each execution that leaves the pair healthy.
+    pub step: Decimal,
+    /// Mean fraction of the legs that must fill for a pair to be healthy.
+    pub target_fill_ratio: Decimal,
+    /// Mean slippage past the opportunity's prices a healthy pair stays
+    /// within, in basis points.
+    pub max_slippage_bps: Decimal,
+}
+
+impl Default for SizingConfig {
+    fn default() -> Self {
+        Self {
+            window: 20,
+            min_scale: Decimal::new(25, 2),
+            max_scale: Decimal::from(2),
+            step: Decimal::new(5, 2),
+            target_fill_ratio: Decimal::new(95, 2),
+            max_slippage_bps: Decimal::from(10),
+        }
+    }
+}
+
+/// The venues an arbitrage buys on and sells on, in that order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
+pub struct VenuePair {
+    pub buy_venue: VenueId,
+    pub sell_venue: VenueId,
+}
+
+impl VenuePair {
+    pub fn new(buy_venue: VenueId, sell_venue: VenueId) -> Self {
+        Self { buy_venue, sell_venue }
+    }
+
+    pub fn of(opportunity: &ArbitrageOpportunity) -> Self {
+        Self::new(opportunity.buy_venue.clone(), opportunity.sell_venue.clone())
+    }
+}
+
+impl fmt::Display for VenuePair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} -> {}", self.buy_venue, self.sell_venue)
+    }
+}
+
+/// How one execution filled.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct FillQuality {
+    /// Filled fraction of the less filled leg.
+    pub fill_ratio: Decimal,
+    /// How far the fills' average prices were worse than the
+    /// opportunity's, in basis points of its prices; negative if better.
+    pub slippage_bps: Decimal,
+}
+
+impl FillQuality {
+    /// Quality of the `buy` and `sell` legs placed for `opportunity`, as
+    /// they stand. Slippage is measured on what filled; it is zero if
+    /// nothing did.
+    pub fn of(opportunity: &ArbitrageOpportunity, buy: &Order, sell: &Order) -> Self {
+        let ratio = |order: &Order| {
+            if order.quantity.is_zero() {
+                Decimal::ZERO
+            } else {
+                (order.filled_quantity / order.quantity).min(Decimal::ONE)
+            }
+        };
+        let bps = |slipped: Decimal, price: Decimal| {
+            if price.is_zero() {
+                Decimal::ZERO
+            } else {
+                slipped / price * Decimal::from(10_000)
+            }
+        };
+
+        let buy_slippage = buy
+            .average_fill_price
+            .map(|fill| bps(fill - opportunity.buy_price, opportunity.buy_price));
+        let sell_slippage = sell
+            .average_fill_price
+            .map(|fill| bps(opportunity.sell_price - fill, opportunity.sell_price));
+        let slippage_bps = match (buy_slippage, sell_slippage) {
+            (Some(buy), Some(sell)) => (buy + sell) / Decimal::from(2),
+            (Some(one), None) | (None, Some(one)) => one,
+            (None, None) => Decimal::ZERO,
+        };
+
+        Self {
+            fill_ratio: ratio(buy).min(ratio(sell)),
+            slippage_bps,
+        }
+    }
+}
+
+/// A venue pair's recent fill quality and the scale it has earned.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PairSizing {
+    pub pair: VenuePair,
+    pub scale: Decimal,
+    pub executions: usize,
+    pub mean_fill_ratio: Decimal,
+    pub mean_slippage_bps: Decimal,
+}
+
+#[derive(Debug, Default)]
+struct PairHistory {
+    recent: VecDeque<FillQuality>,
+    scale: Option<Decimal>,
+}
+
+impl PairHistory {
+    fn summary(&self, pair: &VenuePair) -> PairSizing {
+        let count = Decimal::from(self.recent.len().max(1));
+        PairSizing {
+            pair: pair.clone(),
+            scale: self.scale.unwrap_or(Decimal::ONE),
+            executions: self.recent.len(),
+            mean_fill_ratio: self.recent.iter().map(|q| q.fill_ratio).sum::<Decimal>() / count,
+            mean_slippage_bps: self.recent.iter().map(|q| q.slippage_bps).sum::<Decimal>() / count,
+        }
+    }
+}
+
+/// Scales per venue pair, shared by every clone.
+#[derive(Debug, Clone, Default)]
+pub struct AdaptiveSizer {
+    config: SizingConfig,
+    pairs: Arc<parking_lot::RwLock<HashMap<VenuePair, PairHistory>>>,
+}
+
+impl AdaptiveSizer {
+    pub fn new(config: SizingConfig) -> Self {
+        Self {
+            config,
+            pairs: Arc::default(),
+        }
+    }
+
+    pub fn config(&self) -> &SizingConfig {
+        &self.config
+    }
+
+    /// The scale `pair` has earned; 1 until it has executions.
+    pub fn scale(&self, pair: &VenuePair) -> Decimal {
+        self.pairs
+            .read()
+            .get(pair)
+            .and_then(|history| history.scale)
+            .unwrap_or(Decimal::ONE)
+    }
+
+    /// `quantity` scaled for `pair`.
+    pub fn size(&self, pair: &VenuePair, quantity: Decimal) -> Decimal {
+        quantity * self.scale(pair)
+    }
+
+    /// Adds an execution on `pair` to its window and rescales the pair.
+    pub fn record(&self, pair: VenuePair, quality: FillQuality) -> PairSizing {
+        let config = &self.config;
+        let mut pairs = self.pairs.write();
+        let history = pairs.entry(pair.clone()).or_default();
+        history.recent.push_back(quality);
+        while history.recent.len() > config.window.max(1) {
+            history.recent.pop_front();
+        }
+
+        let PairSizing { scale, mean_fill_ratio, mean_slippage_bps, .. } = history.summary(&pair);
+        let fill_shortfall = if config.target_fill_ratio > Decimal::ZERO {
+            (mean_fill_ratio / config.target_fill_ratio).min(Decimal::ONE)
+        } else {
+            Decimal::ONE
+        };
+        let slippage_excess = if mean_slippage_bps > config.max_slippage_bps && mean_slippage_bps > Decimal::ZERO {
+            config.max_slippage_bps.max(Decimal::ZERO) / mean_slippage_bps
+        } else {
+            Decimal::ONE
+        };
+        let scale = if fill_shortfall == Decimal::ONE && slippage_excess == Decimal::ONE {
+            scale + config.step
+        } else {
+            scale * fill_shortfall * slippage_excess
+        };
+        history.scale = Some(scale.clamp(config.min_scale, config.max_scale.max(config.min_scale)));
+        history.summary(&pair)
+    }
+
+    /// Every pair with executions, lowest scale first.
+    pub fn pairs(&self) -> Vec<PairSizing> {
+        let mut pairs: Vec<PairSizing> = self
+            .pairs
+            .read()
+            .iter()
+            .filter(|(_, history)| !history.recent.is_empty())
+            .map(|(pair, history)| history.summary(pair))
+            .collect();
+        pairs.sort_by_key(|pair| pair.scale);
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn quality(fill_ratio: Decimal, slippage_bps: Decimal) -> FillQuality {
+        FillQuality { fill_ratio, slippage_bps }
+    }
+
+    #[test]
+    fn test_scale_follows_fill_quality() {
+        let sizer = AdaptiveSizer::new(SizingConfig {
+            window: 4,
+            ..SizingConfig::default()
+        });
+        let pair = VenuePair::new(VenueId::Binance, VenueId::Kraken);
+        assert_eq!(sizer.size(&pair, Decimal::from(2)), Decimal::from(2));
+
+        // Clean fills grow the scale a step at a time
+        for _ in 0..3 {
+            sizer.record(pair.clone(), quality(Decimal::ONE, Decimal::from(2)));
+        }
+        assert_eq!(sizer.scale(&pair), Decimal::new(115, 2));
+
+        // Half filling pulls the mean to 0.875, 0.875 / 0.95 of the scale
+        let sizing = sizer.record(pair.clone(), quality(Decimal::new(5, 1), Decimal::ZERO));
+        assert_eq!(sizing.mean_fill_ratio, Decimal::new(875, 3));
+        assert_eq!(sizing.scale, Decimal::new(115, 2) * (Decimal::new(875, 3) / Decimal::new(95, 2)));
+
+        // Slippage of 40 bps on average keeps a quarter at most
+        let other = VenuePair::new(VenueId::Kraken, VenueId::Binance);
+        assert_eq!(sizer.record(other.clone(), quality(Decimal::ONE, Decimal::from(40))).scale, Decimal::new(25, 2));
+        assert_eq!(sizer.record(other.clone(), quality(Decimal::ONE, Decimal::from(40))).scale, Decimal::new(25, 2));
+        assert_eq!(sizer.pairs()[0].pair, other);
+    }
+
+    #[test]
+    fn test_fill_quality_of_legs() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let opportunity = ArbitrageOpportunity::new(
+            symbol.clone(),
+            VenueId::Binance,
+            VenueId::Kraken,
+            Decimal::from(10_000),
+            Decimal::from(10_100),
+            Decimal::ONE,
+            ArbitrageStrategy::CrossExchange,
+        );
+        let mut buy = Order::new_limit(VenueId::Binance, symbol.clone(), OrderSide::Buy, Decimal::ONE, Decimal::from(10_000));
+        buy.filled_quantity = Decimal::ONE;
+        buy.average_fill_price = Some(Decimal::from(10_010));
+        let mut sell = Order::new_limit(VenueId::Kraken, symbol, OrderSide::Sell, Decimal::ONE, Decimal::from(10_100));
+        sell.filled_quantity = Decimal::new(5, 1);
+        sell.average_fill_price = Some(Decimal::from(10_100));
+
+        let quality = FillQuality::of(&opportunity, &buy, &sell);
+        assert_eq!(quality.fill_ratio, Decimal::new(5, 1));
+        // 10 bps on the buy, none on the sell
+        assert_eq!(quality.slippage_bps, Decimal::from(5));
+    }
+}
@@ -0,0 +1,277 @@
+//! Fair order throttling across strategies.
+//!
+//! Each venue takes at most `max_orders_per_second`. While it has room,
+//! orders go straight through; once it is full, orders wait, and freed
+//! capacity goes to strategies in proportion to their priority rather than
+//! to whichever order happened to ask first. This is weighted fair queueing:
+//! every waiting order gets a virtual finish time, advanced by 1/priority per
+//! order of its strategy, and the earliest finish time goes next. An order
+//! that waits longer than `max_wait` is refused with a rate limit error.
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio::sync::Notify;
+use tokio::time::Instant;
+
+use arbfinder_core::config::StrategyConfig;
+use arbfinder_core::prelude::*;
+
+/// Strategy name for orders placed outside any strategy.
+pub const MANUAL_STRATEGY: &str = "manual";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThrottleConfig {
+    /// Relative share of throttled capacity per strategy; strategies not
+    /// listed weigh 1.
+    pub priorities: HashMap<String, u32>,
+    /// Longest an order waits for capacity before it is refused.
+    pub max_wait: Duration,
+}
+
+impl Default for ThrottleConfig {
+    fn default() -> Self {
+        Self {
+            priorities: HashMap::new(),
+            max_wait: Duration::from_secs(2),
+        }
+    }
+}
+
+impl From<&StrategyConfig> for ThrottleConfig {
+    fn from(strategy: &StrategyConfig) -> Self {
+        Self {
+            priorities: strategy.priorities.clone(),
+            ..Self::default()
+        }
+    }
+}
+
+impl ThrottleConfig {
+    pub fn priority(&self, strategy: &str) -> u32 {
+        self.priorities.get(strategy).copied().unwrap_or(1).max(1)
+    }
+}
+
+/// Virtual time an order of a strategy with priority 1 takes. Divisible by
+/// every priority up to 16, so common weights tie exactly.
+const VIRTUAL_ORDER: u64 = 720_720;
+
+/// Orders waiting on one venue, in weighted fair order.
+#[derive(Debug, Default)]
+struct FairQueue {
+    /// Virtual time of the last order granted.
+    clock: u64,
+    /// Virtual finish time of each strategy's last queued order.
+    finish: HashMap<String, u64>,
+    /// (finish time, ticket) of each waiting order.
+    waiting: Vec<(u64, u64)>,
+    next_ticket: u64,
+}
+
+impl FairQueue {
+    fn enqueue(&mut self, strategy: &str, priority: u32) -> u64 {
+        let start = self.finish.get(strategy).copied().unwrap_or(0).max(self.clock);
+        let finish = start + VIRTUAL_ORDER / priority as u64;
+        self.finish.insert(strategy.to_string(), finish);
+        let ticket = self.next_ticket;
+        self.next_ticket += 1;
+        self.waiting.push((finish, ticket));
+        ticket
+    }
+
+    /// The waiting order to go next; ties go to the earlier ticket.
+    fn head(&self) -> Option<u64> {
+        self.waiting.iter().min().map(|(_, ticket)| *ticket)
+    }
+
+    fn remove(&mut self, ticket: u64) -> Option<u64> {
+        let index = self.waiting.iter().position(|(_, t)| *t == ticket)?;
+        Some(self.waiting.swap_remove(index).0)
+    }
+
+    fn grant(&mut self, ticket: u64) {
+        if let Some(finish) = self.remove(ticket) {
+            self.clock = self.clock.max(finish);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct VenueThrottle {
+    /// When each order of the last window was let through.
+    sent: VecDeque<Instant>,
+    queue: FairQueue,
+}
+
+impl VenueThrottle {
+    fn prune(&mut self, now: Instant, window: Duration) {
+        while self.sent.front().is_some_and(|sent| now.duration_since(*sent) >= window) {
+            self.sent.pop_front();
+        }
+    }
+}
+
+/// Per-venue order rate limit, shared fairly between strategies. Cloning
+/// shares the limit.
+#[derive(Debug, Clone)]
+pub struct OrderThrottle {
+    config: ThrottleConfig,
+    max_orders: usize,
+    window: Duration,
+    venues: Arc<parking_lot::Mutex<HashMap<String, VenueThrottle>>>,
+    released: Arc<Notify>,
+}
+
+impl OrderThrottle {
+    pub fn new(max_orders_per_second: u32, config: ThrottleConfig) -> Self {
+        Self::with_window(max_orders_per_second, Duration::from_secs(1), config)
+    }
+
+    /// At most `max_orders` per `window` on each venue.
+    pub fn with_window(max_orders: u32, window: Duration, config: ThrottleConfig) -> Self {
+        Self {
+            config,
+            max_orders: max_orders.max(1) as usize,
+            window,
+            venues: Arc::new(parking_lot::Mutex::new(HashMap::new())),
+            released: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn config(&self) -> &ThrottleConfig {
+        &self.config
+    }
+
+    /// Waits until `strategy` may send an order to `venue`, returning how
+    /// long it waited, or a rate limit error once it has waited `max_wait`.
+    pub async fn acquire(&self, venue: &str, strategy: &str) -> Result<Duration> {
+        let started = Instant::now();
+        let deadline = started + self.config.max_wait;
+        let mut ticket = None;
+
+        loop {
+            // Registered before checking, so a release in between is seen
+            let released = self.released.notified();
+            tokio::pin!(released);
+            released.as_mut().enable();
+
+            let now = Instant::now();
+            let wake = {
+                let mut venues = self.venues.lock();
+                let throttle = venues.entry(venue.to_string()).or_default();
+                throttle.prune(now, self.window);
+                let has_room = throttle.sent.len() < self.max_orders;
+
+                match ticket {
+                    None if has_room && throttle.queue.waiting.is_empty() => {
+                        throttle.sent.push_back(now);
+                        return Ok(Duration::ZERO);
+                    }
+                    None => {
+                        let priority = self.config.priority(strategy);
+                        ticket = Some(throttle.queue.enqueue(strategy, priority));
+                    }
+                    Some(_) => {}
+                }
+                let ticket = ticket.expect("queued above");
+
+                if has_room && throttle.queue.head() == Some(ticket) {
+                    throttle.queue.grant(ticket);
+                    throttle.sent.push_back(now);
+                    drop(venues);
+                    // The next in line may fit in the same window
+                    self.released.notify_waiters();
+                    return Ok(now.duration_since(started));
+                }
+                if now >= deadline {
+                    throttle.queue.remove(ticket);
+                    drop(venues);
+                    self.released.notify_waiters();
+                    return Err(ArbFinderError::RateLimit(format!(
+                        "{} orders throttled on {} for {}ms",
+                        strategy,
+                        venue,
+                        self.config.max_wait.as_millis()
+                    )));
+                }
+                match throttle.sent.front() {
+                    Some(oldest) if !has_room => (*oldest + self.window).min(deadline),
+                    _ => deadline,
+                }
+            };
+
+            tokio::select! {
+                _ = released => {}
+                _ = tokio::time::sleep_until(wake) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_weighted_fair_order() {
+        let config = ThrottleConfig {
+            priorities: [("cross_exchange".to_string(), 3)].into_iter().collect(),
+            ..ThrottleConfig::default()
+        };
+        let mut queue = FairQueue::default();
+
+        // A burst from a low priority strategy queues first...
+        let mut strategies = HashMap::new();
+        for strategy in ["triangular"; 6].into_iter().chain(["cross_exchange"; 6]) {
+            strategies.insert(queue.enqueue(strategy, config.priority(strategy)), strategy);
+        }
+        let mut granted = Vec::new();
+        while let Some(ticket) = queue.head() {
+            queue.grant(ticket);
+            granted.push(strategies[&ticket]);
+        }
+        // ...but the higher priority one still gets three orders in for
+        // each of the other's
+        let first: Vec<_> = granted[..8].iter().map(|s| &s[..1]).collect();
+        assert_eq!(first.concat(), "cctccctc");
+
+        // A strategy idle while others queued starts at the current
+        // virtual time, not with credit for the time it was idle
+        let ticket = queue.enqueue("manual", 1);
+        assert_eq!(queue.head(), Some(ticket));
+        assert!(queue.remove(ticket).unwrap() > queue.clock);
+    }
+
+    #[tokio::test]
+    async fn test_acquire_waits_then_refuses() {
+        let config = ThrottleConfig {
+            max_wait: Duration::from_millis(500),
+            ..ThrottleConfig::default()
+        };
+        let throttle = OrderThrottle::with_window(1, Duration::from_millis(50), config.clone());
+        assert_eq!(throttle.acquire("binance", "a").await.unwrap(), Duration::ZERO);
+        // Other venues have their own limit
+        assert_eq!(throttle.acquire("kraken", "a").await.unwrap(), Duration::ZERO);
+        let waited = throttle.acquire("binance", "a").await.unwrap();
+        assert!(waited >= Duration::from_millis(30), "waited {:?}", waited);
+
+        // A window that never frees up
+        let throttle = OrderThrottle::with_window(
+            1,
+            Duration::from_secs(3600),
+            ThrottleConfig {
+                max_wait: Duration::from_millis(20),
+                ..config
+            },
+        );
+        throttle.acquire("binance", "a").await.unwrap();
+        assert!(matches!(
+            throttle.acquire("binance", "b").await,
+            Err(ArbFinderError::RateLimit(_))
+        ));
+        // The refused order left the queue
+        assert!(throttle.venues.lock()["binance"].queue.waiting.is_empty());
+    }
+}
@@ -326,6 +326,75 @@ impl AlertManager {
         }
     }
 
+    /// Activity on `venue`'s account that the bot did not originate, which
+    /// may mean its keys have leaked or another bot shares the account.
+    pub fn create_account_activity_alert(venue: &str, message: &str) -> Alert {
+        Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            level: AlertLevel::Critical,
+            title: format!("Unexpected Account Activity: {}", venue),
+            message: message.to_string(),
+            timestamp: Utc::now(),
+            metadata: {
+                let mut map = HashMap::new();
+                map.insert("exchange".to_string(), venue.to_string());
+                map
+            },
+        }
+    }
+
+    /// A pair that stopped trading on `venue`, or started again.
+    pub fn create_pair_status_alert(venue: &str, symbol: &str, message: &str, stopped: bool) -> Alert {
+        Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            level: if stopped { AlertLevel::Critical } else { AlertLevel::Info },
+            title: format!("Pair Status Change: {} on {}", symbol, venue),
+            message: message.to_string(),
+            timestamp: Utc::now(),
+            metadata: {
+                let mut map = HashMap::new();
+                map.insert("exchange".to_string(), venue.to_string());
+                map.insert("symbol".to_string(), symbol.to_string());
+                map
+            },
+        }
+    }
+
+    /// A reconciliation that found orders or fills on `venue` the bot's
+    /// records and the venue's disagree on.
+    pub fn create_reconciliation_alert(venue: &str, message: &str, discrepancies: usize) -> Alert {
+        Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            level: AlertLevel::Critical,
+            title: format!("Reconciliation Mismatch: {}", venue),
+            message: message.to_string(),
+            timestamp: Utc::now(),
+            metadata: {
+                let mut map = HashMap::new();
+                map.insert("exchange".to_string(), venue.to_string());
+                map.insert("discrepancies".to_string(), discrepancies.to_string());
+                map
+            },
+        }
+    }
+
+    /// A venue the exchange supervisor found down, restarted or gave up
+    /// on.
+    pub fn create_venue_health_alert(venue: &str, message: &str, level: AlertLevel) -> Alert {
+        Alert {
+            id: uuid::Uuid::new_v4().to_string(),
+            level,
+            title: format!("Venue Health: {}", venue),
+            message: message.to_string(),
+            timestamp: Utc::now(),
+            metadata: {
+                let mut map = HashMap::new();
+                map.insert("exchange".to_string(), venue.to_string());
+                map
+            },
+        }
+    }
+
     pub fn create_system_alert(component: &str, message: &str, level: AlertLevel) -> Alert {
         Alert {
             id: uuid::Uuid::new_v4().to_string(),
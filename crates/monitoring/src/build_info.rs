@@ -0,0 +1,72 @@
+//! What a binary was built from, for tying behavior and bug reports to an
+//! exact build. The binary fills it in from what its build script recorded
+//! and it is reported by `arbfinder version`, `GET /version` and the
+//! `arbfinder_build_info` metric.
+
+use axum::{routing::get, Json, Router};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BuildInfo {
+    pub version: String,
+    /// Commit built, or `unknown` outside a git checkout.
+    pub git_commit: String,
+    /// The working tree had uncommitted changes.
+    pub git_dirty: bool,
+    pub build_time: Option<DateTime<Utc>>,
+    /// `debug` or `release`.
+    pub profile: String,
+    /// Cargo features the binary was built with, sorted.
+    pub features: Vec<String>,
+}
+
+impl BuildInfo {
+    /// Commit abbreviated to 12 characters, marked `-dirty` if the tree
+    /// had changes.
+    pub fn short_commit(&self) -> String {
+        let commit: String = self.git_commit.chars().take(12).collect();
+        if self.git_dirty {
+            format!("{}-dirty", commit)
+        } else {
+            commit
+        }
+    }
+
+    /// Serves this as JSON at `GET /version`.
+    pub fn routes(self) -> Router {
+        Router::new().route("/version", get(move || async move { Json(self) }))
+    }
+}
+
+impl std::fmt::Display for BuildInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ArbFinder v{} ({}, {}", self.version, self.short_commit(), self.profile)?;
+        if let Some(time) = self.build_time {
+            write!(f, ", built {}", time.format("%Y-%m-%d %H:%M:%S UTC"))?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let mut info = BuildInfo {
+            version: "0.1.0".to_string(),
+            git_commit: "84a506e1f2c3d4b5a6978877665544332211ffee".to_string(),
+            git_dirty: false,
+            build_time: DateTime::from_timestamp(1_760_000_000, 0),
+            profile: "release".to_string(),
+            features: vec!["binance".to_string()],
+        };
+        assert_eq!(info.to_string(), "ArbFinder v0.1.0 (84a506e1f2c3, release, built 2025-10-09 08:53:20 UTC)");
+
+        info.git_dirty = true;
+        info.build_time = None;
+        assert_eq!(info.to_string(), "ArbFinder v0.1.0 (84a506e1f2c3-dirty, release)");
+    }
+}
@@ -8,7 +8,9 @@ pub mod metrics;
 pub mod logging;
 pub mod alerts;
 pub mod health;
+pub mod build_info;
 
+pub use build_info::BuildInfo;
 pub use metrics::{MetricsCollector, MetricsServer};
 pub use logging::{LoggingConfig, setup_logging};
 pub use alerts::{AlertManager, AlertConfig, Alert, AlertLevel};
@@ -41,6 +43,7 @@ pub struct MonitoringSystem {
     config: MonitoringConfig,
     metrics_collector: Arc<MetricsCollector>,
     metrics_server: Option<MetricsServer>,
+    extra_routes: axum::Router,
     alert_manager: Arc<RwLock<AlertManager>>,
     health_checker: Arc<HealthChecker>,
 }
@@ -55,6 +58,7 @@ impl MonitoringSystem {
             config,
             metrics_collector,
             metrics_server: None,
+            extra_routes: axum::Router::new(),
             alert_manager,
             health_checker,
         })
@@ -70,7 +74,8 @@ impl MonitoringSystem {
         let metrics_server = MetricsServer::new(
             self.config.metrics_port,
             Arc::clone(&self.metrics_collector),
-        );
+        )
+        .with_routes(self.extra_routes.clone());
         metrics_server.start().await?;
         self.metrics_server = Some(metrics_server);
 
@@ -84,6 +89,12 @@ impl MonitoringSystem {
         Ok(())
     }
 
+    /// Adds routes to the metrics server, e.g. admin endpoints. Takes effect
+    /// when the system is started.
+    pub fn add_routes(&mut self, routes: axum::Router) {
+        self.extra_routes = std::mem::take(&mut self.extra_routes).merge(routes);
+    }
+
     pub async fn stop(&mut self) -> Result<()> {
         info!("Stopping monitoring system");
 
@@ -147,7 +158,7 @@ impl MonitoringSystem {
 
 pub mod prelude {
     pub use super::{
-        MonitoringSystem, MonitoringConfig,
+        MonitoringSystem, MonitoringConfig, BuildInfo,
         MetricsCollector, AlertManager, Alert, AlertLevel,
         HealthChecker, HealthStatus, HealthState, ComponentHealth, SystemMetrics,
         setup_logging,
@@ -2,7 +2,7 @@ use std::sync::Arc;
 use std::collections::HashMap;
 use prometheus::{
     Registry, Counter, Gauge, Histogram, HistogramOpts, Opts,
-    Encoder, TextEncoder, IntCounterVec, HistogramVec,
+    Encoder, TextEncoder, IntCounterVec, IntGaugeVec, GaugeVec, HistogramVec,
 };
 use axum::{
     extract::State,
@@ -15,6 +15,17 @@ use tokio::net::TcpListener;
 use tracing::{info, error};
 
 use arbfinder_core::prelude::*;
+use arbfinder_core::utils::{write_prometheus_header, SharedStats};
+
+use crate::build_info::BuildInfo;
+
+/// Rolling statistics exported as a Prometheus summary series.
+struct SummarySeries {
+    name: String,
+    help: String,
+    labels: Vec<(String, String)>,
+    stats: SharedStats,
+}
 
 pub struct MetricsCollector {
     registry: Registry,
@@ -26,20 +37,37 @@ pub struct MetricsCollector {
     pub profit_total: Gauge,
     pub portfolio_value: Gauge,
     
+    // Risk metrics
+    pub risk_checks: IntCounterVec,
+    pub risk_limit_utilization: HistogramVec,
+    
+    // Throttle metrics
+    pub order_throttle_wait: HistogramVec,
+    pub order_throttle_timeouts: IntCounterVec,
+    pub opportunity_claims: HistogramVec,
+    
     // Exchange metrics
     pub exchange_requests: IntCounterVec,
     pub exchange_errors: IntCounterVec,
     pub exchange_latency: HistogramVec,
+    pub websocket_bytes: IntCounterVec,
+    pub venue_uptime: GaugeVec,
+    pub venue_connection_gaps: IntGaugeVec,
+    pub venue_longest_gap: GaugeVec,
     
     // System metrics
     pub system_uptime: Gauge,
     pub memory_usage: Gauge,
     pub cpu_usage: Gauge,
+    pub build_info: IntGaugeVec,
     
     // Custom metrics
     custom_counters: HashMap<String, Counter>,
     custom_gauges: HashMap<String, Gauge>,
     custom_histograms: HashMap<String, Histogram>,
+
+    // Summaries of rolling statistics, read when gathered
+    summaries: std::sync::Mutex<Vec<SummarySeries>>,
 }
 
 impl MetricsCollector {
@@ -81,6 +109,51 @@ impl MetricsCollector {
             "Current portfolio value in USD"
         )).unwrap();
         
+        // Risk metrics: which limits reject orders, and how close passing
+        // orders come to them
+        let risk_checks = IntCounterVec::new(
+            Opts::new(
+                "arbfinder_risk_checks_total",
+                "Pre-trade risk checks, by result and the rule that rejected the order"
+            ),
+            &["result", "rule"]
+        ).unwrap();
+        
+        let risk_limit_utilization = HistogramVec::new(
+            HistogramOpts::new(
+                "arbfinder_risk_limit_utilization_ratio",
+                "Fraction of each risk limit an order would use, above 1 breaking it"
+            )
+            .buckets(vec![0.1, 0.25, 0.5, 0.75, 0.9, 0.95, 1.0, 1.5, 2.0]),
+            &["rule"]
+        ).unwrap();
+        
+        let order_throttle_wait = HistogramVec::new(
+            HistogramOpts::new(
+                "arbfinder_order_throttle_wait_seconds",
+                "Time orders waited for the venue's order rate limit, by strategy"
+            )
+            .buckets(vec![0.0, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.0, 5.0]),
+            &["strategy", "exchange"]
+        ).unwrap();
+        
+        let order_throttle_timeouts = IntCounterVec::new(
+            Opts::new(
+                "arbfinder_order_throttle_timeouts_total",
+                "Orders refused after waiting too long for the venue's order rate limit"
+            ),
+            &["strategy", "exchange"]
+        ).unwrap();
+        
+        let opportunity_claims = HistogramVec::new(
+            HistogramOpts::new(
+                "arbfinder_opportunity_claim_seconds",
+                "Time taken to claim opportunities across executors, by result"
+            )
+            .buckets(vec![0.0005, 0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25]),
+            &["result"]
+        ).unwrap();
+        
         // Exchange metrics with labels
         let exchange_requests = IntCounterVec::new(
             Opts::new(
@@ -106,6 +179,39 @@ impl MetricsCollector {
             &["exchange", "endpoint"]
         ).unwrap();
         
+        let websocket_bytes = IntCounterVec::new(
+            Opts::new(
+                "arbfinder_websocket_bytes_total",
+                "WebSocket payload bytes received, as sent on the wire and after decompression"
+            ),
+            &["exchange", "encoding"]
+        ).unwrap();
+        
+        // Venue connectivity since each venue first connected
+        let venue_uptime = GaugeVec::new(
+            Opts::new(
+                "arbfinder_venue_uptime_percent",
+                "Share of the time since the venue first connected that it has been connected"
+            ),
+            &["exchange"]
+        ).unwrap();
+        
+        let venue_connection_gaps = IntGaugeVec::new(
+            Opts::new(
+                "arbfinder_venue_connection_gaps",
+                "Disconnections since the venue first connected"
+            ),
+            &["exchange"]
+        ).unwrap();
+        
+        let venue_longest_gap = GaugeVec::new(
+            Opts::new(
+                "arbfinder_venue_longest_gap_seconds",
+                "Longest disconnection of the venue, including a current one"
+            ),
+            &["exchange"]
+        ).unwrap();
+        
         // System metrics
         let system_uptime = Gauge::with_opts(Opts::new(
             "arbfinder_system_uptime_seconds",
@@ -122,18 +228,37 @@ impl MetricsCollector {
             "CPU usage percentage"
         )).unwrap();
         
+        // Always 1, labelled with what the binary was built from
+        let build_info = IntGaugeVec::new(
+            Opts::new(
+                "arbfinder_build_info",
+                "Version, commit, profile and cargo features of the running binary"
+            ),
+            &["version", "commit", "profile", "features"]
+        ).unwrap();
+        
         // Register metrics
         registry.register(Box::new(trades_total.clone())).unwrap();
         registry.register(Box::new(orders_total.clone())).unwrap();
         registry.register(Box::new(arbitrage_opportunities.clone())).unwrap();
         registry.register(Box::new(profit_total.clone())).unwrap();
         registry.register(Box::new(portfolio_value.clone())).unwrap();
+        registry.register(Box::new(risk_checks.clone())).unwrap();
+        registry.register(Box::new(risk_limit_utilization.clone())).unwrap();
+        registry.register(Box::new(order_throttle_wait.clone())).unwrap();
+        registry.register(Box::new(order_throttle_timeouts.clone())).unwrap();
+        registry.register(Box::new(opportunity_claims.clone())).unwrap();
         registry.register(Box::new(exchange_requests.clone())).unwrap();
         registry.register(Box::new(exchange_errors.clone())).unwrap();
         registry.register(Box::new(exchange_latency.clone())).unwrap();
+        registry.register(Box::new(websocket_bytes.clone())).unwrap();
+        registry.register(Box::new(venue_uptime.clone())).unwrap();
+        registry.register(Box::new(venue_connection_gaps.clone())).unwrap();
+        registry.register(Box::new(venue_longest_gap.clone())).unwrap();
         registry.register(Box::new(system_uptime.clone())).unwrap();
         registry.register(Box::new(memory_usage.clone())).unwrap();
         registry.register(Box::new(cpu_usage.clone())).unwrap();
+        registry.register(Box::new(build_info.clone())).unwrap();
         
         Self {
             registry,
@@ -142,15 +267,26 @@ impl MetricsCollector {
             arbitrage_opportunities,
             profit_total,
             portfolio_value,
+            risk_checks,
+            risk_limit_utilization,
+            order_throttle_wait,
+            order_throttle_timeouts,
+            opportunity_claims,
             exchange_requests,
             exchange_errors,
             exchange_latency,
+            websocket_bytes,
+            venue_uptime,
+            venue_connection_gaps,
+            venue_longest_gap,
             system_uptime,
             memory_usage,
             cpu_usage,
+            build_info,
             custom_counters: HashMap::new(),
             custom_gauges: HashMap::new(),
             custom_histograms: HashMap::new(),
+            summaries: std::sync::Mutex::new(Vec::new()),
         }
     }
     
@@ -180,6 +316,43 @@ impl MetricsCollector {
         self.portfolio_value.set(value);
     }
     
+    /// Counts a pre-trade check, passed if no rule rejected the order.
+    pub fn record_risk_check(&self, rejected_by: Option<&str>) {
+        let labels = match rejected_by {
+            Some(rule) => ["rejected", rule],
+            None => ["passed", "none"],
+        };
+        self.risk_checks
+            .with_label_values(&labels)
+            .inc();
+    }
+    
+    pub fn record_risk_utilization(&self, rule: &str, utilization: f64) {
+        self.risk_limit_utilization
+            .with_label_values(&[rule])
+            .observe(utilization);
+    }
+    
+    pub fn record_throttle_wait(&self, strategy: &str, exchange: &str, seconds: f64) {
+        self.order_throttle_wait
+            .with_label_values(&[strategy, exchange])
+            .observe(seconds);
+    }
+    
+    pub fn record_throttle_timeout(&self, strategy: &str, exchange: &str) {
+        self.order_throttle_timeouts
+            .with_label_values(&[strategy, exchange])
+            .inc();
+    }
+    
+    /// Records a claim attempt taking `seconds`, its result one of `won`,
+    /// `lost` or `error`.
+    pub fn record_opportunity_claim(&self, result: &str, seconds: f64) {
+        self.opportunity_claims
+            .with_label_values(&[result])
+            .observe(seconds);
+    }
+    
     pub fn record_exchange_request(&self, exchange: &str, endpoint: &str) {
         self.exchange_requests
             .with_label_values(&[exchange, endpoint])
@@ -198,6 +371,27 @@ impl MetricsCollector {
             .observe(duration);
     }
     
+    pub fn record_websocket_bytes(&self, exchange: &str, compressed: u64, uncompressed: u64) {
+        self.websocket_bytes
+            .with_label_values(&[exchange, "compressed"])
+            .inc_by(compressed);
+        self.websocket_bytes
+            .with_label_values(&[exchange, "uncompressed"])
+            .inc_by(uncompressed);
+    }
+    
+    pub fn record_venue_uptime(&self, exchange: &str, uptime_percent: f64, gaps: u64, longest_gap_seconds: f64) {
+        self.venue_uptime
+            .with_label_values(&[exchange])
+            .set(uptime_percent);
+        self.venue_connection_gaps
+            .with_label_values(&[exchange])
+            .set(gaps as i64);
+        self.venue_longest_gap
+            .with_label_values(&[exchange])
+            .set(longest_gap_seconds);
+    }
+    
     pub fn update_system_uptime(&self, uptime: f64) {
         self.system_uptime.set(uptime);
     }
@@ -210,6 +404,12 @@ impl MetricsCollector {
         self.cpu_usage.set(usage);
     }
     
+    pub fn record_build_info(&self, info: &BuildInfo) {
+        self.build_info
+            .with_label_values(&[&info.version, &info.short_commit(), &info.profile, &info.features.join(",")])
+            .set(1);
+    }
+    
     pub fn create_custom_counter(&mut self, name: &str, help: &str) -> Result<()> {
         let counter = Counter::with_opts(Opts::new(name, help))
             .map_err(|e| ArbFinderError::Internal(e.to_string()))?;
@@ -261,6 +461,17 @@ impl MetricsCollector {
         }
     }
     
+    /// Exports `stats` as the series of summary `name` with `labels`,
+    /// from its window as it stands whenever metrics are gathered.
+    pub fn register_summary(&self, name: &str, help: &str, labels: &[(&str, &str)], stats: SharedStats) {
+        self.summaries.lock().unwrap_or_else(|e| e.into_inner()).push(SummarySeries {
+            name: name.to_string(),
+            help: help.to_string(),
+            labels: labels.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
+            stats,
+        });
+    }
+
     pub fn gather_metrics(&self) -> Result<String> {
         let encoder = TextEncoder::new();
         let metric_families = self.registry.gather();
@@ -269,14 +480,31 @@ impl MetricsCollector {
         encoder.encode(&metric_families, &mut buffer)
             .map_err(|e| ArbFinderError::Internal(e.to_string()))?;
         
-        String::from_utf8(buffer)
-            .map_err(|e| ArbFinderError::Internal(e.to_string()))
+        let mut text = String::from_utf8(buffer)
+            .map_err(|e| ArbFinderError::Internal(e.to_string()))?;
+
+        // Every series of a summary follows its one header
+        let mut summaries = self.summaries.lock().unwrap_or_else(|e| e.into_inner());
+        summaries.sort_by(|a, b| a.name.cmp(&b.name));
+        for (i, series) in summaries.iter().enumerate() {
+            if i == 0 || summaries[i - 1].name != series.name {
+                write_prometheus_header(&mut text, &series.name, &series.help);
+            }
+            let labels: Vec<(&str, &str)> = series.labels.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+            series
+                .stats
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .write_prometheus(&mut text, &series.name, &labels);
+        }
+        Ok(text)
     }
 }
 
 pub struct MetricsServer {
     port: u16,
     metrics_collector: Arc<MetricsCollector>,
+    extra_routes: Router,
 }
 
 impl MetricsServer {
@@ -284,14 +512,22 @@ impl MetricsServer {
         Self {
             port,
             metrics_collector,
+            extra_routes: Router::new(),
         }
     }
+
+    /// Serves `routes` alongside `/metrics` and `/health`.
+    pub fn with_routes(mut self, routes: Router) -> Self {
+        self.extra_routes = self.extra_routes.merge(routes);
+        self
+    }
     
     pub async fn start(&self) -> Result<()> {
         let app = Router::new()
             .route("/metrics", get(metrics_handler))
             .route("/health", get(health_handler))
-            .with_state(Arc::clone(&self.metrics_collector));
+            .with_state(Arc::clone(&self.metrics_collector))
+            .merge(self.extra_routes.clone());
         
         let listener = TcpListener::bind(format!("0.0.0.0:{}", self.port)).await
             .map_err(|e| ArbFinderError::Internal(e.to_string()))?;
@@ -0,0 +1,33 @@
+use arbfinder_core::utils::{coarse_now, start_coarse_clock};
+use arbfinder_core::Symbol;
+use arbfinder_orderbook::FastOrderBook;
+use chrono::Utc;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rust_decimal::Decimal;
+
+fn bench_clocks(c: &mut Criterion) {
+    start_coarse_clock();
+
+    let mut group = c.benchmark_group("clock");
+    group.bench_function("utc_now", |b| b.iter(|| black_box(Utc::now())));
+    group.bench_function("coarse_now", |b| b.iter(|| black_box(coarse_now())));
+    group.finish();
+}
+
+fn bench_level_updates(c: &mut Criterion) {
+    start_coarse_clock();
+
+    c.bench_function("fast_order_book_level_updates", |b| {
+        let mut book = FastOrderBook::new(Symbol::new("BTC", "USDT"), Some(100));
+        let mut i = 0u64;
+        b.iter(|| {
+            i = i.wrapping_add(1);
+            let offset = Decimal::from(i % 50);
+            book.update_bid(Decimal::from(50_000) - offset, Decimal::from(1 + i % 3), None);
+            book.update_ask(Decimal::from(50_001) + offset, Decimal::from(1 + i % 3), None);
+        })
+    });
+}
+
+criterion_group!(benches, bench_clocks, bench_level_updates);
+criterion_main!(benches);
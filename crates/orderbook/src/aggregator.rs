@@ -1,15 +1,17 @@
 //! OrderBook Aggregator
 //!
-//! Aggregates order books from multiple venues into a unified view
+//! Aggregates order books from multiple venues into a unified view. Books
+//! waiting for a resync after a sequence gap are left out of every view.
 
 use std::collections::HashMap;
-use arbfinder_core::{Symbol, VenueId};
+use arbfinder_core::{OrderBook, Side, Symbol, VenueId};
 use rust_decimal::Decimal;
 use chrono::{DateTime, Utc};
 
 use crate::{FastOrderBook, PriceLevel};
 
 /// Aggregated order book combining data from multiple venues
+#[derive(Debug, Clone)]
 pub struct AggregatedOrderBook {
     pub symbol: Symbol,
     pub venues: HashMap<VenueId, FastOrderBook>,
@@ -44,10 +46,14 @@ impl AggregatedOrderBook {
         self.venues.get_mut(venue_id)
     }
 
+    /// Books that can be quoted from: those not stale
+    fn live_books(&self) -> impl Iterator<Item = (&VenueId, &FastOrderBook)> {
+        self.venues.iter().filter(|(_, book)| !book.is_stale())
+    }
+
     /// Get best bid across all venues
     pub fn best_bid_across_venues(&self) -> Option<(VenueId, &PriceLevel)> {
-        self.venues
-            .iter()
+        self.live_books()
             .filter_map(|(venue_id, book)| {
                 book.best_bid().map(|level| (venue_id.clone(), level))
             })
@@ -56,14 +62,37 @@ impl AggregatedOrderBook {
 
     /// Get best ask across all venues
     pub fn best_ask_across_venues(&self) -> Option<(VenueId, &PriceLevel)> {
-        self.venues
-            .iter()
+        self.live_books()
             .filter_map(|(venue_id, book)| {
                 book.best_ask().map(|level| (venue_id.clone(), level))
             })
             .min_by(|(_, a), (_, b)| a.price.cmp(&b.price))
     }
 
+    /// Best bid of each venue, highest first
+    pub fn best_bid_by_venue(&self) -> Vec<(VenueId, &PriceLevel)> {
+        let mut bids: Vec<_> = self
+            .live_books()
+            .filter_map(|(venue_id, book)| book.best_bid().map(|level| (venue_id.clone(), level)))
+            .collect();
+        bids.sort_by(|(venue_a, a), (venue_b, b)| {
+            b.price.cmp(&a.price).then_with(|| venue_a.to_string().cmp(&venue_b.to_string()))
+        });
+        bids
+    }
+
+    /// Best ask of each venue, lowest first
+    pub fn best_ask_by_venue(&self) -> Vec<(VenueId, &PriceLevel)> {
+        let mut asks: Vec<_> = self
+            .live_books()
+            .filter_map(|(venue_id, book)| book.best_ask().map(|level| (venue_id.clone(), level)))
+            .collect();
+        asks.sort_by(|(venue_a, a), (venue_b, b)| {
+            a.price.cmp(&b.price).then_with(|| venue_a.to_string().cmp(&venue_b.to_string()))
+        });
+        asks
+    }
+
     /// Calculate best cross-venue spread
     pub fn cross_venue_spread(&self) -> Option<Decimal> {
         let best_bid = self.best_bid_across_venues()?.1.price;
@@ -73,46 +102,92 @@ impl AggregatedOrderBook {
 
     /// Get total liquidity at a price level across all venues
     pub fn total_liquidity_at_price(&self, price: Decimal, is_bid: bool) -> Decimal {
-        self.venues
-            .values()
-            .map(|book| {
-                let side = if is_bid { arbfinder_core::Side::Bid } else { arbfinder_core::Side::Ask };
-                book.get_liquidity_at_price(side, price)
-            })
+        let side = if is_bid { Side::Bid } else { Side::Ask };
+        self.live_books()
+            .map(|(_, book)| book.get_liquidity_at_price(side, price))
             .sum()
     }
 
-    /// Get combined orderbook depth across all venues
-    pub fn aggregate_depth(&self, depth: usize) -> (Vec<AggregatedLevel>, Vec<AggregatedLevel>) {
-        let mut all_bids: Vec<AggregatedLevel> = Vec::new();
-        let mut all_asks: Vec<AggregatedLevel> = Vec::new();
-
-        for (venue_id, book) in &self.venues {
-            for level in book.get_bids(Some(depth)) {
-                all_bids.push(AggregatedLevel {
+    /// One side of every venue's book merged, best price first, each
+    /// level tagged with its venue. `depth` limits the levels taken from
+    /// each venue. Venues quoting the same price are in venue name order.
+    pub fn merged_levels(&self, side: Side, depth: Option<usize>) -> Vec<AggregatedLevel> {
+        let mut levels: Vec<AggregatedLevel> = self
+            .live_books()
+            .flat_map(|(venue_id, book)| {
+                let levels = match side {
+                    Side::Bid => book.get_bids(depth),
+                    Side::Ask => book.get_asks(depth),
+                };
+                levels.into_iter().map(move |level| AggregatedLevel {
                     price: level.price,
                     quantity: level.quantity,
                     venue: venue_id.clone(),
                     order_count: level.order_count,
-                });
+                })
+            })
+            .collect();
+
+        levels.sort_by(|a, b| {
+            let by_price = match side {
+                // Bids descending by price
+                Side::Bid => b.price.cmp(&a.price),
+                // Asks ascending by price
+                Side::Ask => a.price.cmp(&b.price),
+            };
+            by_price.then_with(|| a.venue.to_string().cmp(&b.venue.to_string()))
+        });
+        levels
+    }
+
+    /// Get combined orderbook depth across all venues
+    pub fn aggregate_depth(&self, depth: usize) -> (Vec<AggregatedLevel>, Vec<AggregatedLevel>) {
+        (self.merged_levels(Side::Bid, Some(depth)), self.merged_levels(Side::Ask, Some(depth)))
+    }
+
+    /// Average price of `quantity` taken from `side` of all venues at
+    /// once, best prices first wherever they are, and how much of it each
+    /// venue fills. `None` if the venues together can't fill it.
+    pub fn cross_venue_vwap(&self, side: Side, quantity: Decimal) -> Option<CrossVenueFill> {
+        if quantity <= Decimal::ZERO {
+            return None;
+        }
+
+        let mut remaining = quantity;
+        let mut notional = Decimal::ZERO;
+        let mut fills: Vec<(VenueId, Decimal)> = Vec::new();
+
+        for level in self.merged_levels(side, None) {
+            if remaining.is_zero() {
+                break;
             }
 
-            for level in book.get_asks(Some(depth)) {
-                all_asks.push(AggregatedLevel {
-                    price: level.price,
-                    quantity: level.quantity,
-                    venue: venue_id.clone(),
-                    order_count: level.order_count,
-                });
+            let taken = level.quantity.min(remaining);
+            notional += taken * level.price;
+            remaining -= taken;
+            match fills.iter_mut().find(|(venue, _)| *venue == level.venue) {
+                Some((_, filled)) => *filled += taken,
+                None => fills.push((level.venue, taken)),
             }
         }
 
-        // Sort bids descending by price
-        all_bids.sort_by(|a, b| b.price.cmp(&a.price));
-        // Sort asks ascending by price
-        all_asks.sort_by(|a, b| a.price.cmp(&b.price));
+        if !remaining.is_zero() {
+            return None; // Not enough liquidity
+        }
 
-        (all_bids, all_asks)
+        Some(CrossVenueFill {
+            vwap: notional / quantity,
+            quantity,
+            fills,
+        })
+    }
+
+    /// Each venue's book as the core type the arbitrage detector compares
+    pub fn core_books(&self) -> HashMap<VenueId, OrderBook> {
+        self.live_books()
+            .filter(|(_, book)| !book.is_empty())
+            .map(|(venue_id, book)| (venue_id.clone(), book.to_core_orderbook()))
+            .collect()
     }
 
     /// Check if any venues have crossed order books
@@ -131,7 +206,7 @@ impl AggregatedOrderBook {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct AggregatedLevel {
     pub price: Decimal,
     pub quantity: Decimal,
@@ -139,6 +214,15 @@ pub struct AggregatedLevel {
     pub order_count: u32,
 }
 
+/// A quantity filled across venues by [`AggregatedOrderBook::cross_venue_vwap`]
+#[derive(Debug, Clone, PartialEq)]
+pub struct CrossVenueFill {
+    pub vwap: Decimal,
+    pub quantity: Decimal,
+    /// Quantity taken from each venue, in the order they were first used
+    pub fills: Vec<(VenueId, Decimal)>,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -156,4 +240,47 @@ mod tests {
 
         assert_eq!(agg_book.venue_count(), 1);
     }
+
+    #[test]
+    fn test_cross_venue_levels_and_vwap() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let book = |bid: i64, ask: i64, quantity: i64| {
+            let mut book = FastOrderBook::new(symbol.clone(), None);
+            book.update_bid(Decimal::from(bid), Decimal::from(quantity), None);
+            book.update_ask(Decimal::from(ask), Decimal::from(quantity), None);
+            book.update_ask(Decimal::from(ask + 10), Decimal::from(quantity), None);
+            book
+        };
+        let mut agg_book = AggregatedOrderBook::new(symbol.clone());
+        agg_book.add_venue(VenueId::Binance, book(99, 101, 1));
+        agg_book.add_venue(VenueId::Kraken, book(100, 102, 2));
+
+        let bids = agg_book.best_bid_by_venue();
+        assert_eq!(bids[0].0, VenueId::Kraken);
+        assert_eq!(bids[1].1.price, Decimal::from(99));
+        let asks = agg_book.best_ask_by_venue();
+        assert_eq!(asks[0].0, VenueId::Binance);
+
+        let merged = agg_book.merged_levels(Side::Ask, None);
+        let prices: Vec<Decimal> = merged.iter().map(|level| level.price).collect();
+        assert_eq!(prices, [101, 102, 111, 112].map(Decimal::from));
+        assert_eq!(merged[1].venue, VenueId::Kraken);
+
+        // 1 at 101 on Binance, then 2 at 102 on Kraken
+        let fill = agg_book.cross_venue_vwap(Side::Ask, Decimal::from(3)).unwrap();
+        assert_eq!(fill.vwap, Decimal::from(305) / Decimal::from(3));
+        assert_eq!(fill.fills, vec![(VenueId::Binance, Decimal::ONE), (VenueId::Kraken, Decimal::from(2))]);
+        assert!(agg_book.cross_venue_vwap(Side::Ask, Decimal::from(7)).is_none());
+        assert_eq!(agg_book.core_books().len(), 2);
+
+        // A book waiting for a resync is left out
+        agg_book
+            .get_venue_book_mut(&VenueId::Kraken)
+            .unwrap()
+            .apply_update_with_sequence(1, Vec::new());
+        assert_eq!(agg_book.best_bid_across_venues().unwrap().0, VenueId::Binance);
+        assert_eq!(agg_book.best_ask_by_venue().len(), 1);
+        assert!(!agg_book.core_books().contains_key(&VenueId::Kraken));
+        assert!(agg_book.cross_venue_vwap(Side::Ask, Decimal::from(3)).is_none());
+    }
 }
@@ -1,10 +1,13 @@
-use arbfinder_core::{OrderBook, OrderBookLevel, Side, Symbol};
+use arbfinder_core::utils::coarse_now;
+use arbfinder_core::{OrderBook, OrderBookLevel, Side, Symbol, Versioned};
 use chrono::{DateTime, Utc};
 use ordered_float::OrderedFloat;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
-use tracing::debug;
+use std::fmt;
+use std::sync::Arc;
+use tracing::{debug, warn};
 use rust_decimal::prelude::ToPrimitive;
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -16,6 +19,78 @@ pub struct FastOrderBook {
     pub last_update: DateTime<Utc>,
     pub checksum: Option<u32>,
     max_depth: usize,
+    /// Last venue sequence number applied, `None` until a snapshot with
+    /// one; see [`FastOrderBook::apply_update_with_sequence`].
+    #[serde(default)]
+    venue_sequence: Option<u64>,
+    /// Set once an update is missed, until the next snapshot.
+    #[serde(default)]
+    stale: bool,
+    /// Updates received while stale, replayed over the next snapshot.
+    #[serde(skip)]
+    pending: Vec<(VenueSequence, Vec<OrderBookUpdate>)>,
+    #[serde(skip)]
+    resync: Option<ResyncCallback>,
+}
+
+/// Most updates held for replay while a book waits for its snapshot. Past
+/// this they are dropped, and the snapshot will show a gap of its own.
+pub const MAX_PENDING_UPDATES: usize = 1000;
+
+/// Venue sequence numbers an update covers. Most venues number each
+/// update; Binance gives the first and last update ids in each event.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VenueSequence {
+    pub first: u64,
+    pub last: u64,
+}
+
+impl VenueSequence {
+    pub fn range(first: u64, last: u64) -> Self {
+        Self { first, last: last.max(first) }
+    }
+}
+
+impl From<u64> for VenueSequence {
+    fn from(sequence: u64) -> Self {
+        Self { first: sequence, last: sequence }
+    }
+}
+
+/// What [`FastOrderBook::apply_update_with_sequence`] did with an update.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequenceOutcome {
+    Applied,
+    /// Already covered by the book, e.g. by the snapshot it was built from.
+    Outdated,
+    /// Held until the book is resynced.
+    Pending,
+    /// Updates between `expected` and `received` were missed. The book is
+    /// stale from now until its next snapshot.
+    Gap { expected: u64, received: u64 },
+}
+
+/// Asked for a fresh snapshot when a book goes stale, with the book's
+/// symbol.
+#[derive(Clone)]
+pub struct ResyncCallback(Arc<dyn Fn(&Symbol) + Send + Sync>);
+
+impl ResyncCallback {
+    pub fn new(callback: impl Fn(&Symbol) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(callback))
+    }
+}
+
+impl fmt::Debug for ResyncCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ResyncCallback")
+    }
+}
+
+impl PartialEq for ResyncCallback {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -32,7 +107,7 @@ impl PriceLevel {
             price,
             quantity,
             order_count: 1,
-            last_updated: Utc::now(),
+            last_updated: coarse_now(),
         }
     }
 
@@ -41,7 +116,7 @@ impl PriceLevel {
             price,
             quantity,
             order_count,
-            last_updated: Utc::now(),
+            last_updated: coarse_now(),
         }
     }
 
@@ -50,7 +125,7 @@ impl PriceLevel {
         if let Some(count) = order_count {
             self.order_count = count;
         }
-        self.last_updated = Utc::now();
+        self.last_updated = coarse_now();
     }
 
     pub fn is_empty(&self) -> bool {
@@ -65,9 +140,106 @@ impl FastOrderBook {
             bids: BTreeMap::new(),
             asks: BTreeMap::new(),
             sequence: 0,
-            last_update: Utc::now(),
+            last_update: coarse_now(),
             checksum: None,
             max_depth: max_depth.unwrap_or(1000),
+            venue_sequence: None,
+            stale: false,
+            pending: Vec::new(),
+            resync: None,
+        }
+    }
+
+    /// Calls `callback` each time the book goes stale, to fetch a snapshot
+    /// for [`FastOrderBook::apply_snapshot_with_sequence`].
+    pub fn with_resync(mut self, callback: ResyncCallback) -> Self {
+        self.resync = Some(callback);
+        self
+    }
+
+    pub fn set_resync(&mut self, callback: Option<ResyncCallback>) {
+        self.resync = callback;
+    }
+
+    /// Whether the book missed an update and waits for a snapshot. Prices
+    /// read from a stale book may be wrong, and it should not be traded on.
+    pub fn is_stale(&self) -> bool {
+        self.stale
+    }
+
+    /// Last venue sequence number the book has applied.
+    pub fn venue_sequence(&self) -> Option<u64> {
+        self.venue_sequence
+    }
+
+    /// Applies `updates` numbered `sequence` by the venue, if they follow
+    /// on from the last ones applied. Missing some marks the book stale
+    /// and asks for a snapshot through the resync callback; until one is
+    /// applied, later updates are held rather than applied. A book that
+    /// never had a snapshot goes stale on its first update.
+    pub fn apply_update_with_sequence(
+        &mut self,
+        sequence: impl Into<VenueSequence>,
+        updates: Vec<OrderBookUpdate>,
+    ) -> SequenceOutcome {
+        let sequence = sequence.into();
+        if self.stale {
+            self.hold(sequence, updates);
+            return SequenceOutcome::Pending;
+        }
+
+        let Some(last) = self.venue_sequence else {
+            self.hold(sequence, updates);
+            self.mark_stale();
+            return SequenceOutcome::Pending;
+        };
+        if sequence.last <= last {
+            return SequenceOutcome::Outdated;
+        }
+        let expected = last.wrapping_add(1);
+        if sequence.first > expected {
+            warn!(
+                "Sequence gap in {} book: expected {}, got {}",
+                self.symbol, expected, sequence.first
+            );
+            self.hold(sequence, updates);
+            self.mark_stale();
+            return SequenceOutcome::Gap { expected, received: sequence.first };
+        }
+
+        self.batch_update(updates);
+        self.venue_sequence = Some(sequence.last);
+        SequenceOutcome::Applied
+    }
+
+    /// Replaces the book with a venue snapshot as of `sequence`, then
+    /// replays the updates held since it went stale that come after it.
+    /// The book is no longer stale unless they leave a gap of their own.
+    pub fn apply_snapshot_with_sequence(&mut self, bids: Vec<PriceLevel>, asks: Vec<PriceLevel>, sequence: u64) {
+        self.replace_bids(bids);
+        self.replace_asks(asks);
+        self.venue_sequence = Some(sequence);
+        self.stale = false;
+
+        for (sequence, updates) in std::mem::take(&mut self.pending) {
+            self.apply_update_with_sequence(sequence, updates);
+        }
+    }
+
+    fn hold(&mut self, sequence: VenueSequence, updates: Vec<OrderBookUpdate>) {
+        if self.pending.len() >= MAX_PENDING_UPDATES {
+            self.pending.clear();
+        }
+        self.pending.push((sequence, updates));
+    }
+
+    fn mark_stale(&mut self) {
+        if self.stale {
+            return;
+        }
+        self.stale = true;
+        if let Some(resync) = &self.resync {
+            (resync.0)(&self.symbol);
         }
     }
 
@@ -97,7 +269,7 @@ impl FastOrderBook {
 
         self.trim_depth(Side::Bid);
         self.increment_sequence();
-        self.last_update = Utc::now();
+        self.last_update = coarse_now();
     }
 
     pub fn update_ask(&mut self, price: Decimal, quantity: Decimal, order_count: Option<u32>) {
@@ -114,7 +286,7 @@ impl FastOrderBook {
 
         self.trim_depth(Side::Ask);
         self.increment_sequence();
-        self.last_update = Utc::now();
+        self.last_update = coarse_now();
     }
 
     pub fn batch_update(&mut self, updates: Vec<OrderBookUpdate>) {
@@ -134,7 +306,7 @@ impl FastOrderBook {
         }
         self.trim_depth(Side::Bid);
         self.increment_sequence();
-        self.last_update = Utc::now();
+        self.last_update = coarse_now();
     }
 
     pub fn replace_asks(&mut self, levels: Vec<PriceLevel>) {
@@ -145,7 +317,7 @@ impl FastOrderBook {
         }
         self.trim_depth(Side::Ask);
         self.increment_sequence();
-        self.last_update = Utc::now();
+        self.last_update = coarse_now();
     }
 
     pub fn best_bid(&self) -> Option<&PriceLevel> {
@@ -302,8 +474,11 @@ impl FastOrderBook {
         self.bids.clear();
         self.asks.clear();
         self.sequence = 0;
-        self.last_update = Utc::now();
+        self.last_update = coarse_now();
         self.checksum = None;
+        self.venue_sequence = None;
+        self.stale = false;
+        self.pending.clear();
     }
 
     pub fn is_empty(&self) -> bool {
@@ -405,7 +580,7 @@ impl OrderBookUpdate {
             price,
             quantity,
             order_count: None,
-            timestamp: Some(Utc::now()),
+            timestamp: Some(coarse_now()),
         }
     }
 
@@ -415,7 +590,7 @@ impl OrderBookUpdate {
             price,
             quantity,
             order_count: Some(order_count),
-            timestamp: Some(Utc::now()),
+            timestamp: Some(coarse_now()),
         }
     }
 
@@ -426,6 +601,8 @@ impl OrderBookUpdate {
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OrderBookSnapshot {
+    #[serde(default)]
+    pub schema_version: u32,
     pub symbol: Symbol,
     pub bids: Vec<PriceLevel>,
     pub asks: Vec<PriceLevel>,
@@ -436,6 +613,7 @@ pub struct OrderBookSnapshot {
 impl OrderBookSnapshot {
     pub fn from_fast_orderbook(book: &FastOrderBook) -> Self {
         Self {
+            schema_version: Self::SCHEMA_VERSION,
             symbol: book.symbol.clone(),
             bids: book.get_bids(None).into_iter().cloned().collect(),
             asks: book.get_asks(None).into_iter().cloned().collect(),
@@ -453,6 +631,10 @@ impl OrderBookSnapshot {
     }
 }
 
+impl Versioned for OrderBookSnapshot {
+    const SCHEMA_VERSION: u32 = 1;
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -553,6 +735,43 @@ mod tests {
         assert_eq!(book.get_sequence(), 2);
     }
 
+    #[test]
+    fn test_sequence_gap_resyncs_from_snapshot() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let requested = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let log = Arc::clone(&requested);
+        let mut book = FastOrderBook::new(symbol.clone(), None)
+            .with_resync(ResyncCallback::new(move |symbol: &Symbol| log.lock().unwrap().push(symbol.clone())));
+        let bid = |price: i64, quantity: i64| vec![OrderBookUpdate::new(Side::Bid, Decimal::from(price), Decimal::from(quantity))];
+
+        // Nothing to check the first update against
+        assert_eq!(book.apply_update_with_sequence(VenueSequence::range(98, 101), bid(100, 1)), SequenceOutcome::Pending);
+        assert!(book.is_stale());
+        assert_eq!(requested.lock().unwrap().len(), 1);
+
+        // The snapshot covers up to 100, so 101 is replayed over it
+        book.apply_snapshot_with_sequence(vec![PriceLevel::new(Decimal::from(99), Decimal::from(5))], vec![], 100);
+        assert!(!book.is_stale());
+        assert_eq!(book.venue_sequence(), Some(101));
+        assert_eq!(book.best_bid_price(), Some(Decimal::from(100)));
+
+        assert_eq!(book.apply_update_with_sequence(101, bid(100, 2)), SequenceOutcome::Outdated);
+        assert_eq!(book.apply_update_with_sequence(102, bid(100, 3)), SequenceOutcome::Applied);
+        assert_eq!(
+            book.apply_update_with_sequence(105, bid(101, 1)),
+            SequenceOutcome::Gap { expected: 103, received: 105 }
+        );
+        assert!(book.is_stale());
+        assert_eq!(book.best_bid_price(), Some(Decimal::from(100)));
+        assert_eq!(book.apply_update_with_sequence(106, bid(102, 1)), SequenceOutcome::Pending);
+        assert_eq!(requested.lock().unwrap().as_slice(), &[symbol.clone(), symbol]);
+
+        book.apply_snapshot_with_sequence(vec![PriceLevel::new(Decimal::from(100), Decimal::from(3))], vec![], 104);
+        assert!(!book.is_stale());
+        assert_eq!(book.venue_sequence(), Some(106));
+        assert_eq!(book.best_bid_price(), Some(Decimal::from(102)));
+    }
+
     #[test]
     fn test_checksum() {
         let symbol = Symbol::new("BTC", "USDT");
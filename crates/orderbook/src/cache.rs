@@ -5,7 +5,7 @@
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use arbfinder_core::{Symbol, VenueId};
+use arbfinder_core::{Symbol, SymbolId, VenueId};
 use chrono::{DateTime, Utc, Duration};
 
 use crate::{FastOrderBook, OrderBookSnapshot};
@@ -28,7 +28,7 @@ pub struct OrderBookCache {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct CacheKey {
     venue_id: VenueId,
-    symbol: Symbol,
+    symbol: SymbolId,
 }
 
 impl OrderBookCache {
@@ -44,7 +44,7 @@ impl OrderBookCache {
         let mut cache = self.cache.write().await;
         let key = CacheKey {
             venue_id: venue_id.clone(),
-            symbol: symbol.clone(),
+            symbol: symbol.id(),
         };
 
         if let Some(entry) = cache.get_mut(&key) {
@@ -66,7 +66,7 @@ impl OrderBookCache {
         let mut cache = self.cache.write().await;
         let key = CacheKey {
             venue_id,
-            symbol: book.symbol.clone(),
+            symbol: book.symbol.id(),
         };
 
         // Evict if cache is full
@@ -87,7 +87,7 @@ impl OrderBookCache {
         let mut cache = self.cache.write().await;
         let key = CacheKey {
             venue_id: venue_id.clone(),
-            symbol: symbol.clone(),
+            symbol: symbol.id(),
         };
         cache.remove(&key);
     }
@@ -152,7 +152,7 @@ pub struct SnapshotCache {
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct SnapshotKey {
     venue_id: VenueId,
-    symbol: Symbol,
+    symbol: SymbolId,
 }
 
 impl SnapshotCache {
@@ -167,7 +167,7 @@ impl SnapshotCache {
         let mut snapshots = self.snapshots.write().await;
         let key = SnapshotKey {
             venue_id,
-            symbol: snapshot.symbol.clone(),
+            symbol: snapshot.symbol.id(),
         };
 
         let entry = snapshots.entry(key).or_insert_with(Vec::new);
@@ -183,7 +183,7 @@ impl SnapshotCache {
         let snapshots = self.snapshots.read().await;
         let key = SnapshotKey {
             venue_id: venue_id.clone(),
-            symbol: symbol.clone(),
+            symbol: symbol.id(),
         };
 
         snapshots.get(&key)?.last().cloned()
@@ -193,7 +193,7 @@ impl SnapshotCache {
         let snapshots = self.snapshots.read().await;
         let key = SnapshotKey {
             venue_id: venue_id.clone(),
-            symbol: symbol.clone(),
+            symbol: symbol.id(),
         };
 
         snapshots.get(&key).cloned().unwrap_or_default()
@@ -203,7 +203,7 @@ impl SnapshotCache {
         let mut snapshots = self.snapshots.write().await;
         let key = SnapshotKey {
             venue_id: venue_id.clone(),
-            symbol: symbol.clone(),
+            symbol: symbol.id(),
         };
         snapshots.remove(&key);
     }
@@ -1,8 +1,12 @@
-use arbfinder_core::{Side, Symbol, VenueId};
-use chrono::{DateTime, Utc};
+use arbfinder_core::{EventTime, Side, Symbol, VenueId};
+use async_trait::async_trait;
 use rust_decimal::Decimal;
 use rust_decimal::prelude::{ToPrimitive, FromStr};
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 
 use crate::{FastOrderBook, OrderBookSnapshot, OrderBookUpdate, PriceLevel};
 
@@ -18,12 +22,29 @@ pub enum OrderBookEvent {
     PriceMovement(PriceMovementEvent),
 }
 
+impl OrderBookEvent {
+    /// When the event was generated. Measure handler lag with
+    /// `event.timestamp().elapsed()` rather than against the wall clock.
+    pub fn timestamp(&self) -> EventTime {
+        match self {
+            OrderBookEvent::Snapshot(evt) => evt.timestamp,
+            OrderBookEvent::Update(evt) => evt.timestamp,
+            OrderBookEvent::BestBidAskUpdate(evt) => evt.timestamp,
+            OrderBookEvent::SpreadUpdate(evt) => evt.timestamp,
+            OrderBookEvent::VolumeUpdate(evt) => evt.timestamp,
+            OrderBookEvent::CrossingDetected(evt) => evt.timestamp,
+            OrderBookEvent::LiquidityGap(evt) => evt.timestamp,
+            OrderBookEvent::PriceMovement(evt) => evt.timestamp,
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OrderBookSnapshotEvent {
     pub venue_id: VenueId,
     pub symbol: Symbol,
     pub snapshot: OrderBookSnapshot,
-    pub timestamp: DateTime<Utc>,
+    pub timestamp: EventTime,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -32,7 +53,7 @@ pub struct OrderBookUpdateEvent {
     pub symbol: Symbol,
     pub updates: Vec<OrderBookUpdate>,
     pub sequence: u64,
-    pub timestamp: DateTime<Utc>,
+    pub timestamp: EventTime,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -43,7 +64,7 @@ pub struct BestBidAskEvent {
     pub best_ask: Option<PriceLevel>,
     pub previous_best_bid: Option<PriceLevel>,
     pub previous_best_ask: Option<PriceLevel>,
-    pub timestamp: DateTime<Utc>,
+    pub timestamp: EventTime,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -54,7 +75,7 @@ pub struct SpreadUpdateEvent {
     pub spread_bps: Option<i32>,
     pub previous_spread: Option<Decimal>,
     pub mid_price: Option<Decimal>,
-    pub timestamp: DateTime<Utc>,
+    pub timestamp: EventTime,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -65,7 +86,7 @@ pub struct VolumeUpdateEvent {
     pub total_ask_volume: Decimal,
     pub imbalance_ratio: Option<f64>,
     pub depth: usize,
-    pub timestamp: DateTime<Utc>,
+    pub timestamp: EventTime,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -76,7 +97,7 @@ pub struct CrossingEvent {
     pub best_ask: PriceLevel,
     pub cross_amount: Decimal,
     pub severity: CrossingSeverity,
-    pub timestamp: DateTime<Utc>,
+    pub timestamp: EventTime,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -95,7 +116,7 @@ pub struct LiquidityGapEvent {
     pub gap_end: Decimal,
     pub gap_size: Decimal,
     pub depth_level: usize,
-    pub timestamp: DateTime<Utc>,
+    pub timestamp: EventTime,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -107,7 +128,7 @@ pub struct PriceMovementEvent {
     pub new_price: Decimal,
     pub change_bps: i32,
     pub movement_type: PriceMovementType,
-    pub timestamp: DateTime<Utc>,
+    pub timestamp: EventTime,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -117,10 +138,57 @@ pub enum PriceMovementType {
     Neutral,     // Same price
 }
 
+/// Called inline on the book update path, so implementations must be cheap.
 pub trait OrderBookEventHandler: Send + Sync {
     fn handle_event(&mut self, event: OrderBookEvent);
 }
 
+/// Handler that may await IO. Registered through
+/// [`OrderBookEventProcessor::add_async_handler`], which runs it on its own
+/// task so the book update path never waits on it.
+#[async_trait]
+pub trait AsyncOrderBookEventHandler: Send {
+    async fn handle_event(&mut self, event: OrderBookEvent);
+}
+
+#[async_trait]
+impl<T: OrderBookEventHandler> AsyncOrderBookEventHandler for T {
+    async fn handle_event(&mut self, event: OrderBookEvent) {
+        OrderBookEventHandler::handle_event(self, event);
+    }
+}
+
+/// Forwards events into a bounded channel. When the consumer falls behind,
+/// events are dropped and counted rather than blocking the producer.
+#[derive(Debug)]
+pub struct ChannelEventHandler {
+    tx: mpsc::Sender<OrderBookEvent>,
+    dropped: Arc<AtomicU64>,
+}
+
+impl ChannelEventHandler {
+    pub fn new(tx: mpsc::Sender<OrderBookEvent>) -> Self {
+        Self {
+            tx,
+            dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Shared counter of events dropped because the channel was full.
+    pub fn dropped_counter(&self) -> Arc<AtomicU64> {
+        self.dropped.clone()
+    }
+}
+
+impl OrderBookEventHandler for ChannelEventHandler {
+    fn handle_event(&mut self, event: OrderBookEvent) {
+        if let Err(mpsc::error::TrySendError::Full(_)) = self.tx.try_send(event) {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            tracing::warn!("Order book event handler lagging, {} events dropped", dropped);
+        }
+    }
+}
+
 pub struct OrderBookEventProcessor {
     handlers: Vec<Box<dyn OrderBookEventHandler>>,
     previous_state: Option<OrderBookState>,
@@ -148,6 +216,24 @@ impl OrderBookEventProcessor {
         self.handlers.push(handler);
     }
 
+    /// Runs `handler` on a dedicated task fed by a channel of `capacity`
+    /// events. The task ends once the processor is dropped and the channel
+    /// drains. Must be called from within a Tokio runtime.
+    pub fn add_async_handler(
+        &mut self,
+        mut handler: Box<dyn AsyncOrderBookEventHandler>,
+        capacity: usize,
+    ) -> JoinHandle<()> {
+        let (tx, mut rx) = mpsc::channel(capacity.max(1));
+        self.handlers.push(Box::new(ChannelEventHandler::new(tx)));
+
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                handler.handle_event(event).await;
+            }
+        })
+    }
+
     pub fn process_book_update(&mut self, venue_id: VenueId, book: &FastOrderBook) {
         let current_state = OrderBookState {
             best_bid: book.best_bid().cloned(),
@@ -174,7 +260,7 @@ impl OrderBookEventProcessor {
         current_state: &OrderBookState,
     ) -> Vec<OrderBookEvent> {
         let mut events = Vec::new();
-        let timestamp = Utc::now();
+        let timestamp = EventTime::now();
 
         // Check for best bid/ask changes
         if let Some(prev_state) = &self.previous_state {
@@ -284,7 +370,7 @@ impl OrderBookEventProcessor {
         prev_state: &OrderBookState,
         current_state: &OrderBookState,
         events: &mut Vec<OrderBookEvent>,
-        timestamp: DateTime<Utc>,
+        timestamp: EventTime,
     ) {
         // Check bid movement
         if let (Some(prev_bid), Some(current_bid)) = (&prev_state.best_bid, &current_state.best_bid) {
@@ -498,6 +584,69 @@ mod tests {
         processor.process_book_update(VenueId::Binance, &book);
     }
 
+    struct RecordingHandler {
+        events: Arc<parking_lot::Mutex<Vec<OrderBookEvent>>>,
+    }
+
+    #[async_trait]
+    impl AsyncOrderBookEventHandler for RecordingHandler {
+        async fn handle_event(&mut self, event: OrderBookEvent) {
+            tokio::task::yield_now().await;
+            self.events.lock().push(event);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_async_handler() {
+        let events = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let mut processor = OrderBookEventProcessor::new();
+        let recorder = processor.add_async_handler(
+            Box::new(RecordingHandler { events: events.clone() }),
+            16,
+        );
+        // Sync handlers can run behind the channel too
+        let metrics = processor.add_async_handler(Box::new(MetricsEventHandler::new()), 16);
+
+        let mut book = FastOrderBook::new(Symbol::new("BTC", "USDT"), None);
+        book.update_bid(Decimal::from(50000), Decimal::from(1), None);
+        book.update_ask(Decimal::from(50001), Decimal::from(1), None);
+        processor.process_book_update(VenueId::Binance, &book);
+
+        book.update_bid(Decimal::from(50000), Decimal::ZERO, None);
+        book.update_bid(Decimal::from(49000), Decimal::from(1), None);
+        processor.process_book_update(VenueId::Binance, &book);
+
+        drop(processor);
+        recorder.await.unwrap();
+        metrics.await.unwrap();
+
+        let events = events.lock();
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, OrderBookEvent::BestBidAskUpdate(_))));
+    }
+
+    #[tokio::test]
+    async fn test_channel_handler_drops_when_full() {
+        let (tx, _rx) = mpsc::channel(1);
+        let mut handler = ChannelEventHandler::new(tx);
+        let dropped = handler.dropped_counter();
+        let event = OrderBookEvent::LiquidityGap(LiquidityGapEvent {
+            venue_id: VenueId::Binance,
+            symbol: Symbol::new("BTC", "USDT"),
+            side: Side::Bid,
+            gap_start: Decimal::from(100),
+            gap_end: Decimal::from(110),
+            gap_size: Decimal::from(10),
+            depth_level: 1,
+            timestamp: EventTime::now(),
+        });
+
+        OrderBookEventHandler::handle_event(&mut handler, event.clone());
+        OrderBookEventHandler::handle_event(&mut handler, event);
+        assert_eq!(dropped.load(Ordering::Relaxed), 1);
+    }
+
     #[test]
     fn test_crossing_detection() {
         let symbol = Symbol::new("BTC", "USDT");
@@ -0,0 +1,283 @@
+//! Order-by-order (L3) books
+//!
+//! Tracks each resting order for venues publishing full feeds, such as
+//! Coinbase's `full` channel and Bitfinex raw books, keeping the orders at
+//! each price in time priority so an order's place in the queue is known.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use arbfinder_core::utils::coarse_now;
+use arbfinder_core::{Side, Symbol};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+
+use crate::{FastOrderBook, PriceLevel};
+
+/// A resting order
+#[derive(Debug, Clone, PartialEq)]
+pub struct L3Order {
+    pub id: String,
+    pub side: Side,
+    pub price: Decimal,
+    /// Size still resting
+    pub size: Decimal,
+    /// When the order took its place in the queue
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Where an order stands, or would stand, in the queue at its price
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueuePosition {
+    /// Orders that fill before it
+    pub orders_ahead: usize,
+    /// Their combined size, which must trade before it fills at all
+    pub size_ahead: Decimal,
+    /// Size of the whole level, the order included
+    pub level_size: Decimal,
+}
+
+/// Order book of individual orders, keyed by venue order id
+#[derive(Debug, Clone)]
+pub struct L3OrderBook {
+    pub symbol: Symbol,
+    orders: HashMap<String, L3Order>,
+    /// Order ids at each price, oldest first
+    bids: BTreeMap<Decimal, VecDeque<String>>,
+    asks: BTreeMap<Decimal, VecDeque<String>>,
+    sequence: u64,
+    last_update: DateTime<Utc>,
+}
+
+impl L3OrderBook {
+    pub fn new(symbol: Symbol) -> Self {
+        Self {
+            symbol,
+            orders: HashMap::new(),
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            sequence: 0,
+            last_update: coarse_now(),
+        }
+    }
+
+    pub fn get_sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    pub fn set_sequence(&mut self, sequence: u64) {
+        self.sequence = sequence;
+    }
+
+    pub fn last_update(&self) -> DateTime<Utc> {
+        self.last_update
+    }
+
+    fn queues(&mut self, side: Side) -> &mut BTreeMap<Decimal, VecDeque<String>> {
+        match side {
+            Side::Bid => &mut self.bids,
+            Side::Ask => &mut self.asks,
+        }
+    }
+
+    fn queue(&self, side: Side, price: Decimal) -> Option<&VecDeque<String>> {
+        match side {
+            Side::Bid => self.bids.get(&price),
+            Side::Ask => self.asks.get(&price),
+        }
+    }
+
+    /// Adds an order at the back of its price's queue, or updates one
+    /// already in the book. Like venues' matching engines, an order keeps
+    /// its place only when its size shrinks at the same price; a new price
+    /// or a larger size sends it to the back. A zero size removes it.
+    pub fn add_order(&mut self, id: impl Into<String>, side: Side, price: Decimal, size: Decimal) {
+        let id = id.into();
+        self.last_update = coarse_now();
+        if size <= Decimal::ZERO {
+            self.remove_order(&id);
+            return;
+        }
+
+        if let Some(order) = self.orders.get_mut(&id) {
+            if order.side == side && order.price == price && size <= order.size {
+                order.size = size;
+                return;
+            }
+            self.remove_order(&id);
+        }
+
+        self.queues(side).entry(price).or_default().push_back(id.clone());
+        let timestamp = self.last_update;
+        self.orders.insert(id.clone(), L3Order { id, side, price, size, timestamp });
+    }
+
+    /// Sets the resting size of an order already in the book, as for a
+    /// Coinbase `change`. Returns false for an order not in the book.
+    pub fn change_order(&mut self, id: &str, size: Decimal) -> bool {
+        let Some(order) = self.orders.get(id) else {
+            return false;
+        };
+        let (side, price) = (order.side, order.price);
+        self.add_order(id, side, price, size);
+        true
+    }
+
+    /// Takes `size` traded off an order, removing it once nothing rests.
+    /// Returns false for an order not in the book.
+    pub fn fill_order(&mut self, id: &str, size: Decimal) -> bool {
+        let Some(order) = self.orders.get_mut(id) else {
+            return false;
+        };
+        self.last_update = coarse_now();
+        order.size -= size;
+        if order.size <= Decimal::ZERO {
+            self.remove_order(id);
+        }
+        true
+    }
+
+    pub fn remove_order(&mut self, id: &str) -> Option<L3Order> {
+        let order = self.orders.remove(id)?;
+        self.last_update = coarse_now();
+        let queues = self.queues(order.side);
+        if let Some(queue) = queues.get_mut(&order.price) {
+            queue.retain(|queued| queued != id);
+            if queue.is_empty() {
+                queues.remove(&order.price);
+            }
+        }
+        Some(order)
+    }
+
+    pub fn get_order(&self, id: &str) -> Option<&L3Order> {
+        self.orders.get(id)
+    }
+
+    /// Orders resting at `price`, oldest first
+    pub fn orders_at(&self, side: Side, price: Decimal) -> Vec<&L3Order> {
+        self.queue(side, price)
+            .into_iter()
+            .flatten()
+            .filter_map(|id| self.orders.get(id))
+            .collect()
+    }
+
+    pub fn level_size(&self, side: Side, price: Decimal) -> Decimal {
+        self.orders_at(side, price).iter().map(|order| order.size).sum()
+    }
+
+    pub fn best_bid_price(&self) -> Option<Decimal> {
+        self.bids.keys().next_back().copied()
+    }
+
+    pub fn best_ask_price(&self) -> Option<Decimal> {
+        self.asks.keys().next().copied()
+    }
+
+    /// Where the order with venue id `id` stands in its queue
+    pub fn queue_position(&self, id: &str) -> Option<QueuePosition> {
+        let order = self.orders.get(id)?;
+        let mut position = QueuePosition {
+            orders_ahead: 0,
+            size_ahead: Decimal::ZERO,
+            level_size: Decimal::ZERO,
+        };
+        let mut ahead = true;
+        for queued in self.orders_at(order.side, order.price) {
+            if queued.id == order.id {
+                ahead = false;
+            } else if ahead {
+                position.orders_ahead += 1;
+                position.size_ahead += queued.size;
+            }
+            position.level_size += queued.size;
+        }
+        Some(position)
+    }
+
+    /// Where an order of `size` placed at `price` now would stand: behind
+    /// everything already resting there
+    pub fn estimate_queue_position(&self, side: Side, price: Decimal, size: Decimal) -> QueuePosition {
+        let ahead = self.orders_at(side, price);
+        let size_ahead: Decimal = ahead.iter().map(|order| order.size).sum();
+        QueuePosition {
+            orders_ahead: ahead.len(),
+            size_ahead,
+            level_size: size_ahead + size,
+        }
+    }
+
+    /// Orders resting, on both sides
+    pub fn order_count(&self) -> usize {
+        self.orders.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.orders.clear();
+        self.bids.clear();
+        self.asks.clear();
+        self.sequence = 0;
+        self.last_update = coarse_now();
+    }
+
+    /// The book by price level, each level's order count filled in
+    pub fn to_fast_orderbook(&self, max_depth: Option<usize>) -> FastOrderBook {
+        let level = |(price, queue): (&Decimal, &VecDeque<String>)| {
+            let size = queue.iter().filter_map(|id| self.orders.get(id)).map(|order| order.size).sum();
+            PriceLevel::with_order_count(*price, size, queue.len() as u32)
+        };
+        let mut book = FastOrderBook::new(self.symbol.clone(), max_depth);
+        book.replace_bids(self.bids.iter().map(level).collect());
+        book.replace_asks(self.asks.iter().map(level).collect());
+        book.set_sequence(self.sequence);
+        book
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_l3_queue_position() {
+        let mut book = L3OrderBook::new(Symbol::new("BTC", "USD"));
+        let price = Decimal::from(100);
+        book.add_order("a", Side::Bid, price, Decimal::from(2));
+        book.add_order("b", Side::Bid, price, Decimal::from(3));
+        book.add_order("mine", Side::Bid, price, Decimal::ONE);
+        book.add_order("c", Side::Bid, Decimal::from(99), Decimal::from(5));
+        book.add_order("d", Side::Ask, Decimal::from(101), Decimal::ONE);
+
+        let position = book.queue_position("mine").unwrap();
+        assert_eq!(position.orders_ahead, 2);
+        assert_eq!(position.size_ahead, Decimal::from(5));
+        assert_eq!(position.level_size, Decimal::from(6));
+
+        // Shrinking keeps a's place, growing sends b to the back
+        book.change_order("a", Decimal::ONE);
+        book.add_order("b", Side::Bid, price, Decimal::from(4));
+        let ids: Vec<&str> = book.orders_at(Side::Bid, price).iter().map(|order| order.id.as_str()).collect();
+        assert_eq!(ids, ["a", "mine", "b"]);
+
+        // a trades away, leaving mine at the front
+        assert!(book.fill_order("a", Decimal::ONE));
+        assert!(book.get_order("a").is_none());
+        assert_eq!(book.queue_position("mine").unwrap().orders_ahead, 0);
+
+        let estimate = book.estimate_queue_position(Side::Bid, price, Decimal::ONE);
+        assert_eq!(estimate.orders_ahead, 2);
+        assert_eq!(estimate.size_ahead, Decimal::from(5));
+
+        let fast = book.to_fast_orderbook(None);
+        assert_eq!(fast.best_bid().unwrap().quantity, Decimal::from(5));
+        assert_eq!(fast.best_bid().unwrap().order_count, 2);
+        assert_eq!(fast.best_ask_price(), Some(Decimal::from(101)));
+
+        book.remove_order("d");
+        assert_eq!(book.best_ask_price(), None);
+        assert_eq!(book.order_count(), 3);
+    }
+}
@@ -3,11 +3,17 @@ pub mod builder;
 pub mod aggregator;
 pub mod cache;
 pub mod events;
+pub mod l3;
 pub mod manager;
+pub mod recording;
+pub mod sanity;
 
 pub use book::*;
 pub use builder::*;
 pub use aggregator::*;
 pub use cache::*;
 pub use events::*;
-pub use manager::*;
\ No newline at end of file
+pub use l3::*;
+pub use manager::*;
+pub use recording::*;
+pub use sanity::*;
\ No newline at end of file
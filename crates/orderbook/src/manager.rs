@@ -1,26 +1,31 @@
 //! OrderBook Manager
 //!
-//! Manages multiple order books across venues and symbols
+//! Manages multiple order books across venues and symbols. Books are looked
+//! up by [`SymbolId`], which callers intern once where the symbol comes in
+//! rather than on every lookup.
 
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, info, warn};
 
-use arbfinder_core::{Symbol, VenueId};
-use crate::{FastOrderBook, OrderBookSnapshot, OrderBookUpdate, OrderBookCache};
+use arbfinder_core::{Symbol, SymbolId, VenueId};
+use crate::{AggregatedOrderBook, FastOrderBook, OrderBookSnapshot, OrderBookUpdate, OrderBookCache, ResyncCallback, SequenceOutcome, VenueSequence};
+
+type VenueResync = Arc<dyn Fn(&VenueId, &Symbol) + Send + Sync>;
 
 /// Manages order books for multiple venues and symbols
 pub struct OrderBookManager {
     books: Arc<RwLock<HashMap<BookKey, Arc<RwLock<FastOrderBook>>>>>,
     cache: Option<OrderBookCache>,
     max_depth: usize,
+    resync: Option<VenueResync>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 struct BookKey {
     venue_id: VenueId,
-    symbol: Symbol,
+    symbol: SymbolId,
 }
 
 impl OrderBookManager {
@@ -29,6 +34,7 @@ impl OrderBookManager {
             books: Arc::new(RwLock::new(HashMap::new())),
             cache: None,
             max_depth,
+            resync: None,
         }
     }
 
@@ -37,14 +43,21 @@ impl OrderBookManager {
         self
     }
 
+    /// Calls `callback` with the venue and symbol of each book that goes
+    /// stale, to fetch a snapshot for [`Self::apply_snapshot_with_sequence`].
+    pub fn with_resync(mut self, callback: impl Fn(&VenueId, &Symbol) + Send + Sync + 'static) -> Self {
+        self.resync = Some(Arc::new(callback));
+        self
+    }
+
     pub async fn get_or_create_book(
         &self,
         venue_id: VenueId,
-        symbol: Symbol,
+        symbol: SymbolId,
     ) -> Arc<RwLock<FastOrderBook>> {
         let key = BookKey {
             venue_id: venue_id.clone(),
-            symbol: symbol.clone(),
+            symbol,
         };
 
         let books = self.books.read().await;
@@ -61,17 +74,22 @@ impl OrderBookManager {
             return Arc::clone(book);
         }
 
-        let new_book = Arc::new(RwLock::new(FastOrderBook::new(symbol, Some(self.max_depth))));
+        let mut new_book = FastOrderBook::new(symbol.symbol().clone(), Some(self.max_depth));
+        if let Some(resync) = &self.resync {
+            let (resync, venue_id) = (Arc::clone(resync), venue_id.clone());
+            new_book.set_resync(Some(ResyncCallback::new(move |symbol: &Symbol| resync(&venue_id, symbol))));
+        }
+        let new_book = Arc::new(RwLock::new(new_book));
         books.insert(key, Arc::clone(&new_book));
         
         info!("Created new orderbook for {} on {}", new_book.read().await.symbol, venue_id);
         new_book
     }
 
-    pub async fn get_book(&self, venue_id: &VenueId, symbol: &Symbol) -> Option<Arc<RwLock<FastOrderBook>>> {
+    pub async fn get_book(&self, venue_id: &VenueId, symbol: SymbolId) -> Option<Arc<RwLock<FastOrderBook>>> {
         let key = BookKey {
             venue_id: venue_id.clone(),
-            symbol: symbol.clone(),
+            symbol,
         };
         
         let books = self.books.read().await;
@@ -79,7 +97,7 @@ impl OrderBookManager {
     }
 
     pub async fn apply_snapshot(&self, venue_id: VenueId, snapshot: OrderBookSnapshot) {
-        let book = self.get_or_create_book(venue_id.clone(), snapshot.symbol.clone()).await;
+        let book = self.get_or_create_book(venue_id.clone(), snapshot.symbol.id()).await;
         let mut book_guard = book.write().await;
         snapshot.apply_to_book(&mut book_guard);
         
@@ -94,8 +112,8 @@ impl OrderBookManager {
         }
     }
 
-    pub async fn apply_updates(&self, venue_id: VenueId, symbol: Symbol, updates: Vec<OrderBookUpdate>) {
-        let book = self.get_or_create_book(venue_id.clone(), symbol.clone()).await;
+    pub async fn apply_updates(&self, venue_id: VenueId, symbol: SymbolId, updates: Vec<OrderBookUpdate>) {
+        let book = self.get_or_create_book(venue_id.clone(), symbol).await;
         let mut book_guard = book.write().await;
         book_guard.batch_update(updates.clone());
         
@@ -112,10 +130,60 @@ impl OrderBookManager {
         }
     }
 
-    pub async fn remove_book(&self, venue_id: &VenueId, symbol: &Symbol) -> Option<Arc<RwLock<FastOrderBook>>> {
+    /// Applies `updates` numbered `sequence` by the venue; see
+    /// [`FastOrderBook::apply_update_with_sequence`].
+    pub async fn apply_updates_with_sequence(
+        &self,
+        venue_id: VenueId,
+        symbol: SymbolId,
+        sequence: impl Into<VenueSequence>,
+        updates: Vec<OrderBookUpdate>,
+    ) -> SequenceOutcome {
+        let book = self.get_or_create_book(venue_id.clone(), symbol).await;
+        let mut book_guard = book.write().await;
+        let outcome = book_guard.apply_update_with_sequence(sequence, updates);
+
+        if outcome == SequenceOutcome::Applied {
+            if let Some(cache) = &self.cache {
+                cache.put(venue_id, book_guard.clone()).await;
+            }
+        }
+        outcome
+    }
+
+    /// Resyncs a book from a venue snapshot whose `sequence` is the
+    /// venue's, replaying the updates held while it was stale.
+    pub async fn apply_snapshot_with_sequence(&self, venue_id: VenueId, snapshot: OrderBookSnapshot) {
+        let book = self.get_or_create_book(venue_id.clone(), snapshot.symbol.id()).await;
+        let mut book_guard = book.write().await;
+        book_guard.apply_snapshot_with_sequence(snapshot.bids, snapshot.asks, snapshot.sequence);
+        if book_guard.is_stale() {
+            warn!("{} book on {} is still missing updates after its snapshot", snapshot.symbol, venue_id);
+        } else {
+            info!("Resynced {} book on {} at sequence {}", snapshot.symbol, venue_id, snapshot.sequence);
+        }
+
+        if let Some(cache) = &self.cache {
+            cache.put(venue_id, book_guard.clone()).await;
+        }
+    }
+
+    /// Books that missed updates and wait for a snapshot.
+    pub async fn stale_books(&self) -> Vec<(VenueId, Symbol)> {
+        let mut stale = Vec::new();
+        for (key, book) in self.books.read().await.iter() {
+            let book_guard = book.read().await;
+            if book_guard.is_stale() {
+                stale.push((key.venue_id.clone(), book_guard.symbol.clone()));
+            }
+        }
+        stale
+    }
+
+    pub async fn remove_book(&self, venue_id: &VenueId, symbol: SymbolId) -> Option<Arc<RwLock<FastOrderBook>>> {
         let key = BookKey {
             venue_id: venue_id.clone(),
-            symbol: symbol.clone(),
+            symbol,
         };
 
         let mut books = self.books.write().await;
@@ -126,7 +194,7 @@ impl OrderBookManager {
             
             // Invalidate cache if available
             if let Some(cache) = &self.cache {
-                cache.invalidate(venue_id, symbol).await;
+                cache.invalidate(venue_id, symbol.symbol()).await;
             }
         }
 
@@ -169,14 +237,14 @@ impl OrderBookManager {
         books
             .keys()
             .filter(|key| &key.venue_id == venue_id)
-            .map(|key| key.symbol.clone())
+            .map(|key| key.symbol.symbol().clone())
             .collect()
     }
 
-    pub async fn has_book(&self, venue_id: &VenueId, symbol: &Symbol) -> bool {
+    pub async fn has_book(&self, venue_id: &VenueId, symbol: SymbolId) -> bool {
         let key = BookKey {
             venue_id: venue_id.clone(),
-            symbol: symbol.clone(),
+            symbol,
         };
         self.books.read().await.contains_key(&key)
     }
@@ -185,11 +253,29 @@ impl OrderBookManager {
         let books = self.books.read().await;
         books
             .iter()
-            .map(|(key, book)| (key.venue_id.clone(), key.symbol.clone(), Arc::clone(book)))
+            .map(|(key, book)| (key.venue_id.clone(), key.symbol.symbol().clone(), Arc::clone(book)))
             .collect()
     }
 
-    pub async fn get_snapshot(&self, venue_id: &VenueId, symbol: &Symbol) -> Option<OrderBookSnapshot> {
+    /// Copies of every venue's book for `symbol`, merged into one view
+    pub async fn aggregated_book(&self, symbol: SymbolId) -> AggregatedOrderBook {
+        let books: Vec<_> = self
+            .books
+            .read()
+            .await
+            .iter()
+            .filter(|(key, _)| key.symbol == symbol)
+            .map(|(key, book)| (key.venue_id.clone(), Arc::clone(book)))
+            .collect();
+
+        let mut aggregated = AggregatedOrderBook::new(symbol.symbol().clone());
+        for (venue_id, book) in books {
+            aggregated.add_venue(venue_id, book.read().await.clone());
+        }
+        aggregated
+    }
+
+    pub async fn get_snapshot(&self, venue_id: &VenueId, symbol: SymbolId) -> Option<OrderBookSnapshot> {
         let book = self.get_book(venue_id, symbol).await?;
         let book_guard = book.read().await;
         Some(OrderBookSnapshot::from_fast_orderbook(&book_guard))
@@ -200,6 +286,7 @@ impl OrderBookManager {
         let total_books = books.len();
         let mut empty_books = 0;
         let mut crossed_books = 0;
+        let mut stale_books = 0;
 
         for book in books.values() {
             let book_guard = book.read().await;
@@ -210,13 +297,17 @@ impl OrderBookManager {
                 crossed_books += 1;
                 warn!("Crossed orderbook detected: {}", book_guard.symbol);
             }
+            if book_guard.is_stale() {
+                stale_books += 1;
+            }
         }
 
         ManagerHealthStatus {
             total_books,
             empty_books,
             crossed_books,
-            healthy: crossed_books == 0,
+            stale_books,
+            healthy: crossed_books == 0 && stale_books == 0,
         }
     }
 }
@@ -226,6 +317,8 @@ pub struct ManagerHealthStatus {
     pub total_books: usize,
     pub empty_books: usize,
     pub crossed_books: usize,
+    /// Books waiting for a snapshot after missing updates.
+    pub stale_books: usize,
     pub healthy: bool,
 }
 
@@ -242,25 +335,53 @@ mod tests {
     #[tokio::test]
     async fn test_manager_basic() {
         let manager = OrderBookManager::new(100);
-        let symbol = Symbol::new("BTC", "USDT");
+        let symbol = Symbol::new("BTC", "USDT").id();
         
-        assert!(!manager.has_book(&VenueId::Binance, &symbol).await);
+        assert!(!manager.has_book(&VenueId::Binance, symbol).await);
         
-        let _book = manager.get_or_create_book(VenueId::Binance, symbol.clone()).await;
+        let _book = manager.get_or_create_book(VenueId::Binance, symbol).await;
         
-        assert!(manager.has_book(&VenueId::Binance, &symbol).await);
+        assert!(manager.has_book(&VenueId::Binance, symbol).await);
         assert_eq!(manager.get_book_count().await, 1);
+
+        let _book = manager.get_or_create_book(VenueId::Kraken, symbol).await;
+        let _book = manager.get_or_create_book(VenueId::Kraken, Symbol::new("ETH", "USDT").id()).await;
+        assert_eq!(manager.aggregated_book(symbol).await.venue_count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_manager_resyncs_stale_books() {
+        let (tx, mut resyncs) = tokio::sync::mpsc::unbounded_channel();
+        let manager = OrderBookManager::new(100)
+            .with_resync(move |venue: &VenueId, symbol: &Symbol| {
+                let _ = tx.send((venue.clone(), symbol.clone()));
+            });
+        let symbol = Symbol::new("BTC", "USDT");
+        let update = || vec![OrderBookUpdate::new(arbfinder_core::Side::Ask, rust_decimal::Decimal::from(101), rust_decimal::Decimal::ONE)];
+
+        let outcome = manager.apply_updates_with_sequence(VenueId::Kraken, symbol.id(), 7, update()).await;
+        assert_eq!(outcome, SequenceOutcome::Pending);
+        assert_eq!(resyncs.try_recv().unwrap(), (VenueId::Kraken, symbol.clone()));
+        assert_eq!(manager.stale_books().await, vec![(VenueId::Kraken, symbol.clone())]);
+        assert!(!manager.health_check().await.healthy);
+
+        let book = manager.get_book(&VenueId::Kraken, symbol.id()).await.unwrap();
+        let mut snapshot = OrderBookSnapshot::from_fast_orderbook(&*book.read().await);
+        snapshot.sequence = 6;
+        manager.apply_snapshot_with_sequence(VenueId::Kraken, snapshot).await;
+        assert!(manager.stale_books().await.is_empty());
+        assert_eq!(book.read().await.venue_sequence(), Some(7));
     }
 
     #[tokio::test]
     async fn test_manager_remove() {
         let manager = OrderBookManager::new(100);
-        let symbol = Symbol::new("BTC", "USDT");
+        let symbol = Symbol::new("BTC", "USDT").id();
         
-        let _book = manager.get_or_create_book(VenueId::Binance, symbol.clone()).await;
+        let _book = manager.get_or_create_book(VenueId::Binance, symbol).await;
         assert_eq!(manager.get_book_count().await, 1);
         
-        manager.remove_book(&VenueId::Binance, &symbol).await;
+        manager.remove_book(&VenueId::Binance, symbol).await;
         assert_eq!(manager.get_book_count().await, 0);
     }
 }
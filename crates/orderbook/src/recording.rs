@@ -0,0 +1,267 @@
+//! Line-delimited JSON recordings of market data and opportunities, as read
+//! by the replayer and backtester.
+//!
+//! A recording starts with a [`RecordingHeader`] line followed by one
+//! [`RecordedEvent`] per line. Files written before the header existed are
+//! read as version 0. Each payload carries its own `schema_version` and is
+//! upgraded independently on load.
+
+use arbfinder_core::{ArbFinderError, OpportunityRecord, Result, TradeRecord, VenueId, Versioned};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::{BufRead, Write};
+
+use crate::OrderBookSnapshot;
+
+pub const RECORDING_FORMAT: &str = "arbfinder-recording";
+pub const RECORDING_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub format: String,
+    pub schema_version: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+impl RecordingHeader {
+    pub fn new() -> Self {
+        Self {
+            format: RECORDING_FORMAT.to_string(),
+            schema_version: RECORDING_SCHEMA_VERSION,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// This header as rewritten at the current schema version.
+    pub fn upgraded(&self) -> Self {
+        Self {
+            format: RECORDING_FORMAT.to_string(),
+            schema_version: RECORDING_SCHEMA_VERSION,
+            // Version 0 had no header, so there is no creation time to keep
+            created_at: if self.schema_version == 0 { Utc::now() } else { self.created_at },
+        }
+    }
+
+    fn from_line(line: &Value) -> Option<Result<Self>> {
+        if line.get("format").and_then(Value::as_str) != Some(RECORDING_FORMAT) {
+            return None;
+        }
+        Some(serde_json::from_value(line.clone()).map_err(ArbFinderError::from))
+    }
+}
+
+impl Default for RecordingHeader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum RecordedEvent {
+    OrderBook {
+        venue_id: VenueId,
+        snapshot: OrderBookSnapshot,
+    },
+    Trade(TradeRecord),
+    Opportunity(OpportunityRecord),
+}
+
+impl RecordedEvent {
+    /// Decodes one event line, upgrading its payload to the current schema.
+    pub fn from_value(mut line: Value) -> Result<Self> {
+        let fields = line
+            .as_object_mut()
+            .ok_or_else(|| ArbFinderError::InvalidData("recorded event is not an object".to_string()))?;
+        let kind = fields
+            .remove("type")
+            .and_then(|kind| kind.as_str().map(str::to_string))
+            .ok_or_else(|| ArbFinderError::InvalidData("recorded event has no type".to_string()))?;
+
+        match kind.as_str() {
+            "order_book" => {
+                let venue_id = fields
+                    .remove("venue_id")
+                    .ok_or_else(|| ArbFinderError::InvalidData("order book event has no venue_id".to_string()))?;
+                let snapshot = fields
+                    .remove("snapshot")
+                    .ok_or_else(|| ArbFinderError::InvalidData("order book event has no snapshot".to_string()))?;
+                Ok(RecordedEvent::OrderBook {
+                    venue_id: serde_json::from_value(venue_id)?,
+                    snapshot: OrderBookSnapshot::from_versioned_value(snapshot)?,
+                })
+            }
+            "trade" => Ok(RecordedEvent::Trade(TradeRecord::from_versioned_value(line)?)),
+            "opportunity" => Ok(RecordedEvent::Opportunity(OpportunityRecord::from_versioned_value(line)?)),
+            other => Err(ArbFinderError::InvalidData(format!("unknown recorded event type: {}", other))),
+        }
+    }
+}
+
+pub struct RecordingWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> RecordingWriter<W> {
+    pub fn new(writer: W) -> Result<Self> {
+        Self::with_header(writer, &RecordingHeader::new())
+    }
+
+    pub fn with_header(mut writer: W, header: &RecordingHeader) -> Result<Self> {
+        serde_json::to_writer(&mut writer, header)?;
+        writer.write_all(b"\n")?;
+        Ok(Self { writer })
+    }
+
+    pub fn write(&mut self, event: &RecordedEvent) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, event)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<()> {
+        Ok(self.writer.flush()?)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Iterates the events of a recording, current or older.
+pub struct RecordingReader<R: BufRead> {
+    lines: std::io::Lines<R>,
+    header: RecordingHeader,
+    /// First line of a header-less recording, which is already an event.
+    pending: Option<Value>,
+}
+
+impl<R: BufRead> RecordingReader<R> {
+    pub fn new(reader: R) -> Result<Self> {
+        let mut lines = reader.lines();
+        let mut pending = None;
+
+        let header = loop {
+            let Some(line) = lines.next() else {
+                break RecordingHeader::new();
+            };
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let value: Value = serde_json::from_str(&line)?;
+            match RecordingHeader::from_line(&value) {
+                Some(header) => break header?,
+                None => {
+                    pending = Some(value);
+                    break RecordingHeader {
+                        format: RECORDING_FORMAT.to_string(),
+                        schema_version: 0,
+                        created_at: DateTime::<Utc>::UNIX_EPOCH,
+                    };
+                }
+            }
+        };
+
+        if header.schema_version > RECORDING_SCHEMA_VERSION {
+            return Err(ArbFinderError::InvalidData(format!(
+                "recording has schema version {}, newest supported is {}",
+                header.schema_version, RECORDING_SCHEMA_VERSION
+            )));
+        }
+
+        Ok(Self { lines, header, pending })
+    }
+
+    pub fn header(&self) -> &RecordingHeader {
+        &self.header
+    }
+}
+
+impl<R: BufRead> Iterator for RecordingReader<R> {
+    type Item = Result<RecordedEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(value) = self.pending.take() {
+            return Some(RecordedEvent::from_value(value));
+        }
+
+        loop {
+            let line = match self.lines.next()? {
+                Ok(line) => line,
+                Err(e) => return Some(Err(e.into())),
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(serde_json::from_str(&line).map_err(ArbFinderError::from).and_then(RecordedEvent::from_value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FastOrderBook;
+    use arbfinder_core::{Side, Symbol, Trade};
+    use rust_decimal::Decimal;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_round_trip() {
+        let mut book = FastOrderBook::new(Symbol::new("BTC", "USDT"), None);
+        book.update_bid(Decimal::from(100), Decimal::ONE, None);
+        let snapshot = OrderBookSnapshot::from_fast_orderbook(&book);
+        let trade = Trade::new(Symbol::new("BTC", "USDT"), Decimal::from(100), Decimal::ONE, Side::Ask, "7".to_string());
+
+        let events = vec![
+            RecordedEvent::OrderBook { venue_id: VenueId::Binance, snapshot },
+            RecordedEvent::Trade(TradeRecord::new(VenueId::Kraken, &trade)),
+        ];
+        let mut writer = RecordingWriter::new(Vec::new()).unwrap();
+        for event in &events {
+            writer.write(event).unwrap();
+        }
+
+        let bytes = writer.into_inner();
+        let reader = RecordingReader::new(Cursor::new(bytes)).unwrap();
+        assert_eq!(reader.header().schema_version, RECORDING_SCHEMA_VERSION);
+        let read: Vec<_> = reader.collect::<Result<_>>().unwrap();
+        assert_eq!(read, events);
+    }
+
+    #[test]
+    fn test_reads_unversioned_recording() {
+        let legacy = concat!(
+            r#"{"type":"order_book","venue_id":"Binance","snapshot":{"symbol":{"base":"BTC","quote":"USDT"},"bids":[],"asks":[],"sequence":3,"timestamp":"2024-01-01T00:00:00Z"}}"#,
+            "\n",
+            r#"{"type":"trade","venue_id":"Kraken","symbol":{"base":"BTC","quote":"USD"},"trade_id":"1","price":"100","quantity":"1","side":"Bid","timestamp":"2024-01-01T00:00:00Z"}"#,
+            "\n",
+        );
+
+        let reader = RecordingReader::new(Cursor::new(legacy)).unwrap();
+        assert_eq!(reader.header().schema_version, 0);
+        let events: Vec<_> = reader.collect::<Result<_>>().unwrap();
+        assert_eq!(events.len(), 2);
+        match &events[0] {
+            RecordedEvent::OrderBook { snapshot, .. } => {
+                assert_eq!(snapshot.schema_version, OrderBookSnapshot::SCHEMA_VERSION);
+                assert_eq!(snapshot.sequence, 3);
+            }
+            other => panic!("unexpected event {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_rejects_newer_recording() {
+        let future = format!(
+            r#"{{"format":"{}","schema_version":99,"created_at":"2024-01-01T00:00:00Z"}}"#,
+            RECORDING_FORMAT
+        );
+        assert!(matches!(
+            RecordingReader::new(Cursor::new(future)),
+            Err(ArbFinderError::InvalidData(_))
+        ));
+    }
+}
@@ -0,0 +1,280 @@
+//! Price Sanity Filter
+//!
+//! Rejects book updates and tickers whose price strays too far from the
+//! median of what the other venues are showing for the same symbol, so a
+//! corrupt print (a misplaced decimal point, a zero) never reaches a
+//! strategy. The venue that sent it is flagged.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use tracing::warn;
+
+use arbfinder_core::prelude::*;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceSanityConfig {
+    /// Largest accepted distance from the reference price, as a fraction of
+    /// it (0.1 = 10%).
+    pub max_deviation: Decimal,
+    /// How long a venue's last accepted price counts toward the reference,
+    /// and how long a venue stays flagged after a rejected print.
+    pub window: Duration,
+    /// Other venues that must have a recent price before prints are judged;
+    /// until then they are accepted as they come.
+    pub min_reference_venues: usize,
+}
+
+impl Default for PriceSanityConfig {
+    fn default() -> Self {
+        Self {
+            max_deviation: Decimal::new(1, 1),
+            window: Duration::from_secs(60),
+            min_reference_venues: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceRejection {
+    pub venue: VenueId,
+    pub symbol: Symbol,
+    pub price: Decimal,
+    /// Cross-venue median the price was judged against; `None` when the
+    /// price was rejected on its own, e.g. for not being positive.
+    pub reference: Option<Decimal>,
+    pub deviation: Option<Decimal>,
+}
+
+impl fmt::Display for PriceRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.reference, self.deviation) {
+            (Some(reference), Some(deviation)) => write!(
+                f,
+                "{} price {} on {} is {:.2}% from the cross-venue median {}",
+                self.symbol,
+                self.price,
+                self.venue,
+                deviation * Decimal::from(100),
+                reference
+            ),
+            _ => write!(f, "{} price {} on {} is not positive", self.symbol, self.price, self.venue),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VenueFlag {
+    pub rejections: u64,
+    pub last_rejection: PriceRejection,
+    pub flagged_at: DateTime<Utc>,
+}
+
+pub struct PriceSanityFilter {
+    config: PriceSanityConfig,
+    /// Last accepted price and its time, per symbol per venue.
+    prices: HashMap<Symbol, HashMap<VenueId, (Decimal, DateTime<Utc>)>>,
+    flags: HashMap<VenueId, VenueFlag>,
+}
+
+impl PriceSanityFilter {
+    pub fn new(config: PriceSanityConfig) -> Self {
+        Self {
+            config,
+            prices: HashMap::new(),
+            flags: HashMap::new(),
+        }
+    }
+
+    pub fn config(&self) -> &PriceSanityConfig {
+        &self.config
+    }
+
+    /// Checks a ticker or book update from `venue_id` against the other
+    /// venues. Other market data is not checked.
+    pub fn check(&mut self, venue_id: &VenueId, data: &MarketData) -> Result<()> {
+        match data {
+            MarketData::Ticker(ticker) => self.check_price(venue_id, &ticker.symbol, ticker.price, ticker.timestamp),
+            MarketData::OrderBook(book) => self.check_book(venue_id, book),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks a book by its mid, or its only side when the other is empty.
+    pub fn check_book(&mut self, venue_id: &VenueId, book: &OrderBook) -> Result<()> {
+        let price = book
+            .mid_price()
+            .or_else(|| book.best_bid().map(|level| level.price))
+            .or_else(|| book.best_ask().map(|level| level.price));
+        match price {
+            Some(price) => self.check_price(venue_id, &book.symbol, price, book.timestamp),
+            None => Ok(()),
+        }
+    }
+
+    /// Accepted prices join the reference; rejected ones flag the venue,
+    /// which keeps the details, and are otherwise forgotten.
+    fn check_price(
+        &mut self,
+        venue_id: &VenueId,
+        symbol: &Symbol,
+        price: Decimal,
+        at: DateTime<Utc>,
+    ) -> Result<()> {
+        let reference = self.reference_price(symbol, venue_id, at);
+        let rejection = if price <= Decimal::ZERO {
+            Some((reference, None))
+        } else {
+            reference.and_then(|reference| {
+                let deviation = ((price - reference) / reference).abs();
+                (deviation > self.config.max_deviation).then_some((Some(reference), Some(deviation)))
+            })
+        };
+
+        if let Some((reference, deviation)) = rejection {
+            let rejection = PriceRejection {
+                venue: venue_id.clone(),
+                symbol: symbol.clone(),
+                price,
+                reference,
+                deviation,
+            };
+            warn!("Rejected print: {}", rejection);
+            let flag = self.flags.entry(venue_id.clone()).or_insert_with(|| VenueFlag {
+                rejections: 0,
+                last_rejection: rejection.clone(),
+                flagged_at: at,
+            });
+            flag.rejections += 1;
+            flag.last_rejection = rejection.clone();
+            flag.flagged_at = at;
+            return Err(ArbFinderError::InvalidData(rejection.to_string()));
+        }
+
+        self.prices
+            .entry(symbol.clone())
+            .or_default()
+            .insert(venue_id.clone(), (price, at));
+        Ok(())
+    }
+
+    /// Median of the other venues' prices for `symbol` accepted within the
+    /// window before `at`, if enough venues have one.
+    pub fn reference_price(&self, symbol: &Symbol, excluding: &VenueId, at: DateTime<Utc>) -> Option<Decimal> {
+        let window = chrono::Duration::from_std(self.config.window).unwrap_or(chrono::Duration::MAX);
+        let mut prices: Vec<Decimal> = self
+            .prices
+            .get(symbol)?
+            .iter()
+            .filter(|(venue, (_, seen))| *venue != excluding && at - *seen <= window)
+            .map(|(_, (price, _))| *price)
+            .collect();
+        if prices.is_empty() || prices.len() < self.config.min_reference_venues {
+            return None;
+        }
+
+        prices.sort();
+        let middle = prices.len() / 2;
+        if prices.len() % 2 == 1 {
+            Some(prices[middle])
+        } else {
+            Some((prices[middle - 1] + prices[middle]) / Decimal::from(2))
+        }
+    }
+
+    /// Whether `venue_id` sent a rejected print within the last window.
+    pub fn is_flagged(&self, venue_id: &VenueId) -> bool {
+        self.flagged_venues().iter().any(|(venue, _)| venue == venue_id)
+    }
+
+    pub fn flagged_venues(&self) -> Vec<(VenueId, VenueFlag)> {
+        let window = chrono::Duration::from_std(self.config.window).unwrap_or(chrono::Duration::MAX);
+        let now = Utc::now();
+        self.flags
+            .iter()
+            .filter(|(_, flag)| now - flag.flagged_at <= window)
+            .map(|(venue, flag)| (venue.clone(), flag.clone()))
+            .collect()
+    }
+
+    pub fn clear_flag(&mut self, venue_id: &VenueId) {
+        self.flags.remove(venue_id);
+    }
+}
+
+impl Default for PriceSanityFilter {
+    fn default() -> Self {
+        Self::new(PriceSanityConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(bid: i64, ask: i64) -> MarketData {
+        let mut book = OrderBook::new(Symbol::new("BTC", "USDT"));
+        book.update_bid(Decimal::from(bid), Decimal::ONE);
+        book.update_ask(Decimal::from(ask), Decimal::ONE);
+        MarketData::OrderBook(book)
+    }
+
+    fn seeded() -> PriceSanityFilter {
+        let mut filter = PriceSanityFilter::default();
+        filter.check(&VenueId::Binance, &book(50000, 50010)).unwrap();
+        filter.check(&VenueId::Coinbase, &book(50020, 50030)).unwrap();
+        filter.check(&VenueId::Kraken, &book(49990, 50000)).unwrap();
+        filter
+    }
+
+    #[test]
+    fn test_misplaced_decimal_is_rejected_and_flagged() {
+        let mut filter = seeded();
+
+        assert!(filter.check(&VenueId::Gemini, &book(5000, 5001)).is_err());
+        assert!(filter.is_flagged(&VenueId::Gemini));
+        let rejection = filter.flagged_venues()[0].1.last_rejection.clone();
+        assert_eq!(rejection.reference, Some(Decimal::from(50005)));
+        assert!(rejection.deviation.unwrap() > Decimal::new(8, 1));
+        assert!(!filter.is_flagged(&VenueId::Binance));
+
+        // A sane print is still accepted, and the flag stays until cleared
+        filter.check(&VenueId::Gemini, &book(50100, 50110)).unwrap();
+        assert_eq!(filter.flagged_venues()[0].1.rejections, 1);
+        filter.clear_flag(&VenueId::Gemini);
+        assert!(!filter.is_flagged(&VenueId::Gemini));
+    }
+
+    #[test]
+    fn test_prints_pass_until_a_reference_exists() {
+        let mut filter = PriceSanityFilter::default();
+        filter.check(&VenueId::Binance, &book(50000, 50010)).unwrap();
+
+        let zero = MarketData::Ticker(Ticker {
+            symbol: Symbol::new("BTC", "USDT"),
+            price: Decimal::ZERO,
+            volume_24h: Decimal::ZERO,
+            change_24h: Decimal::ZERO,
+            timestamp: Utc::now(),
+        });
+        let error = filter.check(&VenueId::Kraken, &zero).unwrap_err();
+        assert!(error.to_string().contains("not positive"));
+        assert_eq!(filter.flagged_venues()[0].1.last_rejection.reference, None);
+
+        // Only one other venue so far
+        filter.check(&VenueId::Coinbase, &book(500, 501)).unwrap();
+    }
+
+    #[test]
+    fn test_stale_prices_leave_the_reference() {
+        let mut filter = seeded();
+        let later = Utc::now() + chrono::Duration::seconds(120);
+        assert_eq!(filter.reference_price(&Symbol::new("BTC", "USDT"), &VenueId::Gemini, later), None);
+
+        let MarketData::OrderBook(mut stale) = book(5000, 5001) else { unreachable!() };
+        stale.timestamp = later;
+        assert!(filter.check(&VenueId::Gemini, &MarketData::OrderBook(stale)).is_ok());
+    }
+}
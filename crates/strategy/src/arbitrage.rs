@@ -7,7 +7,12 @@ use rust_decimal::Decimal;
 use rust_decimal::prelude::ToPrimitive;
 use tracing::debug;
 
+use arbfinder_core::config::StrategyConfig;
 use arbfinder_core::prelude::*;
+use arbfinder_orderbook::AggregatedOrderBook;
+
+use crate::fair_value::FairValueModel;
+use crate::synthetic::{ConversionLeg, SyntheticBook};
 
 #[derive(Debug, Clone)]
 pub struct ArbitrageOpportunity {
@@ -20,9 +25,29 @@ pub struct ArbitrageOpportunity {
     pub max_volume: Decimal,
     pub estimated_profit: Decimal,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Trades replacing the buy or sell leg where that venue's book is
+    /// synthetic, in order; empty when both venues list the symbol.
+    pub conversion_legs: Vec<ConversionLeg>,
+    pub buy_fee: LegFee,
+    pub sell_fee: LegFee,
+}
+
+/// The fee a leg was priced with.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LegFee {
+    /// As decimal (0.001 = 0.1%)
+    pub rate: Decimal,
+    /// Asset the fee is paid from instead of the traded ones, at the
+    /// venue's discounted rate; `None` for a full fee taken out of what
+    /// the leg trades.
+    pub discount_asset: Option<String>,
 }
 
 impl ArbitrageOpportunity {
+    pub fn is_synthetic(&self) -> bool {
+        !self.conversion_legs.is_empty()
+    }
+
     pub fn calculate_net_profit(&self, trading_fees: &TradingFeePair) -> Decimal {
         let gross_profit = self.sell_price - self.buy_price;
         let buy_fee = self.buy_price * trading_fees.buy_exchange_fee;
@@ -32,6 +57,24 @@ impl ArbitrageOpportunity {
     }
 }
 
+impl From<&ArbitrageOpportunity> for OpportunityRecord {
+    fn from(opportunity: &ArbitrageOpportunity) -> Self {
+        Self {
+            schema_version: Self::SCHEMA_VERSION,
+            id: Uuid::new_v4().to_string(),
+            symbol: opportunity.symbol.clone(),
+            buy_venue: opportunity.buy_venue.clone(),
+            sell_venue: opportunity.sell_venue.clone(),
+            buy_price: opportunity.buy_price,
+            sell_price: opportunity.sell_price,
+            profit_percentage: opportunity.profit_percentage,
+            max_quantity: opportunity.max_volume,
+            estimated_profit: opportunity.estimated_profit,
+            detected_at: opportunity.timestamp,
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct TradingFeePair {
     pub buy_exchange_fee: Decimal, // as decimal (0.001 = 0.1%)
@@ -41,7 +84,12 @@ pub struct TradingFeePair {
 pub struct CrossExchangeArbitrageDetector {
     min_profit_threshold: Decimal, // Minimum profit percentage
     min_volume_threshold: Decimal, // Minimum volume in quote currency
+    min_profit_quote: Decimal, // Minimum net profit in quote currency
     trading_fees: HashMap<VenueId, Decimal>, // Default trading fees per exchange
+    fixed_costs: HashMap<VenueId, Decimal>, // Per-trade costs in quote currency, e.g. gas
+    fair_value: Option<FairValueModel>, // Lead/lag between venues, to skip gaps that will close
+    fee_discounts: HashMap<VenueId, FeeDiscount>, // Cheaper rates for fees paid in e.g. BNB
+    discount_balances: HashMap<VenueId, Decimal>, // Value of the discount asset held, in quote currency
 }
 
 impl CrossExchangeArbitrageDetector {
@@ -64,10 +112,22 @@ impl CrossExchangeArbitrageDetector {
             // Store threshold directly in bps (e.g., 10 = 10 bps)
             min_profit_threshold: Decimal::from(min_profit_bps),
             min_volume_threshold: min_volume,
+            min_profit_quote: Decimal::ZERO,
             trading_fees,
+            fixed_costs: HashMap::new(),
+            fair_value: None,
+            fee_discounts: HashMap::new(),
+            discount_balances: HashMap::new(),
         }
     }
 
+    /// Detector with the spread, volume and profit thresholds of `config`
+    pub fn from_config(config: &StrategyConfig) -> Self {
+        let mut detector = Self::new(config.min_spread_bps, config.min_volume_threshold);
+        detector.set_min_profit_quote(config.min_profit_quote);
+        detector
+    }
+
     /// Detect arbitrage opportunities across multiple orderbooks
     pub fn detect_opportunities(
         &self,
@@ -111,6 +171,62 @@ impl CrossExchangeArbitrageDetector {
             }
         }
 
+        // Gaps a lagging venue closes before an order reaches it are not
+        // there to take
+        if let Some(model) = &self.fair_value {
+            let threshold = self.min_profit_threshold / Decimal::from(10000);
+            opportunities.retain(|opportunity| {
+                let adjusted = model.adjusted_profit(opportunity, orderbooks);
+                if adjusted < threshold {
+                    debug!(
+                        "Skipping lag-driven {} {:?} -> {:?}: {:.2} bps once caught up",
+                        symbol.to_pair(), opportunity.buy_venue, opportunity.sell_venue,
+                        (adjusted * Decimal::from(10000)).to_f64().unwrap_or(0.0)
+                    );
+                }
+                adjusted >= threshold
+            });
+        }
+
+        opportunities
+    }
+
+    /// Detect arbitrage opportunities across the venues of an aggregated
+    /// book. Books waiting for a resync are not compared.
+    pub fn detect_aggregated(&self, book: &AggregatedOrderBook) -> Vec<ArbitrageOpportunity> {
+        let books = book.core_books();
+        let refs: HashMap<VenueId, &OrderBook> = books.iter().map(|(venue, book)| (venue.clone(), book)).collect();
+        self.detect_opportunities(&book.symbol, &refs)
+    }
+
+    /// Like [`Self::detect_opportunities`], also comparing `synthetic` books
+    /// of `symbol` on venues without a direct book for it. Opportunities
+    /// buying or selling on a synthetic book carry its conversion legs.
+    pub fn detect_with_synthetic(
+        &self,
+        symbol: &Symbol,
+        orderbooks: &HashMap<VenueId, &OrderBook>,
+        synthetic: &[SyntheticBook],
+    ) -> Vec<ArbitrageOpportunity> {
+        let synthetic: HashMap<&VenueId, &SyntheticBook> = synthetic
+            .iter()
+            .filter(|book| book.book.symbol == *symbol && !orderbooks.contains_key(&book.venue))
+            .map(|book| (&book.venue, book))
+            .collect();
+        let mut books = orderbooks.clone();
+        for (venue, book) in &synthetic {
+            books.insert((*venue).clone(), &book.book);
+        }
+
+        let mut opportunities = self.detect_opportunities(symbol, &books);
+        for opportunity in &mut opportunities {
+            if let Some(book) = synthetic.get(&opportunity.buy_venue) {
+                opportunity.conversion_legs.extend(book.legs(OrderSide::Buy));
+            }
+            if let Some(book) = synthetic.get(&opportunity.sell_venue) {
+                opportunity.conversion_legs.extend(book.legs(OrderSide::Sell));
+            }
+        }
         opportunities
     }
 
@@ -122,9 +238,19 @@ impl CrossExchangeArbitrageDetector {
         buy_book: &OrderBook,
         sell_book: &OrderBook,
     ) -> Option<ArbitrageOpportunity> {
-        let best_ask = buy_book.best_ask()?;
-        let best_bid = sell_book.best_bid()?;
-        
+        self.check_quotes(symbol, buy_venue, sell_venue, buy_book.best_ask()?, sell_book.best_bid()?)
+    }
+
+    /// Checks buying at `best_ask` on `buy_venue` and selling at `best_bid`
+    /// on `sell_venue`, which is all there is to compare of two books.
+    pub(crate) fn check_quotes(
+        &self,
+        symbol: &Symbol,
+        buy_venue: VenueId,
+        sell_venue: VenueId,
+        best_ask: &OrderBookLevel,
+        best_bid: &OrderBookLevel,
+    ) -> Option<ArbitrageOpportunity> {
         let buy_price = best_ask.price;
         let sell_price = best_bid.price;
         
@@ -137,19 +263,36 @@ impl CrossExchangeArbitrageDetector {
         // Formula: ((sell - buy) / buy) * 10000 = profit in bps
         let gross_profit_bps = ((sell_price - buy_price) / buy_price) * Decimal::from(10000);
         
-        // Calculate fees (fees are stored as decimals, e.g., 0.001 = 0.1%)
-        let buy_fee = self.trading_fees.get(&buy_venue)
-            .copied()
-            .unwrap_or(Decimal::new(1, 3)); // Default 0.1%
-        let sell_fee = self.trading_fees.get(&sell_venue)
-            .copied()
-            .unwrap_or(Decimal::new(1, 3)); // Default 0.1%
+        // Calculate maximum volume (limited by available liquidity)
+        let max_volume = best_ask.quantity.min(best_bid.quantity);
+        let volume_value = max_volume * buy_price;
+        
+        // Check if volume meets threshold (min_volume_threshold is in quote currency)
+        if volume_value < self.min_volume_threshold {
+            return None;
+        }
+        
+        // Each leg pays its venue's fee, discounted where the discount asset
+        // held covers it (fees are stored as decimals, e.g., 0.001 = 0.1%)
+        let buy_leg_fee = self.leg_fee(&buy_venue, volume_value);
+        let sell_leg_fee = self.leg_fee(&sell_venue, max_volume * sell_price);
+        let (buy_fee, sell_fee) = (buy_leg_fee.rate, sell_leg_fee.rate);
         
         // Convert fees to bps: 0.001 * 10000 = 10 bps
         let total_fee_bps = (buy_fee + sell_fee) * Decimal::from(10000);
         
+        // Fixed costs are spread over the volume that can be traded
+        let fixed_cost = self.fixed_cost(&buy_venue) + self.fixed_cost(&sell_venue);
+        let fixed_cost_bps = if fixed_cost.is_zero() {
+            Decimal::ZERO
+        } else if volume_value.is_zero() {
+            return None;
+        } else {
+            fixed_cost / volume_value * Decimal::from(10000)
+        };
+        
         // Net profit in bps
-        let net_profit_bps = gross_profit_bps - total_fee_bps;
+        let net_profit_bps = gross_profit_bps - total_fee_bps - fixed_cost_bps;
         
         // min_profit_threshold is already in bps (e.g., 10 = 10 bps = 0.1%)
         // So we compare directly
@@ -157,26 +300,22 @@ impl CrossExchangeArbitrageDetector {
             return None;
         }
         
-        // Calculate maximum volume (limited by available liquidity)
-        let max_volume = best_ask.quantity.min(best_bid.quantity);
-        let volume_value = max_volume * buy_price;
-        
-        // Check if volume meets threshold (min_volume_threshold is in quote currency)
-        if volume_value < self.min_volume_threshold {
-            return None;
-        }
-        
         // Calculate estimated profit in quote currency
         let gross_profit_per_unit = sell_price - buy_price;
         let buy_fee_per_unit = buy_price * buy_fee;
         let sell_fee_per_unit = sell_price * sell_fee;
         let net_profit_per_unit = gross_profit_per_unit - buy_fee_per_unit - sell_fee_per_unit;
-        let estimated_profit = net_profit_per_unit * max_volume;
+        let estimated_profit = net_profit_per_unit * max_volume - fixed_cost;
+        
+        // A wide spread on a thin book can still be too little to bother with
+        if estimated_profit < self.min_profit_quote {
+            return None;
+        }
         
         debug!(
-            "Found arbitrage: Buy {} on {:?} @ {}, Sell on {:?} @ {}, Profit: {:.2} bps, Volume: {}",
+            "Found arbitrage: Buy {} on {:?} @ {}, Sell on {:?} @ {}, Profit: {:.2} bps ({} {}), Volume: {}",
             symbol.to_pair(), buy_venue, buy_price, sell_venue, sell_price,
-            net_profit_bps.to_f64().unwrap_or(0.0), max_volume
+            net_profit_bps.to_f64().unwrap_or(0.0), estimated_profit.round_dp(2), symbol.quote(), max_volume
         );
         
         Some(ArbitrageOpportunity {
@@ -189,12 +328,70 @@ impl CrossExchangeArbitrageDetector {
             max_volume,
             estimated_profit,
             timestamp: chrono::Utc::now(),
+            conversion_legs: Vec::new(),
+            buy_fee: buy_leg_fee,
+            sell_fee: sell_leg_fee,
         })
     }
 
+    /// Smallest net profit, in quote currency, an opportunity must make on
+    /// its full volume in addition to clearing the bps threshold.
+    pub fn set_min_profit_quote(&mut self, min_profit: Decimal) {
+        self.min_profit_quote = min_profit;
+    }
+
     pub fn set_trading_fee(&mut self, venue: VenueId, fee: Decimal) {
         self.trading_fees.insert(venue, fee);
     }
+
+    /// Cost of each trade on `venue` in quote currency, independent of its
+    /// size, such as gas for an on-chain swap.
+    pub fn set_fixed_cost(&mut self, venue: VenueId, cost: Decimal) {
+        self.fixed_costs.insert(venue, cost);
+    }
+
+    /// Drops opportunities that `model` expects to vanish before execution
+    /// because one venue is only lagging another.
+    pub fn set_fair_value_model(&mut self, model: FairValueModel) {
+        self.fair_value = Some(model);
+    }
+
+    /// Taker rate on `venue` when its fees are paid in `discount.asset`.
+    /// The discount is priced in while that asset's balance, last set with
+    /// [`Self::set_discount_balance`], covers a leg's fee.
+    pub fn set_fee_discount(&mut self, venue: VenueId, discount: FeeDiscount) {
+        self.fee_discounts.insert(venue, discount);
+    }
+
+    /// Value, in quote currency, of the fee discount asset held on `venue`.
+    /// Until it is set the balance is taken to be kept topped up.
+    pub fn set_discount_balance(&mut self, venue: VenueId, value: Decimal) {
+        self.discount_balances.insert(venue, value);
+    }
+
+    /// Fee for trading `notional` of quote currency on `venue`.
+    fn leg_fee(&self, venue: &VenueId, notional: Decimal) -> LegFee {
+        if let Some(discount) = self.fee_discounts.get(venue) {
+            let covered = self
+                .discount_balances
+                .get(venue)
+                .is_none_or(|balance| *balance >= notional * discount.taker_fee);
+            if covered {
+                return LegFee {
+                    rate: discount.taker_fee,
+                    discount_asset: Some(discount.asset.clone()),
+                };
+            }
+        }
+        LegFee {
+            rate: self.trading_fees.get(venue).copied().unwrap_or(Decimal::new(1, 3)), // Default 0.1%
+            discount_asset: None,
+        }
+    }
+
+    fn fixed_cost(&self, venue: &VenueId) -> Decimal {
+        self.fixed_costs.get(venue).copied().unwrap_or(Decimal::ZERO)
+    }
 }
 
 #[cfg(test)]
@@ -239,6 +436,33 @@ mod tests {
         println!("Found arbitrage: {:?}", opp);
     }
 
+    #[test]
+    fn test_detect_aggregated_skips_stale_books() {
+        let detector = CrossExchangeArbitrageDetector::new(10, dec!(100));
+        let symbol = Symbol::new("BTC", "USDT");
+        let fast_book = |bid: Decimal, ask: Decimal| {
+            let mut book = arbfinder_orderbook::FastOrderBook::new(symbol.clone(), None);
+            book.update_bid(bid, dec!(1.0), None);
+            book.update_ask(ask, dec!(1.0), None);
+            book
+        };
+        let mut aggregated = AggregatedOrderBook::new(symbol.clone());
+        aggregated.add_venue(VenueId::Binance, fast_book(dec!(99), dec!(100)));
+        aggregated.add_venue(VenueId::Kraken, fast_book(dec!(102), dec!(103)));
+
+        let opportunities = detector.detect_aggregated(&aggregated);
+        assert_eq!(opportunities.len(), 1);
+        assert_eq!(opportunities[0].buy_venue, VenueId::Binance);
+        assert_eq!(opportunities[0].sell_venue, VenueId::Kraken);
+
+        // Kraken missed an update, so its bid can't be trusted
+        aggregated
+            .get_venue_book_mut(&VenueId::Kraken)
+            .unwrap()
+            .apply_update_with_sequence(1, Vec::new());
+        assert!(detector.detect_aggregated(&aggregated).is_empty());
+    }
+
     #[test]
     fn test_no_arbitrage_when_prices_equal() {
         let detector = CrossExchangeArbitrageDetector::new(10, dec!(100));
@@ -327,4 +551,131 @@ mod tests {
             assert!(profit_pct > 0.0, "Should still be profitable");
         }
     }
+
+    #[test]
+    fn test_fixed_cost_needs_volume_to_pay_off() {
+        let mut detector = CrossExchangeArbitrageDetector::new(10, dec!(0));
+        detector.set_trading_fee(VenueId::Binance, dec!(0));
+        detector.set_trading_fee(VenueId::Uniswap, dec!(0));
+        // $5 of gas per swap
+        detector.set_fixed_cost(VenueId::Uniswap, dec!(5));
+
+        let symbol = Symbol::new("ETH", "USDT");
+        // 1% spread
+        let cex = create_test_orderbook(dec!(100), dec!(100), dec!(1.0));
+        let dex = create_test_orderbook(dec!(101), dec!(101), dec!(1.0));
+        let orderbooks = HashMap::from([(VenueId::Binance, &cex), (VenueId::Uniswap, &dex)]);
+
+        // $1 of spread on one unit does not cover $5 of gas
+        assert!(detector.detect_opportunities(&symbol, &orderbooks).is_empty());
+
+        let cex = create_test_orderbook(dec!(100), dec!(100), dec!(10.0));
+        let dex = create_test_orderbook(dec!(101), dec!(101), dec!(10.0));
+        let orderbooks = HashMap::from([(VenueId::Binance, &cex), (VenueId::Uniswap, &dex)]);
+        let opportunities = detector.detect_opportunities(&symbol, &orderbooks);
+        assert_eq!(opportunities.len(), 1);
+        assert_eq!(opportunities[0].estimated_profit, dec!(5));
+        assert_eq!(opportunities[0].profit_percentage, dec!(0.005));
+    }
+
+    #[test]
+    fn test_synthetic_book_opportunity() {
+        let mut detector = CrossExchangeArbitrageDetector::new(10, dec!(0));
+        detector.set_trading_fee(VenueId::Binance, dec!(0));
+        detector.set_trading_fee(VenueId::Kraken, dec!(0));
+
+        let symbol = Symbol::new("BTC", "USDT");
+        let direct = create_test_orderbook(dec!(100), dec!(101), dec!(1.0));
+        let mut btc_eur = OrderBook::new(Symbol::new("BTC", "EUR"));
+        btc_eur.update_bid(dec!(100), dec!(1.0));
+        let mut eur_usdt = OrderBook::new(Symbol::new("EUR", "USDT"));
+        eur_usdt.update_bid(dec!(1.05), dec!(1000));
+        let synthetic = SyntheticBook::build(VenueId::Kraken, &symbol, &btc_eur, &eur_usdt, dec!(0)).unwrap();
+
+        let orderbooks = HashMap::from([(VenueId::Binance, &direct)]);
+        assert!(detector.detect_opportunities(&symbol, &orderbooks).is_empty());
+        let opportunities = detector.detect_with_synthetic(&symbol, &orderbooks, &[synthetic]);
+        assert_eq!(opportunities.len(), 1);
+        let opp = &opportunities[0];
+        assert_eq!((opp.buy_venue.clone(), opp.sell_venue.clone()), (VenueId::Binance, VenueId::Kraken));
+        assert_eq!(opp.sell_price, dec!(105));
+        assert!(opp.is_synthetic());
+        assert_eq!(opp.conversion_legs.len(), 2);
+        assert_eq!(opp.conversion_legs[0].symbol, Symbol::new("BTC", "EUR"));
+    }
+
+    #[test]
+    fn test_fair_value_skips_lagging_venue() {
+        let mut detector = CrossExchangeArbitrageDetector::new(10, dec!(0));
+        detector.set_trading_fee(VenueId::Binance, dec!(0));
+        detector.set_trading_fee(VenueId::Coinbase, dec!(0));
+
+        let symbol = Symbol::new("BTC", "USDT");
+        // Binance has jumped; Coinbase has not caught up yet
+        let binance = create_test_orderbook(dec!(102), dec!(102.1), dec!(1.0));
+        let coinbase = create_test_orderbook(dec!(100), dec!(100.1), dec!(1.0));
+        let orderbooks = HashMap::from([(VenueId::Binance, &binance), (VenueId::Coinbase, &coinbase)]);
+        assert_eq!(detector.detect_opportunities(&symbol, &orderbooks).len(), 1);
+
+        let mut model = FairValueModel::new();
+        model.add_lead_lag(&crate::fair_value::LeadLag {
+            symbol: symbol.clone(),
+            leader: VenueId::Binance,
+            follower: VenueId::Coinbase,
+            lag: std::time::Duration::from_millis(80),
+            correlation: 0.8,
+        });
+        model.set_latency(VenueId::Coinbase, std::time::Duration::from_millis(100));
+        detector.set_fair_value_model(model);
+        assert!(detector.detect_opportunities(&symbol, &orderbooks).is_empty());
+    }
+
+    #[test]
+    fn test_min_profit_quote_rejects_small_notional() {
+        let mut detector = CrossExchangeArbitrageDetector::new(10, dec!(0));
+        detector.set_trading_fee(VenueId::Binance, dec!(0));
+        detector.set_trading_fee(VenueId::Kraken, dec!(0));
+        detector.set_min_profit_quote(dec!(10));
+
+        let symbol = Symbol::new("ETH", "USDT");
+        // 200 bps on a single unit is only $2
+        let thin_a = create_test_orderbook(dec!(100), dec!(100), dec!(1.0));
+        let thin_b = create_test_orderbook(dec!(102), dec!(102), dec!(1.0));
+        let orderbooks = HashMap::from([(VenueId::Binance, &thin_a), (VenueId::Kraken, &thin_b)]);
+        assert!(detector.detect_opportunities(&symbol, &orderbooks).is_empty());
+
+        let deep_a = create_test_orderbook(dec!(100), dec!(100), dec!(5.0));
+        let deep_b = create_test_orderbook(dec!(102), dec!(102), dec!(5.0));
+        let orderbooks = HashMap::from([(VenueId::Binance, &deep_a), (VenueId::Kraken, &deep_b)]);
+        let opportunities = detector.detect_opportunities(&symbol, &orderbooks);
+        assert_eq!(opportunities.len(), 1);
+        assert_eq!(opportunities[0].estimated_profit, dec!(10));
+        assert_eq!(opportunities[0].profit_percentage, dec!(0.02));
+    }
+
+    #[test]
+    fn test_fee_discount_needs_balance() {
+        let mut detector = CrossExchangeArbitrageDetector::new(10, dec!(0));
+        detector.set_trading_fee(VenueId::Binance, dec!(0.001));
+        detector.set_trading_fee(VenueId::Kraken, dec!(0));
+        detector.set_fee_discount(VenueId::Binance, FeeDiscount::new("BNB", dec!(0.00075), dec!(0.00075)));
+
+        let symbol = Symbol::new("ETH", "USDT");
+        let binance = create_test_orderbook(dec!(100), dec!(100), dec!(10.0));
+        let kraken = create_test_orderbook(dec!(101), dec!(101), dec!(10.0));
+        let orderbooks = HashMap::from([(VenueId::Binance, &binance), (VenueId::Kraken, &kraken)]);
+
+        // Buying 1000 USDT on Binance costs 0.75 in BNB
+        detector.set_discount_balance(VenueId::Binance, dec!(1));
+        let opportunities = detector.detect_opportunities(&symbol, &orderbooks);
+        assert_eq!(opportunities[0].estimated_profit, dec!(9.25));
+        assert_eq!(opportunities[0].buy_fee.discount_asset.as_deref(), Some("BNB"));
+        assert_eq!(opportunities[0].sell_fee, LegFee::default());
+
+        // Too little BNB left, so the full fee comes out of the ETH bought
+        detector.set_discount_balance(VenueId::Binance, dec!(0.5));
+        let opportunities = detector.detect_opportunities(&symbol, &orderbooks);
+        assert_eq!(opportunities[0].estimated_profit, dec!(9));
+        assert_eq!(opportunities[0].buy_fee, LegFee { rate: dec!(0.001), discount_asset: None });
+    }
 }
@@ -0,0 +1,436 @@
+//! Latency-compensated fair value
+//!
+//! Venues do not move together: a move on the busiest venue shows up on the
+//! others tens of milliseconds later. A gap that only exists because one
+//! venue has not caught up yet closes before an order can reach it, so it
+//! is not an opportunity. Lead/lag between venues is estimated from
+//! recorded books, and a venue's fair value is its leader's price whenever
+//! the venue catches up faster than an order gets there.
+
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+
+use arbfinder_core::prelude::*;
+use arbfinder_orderbook::RecordedEvent;
+
+use crate::arbitrage::ArbitrageOpportunity;
+
+/// Returns needed on both venues before a lag is estimated.
+const MIN_SAMPLES: usize = 30;
+
+/// `follower`'s prices for `symbol` move `lag` after `leader`'s.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LeadLag {
+    pub symbol: Symbol,
+    pub leader: VenueId,
+    pub follower: VenueId,
+    pub lag: Duration,
+    /// Correlation of the leader's mid-price returns with the follower's
+    /// `lag` later.
+    pub correlation: f64,
+}
+
+impl fmt::Display for LeadLag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} leads {} by {}ms (correlation {:.2})",
+            self.symbol,
+            self.leader,
+            self.follower,
+            self.lag.as_millis(),
+            self.correlation
+        )
+    }
+}
+
+/// Estimates lead/lag between venues by cross-correlating their mid-price
+/// returns, sampled every `bucket`, at lags of up to `max_lag`.
+#[derive(Debug, Clone)]
+pub struct LeadLagEstimator {
+    bucket: Duration,
+    max_lag: Duration,
+    /// Mid prices as (milliseconds since the epoch, mid), in time order.
+    mids: HashMap<Symbol, HashMap<VenueId, Vec<(i64, f64)>>>,
+}
+
+impl LeadLagEstimator {
+    pub fn new(bucket: Duration, max_lag: Duration) -> Self {
+        Self {
+            bucket,
+            max_lag,
+            mids: HashMap::new(),
+        }
+    }
+
+    /// Estimates from every event, failing on the first that cannot be
+    /// read.
+    pub fn run(
+        bucket: Duration,
+        max_lag: Duration,
+        events: impl IntoIterator<Item = Result<RecordedEvent>>,
+    ) -> Result<Self> {
+        let mut estimator = Self::new(bucket, max_lag);
+        for event in events {
+            estimator.on_event(&event?);
+        }
+        Ok(estimator)
+    }
+
+    pub fn on_event(&mut self, event: &RecordedEvent) {
+        if let RecordedEvent::OrderBook { venue_id, snapshot } = event {
+            let best_bid = snapshot.bids.iter().map(|level| level.price).max();
+            let best_ask = snapshot.asks.iter().map(|level| level.price).min();
+            if let (Some(bid), Some(ask)) = (best_bid, best_ask) {
+                self.observe(venue_id.clone(), snapshot.symbol.clone(), (bid + ask) / Decimal::TWO, snapshot.timestamp);
+            }
+        }
+    }
+
+    /// Records `venue`'s mid price for `symbol` as of `at`. Observations
+    /// older than the venue's last are ignored.
+    pub fn observe(&mut self, venue: VenueId, symbol: Symbol, mid: Decimal, at: DateTime<Utc>) {
+        let Some(mid) = mid.to_f64().filter(|mid| *mid > 0.0) else {
+            return;
+        };
+        let series = self.mids.entry(symbol).or_default().entry(venue).or_default();
+        let at = at.timestamp_millis();
+        if series.last().is_some_and(|(last, _)| *last > at) {
+            return;
+        }
+        series.push((at, mid));
+    }
+
+    /// Symbols with observations from more than one venue.
+    pub fn symbols(&self) -> Vec<Symbol> {
+        self.mids
+            .iter()
+            .filter(|(_, venues)| venues.len() > 1)
+            .map(|(symbol, _)| symbol.clone())
+            .collect()
+    }
+
+    /// Log returns of `series` over each bucket from `start` to `end`,
+    /// carrying the last mid forward through buckets without one.
+    fn returns(&self, series: &[(i64, f64)], start: i64, end: i64) -> Vec<f64> {
+        let bucket = self.bucket.as_millis().max(1) as i64;
+        let mut returns = Vec::new();
+        let mut next = 0;
+        let mut last = None;
+        let mut t = start;
+        while t <= end {
+            while next < series.len() && series[next].0 <= t {
+                last = Some(series[next].1);
+                next += 1;
+            }
+            returns.push(last);
+            t += bucket;
+        }
+        returns
+            .windows(2)
+            .map(|pair| match (pair[0], pair[1]) {
+                (Some(previous), Some(current)) => (current / previous).ln(),
+                _ => 0.0,
+            })
+            .collect()
+    }
+
+    /// Lead/lag between each pair of venues quoting `symbol`, where one
+    /// leads the other by at least a bucket. Pairs moving together, or
+    /// without enough overlapping data, are left out.
+    pub fn estimate(&self, symbol: &Symbol) -> Vec<LeadLag> {
+        let Some(venues) = self.mids.get(symbol) else {
+            return Vec::new();
+        };
+        let mut venues: Vec<(&VenueId, &Vec<(i64, f64)>)> = venues.iter().collect();
+        venues.sort_by_key(|(venue, _)| venue.to_string());
+
+        let bucket = self.bucket.as_millis().max(1) as i64;
+        let max_shift = (self.max_lag.as_millis() as i64 / bucket) as usize;
+        let mut estimates = Vec::new();
+        for (i, (venue_a, series_a)) in venues.iter().enumerate() {
+            for (venue_b, series_b) in &venues[i + 1..] {
+                let (Some(first_a), Some(first_b)) = (series_a.first(), series_b.first()) else {
+                    continue;
+                };
+                let start = first_a.0.max(first_b.0);
+                let end = series_a.last().unwrap().0.min(series_b.last().unwrap().0);
+                if end <= start {
+                    continue;
+                }
+                let returns_a = self.returns(series_a, start, end);
+                let returns_b = self.returns(series_b, start, end);
+
+                // Positive shifts have A leading, negative B
+                let mut best: Option<(i64, f64)> = None;
+                for shift in -(max_shift as i64)..=max_shift as i64 {
+                    let (leading, following) = if shift >= 0 {
+                        (&returns_a, &returns_b)
+                    } else {
+                        (&returns_b, &returns_a)
+                    };
+                    let offset = shift.unsigned_abs() as usize;
+                    if following.len() < offset + MIN_SAMPLES {
+                        continue;
+                    }
+                    let Some(correlation) = correlation(&leading[..leading.len() - offset], &following[offset..]) else {
+                        continue;
+                    };
+                    match best {
+                        Some((_, best_correlation)) if best_correlation >= correlation => {}
+                        _ => best = Some((shift, correlation)),
+                    }
+                }
+
+                let Some((shift, correlation)) = best else {
+                    continue;
+                };
+                if shift == 0 || correlation <= 0.0 {
+                    continue;
+                }
+                let (leader, follower) = if shift > 0 { (venue_a, venue_b) } else { (venue_b, venue_a) };
+                estimates.push(LeadLag {
+                    symbol: symbol.clone(),
+                    leader: (*leader).clone(),
+                    follower: (*follower).clone(),
+                    lag: Duration::from_millis(shift.unsigned_abs() * bucket as u64),
+                    correlation,
+                });
+            }
+        }
+        estimates
+    }
+}
+
+/// Pearson correlation of two equally long series, `None` if either is
+/// flat.
+fn correlation(a: &[f64], b: &[f64]) -> Option<f64> {
+    let n = a.len().min(b.len()) as f64;
+    if n == 0.0 {
+        return None;
+    }
+    let mean_a = a.iter().sum::<f64>() / n;
+    let mean_b = b.iter().sum::<f64>() / n;
+    let (mut covariance, mut variance_a, mut variance_b) = (0.0, 0.0, 0.0);
+    for (x, y) in a.iter().zip(b) {
+        covariance += (x - mean_a) * (y - mean_b);
+        variance_a += (x - mean_a).powi(2);
+        variance_b += (y - mean_b).powi(2);
+    }
+    if variance_a == 0.0 || variance_b == 0.0 {
+        return None;
+    }
+    Some(covariance / (variance_a * variance_b).sqrt())
+}
+
+/// Fair values of each venue's books, given who leads whom and how long
+/// data takes to arrive from each venue.
+#[derive(Debug, Clone, Default)]
+pub struct FairValueModel {
+    /// Each follower's leader and lag, per symbol.
+    leaders: HashMap<Symbol, HashMap<VenueId, (VenueId, Duration, f64)>>,
+    /// One-way latency to each venue.
+    latencies: HashMap<VenueId, Duration>,
+}
+
+impl FairValueModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Follows `lead_lag`, unless its follower already has a leader for the
+    /// symbol with a stronger correlation.
+    pub fn add_lead_lag(&mut self, lead_lag: &LeadLag) {
+        let followers = self.leaders.entry(lead_lag.symbol.clone()).or_default();
+        let stronger = followers
+            .get(&lead_lag.follower)
+            .is_some_and(|(_, _, correlation)| *correlation >= lead_lag.correlation);
+        if !stronger {
+            followers.insert(
+                lead_lag.follower.clone(),
+                (lead_lag.leader.clone(), lead_lag.lag, lead_lag.correlation),
+            );
+        }
+    }
+
+    pub fn set_latency(&mut self, venue: VenueId, latency: Duration) {
+        self.latencies.insert(venue, latency);
+    }
+
+    /// Folds one message from `venue`, stamped `sent_at` by the venue and
+    /// received at `received_at`, into its one-way latency.
+    pub fn observe_latency(&mut self, venue: VenueId, sent_at: DateTime<Utc>, received_at: DateTime<Utc>) {
+        let Ok(sample) = (received_at - sent_at).to_std() else {
+            return;
+        };
+        let latency = match self.latencies.get(&venue) {
+            Some(latency) => latency.mul_f64(0.9) + sample.mul_f64(0.1),
+            None => sample,
+        };
+        self.latencies.insert(venue, latency);
+    }
+
+    pub fn latency(&self, venue: &VenueId) -> Option<Duration> {
+        self.latencies.get(venue).copied()
+    }
+
+    /// `venue`'s leader for `symbol` and how far behind it the venue is.
+    pub fn leader(&self, symbol: &Symbol, venue: &VenueId) -> Option<(&VenueId, Duration)> {
+        self.leaders
+            .get(symbol)?
+            .get(venue)
+            .map(|(leader, lag, _)| (leader, *lag))
+    }
+
+    /// The mid `venue`'s book for `symbol` is heading to: its leader's mid
+    /// if the venue catches up with it before an order sent now arrives,
+    /// otherwise its own.
+    pub fn fair_value(
+        &self,
+        symbol: &Symbol,
+        venue: &VenueId,
+        orderbooks: &HashMap<VenueId, &OrderBook>,
+    ) -> Option<Decimal> {
+        let own = orderbooks.get(venue)?.mid_price()?;
+        let Some((leader, lag)) = self.leader(symbol, venue) else {
+            return Some(own);
+        };
+        let latency = self.latency(venue).unwrap_or_default();
+        if lag > latency {
+            return Some(own);
+        }
+        Some(orderbooks.get(leader).and_then(|book| book.mid_price()).unwrap_or(own))
+    }
+
+    /// Net profit of `opportunity` once both venues' books have moved to
+    /// their fair values, as a fraction like its `profit_percentage`.
+    pub fn adjusted_profit(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        orderbooks: &HashMap<VenueId, &OrderBook>,
+    ) -> Decimal {
+        let shift = |venue: &VenueId| -> Decimal {
+            let mid = orderbooks.get(venue).and_then(|book| book.mid_price());
+            match (self.fair_value(&opportunity.symbol, venue, orderbooks), mid) {
+                (Some(fair), Some(mid)) => fair - mid,
+                _ => Decimal::ZERO,
+            }
+        };
+        if opportunity.buy_price.is_zero() {
+            return opportunity.profit_percentage;
+        }
+        let buy_shift = shift(&opportunity.buy_venue);
+        let sell_shift = shift(&opportunity.sell_venue);
+        opportunity.profit_percentage + (sell_shift - buy_shift) / opportunity.buy_price
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arbitrage::LegFee;
+    use arbfinder_orderbook::{OrderBookSnapshot, PriceLevel};
+    use rust_decimal_macros::dec;
+
+    fn book(symbol: &Symbol, mid: Decimal) -> OrderBook {
+        let mut book = OrderBook::new(symbol.clone());
+        book.update_bid(mid - dec!(1), dec!(1));
+        book.update_ask(mid + dec!(1), dec!(1));
+        book
+    }
+
+    fn book_event(venue_id: VenueId, mid: Decimal, at: DateTime<Utc>) -> RecordedEvent {
+        RecordedEvent::OrderBook {
+            venue_id,
+            snapshot: OrderBookSnapshot {
+                schema_version: OrderBookSnapshot::SCHEMA_VERSION,
+                symbol: Symbol::new("BTC", "USDT"),
+                bids: vec![PriceLevel::new(mid - dec!(1), dec!(1))],
+                asks: vec![PriceLevel::new(mid + dec!(1), dec!(1))],
+                sequence: 0,
+                timestamp: at,
+            },
+        }
+    }
+
+    #[test]
+    fn test_lead_lag_from_recording() {
+        let start = Utc::now();
+        let millis = chrono::Duration::milliseconds;
+        // A random walk on Binance that Coinbase repeats 80ms later
+        let mut mid = dec!(50000);
+        let mut seed: u64 = 7;
+        let mut events = Vec::new();
+        for step in 0..200 {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            mid += Decimal::from((seed >> 33) % 21) - dec!(10);
+            let at = start + millis(step * 20);
+            events.push(Ok(book_event(VenueId::Binance, mid, at)));
+            events.push(Ok(book_event(VenueId::Coinbase, mid, at + millis(80))));
+        }
+        events.sort_by_key(|event: &Result<RecordedEvent>| match event {
+            Ok(RecordedEvent::OrderBook { snapshot, .. }) => snapshot.timestamp,
+            _ => start,
+        });
+
+        let estimator =
+            LeadLagEstimator::run(Duration::from_millis(10), Duration::from_millis(200), events).unwrap();
+        let symbol = Symbol::new("BTC", "USDT");
+        assert_eq!(estimator.symbols(), vec![symbol.clone()]);
+        let estimates = estimator.estimate(&symbol);
+        assert_eq!(estimates.len(), 1);
+        assert_eq!(estimates[0].leader, VenueId::Binance);
+        assert_eq!(estimates[0].follower, VenueId::Coinbase);
+        assert_eq!(estimates[0].lag, Duration::from_millis(80));
+        assert!(estimates[0].correlation > 0.9);
+    }
+
+    #[test]
+    fn test_lag_driven_opportunity() {
+        let symbol = Symbol::new("BTC", "USDT");
+        let mut model = FairValueModel::new();
+        model.add_lead_lag(&LeadLag {
+            symbol: symbol.clone(),
+            leader: VenueId::Binance,
+            follower: VenueId::Coinbase,
+            lag: Duration::from_millis(80),
+            correlation: 0.8,
+        });
+        let now = Utc::now();
+        model.observe_latency(VenueId::Coinbase, now - chrono::Duration::milliseconds(100), now);
+        assert_eq!(model.latency(&VenueId::Coinbase), Some(Duration::from_millis(100)));
+
+        // Binance has moved up 100; Coinbase will follow before an order
+        // gets there
+        let binance = book(&symbol, dec!(50100));
+        let coinbase = book(&symbol, dec!(50000));
+        let books: HashMap<VenueId, &OrderBook> =
+            [(VenueId::Binance, &binance), (VenueId::Coinbase, &coinbase)].into_iter().collect();
+        assert_eq!(model.fair_value(&symbol, &VenueId::Coinbase, &books), Some(dec!(50100)));
+        assert_eq!(model.fair_value(&symbol, &VenueId::Binance, &books), Some(dec!(50100)));
+
+        let opportunity = ArbitrageOpportunity {
+            symbol: symbol.clone(),
+            buy_venue: VenueId::Coinbase,
+            sell_venue: VenueId::Binance,
+            buy_price: dec!(50001),
+            sell_price: dec!(50099),
+            profit_percentage: dec!(0.001),
+            max_volume: dec!(1),
+            estimated_profit: dec!(50),
+            timestamp: now,
+            conversion_legs: Vec::new(),
+            buy_fee: LegFee::default(),
+            sell_fee: LegFee::default(),
+        };
+        assert!(model.adjusted_profit(&opportunity, &books) < Decimal::ZERO);
+
+        // An order beating the catch-up still gets the stale price
+        model.set_latency(VenueId::Coinbase, Duration::from_millis(20));
+        assert_eq!(model.adjusted_profit(&opportunity, &books), dec!(0.001));
+    }
+}
@@ -0,0 +1,192 @@
+//! KRW premium ("kimchi premium") measurement.
+//!
+//! Korean venues quote in KRW and capital controls make the gap to offshore
+//! USD prices persistent, so it is tracked as a premium rather than treated
+//! as a plain cross-exchange arbitrage.
+
+use arbfinder_core::prelude::*;
+use rust_decimal::Decimal;
+use tracing::debug;
+
+pub const KRW: &str = "KRW";
+
+/// Where the USD/KRW conversion rate comes from.
+#[derive(Debug, Clone, PartialEq)]
+pub enum KrwRateSource {
+    /// A rate supplied from outside, e.g. a bank or FX feed.
+    Fixed(Decimal),
+    /// The mid price of a USDT/KRW (or USDC/KRW) book on the Korean venue.
+    /// The stablecoin market carries its own premium, so this measures the
+    /// gap against on-venue dollars rather than the official FX rate.
+    Stablecoin(Symbol),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct KrwPremium {
+    pub base: String,
+    pub krw_venue: VenueId,
+    pub reference_venue: VenueId,
+    /// KRW per USD used for the conversion.
+    pub usd_krw_rate: Decimal,
+    /// KRW mid converted to USD.
+    pub krw_price_usd: Decimal,
+    pub reference_price: Decimal,
+    /// Mid vs mid, as a fraction (0.03 = 3%).
+    pub premium: Decimal,
+    /// Selling into the KRW bid after buying the reference ask; what a trade
+    /// in that direction would capture before fees and transfer costs.
+    pub executable_premium: Option<Decimal>,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+}
+
+impl KrwPremium {
+    pub fn premium_bps(&self) -> Decimal {
+        self.premium * Decimal::from(10000)
+    }
+}
+
+pub struct KrwPremiumCalculator {
+    rate_source: KrwRateSource,
+    usd_krw_rate: Option<Decimal>,
+}
+
+impl KrwPremiumCalculator {
+    pub fn new(rate_source: KrwRateSource) -> Self {
+        let usd_krw_rate = match &rate_source {
+            KrwRateSource::Fixed(rate) => Some(*rate),
+            KrwRateSource::Stablecoin(_) => None,
+        };
+        Self { rate_source, usd_krw_rate }
+    }
+
+    pub fn rate_source(&self) -> &KrwRateSource {
+        &self.rate_source
+    }
+
+    pub fn usd_krw_rate(&self) -> Option<Decimal> {
+        self.usd_krw_rate
+    }
+
+    pub fn set_usd_krw_rate(&mut self, rate: Decimal) {
+        if rate > Decimal::ZERO {
+            self.usd_krw_rate = Some(rate);
+        }
+    }
+
+    /// Feeds a book into the rate; books other than the configured
+    /// stablecoin market are ignored. Returns the rate after the update.
+    pub fn on_orderbook(&mut self, book: &OrderBook) -> Option<Decimal> {
+        if let KrwRateSource::Stablecoin(symbol) = &self.rate_source {
+            if &book.symbol == symbol {
+                if let Some(mid) = book.mid_price() {
+                    self.set_usd_krw_rate(mid);
+                }
+            }
+        }
+        self.usd_krw_rate
+    }
+
+    /// Premium of `krw_book` (BASE/KRW) over `reference_book` (BASE/USD or a
+    /// USD stablecoin). Returns `None` until a rate is known, when the books
+    /// are for different assets, or when either side is empty.
+    pub fn calculate(
+        &self,
+        krw_venue: VenueId,
+        krw_book: &OrderBook,
+        reference_venue: VenueId,
+        reference_book: &OrderBook,
+    ) -> Option<KrwPremium> {
+        let rate = self.usd_krw_rate?;
+        if !krw_book.symbol.quote().eq_ignore_ascii_case(KRW)
+            || !krw_book.symbol.base().eq_ignore_ascii_case(reference_book.symbol.base())
+        {
+            return None;
+        }
+
+        let krw_price_usd = krw_book.mid_price()? / rate;
+        let reference_price = reference_book.mid_price()?;
+        if reference_price.is_zero() {
+            return None;
+        }
+        let premium = krw_price_usd / reference_price - Decimal::ONE;
+
+        let executable_premium = match (krw_book.best_bid(), reference_book.best_ask()) {
+            (Some(bid), Some(ask)) if !ask.price.is_zero() => {
+                Some(bid.price / rate / ask.price - Decimal::ONE)
+            }
+            _ => None,
+        };
+
+        debug!(
+            "{} KRW premium {} vs {}: {} (rate {})",
+            krw_book.symbol.base(), krw_venue, reference_venue, premium, rate
+        );
+
+        Some(KrwPremium {
+            base: krw_book.symbol.base().to_string(),
+            krw_venue,
+            reference_venue,
+            usd_krw_rate: rate,
+            krw_price_usd,
+            reference_price,
+            premium,
+            executable_premium,
+            timestamp: chrono::Utc::now(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn book(base: &str, quote: &str, bid: Decimal, ask: Decimal) -> OrderBook {
+        let mut book = OrderBook::new(Symbol::new(base, quote));
+        book.update_bid(bid, dec!(1));
+        book.update_ask(ask, dec!(1));
+        book
+    }
+
+    #[test]
+    fn test_premium_with_fixed_rate() {
+        let calc = KrwPremiumCalculator::new(KrwRateSource::Fixed(dec!(1300)));
+        let krw = book("BTC", "KRW", dec!(135_200_000), dec!(135_200_000));
+        let usd = book("BTC", "USDT", dec!(100_000), dec!(100_000));
+
+        let premium = calc.calculate(VenueId::Upbit, &krw, VenueId::Binance, &usd).unwrap();
+        assert_eq!(premium.krw_price_usd, dec!(104_000));
+        assert_eq!(premium.premium, dec!(0.04));
+        assert_eq!(premium.premium_bps(), dec!(400));
+        assert_eq!(premium.executable_premium, Some(dec!(0.04)));
+    }
+
+    #[test]
+    fn test_rate_from_stablecoin_book() {
+        let usdt_krw = Symbol::new("USDT", "KRW");
+        let mut calc = KrwPremiumCalculator::new(KrwRateSource::Stablecoin(usdt_krw));
+        let krw = book("ETH", "KRW", dec!(4_200_000), dec!(4_210_000));
+        let usd = book("ETH", "USDT", dec!(3000), dec!(3001));
+
+        assert!(calc.calculate(VenueId::Upbit, &krw, VenueId::Binance, &usd).is_none());
+
+        // Unrelated books do not move the rate
+        assert_eq!(calc.on_orderbook(&krw), None);
+        assert_eq!(calc.on_orderbook(&book("USDT", "KRW", dec!(1360), dec!(1380))), Some(dec!(1370)));
+
+        let premium = calc.calculate(VenueId::Upbit, &krw, VenueId::Binance, &usd).unwrap();
+        assert!(premium.premium > Decimal::ZERO);
+        assert!(premium.executable_premium.unwrap() < premium.premium);
+    }
+
+    #[test]
+    fn test_mismatched_books_are_rejected() {
+        let calc = KrwPremiumCalculator::new(KrwRateSource::Fixed(dec!(1300)));
+        let krw = book("BTC", "KRW", dec!(130_000_000), dec!(130_000_000));
+        let eth = book("ETH", "USDT", dec!(3000), dec!(3000));
+        let not_krw = book("BTC", "USDT", dec!(100_000), dec!(100_000));
+
+        assert!(calc.calculate(VenueId::Upbit, &krw, VenueId::Binance, &eth).is_none());
+        assert!(calc.calculate(VenueId::Binance, &not_krw, VenueId::Binance, &not_krw).is_none());
+    }
+}
@@ -5,6 +5,12 @@ use arbfinder_orderbook::FastOrderBook;
 
 pub mod simple;
 pub mod arbitrage;
+pub mod kimchi;
+pub mod profile;
+pub mod replay;
+pub mod synthetic;
+pub mod fair_value;
+pub mod top_of_book;
 
 #[async_trait]
 pub trait Strategy: Send + Sync {
@@ -25,4 +31,10 @@ pub mod prelude {
     pub use super::{Strategy};
     pub use super::simple::*;
     pub use super::arbitrage::*;
+    pub use super::kimchi::*;
+    pub use super::profile::*;
+    pub use super::replay::*;
+    pub use super::synthetic::*;
+    pub use super::fair_value::*;
+    pub use super::top_of_book::*;
 }
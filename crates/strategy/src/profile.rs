@@ -0,0 +1,156 @@
+//! What-if Venue Profiles
+//!
+//! A profile overrides per-venue fees and latencies for a replay only, so a
+//! recording can answer questions like "would this be profitable at VIP3
+//! fees" or "from a Tokyo VPS" without touching the live configuration.
+//! Latencies are drawn from a seeded generator, so the same profile over
+//! the same recording always gives the same result.
+
+use std::collections::HashMap;
+use std::time::Duration;
+use rust_decimal::Decimal;
+
+use arbfinder_core::prelude::*;
+
+use crate::arbitrage::CrossExchangeArbitrageDetector;
+
+/// One-way latency from the bot to a venue.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LatencyDistribution {
+    Fixed(Duration),
+    /// Uniform between `min` and `max`.
+    Uniform { min: Duration, max: Duration },
+    /// Normal, clamped at zero.
+    Normal { mean: Duration, std_dev: Duration },
+}
+
+impl LatencyDistribution {
+    fn sample(&self, rng: &mut SplitMix64) -> Duration {
+        match self {
+            LatencyDistribution::Fixed(latency) => *latency,
+            LatencyDistribution::Uniform { min, max } => {
+                *min + max.saturating_sub(*min).mul_f64(rng.next_f64())
+            }
+            LatencyDistribution::Normal { mean, std_dev } => {
+                // Box-Muller
+                let (u1, u2) = (1.0 - rng.next_f64(), rng.next_f64());
+                let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                Duration::from_secs_f64((mean.as_secs_f64() + z * std_dev.as_secs_f64()).max(0.0))
+            }
+        }
+    }
+}
+
+/// Overrides for one venue; anything left `None` keeps the detector's own
+/// setting.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VenueProfile {
+    pub taker_fee: Option<Decimal>,
+    pub fixed_cost: Option<Decimal>,
+    pub latency: Option<LatencyDistribution>,
+}
+
+/// Small deterministic generator; replays need reproducible draws, not
+/// cryptographic ones.
+#[derive(Debug, Clone)]
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in [0, 1).
+    fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SimulationProfile {
+    venues: HashMap<VenueId, VenueProfile>,
+    rng: SplitMix64,
+}
+
+impl SimulationProfile {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            venues: HashMap::new(),
+            rng: SplitMix64(seed),
+        }
+    }
+
+    pub fn set_venue(&mut self, venue: VenueId, profile: VenueProfile) {
+        self.venues.insert(venue, profile);
+    }
+
+    pub fn venue(&self, venue: &VenueId) -> Option<&VenueProfile> {
+        self.venues.get(venue)
+    }
+
+    /// Whether any venue has a latency to model.
+    pub fn models_latency(&self) -> bool {
+        self.venues.values().any(|profile| profile.latency.is_some())
+    }
+
+    /// Sets the profile's fees and fixed costs on `detector`.
+    pub fn apply(&self, detector: &mut CrossExchangeArbitrageDetector) {
+        for (venue, profile) in &self.venues {
+            if let Some(fee) = profile.taker_fee {
+                detector.set_trading_fee(venue.clone(), fee);
+            }
+            if let Some(cost) = profile.fixed_cost {
+                detector.set_fixed_cost(venue.clone(), cost);
+            }
+        }
+    }
+
+    /// Draws the latency of the next order to `venue`, zero for a venue
+    /// without one.
+    pub fn sample_latency(&mut self, venue: &VenueId) -> Duration {
+        match self.venues.get(venue).and_then(|profile| profile.latency.as_ref()) {
+            Some(latency) => latency.sample(&mut self.rng),
+            None => Duration::ZERO,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_latency() {
+        let mut profile = SimulationProfile::new(7);
+        profile.set_venue(VenueId::Binance, VenueProfile {
+            latency: Some(LatencyDistribution::Uniform {
+                min: Duration::from_millis(2),
+                max: Duration::from_millis(6),
+            }),
+            ..VenueProfile::default()
+        });
+        profile.set_venue(VenueId::Coinbase, VenueProfile {
+            latency: Some(LatencyDistribution::Normal {
+                mean: Duration::from_millis(160),
+                std_dev: Duration::from_millis(20),
+            }),
+            ..VenueProfile::default()
+        });
+        assert!(profile.models_latency());
+
+        let mut replay = profile.clone();
+        let draws: Vec<_> = (0..100).map(|_| profile.sample_latency(&VenueId::Binance)).collect();
+        assert!(draws.iter().all(|d| *d >= Duration::from_millis(2) && *d <= Duration::from_millis(6)));
+        assert_eq!(draws, (0..100).map(|_| replay.sample_latency(&VenueId::Binance)).collect::<Vec<_>>());
+
+        let mean = (0..1000)
+            .map(|_| profile.sample_latency(&VenueId::Coinbase).as_secs_f64())
+            .sum::<f64>() / 1000.0;
+        assert!((mean - 0.160).abs() < 0.005, "mean {}", mean);
+        assert_eq!(profile.sample_latency(&VenueId::Kraken), Duration::ZERO);
+    }
+}
@@ -0,0 +1,469 @@
+//! Opportunity Replay
+//!
+//! Re-runs cross-exchange detection over the books in a recording and
+//! compares the result with the opportunities recorded when it was made, so
+//! a change to detection settings or logic can be checked against real
+//! market history before it goes live. With a what-if profile, fees are
+//! overridden and each replayed opportunity's legs are sent with simulated
+//! latency, counting those still there when both orders land.
+
+use std::collections::HashMap;
+use std::fmt;
+use rust_decimal::Decimal;
+
+use arbfinder_core::prelude::*;
+use arbfinder_orderbook::{OrderBookSnapshot, RecordedEvent};
+use chrono::{DateTime, Utc};
+
+use crate::arbitrage::CrossExchangeArbitrageDetector;
+use crate::profile::SimulationProfile;
+
+/// Opportunities on one symbol and direction, as originally detected and
+/// as detected on replay.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RouteDiff {
+    pub symbol: Symbol,
+    pub buy_venue: VenueId,
+    pub sell_venue: VenueId,
+    pub original: usize,
+    pub replayed: usize,
+    /// Best net profit fraction seen, if any were detected.
+    pub original_best: Option<Decimal>,
+    pub replayed_best: Option<Decimal>,
+    /// Replayed opportunities whose legs would both have filled at the
+    /// detected prices after latency, if latency was modeled.
+    pub survived: Option<usize>,
+}
+
+impl RouteDiff {
+    fn new(record: &OpportunityRecord) -> Self {
+        Self {
+            symbol: record.symbol.clone(),
+            buy_venue: record.buy_venue.clone(),
+            sell_venue: record.sell_venue.clone(),
+            original: 0,
+            replayed: 0,
+            original_best: None,
+            replayed_best: None,
+            survived: None,
+        }
+    }
+}
+
+impl fmt::Display for RouteDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let bps = |best: Option<Decimal>| {
+            best.map(|best| format!("{:.1} bps", best * Decimal::from(10000)))
+                .unwrap_or_else(|| "-".to_string())
+        };
+        write!(
+            f,
+            "{} {} -> {}: {} -> {} ({} -> {})",
+            self.symbol,
+            self.buy_venue,
+            self.sell_venue,
+            self.original,
+            self.replayed,
+            bps(self.original_best),
+            bps(self.replayed_best)
+        )?;
+        if let Some(survived) = self.survived {
+            write!(f, ", {} survive latency", survived)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct OpportunityDiff {
+    pub books: usize,
+    /// One entry per symbol and direction found either way, ordered by
+    /// symbol and venues.
+    pub routes: Vec<RouteDiff>,
+}
+
+impl OpportunityDiff {
+    /// Routes only the original detection found.
+    pub fn dropped(&self) -> impl Iterator<Item = &RouteDiff> {
+        self.routes.iter().filter(|route| route.replayed == 0)
+    }
+
+    /// Routes only the replay found.
+    pub fn added(&self) -> impl Iterator<Item = &RouteDiff> {
+        self.routes.iter().filter(|route| route.original == 0)
+    }
+
+    pub fn original_count(&self) -> usize {
+        self.routes.iter().map(|route| route.original).sum()
+    }
+
+    pub fn replayed_count(&self) -> usize {
+        self.routes.iter().map(|route| route.replayed).sum()
+    }
+
+    /// Replayed opportunities that survived latency, if it was modeled.
+    pub fn survived_count(&self) -> Option<usize> {
+        self.routes.first()?.survived?;
+        self.routes.iter().map(|route| route.survived).sum()
+    }
+}
+
+impl fmt::Display for OpportunityDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Replayed {} books: {} opportunities originally, {} now",
+            self.books,
+            self.original_count(),
+            self.replayed_count()
+        )?;
+        if let Some(survived) = self.survived_count() {
+            write!(f, ", {} surviving latency", survived)?;
+        }
+        for (title, routes) in [
+            ("Dropped", self.dropped().collect::<Vec<_>>()),
+            ("Added", self.added().collect()),
+            (
+                "Changed",
+                self.routes
+                    .iter()
+                    .filter(|route| route.original > 0 && route.replayed > 0)
+                    .filter(|route| route.original != route.replayed || route.original_best != route.replayed_best)
+                    .collect(),
+            ),
+        ] {
+            if !routes.is_empty() {
+                write!(f, "\n  {}:", title)?;
+                for route in routes {
+                    write!(f, "\n    {}", route)?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An order leg of a replayed opportunity, on its way to the venue.
+#[derive(Debug, Clone)]
+struct InFlightLeg {
+    /// Index of the opportunity in `replayed`.
+    opportunity: usize,
+    venue: VenueId,
+    symbol: Symbol,
+    side: OrderSide,
+    price: Decimal,
+    lands_at: DateTime<Utc>,
+}
+
+impl InFlightLeg {
+    /// Whether an IOC limit order at the detected price fills against
+    /// `book`.
+    fn fills(&self, book: Option<&OrderBook>) -> bool {
+        let Some(book) = book else {
+            return false;
+        };
+        match self.side {
+            OrderSide::Buy => book.best_ask().map(|level| level.price <= self.price).unwrap_or(false),
+            OrderSide::Sell => book.best_bid().map(|level| level.price >= self.price).unwrap_or(false),
+        }
+    }
+}
+
+/// Feeds recorded events through a detector, keeping the latest book per
+/// venue and symbol and detecting on every book update.
+pub struct OpportunityReplay {
+    detector: CrossExchangeArbitrageDetector,
+    books: HashMap<Symbol, HashMap<VenueId, OrderBook>>,
+    book_count: usize,
+    original: Vec<OpportunityRecord>,
+    replayed: Vec<OpportunityRecord>,
+    profile: Option<SimulationProfile>,
+    in_flight: Vec<InFlightLeg>,
+    /// Per replayed opportunity, false once a leg has missed.
+    survived: Vec<bool>,
+}
+
+impl OpportunityReplay {
+    pub fn new(detector: CrossExchangeArbitrageDetector) -> Self {
+        Self {
+            detector,
+            books: HashMap::new(),
+            book_count: 0,
+            original: Vec::new(),
+            replayed: Vec::new(),
+            profile: None,
+            in_flight: Vec::new(),
+            survived: Vec::new(),
+        }
+    }
+
+    /// Replays with `profile`'s fees, and its latencies if it has any.
+    pub fn with_profile(mut self, profile: SimulationProfile) -> Self {
+        profile.apply(&mut self.detector);
+        self.profile = Some(profile);
+        self
+    }
+
+    /// Replays every event, failing on the first that cannot be read.
+    pub fn run(
+        detector: CrossExchangeArbitrageDetector,
+        events: impl IntoIterator<Item = Result<RecordedEvent>>,
+    ) -> Result<OpportunityDiff> {
+        Self::new(detector).replay(events)
+    }
+
+    /// As `run`, for a replay already set up.
+    pub fn replay(mut self, events: impl IntoIterator<Item = Result<RecordedEvent>>) -> Result<OpportunityDiff> {
+        for event in events {
+            self.on_event(&event?);
+        }
+        Ok(self.diff())
+    }
+
+    pub fn on_event(&mut self, event: &RecordedEvent) {
+        match event {
+            RecordedEvent::OrderBook { venue_id, snapshot } => self.on_book(venue_id, snapshot),
+            RecordedEvent::Opportunity(record) => self.original.push(record.clone()),
+            RecordedEvent::Trade(_) => {}
+        }
+    }
+
+    fn models_latency(&self) -> bool {
+        self.profile.as_ref().map(|profile| profile.models_latency()).unwrap_or(false)
+    }
+
+    /// Settles legs that landed on `venue` before `now` against its book
+    /// as it stood then, the one about to be replaced.
+    fn land_legs(&mut self, venue_id: &VenueId, symbol: &Symbol, now: DateTime<Utc>) {
+        let book = self.books.get(symbol).and_then(|venues| venues.get(venue_id));
+        let mut i = 0;
+        while i < self.in_flight.len() {
+            let leg = &self.in_flight[i];
+            if leg.venue == *venue_id && leg.symbol == *symbol && leg.lands_at < now {
+                if !leg.fills(book) {
+                    self.survived[leg.opportunity] = false;
+                }
+                self.in_flight.swap_remove(i);
+            } else {
+                i += 1;
+            }
+        }
+    }
+
+    fn on_book(&mut self, venue_id: &VenueId, snapshot: &OrderBookSnapshot) {
+        self.book_count += 1;
+        self.land_legs(venue_id, &snapshot.symbol, snapshot.timestamp);
+
+        let mut book = OrderBook::new(snapshot.symbol.clone());
+        for level in &snapshot.bids {
+            book.update_bid(level.price, level.quantity);
+        }
+        for level in &snapshot.asks {
+            book.update_ask(level.price, level.quantity);
+        }
+        book.timestamp = snapshot.timestamp;
+
+        let venues = self.books.entry(snapshot.symbol.clone()).or_default();
+        venues.insert(venue_id.clone(), book);
+        if venues.len() < 2 {
+            return;
+        }
+
+        let books: HashMap<VenueId, &OrderBook> = venues.iter().map(|(venue, book)| (venue.clone(), book)).collect();
+        let opportunities = self.detector.detect_opportunities(&snapshot.symbol, &books);
+        let models_latency = self.models_latency();
+        for opportunity in opportunities {
+            let mut record = OpportunityRecord::from(&opportunity);
+            // Detected as of the book, not the replay
+            record.detected_at = snapshot.timestamp;
+
+            if let (true, Some(profile)) = (models_latency, self.profile.as_mut()) {
+                let legs = [
+                    (record.buy_venue.clone(), OrderSide::Buy, record.buy_price),
+                    (record.sell_venue.clone(), OrderSide::Sell, record.sell_price),
+                ];
+                for (venue, side, price) in legs {
+                    let latency = profile.sample_latency(&venue);
+                    self.in_flight.push(InFlightLeg {
+                        opportunity: self.replayed.len(),
+                        lands_at: record.detected_at + chrono::Duration::from_std(latency).unwrap_or_default(),
+                        venue,
+                        symbol: record.symbol.clone(),
+                        side,
+                        price,
+                    });
+                }
+            }
+            self.survived.push(true);
+            self.replayed.push(record);
+        }
+    }
+
+    pub fn original(&self) -> &[OpportunityRecord] {
+        &self.original
+    }
+
+    pub fn replayed(&self) -> &[OpportunityRecord] {
+        &self.replayed
+    }
+
+    /// Compares original and replayed opportunities route by route.
+    pub fn diff(&self) -> OpportunityDiff {
+        let mut routes: HashMap<(Symbol, VenueId, VenueId), RouteDiff> = HashMap::new();
+        let key = |record: &OpportunityRecord| {
+            (record.symbol.clone(), record.buy_venue.clone(), record.sell_venue.clone())
+        };
+
+        for record in &self.original {
+            let route = routes.entry(key(record)).or_insert_with(|| RouteDiff::new(record));
+            route.original += 1;
+            route.original_best = route.original_best.max(Some(record.profit_percentage));
+        }
+        // Legs still in flight at the end land on the last books
+        let mut survived = self.survived.clone();
+        for leg in &self.in_flight {
+            let book = self.books.get(&leg.symbol).and_then(|venues| venues.get(&leg.venue));
+            if !leg.fills(book) {
+                survived[leg.opportunity] = false;
+            }
+        }
+        let models_latency = self.models_latency();
+        for (record, survived) in self.replayed.iter().zip(survived) {
+            let route = routes.entry(key(record)).or_insert_with(|| RouteDiff::new(record));
+            route.replayed += 1;
+            route.replayed_best = route.replayed_best.max(Some(record.profit_percentage));
+            if models_latency {
+                *route.survived.get_or_insert(0) += usize::from(survived);
+            }
+        }
+        if models_latency {
+            // Routes only originally detected survived none of zero
+            for route in routes.values_mut() {
+                route.survived.get_or_insert(0);
+            }
+        }
+
+        let mut routes: Vec<RouteDiff> = routes.into_values().collect();
+        routes.sort_by_key(|route| {
+            (route.symbol.to_string(), route.buy_venue.to_string(), route.sell_venue.to_string())
+        });
+        OpportunityDiff {
+            books: self.book_count,
+            routes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arbfinder_orderbook::PriceLevel;
+    use rust_decimal_macros::dec;
+
+    fn book_event(venue_id: VenueId, bid: Decimal, ask: Decimal) -> RecordedEvent {
+        book_at(venue_id, bid, ask, chrono::Utc::now())
+    }
+
+    fn book_at(venue_id: VenueId, bid: Decimal, ask: Decimal, timestamp: DateTime<Utc>) -> RecordedEvent {
+        RecordedEvent::OrderBook {
+            venue_id,
+            snapshot: OrderBookSnapshot {
+                schema_version: OrderBookSnapshot::SCHEMA_VERSION,
+                symbol: Symbol::new("BTC", "USDT"),
+                bids: vec![PriceLevel::new(bid, dec!(1))],
+                asks: vec![PriceLevel::new(ask, dec!(1))],
+                sequence: 1,
+                timestamp,
+            },
+        }
+    }
+
+    fn recorded(buy_venue: VenueId, sell_venue: VenueId) -> RecordedEvent {
+        RecordedEvent::Opportunity(OpportunityRecord {
+            schema_version: OpportunityRecord::SCHEMA_VERSION,
+            id: "1".to_string(),
+            symbol: Symbol::new("BTC", "USDT"),
+            buy_venue,
+            sell_venue,
+            buy_price: dec!(100),
+            sell_price: dec!(101),
+            profit_percentage: dec!(0.008),
+            max_quantity: dec!(1),
+            estimated_profit: dec!(0.8),
+            detected_at: chrono::Utc::now(),
+        })
+    }
+
+    #[test]
+    fn test_stricter_detector_drops_route() {
+        let events = vec![
+            book_event(VenueId::Binance, dec!(99), dec!(100)),
+            book_event(VenueId::Kraken, dec!(101), dec!(102)),
+            recorded(VenueId::Binance, VenueId::Kraken),
+            recorded(VenueId::Kraken, VenueId::Binance),
+        ];
+
+        // 100 bps gross less 36 bps of fees clears 10 bps
+        let mut lenient = CrossExchangeArbitrageDetector::new(10, dec!(0));
+        lenient.set_trading_fee(VenueId::Kraken, dec!(0.0026));
+        let diff = OpportunityReplay::run(lenient, events.clone().into_iter().map(Ok)).unwrap();
+        assert_eq!(diff.books, 2);
+        assert_eq!(diff.original_count(), 2);
+        assert_eq!(diff.replayed_count(), 1);
+        let dropped: Vec<_> = diff.dropped().collect();
+        assert_eq!(dropped.len(), 1);
+        assert_eq!(dropped[0].buy_venue, VenueId::Kraken);
+        assert_eq!(diff.added().count(), 0);
+
+        let strict = CrossExchangeArbitrageDetector::new(100, dec!(0));
+        let diff = OpportunityReplay::run(strict, events.into_iter().map(Ok)).unwrap();
+        assert_eq!(diff.replayed_count(), 0);
+        assert_eq!(diff.dropped().count(), 2);
+        assert!(diff.to_string().contains("Dropped"));
+    }
+
+    #[test]
+    fn test_profile_fees_and_latency() {
+        use crate::profile::{LatencyDistribution, VenueProfile};
+        use std::time::Duration;
+
+        let start = chrono::Utc::now();
+        let ms = |ms: i64| start + chrono::Duration::milliseconds(ms);
+        let profile = || {
+            let mut profile = SimulationProfile::new(0);
+            profile.set_venue(VenueId::Kraken, VenueProfile {
+                taker_fee: Some(dec!(0.001)),
+                latency: Some(LatencyDistribution::Fixed(Duration::from_millis(50))),
+                ..VenueProfile::default()
+            });
+            profile
+        };
+        let replay = |events: Vec<RecordedEvent>| {
+            let detector = CrossExchangeArbitrageDetector::new(10, dec!(0));
+            OpportunityReplay::new(detector)
+                .with_profile(profile())
+                .replay(events.into_iter().map(Ok))
+                .unwrap()
+        };
+
+        // Kraken's bid is gone before the sell leg lands 50ms later
+        let diff = replay(vec![
+            book_at(VenueId::Binance, dec!(99), dec!(100), ms(0)),
+            book_at(VenueId::Kraken, dec!(101), dec!(102), ms(0)),
+            book_at(VenueId::Kraken, dec!(100), dec!(101), ms(10)),
+            book_at(VenueId::Kraken, dec!(101), dec!(102), ms(100)),
+        ]);
+        assert_eq!(diff.routes.len(), 1);
+        assert_eq!(diff.routes[0].replayed, 2);
+        assert_eq!(diff.routes[0].survived, Some(1));
+        // 100 bps gross less 10 bps on Binance and Kraken's 10 bps VIP fee
+        assert_eq!(diff.routes[0].replayed_best, Some(dec!(0.008)));
+
+        let diff = replay(vec![
+            book_at(VenueId::Binance, dec!(99), dec!(100), ms(0)),
+            book_at(VenueId::Kraken, dec!(101), dec!(102), ms(0)),
+        ]);
+        assert_eq!(diff.survived_count(), Some(1));
+        assert!(diff.to_string().contains("1 surviving latency"));
+    }
+}
@@ -125,6 +125,12 @@ impl Strategy for TriangularArbitrage {
     }
 
     async fn on_tick(&mut self, symbol: &Symbol, _ticker: &Ticker, orderbook: Arc<FastOrderBook>) {
+        // A book that missed updates is left out until it is resynced
+        if orderbook.is_stale() {
+            self.market_data.remove(&symbol.to_pair());
+            return;
+        }
+
         // Update market data
         self.market_data.insert(symbol.to_pair(), orderbook);
 
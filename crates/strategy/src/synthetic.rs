@@ -0,0 +1,261 @@
+//! Synthetic cross-rate books
+//!
+//! A venue that does not list a pair may still trade it through a third
+//! currency: BTC/USDT on a venue with only BTC/EUR is BTC/EUR followed by
+//! EUR/USDT. Composing the two books gives a synthetic BTC/USDT book the
+//! detector can compare against venues that list the pair directly.
+
+use std::collections::BTreeMap;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::ToPrimitive;
+
+use arbfinder_core::prelude::*;
+
+/// A trade an opportunity on a synthetic book needs in place of its single
+/// leg on that venue, in the order they are made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionLeg {
+    pub venue: VenueId,
+    pub symbol: Symbol,
+    pub side: OrderSide,
+}
+
+/// `symbol` on `venue`, built from a book on `direct` (same base, another
+/// quote) and a book on `conversion` between that quote and `symbol`'s.
+#[derive(Debug, Clone)]
+pub struct SyntheticBook {
+    pub venue: VenueId,
+    pub book: OrderBook,
+    pub direct: Symbol,
+    pub conversion: Symbol,
+}
+
+/// Levels of one side of a book, best first, as (price, quantity).
+fn levels(book: &OrderBook, side: Side) -> Vec<(Decimal, Decimal)> {
+    match side {
+        Side::Bid => book.bids.values().rev().map(|level| (level.price, level.quantity)).collect(),
+        Side::Ask => book.asks.values().map(|level| (level.price, level.quantity)).collect(),
+    }
+}
+
+/// Merges `direct` levels, priced in the intermediate currency per unit of
+/// base, with `rates`, the quote price of the intermediate currency and how
+/// much of it is available. Each resulting level is as much base as both
+/// can fill at the product of their prices.
+fn compose(direct: &[(Decimal, Decimal)], rates: &[(Decimal, Decimal)]) -> Vec<(Decimal, Decimal)> {
+    let mut composed = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    let mut direct_left = direct.first().map(|(_, quantity)| *quantity).unwrap_or_default();
+    let mut rate_left = rates.first().map(|(_, quantity)| *quantity).unwrap_or_default();
+
+    while i < direct.len() && j < rates.len() {
+        let (price, rate) = (direct[i].0, rates[j].0);
+        // Intermediate currency both levels can still move
+        let needed = direct_left * price;
+        let moved = needed.min(rate_left);
+        if moved > Decimal::ZERO {
+            composed.push((price * rate, moved / price));
+        }
+
+        if needed <= rate_left {
+            rate_left -= needed;
+            i += 1;
+            direct_left = direct.get(i).map(|(_, quantity)| *quantity).unwrap_or_default();
+        } else {
+            direct_left -= rate_left / price;
+            j += 1;
+            rate_left = rates.get(j).map(|(_, quantity)| *quantity).unwrap_or_default();
+        }
+    }
+    composed
+}
+
+impl SyntheticBook {
+    /// Composes `target` from `direct` and `conversion`, both on `venue`.
+    /// `conversion` may be quoted either way round, e.g. EUR/USDT or
+    /// USDT/EUR for a BTC/EUR book. Its `conversion_fee` is charged in the
+    /// synthetic prices, so the detector's fee for the venue covers only
+    /// the direct leg. Returns `None` if the books do not connect or leave
+    /// no level on either side.
+    pub fn build(
+        venue: VenueId,
+        target: &Symbol,
+        direct: &OrderBook,
+        conversion: &OrderBook,
+        conversion_fee: Decimal,
+    ) -> Option<Self> {
+        let via = direct.symbol.quote();
+        if direct.symbol.base() != target.base() || via == target.quote() {
+            return None;
+        }
+
+        let conversion_symbol = &conversion.symbol;
+        let inverted = if conversion_symbol.base() == via && conversion_symbol.quote() == target.quote() {
+            false
+        } else if conversion_symbol.base() == target.quote() && conversion_symbol.quote() == via {
+            true
+        } else {
+            return None;
+        };
+
+        // The quote price of one unit of the intermediate currency, and the
+        // units available, when buying it (for asks) or selling it (bids)
+        let rates = |side: Side| -> Vec<(Decimal, Decimal)> {
+            if inverted {
+                // Buying the intermediate currency sells the quote currency
+                // into the conversion book's bids, and the other way round
+                let opposite = match side {
+                    Side::Ask => Side::Bid,
+                    Side::Bid => Side::Ask,
+                };
+                levels(conversion, opposite)
+                    .into_iter()
+                    .filter(|(price, _)| *price > Decimal::ZERO)
+                    .map(|(price, quantity)| (Decimal::ONE / price, quantity * price))
+                    .collect()
+            } else {
+                levels(conversion, side)
+            }
+        };
+
+        let mut book = OrderBook::new(target.clone());
+        for side in [Side::Ask, Side::Bid] {
+            let fee_factor = match side {
+                Side::Ask => Decimal::ONE + conversion_fee,
+                Side::Bid => Decimal::ONE - conversion_fee,
+            };
+            let book_side = match side {
+                Side::Ask => &mut book.asks,
+                Side::Bid => &mut book.bids,
+            };
+            for (price, quantity) in compose(&levels(direct, side), &rates(side)) {
+                let price = price * fee_factor;
+                let key = ordered_float::OrderedFloat(price.to_f64().unwrap_or(0.0));
+                insert_level(book_side, key, price, quantity);
+            }
+        }
+        if book.bids.is_empty() && book.asks.is_empty() {
+            return None;
+        }
+        book.timestamp = direct.timestamp.min(conversion.timestamp);
+
+        Some(Self {
+            venue,
+            book,
+            direct: direct.symbol.clone(),
+            conversion: conversion_symbol.clone(),
+        })
+    }
+
+    /// The trades buying (`OrderSide::Buy`) or selling the target through
+    /// this book takes.
+    pub fn legs(&self, side: OrderSide) -> Vec<ConversionLeg> {
+        // Conversion pairs quoted in the intermediate currency trade the
+        // opposite way
+        let inverted = self.conversion.quote() == self.direct.quote();
+        let conversion_side = match (side, inverted) {
+            (OrderSide::Buy, false) | (OrderSide::Sell, true) => OrderSide::Buy,
+            _ => OrderSide::Sell,
+        };
+        let conversion = ConversionLeg {
+            venue: self.venue.clone(),
+            symbol: self.conversion.clone(),
+            side: conversion_side,
+        };
+        let direct = ConversionLeg {
+            venue: self.venue.clone(),
+            symbol: self.direct.clone(),
+            side,
+        };
+        match side {
+            // Get the intermediate currency first, then buy with it
+            OrderSide::Buy => vec![conversion, direct],
+            OrderSide::Sell => vec![direct, conversion],
+        }
+    }
+}
+
+fn insert_level(
+    levels: &mut BTreeMap<ordered_float::OrderedFloat<f64>, OrderBookLevel>,
+    key: ordered_float::OrderedFloat<f64>,
+    price: Decimal,
+    quantity: Decimal,
+) {
+    levels
+        .entry(key)
+        .and_modify(|level| level.quantity += quantity)
+        .or_insert_with(|| OrderBookLevel::new(price, quantity));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn book(symbol: Symbol, bids: &[(Decimal, Decimal)], asks: &[(Decimal, Decimal)]) -> OrderBook {
+        let mut book = OrderBook::new(symbol);
+        for (price, quantity) in bids {
+            book.update_bid(*price, *quantity);
+        }
+        for (price, quantity) in asks {
+            book.update_ask(*price, *quantity);
+        }
+        book
+    }
+
+    #[test]
+    fn test_composed_depth() {
+        let target = Symbol::new("BTC", "USDT");
+        let btc_eur = book(
+            Symbol::new("BTC", "EUR"),
+            &[(dec!(40000), dec!(1))],
+            &[(dec!(40100), dec!(1)), (dec!(40200), dec!(2))],
+        );
+        // 60,200 EUR at 1.1, then plenty at 1.2
+        let eur_usdt = book(
+            Symbol::new("EUR", "USDT"),
+            &[(dec!(1.05), dec!(100000))],
+            &[(dec!(1.1), dec!(60200)), (dec!(1.2), dec!(1000000))],
+        );
+
+        let synthetic = SyntheticBook::build(VenueId::Kraken, &target, &btc_eur, &eur_usdt, dec!(0)).unwrap();
+        assert_eq!(synthetic.book.symbol, target);
+        let asks: Vec<(Decimal, Decimal)> = levels(&synthetic.book, Side::Ask);
+        assert_eq!(
+            asks,
+            vec![
+                (dec!(44110), dec!(1)),   // 40,100 EUR at 1.1
+                (dec!(44220), dec!(0.5)), // the other 20,100 EUR at 1.1
+                (dec!(48240), dec!(1.5)),
+            ]
+        );
+        assert_eq!(synthetic.book.best_bid().unwrap().price, dec!(42000));
+
+        assert_eq!(
+            synthetic.legs(OrderSide::Buy),
+            vec![
+                ConversionLeg { venue: VenueId::Kraken, symbol: Symbol::new("EUR", "USDT"), side: OrderSide::Buy },
+                ConversionLeg { venue: VenueId::Kraken, symbol: Symbol::new("BTC", "EUR"), side: OrderSide::Buy },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_inverted_conversion_and_fee() {
+        let target = Symbol::new("BTC", "USDT");
+        let btc_eur = book(Symbol::new("BTC", "EUR"), &[(dec!(40000), dec!(1))], &[]);
+        // 0.8 EUR per USDT, so 1.25 USDT per EUR; 100,000 USDT of asks
+        let usdt_eur = book(Symbol::new("USDT", "EUR"), &[], &[(dec!(0.8), dec!(100000))]);
+
+        let synthetic = SyntheticBook::build(VenueId::Kraken, &target, &btc_eur, &usdt_eur, dec!(0.001)).unwrap();
+        let bid = synthetic.book.best_bid().unwrap();
+        assert_eq!(bid.price, dec!(49950));
+        assert_eq!(bid.quantity, dec!(1));
+        assert!(synthetic.book.asks.is_empty());
+        assert_eq!(synthetic.legs(OrderSide::Sell)[1].side, OrderSide::Buy);
+
+        // Books that do not connect
+        let eth_usdt = book(Symbol::new("ETH", "USDT"), &[], &[(dec!(1), dec!(1))]);
+        assert!(SyntheticBook::build(VenueId::Kraken, &target, &btc_eur, &eth_usdt, dec!(0)).is_none());
+    }
+}
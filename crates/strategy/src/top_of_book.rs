@@ -0,0 +1,100 @@
+//! Top-of-Book Arbitrage Detection
+//!
+//! For deployments that follow only each venue's best bid and offer. The
+//! latest quote of every venue is kept per symbol, and a new quote is only
+//! compared with the other venues' quotes for the same symbol, using the
+//! cross-exchange detector's thresholds and fees. With no depth there is
+//! nothing to walk, so lag adjustment and synthetic books are left out.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use arbfinder_core::prelude::*;
+
+use crate::arbitrage::{ArbitrageOpportunity, CrossExchangeArbitrageDetector};
+
+pub struct TopOfBookDetector {
+    detector: CrossExchangeArbitrageDetector,
+    /// How much older than a new quote another venue's may be and still be
+    /// compared with it.
+    max_age: Duration,
+    quotes: HashMap<Symbol, HashMap<VenueId, OrderBook>>,
+}
+
+impl TopOfBookDetector {
+    pub fn new(detector: CrossExchangeArbitrageDetector, max_age: Duration) -> Self {
+        Self {
+            detector,
+            max_age,
+            quotes: HashMap::new(),
+        }
+    }
+
+    /// Takes `venue`'s latest best bid and offer from `book`, returning the
+    /// opportunities between it and the other venues' quotes.
+    pub fn update(&mut self, venue: VenueId, book: &OrderBook) -> Vec<ArbitrageOpportunity> {
+        let symbol = book.symbol.clone();
+        let quote = book.top_of_book();
+        let max_age = chrono::Duration::from_std(self.max_age).unwrap_or(chrono::Duration::MAX);
+        let quotes = self.quotes.entry(symbol.clone()).or_default();
+        quotes.retain(|_, other| quote.timestamp - other.timestamp <= max_age);
+
+        let mut opportunities = Vec::new();
+        for (other_venue, other) in quotes.iter().filter(|(other_venue, _)| **other_venue != venue) {
+            let directions = [
+                (venue.clone(), other_venue.clone(), quote.best_ask(), other.best_bid()),
+                (other_venue.clone(), venue.clone(), other.best_ask(), quote.best_bid()),
+            ];
+            for (buy_venue, sell_venue, ask, bid) in directions {
+                if let (Some(ask), Some(bid)) = (ask, bid) {
+                    opportunities.extend(self.detector.check_quotes(&symbol, buy_venue, sell_venue, ask, bid));
+                }
+            }
+        }
+        quotes.insert(venue, quote);
+        opportunities
+    }
+
+    /// Venues with a current quote for `symbol`.
+    pub fn venues(&self, symbol: &Symbol) -> Vec<VenueId> {
+        self.quotes
+            .get(symbol)
+            .map(|quotes| quotes.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn quote(bid: Decimal, ask: Decimal, age_secs: i64) -> OrderBook {
+        let mut book = OrderBook::new(Symbol::new("BTC", "USDT"));
+        book.update_bid(bid - dec!(1), dec!(5));
+        book.update_bid(bid, dec!(1));
+        book.update_ask(ask, dec!(1));
+        book.timestamp = chrono::Utc::now() - chrono::Duration::seconds(age_secs);
+        book
+    }
+
+    #[test]
+    fn test_compares_new_quote_with_other_venues() {
+        let detector = CrossExchangeArbitrageDetector::new(10, dec!(100));
+        let mut top = TopOfBookDetector::new(detector, Duration::from_secs(5));
+
+        assert!(top.update(VenueId::Binance, &quote(dec!(100), dec!(101), 0)).is_empty());
+        let opportunities = top.update(VenueId::Coinbase, &quote(dec!(102), dec!(103), 0));
+        assert_eq!(opportunities.len(), 1);
+        assert_eq!(opportunities[0].buy_venue, VenueId::Binance);
+        assert_eq!(opportunities[0].sell_venue, VenueId::Coinbase);
+        assert_eq!(opportunities[0].sell_price, dec!(102));
+
+        // A venue that has gone quiet is not compared with
+        let mut top = TopOfBookDetector::new(CrossExchangeArbitrageDetector::new(10, dec!(100)), Duration::from_secs(5));
+        top.update(VenueId::Binance, &quote(dec!(100), dec!(101), 60));
+        assert!(top.update(VenueId::Coinbase, &quote(dec!(102), dec!(103), 0)).is_empty());
+        assert_eq!(top.venues(&Symbol::new("BTC", "USDT")), vec![VenueId::Coinbase]);
+    }
+}
@@ -0,0 +1,405 @@
+//! Live self-benchmark of the market data pipeline.
+//!
+//! Subscribes to the pairs given on every venue given and, for a while,
+//! measures on this machine and network: messages received a second per
+//! venue, how old book updates are when they arrive, REST round trips, and
+//! how long updating a book and running detection on it takes. From those
+//! it estimates how many messages a second the pipeline keeps up with, and
+//! so how many symbols it could follow on those venues at the rates seen.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use futures::StreamExt;
+use serde::Serialize;
+use tokio::sync::{mpsc, Mutex};
+use tracing::{info, warn};
+
+use arbfinder_core::utils::{RollingStats, StatsSummary};
+use arbfinder_core::{ArbFinderError, MarketData, OrderBook, Result, Symbol, VenueId};
+use arbfinder_exchange::{measure_clock_skew, MarketDataAdapter, MarketDataFanout};
+use arbfinder_strategy::arbitrage::CrossExchangeArbitrageDetector;
+
+/// Share of the measured processing capacity a deployment should plan on
+/// using, leaving the rest for bursts.
+pub const CAPACITY_HEADROOM: f64 = 0.5;
+
+/// Samples kept of each measurement; enough for a few minutes of a busy
+/// venue.
+const BENCH_SAMPLES: usize = 100_000;
+
+/// How often each venue's REST round trip is measured.
+const REST_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Parses a duration such as `60s`, `5m`, `1h` or `250ms`; a bare number is
+/// seconds.
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number
+        .parse()
+        .map_err(|_| ArbFinderError::InvalidData(format!("Invalid duration: {}", value)))?;
+    let secs = match unit.trim() {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return Err(ArbFinderError::InvalidData(format!("Invalid duration unit: {}", value))),
+    };
+    Ok(Duration::from_secs_f64(secs))
+}
+
+/// What one venue delivered during a benchmark.
+#[derive(Debug, Clone, Serialize)]
+pub struct VenueBench {
+    pub venue: VenueId,
+    pub messages: u64,
+    pub messages_per_sec: f64,
+    /// Age of book updates on arrival in seconds, by the venue's clock.
+    pub book_latency: Option<StatsSummary>,
+    /// REST round trips in seconds.
+    pub rest_round_trip: Option<StatsSummary>,
+    pub rest_errors: u64,
+    /// Venue's clock minus ours, taken out of the book latencies.
+    pub clock_offset_ms: i64,
+}
+
+/// What the pipeline kept up with during a benchmark, and how far it would
+/// go.
+#[derive(Debug, Clone, Serialize)]
+pub struct CapacityReport {
+    pub duration_secs: f64,
+    pub symbols: usize,
+    pub venues: Vec<VenueBench>,
+    /// Time to apply a book update and run detection on its symbol, in
+    /// seconds.
+    pub processing: Option<StatsSummary>,
+    /// Time of detection alone, in seconds.
+    pub detection: Option<StatsSummary>,
+    pub opportunities: u64,
+    /// Messages a second one core processes at the mean processing time,
+    /// within [`CAPACITY_HEADROOM`].
+    pub max_messages_per_sec: Option<f64>,
+    /// Symbols that could be followed on the same venues at the message
+    /// rates seen.
+    pub max_symbols: Option<usize>,
+}
+
+impl CapacityReport {
+    pub fn messages_per_sec(&self) -> f64 {
+        self.venues.iter().map(|venue| venue.messages_per_sec).sum()
+    }
+}
+
+fn millis(secs: f64) -> String {
+    format!("{:.3}ms", secs * 1000.0)
+}
+
+fn percentiles(summary: &Option<StatsSummary>) -> String {
+    match summary {
+        Some(summary) => format!("p50 {} p99 {}", millis(summary.p50), millis(summary.p99)),
+        None => "none".to_string(),
+    }
+}
+
+impl std::fmt::Display for CapacityReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Benchmarked {} symbols on {} venues for {:.1}s", self.symbols, self.venues.len(), self.duration_secs)?;
+        for venue in &self.venues {
+            writeln!(
+                f,
+                "  {}: {} messages ({:.1}/s), book latency {}, REST {} ({} errors), clock offset {:+}ms",
+                venue.venue,
+                venue.messages,
+                venue.messages_per_sec,
+                percentiles(&venue.book_latency),
+                percentiles(&venue.rest_round_trip),
+                venue.rest_errors,
+                venue.clock_offset_ms
+            )?;
+        }
+        writeln!(f, "Processing per book update: {}", percentiles(&self.processing))?;
+        writeln!(f, "Detection per book update: {}", percentiles(&self.detection))?;
+        writeln!(f, "Opportunities found: {}", self.opportunities)?;
+        match (self.max_messages_per_sec, self.max_symbols) {
+            (Some(rate), Some(symbols)) => write!(
+                f,
+                "Capacity: about {:.0} messages/s ({:.1}/s seen), {} symbols on these venues",
+                rate,
+                self.messages_per_sec(),
+                symbols
+            ),
+            (Some(rate), None) => write!(f, "Capacity: about {:.0} messages/s; no messages seen to size symbols by", rate),
+            _ => write!(f, "Capacity: unknown, no book updates were processed"),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct VenueStats {
+    messages: u64,
+    book_latency: RollingStats,
+    rest_round_trip: RollingStats,
+    rest_errors: u64,
+    clock_offset: chrono::Duration,
+}
+
+/// Measurements taken during a benchmark, fed the updates as they arrive.
+pub struct LiveBench {
+    detector: CrossExchangeArbitrageDetector,
+    symbols: usize,
+    books: HashMap<Symbol, HashMap<VenueId, OrderBook>>,
+    venues: HashMap<VenueId, VenueStats>,
+    processing: RollingStats,
+    detection: RollingStats,
+    opportunities: u64,
+}
+
+impl LiveBench {
+    pub fn new(detector: CrossExchangeArbitrageDetector, symbols: usize) -> Self {
+        Self {
+            detector,
+            symbols,
+            books: HashMap::new(),
+            venues: HashMap::new(),
+            processing: RollingStats::new(BENCH_SAMPLES),
+            detection: RollingStats::new(BENCH_SAMPLES),
+            opportunities: 0,
+        }
+    }
+
+    /// Adds `venue`, whose clock is `clock_offset` ahead of ours.
+    pub fn add_venue(&mut self, venue: VenueId, clock_offset: chrono::Duration) {
+        self.venues.insert(
+            venue,
+            VenueStats {
+                messages: 0,
+                book_latency: RollingStats::new(BENCH_SAMPLES),
+                rest_round_trip: RollingStats::new(BENCH_SAMPLES),
+                rest_errors: 0,
+                clock_offset,
+            },
+        );
+    }
+
+    pub fn record_rest(&mut self, venue: &VenueId, round_trip: Result<Duration>) {
+        if let Some(stats) = self.venues.get_mut(venue) {
+            match round_trip {
+                Ok(round_trip) => stats.rest_round_trip.record_duration(round_trip),
+                Err(_) => stats.rest_errors += 1,
+            }
+        }
+    }
+
+    /// Counts an update from `venue` received at `received`; book updates
+    /// are applied and detected on as the pipeline would.
+    pub fn record(&mut self, venue: VenueId, data: MarketData, received: DateTime<Utc>) {
+        let Some(stats) = self.venues.get_mut(&venue) else {
+            return;
+        };
+        stats.messages += 1;
+        let MarketData::OrderBook(book) = data else {
+            return;
+        };
+        let age = received + stats.clock_offset - book.timestamp;
+        stats.book_latency.record_duration(age.to_std().unwrap_or_default());
+
+        let started = Instant::now();
+        let symbol = book.symbol.clone();
+        let books = self.books.entry(symbol.clone()).or_default();
+        books.insert(venue, book);
+        let refs: HashMap<VenueId, &OrderBook> = books.iter().map(|(venue, book)| (venue.clone(), book)).collect();
+        let detecting = Instant::now();
+        let opportunities = self.detector.detect_opportunities(&symbol, &refs);
+        let done = Instant::now();
+
+        self.opportunities += opportunities.len() as u64;
+        self.detection.record_duration(done - detecting);
+        self.processing.record_duration(done - started);
+    }
+
+    pub fn report(&self, elapsed: Duration) -> CapacityReport {
+        let duration_secs = elapsed.as_secs_f64().max(f64::EPSILON);
+        let mut venues: Vec<VenueBench> = self
+            .venues
+            .iter()
+            .map(|(venue, stats)| VenueBench {
+                venue: venue.clone(),
+                messages: stats.messages,
+                messages_per_sec: stats.messages as f64 / duration_secs,
+                book_latency: stats.book_latency.summary(),
+                rest_round_trip: stats.rest_round_trip.summary(),
+                rest_errors: stats.rest_errors,
+                clock_offset_ms: stats.clock_offset.num_milliseconds(),
+            })
+            .collect();
+        venues.sort_by_key(|venue| venue.venue.to_string());
+
+        let processing = self.processing.summary();
+        let max_messages_per_sec = processing
+            .filter(|processing| processing.mean > 0.0)
+            .map(|processing| CAPACITY_HEADROOM / processing.mean);
+        let per_symbol = venues.iter().map(|venue| venue.messages_per_sec).sum::<f64>() / self.symbols.max(1) as f64;
+        let max_symbols = max_messages_per_sec
+            .filter(|_| per_symbol > 0.0)
+            .map(|rate| (rate / per_symbol).floor() as usize);
+
+        CapacityReport {
+            duration_secs,
+            symbols: self.symbols,
+            venues,
+            processing,
+            detection: self.detection.summary(),
+            opportunities: self.opportunities,
+            max_messages_per_sec,
+            max_symbols,
+        }
+    }
+}
+
+/// Runs a benchmark over `adapters` for `duration`, following the books of
+/// `symbols`. Venues that fail to connect or stream are left out with a
+/// warning.
+pub async fn run_live(
+    adapters: Vec<Box<dyn MarketDataAdapter>>,
+    symbols: &[Symbol],
+    duration: Duration,
+    detector: CrossExchangeArbitrageDetector,
+) -> Result<CapacityReport> {
+    let fanout = MarketDataFanout::default();
+    let mut updates = fanout.subscribe();
+    let mut bench = LiveBench::new(detector, symbols.len());
+    let (rest_tx, mut round_trips) = mpsc::unbounded_channel();
+    let mut connected = Vec::new();
+    let mut pingers = Vec::new();
+
+    for mut adapter in adapters {
+        let venue = adapter.venue_id();
+        if let Err(e) = adapter.connect().await {
+            warn!("Leaving {} out of the benchmark, failed to connect: {}", venue, e);
+            continue;
+        }
+        let offset = measure_clock_skew(adapter.as_ref()).await.map(|sample| sample.offset);
+        let offset = offset.unwrap_or_else(|e| {
+            warn!("Could not read {} server time, taking its clock as ours: {}", venue, e);
+            chrono::Duration::zero()
+        });
+        for symbol in symbols {
+            if let Err(e) = adapter.subscribe_orderbook(symbol, None).await {
+                warn!("Failed to subscribe to {} on {}: {}", symbol, venue, e);
+            }
+        }
+        match adapter.market_data_stream().await {
+            Ok(stream) => fanout.add_feed(venue.clone(), stream),
+            Err(e) => {
+                warn!("Leaving {} out of the benchmark, no market data stream: {}", venue, e);
+                let _ = adapter.disconnect().await;
+                continue;
+            }
+        }
+        info!("Benchmarking {}, clock offset {}ms", venue, offset.num_milliseconds());
+        bench.add_venue(venue.clone(), offset);
+
+        let adapter = Arc::new(Mutex::new(adapter));
+        let pinged = adapter.clone();
+        let rest_tx = rest_tx.clone();
+        pingers.push(tokio::spawn(async move {
+            let mut interval = tokio::time::interval(REST_INTERVAL);
+            loop {
+                interval.tick().await;
+                let started = Instant::now();
+                let result = pinged.lock().await.ping().await.map(|_| started.elapsed());
+                if rest_tx.send((venue.clone(), result)).is_err() {
+                    break;
+                }
+            }
+        }));
+        connected.push(adapter);
+    }
+    if connected.is_empty() {
+        return Err(ArbFinderError::Exchange("No venue could be benchmarked".to_string()));
+    }
+
+    let started = Instant::now();
+    let deadline = tokio::time::sleep(duration);
+    tokio::pin!(deadline);
+    loop {
+        tokio::select! {
+            _ = &mut deadline => break,
+            Some(update) = updates.next() => bench.record(update.venue, update.data, Utc::now()),
+            Some((venue, round_trip)) = round_trips.recv() => bench.record_rest(&venue, round_trip),
+        }
+    }
+    let elapsed = started.elapsed();
+
+    for pinger in pingers {
+        pinger.abort();
+    }
+    for adapter in connected {
+        let mut adapter = adapter.lock().await;
+        if let Err(e) = adapter.disconnect().await {
+            warn!("Failed to disconnect from {}: {}", adapter.venue_id(), e);
+        }
+    }
+
+    Ok(bench.report(elapsed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use rust_decimal_macros::dec;
+
+    fn book(bid: Decimal, ask: Decimal, timestamp: DateTime<Utc>) -> MarketData {
+        let mut book = OrderBook::new(Symbol::new("BTC", "USDT"));
+        book.update_bid(bid, dec!(1));
+        book.update_ask(ask, dec!(1));
+        book.timestamp = timestamp;
+        MarketData::OrderBook(book)
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("60s").unwrap(), Duration::from_secs(60));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert!(parse_duration("60 fortnights").is_err());
+    }
+
+    #[test]
+    fn test_report_measures_venues_and_capacity() {
+        let mut bench = LiveBench::new(CrossExchangeArbitrageDetector::new(10, dec!(0)), 1);
+        let now = Utc::now();
+        // Kraken's clock runs a second ahead of ours
+        bench.add_venue(VenueId::Binance, chrono::Duration::zero());
+        bench.add_venue(VenueId::Kraken, chrono::Duration::seconds(1));
+
+        bench.record(VenueId::Binance, book(dec!(100), dec!(101), now - chrono::Duration::milliseconds(20)), now);
+        bench.record(VenueId::Kraken, book(dec!(103), dec!(104), now + chrono::Duration::milliseconds(970)), now);
+        bench.record_rest(&VenueId::Kraken, Ok(Duration::from_millis(80)));
+        bench.record_rest(&VenueId::Kraken, Err(ArbFinderError::Timeout("ping".to_string())));
+        // Updates from venues not benchmarked are ignored
+        bench.record(VenueId::Coinbase, book(dec!(100), dec!(101), now), now);
+
+        let report = bench.report(Duration::from_secs(2));
+        assert_eq!(report.venues.len(), 2);
+        let binance = &report.venues[0];
+        assert_eq!((binance.messages, binance.messages_per_sec), (1, 0.5));
+        assert!((binance.book_latency.unwrap().max - 0.020).abs() < 1e-9);
+        let kraken = &report.venues[1];
+        assert!((kraken.book_latency.unwrap().max - 0.030).abs() < 1e-9);
+        assert_eq!(kraken.rest_round_trip.unwrap().count, 1);
+        assert_eq!((kraken.rest_errors, kraken.clock_offset_ms), (1, 1000));
+
+        // Buying on Binance at 101 and selling on Kraken at 103
+        assert_eq!(report.opportunities, 1);
+        assert_eq!(report.processing.unwrap().count, 2);
+        let rate = report.max_messages_per_sec.unwrap();
+        assert_eq!(report.max_symbols, Some((rate / report.messages_per_sec()).floor() as usize));
+    }
+}
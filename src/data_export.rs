@@ -2,9 +2,22 @@ use std::fs::{self, File};
 use std::io::{self, Write};
 use std::path::{Path, PathBuf};
 use chrono::Local;
-use csv::Writer;
+use arbfinder_core::utils::ReportLocale;
+use csv::WriterBuilder;
 use serde::Serialize;
 
+/// Column names of CSV exports, in field order.
+const CSV_HEADER: [&str; 8] = [
+    "timestamp",
+    "exchange1",
+    "exchange2",
+    "asset_pair",
+    "price1",
+    "price2",
+    "spread_percentage",
+    "volume",
+];
+
 /// Represents exportable data from the arbitrage finder
 #[derive(Serialize, Clone, Debug)]
 pub struct ExportData {
@@ -21,6 +34,7 @@ pub struct ExportData {
 /// Data export module for handling various export formats
 pub struct DataExporter {
     output_dir: PathBuf,
+    locale: ReportLocale,
 }
 
 impl DataExporter {
@@ -30,18 +44,43 @@ impl DataExporter {
         fs::create_dir_all(dir_path)?;
         Ok(DataExporter {
             output_dir: dir_path.to_path_buf(),
+            locale: ReportLocale::default(),
         })
     }
 
-    /// Export data to CSV format
+    /// Write CSV numbers with `locale`'s separators. JSON exports are
+    /// machine formats and keep `.` decimals.
+    pub fn with_locale(mut self, locale: ReportLocale) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Export data to CSV format, delimited by `;` where the locale uses
+    /// `,` for decimals
     pub fn export_csv(&self, filename: &str, data: &[ExportData]) -> io::Result<PathBuf> {
         let file_path = self.output_dir.join(filename);
         let file = File::create(&file_path)?;
-        let mut writer = Writer::from_writer(file);
+        let mut writer = WriterBuilder::new()
+            .delimiter(self.locale.csv_delimiter())
+            .from_writer(file);
 
+        if !data.is_empty() {
+            writer
+                .write_record(CSV_HEADER)
+                .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        }
         for record in data {
             writer
-                .serialize(record)
+                .write_record([
+                    record.timestamp.clone(),
+                    record.exchange1.clone(),
+                    record.exchange2.clone(),
+                    record.asset_pair.clone(),
+                    self.locale.float(record.price1),
+                    self.locale.float(record.price2),
+                    self.locale.float(record.spread_percentage),
+                    self.locale.float(record.volume),
+                ])
                 .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
         }
 
@@ -257,6 +296,28 @@ mod tests {
         assert!(content.contains("45000"));
     }
 
+    #[test]
+    fn test_csv_locale() {
+        let temp_dir = TempDir::new().expect("Failed to create temp directory");
+        let exporter = DataExporter::new(temp_dir.path())
+            .expect("Failed to create DataExporter")
+            .with_locale(ReportLocale {
+                decimal_separator: ',',
+                thousands_separator: Some('.'),
+                ..Default::default()
+            });
+        let data = create_test_data();
+
+        let file_path = exporter
+            .export_csv("locale_test.csv", &data)
+            .expect("Failed to export CSV");
+
+        let content = fs::read_to_string(&file_path).expect("Failed to read CSV file");
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines[0], CSV_HEADER.join(";"));
+        assert_eq!(lines[1], "2024-01-01 12:00:00;Binance;Kraken;BTC/USD;45.000;45.100;0,22;1,5");
+    }
+
     #[test]
     fn test_json_content_integrity() {
         let temp_dir = TempDir::new().expect("Failed to create temp directory");
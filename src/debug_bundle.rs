@@ -0,0 +1,240 @@
+//! Debug bundles for support requests.
+//!
+//! A bundle is a gzipped tarball of text files: configuration, log tails,
+//! health and version reports, and the most recent recorded events. Secrets
+//! are redacted from configuration by key name, and every value redacted
+//! that way is also scrubbed from the other files when the bundle is
+//! written, so a key echoed into a log does not leak through it.
+
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use arbfinder_core::{ArbFinderError, Result};
+
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Whether a configuration key names a credential.
+pub fn is_secret_key(key: &str) -> bool {
+    let key = key.to_lowercase();
+    ["key", "secret", "passphrase", "password", "token", "webhook"]
+        .iter()
+        .any(|marker| key.contains(marker))
+}
+
+/// Replaces every string under a secret key with [`REDACTED`], returning
+/// the values removed.
+pub fn redact_toml(value: &mut toml::Value) -> Vec<String> {
+    let mut removed = Vec::new();
+    redact_into(value, false, &mut removed);
+    removed
+}
+
+fn redact_into(value: &mut toml::Value, secret: bool, removed: &mut Vec<String>) {
+    match value {
+        toml::Value::String(s) if secret && !s.is_empty() && s != REDACTED => {
+            removed.push(std::mem::replace(s, REDACTED.to_string()));
+        }
+        toml::Value::Table(table) => {
+            for (key, value) in table.iter_mut() {
+                redact_into(value, secret || is_secret_key(key), removed);
+            }
+        }
+        toml::Value::Array(values) => {
+            for value in values {
+                redact_into(value, secret, removed);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Files collected for a bundle, written out together by [`DebugBundle::write`].
+#[derive(Debug, Default)]
+pub struct DebugBundle {
+    files: Vec<(String, String)>,
+    secrets: Vec<String>,
+}
+
+impl DebugBundle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A value to scrub from every file in the bundle.
+    pub fn add_secret(&mut self, secret: impl Into<String>) {
+        let secret = secret.into();
+        if !secret.is_empty() && !self.secrets.contains(&secret) {
+            self.secrets.push(secret);
+        }
+    }
+
+    pub fn add_text(&mut self, name: impl Into<String>, text: impl Into<String>) {
+        self.files.push((name.into(), text.into()));
+    }
+
+    /// Adds a TOML configuration file with its secrets redacted.
+    pub fn add_config(&mut self, name: impl Into<String>, contents: &str) -> Result<()> {
+        let mut value: toml::Value = toml::from_str(contents)
+            .map_err(|e| ArbFinderError::InvalidData(format!("Failed to parse config: {}", e)))?;
+        for secret in redact_toml(&mut value) {
+            self.add_secret(secret);
+        }
+        let redacted = toml::to_string_pretty(&value)
+            .map_err(|e| ArbFinderError::Internal(format!("Failed to write config: {}", e)))?;
+        self.add_text(name, redacted);
+        Ok(())
+    }
+
+    /// Adds the last `max_bytes` of a log file, starting at a line boundary.
+    pub fn add_log_tail(&mut self, path: &Path, max_bytes: u64) -> Result<()> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let start = len.saturating_sub(max_bytes);
+        file.seek(SeekFrom::Start(start))?;
+
+        let mut bytes = Vec::new();
+        file.read_to_end(&mut bytes)?;
+        let mut text = String::from_utf8_lossy(&bytes).into_owned();
+        if start > 0 {
+            // The first line was cut part way through
+            text = text.split_once('\n').map(|(_, rest)| rest.to_string()).unwrap_or_default();
+        }
+
+        let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        self.add_text(format!("logs/{}", name), text);
+        Ok(())
+    }
+
+    /// Adds the last `count` events of a recording, keeping its header line
+    /// so the excerpt is still a readable recording. Lines are copied as-is,
+    /// so a recording the current schema cannot parse is still captured.
+    pub fn add_recent_events(&mut self, path: &Path, count: usize) -> Result<()> {
+        let mut lines = BufReader::new(File::open(path)?).lines();
+        let header = lines.next().transpose()?.unwrap_or_default();
+        let is_header = header.contains("\"format\"");
+
+        let first = (!is_header).then(|| Ok(header.clone()));
+        let mut recent = VecDeque::with_capacity(count);
+        for line in first.into_iter().chain(lines) {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            recent.push_back(line);
+            if recent.len() > count {
+                recent.pop_front();
+            }
+        }
+
+        let mut text = String::new();
+        if is_header {
+            text.push_str(&header);
+            text.push('\n');
+        }
+        for line in recent {
+            text.push_str(&line);
+            text.push('\n');
+        }
+        self.add_text("events.jsonl", text);
+        Ok(())
+    }
+
+    pub fn file_names(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().map(|(name, _)| name.as_str())
+    }
+
+    fn scrub(&self, text: &str) -> String {
+        // Longest first, so a secret containing another is removed whole
+        let mut secrets: Vec<&String> = self.secrets.iter().collect();
+        secrets.sort_by_key(|secret| std::cmp::Reverse(secret.len()));
+        secrets
+            .into_iter()
+            .fold(text.to_string(), |text, secret| text.replace(secret.as_str(), REDACTED))
+    }
+
+    /// Writes the bundle as a `.tar.gz` under a top-level `root` directory.
+    pub fn write<W: Write>(&self, writer: W, root: &str) -> Result<W> {
+        let encoder = flate2::write::GzEncoder::new(writer, flate2::Compression::default());
+        let mut archive = tar::Builder::new(encoder);
+        let mtime = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|since| since.as_secs())
+            .unwrap_or_default();
+
+        for (name, text) in &self.files {
+            let text = self.scrub(text);
+            let mut header = tar::Header::new_gnu();
+            header.set_size(text.len() as u64);
+            header.set_mode(0o644);
+            header.set_mtime(mtime);
+            header.set_cksum();
+            archive.append_data(&mut header, PathBuf::from(root).join(name), text.as_bytes())?;
+        }
+
+        Ok(archive.into_inner()?.finish()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_toml() {
+        let mut value: toml::Value = toml::from_str(
+            r#"
+            [exchanges.binance]
+            api_key = "abc"
+            api_secret = "def"
+            sandbox = true
+
+            [monitoring]
+            alert_webhook_url = "https://hooks.example/xyz"
+            log_level = "info"
+            "#,
+        )
+        .unwrap();
+
+        let mut removed = redact_toml(&mut value);
+        removed.sort();
+        assert_eq!(removed, vec!["abc", "def", "https://hooks.example/xyz"]);
+        assert_eq!(value["exchanges"]["binance"]["api_key"].as_str(), Some(REDACTED));
+        assert_eq!(value["exchanges"]["binance"]["sandbox"].as_bool(), Some(true));
+        assert_eq!(value["monitoring"]["log_level"].as_str(), Some("info"));
+    }
+
+    #[test]
+    fn test_bundle_scrubs_secrets_from_every_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let recording = dir.path().join("session.jsonl");
+        std::fs::write(
+            &recording,
+            "{\"format\":\"arbfinder-recording\",\"schema_version\":1}\n{\"n\":1}\n{\"n\":2}\n{\"n\":3}\n",
+        )
+        .unwrap();
+
+        let mut bundle = DebugBundle::new();
+        bundle.add_config("config.toml", "[exchanges.kraken]\napi_key = \"sekrit\"\n").unwrap();
+        bundle.add_text("logs/arbfinder.log", "signing with sekrit\n");
+        bundle.add_recent_events(&recording, 2).unwrap();
+
+        let bytes = bundle.write(Vec::new(), "bundle").unwrap();
+        let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(bytes.as_slice()));
+        let mut files = std::collections::HashMap::new();
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let mut text = String::new();
+            entry.read_to_string(&mut text).unwrap();
+            files.insert(entry.path().unwrap().to_string_lossy().into_owned(), text);
+        }
+
+        assert!(!files.values().any(|text| text.contains("sekrit")));
+        assert_eq!(files["bundle/logs/arbfinder.log"], "signing with [REDACTED]\n");
+        let events: Vec<&str> = files["bundle/events.jsonl"].lines().collect();
+        assert_eq!(events.len(), 3);
+        assert!(events[0].contains("arbfinder-recording"));
+        assert_eq!(&events[1..], ["{\"n\":2}", "{\"n\":3}"]);
+    }
+}
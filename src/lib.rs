@@ -1,11 +1,118 @@
-pub use arbfinder_core::prelude::*;
-pub use arbfinder_exchange::prelude::*;
-pub use arbfinder_orderbook::*;
-pub use arbfinder_strategy::prelude::*;
-pub use arbfinder_execution::prelude::*;
-pub use arbfinder_monitoring::prelude::*;
-
-// Re-export exchange adapters
-pub use arbfinder_binance::BinanceAdapter;
-pub use arbfinder_coinbase::CoinbaseAdapter;
-pub use arbfinder_kraken::KrakenAdapter;
+//! ArbFinder facade.
+//!
+//! Each workspace crate is available as a module. [`prelude`] collects the
+//! types most programs need; where two crates use the same name for
+//! different things, the prelude renames one of them so a single glob
+//! import stays unambiguous.
+
+pub use arbfinder_core as common;
+pub use arbfinder_exchange as exchange;
+pub use arbfinder_execution as execution;
+pub use arbfinder_ml as ml;
+pub use arbfinder_monitoring as monitoring;
+pub use arbfinder_orderbook as orderbook;
+pub use arbfinder_strategy as strategy;
+
+pub mod archive;
+pub mod bench;
+pub mod debug_bundle;
+pub mod migrate;
+
+/// What this binary was built from, as recorded by the build script.
+pub fn build_info() -> monitoring::BuildInfo {
+    let features = env!("ARBFINDER_FEATURES");
+    monitoring::BuildInfo {
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        git_commit: env!("ARBFINDER_GIT_COMMIT").to_string(),
+        git_dirty: env!("ARBFINDER_GIT_DIRTY") == "true",
+        build_time: env!("ARBFINDER_BUILD_TIME")
+            .parse()
+            .ok()
+            .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0)),
+        profile: env!("ARBFINDER_PROFILE").to_string(),
+        features: features.split(',').filter(|f| !f.is_empty()).map(str::to_string).collect(),
+    }
+}
+
+/// Exchange adapters, each behind a cargo feature of the same name. All are
+/// enabled by default through `all-adapters`.
+pub mod adapters {
+    #[cfg(feature = "binance")]
+    pub use arbfinder_binance::BinanceAdapter;
+    #[cfg(feature = "coinbase")]
+    pub use arbfinder_coinbase::CoinbaseAdapter;
+    #[cfg(feature = "kraken")]
+    pub use arbfinder_kraken::KrakenAdapter;
+    #[cfg(feature = "gemini")]
+    pub use arbfinder_gemini::GeminiAdapter;
+    #[cfg(feature = "htx")]
+    pub use arbfinder_htx::HtxAdapter;
+    #[cfg(feature = "mexc")]
+    pub use arbfinder_mexc::MexcAdapter;
+    #[cfg(feature = "bitfinex")]
+    pub use arbfinder_bitfinex::BitfinexAdapter;
+    #[cfg(feature = "poloniex")]
+    pub use arbfinder_poloniex::PoloniexAdapter;
+    #[cfg(feature = "upbit")]
+    pub use arbfinder_upbit::UpbitAdapter;
+    #[cfg(feature = "bithumb")]
+    pub use arbfinder_bithumb::BithumbAdapter;
+    #[cfg(feature = "bitget")]
+    pub use arbfinder_bitget::BitgetAdapter;
+    #[cfg(feature = "deribit")]
+    pub use arbfinder_deribit::DeribitAdapter;
+    #[cfg(feature = "hyperliquid")]
+    pub use arbfinder_hyperliquid::HyperliquidAdapter;
+    #[cfg(feature = "uniswap")]
+    pub use arbfinder_uniswap::UniswapV3Adapter;
+    #[cfg(feature = "curve")]
+    pub use arbfinder_curve::CurveAdapter;
+    #[cfg(feature = "jupiter")]
+    pub use arbfinder_jupiter::JupiterAdapter;
+    #[cfg(feature = "zerox")]
+    pub use arbfinder_zerox::ZeroExAdapter;
+}
+
+pub mod prelude {
+    pub use arbfinder_core::prelude::*;
+
+    // The file-level settings share names with the runtime configs of the
+    // execution and monitoring crates, which are what the engine takes.
+    pub use arbfinder_core::config::{
+        ArbFinderConfig,
+        ExecutionConfig as ExecutionSettings,
+        MonitoringConfig as MonitoringSettings,
+    };
+
+    pub use arbfinder_exchange::prelude::{
+        AccountInfo, ConnectionStatus, DefaultSymbolNormalizer, ExchangeAdapter, ExchangeConfig,
+        ExchangeManager, InstrumentInfo, InstrumentKind, MarketDataAdapter, MarketDataStream,
+        OptionKind, OrderUpdateStream, RateLimiter, RestClient, SubscriptionInfo, SymbolFormat,
+        SymbolInfo, SymbolNormalizer, TradingAdapter, TradingFees, VenueAdapter, WebSocketHandler,
+    };
+
+    pub use arbfinder_orderbook::{
+        AggregatedOrderBook, CrossVenueFill, FastOrderBook, L3OrderBook, OrderBookCache, OrderBookEvent,
+        OrderBookManager, OrderBookSnapshot, OrderBookUpdate, PriceLevel, QueuePosition, RecordedEvent,
+        RecordingReader, RecordingWriter,
+    };
+
+    // The detector's output is a lighter record than the core
+    // `ArbitrageOpportunity` that execution consumes.
+    pub use arbfinder_strategy::arbitrage::{
+        ArbitrageOpportunity as DetectedOpportunity,
+        CrossExchangeArbitrageDetector,
+        TradingFeePair,
+    };
+    pub use arbfinder_strategy::fair_value::{FairValueModel, LeadLag, LeadLagEstimator};
+    pub use arbfinder_strategy::kimchi::{KrwPremium, KrwPremiumCalculator, KrwRateSource};
+    pub use arbfinder_strategy::replay::{OpportunityDiff, OpportunityReplay, RouteDiff};
+    pub use arbfinder_strategy::Strategy;
+
+    pub use arbfinder_execution::prelude::*;
+    pub use arbfinder_monitoring::prelude::*;
+
+    // Empty when built without any adapter feature
+    #[allow(unused_imports)]
+    pub use crate::adapters::*;
+}
@@ -1,20 +1,37 @@
+use std::path::Path;
 use std::sync::Arc;
 use tokio::signal;
-use tracing::{info, error};
+use tracing::{debug, info, error, warn};
 use clap::{Parser, Subcommand};
 
 use arbfinder_core::prelude::*;
+use arbfinder_core::config::{ArbFinderConfig, ProxyConfig, RateLimitConfig, StrategyConfig};
+use arbfinder_core::utils::ReportLocale;
 use arbfinder_strategy::prelude::*;
 use arbfinder_execution::prelude::*;
+use arbfinder_execution::{admin_routes, credential_routes};
+use arbfinder_exchange::{
+    EnvSecrets, KeyringSecrets, MarketDataAdapter, Proxy, SecretsProvider, SharedRateLimiter, SymbolMappings, TlsPins,
+    VaultSecrets, VenueHealth, VenueStatusConfig, WeightedRateLimiter,
+};
+use arbfinder_orderbook::{PriceSanityConfig, RecordingReader};
 use arbfinder_monitoring::prelude::*;
 use arbfinder_monitoring::alerts::AlertConfig;
+use arbfinder::archive::{ArchiveConfig, Archiver};
+use arbfinder::debug_bundle::{DebugBundle, REDACTED};
+use arbfinder::migrate;
 use rust_decimal::Decimal;
 use rust_decimal::prelude::FromPrimitive;
 
 // Exchange adapters
+#[cfg(feature = "binance")]
 use arbfinder_binance::BinanceAdapter;
+#[cfg(feature = "coinbase")]
 use arbfinder_coinbase::CoinbaseAdapter;
+#[cfg(feature = "kraken")]
 use arbfinder_kraken::KrakenAdapter;
+#[cfg(feature = "bitget")]
+use arbfinder_bitget::BitgetAdapter;
 
 #[derive(Parser)]
 #[command(name = "arbfinder")]
@@ -39,11 +56,132 @@ enum Commands {
         /// Log level
         #[arg(long, default_value = "info")]
         log_level: String,
+
+        /// Only log servers presenting unpinned keys, e.g. while a venue
+        /// rotates its certificates
+        #[arg(long)]
+        ignore_tls_pins: bool,
+    },
+    /// Re-run detection over a recording and diff the opportunities found
+    /// against those originally detected
+    ReplayOpportunities {
+        /// Recording file path
+        #[arg(long)]
+        recording: String,
+
+        /// Detector settings to replay with
+        #[arg(long)]
+        detector_config: String,
+
+        /// What-if venue fees and latencies to replay with
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// Estimate which venues lead which, and by how long, from the books in
+    /// a recording
+    LeadLag {
+        /// Recording file path
+        #[arg(long)]
+        recording: String,
+
+        /// Sampling interval in milliseconds
+        #[arg(long, default_value_t = 10)]
+        bucket_ms: u64,
+
+        /// Longest lag to look for in milliseconds
+        #[arg(long, default_value_t = 500)]
+        max_lag_ms: u64,
+    },
+    /// Collect logs, redacted config, health and recent events into a
+    /// tarball to attach to a support request
+    DebugBundle {
+        /// Configuration file path
+        #[arg(short, long, default_value = "config.toml")]
+        config: String,
+
+        /// Output path; defaults to arbfinder-debug-<timestamp>.tar.gz
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Recording to take the most recent events from
+        #[arg(long)]
+        recording: Option<String>,
+
+        /// Number of recent events to include
+        #[arg(long, default_value_t = 1000)]
+        events: usize,
+
+        /// Number of most recent log files to include
+        #[arg(long, default_value_t = 3)]
+        log_files: usize,
+
+        /// Bytes kept from the end of each log file
+        #[arg(long, default_value_t = 1024 * 1024)]
+        log_bytes: u64,
+    },
+    /// Rewrite stored recordings at the current schema version
+    Migrate {
+        /// Configuration file path
+        #[arg(short, long, default_value = "config.toml")]
+        config: String,
+
+        /// Recordings directory; defaults to `[storage] recordings_dir`
+        #[arg(long)]
+        dir: Option<String>,
+
+        /// List what would be migrated without writing anything
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Restore the recordings as they were before their last migration
+        #[arg(long, conflicts_with = "dry_run")]
+        rollback: bool,
+    },
+    /// Measure message throughput, book and detection latency and REST
+    /// round trips on this machine and network, and estimate how many
+    /// symbols the deployment can follow
+    Bench {
+        /// Configuration file path; its exchanges are benchmarked
+        #[arg(short, long, default_value = "config.toml")]
+        config: String,
+
+        /// How long to follow live market data, e.g. 60s or 5m
+        #[arg(long, value_parser = parse_bench_duration)]
+        live: std::time::Duration,
+
+        /// Pairs to follow, e.g. BTC/USDT; defaults to the configured
+        /// trading pairs
+        #[arg(long, value_delimiter = ',')]
+        symbols: Vec<String>,
+
+        /// Detector settings to detect with
+        #[arg(long)]
+        detector_config: Option<String>,
+
+        /// Print the report as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Regenerate the symbol mappings file from the symbols each
+    /// configured exchange lists
+    Symbols {
+        /// Configuration file path
+        #[arg(short, long, default_value = "config.toml")]
+        config: String,
+
+        /// File to write, `.json` or `.toml`; defaults to the configured
+        /// `symbol_mappings`
+        #[arg(short, long)]
+        output: Option<String>,
     },
     /// Check system health
     Health,
-    /// Show version information
-    Version,
+    /// Show version, commit, build time and enabled features
+    Version {
+        /// Print as JSON, e.g. to attach to a bug report
+        #[arg(long)]
+        json: bool,
+    },
 }
 
 #[derive(Debug, Clone)]
@@ -51,6 +189,59 @@ pub struct AppConfig {
     pub execution: ExecutionConfig,
     pub monitoring: MonitoringConfig,
     pub exchanges: ExchangeConfigs,
+    pub storage: StorageConfig,
+    /// Pairs monitored for opportunities, and checked for halts.
+    pub trading_pairs: Vec<Symbol>,
+    /// Number separators and timezone of reports read by people.
+    pub reports: ReportLocale,
+    /// Where venue credentials are kept.
+    pub secrets: SecretsSource,
+}
+
+/// Where venue credentials are read from, `provider` under `[secrets]`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum SecretsSource {
+    /// `api_key` and `api_secret` in each `[exchanges.*]` table.
+    #[default]
+    ConfigFile,
+    /// `{prefix}_{VENUE}_API_KEY` and `_API_SECRET` variables.
+    Env { prefix: String },
+    /// Entries of `service` in the OS keyring.
+    Keyring { service: String },
+    /// Secrets at `{mount}/{path}/{venue}` in the Vault at `VAULT_ADDR`.
+    Vault { mount: String, path: String },
+}
+
+impl SecretsSource {
+    /// Provider the credentials are read from, on startup and on reloads.
+    fn provider(&self, config_path: &str) -> Result<Arc<dyn SecretsProvider>> {
+        Ok(match self {
+            SecretsSource::ConfigFile => Arc::new(ConfigFileSecrets::new(config_path)),
+            SecretsSource::Env { prefix } => Arc::new(EnvSecrets::new(prefix.clone())),
+            SecretsSource::Keyring { service } => Arc::new(KeyringSecrets::new(service.clone())),
+            SecretsSource::Vault { mount, path } => Arc::new(VaultSecrets::from_env(mount.clone(), path.clone())?),
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StorageConfig {
+    pub recordings_dir: Option<String>,
+    /// Migrate recordings to the current schema before running
+    pub migrate_on_startup: bool,
+    /// Upload finished files to S3-compatible storage; `None` keeps
+    /// everything local.
+    pub archive: Option<ArchiveConfig>,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            recordings_dir: None,
+            migrate_on_startup: true,
+            archive: None,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -58,14 +249,168 @@ pub struct ExchangeConfigs {
     pub binance: Option<ExchangeCredentials>,
     pub coinbase: Option<ExchangeCredentials>,
     pub kraken: Option<ExchangeCredentials>,
+    pub bitget: Option<ExchangeCredentials>,
+    /// File venue symbol names are mapped in, `symbol_mappings` under
+    /// `[exchanges]`.
+    pub symbol_mappings_file: Option<String>,
+    /// Its mappings, registered with each adapter on startup.
+    pub symbol_mappings: SymbolMappings,
 }
 
 #[derive(Debug, Clone)]
 pub struct ExchangeCredentials {
-    pub api_key: String,
-    pub api_secret: String,
-    pub passphrase: Option<String>, // For Coinbase
+    pub api_key: SecretString,
+    pub api_secret: SecretString,
+    pub passphrase: Option<SecretString>, // For Bitget
     pub sandbox: bool,
+    /// Keys the venue's REST and WebSocket servers must present.
+    pub tls_pins: Option<TlsPins>,
+    /// Proxy the venue's REST requests and WebSocket connections go through.
+    pub proxy: Option<ProxyConfig>,
+    /// Binance margin account to trade, `cross` or `isolated`, and whether
+    /// its orders borrow and repay automatically.
+    pub margin: Option<String>,
+    pub auto_borrow: bool,
+    /// How long symbol lists and trading rules are cached, where the
+    /// adapter caches them.
+    pub symbols_ttl: Option<std::time::Duration>,
+    /// Limits the venue's requests are drawn from in place of the
+    /// adapter's defaults, shared by every adapter built from this config.
+    pub rate_limiter: Option<SharedRateLimiter>,
+}
+
+impl ExchangeCredentials {
+    fn venue_credentials(&self) -> VenueCredentials {
+        VenueCredentials {
+            api_key: self.api_key.clone(),
+            secret_key: self.api_secret.clone(),
+            passphrase: self.passphrase.clone(),
+            sandbox: self.sandbox,
+        }
+    }
+}
+
+/// Credentials as the config file has them now, reread on every call so
+/// keys rotated in the file are picked up.
+pub struct ConfigFileSecrets {
+    path: String,
+}
+
+impl ConfigFileSecrets {
+    pub fn new(path: impl Into<String>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[async_trait::async_trait]
+impl SecretsProvider for ConfigFileSecrets {
+    async fn credentials(&self, venue: &VenueId) -> Result<Option<VenueCredentials>> {
+        let exchanges = load_config(&self.path)?.exchanges;
+        let credentials = match venue {
+            VenueId::Binance => exchanges.binance,
+            VenueId::Coinbase => exchanges.coinbase,
+            VenueId::Kraken => exchanges.kraken,
+            VenueId::Bitget => exchanges.bitget,
+            _ => None,
+        };
+        Ok(credentials.as_ref().map(ExchangeCredentials::venue_credentials))
+    }
+}
+
+/// Fills in the configured venues' credentials from the `[secrets]`
+/// provider, returning it to reload them from later. Venues it has no
+/// credentials for are left out, as venues without keys in the config file
+/// are.
+async fn load_credentials(config: &mut AppConfig, config_path: &str) -> Result<Arc<dyn SecretsProvider>> {
+    let secrets = config.secrets.provider(config_path)?;
+    if config.secrets == SecretsSource::ConfigFile {
+        return Ok(secrets);
+    }
+
+    let exchanges = &mut config.exchanges;
+    for (venue, configured) in [
+        (VenueId::Binance, &mut exchanges.binance),
+        (VenueId::Coinbase, &mut exchanges.coinbase),
+        (VenueId::Kraken, &mut exchanges.kraken),
+        (VenueId::Bitget, &mut exchanges.bitget),
+    ] {
+        let Some(credentials) = configured.as_mut() else {
+            continue;
+        };
+        match secrets.credentials(&venue).await? {
+            Some(found) => {
+                credentials.api_key = found.api_key.clone();
+                credentials.api_secret = found.secret_key.clone();
+                if found.passphrase.is_some() {
+                    credentials.passphrase = found.passphrase.clone();
+                }
+            }
+            None if credentials.api_key.is_empty() => {
+                warn!("No credentials for {} in {:?}; leaving it out", venue, config.secrets);
+                *configured = None;
+            }
+            None => {}
+        }
+    }
+    Ok(secrets)
+}
+
+impl AppConfig {
+    /// A copy safe to share, with credentials and webhook URLs replaced by
+    /// [`REDACTED`], along with the values removed.
+    fn redacted(&self) -> (Self, Vec<String>) {
+        let mut config = self.clone();
+        let mut removed = Vec::new();
+        let mut redact = |value: &mut String| {
+            removed.push(std::mem::replace(value, REDACTED.to_string()));
+        };
+
+        for credentials in [
+            &mut config.exchanges.binance,
+            &mut config.exchanges.coinbase,
+            &mut config.exchanges.kraken,
+            &mut config.exchanges.bitget,
+        ]
+        .into_iter()
+        .flatten()
+        {
+            for secret in [&mut credentials.api_key, &mut credentials.api_secret]
+                .into_iter()
+                .chain(credentials.passphrase.as_mut())
+            {
+                let mut value = secret.expose().to_string();
+                redact(&mut value);
+                *secret = SecretString::from(value);
+            }
+            if let Some(proxy) = credentials.proxy.as_mut() {
+                // The URL may carry credentials of its own
+                if proxy.url.contains('@') {
+                    redact(&mut proxy.url);
+                }
+                if let Some(password) = proxy.password.as_mut() {
+                    redact(password);
+                }
+            }
+        }
+
+        if let Some(archive) = config.storage.archive.as_mut() {
+            redact(&mut archive.access_key);
+            redact(&mut archive.secret_key);
+        }
+
+        let alerts = &mut config.monitoring.alert_config;
+        if let Some(url) = alerts.webhook_url.as_mut() {
+            redact(url);
+        }
+        if let Some(email) = alerts.email_config.as_mut() {
+            redact(&mut email.password);
+        }
+        if let Some(slack) = alerts.slack_config.as_mut() {
+            redact(&mut slack.webhook_url);
+        }
+
+        (config, removed)
+    }
 }
 
 impl Default for AppConfig {
@@ -77,7 +422,14 @@ impl Default for AppConfig {
                 binance: None,
                 coinbase: None,
                 kraken: None,
+                bitget: None,
+                symbol_mappings_file: None,
+                symbol_mappings: SymbolMappings::new(),
             },
+            storage: StorageConfig::default(),
+            trading_pairs: Vec::new(),
+            reports: ReportLocale::default(),
+            secrets: SecretsSource::default(),
         }
     }
 }
@@ -87,12 +439,16 @@ pub struct ArbFinderApp {
     execution_engine: ExecutionEngine,
     monitoring_system: MonitoringSystem,
     health_checker: Arc<HealthChecker>,
+    /// Where credentials are reread from when reloaded; `None` keeps the
+    /// ones the exchanges were set up with.
+    secrets: Option<Arc<dyn SecretsProvider>>,
 }
 
 impl ArbFinderApp {
     pub fn new(config: AppConfig) -> Result<Self> {
         let execution_engine = ExecutionEngine::new(config.execution.clone());
         let monitoring_system = MonitoringSystem::new(config.monitoring.clone())?;
+        execution_engine.set_metrics(monitoring_system.get_metrics_collector());
         let health_checker = Arc::new(HealthChecker::new());
 
         Ok(Self {
@@ -100,15 +456,49 @@ impl ArbFinderApp {
             execution_engine,
             monitoring_system,
             health_checker,
+            secrets: None,
         })
     }
 
+    pub fn with_secrets(mut self, secrets: Arc<dyn SecretsProvider>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
     pub async fn run(&mut self) -> Result<()> {
         info!("Starting ArbFinder application");
 
-        // Start monitoring system
+        let build_info = arbfinder::build_info();
+        info!("{}", build_info);
+        self.monitoring_system.get_metrics_collector().record_build_info(&build_info);
+
+        // Start monitoring system, serving the admin API next to metrics
+        self.monitoring_system.add_routes(admin_routes(
+            self.execution_engine.simulator(),
+            self.execution_engine.feature_flags().clone(),
+            self.execution_engine.cooldowns().clone(),
+        ));
+        self.monitoring_system.add_routes(build_info.routes());
+        let (reload_tx, mut reloads) = tokio::sync::mpsc::unbounded_channel::<ReloadRequest>();
+        self.monitoring_system.add_routes(credential_routes(reload_tx.clone()));
         self.monitoring_system.start().await?;
+
+        if let Some(archive) = &self.config.storage.archive {
+            info!("Archiving {} directories to bucket {}", archive.dirs.len(), archive.bucket);
+            tokio::spawn(Archiver::new(archive.clone()).run());
+        }
         
+        // Share opportunities with other executors
+        if let Some(claims) = &self.config.execution.claims {
+            let store = NatsClaimStore::connect(claims).await?;
+            self.execution_engine.set_claims(OpportunityClaims::new(Arc::new(store), claims.executor_id.clone()));
+            info!("Claiming opportunities in {} as {}", claims.bucket, claims.executor_id);
+        }
+        
+        if self.config.execution.market_data == MarketDataMode::BboOnly {
+            info!("Following best bid and offer only; books are kept one level deep");
+        }
+
         // Register health check components
         self.health_checker.register_component("execution_engine").await;
         self.health_checker.register_component("monitoring_system").await;
@@ -116,6 +506,19 @@ impl ArbFinderApp {
         // Setup exchanges
         self.setup_exchanges().await?;
 
+        // Weigh venues by the account's own fee tiers
+        if let Some(symbol) = self.config.trading_pairs.first() {
+            for (venue, fees) in self.execution_engine.refresh_venue_fees(symbol).await {
+                info!(
+                    "{} fees: maker {}, taker {}{}",
+                    venue,
+                    fees.maker_fee,
+                    fees.taker_fee,
+                    fees.tier.map(|tier| format!(" ({})", tier)).unwrap_or_default()
+                );
+            }
+        }
+
         // Setup strategies
         self.setup_strategies().await?;
 
@@ -131,8 +534,130 @@ impl ArbFinderApp {
 
         info!("ArbFinder application started successfully");
 
-        // Wait for shutdown signal
-        self.wait_for_shutdown().await;
+        // Alert on account activity this instance did not originate
+        let (anomaly_tx, mut anomalies) = tokio::sync::mpsc::unbounded_channel();
+        self.execution_engine.watch_account_activity(anomaly_tx).await?;
+
+        // Watch for pairs halted or delisted
+        let (pair_tx, mut pair_events) = tokio::sync::mpsc::unbounded_channel();
+        self.execution_engine.watch_pair_lifecycle(self.config.trading_pairs.clone(), pair_tx);
+
+        // Watch venue status pages for outages and scheduled maintenance
+        let (status_tx, mut venue_events) = tokio::sync::mpsc::unbounded_channel();
+        self.execution_engine.watch_venue_status(status_tx);
+
+        // Reconcile with each venue's records daily
+        let (report_tx, mut reconciliations) = tokio::sync::mpsc::unbounded_channel();
+        self.execution_engine.watch_reconciliation(report_tx);
+
+        // Reload credentials on SIGHUP as on `POST /credentials/reload`
+        #[cfg(unix)]
+        {
+            let mut hangups = signal::unix::signal(signal::unix::SignalKind::hangup())?;
+            tokio::spawn(async move {
+                while hangups.recv().await.is_some() {
+                    info!("Received hangup signal, reloading credentials");
+                    let (tx, _) = tokio::sync::oneshot::channel();
+                    if reload_tx.send(tx).is_err() {
+                        break;
+                    }
+                }
+            });
+        }
+        #[cfg(not(unix))]
+        drop(reload_tx);
+
+        // Wait for shutdown signal, reporting on inventory, reloading
+        // credentials and raising account activity, pair status, venue
+        // status and reconciliation alerts in the meantime
+        {
+            let shutdown = self.wait_for_shutdown();
+            tokio::pin!(shutdown);
+            let mut reports = self.config.execution.inventory.as_ref().map(|inv| tokio::time::interval(inv.report_interval));
+            let mut discount_checks = self.config.execution.fee_discounts.as_ref().map(|discounts| tokio::time::interval(discounts.check_interval));
+            loop {
+                tokio::select! {
+                    _ = &mut shutdown => break,
+                    _ = async { reports.as_mut().unwrap().tick().await }, if reports.is_some() => {
+                        match self.execution_engine.rebalance_inventory().await {
+                            Ok(plan) => info!("{}", plan.render(&self.config.reports)),
+                            Err(e) => error!("Inventory report failed: {}", e),
+                        }
+                    }
+                    _ = async { discount_checks.as_mut().unwrap().tick().await }, if discount_checks.is_some() => {
+                        match self.execution_engine.maintain_fee_discounts().await {
+                            Ok(balances) => {
+                                for balance in balances {
+                                    if balance.discounted && balance.topped_up.is_none() {
+                                        debug!("Fee discount: {}", balance);
+                                    } else {
+                                        warn!("Fee discount: {}", balance);
+                                    }
+                                }
+                            }
+                            Err(e) => error!("Fee discount check failed: {}", e),
+                        }
+                    }
+                    Some(anomaly) = anomalies.recv() => {
+                        error!("Unexpected account activity: {}", anomaly);
+                        let alert = AlertManager::create_account_activity_alert(&anomaly.venue().to_string(), &anomaly.to_string());
+                        self.monitoring_system.send_alert(alert).await;
+                    }
+                    Some(event) = pair_events.recv() => {
+                        let mut message = event.to_string();
+                        if event.stopped() {
+                            error!("{}", message);
+                            match self.execution_engine.handle_pair_event(&event).await {
+                                Ok(canceled) => message.push_str(&format!("; canceled {} open orders", canceled.len())),
+                                Err(e) => {
+                                    error!("Failed to wind down {} on {}: {}", event.symbol, event.venue, e);
+                                    message.push_str(&format!("; winding down failed: {}", e));
+                                }
+                            }
+                        } else {
+                            info!("{}", message);
+                        }
+                        let alert = AlertManager::create_pair_status_alert(&event.venue.to_string(), &event.symbol.to_string(), &message, event.stopped());
+                        self.monitoring_system.send_alert(alert).await;
+                    }
+                    Some(event) = venue_events.recv() => {
+                        let (state, level) = match &event.current {
+                            VenueHealth::Operational => (HealthState::Healthy, AlertLevel::Info),
+                            VenueHealth::Degraded(_) => (HealthState::Degraded, AlertLevel::Warning),
+                            VenueHealth::Outage(_) | VenueHealth::Maintenance(_) => (HealthState::Degraded, AlertLevel::Critical),
+                        };
+                        if event.current.accepts_orders() {
+                            info!("{}", event);
+                        } else {
+                            warn!("{}; not sending it orders", event);
+                        }
+                        let venue = event.venue.to_string();
+                        self.health_checker.update_component_health(
+                            &format!("exchange_{}", venue),
+                            state,
+                            &event.current.to_string(),
+                        ).await;
+                        let alert = AlertManager::create_venue_health_alert(&venue, &event.to_string(), level);
+                        self.monitoring_system.send_alert(alert).await;
+                    }
+                    Some(reply) = reloads.recv() => {
+                        let _ = reply.send(self.reload_credentials().await);
+                    }
+                    Some(report) = reconciliations.recv() => {
+                        if !report.is_clean() {
+                            let locale = &self.config.reports;
+                            let mut message = report.render(locale);
+                            for discrepancy in &report.discrepancies {
+                                error!("{} reconciliation: {}", report.venue, discrepancy);
+                                message.push_str(&format!("\n- {}", discrepancy.render(locale)));
+                            }
+                            let alert = AlertManager::create_reconciliation_alert(&report.venue.to_string(), &message, report.discrepancies.len());
+                            self.monitoring_system.send_alert(alert).await;
+                        }
+                    }
+                }
+            }
+        }
 
         // Graceful shutdown
         self.shutdown().await?;
@@ -144,48 +669,146 @@ impl ArbFinderApp {
         info!("Setting up exchange connections");
 
         // Setup Binance
+        #[cfg(feature = "binance")]
         if let Some(binance_config) = &self.config.exchanges.binance {
-            let binance_adapter = Arc::new(BinanceAdapter::with_credentials(
+            let mut binance_adapter = BinanceAdapter::with_credentials(
                 binance_config.api_key.clone(),
                 binance_config.api_secret.clone(),
-            ));
+            ).sandbox(binance_config.sandbox);
+            if let Some(pins) = &binance_config.tls_pins {
+                binance_adapter = binance_adapter.with_tls_pins(pins.clone())?;
+            }
+            if let Some(proxy) = &binance_config.proxy {
+                binance_adapter = binance_adapter.with_proxy(proxy.clone())?;
+                info!("Binance connects through a proxy");
+            }
+            if let Some(mode) = &binance_config.margin {
+                binance_adapter = binance_adapter.with_margin(mode.parse()?, binance_config.auto_borrow);
+                info!("Binance orders go to the {} margin account", mode);
+            }
             
-            self.execution_engine.add_exchange("binance".to_string(), binance_adapter);
+            if let Some(ttl) = binance_config.symbols_ttl {
+                binance_adapter = binance_adapter.with_symbols_ttl(ttl);
+            }
+            if let Some(rate_limiter) = &binance_config.rate_limiter {
+                binance_adapter = binance_adapter.with_rate_limiter(rate_limiter.clone());
+            }
+            binance_adapter.add_symbol_mappings(&self.config.exchanges.symbol_mappings);
+            self.execution_engine.add_exchange("binance".to_string(), Arc::new(binance_adapter));
             self.health_checker.register_component("exchange_binance").await;
             
-            info!("Binance exchange configured");
+            if binance_config.sandbox {
+                info!("Binance exchange configured against the spot testnet");
+            } else {
+                info!("Binance exchange configured");
+            }
         }
 
         // Setup Coinbase
+        #[cfg(feature = "coinbase")]
         if let Some(coinbase_config) = &self.config.exchanges.coinbase {
-            let coinbase_adapter = Arc::new(CoinbaseAdapter::with_credentials(
+            // Advanced Trade authenticates with a CDP key: its name and EC private key
+            let mut coinbase_adapter = CoinbaseAdapter::with_credentials(
                 coinbase_config.api_key.clone(),
                 coinbase_config.api_secret.clone(),
-                coinbase_config.passphrase.clone().unwrap_or_default(),
-            ));
+            )?;
+            if let Some(pins) = &coinbase_config.tls_pins {
+                coinbase_adapter = coinbase_adapter.with_tls_pins(pins.clone())?;
+            }
+            if let Some(proxy) = &coinbase_config.proxy {
+                coinbase_adapter = coinbase_adapter.with_proxy(proxy.clone())?;
+                info!("Coinbase connects through a proxy");
+            }
             
-            self.execution_engine.add_exchange("coinbase".to_string(), coinbase_adapter);
+            if let Some(ttl) = coinbase_config.symbols_ttl {
+                coinbase_adapter = coinbase_adapter.with_symbols_ttl(ttl);
+            }
+            if let Some(rate_limiter) = &coinbase_config.rate_limiter {
+                coinbase_adapter = coinbase_adapter.with_rate_limiter(rate_limiter.clone());
+            }
+            coinbase_adapter.add_symbol_mappings(&self.config.exchanges.symbol_mappings);
+            self.execution_engine.add_exchange("coinbase".to_string(), Arc::new(coinbase_adapter));
             self.health_checker.register_component("exchange_coinbase").await;
             
             info!("Coinbase exchange configured");
         }
 
         // Setup Kraken
+        #[cfg(feature = "kraken")]
         if let Some(kraken_config) = &self.config.exchanges.kraken {
-            let kraken_adapter = Arc::new(KrakenAdapter::with_credentials(
+            let mut kraken_adapter = KrakenAdapter::with_credentials(
                 kraken_config.api_key.clone(),
                 kraken_config.api_secret.clone(),
-            ));
+            );
+            if let Some(pins) = &kraken_config.tls_pins {
+                kraken_adapter = kraken_adapter.with_tls_pins(pins.clone())?;
+            }
+            if let Some(proxy) = &kraken_config.proxy {
+                kraken_adapter = kraken_adapter.with_proxy(proxy.clone())?;
+                info!("Kraken connects through a proxy");
+            }
             
-            self.execution_engine.add_exchange("kraken".to_string(), kraken_adapter);
+            if let Some(ttl) = kraken_config.symbols_ttl {
+                kraken_adapter = kraken_adapter.with_symbols_ttl(ttl);
+            }
+            if let Some(rate_limiter) = &kraken_config.rate_limiter {
+                kraken_adapter = kraken_adapter.with_rate_limiter(rate_limiter.clone());
+            }
+            kraken_adapter.add_symbol_mappings(&self.config.exchanges.symbol_mappings);
+            self.execution_engine.add_exchange("kraken".to_string(), Arc::new(kraken_adapter));
             self.health_checker.register_component("exchange_kraken").await;
             
             info!("Kraken exchange configured");
         }
 
+        // Setup Bitget
+        #[cfg(feature = "bitget")]
+        if let Some(bitget_config) = &self.config.exchanges.bitget {
+            let mut bitget_adapter = BitgetAdapter::with_credentials(
+                bitget_config.api_key.clone(),
+                bitget_config.api_secret.clone(),
+                bitget_config.passphrase.clone().unwrap_or_default(),
+            );
+            if let Some(pins) = &bitget_config.tls_pins {
+                bitget_adapter = bitget_adapter.with_tls_pins(pins.clone())?;
+            }
+            if let Some(proxy) = &bitget_config.proxy {
+                bitget_adapter = bitget_adapter.with_proxy(proxy.clone())?;
+                info!("Bitget connects through a proxy");
+            }
+            if let Some(rate_limiter) = &bitget_config.rate_limiter {
+                bitget_adapter = bitget_adapter.with_rate_limiter(rate_limiter.clone());
+            }
+            
+            bitget_adapter.add_symbol_mappings(&self.config.exchanges.symbol_mappings);
+            self.execution_engine.add_exchange("bitget".to_string(), Arc::new(bitget_adapter));
+            self.health_checker.register_component("exchange_bitget").await;
+            
+            info!("Bitget exchange configured");
+        }
+
         Ok(())
     }
 
+    /// Rotates each venue to the credentials the secrets provider now
+    /// holds, without dropping market data connections.
+    async fn reload_credentials(&self) -> Vec<CredentialRotation> {
+        let Some(secrets) = &self.secrets else {
+            warn!("No secrets provider to reload credentials from");
+            return Vec::new();
+        };
+
+        let rotations = self.execution_engine.rotate_credentials(secrets.as_ref()).await;
+        for rotation in &rotations {
+            if rotation.failed() {
+                error!("{}", rotation);
+            } else {
+                info!("{}", rotation);
+            }
+        }
+        rotations
+    }
+
     async fn setup_strategies(&mut self) -> Result<()> {
         info!("Setting up trading strategies");
 
@@ -277,10 +900,40 @@ fn load_config(config_path: &str) -> Result<AppConfig> {
                     enable_paper_trading: exec.get("enable_paper_trading")
                         .and_then(|v| v.as_bool())
                         .unwrap_or(true),
+                    mode: exec.get("mode")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.parse())
+                        .transpose()?
+                        .unwrap_or_default(),
+                    profit_allocation: exec.get("profit_allocation")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.parse())
+                        .transpose()?
+                        .unwrap_or_default(),
+                    ..ExecutionConfig::default()
                 }
             } else {
                 ExecutionConfig::default()
             };
+            let execution = ExecutionConfig {
+                inventory: toml_value.get("inventory").map(parse_inventory).transpose()?,
+                feature_flags: toml_value.get("feature_flags")
+                    .map(FeatureFlags::from_toml)
+                    .transpose()?
+                    .unwrap_or_default(),
+                price_sanity: toml_value.get("price_sanity").map(parse_price_sanity),
+                cooldown: toml_value.get("cooldown").map(parse_cooldown).unwrap_or_default(),
+                sizing: toml_value.get("sizing").map(parse_sizing),
+                fee_discounts: toml_value.get("fee_discounts").map(parse_fee_discounts).transpose()?,
+                activity: toml_value.get("activity").map(parse_activity).unwrap_or_default(),
+                lifecycle: toml_value.get("lifecycle").map(parse_lifecycle).transpose()?.unwrap_or_default(),
+                venue_status: toml_value.get("venue_status").map(parse_venue_status).unwrap_or_default(),
+                throttle: toml_value.get("strategy").map(parse_throttle).unwrap_or_default(),
+                claims: toml_value.get("messaging").and_then(parse_claims),
+                reconciliation: toml_value.get("reconciliation").map(parse_reconciliation).transpose()?,
+                market_data: toml_value.get("market_data").map(parse_market_data).transpose()?.unwrap_or_default(),
+                ..execution
+            };
             
             // Extract monitoring config
             let monitoring = if let Some(mon) = toml_value.get("monitoring") {
@@ -313,42 +966,155 @@ fn load_config(config_path: &str) -> Result<AppConfig> {
                 MonitoringConfig::default()
             };
             
-            // Extract exchange credentials
-            let exchanges = if let Some(exch) = toml_value.get("exchanges") {
+            // Extract exchange credentials. Keys kept elsewhere than the
+            // config file are filled in by `load_credentials`.
+            let secrets = toml_value.get("secrets").map(parse_secrets).transpose()?.unwrap_or_default();
+            let keys = |venue: &toml::Value| -> Option<(SecretString, SecretString)> {
+                let field = |name: &str| venue.get(name).and_then(|v| v.as_str()).map(SecretString::from);
+                match (field("api_key"), field("api_secret")) {
+                    (Some(api_key), Some(api_secret)) => Some((api_key, api_secret)),
+                    _ if secrets != SecretsSource::ConfigFile => Some(Default::default()),
+                    _ => None,
+                }
+            };
+            let mut exchanges = if let Some(exch) = toml_value.get("exchanges") {
                 ExchangeConfigs {
                     binance: exch.get("binance").and_then(|b| {
+                        let (api_key, api_secret) = keys(b)?;
                         Some(ExchangeCredentials {
-                            api_key: b.get("api_key")?.as_str()?.to_string(),
-                            api_secret: b.get("api_secret")?.as_str()?.to_string(),
+                            api_key,
+                            api_secret,
                             passphrase: None,
                             sandbox: b.get("sandbox").and_then(|v| v.as_bool()).unwrap_or(true),
+                            tls_pins: None,
+                            proxy: None,
+                            margin: b.get("margin").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                            auto_borrow: b.get("auto_borrow").and_then(|v| v.as_bool()).unwrap_or(false),
+                            symbols_ttl: None,
+                            rate_limiter: None,
                         })
                     }),
                     coinbase: exch.get("coinbase").and_then(|c| {
+                        let (api_key, api_secret) = keys(c)?;
                         Some(ExchangeCredentials {
-                            api_key: c.get("api_key")?.as_str()?.to_string(),
-                            api_secret: c.get("api_secret")?.as_str()?.to_string(),
-                            passphrase: c.get("passphrase").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                            api_key,
+                            api_secret,
+                            passphrase: None,
                             sandbox: c.get("sandbox").and_then(|v| v.as_bool()).unwrap_or(true),
+                            tls_pins: None,
+                            proxy: None,
+                            margin: None,
+                            auto_borrow: false,
+                            symbols_ttl: None,
+                            rate_limiter: None,
                         })
                     }),
                     kraken: exch.get("kraken").and_then(|k| {
+                        let (api_key, api_secret) = keys(k)?;
                         Some(ExchangeCredentials {
-                            api_key: k.get("api_key")?.as_str()?.to_string(),
-                            api_secret: k.get("api_secret")?.as_str()?.to_string(),
+                            api_key,
+                            api_secret,
                             passphrase: None,
                             sandbox: k.get("sandbox").and_then(|v| v.as_bool()).unwrap_or(true),
+                            tls_pins: None,
+                            proxy: None,
+                            margin: None,
+                            auto_borrow: false,
+                            symbols_ttl: None,
+                            rate_limiter: None,
                         })
                     }),
+                    bitget: exch.get("bitget").and_then(|b| {
+                        let (api_key, api_secret) = keys(b)?;
+                        Some(ExchangeCredentials {
+                            api_key,
+                            api_secret,
+                            passphrase: b.get("passphrase").and_then(|v| v.as_str()).map(SecretString::from),
+                            sandbox: b.get("sandbox").and_then(|v| v.as_bool()).unwrap_or(true),
+                            tls_pins: None,
+                            proxy: None,
+                            margin: None,
+                            auto_borrow: false,
+                            symbols_ttl: None,
+                            rate_limiter: None,
+                        })
+                    }),
+                    symbol_mappings_file: exch.get("symbol_mappings").and_then(|v| v.as_str()).map(|s| s.to_string()),
+                    symbol_mappings: SymbolMappings::new(),
                 }
             } else {
                 ExchangeConfigs {
                     binance: None,
                     coinbase: None,
                     kraken: None,
+                    bitget: None,
+                    symbol_mappings_file: None,
+                    symbol_mappings: SymbolMappings::new(),
                 }
             };
+            for (name, credentials) in [
+                ("binance", &mut exchanges.binance),
+                ("coinbase", &mut exchanges.coinbase),
+                ("kraken", &mut exchanges.kraken),
+                ("bitget", &mut exchanges.bitget),
+            ] {
+                let venue = toml_value.get("exchanges").and_then(|exch| exch.get(name));
+                if let (Some(credentials), Some(venue)) = (credentials.as_mut(), venue) {
+                    credentials.tls_pins = parse_tls_pins(name, venue)?;
+                    credentials.proxy = parse_proxy(name, venue)?;
+                    credentials.symbols_ttl = venue
+                        .get("symbols_ttl_secs")
+                        .and_then(|v| v.as_integer())
+                        .map(|secs| std::time::Duration::from_secs(secs.max(0) as u64));
+                    credentials.rate_limiter = parse_rate_limits(name, venue)?;
+                }
+            }
+            if let Some(path) = &exchanges.symbol_mappings_file {
+                if Path::new(path).exists() {
+                    exchanges.symbol_mappings = SymbolMappings::load(path)?;
+                    info!("Loaded {} symbol mappings from {}", exchanges.symbol_mappings.len(), path);
+                } else {
+                    warn!("Symbol mappings file {} not found; run `arbfinder symbols` to generate it", path);
+                }
+            }
+
+            let storage = match toml_value.get("storage") {
+                Some(storage) => {
+                    let recordings_dir = storage.get("recordings_dir")
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string());
+                    StorageConfig {
+                        archive: storage.get("archive")
+                            .map(|archive| parse_archive(archive, recordings_dir.as_deref()))
+                            .transpose()?,
+                        recordings_dir,
+                        migrate_on_startup: storage.get("migrate_on_startup")
+                            .and_then(|v| v.as_bool())
+                            .unwrap_or(true),
+                    }
+                }
+                None => StorageConfig::default(),
+            };
             
+            let trading_pairs = toml_value
+                .get("trading_pairs")
+                .and_then(|pairs| pairs.get("symbols"))
+                .and_then(|symbols| symbols.as_array())
+                .map(|symbols| {
+                    symbols
+                        .iter()
+                        .filter_map(|symbol| symbol.as_str())
+                        .map(|pair| {
+                            Symbol::from_pair(pair)
+                                .ok_or_else(|| ArbFinderError::InvalidData(format!("Invalid trading pair: {}", pair)))
+                        })
+                        .collect::<Result<Vec<_>>>()
+                })
+                .transpose()?
+                .unwrap_or_default();
+
+            let reports = toml_value.get("reports").map(parse_reports).transpose()?.unwrap_or_default();
+
             info!("Configuration loaded successfully");
             info!("  Paper trading: {}", execution.enable_paper_trading);
             info!("  Max position size: ${}", execution.max_position_size);
@@ -358,6 +1124,10 @@ fn load_config(config_path: &str) -> Result<AppConfig> {
                 execution,
                 monitoring,
                 exchanges,
+                storage,
+                trading_pairs,
+                reports,
+                secrets,
             })
         }
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
@@ -371,23 +1141,900 @@ fn load_config(config_path: &str) -> Result<AppConfig> {
     }
 }
 
+fn parse_inventory(inv: &toml::Value) -> Result<InventoryConfig> {
+    let defaults = InventoryConfig::default();
+    let decimal = |v: &toml::Value| {
+        v.as_float()
+            .and_then(Decimal::from_f64_retain)
+            .or_else(|| v.as_integer().map(Decimal::from))
+    };
+
+    let mut targets = Vec::new();
+    for target in inv.get("targets").and_then(|v| v.as_array()).into_iter().flatten() {
+        let field = |name: &str| {
+            target.get(name).ok_or_else(|| {
+                ArbFinderError::InvalidData(format!("Inventory target is missing {}", name))
+            })
+        };
+        let venue = field("venue")?.as_str().unwrap_or_default();
+        let asset = field("asset")?.as_str().unwrap_or_default();
+        let amount = decimal(field("target")?).ok_or_else(|| {
+            ArbFinderError::InvalidData(format!("Invalid inventory target for {} on {}", asset, venue))
+        })?;
+        let band = target.get("band").and_then(decimal).unwrap_or(Decimal::new(1, 1));
+        targets.push(InventoryTarget::new(VenueId::from(venue), asset.to_uppercase(), amount, band));
+    }
+
+    Ok(InventoryConfig {
+        targets,
+        quote_asset: inv.get("quote_asset")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_uppercase())
+            .unwrap_or(defaults.quote_asset),
+        report_interval: inv.get("report_interval_secs")
+            .and_then(|v| v.as_integer())
+            .map(|secs| std::time::Duration::from_secs(secs as u64))
+            .unwrap_or(defaults.report_interval),
+        auto_execute: inv.get("auto_execute")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(defaults.auto_execute),
+        auto_transfer: inv.get("auto_transfer")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(defaults.auto_transfer),
+    })
+}
+
+fn parse_price_sanity(sanity: &toml::Value) -> PriceSanityConfig {
+    let defaults = PriceSanityConfig::default();
+    PriceSanityConfig {
+        max_deviation: sanity.get("max_deviation")
+            .and_then(|v| v.as_float())
+            .and_then(Decimal::from_f64_retain)
+            .unwrap_or(defaults.max_deviation),
+        window: sanity.get("window_secs")
+            .and_then(|v| v.as_integer())
+            .map(|secs| std::time::Duration::from_secs(secs as u64))
+            .unwrap_or(defaults.window),
+        min_reference_venues: sanity.get("min_reference_venues")
+            .and_then(|v| v.as_integer())
+            .map(|n| n as usize)
+            .unwrap_or(defaults.min_reference_venues),
+    }
+}
+
+fn parse_cooldown(cooldown: &toml::Value) -> CooldownConfig {
+    let defaults = CooldownConfig::default();
+    let secs = |key: &str| {
+        cooldown.get(key)
+            .and_then(|v| v.as_integer())
+            .map(|secs| std::time::Duration::from_secs(secs as u64))
+    };
+    CooldownConfig {
+        base: secs("base_secs").unwrap_or(defaults.base),
+        max: secs("max_secs").unwrap_or(defaults.max),
+    }
+}
+
+fn parse_sizing(sizing: &toml::Value) -> SizingConfig {
+    let defaults = SizingConfig::default();
+    let decimal = |key: &str| {
+        sizing.get(key).and_then(|v| {
+            v.as_float()
+                .and_then(Decimal::from_f64)
+                .or_else(|| v.as_integer().map(Decimal::from))
+        })
+    };
+    SizingConfig {
+        window: sizing.get("window")
+            .and_then(|v| v.as_integer())
+            .map(|n| n.max(1) as usize)
+            .unwrap_or(defaults.window),
+        min_scale: decimal("min_scale").unwrap_or(defaults.min_scale),
+        max_scale: decimal("max_scale").unwrap_or(defaults.max_scale),
+        step: decimal("step").unwrap_or(defaults.step),
+        target_fill_ratio: decimal("target_fill_ratio").unwrap_or(defaults.target_fill_ratio),
+        max_slippage_bps: decimal("max_slippage_bps").unwrap_or(defaults.max_slippage_bps),
+    }
+}
+
+fn parse_fee_discounts(discounts: &toml::Value) -> Result<FeeDiscountConfig> {
+    let defaults = FeeDiscountConfig::default();
+    let decimal = |v: &toml::Value| {
+        v.as_float()
+            .and_then(Decimal::from_f64_retain)
+            .or_else(|| v.as_integer().map(Decimal::from))
+    };
+
+    let mut assets = Vec::new();
+    for asset in discounts.get("assets").and_then(|v| v.as_array()).into_iter().flatten() {
+        let field = |name: &str| {
+            asset.get(name).ok_or_else(|| {
+                ArbFinderError::InvalidData(format!("Fee discount asset is missing {}", name))
+            })
+        };
+        let venue = field("venue")?.as_str().unwrap_or_default();
+        let name = field("asset")?.as_str().unwrap_or_default().to_uppercase();
+        let amount = |key: &str| {
+            decimal(field(key)?).ok_or_else(|| {
+                ArbFinderError::InvalidData(format!("Invalid {} for {} on {}", key, name, venue))
+            })
+        };
+        let taker_fee = amount("taker_fee")?;
+        let maker_fee = asset.get("maker_fee").and_then(decimal).unwrap_or(taker_fee);
+        let min_balance = amount("min_balance")?;
+        assets.push(DiscountAsset {
+            venue: VenueId::from(venue),
+            discount: FeeDiscount::new(name.clone(), maker_fee, taker_fee),
+            quote_asset: asset.get("quote_asset")
+                .and_then(|v| v.as_str())
+                .unwrap_or("USDT")
+                .to_uppercase(),
+            min_balance,
+            target_balance: asset.get("target_balance").and_then(decimal).unwrap_or(min_balance),
+        });
+    }
+
+    Ok(FeeDiscountConfig {
+        assets,
+        check_interval: discounts.get("check_interval_secs")
+            .and_then(|v| v.as_integer())
+            .map(|secs| std::time::Duration::from_secs(secs.max(1) as u64))
+            .unwrap_or(defaults.check_interval),
+        auto_top_up: discounts.get("auto_top_up")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(defaults.auto_top_up),
+    })
+}
+
+fn parse_activity(activity: &toml::Value) -> ActivityConfig {
+    let defaults = ActivityConfig::default();
+    // Intervals of zero would spin, so checks run at least every second
+    let secs = |key: &str| {
+        activity.get(key)
+            .and_then(|v| v.as_integer())
+            .map(|secs| std::time::Duration::from_secs(secs.max(1) as u64))
+    };
+    let fraction = |key: &str| {
+        activity.get(key)
+            .and_then(|v| v.as_float())
+            .and_then(Decimal::from_f64)
+    };
+    ActivityConfig {
+        balance_interval: secs("balance_check_secs").unwrap_or(defaults.balance_interval),
+        unknown_order_grace: secs("unknown_order_grace_secs").unwrap_or(defaults.unknown_order_grace),
+        fee_tolerance: fraction("fee_tolerance").unwrap_or(defaults.fee_tolerance),
+        dust_tolerance: fraction("dust_tolerance").unwrap_or(defaults.dust_tolerance),
+        order_retention: secs("order_retention_secs").unwrap_or(defaults.order_retention),
+    }
+}
+
+/// Credentials default to `AWS_ACCESS_KEY_ID` and `AWS_SECRET_ACCESS_KEY`,
+/// and the directories to the recordings directory.
+fn parse_archive(archive: &toml::Value, recordings_dir: Option<&str>) -> Result<ArchiveConfig> {
+    let defaults = ArchiveConfig::default();
+    let string = |key: &str, env: Option<&str>| {
+        archive.get(key)
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string())
+            .or_else(|| env.and_then(|env| std::env::var(env).ok()))
+    };
+    let days = |key: &str| {
+        archive.get(key)
+            .and_then(|v| v.as_integer())
+            .map(|days| std::time::Duration::from_secs(days.max(0) as u64 * 86_400))
+    };
+    let secs = |key: &str, default: std::time::Duration| {
+        archive.get(key)
+            .and_then(|v| v.as_integer())
+            .map(|secs| std::time::Duration::from_secs(secs.max(1) as u64))
+            .unwrap_or(default)
+    };
+
+    let bucket = string("bucket", None)
+        .ok_or_else(|| ArbFinderError::InvalidData("[storage.archive] needs a bucket".to_string()))?;
+    let dirs = match archive.get("dirs").and_then(|v| v.as_array()) {
+        Some(dirs) => dirs.iter().filter_map(|dir| dir.as_str()).map(std::path::PathBuf::from).collect(),
+        None => recordings_dir.map(std::path::PathBuf::from).into_iter().collect(),
+    };
+    Ok(ArchiveConfig {
+        endpoint: string("endpoint", None).unwrap_or(defaults.endpoint),
+        region: string("region", Some("AWS_REGION")).unwrap_or(defaults.region),
+        bucket,
+        prefix: string("prefix", None).unwrap_or(defaults.prefix),
+        access_key: string("access_key", Some("AWS_ACCESS_KEY_ID")).unwrap_or_default(),
+        secret_key: string("secret_key", Some("AWS_SECRET_ACCESS_KEY")).unwrap_or_default(),
+        path_style: archive.get("path_style").and_then(|v| v.as_bool()).unwrap_or(defaults.path_style),
+        dirs,
+        settle: secs("settle_secs", defaults.settle),
+        interval: secs("interval_secs", defaults.interval),
+        keep_local: days("keep_local_days"),
+        keep_remote: days("keep_remote_days"),
+    })
+}
+
+fn parse_secrets(secrets: &toml::Value) -> Result<SecretsSource> {
+    let field = |name: &str, default: &str| {
+        secrets.get(name).and_then(|v| v.as_str()).unwrap_or(default).to_string()
+    };
+    match secrets.get("provider").and_then(|v| v.as_str()).unwrap_or("config") {
+        "config" => Ok(SecretsSource::ConfigFile),
+        "env" => Ok(SecretsSource::Env { prefix: field("env_prefix", "ARBFINDER") }),
+        "keyring" => Ok(SecretsSource::Keyring { service: field("keyring_service", "arbfinder") }),
+        "vault" => Ok(SecretsSource::Vault {
+            mount: field("vault_mount", "secret"),
+            path: field("vault_path", "arbfinder"),
+        }),
+        other => Err(ArbFinderError::InvalidData(format!(
+            "Unknown secrets provider: {} (expected config, env, keyring or vault)",
+            other
+        ))),
+    }
+}
+
+fn parse_lifecycle(lifecycle: &toml::Value) -> Result<LifecycleConfig> {
+    let defaults = LifecycleConfig::default();
+    let unwind = match lifecycle.get("unwind").and_then(|v| v.as_str()) {
+        None => defaults.unwind,
+        Some("hold") => UnwindPolicy::Hold,
+        Some("close") => UnwindPolicy::Close,
+        Some(other) => {
+            return Err(ArbFinderError::InvalidData(format!(
+                "Unknown lifecycle unwind policy: {} (expected hold or close)",
+                other
+            )))
+        }
+    };
+    Ok(LifecycleConfig {
+        check_interval: lifecycle.get("check_secs")
+            .and_then(|v| v.as_integer())
+            .map(|secs| std::time::Duration::from_secs(secs.max(1) as u64))
+            .unwrap_or(defaults.check_interval),
+        unwind,
+    })
+}
+
+fn parse_venue_status(venue_status: &toml::Value) -> VenueStatusConfig {
+    let defaults = VenueStatusConfig::default();
+    let secs = |key: &str| {
+        venue_status.get(key)
+            .and_then(|v| v.as_integer())
+            .map(|secs| std::time::Duration::from_secs(secs.max(0) as u64))
+    };
+    VenueStatusConfig {
+        // Checks run at least every second
+        check_interval: secs("check_secs").map(|interval| interval.max(std::time::Duration::from_secs(1))).unwrap_or(defaults.check_interval),
+        maintenance_lead: secs("maintenance_lead_secs").unwrap_or(defaults.maintenance_lead),
+    }
+}
+
+/// Order throttling from the `[strategy]` table: `priorities` weighs each
+/// strategy's share of the order rate limit, and `throttle_max_wait_ms` caps
+/// how long an order waits for it.
+fn parse_throttle(strategy: &toml::Value) -> ThrottleConfig {
+    let defaults = ThrottleConfig::default();
+    let priorities = strategy.get("priorities")
+        .and_then(|v| v.as_table())
+        .into_iter()
+        .flatten()
+        .filter_map(|(name, weight)| Some((name.clone(), weight.as_integer()?.max(1) as u32)))
+        .collect();
+    ThrottleConfig {
+        priorities,
+        max_wait: strategy.get("throttle_max_wait_ms")
+            .and_then(|v| v.as_integer())
+            .map(|ms| std::time::Duration::from_millis(ms.max(0) as u64))
+            .unwrap_or(defaults.max_wait),
+    }
+}
+
+fn parse_reconciliation(reconciliation: &toml::Value) -> Result<ReconcileConfig> {
+    let defaults = ReconcileConfig::default();
+    let run_at = match reconciliation.get("run_at").and_then(|v| v.as_str()) {
+        Some(time) => chrono::NaiveTime::parse_from_str(time, "%H:%M").map_err(|_| {
+            ArbFinderError::InvalidData(format!("Invalid reconciliation run_at: {} (expected HH:MM)", time))
+        })?,
+        None => defaults.run_at,
+    };
+    Ok(ReconcileConfig {
+        run_at,
+        report_dir: reconciliation.get("report_dir")
+            .and_then(|v| v.as_str())
+            .map(std::path::PathBuf::from)
+            .unwrap_or(defaults.report_dir),
+        history_limit: reconciliation.get("history_limit")
+            .and_then(|v| v.as_integer())
+            .map(|limit| limit.max(1) as u32)
+            .unwrap_or(defaults.history_limit),
+    })
+}
+
+/// Report formatting from `[reports]`: `decimal_separator`,
+/// `thousands_separator` (empty for none) and `timezone` (`UTC`, `local` or
+/// an offset such as `+02:00`).
+fn parse_reports(reports: &toml::Value) -> Result<ReportLocale> {
+    let defaults = ReportLocale::default();
+    let string = |key: &str| reports.get(key).and_then(|v| v.as_str());
+    let single = |key: &str, value: &str| {
+        let mut chars = value.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c),
+            _ => Err(ArbFinderError::InvalidData(format!("{} must be a single character: {:?}", key, value))),
+        }
+    };
+    let locale = ReportLocale {
+        decimal_separator: string("decimal_separator")
+            .map(|value| single("decimal_separator", value))
+            .transpose()?
+            .unwrap_or(defaults.decimal_separator),
+        thousands_separator: match string("thousands_separator") {
+            Some("") => None,
+            Some(value) => Some(single("thousands_separator", value)?),
+            None => defaults.thousands_separator,
+        },
+        timezone: string("timezone")
+            .map(str::parse)
+            .transpose()?
+            .unwrap_or(defaults.timezone),
+    };
+    locale.validate()?;
+    Ok(locale)
+}
+
+/// Market data mode from `[market_data].mode`, `full` or `bbo_only`.
+fn parse_market_data(market_data: &toml::Value) -> Result<MarketDataMode> {
+    market_data
+        .get("mode")
+        .and_then(|v| v.as_str())
+        .map(str::parse)
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+/// Opportunity claims from `[messaging.claims]`, against the NATS server at
+/// `[messaging].nats_url`. Without a claims table the executor runs alone.
+fn parse_claims(messaging: &toml::Value) -> Option<ClaimsConfig> {
+    let claims = messaging.get("claims")?;
+    let defaults = ClaimsConfig::default();
+    let string = |table: &toml::Value, key: &str| table.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+    Some(ClaimsConfig {
+        nats_url: string(messaging, "nats_url").unwrap_or(defaults.nats_url),
+        bucket: string(claims, "bucket").unwrap_or(defaults.bucket),
+        ttl: claims.get("ttl_secs")
+            .and_then(|v| v.as_integer())
+            .map(|secs| std::time::Duration::from_secs(secs.max(1) as u64))
+            .unwrap_or(defaults.ttl),
+        executor_id: string(claims, "executor_id").unwrap_or(defaults.executor_id),
+    })
+}
+
+/// `tls_pins` of an `[exchanges.*]` table, enforced unless
+/// `enforce_tls_pins = false`.
+fn parse_tls_pins(venue: &str, exchange: &toml::Value) -> Result<Option<TlsPins>> {
+    let Some(pins) = exchange.get("tls_pins").and_then(|v| v.as_array()) else {
+        return Ok(None);
+    };
+    let pins: Vec<&str> = pins.iter().filter_map(|pin| pin.as_str()).collect();
+    let pins = TlsPins::new(&pins)
+        .map_err(|e| ArbFinderError::InvalidData(format!("{} tls_pins: {}", venue, e)))?;
+    let enforce = exchange.get("enforce_tls_pins").and_then(|v| v.as_bool()).unwrap_or(true);
+    Ok(Some(if enforce { pins } else { pins.report_only() }))
+}
+
+/// `proxy` of an `[exchanges.*]` table: a URL, or a table with `url` and
+/// optionally `username` and `password`.
+fn parse_proxy(venue: &str, exchange: &toml::Value) -> Result<Option<ProxyConfig>> {
+    let Some(proxy) = exchange.get("proxy") else {
+        return Ok(None);
+    };
+    let string = |key: &str| proxy.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+    let config = match proxy.as_str() {
+        Some(url) => ProxyConfig::new(url),
+        None => ProxyConfig {
+            url: string("url").ok_or_else(|| ArbFinderError::InvalidData(format!("{} proxy: missing url", venue)))?,
+            username: string("username"),
+            password: string("password"),
+        },
+    };
+    Proxy::from_config(&config).map_err(|e| ArbFinderError::InvalidData(format!("{} proxy: {}", venue, e)))?;
+    Ok(Some(config))
+}
+
+/// `rate_limits` of an `[exchanges.*]` table, within its
+/// `rate_limit_buffer` if it has one.
+fn parse_rate_limits(venue: &str, exchange: &toml::Value) -> Result<Option<SharedRateLimiter>> {
+    let Some(limits) = exchange.get("rate_limits") else {
+        return Ok(None);
+    };
+    let limits: RateLimitConfig = limits
+        .clone()
+        .try_into()
+        .map_err(|e| ArbFinderError::InvalidData(format!("{} rate_limits: {}", venue, e)))?;
+    let buffer = exchange.get("rate_limit_buffer").and_then(|v| v.as_float()).unwrap_or(1.0);
+    Ok(Some(WeightedRateLimiter::with_buffer(&limits, buffer).shared()))
+}
+
+/// Builds a cross-exchange detector from a TOML file such as:
+///
+/// ```toml
+/// min_spread_bps = 10
+/// min_volume = 100
+/// min_profit_quote = 5
+///
+/// [trading_fees]
+/// binance = 0.001
+///
+/// [fixed_costs]
+/// uniswap = 5
+///
+/// [fee_discounts.binance]
+/// asset = "BNB"
+/// rate = 0.00075
+/// balance = 50  # value of the BNB held, in quote currency; optional
+/// ```
+fn load_detector_config(path: &str) -> Result<CrossExchangeArbitrageDetector> {
+    let contents = std::fs::read_to_string(path)?;
+    let toml_value: toml::Value = toml::from_str(&contents)
+        .map_err(|e| ArbFinderError::Internal(format!("Failed to parse detector config: {}", e)))?;
+    let decimal = |v: &toml::Value| {
+        v.as_float()
+            .and_then(Decimal::from_f64_retain)
+            .or_else(|| v.as_integer().map(Decimal::from))
+    };
+
+    // Only the detector's thresholds come from the file
+    let strategy = StrategyConfig {
+        min_spread_bps: toml_value.get("min_spread_bps")
+            .and_then(|v| v.as_integer())
+            .map(|bps| bps as i32)
+            .unwrap_or(10),
+        min_volume_threshold: toml_value.get("min_volume").and_then(decimal).unwrap_or_default(),
+        min_profit_quote: toml_value.get("min_profit_quote").and_then(decimal).unwrap_or_default(),
+        ..ArbFinderConfig::development().strategy
+    };
+    let mut detector = CrossExchangeArbitrageDetector::from_config(&strategy);
+    for (venue, fee) in toml_value.get("trading_fees").and_then(|v| v.as_table()).into_iter().flatten() {
+        let fee = decimal(fee).ok_or_else(|| {
+            ArbFinderError::InvalidData(format!("Invalid trading fee for {}", venue))
+        })?;
+        detector.set_trading_fee(VenueId::from(venue.as_str()), fee);
+    }
+    for (venue, cost) in toml_value.get("fixed_costs").and_then(|v| v.as_table()).into_iter().flatten() {
+        let cost = decimal(cost).ok_or_else(|| {
+            ArbFinderError::InvalidData(format!("Invalid fixed cost for {}", venue))
+        })?;
+        detector.set_fixed_cost(VenueId::from(venue.as_str()), cost);
+    }
+    for (venue, discount) in toml_value.get("fee_discounts").and_then(|v| v.as_table()).into_iter().flatten() {
+        let venue_id = VenueId::from(venue.as_str());
+        let asset = discount.get("asset").and_then(|v| v.as_str());
+        let rate = discount.get("rate").and_then(decimal);
+        let (Some(asset), Some(rate)) = (asset, rate) else {
+            return Err(ArbFinderError::InvalidData(format!(
+                "Fee discount for {} needs an asset and a rate",
+                venue
+            )));
+        };
+        detector.set_fee_discount(venue_id.clone(), FeeDiscount::new(asset.to_uppercase(), rate, rate));
+        if let Some(balance) = discount.get("balance").and_then(decimal) {
+            detector.set_discount_balance(venue_id, balance);
+        }
+    }
+
+    // Lead/lag estimated from a recording, with one-way latencies to the
+    // venues, to skip gaps a lagging venue closes before an order lands
+    if let Some(fair_value) = toml_value.get("fair_value") {
+        let recording = fair_value.get("recording").and_then(|v| v.as_str()).ok_or_else(|| {
+            ArbFinderError::InvalidData("fair_value needs a recording".to_string())
+        })?;
+        let millis = |key: &str, default: u64| {
+            fair_value.get(key).and_then(|v| v.as_integer()).map(|ms| ms.max(0) as u64).unwrap_or(default)
+        };
+        let estimator = estimate_lead_lag(recording, millis("bucket_ms", 10), millis("max_lag_ms", 500))?;
+        let mut model = FairValueModel::new();
+        for symbol in estimator.symbols() {
+            for lead_lag in estimator.estimate(&symbol) {
+                model.add_lead_lag(&lead_lag);
+            }
+        }
+        for (venue, latency) in fair_value.get("latency_ms").and_then(|v| v.as_table()).into_iter().flatten() {
+            let latency = latency.as_integer().filter(|ms| *ms >= 0).ok_or_else(|| {
+                ArbFinderError::InvalidData(format!("Invalid latency for {}", venue))
+            })?;
+            model.set_latency(VenueId::from(venue.as_str()), std::time::Duration::from_millis(latency as u64));
+        }
+        detector.set_fair_value_model(model);
+    }
+
+    Ok(detector)
+}
+
+/// Reads a what-if profile of per-venue overrides, such as:
+///
+/// ```toml
+/// seed = 42
+///
+/// [venues.binance]
+/// taker_fee = 0.00072
+/// latency_ms = 3
+///
+/// [venues.coinbase]
+/// latency_ms = { mean = 160, std_dev = 20 }
+///
+/// [venues.kraken]
+/// latency_ms = { min = 120, max = 200 }
+/// ```
+fn load_simulation_profile(path: &str) -> Result<SimulationProfile> {
+    let contents = std::fs::read_to_string(path)?;
+    let toml_value: toml::Value = toml::from_str(&contents)
+        .map_err(|e| ArbFinderError::Internal(format!("Failed to parse profile: {}", e)))?;
+    let decimal = |v: &toml::Value| {
+        v.as_float()
+            .and_then(Decimal::from_f64_retain)
+            .or_else(|| v.as_integer().map(Decimal::from))
+    };
+    let millis = |v: &toml::Value| {
+        v.as_float()
+            .or_else(|| v.as_integer().map(|ms| ms as f64))
+            .filter(|ms| *ms >= 0.0)
+            .map(|ms| std::time::Duration::from_secs_f64(ms / 1000.0))
+    };
+
+    let seed = toml_value.get("seed").and_then(|v| v.as_integer()).unwrap_or(0);
+    let mut profile = SimulationProfile::new(seed as u64);
+    for (venue, overrides) in toml_value.get("venues").and_then(|v| v.as_table()).into_iter().flatten() {
+        let invalid = |what: &str| ArbFinderError::InvalidData(format!("Invalid {} for {}", what, venue));
+        let field = |key: &str| -> Result<Option<Decimal>> {
+            overrides.get(key).map(|v| decimal(v).ok_or_else(|| invalid(key))).transpose()
+        };
+        let latency = match overrides.get("latency_ms") {
+            None => None,
+            Some(toml::Value::Table(table)) => {
+                let get = |key: &str| table.get(key).map(|v| millis(v).ok_or_else(|| invalid("latency")));
+                Some(match (get("mean"), get("std_dev"), get("min"), get("max")) {
+                    (Some(mean), std_dev, None, None) => LatencyDistribution::Normal {
+                        mean: mean?,
+                        std_dev: std_dev.transpose()?.unwrap_or_default(),
+                    },
+                    (None, None, Some(min), Some(max)) => LatencyDistribution::Uniform { min: min?, max: max? },
+                    _ => return Err(invalid("latency, expected mean and std_dev or min and max")),
+                })
+            }
+            Some(latency) => Some(LatencyDistribution::Fixed(millis(latency).ok_or_else(|| invalid("latency"))?)),
+        };
+        profile.set_venue(VenueId::from(venue.as_str()), VenueProfile {
+            taker_fee: field("taker_fee")?,
+            fixed_cost: field("fixed_cost")?,
+            latency,
+        });
+    }
+    Ok(profile)
+}
+
+fn parse_bench_duration(value: &str) -> std::result::Result<std::time::Duration, String> {
+    arbfinder::bench::parse_duration(value).map_err(|e| e.to_string())
+}
+
+/// Adapters for the configured exchanges, set up as `run` sets them up.
+fn configured_adapters(exchanges: &ExchangeConfigs) -> Result<Vec<Box<dyn MarketDataAdapter>>> {
+    let mut adapters: Vec<Box<dyn MarketDataAdapter>> = Vec::new();
+
+    #[cfg(feature = "binance")]
+    if let Some(config) = &exchanges.binance {
+        let mut adapter = BinanceAdapter::with_credentials(config.api_key.clone(), config.api_secret.clone())
+            .sandbox(config.sandbox);
+        if let Some(pins) = &config.tls_pins {
+            adapter = adapter.with_tls_pins(pins.clone())?;
+        }
+        if let Some(proxy) = &config.proxy {
+            adapter = adapter.with_proxy(proxy.clone())?;
+        }
+        if let Some(ttl) = config.symbols_ttl {
+            adapter = adapter.with_symbols_ttl(ttl);
+        }
+        if let Some(rate_limiter) = &config.rate_limiter {
+            adapter = adapter.with_rate_limiter(rate_limiter.clone());
+        }
+        adapter.add_symbol_mappings(&exchanges.symbol_mappings);
+        adapters.push(Box::new(adapter));
+    }
+
+    #[cfg(feature = "coinbase")]
+    if let Some(config) = &exchanges.coinbase {
+        let mut adapter = CoinbaseAdapter::with_credentials(config.api_key.clone(), config.api_secret.clone())?;
+        if let Some(pins) = &config.tls_pins {
+            adapter = adapter.with_tls_pins(pins.clone())?;
+        }
+        if let Some(proxy) = &config.proxy {
+            adapter = adapter.with_proxy(proxy.clone())?;
+        }
+        if let Some(ttl) = config.symbols_ttl {
+            adapter = adapter.with_symbols_ttl(ttl);
+        }
+        if let Some(rate_limiter) = &config.rate_limiter {
+            adapter = adapter.with_rate_limiter(rate_limiter.clone());
+        }
+        adapter.add_symbol_mappings(&exchanges.symbol_mappings);
+        adapters.push(Box::new(adapter));
+    }
+
+    #[cfg(feature = "kraken")]
+    if let Some(config) = &exchanges.kraken {
+        let mut adapter = KrakenAdapter::with_credentials(config.api_key.clone(), config.api_secret.clone());
+        if let Some(pins) = &config.tls_pins {
+            adapter = adapter.with_tls_pins(pins.clone())?;
+        }
+        if let Some(proxy) = &config.proxy {
+            adapter = adapter.with_proxy(proxy.clone())?;
+        }
+        if let Some(ttl) = config.symbols_ttl {
+            adapter = adapter.with_symbols_ttl(ttl);
+        }
+        if let Some(rate_limiter) = &config.rate_limiter {
+            adapter = adapter.with_rate_limiter(rate_limiter.clone());
+        }
+        adapter.add_symbol_mappings(&exchanges.symbol_mappings);
+        adapters.push(Box::new(adapter));
+    }
+
+    #[cfg(feature = "bitget")]
+    if let Some(config) = &exchanges.bitget {
+        let mut adapter = BitgetAdapter::with_credentials(
+            config.api_key.clone(),
+            config.api_secret.clone(),
+            config.passphrase.clone().unwrap_or_default(),
+        );
+        if let Some(pins) = &config.tls_pins {
+            adapter = adapter.with_tls_pins(pins.clone())?;
+        }
+        if let Some(proxy) = &config.proxy {
+            adapter = adapter.with_proxy(proxy.clone())?;
+        }
+        if let Some(rate_limiter) = &config.rate_limiter {
+            adapter = adapter.with_rate_limiter(rate_limiter.clone());
+        }
+        adapter.add_symbol_mappings(&exchanges.symbol_mappings);
+        adapters.push(Box::new(adapter));
+    }
+
+    Ok(adapters)
+}
+
+fn estimate_lead_lag(recording: &str, bucket_ms: u64, max_lag_ms: u64) -> Result<LeadLagEstimator> {
+    let file = std::fs::File::open(recording)?;
+    let reader = RecordingReader::new(std::io::BufReader::new(file))?;
+    LeadLagEstimator::run(
+        std::time::Duration::from_millis(bucket_ms),
+        std::time::Duration::from_millis(max_lag_ms),
+        reader,
+    )
+}
+
+/// Log files written by the daily appender for `log_file`, newest first.
+fn recent_log_files(log_file: &str, count: usize) -> Vec<std::path::PathBuf> {
+    let path = Path::new(log_file);
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let Some(prefix) = path.file_name().map(|name| name.to_string_lossy().into_owned()) else {
+        return Vec::new();
+    };
+
+    let mut files: Vec<(std::time::SystemTime, std::path::PathBuf)> = std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with(&prefix))
+        .filter_map(|entry| Some((entry.metadata().ok()?.modified().ok()?, entry.path())))
+        .collect();
+    files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    files.into_iter().take(count).map(|(_, path)| path).collect()
+}
+
+fn version_report() -> String {
+    let adapters = [
+        ("binance", cfg!(feature = "binance")),
+        ("coinbase", cfg!(feature = "coinbase")),
+        ("kraken", cfg!(feature = "kraken")),
+        ("gemini", cfg!(feature = "gemini")),
+        ("htx", cfg!(feature = "htx")),
+        ("mexc", cfg!(feature = "mexc")),
+        ("bitfinex", cfg!(feature = "bitfinex")),
+        ("poloniex", cfg!(feature = "poloniex")),
+        ("upbit", cfg!(feature = "upbit")),
+        ("bithumb", cfg!(feature = "bithumb")),
+        ("bitget", cfg!(feature = "bitget")),
+        ("deribit", cfg!(feature = "deribit")),
+        ("hyperliquid", cfg!(feature = "hyperliquid")),
+        ("uniswap", cfg!(feature = "uniswap")),
+        ("curve", cfg!(feature = "curve")),
+        ("jupiter", cfg!(feature = "jupiter")),
+        ("zerox", cfg!(feature = "zerox")),
+    ];
+    let enabled: Vec<&str> = adapters.iter().filter(|(_, on)| *on).map(|(name, _)| *name).collect();
+
+    format!(
+        "ArbFinder v{}\nTarget: {}-{}\nDebug build: {}\nAdapters: {}\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::ARCH,
+        std::env::consts::OS,
+        cfg!(debug_assertions),
+        enabled.join(", ")
+    )
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
 
     match cli.command {
-        Commands::Run { config, paper_trading, log_level } => {
+        Commands::Run { config, paper_trading, log_level, ignore_tls_pins } => {
             // Load configuration
             let mut app_config = load_config(&config)?;
             
             // Override with CLI options
             app_config.execution.enable_paper_trading = paper_trading;
             app_config.monitoring.log_level = log_level;
+            if ignore_tls_pins {
+                let exchanges = &mut app_config.exchanges;
+                for credentials in [&mut exchanges.binance, &mut exchanges.coinbase, &mut exchanges.kraken, &mut exchanges.bitget]
+                    .into_iter()
+                    .flatten()
+                {
+                    credentials.tls_pins = credentials.tls_pins.take().map(TlsPins::report_only);
+                }
+            }
+
+            if let (Some(dir), true) = (&app_config.storage.recordings_dir, app_config.storage.migrate_on_startup) {
+                if Path::new(dir).is_dir() {
+                    for migration in migrate::migrate_recordings(Path::new(dir), false)? {
+                        info!("Migrated recording {}", migration);
+                    }
+                }
+            }
 
             // Create and run application
-            let mut app = ArbFinderApp::new(app_config)?;
+            let secrets = load_credentials(&mut app_config, &config).await?;
+            let mut app = ArbFinderApp::new(app_config)?.with_secrets(secrets);
             app.run().await?;
         }
+        Commands::ReplayOpportunities { recording, detector_config, profile } => {
+            let mut replay = OpportunityReplay::new(load_detector_config(&detector_config)?);
+            if let Some(profile) = profile {
+                replay = replay.with_profile(load_simulation_profile(&profile)?);
+            }
+            let file = std::fs::File::open(&recording)?;
+            let reader = RecordingReader::new(std::io::BufReader::new(file))?;
+            let diff = replay.replay(reader)?;
+            println!("{}", diff);
+        }
+        Commands::LeadLag { recording, bucket_ms, max_lag_ms } => {
+            let estimator = estimate_lead_lag(&recording, bucket_ms, max_lag_ms)?;
+            let mut symbols = estimator.symbols();
+            symbols.sort_by_key(|symbol| symbol.to_string());
+            for symbol in symbols {
+                let estimates = estimator.estimate(&symbol);
+                if estimates.is_empty() {
+                    println!("{}: no venue leads", symbol);
+                }
+                for estimate in estimates {
+                    println!("{}", estimate);
+                }
+            }
+        }
+        Commands::DebugBundle { config, output, recording, events, log_files, log_bytes } => {
+            let mut bundle = DebugBundle::new();
+            bundle.add_text("version.txt", version_report());
+
+            match std::fs::read_to_string(&config) {
+                Ok(contents) => bundle.add_config("config.toml", &contents)?,
+                Err(e) => bundle.add_text("config.toml", format!("# Could not read {}: {}\n", config, e)),
+            }
+            let app_config = load_config(&config)?;
+            let (resolved, secrets) = app_config.redacted();
+            for secret in secrets {
+                bundle.add_secret(secret);
+            }
+            bundle.add_text("resolved-config.txt", format!("{:#?}\n", resolved));
+
+            for log in app_config.monitoring.log_file.as_deref().map(|file| recent_log_files(file, log_files)).unwrap_or_default() {
+                bundle.add_log_tail(&log, log_bytes)?;
+            }
+
+            let app = ArbFinderApp::new(app_config)?;
+            bundle.add_text("health.txt", format!("{:#?}\n", app.health_check().await));
+
+            if let Some(recording) = recording {
+                bundle.add_recent_events(Path::new(&recording), events)?;
+            }
+
+            let stamp = Utc::now().format("%Y%m%d-%H%M%S");
+            let output = output.unwrap_or_else(|| format!("arbfinder-debug-{}.tar.gz", stamp));
+            bundle.write(std::fs::File::create(&output)?, &format!("arbfinder-debug-{}", stamp))?;
+
+            println!("Wrote {}", output);
+            for name in bundle.file_names() {
+                println!("  {}", name);
+            }
+        }
+        Commands::Migrate { config, dir, dry_run, rollback } => {
+            let dir = match dir {
+                Some(dir) => dir,
+                None => load_config(&config)?.storage.recordings_dir.ok_or_else(|| {
+                    ArbFinderError::InvalidData("No recordings directory given or configured".to_string())
+                })?,
+            };
+            let dir = Path::new(&dir);
+
+            if rollback {
+                let restored = migrate::rollback_recordings(dir)?;
+                for (path, version) in &restored {
+                    println!("Restored {} at v{}", path.display(), version);
+                }
+                println!("{} recording(s) rolled back", restored.len());
+            } else {
+                let migrations = migrate::migrate_recordings(dir, dry_run)?;
+                for migration in &migrations {
+                    println!("{}{}", if dry_run { "Would migrate " } else { "Migrated " }, migration);
+                }
+                println!("{} recording(s) {}", migrations.len(), if dry_run { "to migrate" } else { "migrated" });
+            }
+        }
+        Commands::Bench { config, live, symbols, detector_config, json } => {
+            let mut app_config = load_config(&config)?;
+            load_credentials(&mut app_config, &config).await?;
+            let symbols = if symbols.is_empty() {
+                app_config.trading_pairs.clone()
+            } else {
+                symbols
+                    .iter()
+                    .map(|pair| {
+                        Symbol::from_pair(pair)
+                            .ok_or_else(|| ArbFinderError::InvalidData(format!("Invalid trading pair: {}", pair)))
+                    })
+                    .collect::<Result<Vec<_>>>()?
+            };
+            if symbols.is_empty() {
+                return Err(ArbFinderError::InvalidData("No pairs given or configured to benchmark".to_string()));
+            }
+            let adapters = configured_adapters(&app_config.exchanges)?;
+            if adapters.is_empty() {
+                return Err(ArbFinderError::InvalidData("No exchanges configured to benchmark".to_string()));
+            }
+            let detector = match detector_config {
+                Some(path) => load_detector_config(&path)?,
+                None => CrossExchangeArbitrageDetector::new(10, Decimal::ZERO),
+            };
+
+            println!("Following {} pairs on {} venues for {:?}", symbols.len(), adapters.len(), live);
+            let report = arbfinder::bench::run_live(adapters, &symbols, live, detector).await?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!("{}", report);
+            }
+        }
+        Commands::Symbols { config, output } => {
+            let mut app_config = load_config(&config)?;
+            load_credentials(&mut app_config, &config).await?;
+            let output = output
+                .or_else(|| app_config.exchanges.symbol_mappings_file.clone())
+                .ok_or_else(|| {
+                    ArbFinderError::InvalidData("No --output given or symbol_mappings configured".to_string())
+                })?;
+            let adapters = configured_adapters(&app_config.exchanges)?;
+            if adapters.is_empty() {
+                return Err(ArbFinderError::InvalidData("No exchanges configured to list symbols of".to_string()));
+            }
+
+            // Venues not configured here keep the names they had
+            let mut mappings = app_config.exchanges.symbol_mappings.clone();
+            for adapter in &adapters {
+                let symbols = adapter.get_symbols().await?;
+                println!("{}: {} symbols", adapter.venue_id(), symbols.len());
+                let names = symbols.into_iter().map(|symbol| (adapter.venue_symbol(&symbol), symbol));
+                mappings.set_venue(&adapter.venue_id(), names);
+            }
+            mappings.save(&output)?;
+            println!("Wrote {} symbol mappings to {}", mappings.len(), output);
+        }
         Commands::Health => {
             // Quick health check
             let config = AppConfig::default();
@@ -402,9 +2049,15 @@ async fn main() -> Result<()> {
                 println!("  {}: {:?} - {}", name, component.status, component.message);
             }
         }
-        Commands::Version => {
-            println!("ArbFinder v{}", env!("CARGO_PKG_VERSION"));
-            println!("A cryptocurrency arbitrage finder and trading bot");
+        Commands::Version { json } => {
+            let info = arbfinder::build_info();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&info)?);
+            } else {
+                println!("{}", info);
+                println!("Features: {}", info.features.join(", "));
+                println!("A cryptocurrency arbitrage finder and trading bot");
+            }
         }
     }
 
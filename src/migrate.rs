@@ -0,0 +1,200 @@
+//! Migrations of persisted state to the current schema.
+//!
+//! Recordings are upgraded event by event whenever they are read, which
+//! keeps old files usable but pays the conversion on every replay and stops
+//! working once a converter is retired. Migrating rewrites a recording at
+//! the current schema version. The file it replaces is kept next to it as
+//! `<name>.v<version>.bak`, and rolling back restores the newest of those.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use arbfinder_core::{ArbFinderError, Result};
+use arbfinder_orderbook::{RecordingReader, RecordingWriter, RECORDING_SCHEMA_VERSION};
+
+/// A recording rewritten, or in a dry run one that would be.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingMigration {
+    pub path: PathBuf,
+    pub from_version: u32,
+    pub to_version: u32,
+    pub events: usize,
+}
+
+impl std::fmt::Display for RecordingMigration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: v{} -> v{} ({} events)",
+            self.path.display(),
+            self.from_version,
+            self.to_version,
+            self.events
+        )
+    }
+}
+
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{}.bak", version));
+    path.with_file_name(name)
+}
+
+fn with_path(path: &Path, e: ArbFinderError) -> ArbFinderError {
+    ArbFinderError::InvalidData(format!("{}: {}", path.display(), e))
+}
+
+/// Reads every event of a recording, writing it to `writer` if there is one.
+fn convert<R: BufRead, W: Write>(
+    reader: RecordingReader<R>,
+    mut writer: Option<&mut RecordingWriter<W>>,
+) -> Result<usize> {
+    let mut events = 0;
+    for event in reader {
+        let event = event?;
+        if let Some(writer) = writer.as_deref_mut() {
+            writer.write(&event)?;
+        }
+        events += 1;
+    }
+    Ok(events)
+}
+
+/// Rewrites a recording at the current schema version, returning `None` if
+/// it already is. A dry run reads and converts every event without writing
+/// anything, so it fails the same way the migration would.
+pub fn migrate_recording(path: &Path, dry_run: bool) -> Result<Option<RecordingMigration>> {
+    let reader = RecordingReader::new(BufReader::new(File::open(path)?)).map_err(|e| with_path(path, e))?;
+    let header = reader.header().clone();
+    if header.schema_version == RECORDING_SCHEMA_VERSION {
+        return Ok(None);
+    }
+
+    let events = if dry_run {
+        convert::<_, File>(reader, None)
+    } else {
+        let staging = path.with_extension("migrating");
+        let mut writer = RecordingWriter::with_header(BufWriter::new(File::create(&staging)?), &header.upgraded())?;
+        let converted = convert(reader, Some(&mut writer)).and_then(|events| {
+            writer.flush()?;
+            Ok(events)
+        });
+        drop(writer);
+        if converted.is_ok() {
+            std::fs::rename(path, backup_path(path, header.schema_version))?;
+            std::fs::rename(&staging, path)?;
+        } else {
+            let _ = std::fs::remove_file(&staging);
+        }
+        converted
+    }
+    .map_err(|e| with_path(path, e))?;
+
+    Ok(Some(RecordingMigration {
+        path: path.to_path_buf(),
+        from_version: header.schema_version,
+        to_version: RECORDING_SCHEMA_VERSION,
+        events,
+    }))
+}
+
+/// Restores the newest backup of a recording, returning the version
+/// restored, or `None` if it has no backups.
+pub fn rollback_recording(path: &Path) -> Result<Option<u32>> {
+    let Some(name) = path.file_name().map(|name| name.to_string_lossy().into_owned()) else {
+        return Ok(None);
+    };
+    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let prefix = format!("{}.v", name);
+
+    let newest = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            file_name.strip_prefix(&prefix)?.strip_suffix(".bak")?.parse::<u32>().ok()
+        })
+        .max();
+
+    if let Some(version) = newest {
+        std::fs::rename(backup_path(path, version), path)?;
+    }
+    Ok(newest)
+}
+
+/// The recordings (`*.jsonl`) in a directory, in name order.
+pub fn recordings_in(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Migrates every recording in a directory, stopping at the first that
+/// cannot be read. Recordings already migrated stay migrated.
+pub fn migrate_recordings(dir: &Path, dry_run: bool) -> Result<Vec<RecordingMigration>> {
+    let mut migrated = Vec::new();
+    for path in recordings_in(dir)? {
+        migrated.extend(migrate_recording(&path, dry_run)?);
+    }
+    Ok(migrated)
+}
+
+/// Rolls back every recording in a directory that has a backup, returning
+/// each with the version restored.
+pub fn rollback_recordings(dir: &Path) -> Result<Vec<(PathBuf, u32)>> {
+    let mut restored = Vec::new();
+    for path in recordings_in(dir)? {
+        if let Some(version) = rollback_recording(&path)? {
+            restored.push((path, version));
+        }
+    }
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEGACY: &str = concat!(
+        r#"{"type":"trade","venue_id":"Kraken","symbol":{"base":"BTC","quote":"USD"},"trade_id":"1","price":"100","quantity":"1","side":"Bid","timestamp":"2024-01-01T00:00:00Z"}"#,
+        "\n",
+    );
+
+    #[test]
+    fn test_migrate_and_roll_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+        std::fs::write(&path, LEGACY).unwrap();
+
+        let planned = migrate_recording(&path, true).unwrap().unwrap();
+        assert_eq!((planned.from_version, planned.events), (0, 1));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), LEGACY);
+
+        let migrated = migrate_recordings(dir.path(), false).unwrap();
+        assert_eq!(migrated, vec![planned]);
+        let reader = RecordingReader::new(BufReader::new(File::open(&path).unwrap())).unwrap();
+        assert_eq!(reader.header().schema_version, RECORDING_SCHEMA_VERSION);
+        assert_eq!(reader.count(), 1);
+        assert_eq!(migrate_recording(&path, false).unwrap(), None);
+
+        assert_eq!(rollback_recording(&path).unwrap(), Some(0));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), LEGACY);
+        assert_eq!(rollback_recording(&path).unwrap(), None);
+    }
+
+    #[test]
+    fn test_failed_migration_leaves_recording() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.jsonl");
+        let contents = format!("{}{}\n", LEGACY, r#"{"type":"unknown"}"#);
+        std::fs::write(&path, &contents).unwrap();
+
+        assert!(migrate_recording(&path, false).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), contents);
+        assert_eq!(recordings_in(dir.path()).unwrap(), vec![path]);
+    }
+}
@@ -96,6 +96,7 @@ async fn test_paper_trading_execution() {
         max_daily_loss: dec!(1000.0),
         max_orders_per_second: 10,
         enable_paper_trading: true,
+        ..ExecutionConfig::default()
     };
     
     let engine = ExecutionEngine::new(config);